@@ -0,0 +1,55 @@
+//! ベンチマークで共有する入力の生成
+//!
+//! 同じ `seed` からは常に同じ入力を生成し、実装を変更する前後で同じデータを比べられるようにします。
+
+#![allow(dead_code)]
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use std::collections::BTreeSet;
+
+/// 入力の生成に使う乱数生成器を返します。
+pub fn rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// 各ビットが確率 `density` で `true` になる、長さ `n` のビット列を返します。
+pub fn random_bits(n: usize, density: f64, seed: u64) -> Vec<bool> {
+    let mut rng = rng(seed);
+    (0..n).map(|_| rng.gen_bool(density)).collect()
+}
+
+/// `0..sigma` の値からなる、長さ `n` のバイト列を返します。
+pub fn random_bytes(n: usize, sigma: u16, seed: u64) -> Vec<u8> {
+    let mut rng = rng(seed);
+    (0..n).map(|_| rng.gen_range(0, sigma) as u8).collect()
+}
+
+/// 英小文字からなる、長さ `n` の文字列を返します。
+///
+/// 文字の出現頻度に偏りを持たせ、自然言語の文章に近い繰り返しを含むようにしています。
+pub fn random_text(n: usize, seed: u64) -> Vec<u8> {
+    let mut rng = rng(seed);
+    (0..n).map(|_| b'a' + (rng.gen_range(0, 26) * rng.gen_range(1, 27) / 26) as u8).collect()
+}
+
+/// 英小文字からなる、重複のない `n` 個のキーを辞書順に並べて返します。
+///
+/// キーの長さは 1 文字以上 `max_len` 文字以下です。
+pub fn random_keys(n: usize, max_len: usize, seed: u64) -> Vec<String> {
+    let mut rng = rng(seed);
+    let mut keys = BTreeSet::new();
+    while keys.len() < n {
+        let len = rng.gen_range(1, max_len + 1);
+        keys.insert((0..len).map(|_| (b'a' + rng.gen_range(0, 26) as u8) as char).collect::<String>());
+    }
+    keys.into_iter().collect()
+}
+
+/// `0..upper` の値からなる、長さ `n` の列を返します。
+pub fn random_indices(n: usize, upper: usize, seed: u64) -> Vec<usize> {
+    let mut rng = rng(seed);
+    (0..n).map(|_| rng.gen_range(0, upper)).collect()
+}
@@ -0,0 +1,51 @@
+//! 完備辞書の rank / select のベンチマーク
+
+mod common;
+
+use rust_study::bits::fid::FID;
+use rust_study::bits::fid::NaiveFID;
+
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use std::hint::black_box;
+
+const N: usize = 1 << 20;
+const QUERIES: usize = 1000;
+
+fn bench_fid(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fid");
+    for density in [0.01, 0.5, 0.99] {
+        let bits = common::random_bits(N, density, 1);
+        let fid = NaiveFID::from_bool_vec(&bits);
+        let ones = fid.rank1(N);
+        let positions = common::random_indices(QUERIES, N, 2);
+        let ranks1 = common::random_indices(QUERIES, ones.max(1), 3);
+        let ranks0 = common::random_indices(QUERIES, (N - ones).max(1), 4);
+
+        group.bench_with_input(BenchmarkId::new("build", density), &bits, |b, bits| {
+            b.iter(|| NaiveFID::from_bool_vec(black_box(bits)))
+        });
+        group.bench_with_input(BenchmarkId::new("access", density), &positions, |b, positions| {
+            b.iter(|| positions.iter().filter(|&&i| fid.access(black_box(i))).count())
+        });
+        group.bench_with_input(BenchmarkId::new("rank1", density), &positions, |b, positions| {
+            b.iter(|| positions.iter().map(|&i| fid.rank1(black_box(i))).sum::<usize>())
+        });
+        if ones > 0 {
+            group.bench_with_input(BenchmarkId::new("select1", density), &ranks1, |b, ranks| {
+                b.iter(|| ranks.iter().map(|&i| fid.select1(black_box(i))).sum::<usize>())
+            });
+        }
+        if ones < N {
+            group.bench_with_input(BenchmarkId::new("select0", density), &ranks0, |b, ranks| {
+                b.iter(|| ranks.iter().map(|&i| fid.select0(black_box(i))).sum::<usize>())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_fid);
+criterion_main!(benches);
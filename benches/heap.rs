@@ -0,0 +1,55 @@
+//! 二分ヒープの操作のベンチマーク
+
+mod common;
+
+use rust_study::collections::heap::Heap;
+
+use criterion::BatchSize;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::hint::black_box;
+
+fn bench_heap(c: &mut Criterion) {
+    let mut group = c.benchmark_group("heap");
+    for n in [1 << 10, 1 << 16] {
+        let values = common::random_indices(n, usize::MAX, 1);
+
+        group.bench_with_input(BenchmarkId::new("push", n), &values, |b, values| {
+            b.iter(|| {
+                let mut heap = Heap::new();
+                values.iter().for_each(|&v| heap.push(black_box(v)));
+                heap
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("pop", n), &values, |b, values| {
+            b.iter_batched_ref(|| {
+                let mut heap = Heap::new();
+                values.iter().for_each(|&v| heap.push(v));
+                heap
+            }, |heap| while let Some(v) = heap.pop() { black_box(v); }, BatchSize::SmallInput)
+        });
+        group.bench_with_input(BenchmarkId::new("push_pop_with_compare", n), &values, |b, values| {
+            b.iter(|| {
+                let mut heap = Heap::with_compare(|lhs: &usize, rhs: &usize| rhs.cmp(lhs));
+                values.iter().for_each(|&v| heap.push(black_box(v)));
+                while let Some(v) = heap.pop() { black_box(v); }
+            })
+        });
+        // the standard library as the baseline
+        group.bench_with_input(BenchmarkId::new("std_binary_heap_push_pop", n), &values, |b, values| {
+            b.iter(|| {
+                let mut heap = BinaryHeap::new();
+                values.iter().for_each(|&v| heap.push(Reverse(black_box(v))));
+                while let Some(v) = heap.pop() { black_box(v); }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_heap);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+//! 接尾辞配列と、それを使う索引の構築と検索のベンチマーク
+
+mod common;
+
+use rust_study::string::fm_index::NaiveFMIndex;
+use rust_study::string::suffix_array::lcp_array;
+use rust_study::string::suffix_array::suffix_array;
+
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::Throughput;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use std::hint::black_box;
+
+fn bench_suffix_array(c: &mut Criterion) {
+    let mut group = c.benchmark_group("suffix_array");
+    group.sample_size(20);
+    for n in [1 << 12, 1 << 16, 1 << 20] {
+        let text = common::random_text(n, 1);
+        group.throughput(Throughput::Bytes(n as u64));
+        group.bench_with_input(BenchmarkId::new("suffix_array", n), &text, |b, text| {
+            b.iter(|| suffix_array(black_box(text)))
+        });
+        let sa = suffix_array(&text);
+        group.bench_with_input(BenchmarkId::new("lcp_array", n), &text, |b, text| {
+            b.iter(|| lcp_array(black_box(text), &sa))
+        });
+        group.bench_with_input(BenchmarkId::new("fm_index", n), &text, |b, text| {
+            b.iter(|| NaiveFMIndex::new(black_box(text)))
+        });
+        // a highly repetitive text is the worst case of the naive comparisons
+        let repetitive = vec![b'a'; n];
+        group.bench_with_input(BenchmarkId::new("suffix_array_repetitive", n), &repetitive, |b, text| {
+            b.iter(|| suffix_array(black_box(text)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_locate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fm_index_locate");
+    let text = common::random_text(1 << 16, 1);
+    let index = NaiveFMIndex::new(&text);
+    // the substrings of the text, each of which occurs many times
+    let patterns: Vec<&[u8]> = common::random_indices(1000, text.len() - 2, 2).into_iter().map(|i| &text[i..i + 2]).collect();
+    group.bench_with_input("locate", &patterns, |b, patterns| {
+        b.iter(|| patterns.iter().map(|p| index.locate(black_box(p)).len()).sum::<usize>())
+    });
+    group.bench_with_input("locate_into", &patterns, |b, patterns| {
+        let mut out = vec![];
+        b.iter(|| patterns.iter().map(|p| {
+            index.locate_into(black_box(p), &mut out);
+            out.len()
+        }).sum::<usize>())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_suffix_array, bench_locate);
+criterion_main!(benches);
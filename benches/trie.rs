@@ -0,0 +1,89 @@
+//! トライ木の構築と検索のベンチマーク
+
+mod common;
+
+use rust_study::query::QueryBuffer;
+use rust_study::string::trie::ByteTrie;
+use rust_study::string::trie::NaiveLoudsTrie;
+use rust_study::string::trie::NaiveTrie;
+use rust_study::string::trie::RadixTrie;
+use rust_study::string::trie::Trie;
+use rust_study::string::trie::TrieMut;
+use rust_study::string::trie::children::HashChildren;
+
+use criterion::BenchmarkGroup;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::measurement::WallTime;
+use rustc_hash::FxBuildHasher;
+use std::hint::black_box;
+
+const KEYS: usize = 1 << 14;
+const QUERIES: usize = 1000;
+
+fn build_mut<T: TrieMut + Default>(keys: &[String]) -> T {
+    let mut trie = T::default();
+    keys.iter().for_each(|key| { trie.insert(key); });
+    trie
+}
+
+fn bench_queries<T: Trie>(group: &mut BenchmarkGroup<WallTime>, name: &str, trie: &T, queries: &[String]) {
+    group.bench_with_input(BenchmarkId::new("contains", name), queries, |b, queries| {
+        b.iter(|| queries.iter().filter(|q| trie.contains(black_box(q))).count())
+    });
+    group.bench_with_input(BenchmarkId::new("prefix", name), queries, |b, queries| {
+        b.iter(|| queries.iter().map(|q| trie.prefix(black_box(q)).len()).sum::<usize>())
+    });
+    group.bench_with_input(BenchmarkId::new("predict", name), queries, |b, queries| {
+        b.iter(|| queries.iter().map(|q| trie.predict(black_box(&q[..1])).take(10).count()).sum::<usize>())
+    });
+}
+
+fn bench_trie(c: &mut Criterion) {
+    let keys = common::random_keys(KEYS, 8, 1);
+    // half of the queries are keys, the others are almost always missing
+    let queries: Vec<String> = common::random_indices(QUERIES, KEYS, 2).into_iter()
+        .zip(common::random_keys(QUERIES, 8, 3))
+        .enumerate()
+        .map(|(i, (k, missing))| if i % 2 == 0 { keys[k].clone() } else { missing })
+        .collect();
+
+    let mut group = c.benchmark_group("trie_build");
+    group.bench_function("naive", |b| b.iter(|| build_mut::<NaiveTrie>(black_box(&keys))));
+    group.bench_function("naive_hash", |b| b.iter(|| build_mut::<NaiveTrie<HashChildren>>(black_box(&keys))));
+    group.bench_function("naive_fx_hash", |b| b.iter(|| build_mut::<NaiveTrie<HashChildren<FxBuildHasher>>>(black_box(&keys))));
+    group.bench_function("byte", |b| b.iter(|| build_mut::<ByteTrie>(black_box(&keys))));
+    group.bench_function("radix", |b| b.iter(|| build_mut::<RadixTrie>(black_box(&keys))));
+    group.bench_function("louds", |b| b.iter(|| NaiveLoudsTrie::new(black_box(&keys))));
+    group.finish();
+
+    let mut group = c.benchmark_group("trie_query");
+    bench_queries(&mut group, "naive", &build_mut::<NaiveTrie>(&keys), &queries);
+    bench_queries(&mut group, "naive_hash", &build_mut::<NaiveTrie<HashChildren>>(&keys), &queries);
+    bench_queries(&mut group, "naive_fx_hash", &build_mut::<NaiveTrie<HashChildren<FxBuildHasher>>>(&keys), &queries);
+    bench_queries(&mut group, "byte", &build_mut::<ByteTrie>(&keys), &queries);
+    bench_queries(&mut group, "radix", &build_mut::<RadixTrie>(&keys), &queries);
+    bench_queries(&mut group, "louds", &NaiveLoudsTrie::new(&keys), &queries);
+    group.finish();
+
+    // all the keys with the first two chars of the queries, with and without reusing the buffers
+    let louds = NaiveLoudsTrie::new(&keys);
+    let prefixes: Vec<&str> = queries.iter().map(|q| &q[..q.len().min(2)]).collect();
+    let mut group = c.benchmark_group("trie_predict_all");
+    group.bench_with_input("louds/predict", &prefixes, |b, prefixes| {
+        b.iter(|| prefixes.iter().map(|p| louds.predict(black_box(p)).collect::<Vec<_>>().len()).sum::<usize>())
+    });
+    group.bench_with_input("louds/predict_into", &prefixes, |b, prefixes| {
+        let (mut buffer, mut out) = (QueryBuffer::new(), vec![]);
+        b.iter(|| prefixes.iter().map(|p| {
+            louds.predict_into(black_box(p), &mut buffer, &mut out);
+            out.len()
+        }).sum::<usize>())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_trie);
+criterion_main!(benches);
@@ -0,0 +1,72 @@
+//! ウェーブレット行列の構築と検索のベンチマーク
+
+mod common;
+
+use rust_study::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+use rust_study::query::QueryBuffer;
+
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use std::hint::black_box;
+
+const N: usize = 1 << 18;
+const QUERIES: usize = 1000;
+
+fn bench_wavelet_matrix(c: &mut Criterion) {
+    let mut group = c.benchmark_group("wavelet_matrix");
+    for sigma in [4, 256] {
+        let vec = common::random_bytes(N, sigma, 1);
+        let wm = NaiveU8WaveletMatrix::new(&vec);
+        let positions = common::random_indices(QUERIES, N, 2);
+        // select the occurrences that exist
+        let selects: Vec<(u8, usize)> = positions.iter().map(|&i| (vec[i], wm.rank(vec[i], i))).collect();
+        let ranges: Vec<(usize, usize)> = positions.iter()
+            .zip(common::random_indices(QUERIES, N, 3))
+            .map(|(&s, e)| (s.min(e), s.max(e) + 1))
+            .collect();
+
+        group.bench_with_input(BenchmarkId::new("build", sigma), &vec, |b, vec| {
+            b.iter(|| NaiveU8WaveletMatrix::new(black_box(vec)))
+        });
+        group.bench_with_input(BenchmarkId::new("access", sigma), &positions, |b, positions| {
+            b.iter(|| positions.iter().map(|&i| wm.access(black_box(i)) as usize).sum::<usize>())
+        });
+        group.bench_with_input(BenchmarkId::new("rank", sigma), &selects, |b, selects| {
+            b.iter(|| selects.iter().zip(&positions).map(|(&(v, _), &i)| wm.rank(v, black_box(i))).sum::<usize>())
+        });
+        group.bench_with_input(BenchmarkId::new("select", sigma), &selects, |b, selects| {
+            b.iter(|| selects.iter().map(|&(v, i)| wm.select(v, black_box(i))).sum::<usize>())
+        });
+        group.bench_with_input(BenchmarkId::new("quantile", sigma), &ranges, |b, ranges| {
+            b.iter(|| ranges.iter().map(|&(s, e)| wm.quantile(s, e, black_box((e - s) / 2)) as usize).sum::<usize>())
+        });
+        group.bench_with_input(BenchmarkId::new("topk", sigma), &ranges, |b, ranges| {
+            b.iter(|| ranges.iter().map(|&(s, e)| wm.topk(s, e, black_box(10)).len()).sum::<usize>())
+        });
+        group.bench_with_input(BenchmarkId::new("topk_into", sigma), &ranges, |b, ranges| {
+            let (mut buffer, mut out) = (QueryBuffer::new(), vec![]);
+            b.iter(|| ranges.iter().map(|&(s, e)| {
+                wm.topk_into(s, e, black_box(10), &mut buffer, &mut out);
+                out.len()
+            }).sum::<usize>())
+        });
+        // a quarter of the alphabet
+        let values = sigma as u8 / 4..sigma as u8 / 2;
+        group.bench_with_input(BenchmarkId::new("range_list", sigma), &ranges, |b, ranges| {
+            b.iter(|| ranges.iter().map(|&(s, e)| wm.range_list(s, e, black_box(values.clone())).len()).sum::<usize>())
+        });
+        group.bench_with_input(BenchmarkId::new("range_list_into", sigma), &ranges, |b, ranges| {
+            let (mut buffer, mut out) = (QueryBuffer::new(), vec![]);
+            b.iter(|| ranges.iter().map(|&(s, e)| {
+                wm.range_list_into(s, e, black_box(values.clone()), &mut buffer, &mut out);
+                out.len()
+            }).sum::<usize>())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_wavelet_matrix);
+criterion_main!(benches);
@@ -0,0 +1,13 @@
+//! 任意のバイト列が圧縮と復元で元に戻り、壊れた圧縮データを復元しても panic しないことを確かめます。
+
+#![no_main]
+
+use rust_study::string::compress::compress;
+use rust_study::string::compress::decompress;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    assert_eq!(Some(data.to_vec()), decompress(&compress(data)));
+    decompress(data);
+});
@@ -0,0 +1,26 @@
+//! 壊れたバイト列から完備辞書を読み込んでも panic せず、読み込めた場合は同じバイト列に書き戻せることを確かめます。
+
+#![no_main]
+
+use rust_study::bits::fid::FID;
+use rust_study::bits::fid::NaiveFID;
+use rust_study::serialize::Serializable;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut rest = data;
+    if let Ok(fid) = NaiveFID::deserialize(&mut rest) {
+        let mut bytes = vec![];
+        fid.serialize(&mut bytes).unwrap();
+        assert_eq!(&data[..data.len() - rest.len()], &bytes[..]);
+
+        let ones = fid.rank1(fid.len());
+        if ones > 0 {
+            assert!(fid.get(fid.select1(ones - 1)));
+        }
+        if ones < fid.len() {
+            assert!(!fid.get(fid.select0(fid.len() - ones - 1)));
+        }
+    }
+});
@@ -0,0 +1,37 @@
+//! 壊れたバイト列から FM-index を読み込んでも panic せず、読み込めた場合は整合性の検証を通り、
+//! 検索が panic せずに整合した結果を返し、同じバイト列に書き戻せることを確かめます。
+
+#![no_main]
+
+use rust_study::serialize::Serializable;
+use rust_study::string::fm_index::NaiveFMIndex;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut rest = data;
+    if let Ok(index) = NaiveFMIndex::deserialize(&mut rest) {
+        assert_eq!(Ok(()), index.validate());
+        let n = index.len();
+        assert_eq!((0..=n).collect::<Vec<_>>(), index.locate(b""));
+        // every position but the end starts with exactly one byte
+        let mut total = 0;
+        for &b in index.alphabet().symbols() {
+            let positions = index.locate(&[b]);
+            assert_eq!(positions.len(), index.count(&[b]));
+            assert_eq!(index.alphabet().frequency(b), positions.len());
+            total += positions.len();
+        }
+        assert_eq!(n, total);
+        // patterns of the bytes of the input itself
+        for pattern in data.chunks(3).take(16) {
+            let positions = index.locate(pattern);
+            assert_eq!(positions.len(), index.count(pattern));
+            assert!(positions.iter().all(|&i| i + pattern.len() <= n));
+        }
+
+        let mut bytes = vec![];
+        index.serialize(&mut bytes).unwrap();
+        assert_eq!(&data[..data.len() - rest.len()], &bytes[..]);
+    }
+});
@@ -0,0 +1,35 @@
+//! 壊れたバイト列からウェーブレット行列を読み込んでも panic せず、読み込めた場合は同じバイト列に書き戻せることを確かめます。
+//!
+//! 書式の検査だけでは検索の panic を防げないため、整合性の検証を通った場合に、各操作が panic せずに整合した結果を返すことも確かめます。
+
+#![no_main]
+
+use rust_study::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+use rust_study::serialize::Serializable;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut rest = data;
+    if let Ok(wmat) = NaiveU8WaveletMatrix::deserialize(&mut rest) {
+        let mut bytes = vec![];
+        wmat.serialize(&mut bytes).unwrap();
+        assert_eq!(&data[..data.len() - rest.len()], &bytes[..]);
+
+        if wmat.validate().is_ok() {
+            let n = wmat.len();
+            let values: Vec<u8> = (0..n).map(|i| wmat.access(i)).collect();
+            let mut sorted = values.clone();
+            sorted.sort();
+            for (i, &v) in values.iter().enumerate() {
+                let rank = wmat.rank(v, i);
+                assert_eq!(values[..i].iter().filter(|&&u| u == v).count(), rank);
+                assert_eq!(i, wmat.select(v, rank));
+                assert_eq!(sorted[i], wmat.quantile(0, n, i));
+            }
+            assert_eq!(n, (0..=u8::MAX).map(|v| wmat.rank(v, n)).sum::<usize>());
+            let topk = wmat.topk(0, n, 256);
+            assert_eq!(n, topk.iter().map(|&(_, count)| count).sum::<usize>());
+        }
+    }
+});
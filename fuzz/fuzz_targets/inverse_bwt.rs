@@ -0,0 +1,25 @@
+//! 任意のバイト列の BWT が逆変換で元に戻り、 BWT でないバイト列を逆変換しても panic しないことを確かめます。
+
+#![no_main]
+
+use rust_study::string::bwt::bwt;
+use rust_study::string::bwt::inverse_bwt;
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    text: Vec<u8>,
+    primary: usize,
+}
+
+fuzz_target!(|input: Input| {
+    let (transformed, primary) = bwt(&input.text);
+    assert_eq!(input.text, inverse_bwt(&transformed, primary));
+
+    // an arbitrary byte sequence with a valid primary index
+    let n = input.text.len();
+    let primary = if n == 0 { 0 } else { input.primary % n + 1 };
+    assert_eq!(n, inverse_bwt(&input.text, primary).len());
+});
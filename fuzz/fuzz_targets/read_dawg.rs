@@ -0,0 +1,34 @@
+//! 壊れたバイト列から DAWG を読み込んでも panic せず、読み込めた場合は整合性の検証を通り、
+//! キーの列挙と番号の変換が panic せずに整合した結果を返し、同じバイト列に書き戻せることを確かめます。
+
+#![no_main]
+
+use rust_study::string::trie::Dawg;
+use rust_study::string::trie::Trie;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut rest = data;
+    if let Ok(dawg) = Dawg::read_from(&mut rest) {
+        assert_eq!(Ok(()), dawg.validate());
+        // the number of the keys may be huge, as the states are shared
+        let keys: Vec<String> = dawg.keys().take(1000).collect();
+        assert_eq!(keys.len(), dawg.len().min(1000));
+        for (id, key) in keys.iter().enumerate() {
+            assert!(dawg.contains(key));
+            assert_eq!(Some(id), dawg.key_to_id(key));
+            assert_eq!(Some(key), dawg.id_to_key(id).as_ref());
+            assert_eq!(Some(key.as_str()), dawg.common_prefixes(key).last());
+        }
+        if let Some(key) = keys.last() {
+            assert!(dawg.predict(key).next().is_some());
+            assert!(dawg.count_prefix(key) > 0);
+        }
+        assert_eq!(None, dawg.id_to_key(dawg.len()));
+
+        let mut bytes = vec![];
+        dawg.write_to(&mut bytes).unwrap();
+        assert_eq!(&data[..data.len() - rest.len()], &bytes[..]);
+    }
+});
@@ -0,0 +1,38 @@
+//! 壊れたバイト列から LOUDS トライ木を読み込んでも panic せず、読み込めた場合は同じバイト列に書き戻せることを確かめます。
+//!
+//! 複製して読み込む [`NaiveLoudsTrie::read_from()`] と、複製しない [`LoudsTrieView::new()`] が同じバイト列を受理することも確かめます。
+//! 書式の検査だけでは検索の panic を防げないため、整合性の検証を通った場合に、キーの列挙と番号の変換が整合した結果を返すことも確かめます。
+
+#![no_main]
+
+use rust_study::string::trie::LoudsTrieView;
+use rust_study::string::trie::NaiveLoudsTrie;
+use rust_study::string::trie::Trie;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let trie = NaiveLoudsTrie::read_from(&mut &data[..]);
+    let view = LoudsTrieView::new(data);
+    assert_eq!(trie.is_ok(), view.is_ok());
+    if let (Ok(trie), Ok(view)) = (trie, view) {
+        let mut bytes = vec![];
+        trie.write_to(&mut bytes).unwrap();
+        assert_eq!(data, &bytes[..]);
+
+        assert_eq!(trie.validate().is_ok(), view.validate().is_ok());
+        if trie.validate().is_ok() {
+            let keys: Vec<String> = trie.keys().collect();
+            assert_eq!(keys.len(), trie.len());
+            assert!(keys.windows(2).all(|w| w[0] < w[1]));
+            assert_eq!(keys, view.keys().collect::<Vec<_>>());
+            for key in &keys {
+                let id = trie.key_to_id(key).unwrap();
+                assert_eq!(Some(key), trie.id_to_key(id).as_ref());
+                assert_eq!(Some(id), view.key_to_id(key));
+                assert_eq!(Some(key.as_str()), trie.common_prefixes(key).last());
+                assert!(trie.predict(key).next().is_some());
+            }
+        }
+    }
+});
@@ -0,0 +1,43 @@
+//! キーの追加と削除を任意の順に繰り返しても、各トライ木が `BTreeSet` と同じキーを保持することを確かめます。
+
+#![no_main]
+
+use rust_study::string::trie::ByteTrie;
+use rust_study::string::trie::NaiveTrie;
+use rust_study::string::trie::RadixTrie;
+use rust_study::string::trie::TrieMut;
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use std::collections::BTreeSet;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Insert(String),
+    Remove(String),
+}
+
+fn check<T: TrieMut + Default>(ops: &[Op]) {
+    let mut trie = T::default();
+    let mut expected = BTreeSet::new();
+    for op in ops {
+        match op {
+            Op::Insert(key) => assert_eq!(expected.insert(key.clone()), trie.insert(key), "insert({:?})", key),
+            Op::Remove(key) => assert_eq!(expected.remove(key), trie.remove(key), "remove({:?})", key),
+        }
+    }
+    assert_eq!(expected.len(), trie.len());
+    assert!(trie.keys().eq(expected.iter().cloned()));
+    for op in ops {
+        let (Op::Insert(key) | Op::Remove(key)) = op;
+        assert_eq!(expected.contains(key), trie.contains(key), "contains({:?})", key);
+        assert_eq!(expected.range(key.clone()..).take_while(|k| k.starts_with(key.as_str())).count(), trie.count_prefix(key),
+                   "count_prefix({:?})", key);
+    }
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    check::<NaiveTrie>(&ops);
+    check::<ByteTrie>(&ops);
+    check::<RadixTrie>(&ops);
+});
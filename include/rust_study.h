@@ -0,0 +1,52 @@
+/*
+ * C interface of the FM-index and the LOUDS trie of rust_study.
+ *
+ * Build the shared library with the ffi feature:
+ *
+ *     cargo rustc --release --lib --features ffi --crate-type cdylib
+ *
+ * and link target/release/librust_study.so (or the platform equivalent).
+ * Indexes are opaque pointers freed by the matching *_free function.
+ * Builders and loaders return NULL on failure; loaders also reject indexes which fail their consistency checks.
+ * No function unwinds into C: an internal panic makes it return NULL, 0 or false instead.
+ * Byte strings saved by *_save are in the format of Serializable and freed by rust_study_bytes_free.
+ */
+#ifndef RUST_STUDY_H
+#define RUST_STUDY_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RustStudyFMIndex RustStudyFMIndex;
+typedef struct RustStudyTrie RustStudyTrie;
+
+RustStudyFMIndex *rust_study_fm_index_new(const uint8_t *text, size_t len);
+RustStudyFMIndex *rust_study_fm_index_load(const uint8_t *bytes, size_t len);
+uint8_t *rust_study_fm_index_save(const RustStudyFMIndex *index, size_t *out_len);
+void rust_study_fm_index_free(RustStudyFMIndex *index);
+size_t rust_study_fm_index_len(const RustStudyFMIndex *index);
+size_t rust_study_fm_index_count(const RustStudyFMIndex *index, const uint8_t *pattern, size_t len);
+/* writes at most capacity positions in ascending order and returns the number of all the occurrences */
+size_t rust_study_fm_index_locate(const RustStudyFMIndex *index, const uint8_t *pattern, size_t len, size_t *out, size_t capacity);
+
+/* keys are NUL-terminated UTF-8 strings */
+RustStudyTrie *rust_study_trie_new(const char *const *keys, size_t len);
+RustStudyTrie *rust_study_trie_load(const uint8_t *bytes, size_t len);
+uint8_t *rust_study_trie_save(const RustStudyTrie *trie, size_t *out_len);
+void rust_study_trie_free(RustStudyTrie *trie);
+size_t rust_study_trie_len(const RustStudyTrie *trie);
+bool rust_study_trie_contains(const RustStudyTrie *trie, const char *key);
+size_t rust_study_trie_count_prefix(const RustStudyTrie *trie, const char *prefix);
+
+void rust_study_bytes_free(uint8_t *bytes, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,185 @@
+//! ファイルから索引を構築して保存し、検索する例
+//!
+//! ```text
+//! rsindex build-fm <text> <index>      build an FM-index of the bytes of <text>
+//! rsindex build-trie <keys> <index>    build a LOUDS trie of the lines of <keys>
+//! rsindex query <index> [<query>...]   run the queries, or read them from stdin
+//! ```
+//!
+//! 検索は 1 行に 1 つで、 `count <pattern>` 、 `locate <pattern>` 、 `predict <prefix>` 、 `space` のいずれかです。
+
+use rust_study::progress;
+use rust_study::progress::Phase;
+use rust_study::serialize::Serializable;
+use rust_study::space::SpaceReport;
+use rust_study::string::fm_index::NaiveFMIndex;
+use rust_study::string::trie::NaiveLoudsTrie;
+use rust_study::string::trie::Trie;
+
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Write;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage:
+    rsindex build-fm <text> <index>
+    rsindex build-trie <keys> <index>
+    rsindex query <index> [<query>...]
+
+queries:
+    count <pattern>     the number of the occurrences (FM-index) or the keys with the prefix (trie)
+    locate <pattern>    the positions of the occurrences (FM-index only)
+    predict <prefix>    the keys with the prefix (trie only)
+    space               the memory usage of the index";
+
+/// 保存した索引
+enum Index {
+    Fm(Box<NaiveFMIndex>),
+    Trie(NaiveLoudsTrie),
+}
+
+impl Index {
+    /// `r` から、先頭の識別子で種類を判別して読み込みます。
+    fn read_from<R: io::Read>(r: &mut R) -> io::Result<Self> {
+        let mut bytes = vec![];
+        r.read_to_end(&mut bytes)?;
+        let mut rest = bytes.as_slice();
+        if bytes.starts_with(&NaiveFMIndex::MAGIC) {
+            Ok(Index::Fm(Box::new(NaiveFMIndex::deserialize(&mut rest)?)))
+        } else if bytes.starts_with(&NaiveLoudsTrie::MAGIC) {
+            let trie = NaiveLoudsTrie::deserialize(&mut rest)?;
+            // the queries panic on a broken tree, which the format checks alone do not rule out
+            trie.validate()?;
+            Ok(Index::Trie(trie))
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "not an index file"))
+        }
+    }
+
+    /// 1 行の検索 `query` を実行し、結果を `out` に書き出します。
+    fn query<W: Write>(&self, query: &str, out: &mut W) -> io::Result<()> {
+        let (command, arg) = query.split_once(' ').unwrap_or((query, ""));
+        match (self, command) {
+            (Index::Fm(fm), "count") => writeln!(out, "{}", fm.count(arg.as_bytes())),
+            (Index::Fm(fm), "locate") => {
+                let positions: Vec<String> = fm.locate(arg.as_bytes()).iter().map(usize::to_string).collect();
+                writeln!(out, "{}", positions.join(" "))
+            },
+            (Index::Trie(trie), "count") => writeln!(out, "{}", trie.count_prefix(arg)),
+            (Index::Trie(trie), "predict") => trie.predict(arg).try_for_each(|key| writeln!(out, "{}", key)),
+            (Index::Fm(fm), "space") => write!(out, "{}", SpaceReport::new("fm_index", fm.as_ref())),
+            (Index::Trie(trie), "space") => write!(out, "{}", SpaceReport::new("louds_trie", trie)),
+            (_, "locate" | "predict") => writeln!(out, "error: {} is not supported by this index", command),
+            _ => writeln!(out, "error: unknown query {:?}", command),
+        }
+    }
+}
+
+fn build_fm(text: &str, index: &str) -> io::Result<()> {
+    let fm = NaiveFMIndex::new(&fs::read(text)?);
+    fm.serialize(&mut BufWriter::new(File::create(index)?))?;
+    eprintln!("indexed {} bytes", fm.len());
+    Ok(())
+}
+
+fn build_trie(keys: &str, index: &str) -> io::Result<()> {
+    let keys = BufReader::new(File::open(keys)?).lines().collect::<io::Result<Vec<String>>>()?;
+    let trie = NaiveLoudsTrie::with_progress(&keys, &mut report);
+    trie.serialize(&mut BufWriter::new(File::create(index)?))?;
+    eprintln!("indexed {} keys", trie.len());
+    Ok(())
+}
+
+// shows the percentage of the phase on stderr, overwriting the line until the phase ends
+fn report(phase: Phase, done: usize, total: usize) {
+    eprint!("\r{}: {}%", phase, progress::percent(done, total));
+    if done == total {
+        eprintln!();
+    }
+}
+
+fn query(index: &str, queries: &[String]) -> io::Result<()> {
+    let index = Index::read_from(&mut BufReader::new(File::open(index)?))?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if !queries.is_empty() {
+        return queries.iter().try_for_each(|q| index.query(q, &mut out));
+    }
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if !line.is_empty() {
+            index.query(&line, &mut out)?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["build-fm", text, index] => build_fm(text, index),
+        ["build-trie", keys, index] => build_trie(keys, index),
+        ["query", index, ..] => query(index, &args[2..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        },
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(index: &Index, query: &str) -> String {
+        let mut out = vec![];
+        index.query(query, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn round_trip<T: Serializable>(value: &T) -> Index {
+        let mut bytes = vec![];
+        value.serialize(&mut bytes).unwrap();
+        Index::read_from(&mut bytes.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn fm_index() {
+        let index = round_trip(&NaiveFMIndex::new(b"abracadabra"));
+        assert_eq!("2\n", run(&index, "count abra"));
+        assert_eq!("0 7\n", run(&index, "locate abra"));
+        assert_eq!("\n", run(&index, "locate xyz"));
+        assert!(run(&index, "predict a").starts_with("error: "));
+        assert!(run(&index, "space").starts_with("fm_index: "));
+    }
+
+    #[test]
+    fn louds_trie() {
+        let index = round_trip(&NaiveLoudsTrie::new(&["the", "their", "them", "this"]));
+        assert_eq!("3\n", run(&index, "count the"));
+        assert_eq!("the\ntheir\nthem\n", run(&index, "predict the"));
+        assert!(run(&index, "locate the").starts_with("error: "));
+        assert!(run(&index, "find the").starts_with("error: unknown"));
+    }
+
+    #[test]
+    fn broken() {
+        assert!(Index::read_from(&mut &b"NOTINDEX"[..]).is_err());
+        let mut bytes = vec![];
+        NaiveFMIndex::new(b"abc").serialize(&mut bytes).unwrap();
+        assert!(Index::read_from(&mut &bytes[..bytes.len() - 1]).is_err());
+    }
+}
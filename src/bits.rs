@@ -1,2 +1,6 @@
 pub mod fid;
+pub mod gamma;
+#[cfg(feature = "serde")]
+mod serde_impl;
+pub mod succinct_rmq;
 pub mod wavelet_matrix;
@@ -1,6 +1,14 @@
 pub mod naive_fid;
 pub use naive_fid::NaiveFID;
 
+use crate::error;
+use crate::error::Error;
+#[cfg(feature = "parallel")]
+use crate::parallel::Chunking;
+
+use alloc::format;
+use alloc::vec::Vec;
+
 /// Fully Indexable Dictionary
 ///
 /// rank操作およびselect操作が可能なビットベクトル
@@ -35,6 +43,23 @@ pub trait FID {
     /// `false` は `0` 、 `true` は `1` としてビットベクトルを構築します。
     fn from_bool_vec(vec: &Vec<bool>) -> Self;
 
+    /// [`Self::from_bool_vec()`] と同じビットベクトルを、 `chunking` の大きさのチャンクごとに並列に構築します。
+    ///
+    /// 既定の実装は並列化せずに [`Self::from_bool_vec()`] を呼びます。
+    #[cfg(feature = "parallel")]
+    fn par_from_bool_vec(vec: &[bool], chunking: Chunking) -> Self where Self: Sized {
+        let _ = chunking;
+        Self::from_bool_vec(&vec.to_vec())
+    }
+
+    /// [`Self::from_bool_vec()`] と同じビットベクトルを、 `bits` から順にビットを受け取って構築します。
+    ///
+    /// `Vec<bool>` を作らずに構築できるため、ビットを 1 バイトずつ持つ作業領域を省けます。
+    /// 既定の実装は `bits` を `Vec<bool>` に集めて [`Self::from_bool_vec()`] を呼びます。
+    fn from_bool_iter<I: IntoIterator<Item = bool>>(bits: I) -> Self where Self: Sized {
+        Self::from_bool_vec(&bits.into_iter().collect())
+    }
+
     /// ビットベクトルの `i` 番目(0-based)のビットにアクセスします。
     ///
     /// # Panics
@@ -62,6 +87,27 @@ pub trait FID {
     /// Panics if `i` is out of bounds. `i` should be in `[0, len)`
     fn access(&self, i: usize) -> bool;
 
+    /// ビットベクトルの `[i, i + len)` のビットを、 `i` 番目のビットを最下位ビットとする整数として返します。
+    ///
+    /// 既定では [`Self::get()`] を `len` 回呼び出します。ワード単位でビットを保持する実装は、これを上書きします。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_study::bits::fid::*;
+    /// let fid = NaiveFID::from_bool_vec(&vec![true, true, false, true, false, false, true, false]);
+    /// assert_eq!(0b1011, fid.get_bits(0, 4));
+    /// assert_eq!(0b1001, fid.get_bits(3, 5));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len > 64` or the range is out of bounds. `i + len` should be in `[0, len]`
+    fn get_bits(&self, i: usize, len: usize) -> u64 {
+        assert!(len <= 64 && i + len <= self.len());
+        (0..len).fold(0, |bits, k| bits | (self.get(i + k) as u64) << k)
+    }
+
     /// ビットベクトルの `[0, i)` の中の `0` の個数を数えます。
     ///
     /// # Examples
@@ -175,120 +221,207 @@ pub trait FID {
             }
         }
     }
+
+    /// [`Self::get()`] と同じですが、 `i` が範囲外の場合、パニックせずに [`Error::OutOfBounds`] を返します。
+    fn try_get(&self, i: usize) -> Result<bool, Error> {
+        error::check_index(i, self.len())?;
+        Ok(self.get(i))
+    }
+
+    /// [`Self::rank0()`] と同じですが、 `i` が `[0, len]` の外の場合、パニックせずに [`Error::OutOfBounds`] を返します。
+    fn try_rank0(&self, i: usize) -> Result<usize, Error> {
+        error::check_index(i, self.len() + 1)?;
+        Ok(self.rank0(i))
+    }
+
+    /// [`Self::rank1()`] と同じですが、 `i` が `[0, len]` の外の場合、パニックせずに [`Error::OutOfBounds`] を返します。
+    fn try_rank1(&self, i: usize) -> Result<usize, Error> {
+        error::check_index(i, self.len() + 1)?;
+        Ok(self.rank1(i))
+    }
+
+    /// `i` 番目(0-based)の `0` の位置を返します。
+    ///
+    /// [`Self::select0()`] と異なり、 `0` の個数が `i` 以下の場合、 [`Error::OutOfBounds`] を返します。
+    fn try_select0(&self, i: usize) -> Result<usize, Error> {
+        error::check_index(i, self.rank0(self.len()))?;
+        Ok(self.select0(i))
+    }
+
+    /// `i` 番目(0-based)の `1` の位置を返します。
+    ///
+    /// [`Self::select1()`] と異なり、 `1` の個数が `i` 以下の場合、 [`Error::OutOfBounds`] を返します。
+    fn try_select1(&self, i: usize) -> Result<usize, Error> {
+        error::check_index(i, self.rank1(self.len()))?;
+        Ok(self.select1(i))
+    }
+
+    /// 内部の整合性を検証し、最初に見つけた不整合を [`Error::CorruptData`] で返します。
+    ///
+    /// 信頼できないファイルや壊れている恐れのあるファイルから読み込んだ後に呼び出します。
+    /// 既定の実装は、すべての `i` について [`Self::rank1()`] が `[0, i)` の `1` の個数と一致することを `O(n)` 回の rank 操作で確かめます。
+    fn validate(&self) -> Result<(), Error> {
+        let mut ones = 0;
+        for i in 0..=self.len() {
+            let rank = self.rank1(i);
+            if rank != ones {
+                return Err(Error::CorruptData(format!("rank1({}) is {}, but {} ones precede it", i, rank, ones)));
+            }
+            if i < self.len() && self.get(i) {
+                ones += 1;
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 #[generic_tests::define]
 mod tests {
     use super::*;
+    #[cfg(feature = "std")]
+    use crate::serialize::Serializable;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
     use std::cmp::PartialEq;
     use std::fmt::Debug;
     use std::ops::Not;
-    use rand::Rng;
 
     #[instantiate_tests(<NaiveFID>)]
     mod naive {}
 
-    #[test]
-    fn set_get<T: FID>() {
-        let len = 1000;
-        let mut rng = rand::thread_rng();
+    // bit vectors of various lengths and densities, including all zeros and all ones
+    fn bits() -> impl Strategy<Value = Vec<bool>> {
+        prop_oneof![Just(0.0), 0.0..1.0, Just(1.0)].prop_flat_map(|density| vec(prop::bool::weighted(density), 0..300))
+    }
 
-        let mut bv = vec![false; len];
-        for i in 0..len {
-            bv[i] = rng.gen();
+    // checks every query of fid against the model bits
+    fn assert_same_as_vec<T: FID>(fid: &T, bits: &[bool]) -> Result<(), TestCaseError> {
+        prop_assert_eq!(bits.len(), fid.len());
+        let mut ones = vec![];
+        let mut zeros = vec![];
+        for (i, &b) in bits.iter().enumerate() {
+            prop_assert_eq!(b, fid.get(i), "get({})", i);
+            prop_assert_eq!(b, fid.access(i), "access({})", i);
+            prop_assert_eq!(ones.len(), fid.rank1(i), "rank1({})", i);
+            prop_assert_eq!(zeros.len(), fid.rank0(i), "rank0({})", i);
+            let len = (bits.len() - i).min(64);
+            let expected = bits[i..i + len].iter().rev().fold(0, |block, &b| block << 1 | b as u64);
+            prop_assert_eq!(expected, fid.get_bits(i, len), "get_bits({}, {})", i, len);
+            if b {
+                ones.push(i);
+            } else {
+                zeros.push(i);
+            }
         }
-        let mut fid = T::from_bool_vec(&bv);
-        // check overwrite
-        for i in 0..len {
-            bv[i] = rng.gen();
-            fid.set(i, bv[i]);
+        prop_assert_eq!(ones.len(), fid.rank1(bits.len()));
+        prop_assert_eq!(zeros.len(), fid.rank0(bits.len()));
+        for (k, &i) in ones.iter().enumerate() {
+            prop_assert_eq!(i, fid.select1(k), "select1({})", k);
         }
-
-        for i in 0..len {
-            assert_eq!(bv[i], fid.get(i));
+        for (k, &i) in zeros.iter().enumerate() {
+            prop_assert_eq!(i, fid.select0(k), "select0({})", k);
         }
+        Ok(())
+    }
+
+    #[test]
+    fn same_as_vec<T: FID>() {
+        proptest!(|(mut bits in bits(), updates in vec((any::<prop::sample::Index>(), any::<bool>()), 0..100))| {
+            let mut fid = T::from_bool_vec(&bits);
+            assert_same_as_vec(&fid, &bits)?;
+            // check if set/unset updates offsets correctly
+            if !bits.is_empty() {
+                for (i, b) in updates {
+                    let i = i.index(bits.len());
+                    bits[i] = b;
+                    fid.set(i, b);
+                }
+            }
+            assert_same_as_vec(&fid, &bits)?;
+        });
     }
 
     #[test]
     fn from_bool_vec<T: FID + PartialEq + Debug>() {
-        let len = 1000;
-        let mut rng = rand::thread_rng();
-        let mut bv = vec![false; len];
-        let mut expected = T::new(len);
-        for i in 0..len {
-            bv[i] = rng.gen();
-            expected.set(i, bv[i]);
-        }
-        let fid = T::from_bool_vec(&bv);
-        assert_eq!(expected, fid);
+        proptest!(|(bits in bits())| {
+            let mut expected = T::new(bits.len());
+            for (i, &b) in bits.iter().enumerate() {
+                expected.set(i, b);
+            }
+            prop_assert_eq!(&expected, &T::from_bool_vec(&bits));
+            prop_assert_eq!(expected, T::from_bool_iter(bits.iter().copied()));
+        });
     }
 
     #[test]
-    fn rank<T: FID>() {
-        let mut rng = rand::thread_rng();
-        let len = 1000;
-
-        let mut bv = (0..len).map(|_| rng.gen() ).collect();
-        let mut fid = T::from_bool_vec(&bv);
-        // check if set/unset updates offsets correclty
-        for i in 0..len {
-            bv[i] = rng.gen();
-            fid.set(i, bv[i]);
-        }
+    fn not<T: FID + PartialEq + Debug + Not<Output=T>>() {
+        proptest!(|(bits in bits())| {
+            let inverted: Vec<bool> = bits.iter().map(|b| !b).collect();
+            prop_assert_eq!(T::from_bool_vec(&inverted), !T::from_bool_vec(&bits));
+        });
+    }
 
-        let mut rank0 = 0;
-        let mut rank1 = 0;
-        for i in 0..len {
-            assert_eq!(rank0, fid.rank0(i));
-            assert_eq!(rank1, fid.rank1(i));
-            // rankN counts Ns in [0, i), so increment after check
-            if bv[i] {
-                rank1 += 1
-            } else {
-                rank0 += 1
-            }
-        }
+    #[test]
+    fn try_ops<T: FID>() {
+        let fid = T::from_bool_vec(&vec![true, false, false, true, false]);
+        assert_eq!(Ok(true), fid.try_get(3));
+        assert_eq!(Err(Error::OutOfBounds { index: 5, len: 5 }), fid.try_get(5));
+        assert_eq!(Ok(3), fid.try_rank0(5));
+        assert_eq!(Ok(2), fid.try_rank1(5));
+        assert!(fid.try_rank0(6).is_err());
+        assert!(fid.try_rank1(6).is_err());
+        assert_eq!(Ok(4), fid.try_select0(2));
+        assert_eq!(Err(Error::OutOfBounds { index: 3, len: 3 }), fid.try_select0(3));
+        assert_eq!(Ok(3), fid.try_select1(1));
+        assert_eq!(Err(Error::OutOfBounds { index: 2, len: 2 }), fid.try_select1(2));
     }
 
     #[test]
-    fn select<T: FID>() {
-        let len = 1000;
-        let mut rng = rand::thread_rng();
-
-        let mut bv = (0..len).map(|_| rng.gen() ).collect();
-        let mut fid = T::from_bool_vec(&bv);
-        // check if set/unset updates offsets correclty
-        for i in 0..len {
-            bv[i] = rng.gen();
-            fid.set(i, bv[i]);
-        }
+    fn validate<T: FID>() {
+        let mut fid = T::from_bool_vec(&(0..300).map(|i| i % 7 < 3).collect());
+        fid.set(100, true);
+        fid.set(0, false);
+        assert_eq!(Ok(()), fid.validate());
+        assert_eq!(Ok(()), T::new(0).validate());
+    }
 
-        let mut prev = 0;
-        for i in 0..fid.rank0(fid.len()) {
-            let pos = fid.select0(i);
-            assert!(!fid.access(pos));
-            assert!(i == 0 || pos > prev);
-            prev = pos;
-        }
-        let mut prev = 0;
-        for i in 0..fid.rank1(fid.len()) {
-            let pos = fid.select1(i);
-            assert!(fid.access(pos));
-            assert!(i == 0 || pos > prev);
-            prev = pos;
-        }
+    #[test]
+    #[cfg(feature = "std")]
+    fn serialize<T: FID + Serializable + PartialEq + Debug>() {
+        proptest!(|(bits in bits())| {
+            let fid = T::from_bool_vec(&bits);
+            let mut bytes = vec![];
+            fid.serialize(&mut bytes).unwrap();
+            let read = T::deserialize(&mut bytes.as_slice()).unwrap();
+            prop_assert_eq!(&fid, &read);
+            assert_same_as_vec(&read, &bits)?;
+        });
+    }
+}
+
+// the default validation, on a bit vector whose rank is off by one from a position
+#[cfg(test)]
+mod default_validate_tests {
+    use super::*;
+
+    struct Skewed(NaiveFID, usize);
+
+    impl FID for Skewed {
+        fn new(n: usize) -> Self { Skewed(NaiveFID::new(n), usize::MAX) }
+        fn from_bool_vec(vec: &Vec<bool>) -> Self { Skewed(NaiveFID::from_bool_vec(vec), usize::MAX) }
+        fn get(&self, i: usize) -> bool { self.0.get(i) }
+        fn set(&mut self, i: usize, bit: bool) { self.0.set(i, bit) }
+        fn len(&self) -> usize { self.0.len() }
+        fn access(&self, i: usize) -> bool { self.0.access(i) }
+        fn rank1(&self, i: usize) -> usize { self.0.rank1(i) + (i >= self.1) as usize }
     }
 
     #[test]
-    fn not<T: FID + PartialEq + Debug + Not<Output=T>>() {
-        let len = 1000;
-        let mut rng = rand::thread_rng();
-        let actual_vec: Vec<bool> = (0..len).map(|_| rng.gen() ).collect();
-        let expected_vec: Vec<bool> = actual_vec.iter().map(|b| !b ).collect();
-
-        let bv = T::from_bool_vec(&actual_vec);
-        let expected = T::from_bool_vec(&expected_vec);
-        assert_eq!(expected, !bv);
+    fn validate() {
+        let mut fid = Skewed::from_bool_vec(&vec![true, false, true, true]);
+        assert_eq!(Ok(()), fid.validate());
+        fid.1 = 3;
+        assert_eq!(Err(Error::CorruptData("rank1(3) is 3, but 2 ones precede it".to_string())), fid.validate());
     }
 }
@@ -1,4 +1,22 @@
 use super::FID;
+use crate::error::Error;
+#[cfg(feature = "std")]
+use crate::serialize;
+#[cfg(feature = "std")]
+use crate::serialize::Serializable;
+#[cfg(feature = "parallel")]
+use crate::parallel::Chunking;
+use crate::space::SpaceUsage;
+
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::io::Write;
 
 #[derive(Clone, Debug)]
 pub struct NaiveFID {
@@ -58,6 +76,39 @@ impl FID for NaiveFID {
         }
     }
 
+    #[cfg(feature = "parallel")]
+    fn par_from_bool_vec(vec: &[bool], chunking: Chunking) -> Self {
+        use rayon::prelude::*;
+
+        let n = vec.len();
+        let mut blocks: Vec<u64> = chunking.chunks(vec, 64)
+            .flat_map_iter(|chunk| {
+                chunk.chunks(64).map(|bits| bits.iter().rev().fold(0, |block, &b| block << 1 | b as u64))
+            })
+            .collect();
+        // the last block is always present, even if it has no bits
+        blocks.resize(n / 64 + 1, 0);
+        let popcount_offset = Self::construct_popcount_offset(&blocks);
+        NaiveFID { n, blocks, popcount_offset }
+    }
+
+    fn from_bool_iter<I: IntoIterator<Item = bool>>(bits: I) -> Self {
+        let bits = bits.into_iter();
+        let mut n = 0;
+        let mut blocks = Vec::with_capacity(bits.size_hint().0 / 64 + 1);
+        blocks.push(0u64);
+        for bit in bits {
+            blocks[n / 64] |= (bit as u64) << (n % 64);
+            n += 1;
+            // the last block is always present, even if it has no bits
+            if n.is_multiple_of(64) {
+                blocks.push(0);
+            }
+        }
+        let popcount_offset = Self::construct_popcount_offset(&blocks);
+        NaiveFID { n, blocks, popcount_offset }
+    }
+
     fn get(&self, i: usize) -> bool {
         assert!(i < self.n);
         let block_idx = i / 64;
@@ -66,6 +117,20 @@ impl FID for NaiveFID {
         (self.blocks[block_idx] & mask) != 0
     }
 
+    fn get_bits(&self, i: usize, len: usize) -> u64 {
+        assert!(len <= 64 && i + len <= self.n);
+        if len == 0 {
+            return 0;
+        }
+        let block_idx = i / 64;
+        let bit_idx = i - block_idx * 64;
+        let mut bits = self.blocks[block_idx] >> bit_idx;
+        if bit_idx + len > 64 {
+            bits |= self.blocks[block_idx + 1] << (64 - bit_idx);
+        }
+        if len == 64 { bits } else { bits & ((1 << len) - 1) }
+    }
+
     fn set(&mut self, i: usize, bit: bool) -> () {
         assert!(i < self.n);
         let block_idx = i / 64;
@@ -98,9 +163,30 @@ impl FID for NaiveFID {
         let mask = if bit_idx == 0 { 0 } else { (!0_u64) >> (64 - bit_idx) };
         self.popcount_offset[block_idx] + (self.blocks[block_idx] & mask).count_ones() as usize
     }
+
+    /// ブロックの個数と末尾の未使用ビット、各ブロックの前までの `1` の個数 (rank のディレクトリ) をブロックの popcount と突き合わせて、 `O(n / 64)` で検証します。
+    fn validate(&self) -> Result<(), Error> {
+        if self.blocks.len() != self.n / 64 + 1 || self.popcount_offset.len() != self.blocks.len() {
+            return Err(Error::CorruptData(format!(
+                "{} blocks and {} popcount offsets for {} bits, expected {}",
+                self.blocks.len(), self.popcount_offset.len(), self.n, self.n / 64 + 1,
+            )));
+        }
+        if self.blocks[self.n / 64] >> (self.n % 64) != 0 {
+            return Err(Error::CorruptData(format!("bits beyond length {} are set", self.n)));
+        }
+        let mut popcount = 0;
+        for (b, (block, offset)) in self.blocks.iter().zip(&self.popcount_offset).enumerate() {
+            if *offset != popcount {
+                return Err(Error::CorruptData(format!("popcount offset of block {} is {}, expected {}", b, offset, popcount)));
+            }
+            popcount += block.count_ones() as usize;
+        }
+        Ok(())
+    }
 }
 
-impl std::ops::Not for NaiveFID {
+impl core::ops::Not for NaiveFID {
     type Output = Self;
     fn not(self) -> Self::Output {
         let mut n = self.n;
@@ -111,7 +197,8 @@ impl std::ops::Not for NaiveFID {
                 blocks.push(!b);
                 n -= 64;
             } else {
-                let nb = !b & (!0_u64 >> (64 - n));
+                let mask = if n == 0 { 0 } else { (!0_u64) >> (64 - n) };
+                let nb = !b & mask;
                 blocks.push(nb);
             }
         }
@@ -134,3 +221,52 @@ impl PartialEq for NaiveFID {
         self.blocks == other.blocks
     }
 }
+
+#[cfg(feature = "std")]
+impl Serializable for NaiveFID {
+    const MAGIC: [u8; 8] = *b"NAIVEFID";
+    const VERSION: u32 = 1;
+
+    fn write_body<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        serialize::write_u64(w, self.n as u64)?;
+        serialize::write_u64s(w, &self.blocks)
+    }
+
+    fn read_body<R: Read>(r: &mut R) -> io::Result<Self> {
+        let n = serialize::read_len(r)?;
+        let blocks = serialize::read_u64s(r)?;
+        if blocks.len() != n / 64 + 1 || blocks[n / 64] >> (n % 64) != 0 {
+            return Err(serialize::invalid_data("bits out of range"));
+        }
+        let popcount_offset = Self::construct_popcount_offset(&blocks);
+        Ok(NaiveFID { n, blocks, popcount_offset })
+    }
+}
+
+impl SpaceUsage for NaiveFID {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("blocks", &self.blocks), ("popcount_offset", &self.popcount_offset)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let mut fid = NaiveFID::from_bool_vec(&(0..200).map(|i| i % 3 == 0).collect());
+        fid.set(70, true);
+        assert_eq!(Ok(()), fid.validate());
+
+        let mut broken = fid.clone();
+        broken.popcount_offset[2] += 1;
+        assert_eq!(Err(Error::CorruptData("popcount offset of block 2 is 45, expected 44".to_string())), broken.validate());
+        let mut broken = fid.clone();
+        broken.blocks[3] |= 1 << 63;
+        assert!(broken.validate().unwrap_err().to_string().contains("beyond length 200"));
+        let mut broken = fid.clone();
+        broken.blocks.pop();
+        assert!(broken.validate().is_err());
+    }
+}
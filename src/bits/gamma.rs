@@ -0,0 +1,169 @@
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Elias gamma 符号で整数を詰めた列
+///
+/// 非負整数 `x` を `x + 1` の Elias gamma 符号 (`x + 1` のビット長より 1 少ない個数の `0` と、 `x + 1` の 2 進表記) で表し、
+/// ビット列に順に追加します。小さい値ほど短い符号になるため、ソートした列の差分のような小さい値の列を小さく保持できます。
+/// 読み出しは先頭からの順次アクセスのみです。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::bits::gamma::GammaVector;
+/// let mut gamma = GammaVector::new();
+/// for x in [0, 1, 2, 100] {
+///     gamma.push(x);
+/// }
+/// assert_eq!(4, gamma.len());
+/// // 1 + 3 + 3 + 13 bits
+/// assert_eq!(20, gamma.bits());
+/// assert_eq!(vec![0, 1, 2, 100], gamma.iter().collect::<Vec<_>>());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GammaVector {
+    // the bits in the order from the least significant bit of the first word
+    words: Vec<u64>,
+    bits: usize,
+    len: usize,
+}
+
+impl GammaVector {
+    /// 空の列を作成します。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 非負整数 `x` を末尾に追加します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` is `u64::MAX`.
+    pub fn push(&mut self, x: u64) {
+        assert!(x < u64::MAX, "x should be less than u64::MAX");
+        let x = x + 1;
+        let width = 64 - x.leading_zeros() as usize;
+        self.bits += width - 1;
+        for i in (0..width).rev() {
+            self.push_bit((x >> i) & 1 == 1);
+        }
+        self.len += 1;
+    }
+
+    /// 値の個数を返します。
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 値が存在しない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 符号のビット数を返します。
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// 値を先頭から順に返します。
+    pub fn iter(&self) -> GammaIter<'_> {
+        GammaIter { gamma: self, pos: 0, remaining: self.len }
+    }
+
+    fn push_bit(&mut self, bit: bool) {
+        if self.bits / 64 >= self.words.len() {
+            self.words.push(0);
+        }
+        if bit {
+            self.words[self.bits / 64] |= 1 << (self.bits % 64);
+        }
+        self.bits += 1;
+    }
+
+    fn get_bit(&self, i: usize) -> bool {
+        (self.words[i / 64] >> (i % 64)) & 1 == 1
+    }
+}
+
+/// [`GammaVector`] の値を先頭から順に返すイテレータ
+pub struct GammaIter<'a> {
+    gamma: &'a GammaVector,
+    // the position in bits
+    pos: usize,
+    remaining: usize,
+}
+
+impl Iterator for GammaIter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let mut zeros = 0;
+        while !self.gamma.get_bit(self.pos) {
+            zeros += 1;
+            self.pos += 1;
+        }
+        let mut x = 0;
+        for _ in 0..=zeros {
+            x = (x << 1) | self.gamma.get_bit(self.pos) as u64;
+            self.pos += 1;
+        }
+        self.remaining -= 1;
+        Some(x - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl SpaceUsage for GammaVector {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("words", &self.words)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut gamma = GammaVector::new();
+        assert!(gamma.is_empty());
+        let mut values = vec![0, u64::MAX - 1, 1 << 63, (1 << 63) - 1];
+        for _ in 0..1000 {
+            let width = rng.gen_range(1, 40);
+            values.push(rng.gen_range(0, 1 << width));
+        }
+        for &x in &values {
+            gamma.push(x);
+        }
+        assert_eq!(values.len(), gamma.len());
+        assert_eq!(values, gamma.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn bits() {
+        let mut gamma = GammaVector::new();
+        for x in 0..7 {
+            gamma.push(x);
+        }
+        // 1, 3, 3, 5, 5, 5, 5
+        assert_eq!(27, gamma.bits());
+    }
+
+    #[test]
+    #[should_panic(expected = "x should be less than u64::MAX")]
+    fn too_large() {
+        GammaVector::new().push(u64::MAX);
+    }
+}
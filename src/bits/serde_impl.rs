@@ -0,0 +1,74 @@
+// Serialization of the bit vectors, the wavelet matrix and the RMQ as the bytes of their sections, and of the gamma codes as their values.
+
+use super::fid::FID;
+use super::fid::NaiveFID;
+use super::gamma::GammaVector;
+use super::succinct_rmq::SuccinctRMQ;
+use super::wavelet_matrix::U8WaveletMatrix;
+use crate::serialize;
+use crate::serialize::Serializable;
+
+use serde::de;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+impl Serialize for NaiveFID {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize::serialize_section(self, serializer)
+    }
+}
+
+impl <'de> Deserialize<'de> for NaiveFID {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serialize::deserialize_section(deserializer)
+    }
+}
+
+impl <T: FID + Serializable> Serialize for U8WaveletMatrix<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize::serialize_section(self, serializer)
+    }
+}
+
+impl <'de, T: FID + Serializable> Deserialize<'de> for U8WaveletMatrix<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serialize::deserialize_section(deserializer)
+    }
+}
+
+// the BP of the array, from which the sparse tables are rebuilt
+impl <T: FID + Serializable> Serialize for SuccinctRMQ<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize::serialize_section(self.bp(), serializer)
+    }
+}
+
+impl <'de, T: FID + Serializable> Deserialize<'de> for SuccinctRMQ<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bp = serialize::deserialize_section(deserializer)?;
+        SuccinctRMQ::try_from_bp(bp).map_err(de::Error::custom)
+    }
+}
+
+// the values in order
+impl Serialize for GammaVector {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl <'de> Deserialize<'de> for GammaVector {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let values = Vec::<u64>::deserialize(deserializer)?;
+        let mut gamma = GammaVector::new();
+        for x in values {
+            if x == u64::MAX {
+                return Err(de::Error::custom("u64::MAX can't be encoded"));
+            }
+            gamma.push(x);
+        }
+        Ok(gamma)
+    }
+}
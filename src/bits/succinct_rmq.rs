@@ -0,0 +1,377 @@
+use super::fid::FID;
+use super::fid::NaiveFID;
+#[cfg(feature = "serde")]
+use crate::error::Error;
+use crate::space::SpaceUsage;
+
+#[cfg(feature = "serde")]
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// bits of the BP in a block, scanned with `BYTE_TABLE`
+const BLOCK_SIZE: usize = 256;
+// blocks in a superblock, whose ranges are answered by `block_sparse`
+const SUPERBLOCK_BLOCKS: usize = 32;
+const SUPERBLOCK_SIZE: usize = BLOCK_SIZE * SUPERBLOCK_BLOCKS;
+
+// for each byte (bits in LSB-first order): the excess after the 8 bits,
+// the minimum excess after each bit and the rightmost bit where it is reached
+const BYTE_TABLE: [(i8, i8, u8); 256] = byte_table();
+
+const fn byte_table() -> [(i8, i8, u8); 256] {
+    let mut table = [(0, 0, 0); 256];
+    let mut x = 0;
+    while x < 256 {
+        let mut e = 0;
+        let mut min = i8::MAX;
+        let mut pos = 0;
+        let mut i = 0;
+        while i < 8 {
+            e += if (x >> i) & 1 == 1 { 1 } else { -1 };
+            if e <= min {
+                min = e;
+                pos = i;
+            }
+            i += 1;
+        }
+        table[x] = (e, min, pos as u8);
+        x += 1;
+    }
+    table
+}
+
+/// 簡潔 Range Minimum Query
+///
+/// 配列のデカルト木を括弧列 (BP) で表現したビットベクトル `2n + 2` ビットと、
+/// 括弧列の超過 (excess) の最小値の位置を引く 2 段の疎テーブルから構成される RMQ 。
+/// 構築後は元の配列を保持せずに、区間の最小値の位置を返します。
+///
+/// 括弧列は、配列を左から走査しながら最小値のスタックを管理し、
+/// スタックから要素を取り除くたびに `)` 、積むたびに `(` を出力することで構築します。
+/// 区間 `[s, e)` の最小値の位置は、括弧列上の対応する区間の超過の最小値の位置から求まります。
+///
+/// 括弧列を 256 ビットのブロックと、 32 ブロックのスーパーブロックに分けます。
+/// ブロック内の最小値はバイトごとの表引き (ブロックあたり高々 32 回) で求め、
+/// スーパーブロック内のブロックの範囲は、スーパーブロック内の疎テーブル (ブロックあたり 4 バイト) 、
+/// スーパーブロックの範囲は、スーパーブロックの疎テーブル (スーパーブロックあたり `32 log` ビット) で求めます。
+/// 問い合わせは定数回の表引きと rank 、 2 回の select で済むため、 select が定数時間の FID では `O(1)` です
+/// ( [`NaiveFID`] の select は二分探索のため、 [`NaiveSuccinctRMQ`] では `O(log n)` です)。
+///
+/// 補助構造は括弧列 1 ビットあたり高々約 0.25 ビットです。
+/// ブロック長を定数に固定しているため漸近的には `o(n)` ではなく、位置を `u32` で持つため括弧列は `2^32` ビット未満に限られます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::bits::succinct_rmq::NaiveSuccinctRMQ;
+/// let rmq = NaiveSuccinctRMQ::new(&[3, 1, 4, 1, 5, 9, 2, 6]);
+/// assert_eq!(8, rmq.len());
+///
+/// // rmq(s, e) は [s, e) の最小値の位置を返します。最小値が複数ある場合は一番左の位置を返します。
+/// assert_eq!(1, rmq.rmq(0, 8));
+/// assert_eq!(3, rmq.rmq(2, 8));
+/// assert_eq!(6, rmq.rmq(4, 8));
+/// assert_eq!(5, rmq.rmq(5, 6));
+/// ```
+pub struct SuccinctRMQ<T: FID> {
+    n: usize,
+    bp: T,
+    // block_sparse[k][b]: the best block in [b, b + 2^(k+1)), relative to the superblock of `b`
+    block_sparse: Vec<Vec<u8>>,
+    // superblock_sparse[k][s]: the best position in the superblocks [s, s + 2^k)
+    superblock_sparse: Vec<Vec<u32>>,
+}
+
+impl <T: FID> SuccinctRMQ<T> {
+    /// 配列 `vec` から RMQ を構築します。
+    pub fn new<V: Ord>(vec: &[V]) -> Self {
+        let n = vec.len();
+        let mut bv = Vec::with_capacity(2 * n + 2);
+        let mut stack: Vec<&V> = Vec::with_capacity(n);
+        // sentinel
+        bv.push(true);
+        for v in vec {
+            while let Some(top) = stack.last() {
+                if *top > v {
+                    stack.pop();
+                    bv.push(false);
+                } else {
+                    break;
+                }
+            }
+            stack.push(v);
+            bv.push(true);
+        }
+        bv.resize(bv.len() + stack.len() + 1, false);
+        Self::from_bp(T::from_bool_vec(&bv))
+    }
+
+    /// 配列の括弧列 `bp` から RMQ を構築します。
+    ///
+    /// 括弧列が、配列の括弧列として正しくない場合、 [`Error::CorruptData`] を返します。
+    #[cfg(feature = "serde")]
+    pub(crate) fn try_from_bp(bp: T) -> Result<Self, Error> {
+        let len = bp.len();
+        if len < 2 || !len.is_multiple_of(2) || len > u32::MAX as usize {
+            return Err(Error::CorruptData(format!("{} bits of the BP, expected an even number of bits from 2 to 2^32 - 1", len)));
+        }
+        // the sentinel should be closed only by the last bit
+        let mut excess = 0_isize;
+        for i in 0..len {
+            excess += if bp.access(i) { 1 } else { -1 };
+            if excess <= 0 && i + 1 != len {
+                return Err(Error::CorruptData(format!("the excess of the BP reaches {} at {} before the end", excess, i)));
+            }
+        }
+        if excess != 0 {
+            return Err(Error::CorruptData(format!("the excess of the BP is {} at the end", excess)));
+        }
+        Ok(Self::from_bp(bp))
+    }
+
+    // builds the sparse tables over the BP of an array
+    fn from_bp(bp: T) -> Self {
+        let mut rmq = SuccinctRMQ {
+            n: bp.len() / 2 - 1,
+            bp,
+            block_sparse: vec![],
+            superblock_sparse: vec![],
+        };
+
+        let len = rmq.bp.len();
+        let blocks = len.div_ceil(BLOCK_SIZE);
+        let mut level: Vec<usize> = (0..blocks).map(|b| rmq.block_min(b)).collect();
+        let mut width = 1;
+        while width < SUPERBLOCK_BLOCKS {
+            // entries whose range leaves the superblock are never looked up
+            let next: Vec<usize> = (0..blocks)
+                .map(|b| if b % SUPERBLOCK_BLOCKS + width * 2 <= SUPERBLOCK_BLOCKS && b + width < blocks {
+                    rmq.better(level[b], level[b + width])
+                } else {
+                    level[b]
+                })
+                .collect();
+            // the whole superblock is kept in `superblock_sparse` instead
+            if width * 2 < SUPERBLOCK_BLOCKS {
+                rmq.block_sparse.push(
+                    next.iter().enumerate().map(|(b, p)| (p / BLOCK_SIZE - b / SUPERBLOCK_BLOCKS * SUPERBLOCK_BLOCKS) as u8).collect()
+                );
+            }
+            level = next;
+            width *= 2;
+        }
+
+        let superblocks = len.div_ceil(SUPERBLOCK_SIZE);
+        let mut level: Vec<u32> = level.into_iter().step_by(SUPERBLOCK_BLOCKS).map(|p| p as u32).collect();
+        let mut width = 1;
+        while width * 2 <= superblocks {
+            let next = (0..=(superblocks - width * 2))
+                .map(|s| rmq.better(level[s] as usize, level[s + width] as usize) as u32)
+                .collect();
+            rmq.superblock_sparse.push(level);
+            level = next;
+            width *= 2;
+        }
+        rmq.superblock_sparse.push(level);
+        rmq
+    }
+
+    /// 配列の括弧列を参照します。
+    #[cfg(feature = "serde")]
+    pub(crate) fn bp(&self) -> &T {
+        &self.bp
+    }
+
+    /// 元の配列の長さを返します。
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    /// 元の配列が空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// 区間 `[s, e)` の最小値の位置を返します。
+    ///
+    /// 最小値が複数ある場合は一番左の位置を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is empty or out of bounds. `s < e <= len` should hold.
+    pub fn rmq(&self, s: usize, e: usize) -> usize {
+        assert!(s < e && e <= self.n);
+        let a = self.bp.select1(s + 1) - 1;
+        let b = self.bp.select1(e);
+        let p = self.range_min(a, b);
+        self.bp.rank1(p + 1) - 1
+    }
+
+    // excess after position `p`
+    fn excess(&self, p: usize) -> isize {
+        2 * self.bp.rank1(p + 1) as isize - (p + 1) as isize
+    }
+
+    // rightmost position of the minimum excess
+    fn better(&self, p: usize, q: usize) -> usize {
+        let (ep, eq) = (self.excess(p), self.excess(q));
+        if ep < eq || (ep == eq && p > q) { p } else { q }
+    }
+
+    // rightmost position of the minimum excess in [a, b], with a table lookup per byte
+    fn scan(&self, a: usize, b: usize) -> usize {
+        let mut e = self.excess(a);
+        let mut min = e;
+        let mut pos = a;
+        let mut p = a + 1;
+        while p <= b {
+            let len = (b + 1 - p).min(64);
+            let mut bits = self.bp.get_bits(p, len);
+            let mut i = 0;
+            while i < len {
+                if len - i >= 8 {
+                    let (delta, byte_min, byte_pos) = BYTE_TABLE[(bits & 0xff) as usize];
+                    if e + byte_min as isize <= min {
+                        min = e + byte_min as isize;
+                        pos = p + i + byte_pos as usize;
+                    }
+                    e += delta as isize;
+                    bits >>= 8;
+                    i += 8;
+                } else {
+                    e += if bits & 1 == 1 { 1 } else { -1 };
+                    if e <= min {
+                        min = e;
+                        pos = p + i;
+                    }
+                    bits >>= 1;
+                    i += 1;
+                }
+            }
+            p += len;
+        }
+        pos
+    }
+
+    // rightmost position of the minimum excess in the block `b`
+    fn block_min(&self, b: usize) -> usize {
+        self.scan(b * BLOCK_SIZE, ((b + 1) * BLOCK_SIZE).min(self.bp.len()) - 1)
+    }
+
+    // rightmost position of the minimum excess in the blocks [i, j] of a superblock
+    fn blocks_in_superblock(&self, i: usize, j: usize) -> usize {
+        let width = j - i + 1;
+        if width == 1 {
+            return self.block_min(i);
+        }
+        if width == SUPERBLOCK_BLOCKS {
+            return self.superblock_sparse[0][i / SUPERBLOCK_BLOCKS] as usize;
+        }
+        let k = width.ilog2() as usize;
+        let base = i / SUPERBLOCK_BLOCKS * SUPERBLOCK_BLOCKS;
+        let l = base + self.block_sparse[k - 1][i] as usize;
+        let r = base + self.block_sparse[k - 1][j + 1 - (1 << k)] as usize;
+        self.better(self.block_min(l), self.block_min(r))
+    }
+
+    // rightmost position of the minimum excess in the blocks [i, j]
+    fn blocks_min(&self, i: usize, j: usize) -> usize {
+        let (si, sj) = (i / SUPERBLOCK_BLOCKS, j / SUPERBLOCK_BLOCKS);
+        if si == sj {
+            return self.blocks_in_superblock(i, j);
+        }
+        let mut pos = self.blocks_in_superblock(i, (si + 1) * SUPERBLOCK_BLOCKS - 1);
+        if si + 1 < sj {
+            let (s, e) = (si + 1, sj);
+            let k = (e - s).ilog2() as usize;
+            let l = self.superblock_sparse[k][s] as usize;
+            let r = self.superblock_sparse[k][e - (1 << k)] as usize;
+            pos = self.better(pos, self.better(l, r));
+        }
+        self.better(pos, self.blocks_in_superblock(sj * SUPERBLOCK_BLOCKS, j))
+    }
+
+    fn range_min(&self, a: usize, b: usize) -> usize {
+        let ba = a / BLOCK_SIZE;
+        let bb = b / BLOCK_SIZE;
+        if ba == bb {
+            return self.scan(a, b);
+        }
+        let mut pos = self.scan(a, (ba + 1) * BLOCK_SIZE - 1);
+        if ba + 1 < bb {
+            pos = self.better(pos, self.blocks_min(ba + 1, bb - 1));
+        }
+        self.better(pos, self.scan(bb * BLOCK_SIZE, b))
+    }
+}
+
+impl <T: FID + SpaceUsage> SpaceUsage for SuccinctRMQ<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("bp", &self.bp), ("block_sparse", &self.block_sparse), ("superblock_sparse", &self.superblock_sparse)]
+    }
+}
+
+pub type NaiveSuccinctRMQ = SuccinctRMQ<NaiveFID>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn naive_rmq(vec: &[u32], s: usize, e: usize) -> usize {
+        (s..e).min_by_key(|&i| (vec[i], i)).unwrap()
+    }
+
+    #[test]
+    fn construct() {
+        let rmq = NaiveSuccinctRMQ::new(&[2, 1, 3]);
+        // ( (  ) ( ( ) ) )
+        assert_eq!(
+            NaiveFID::from_bool_vec(&vec![true, true, false, true, true, false, false, false]),
+            rmq.bp
+        );
+    }
+
+    #[test]
+    fn small() {
+        let vec = vec![4, 2, 1, 5, 7, 4, 5, 0, 1, 1];
+        let rmq = NaiveSuccinctRMQ::new(&vec);
+        for s in 0..vec.len() {
+            for e in s + 1..=vec.len() {
+                assert_eq!(naive_rmq(&vec, s, e), rmq.rmq(s, e), "s = {}, e = {}", s, e);
+            }
+        }
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        // the longer arrays span several superblocks
+        for (max, len) in [(4, 3000), (1000, 3000), (4, 50000), (1000, 50000)] {
+            let vec: Vec<u32> = (0..len).map(|_| rng.gen_range(0, max)).collect();
+            let rmq = NaiveSuccinctRMQ::new(&vec);
+            for _ in 0..1000 {
+                let s = rng.gen_range(0, len);
+                let e = rng.gen_range(s + 1, len + 1);
+                assert_eq!(naive_rmq(&vec, s, e), rmq.rmq(s, e), "s = {}, e = {}", s, e);
+            }
+        }
+    }
+
+    #[test]
+    fn sorted() {
+        let inc: Vec<usize> = (0..2000).collect();
+        let dec: Vec<usize> = (0..2000).rev().collect();
+        let rmq_inc = NaiveSuccinctRMQ::new(&inc);
+        let rmq_dec = NaiveSuccinctRMQ::new(&dec);
+        for s in (0..2000).step_by(97) {
+            for e in (s + 1..=2000).step_by(89) {
+                assert_eq!(s, rmq_inc.rmq(s, e));
+                assert_eq!(e - 1, rmq_dec.rmq(s, e));
+            }
+        }
+    }
+}
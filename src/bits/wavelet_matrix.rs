@@ -1,17 +1,43 @@
 use super::fid::FID;
 use super::fid::NaiveFID;
 
-use crate::collections::heap::Heap;
-
-use std::cmp::Ordering;
+use crate::error;
+use crate::error::Error;
+#[cfg(feature = "parallel")]
+use crate::parallel::Chunking;
+use crate::progress;
+use crate::progress::NoProgress;
+use crate::progress::Phase;
+use crate::progress::Progress;
+use crate::query::QueryBuffer;
+#[cfg(feature = "std")]
+use crate::serialize;
+#[cfg(feature = "std")]
+use crate::serialize::Serializable;
+use crate::space::SpaceUsage;
+
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::mem;
+use core::ops::Bound;
+use core::ops::RangeBounds;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::io::Write;
 
 pub struct U8WaveletMatrix<T: FID> {
     n: usize,
+    levels: usize,
     matrix: Vec<T>,
     offset: [usize; 256],
 }
 
-struct TopKItem {
+pub(crate) struct TopKItem {
     s: usize,
     e: usize,
     d: usize,
@@ -22,18 +48,84 @@ impl TopKItem {
     fn new(s: usize, e: usize, d: usize, v: u8) -> Self {
         TopKItem{ s, e, d, v }
     }
+
+    // more freq first, small value first
+    pub(crate) fn compare(lhs: &TopKItem, rhs: &TopKItem) -> Ordering {
+        match ((rhs.e-rhs.s).cmp(&(lhs.e-lhs.s)), lhs.v.cmp(&rhs.v)) {
+            (Ordering::Equal, c2) => c2,
+            (c1, _) => c1,
+        }
+    }
 }
 
 impl <T: FID> U8WaveletMatrix<T> {
     pub fn new(vec: &Vec<u8>) -> Self {
+        Self::with_levels(vec, 8)
+    }
+
+    /// 各値が `2^levels` 未満の列 `vec` の、 `levels` 段のウェーブレット行列を構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is not in `[1, 8]`, or if any value is not less than `2^levels`.
+    pub fn with_levels(vec: &[u8], levels: usize) -> Self {
+        Self::with_levels_and_progress(vec, levels, &mut NoProgress)
+    }
+
+    /// [`Self::new()`] と同じウェーブレット行列を、各段を構築するごとに `progress` に通知しながら構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `progress` cancels the construction.
+    pub fn with_progress<P: Progress + ?Sized>(vec: &[u8], progress: &mut P) -> Self {
+        Self::with_levels_and_progress(vec, 8, progress)
+    }
+
+    /// [`Self::with_progress()`] と同じですが、 `progress` が中断した場合、パニックせずに [`Error::Cancelled`] を返します。
+    pub fn try_with_progress<P: Progress + ?Sized>(vec: &[u8], progress: &mut P) -> Result<Self, Error> {
+        Self::try_with_levels_and_progress(vec, 8, progress)
+    }
+
+    /// [`Self::with_levels()`] と同じウェーブレット行列を、各段を構築するごとに [`Phase::BuildLevels`] を `progress` に通知しながら構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is not in `[1, 8]`, if any value is not less than `2^levels`, or if `progress` cancels the construction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_study::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+    /// use rust_study::progress::Phase;
+    /// let mut levels = vec![];
+    /// let wmat = NaiveU8WaveletMatrix::with_levels_and_progress(&[3, 1, 4, 1, 5], 3, &mut |_: Phase, done: usize, _: usize| {
+    ///     levels.push(done);
+    /// });
+    /// assert_eq!(5, wmat.len());
+    /// assert_eq!(vec![0, 1, 2, 3], levels);
+    /// ```
+    pub fn with_levels_and_progress<P: Progress + ?Sized>(vec: &[u8], levels: usize, progress: &mut P) -> Self {
+        Self::try_with_levels_and_progress(vec, levels, progress).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [`Self::with_levels_and_progress()`] と同じですが、 `progress` が中断した場合、パニックせずに [`Error::Cancelled`] を返します。
+    ///
+    /// 中断は各段の境目で確かめます。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is not in `[1, 8]`, or if any value is not less than `2^levels`.
+    pub fn try_with_levels_and_progress<P: Progress + ?Sized>(vec: &[u8], levels: usize, progress: &mut P) -> Result<Self, Error> {
+        Self::check_levels(vec, levels);
         let n = vec.len();
-        let mut matrix = Vec::with_capacity(8);
-        let mut vec = vec.clone();
-        for i in 0..8 {
+        let mut matrix = Vec::with_capacity(levels);
+        let mut vec = vec.to_vec();
+        progress::checkpoint(progress, Phase::BuildLevels, 0, levels)?;
+        for i in 0..levels {
             let mut zeros: Vec<u8> = Vec::with_capacity(n);
             let mut ones = Vec::with_capacity(n);
 
-            let mask = !((!0_u8) >> 1) >> i;
+            let mask = 1_u8 << (levels - 1 - i);
             let mut bv = Vec::with_capacity(n);
             for v in vec.iter() {
                 if (v & mask) == 0 {
@@ -47,18 +139,134 @@ impl <T: FID> U8WaveletMatrix<T> {
             matrix.push(T::from_bool_vec(&bv));
             vec = zeros;
             vec.append(&mut ones);
+            progress::checkpoint(progress, Phase::BuildLevels, i + 1, levels)?;
+        }
+        Ok(Self::from_levels(matrix, &vec))
+    }
+
+    /// [`Self::new()`] と同じウェーブレット行列を、作業領域を含めておよそ `bytes` バイトのメモリで構築します。
+    ///
+    /// [`Self::with_levels_and_memory_limit()`] を参照してください。
+    pub fn with_memory_limit(vec: &[u8], bytes: usize) -> Result<Self, Error> {
+        Self::with_levels_and_memory_limit(vec, 8, bytes)
+    }
+
+    /// [`Self::with_levels()`] と同じウェーブレット行列を、作業領域を含めておよそ `bytes` バイトのメモリで構築します。
+    ///
+    /// 通常の構築は各段で、値の列を 0 と 1 に振り分けた 2 つの列と、ビットを 1 バイトずつ持つ列を作ります。
+    /// その見積もりが `bytes` を超える場合、ビットを直接ビットベクトルに詰め、値を 1 つの列に振り分けて、作業領域を値の列 2 つ分に抑えます。
+    /// 省メモリの構築でも `bytes` に収まらない場合、構築せずに [`Error::MemoryLimit`] を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is not in `[1, 8]`, or if any value is not less than `2^levels`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_study::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+    /// let values = [3, 1, 4, 1, 5, 9, 2, 6];
+    /// let wmat = NaiveU8WaveletMatrix::with_levels_and_memory_limit(&values, 4, 32).unwrap();
+    /// assert_eq!(2, wmat.rank(1, 8));
+    /// assert_eq!(9, wmat.access(5));
+    /// assert!(NaiveU8WaveletMatrix::with_levels_and_memory_limit(&values, 4, 8).is_err());
+    /// ```
+    pub fn with_levels_and_memory_limit(vec: &[u8], levels: usize, bytes: usize) -> Result<Self, Error> {
+        // the output takes a bit and a popcount offset per 64 bits on each level
+        let output = levels * (vec.len() / 8 + vec.len() / 64 * mem::size_of::<usize>());
+        if output.saturating_add(4 * vec.len()) <= bytes {
+            return Ok(Self::with_levels(vec, levels));
+        }
+        Self::check_levels(vec, levels);
+        let n = vec.len();
+        // the values and their partition
+        let required = output.saturating_add(2 * n);
+        if required > bytes {
+            return Err(Error::MemoryLimit { required, limit: bytes });
+        }
+        let mut matrix: Vec<T> = Vec::with_capacity(levels);
+        let mut vec = vec.to_vec();
+        let mut next = vec![0; n];
+        for i in 0..levels {
+            let mask = 1_u8 << (levels - 1 - i);
+            let bits = T::from_bool_iter(vec.iter().map(|v| (v & mask) != 0));
+            // a stable partition into next, the zeros followed by the ones
+            let (mut zeros, mut ones) = (0, bits.rank0(n));
+            for &v in vec.iter() {
+                if (v & mask) == 0 {
+                    next[zeros] = v;
+                    zeros += 1;
+                } else {
+                    next[ones] = v;
+                    ones += 1;
+                }
+            }
+            matrix.push(bits);
+            mem::swap(&mut vec, &mut next);
+        }
+        Ok(Self::from_levels(matrix, &vec))
+    }
+
+    /// [`Self::new()`] と同じウェーブレット行列を、 `chunking` の大きさのチャンクごとに並列に構築します。
+    #[cfg(feature = "parallel")]
+    pub fn par_new(vec: &[u8], chunking: Chunking) -> Self {
+        Self::par_with_levels(vec, 8, chunking)
+    }
+
+    /// [`Self::with_levels()`] と同じウェーブレット行列を、 `chunking` の大きさのチャンクごとに並列に構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is not in `[1, 8]`, or if any value is not less than `2^levels`.
+    #[cfg(feature = "parallel")]
+    pub fn par_with_levels(vec: &[u8], levels: usize, chunking: Chunking) -> Self {
+        use rayon::prelude::*;
+
+        Self::check_levels(vec, levels);
+        let mut matrix = Vec::with_capacity(levels);
+        let mut vec = vec.to_vec();
+        for i in 0..levels {
+            let mask = 1_u8 << (levels - 1 - i);
+            let bv: Vec<bool> = vec.par_iter().with_min_len(chunking.size()).map(|v| (v & mask) != 0).collect();
+            matrix.push(T::par_from_bool_vec(&bv, chunking));
+            // a stable partition of each chunk, then the zeros of all chunks followed by the ones
+            let parts: Vec<(Vec<u8>, Vec<u8>)> = chunking.chunks(&vec, 1)
+                .map(|chunk| chunk.iter().partition(|&&v| (v & mask) == 0))
+                .collect();
+            vec = parts.iter()
+                .flat_map(|(zeros, _)| zeros)
+                .chain(parts.iter().flat_map(|(_, ones)| ones))
+                .copied()
+                .collect();
+        }
+        Self::from_levels(matrix, &vec)
+    }
+
+    fn check_levels(vec: &[u8], levels: usize) {
+        assert!((1..=8).contains(&levels), "levels should be in [1, 8]");
+        assert!(vec.iter().all(|&v| (v as usize) < 1 << levels), "values should be less than 2^levels");
+    }
+
+    // vec is the values sorted by the last level
+    fn from_levels(matrix: Vec<T>, vec: &[u8]) -> Self {
+        U8WaveletMatrix {
+            n: vec.len(),
+            levels: matrix.len(),
+            matrix,
+            offset: Self::offsets(vec),
         }
+    }
+
+    // the first position of each value in vec, or its length for the absent values
+    fn offsets(vec: &[u8]) -> [usize; 256] {
+        let n = vec.len();
         let mut offset = [n; 256];
         for (i, v) in vec.iter().enumerate() {
             if offset[*v as usize] == n {
                 offset[*v as usize] = i;
             }
         }
-        U8WaveletMatrix {
-            n,
-            matrix,
-            offset,
-        }
+        offset
     }
 
     pub fn len(&self) -> usize {
@@ -84,7 +292,7 @@ impl <T: FID> U8WaveletMatrix<T> {
         if i > self.n {
             i = self.n;
         }
-        let mut mask = !(!0_u8 >> 1);
+        let mut mask = 1_u8 << (self.levels - 1);
         for fid in &self.matrix {
             i = if (v & mask) == 0 {
                 fid.rank0(i)
@@ -132,21 +340,24 @@ impl <T: FID> U8WaveletMatrix<T> {
 
     pub fn topk(&self, s: usize, e: usize, k: usize) -> Vec<(u8, usize)> {
         let mut result = vec![];
-        let mut heap = Heap::with_compare(|lhs: &TopKItem, rhs|
-            // more freq first, small value first
-            match ((rhs.e-rhs.s).cmp(&(lhs.e-lhs.s)), lhs.v.cmp(&rhs.v)) {
-                (Ordering::Equal, c2) => c2,
-                (c1, _) => c1,
-            }
-        );
+        self.topk_into(s, e, k, &mut QueryBuffer::new(), &mut result);
+        result
+    }
 
+    /// [`Self::topk()`] の結果を、 `out` を空にしてから書き込みます。
+    ///
+    /// 作業用のヒープには `buffer` を使うため、同じ `buffer` と `out` で繰り返し検索すると、容量が足りている限り領域を確保しません。
+    pub fn topk_into(&self, s: usize, e: usize, k: usize, buffer: &mut QueryBuffer, out: &mut Vec<(u8, usize)>) {
+        out.clear();
+        let heap = &mut buffer.topk;
+        heap.clear();
         heap.push(TopKItem::new(s, e, 0, 0));
         while let Some(q) = heap.pop() {
-            if result.len() >= k {
+            if out.len() >= k {
                 break;
             }
             if q.d >= self.matrix.len() {
-                result.push((q.v, q.e - q.s));
+                out.push((q.v, q.e - q.s));
                 continue;
             }
             let fid = &self.matrix[q.d];
@@ -164,16 +375,201 @@ impl <T: FID> U8WaveletMatrix<T> {
                 heap.push(TopKItem::new(os, oe, q.d + 1, q.v << 1 | 1));
             }
         }
+    }
+
+    /// 範囲 `[s, e)` に現れる値のうち `values` に含まれるものを、出現回数とともに値の昇順に返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s > e` or `e > len`.
+    pub fn range_list<R: RangeBounds<u8>>(&self, s: usize, e: usize, values: R) -> Vec<(u8, usize)> {
+        let mut result = vec![];
+        self.range_list_into(s, e, values, &mut QueryBuffer::new(), &mut result);
         result
     }
+
+    /// [`Self::range_list()`] の結果を、 `out` を空にしてから書き込みます。
+    ///
+    /// 探索のスタックには `buffer` を使うため、同じ `buffer` と `out` で繰り返し検索すると、容量が足りている限り領域を確保しません。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s > e` or `e > len`.
+    pub fn range_list_into<R: RangeBounds<u8>>(&self, s: usize, e: usize, values: R, buffer: &mut QueryBuffer, out: &mut Vec<(u8, usize)>) {
+        assert!(s <= e && e <= self.n, "range [{}, {}) should be in [0, {}]", s, e, self.n);
+        let lo = match values.start_bound() {
+            Bound::Included(&v) => v as usize,
+            Bound::Excluded(&v) => v as usize + 1,
+            Bound::Unbounded => 0,
+        };
+        let hi = match values.end_bound() {
+            Bound::Included(&v) => v as usize + 1,
+            Bound::Excluded(&v) => v as usize,
+            Bound::Unbounded => 256,
+        };
+        out.clear();
+        let stack = &mut buffer.ranges;
+        stack.clear();
+        stack.push((s, e, 0, 0));
+        while let Some((s, e, d, v)) = stack.pop() {
+            // the values under the node are in [v << rest, (v + 1) << rest)
+            let rest = self.levels - d;
+            if s == e || (v + 1) << rest <= lo || hi <= v << rest {
+                continue;
+            }
+            if d == self.levels {
+                out.push((v as u8, e - s));
+                continue;
+            }
+            let fid = &self.matrix[d];
+            let zeros = fid.rank0(fid.len());
+            // the smaller values are popped first
+            stack.push((zeros + fid.rank1(s), zeros + fid.rank1(e), d + 1, v << 1 | 1));
+            stack.push((fid.rank0(s), fid.rank0(e), d + 1, v << 1));
+        }
+    }
+
+    /// [`Self::access()`] と同じですが、 `i` が範囲外の場合、パニックせずに [`Error::OutOfBounds`] を返します。
+    pub fn try_access(&self, i: usize) -> Result<u8, Error> {
+        error::check_index(i, self.n)?;
+        Ok(self.access(i))
+    }
+
+    /// `i` 番目(0-based)の `v` の位置を返します。
+    ///
+    /// [`Self::select()`] と異なり、 `v` が現れない場合は [`Error::SymbolAbsent`] を、
+    /// `v` の個数が `i` 以下の場合は [`Error::OutOfBounds`] を返します。
+    pub fn try_select(&self, v: u8, i: usize) -> Result<usize, Error> {
+        let count = self.rank(v, self.n);
+        if count == 0 {
+            return Err(Error::SymbolAbsent { symbol: v });
+        }
+        error::check_index(i, count)?;
+        Ok(self.select(v, i))
+    }
+
+    /// [`Self::quantile()`] と同じですが、範囲 `[s, e)` が不正な場合や、 `r` が範囲の長さ以上の場合、
+    /// パニックせずに [`Error::OutOfBounds`] を返します。
+    pub fn try_quantile(&self, s: usize, e: usize, r: usize) -> Result<u8, Error> {
+        self.check_range(s, e)?;
+        error::check_index(r, e - s)?;
+        Ok(self.quantile(s, e, r))
+    }
+
+    /// [`Self::topk()`] と同じですが、範囲 `[s, e)` が不正な場合、パニックせずに [`Error::OutOfBounds`] を返します。
+    pub fn try_topk(&self, s: usize, e: usize, k: usize) -> Result<Vec<(u8, usize)>, Error> {
+        self.check_range(s, e)?;
+        Ok(self.topk(s, e, k))
+    }
+
+    fn check_range(&self, s: usize, e: usize) -> Result<(), Error> {
+        error::check_index(e, self.n + 1)?;
+        error::check_index(s, e + 1)
+    }
+
+    /// 内部の整合性を検証し、最初に見つけた不整合を [`Error::CorruptData`] で返します。
+    ///
+    /// 段数、各段のビットベクトルの長さと [`FID::validate()`] 、各値の開始位置を確かめます。
+    /// 開始位置は列を復元して求め直すため、 `O(n log σ)` 時間と `O(n)` の作業領域を使います。
+    /// 信頼できないファイルや壊れている恐れのあるファイルから読み込んだ後に呼び出します。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_study::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+    /// let wmat = NaiveU8WaveletMatrix::new(&b"abracadabra".to_vec());
+    /// assert_eq!(Ok(()), wmat.validate());
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        if !(1..=8).contains(&self.levels) || self.matrix.len() != self.levels {
+            return Err(Error::CorruptData(format!("{} levels of bit vectors for {} levels", self.matrix.len(), self.levels)));
+        }
+        for (d, fid) in self.matrix.iter().enumerate() {
+            if fid.len() != self.n {
+                return Err(Error::CorruptData(format!("level {} has length {}, expected {}", d, fid.len(), self.n)));
+            }
+            fid.validate().map_err(|e| error::in_part(e, &format!("level {}", d)))?;
+        }
+        // the values sorted by the last level, that is, by their bits from the lowest
+        let mut sorted: Vec<u8> = (0..self.n).map(|i| self.access(i)).collect();
+        sorted.sort_by_key(|v| v.reverse_bits());
+        let expected = Self::offsets(&sorted);
+        match (0..256).find(|&v| self.offset[v] != expected[v]) {
+            Some(v) => Err(Error::CorruptData(format!("offset of value {} is {}, expected {}", v, self.offset[v], expected[v]))),
+            None => Ok(()),
+        }
+    }
 }
+
+#[cfg(feature = "std")]
+impl <T: FID + Serializable> Serializable for U8WaveletMatrix<T> {
+    const MAGIC: [u8; 8] = *b"WAVELETM";
+    const VERSION: u32 = 1;
+
+    fn write_body<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        serialize::write_u64(w, self.n as u64)?;
+        serialize::write_u64(w, self.levels as u64)?;
+        for fid in &self.matrix {
+            fid.serialize(w)?;
+        }
+        self.offset.iter().try_for_each(|&offset| serialize::write_u64(w, offset as u64))
+    }
+
+    fn read_body<R: Read>(r: &mut R) -> io::Result<Self> {
+        let n = serialize::read_len(r)?;
+        let levels = serialize::read_len(r)?;
+        if !(1..=8).contains(&levels) {
+            return Err(serialize::invalid_data("levels out of range"));
+        }
+        let mut matrix = Vec::with_capacity(levels);
+        for _ in 0..levels {
+            let fid = T::deserialize(r)?;
+            if fid.len() != n {
+                return Err(serialize::invalid_data("level length mismatch"));
+            }
+            matrix.push(fid);
+        }
+        let mut offset = [0; 256];
+        for o in offset.iter_mut() {
+            *o = serialize::read_len(r)?;
+            if *o > n {
+                return Err(serialize::invalid_data("offset out of range"));
+            }
+        }
+        Ok(U8WaveletMatrix { n, levels, matrix, offset })
+    }
+}
+
+impl <T: FID + SpaceUsage> SpaceUsage for U8WaveletMatrix<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("matrix", &self.matrix), ("offset", &self.offset)]
+    }
+}
+
 pub type NaiveU8WaveletMatrix = U8WaveletMatrix<NaiveFID>;
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::progress::CancelToken;
+    use crate::progress::Cancellable;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::Index;
+    use std::collections::BTreeMap;
     use std::collections::HashMap;
 
+    // the number of levels and values less than 2^levels
+    fn values() -> impl Strategy<Value = (usize, Vec<u8>)> {
+        (1..=8_usize).prop_flat_map(|levels| (Just(levels), vec(0..=((1_u16 << levels) - 1) as u8, 0..300)))
+    }
+
+    // the range [s, e) of 0..=n
+    fn range(n: usize, s: Index, e: Index) -> (usize, usize) {
+        let (s, e) = (s.index(n + 1), e.index(n + 1));
+        (s.min(e), s.max(e))
+    }
+
     #[test]
     fn construct() {
         let u8s = vec![4, 2, 1, 5, 7, 4, 5, 0];
@@ -343,6 +739,88 @@ mod tests {
         }
     }
 
+    #[test]
+    fn levels() {
+        let u8s = vec![4, 2, 1, 5, 7, 4, 5, 0];
+        let wmat = NaiveU8WaveletMatrix::with_levels(&u8s, 3);
+        let full = NaiveU8WaveletMatrix::new(&u8s);
+        assert_eq!(3, wmat.matrix.len());
+        for (i, &v) in u8s.iter().enumerate() {
+            assert_eq!(v, wmat.access(i));
+            assert_eq!(full.quantile(0, i + 1, i / 2), wmat.quantile(0, i + 1, i / 2));
+        }
+        for v in 0..8 {
+            for i in 0..=u8s.len() {
+                assert_eq!(full.rank(v, i), wmat.rank(v, i));
+                assert_eq!(full.select(v, i), wmat.select(v, i));
+            }
+        }
+        assert_eq!(full.topk(1, 7, 3), wmat.topk(1, 7, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "values should be less than 2^levels")]
+    fn too_few_levels() {
+        NaiveU8WaveletMatrix::with_levels(&[4, 8], 3);
+    }
+
+    #[test]
+    fn with_progress() {
+        let u8s = vec![4, 2, 1, 5, 7, 4, 5, 0];
+        let mut reports = vec![];
+        let wmat = NaiveU8WaveletMatrix::with_progress(&u8s, &mut |phase: Phase, done: usize, total: usize| {
+            reports.push((phase, done, total));
+        });
+        assert_eq!((0..=8).map(|done| (Phase::BuildLevels, done, 8)).collect::<Vec<_>>(), reports);
+        let full = NaiveU8WaveletMatrix::new(&u8s);
+        for i in 0..u8s.len() {
+            assert_eq!(full.access(i), wmat.access(i));
+        }
+    }
+
+    #[test]
+    fn with_memory_limit() {
+        let str = "ATCTATGGGAGGAAGAGAAAGTGGAATCTCTGTATCATCTTTCTTAGTCC";
+        let u8s = str.as_bytes().to_vec();
+        let full = NaiveU8WaveletMatrix::new(&u8s);
+        // the output takes 48 bytes, and the low memory path 100 bytes more
+        for bytes in [148, 200, usize::MAX] {
+            let wmat = NaiveU8WaveletMatrix::with_memory_limit(&u8s, bytes).unwrap();
+            assert_eq!(full.matrix, wmat.matrix);
+            assert_eq!(full.offset, wmat.offset);
+        }
+        assert_eq!(Some(Error::MemoryLimit { required: 148, limit: 147 }), NaiveU8WaveletMatrix::with_memory_limit(&u8s, 147).err());
+        let values = vec![4, 2, 1, 5, 7, 4, 5, 0];
+        assert!(NaiveU8WaveletMatrix::with_levels_and_memory_limit(&values, 3, 0).is_err());
+        let wmat = NaiveU8WaveletMatrix::with_levels_and_memory_limit(&values, 3, 19).unwrap();
+        assert_eq!(NaiveU8WaveletMatrix::with_levels(&values, 3).matrix, wmat.matrix);
+        assert_eq!(Ok(()), wmat.validate());
+    }
+
+    #[test]
+    fn cancel() {
+        let mut levels = vec![];
+        let mut progress = Cancellable::new(|_: Phase, done: usize, _: usize| levels.push(done), || false);
+        assert!(NaiveU8WaveletMatrix::try_with_levels_and_progress(&[1, 2, 3], 2, &mut progress).is_ok());
+        let token = CancelToken::new();
+        let result = NaiveU8WaveletMatrix::try_with_progress(&[1, 2, 3], &mut Cancellable::new(|_: Phase, done: usize, _: usize| {
+            levels.push(done);
+            if done == 3 {
+                token.cancel();
+            }
+        }, || token.is_cancelled()));
+        assert_eq!(Some(Error::Cancelled), result.err());
+        assert_eq!(vec![0, 1, 2, 0, 1, 2, 3], levels);
+    }
+
+    #[test]
+    #[should_panic(expected = "cancelled")]
+    fn cancel_with_progress() {
+        let token = CancelToken::new();
+        token.cancel();
+        NaiveU8WaveletMatrix::with_progress(&[1, 2, 3], &mut token.clone());
+    }
+
     #[test]
     fn example() {
         let str = "ATCTATGGGAGGAAGAGAAAGTGGAATCTCTGTATCATCTTTCTTAGTCC";
@@ -387,4 +865,155 @@ mod tests {
             wmat.topk(20, 30, 4)
         );
     }
+
+    #[test]
+    fn range_list() {
+        let wmat = NaiveU8WaveletMatrix::new(&vec![4, 2, 1, 5, 7, 4, 5, 0]);
+        assert_eq!(vec![(2, 1), (4, 2), (5, 2)], wmat.range_list(0, 8, 2..6));
+        assert_eq!(vec![(4, 1), (5, 2)], wmat.range_list(3, 7, 3..=5));
+        assert_eq!(vec![(7, 1)], wmat.range_list(0, 8, 6..));
+        assert!(wmat.range_list(0, 8, 5..5).is_empty());
+        assert!(wmat.range_list(2, 2, ..).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "should be in [0, 8]")]
+    fn range_list_out_of_bounds() {
+        NaiveU8WaveletMatrix::new(&vec![4, 2, 1, 5, 7, 4, 5, 0]).range_list(0, 9, ..);
+    }
+
+    #[test]
+    fn try_ops() {
+        let wmat = NaiveU8WaveletMatrix::new(&vec![4, 2, 1, 5, 7, 4, 5, 0]);
+        assert_eq!(Ok(5), wmat.try_access(3));
+        assert_eq!(Err(Error::OutOfBounds { index: 8, len: 8 }), wmat.try_access(8));
+        assert_eq!(Ok(5), wmat.try_select(4, 1));
+        assert_eq!(Err(Error::OutOfBounds { index: 2, len: 2 }), wmat.try_select(4, 2));
+        assert_eq!(Err(Error::SymbolAbsent { symbol: 3 }), wmat.try_select(3, 0));
+        assert_eq!(Ok(5), wmat.try_quantile(0, 4, 3));
+        assert!(wmat.try_quantile(0, 4, 4).is_err());
+        assert!(wmat.try_quantile(3, 2, 0).is_err());
+        assert!(wmat.try_quantile(0, 9, 0).is_err());
+        assert_eq!(Ok(vec![(4, 2), (5, 2)]), wmat.try_topk(0, 8, 2));
+        assert!(wmat.try_topk(0, 9, 2).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn same_as_brute_force((levels, values) in values(), ranges in vec((any::<Index>(), any::<Index>(), any::<Index>()), 1..10)) {
+            let wmat = NaiveU8WaveletMatrix::with_levels(&values, levels);
+            let n = values.len();
+            prop_assert_eq!(n, wmat.len());
+            let mut positions: BTreeMap<u8, Vec<usize>> = BTreeMap::new();
+            for (i, &v) in values.iter().enumerate() {
+                prop_assert_eq!(v, wmat.access(i), "access({})", i);
+                positions.entry(v).or_default().push(i);
+            }
+            // the values in the sequence and one absent value
+            if let Some(absent) = (0..=u8::MAX).find(|v| !positions.contains_key(v)) {
+                positions.insert(absent, vec![]);
+            }
+            for (v, positions) in &positions {
+                for i in 0..=n {
+                    prop_assert_eq!(positions.partition_point(|&p| p < i), wmat.rank(*v, i), "rank({}, {})", v, i);
+                }
+                for (k, &p) in positions.iter().enumerate() {
+                    prop_assert_eq!(p, wmat.select(*v, k), "select({}, {})", v, k);
+                }
+                prop_assert_eq!(n, wmat.select(*v, positions.len()), "select({}, {})", v, positions.len());
+            }
+
+            // reused across the queries
+            let mut buffer = QueryBuffer::new();
+            let mut out = vec![];
+            for (s, e, k) in ranges {
+                let (s, e) = range(n, s, e);
+                let mut sorted = values[s..e].to_vec();
+                sorted.sort();
+                for (r, &v) in sorted.iter().enumerate() {
+                    prop_assert_eq!(v, wmat.quantile(s, e, r), "quantile({}, {}, {})", s, e, r);
+                }
+                // more frequent first, smaller value first
+                let mut counts: BTreeMap<u8, usize> = BTreeMap::new();
+                for &v in &values[s..e] {
+                    *counts.entry(v).or_default() += 1;
+                }
+                let mut expected: Vec<(u8, usize)> = counts.into_iter().collect();
+                expected.sort_by(|(v1, c1), (v2, c2)| c2.cmp(c1).then(v1.cmp(v2)));
+                let k = k.index(expected.len() + 1);
+                expected.truncate(k);
+                prop_assert_eq!(&expected, &wmat.topk(s, e, k), "topk({}, {}, {})", s, e, k);
+                wmat.topk_into(s, e, k, &mut buffer, &mut out);
+                prop_assert_eq!(&expected, &out, "topk_into({}, {}, {})", s, e, k);
+
+                // the values between the quartiles, or none if the range is empty
+                let (lo, hi) = sorted.get(sorted.len() / 4).zip(sorted.get(sorted.len() * 3 / 4)).map_or((1, 0), |(&lo, &hi)| (lo, hi));
+                let mut expected: Vec<(u8, usize)> = vec![];
+                for &v in sorted.iter().filter(|v| (lo..=hi).contains(v)) {
+                    match expected.last_mut() {
+                        Some((last, c)) if *last == v => *c += 1,
+                        _ => expected.push((v, 1)),
+                    }
+                }
+                prop_assert_eq!(&expected, &wmat.range_list(s, e, lo..=hi), "range_list({}, {}, {}..={})", s, e, lo, hi);
+                wmat.range_list_into(s, e, lo..=hi, &mut buffer, &mut out);
+                prop_assert_eq!(&expected, &out, "range_list_into({}, {}, {}..={})", s, e, lo, hi);
+                let all: Vec<(u8, usize)> = (0..=u8::MAX).map(|v| (v, wmat.rank(v, e) - wmat.rank(v, s))).filter(|&(_, c)| c > 0).collect();
+                prop_assert_eq!(all, wmat.range_list(s, e, ..), "range_list({}, {}, ..)", s, e);
+            }
+        }
+
+        #[test]
+        #[cfg(feature = "parallel")]
+        fn par_with_levels((levels, values) in values(), size in 1..100_usize) {
+            let expected = NaiveU8WaveletMatrix::with_levels(&values, levels);
+            let wmat = NaiveU8WaveletMatrix::par_with_levels(&values, levels, Chunking::new(size));
+            prop_assert_eq!(expected.matrix, wmat.matrix);
+            prop_assert_eq!(expected.offset, wmat.offset);
+        }
+
+        #[test]
+        #[cfg(feature = "std")]
+        fn serialize((levels, values) in values()) {
+            let wmat = NaiveU8WaveletMatrix::with_levels(&values, levels);
+            let mut bytes = vec![];
+            wmat.serialize(&mut bytes).unwrap();
+            let read = NaiveU8WaveletMatrix::deserialize(&mut bytes.as_slice()).unwrap();
+            prop_assert_eq!(wmat.len(), read.len());
+            prop_assert_eq!(&wmat.matrix, &read.matrix);
+            prop_assert_eq!(wmat.offset, read.offset);
+            prop_assert_eq!(&values, &(0..values.len()).map(|i| read.access(i)).collect::<Vec<_>>());
+            prop_assert_eq!(Ok(()), read.validate());
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let mut wmat = NaiveU8WaveletMatrix::with_levels(&[3, 1, 4, 1, 5, 9, 2, 6], 4);
+        assert_eq!(Ok(()), wmat.validate());
+        assert_eq!(Ok(()), NaiveU8WaveletMatrix::new(&vec![]).validate());
+
+        // an offset which does not match the levels, as a corrupt file may have
+        wmat.offset[1] += 1;
+        assert_eq!(Err(Error::CorruptData("offset of value 1 is 4, expected 3".to_string())), wmat.validate());
+        wmat.offset[1] -= 1;
+        wmat.offset[7] = 0;
+        assert!(wmat.validate().is_err());
+        wmat.offset[7] = 8;
+        wmat.matrix.pop();
+        assert_eq!(Err(Error::CorruptData("3 levels of bit vectors for 4 levels".to_string())), wmat.validate());
+        wmat.matrix.push(NaiveFID::new(7));
+        assert_eq!(Err(Error::CorruptData("level 3 has length 7, expected 8".to_string())), wmat.validate());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn serialize_broken() {
+        // a level whose length differs from the others
+        let mut bytes = vec![];
+        NaiveU8WaveletMatrix::with_levels(&[0, 1, 1], 1).serialize(&mut bytes).unwrap();
+        // the length of the first level follows the outer header, n, levels, and the inner header
+        bytes[20 + 16 + 20] = 2;
+        assert!(NaiveU8WaveletMatrix::deserialize(&mut bytes.as_slice()).is_err());
+    }
 }
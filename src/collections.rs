@@ -1 +1,26 @@
+pub mod adaptive_map;
+pub mod arena;
+pub mod cuckoo_filter;
+pub mod graph;
+pub mod hash;
 pub mod heap;
+pub mod interval_tree;
+pub mod kd_tree;
+pub mod lazy_segment_tree;
+pub mod lru;
+pub mod monotonic_deque;
+pub mod mphf;
+pub mod ordered_multiset;
+pub mod persistent;
+pub mod ring_buffer;
+pub mod rollback_union_find;
+#[cfg(feature = "serde")]
+mod serde_impl;
+pub mod sketch;
+pub mod splay_tree;
+pub mod topk;
+pub mod treap;
+pub mod union_find;
+pub mod veb_tree;
+pub mod weighted_union_find;
+pub mod y_fast_trie;
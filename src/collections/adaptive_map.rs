@@ -0,0 +1,286 @@
+use crate::space::SpaceUsage;
+
+use alloc::borrow::Borrow;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::mem;
+
+/// 配列表現からハッシュテーブルに切り替える要素数
+pub const SMALL_CAPACITY: usize = 8;
+
+// the large representation, a B-tree without std
+#[cfg(feature = "std")]
+type LargeMap<K, V> = std::collections::HashMap<K, V>;
+#[cfg(not(feature = "std"))]
+type LargeMap<K, V> = alloc::collections::BTreeMap<K, V>;
+
+enum Repr<K, V> {
+    Small(Vec<(K, V)>),
+    Large(LargeMap<K, V>),
+}
+
+/// 要素数に応じて表現を切り替える連想配列
+///
+/// 要素数が [`SMALL_CAPACITY`] 以下の間はキーでソートした配列で保持し、二分探索で検索します。
+/// 要素数がこれを超えるとハッシュテーブルに切り替え、 `SMALL_CAPACITY / 2` 未満に減ると配列に戻します。
+///
+/// 要素数が少ない連想配列を大量に持つ場合 (トライ木の子ノードなど) に、
+/// ノードごとにハッシュテーブルを持つよりも使用メモリを大きく減らせます。
+/// 空の間は領域を確保しません。
+/// `std` フィーチャーが無効な場合、ハッシュテーブルの代わりに B 木を使います。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::adaptive_map::AdaptiveMap;
+/// let mut map = AdaptiveMap::new();
+/// assert_eq!(None, map.insert('b', 2));
+/// assert_eq!(None, map.insert('a', 1));
+/// assert_eq!(Some(2), map.insert('b', 20));
+/// assert_eq!(Some(&1), map.get(&'a'));
+/// *map.get_or_insert_with('c', || 0) += 3;
+/// assert_eq!(3, map.len());
+///
+/// // 要素数が少ない間、 iter はキーの小さい順に要素を返します。
+/// assert_eq!(vec![(&'a', &1), (&'b', &20), (&'c', &3)], map.iter().collect::<Vec<_>>());
+///
+/// assert_eq!(Some(1), map.remove(&'a'));
+/// assert!(!map.contains_key(&'a'));
+/// ```
+pub struct AdaptiveMap<K, V> {
+    repr: Repr<K, V>,
+}
+
+impl <K: Ord + Hash, V> AdaptiveMap<K, V> {
+    /// 空の連想配列を構築します。
+    pub fn new() -> Self {
+        AdaptiveMap { repr: Repr::Small(Vec::new()) }
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        match &self.repr {
+            Repr::Small(vec) => vec.len(),
+            Repr::Large(map) => map.len(),
+        }
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// キー `key` に対応付けられた値を参照します。
+    pub fn get<Q: Ord + Hash + ?Sized>(&self, key: &Q) -> Option<&V> where K: Borrow<Q> {
+        match &self.repr {
+            Repr::Small(vec) => vec.binary_search_by(|(k, _)| k.borrow().cmp(key)).ok().map(|i| &vec[i].1),
+            Repr::Large(map) => map.get(key),
+        }
+    }
+
+    /// キー `key` に対応付けられた値を可変参照します。
+    pub fn get_mut<Q: Ord + Hash + ?Sized>(&mut self, key: &Q) -> Option<&mut V> where K: Borrow<Q> {
+        match &mut self.repr {
+            Repr::Small(vec) => vec.binary_search_by(|(k, _)| k.borrow().cmp(key)).ok().map(move |i| &mut vec[i].1),
+            Repr::Large(map) => map.get_mut(key),
+        }
+    }
+
+    /// キー `key` が存在する場合に、 `true` を返します。
+    pub fn contains_key<Q: Ord + Hash + ?Sized>(&self, key: &Q) -> bool where K: Borrow<Q> {
+        self.get(key).is_some()
+    }
+
+    /// キー `key` に値 `value` を対応付けます。
+    ///
+    /// すでにキーが存在した場合、値を置き換えて古い値を返します。
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match &mut self.repr {
+            Repr::Small(vec) => match vec.binary_search_by(|(k, _)| k.cmp(&key)) {
+                Ok(i) => Some(core::mem::replace(&mut vec[i].1, value)),
+                Err(i) => {
+                    vec.insert(i, (key, value));
+                    self.grow();
+                    None
+                }
+            },
+            Repr::Large(map) => map.insert(key, value),
+        }
+    }
+
+    /// キー `key` に対応付けられた値を可変参照します。キーが存在しない場合、 `f()` の値を対応付けます。
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, f: F) -> &mut V {
+        let is_full = match &self.repr {
+            Repr::Small(vec) => vec.len() == SMALL_CAPACITY && vec.binary_search_by(|(k, _)| k.cmp(&key)).is_err(),
+            Repr::Large(_) => false,
+        };
+        if is_full {
+            if let Repr::Small(vec) = &mut self.repr {
+                self.repr = Repr::Large(core::mem::take(vec).into_iter().collect());
+            }
+        }
+        match &mut self.repr {
+            Repr::Small(vec) => {
+                let i = match vec.binary_search_by(|(k, _)| k.cmp(&key)) {
+                    Ok(i) => i,
+                    Err(i) => {
+                        vec.insert(i, (key, f()));
+                        i
+                    }
+                };
+                &mut vec[i].1
+            }
+            Repr::Large(map) => map.entry(key).or_insert_with(f),
+        }
+    }
+
+    /// キー `key` を取り除き、対応付けられていた値を返します。
+    pub fn remove<Q: Ord + Hash + ?Sized>(&mut self, key: &Q) -> Option<V> where K: Borrow<Q> {
+        match &mut self.repr {
+            Repr::Small(vec) => {
+                let i = vec.binary_search_by(|(k, _)| k.borrow().cmp(key)).ok()?;
+                Some(vec.remove(i).1)
+            }
+            Repr::Large(map) => {
+                let value = map.remove(key)?;
+                if map.len() < SMALL_CAPACITY / 2 {
+                    let mut vec: Vec<(K, V)> = core::mem::take(map).into_iter().collect();
+                    vec.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    self.repr = Repr::Small(vec);
+                }
+                Some(value)
+            }
+        }
+    }
+
+    /// すべての要素を取り除きます。
+    pub fn clear(&mut self) {
+        self.repr = Repr::Small(Vec::new());
+    }
+
+    /// キーと値の組を返すイテレータを返します。
+    ///
+    /// 配列で保持している間はキーの小さい順に、ハッシュテーブルで保持している間は任意の順に返します。
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (&K, &V)> + '_> {
+        match &self.repr {
+            Repr::Small(vec) => Box::new(vec.iter().map(|(k, v)| (k, v))),
+            Repr::Large(map) => Box::new(map.iter()),
+        }
+    }
+
+    /// キーと値の組を、キーの小さい順に返します。
+    ///
+    /// ハッシュテーブルで保持している間は、呼び出しのたびに `O(n log n)` で並べ替えます。
+    pub fn sorted_iter(&self) -> impl DoubleEndedIterator<Item = (&K, &V)> + '_ {
+        let mut vec: Vec<(&K, &V)> = self.iter().collect();
+        if let Repr::Large(_) = self.repr {
+            vec.sort_by_key(|(k, _)| *k);
+        }
+        vec.into_iter()
+    }
+
+    /// キーを返すイテレータを返します。順序は [`Self::iter()`] と同じです。
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// 値を返すイテレータを返します。順序は [`Self::iter()`] と同じです。
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// 値を可変参照で返すイテレータを返します。順序は [`Self::iter()`] と同じです。
+    pub fn values_mut(&mut self) -> Box<dyn Iterator<Item = &mut V> + '_> {
+        match &mut self.repr {
+            Repr::Small(vec) => Box::new(vec.iter_mut().map(|(_, v)| v)),
+            Repr::Large(map) => Box::new(map.values_mut()),
+        }
+    }
+
+    /// 要素が所有するヒープ領域を除いた、表そのものの領域の大きさを、バイト単位で返します。
+    pub(crate) fn table_size_in_bytes(&self) -> usize {
+        match &self.repr {
+            Repr::Small(vec) => vec.capacity() * mem::size_of::<(K, V)>(),
+            // the same estimate as the SpaceUsage of the map
+            #[cfg(feature = "std")]
+            Repr::Large(map) => map.capacity() * (mem::size_of::<(K, V)>() + 1),
+            #[cfg(not(feature = "std"))]
+            Repr::Large(map) => map.len() * mem::size_of::<(K, V)>(),
+        }
+    }
+
+    fn grow(&mut self) {
+        if let Repr::Small(vec) = &mut self.repr {
+            if vec.len() > SMALL_CAPACITY {
+                self.repr = Repr::Large(core::mem::take(vec).into_iter().collect());
+            }
+        }
+    }
+}
+
+impl <K: Ord + Hash, V> Default for AdaptiveMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl <K: SpaceUsage, V: SpaceUsage> SpaceUsage for AdaptiveMap<K, V> {
+    fn heap_size_in_bytes(&self) -> usize {
+        match &self.repr {
+            Repr::Small(vec) => vec.heap_size_in_bytes(),
+            Repr::Large(map) => map.heap_size_in_bytes(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for range in [4, 12, 100] {
+            let mut map = AdaptiveMap::new();
+            let mut expected = BTreeMap::new();
+            for _ in 0..3000 {
+                let k: u32 = rng.gen_range(0, range);
+                match rng.gen_range(0, 4) {
+                    0 => assert_eq!(expected.remove(&k), map.remove(&k)),
+                    1 => {
+                        let v = rng.gen_range(0, 1000);
+                        let e = expected.entry(k).or_insert(v);
+                        *e += 1;
+                        let m = map.get_or_insert_with(k, || v);
+                        *m += 1;
+                    }
+                    _ => {
+                        let v: u32 = rng.gen();
+                        assert_eq!(expected.insert(k, v), map.insert(k, v));
+                    }
+                }
+                assert_eq!(expected.len(), map.len());
+                assert_eq!(expected.get(&k), map.get(&k));
+                assert_eq!(expected.contains_key(&k), map.contains_key(&k));
+                let mut actual: Vec<(&u32, &u32)> = map.iter().collect();
+                if actual.len() < SMALL_CAPACITY / 2 {
+                    assert!(actual.windows(2).all(|w| w[0].0 < w[1].0));
+                }
+                actual.sort();
+                assert_eq!(expected.iter().collect::<Vec<_>>(), actual);
+            }
+            let sorted: Vec<(&u32, &u32)> = map.sorted_iter().collect();
+            assert_eq!(expected.iter().collect::<Vec<_>>(), sorted);
+            map.values_mut().for_each(|v| *v = 0);
+            assert!(map.values().all(|v| *v == 0));
+            assert_eq!(map.len(), map.keys().count());
+            map.clear();
+            assert!(map.is_empty());
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use crate::space::SpaceUsage;
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+const NIL: usize = usize::MAX;
+
+/// [`Arena`] の要素を指すハンドル
+///
+/// 要素を取り除いた後に同じ位置が再利用されても、世代番号が異なるため古いハンドルからは参照できません。
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Index {
+    slot: usize,
+    generation: u64,
+}
+
+enum Entry<T> {
+    Occupied { generation: u64, value: T },
+    Free { generation: u64, next_free: usize },
+}
+
+/// 世代付きインデックスによるアリーナ
+///
+/// 要素を 1 つの配列にまとめて確保し、要素を指す安定したハンドル [`Index`] を返します。
+/// 取り除いた位置は空きリストで管理して再利用するため、確保・解放はいずれも `O(1)` です。
+/// ノードごとに `Box` で確保する場合と比べて、確保の回数が減り、ノードがメモリ上で近くに並びます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::arena::Arena;
+/// let mut arena = Arena::new();
+/// let a = arena.insert("a");
+/// let b = arena.insert("b");
+/// assert_eq!(Some(&"a"), arena.get(a));
+/// assert_eq!(2, arena.len());
+///
+/// assert_eq!(Some("a"), arena.remove(a));
+/// // 取り除いた要素のハンドルは、位置が再利用された後も無効のままです。
+/// let c = arena.insert("c");
+/// assert_eq!(None, arena.get(a));
+/// assert_eq!(Some(&"c"), arena.get(c));
+/// assert_eq!(Some(&"b"), arena.get(b));
+/// ```
+pub struct Arena<T> {
+    entries: Vec<Entry<T>>,
+    free_head: usize,
+    len: usize,
+}
+
+impl <T> Arena<T> {
+    /// 空のアリーナを構築します。
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// 再確保せずに `capacity` 個の要素を保持できる空のアリーナを構築します。
+    pub fn with_capacity(capacity: usize) -> Self {
+        Arena { entries: Vec::with_capacity(capacity), free_head: NIL, len: 0 }
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 要素 `value` を追加し、そのハンドルを返します。
+    pub fn insert(&mut self, value: T) -> Index {
+        self.len += 1;
+        if self.free_head == NIL {
+            self.entries.push(Entry::Occupied { generation: 0, value });
+            return Index { slot: self.entries.len() - 1, generation: 0 };
+        }
+        let slot = self.free_head;
+        let generation = match self.entries[slot] {
+            Entry::Free { generation, next_free } => {
+                self.free_head = next_free;
+                generation
+            }
+            Entry::Occupied { .. } => unreachable!(),
+        };
+        self.entries[slot] = Entry::Occupied { generation, value };
+        Index { slot, generation }
+    }
+
+    /// ハンドル `index` の要素を取り除いて返します。すでに取り除かれていた場合、 `None` を返します。
+    pub fn remove(&mut self, index: Index) -> Option<T> {
+        if !self.contains(index) {
+            return None;
+        }
+        let free = Entry::Free { generation: index.generation + 1, next_free: self.free_head };
+        self.free_head = index.slot;
+        self.len -= 1;
+        match core::mem::replace(&mut self.entries[index.slot], free) {
+            Entry::Occupied { value, .. } => Some(value),
+            Entry::Free { .. } => unreachable!(),
+        }
+    }
+
+    /// ハンドル `index` の要素が存在する場合に、 `true` を返します。
+    pub fn contains(&self, index: Index) -> bool {
+        self.get(index).is_some()
+    }
+
+    /// ハンドル `index` の要素を参照します。
+    pub fn get(&self, index: Index) -> Option<&T> {
+        match self.entries.get(index.slot)? {
+            Entry::Occupied { generation, value } if *generation == index.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    /// ハンドル `index` の要素を可変参照します。
+    pub fn get_mut(&mut self, index: Index) -> Option<&mut T> {
+        match self.entries.get_mut(index.slot)? {
+            Entry::Occupied { generation, value } if *generation == index.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    /// すべての要素を取り除きます。それまでのハンドルはすべて無効になります。
+    pub fn clear(&mut self) {
+        for slot in 0..self.entries.len() {
+            if let Entry::Occupied { generation, .. } = self.entries[slot] {
+                self.entries[slot] = Entry::Free { generation: generation + 1, next_free: self.free_head };
+                self.free_head = slot;
+            }
+        }
+        self.len = 0;
+    }
+
+    /// ハンドルと要素の組を、位置の順に返すイテレータを返します。
+    pub fn iter(&self) -> impl Iterator<Item = (Index, &T)> + '_ {
+        self.entries.iter().enumerate().filter_map(|(slot, entry)| match entry {
+            Entry::Occupied { generation, value } => Some((Index { slot, generation: *generation }, value)),
+            Entry::Free { .. } => None,
+        })
+    }
+}
+
+impl <T> Default for Arena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl <T> core::ops::Index<Index> for Arena<T> {
+    type Output = T;
+
+    fn index(&self, index: Index) -> &T {
+        self.get(index).expect("invalid arena index")
+    }
+}
+
+impl <T> core::ops::IndexMut<Index> for Arena<T> {
+    fn index_mut(&mut self, index: Index) -> &mut T {
+        self.get_mut(index).expect("invalid arena index")
+    }
+}
+
+/// 木構造のノードの確保の仕方
+///
+/// [`Treap`](super::treap::Treap) や [`NaiveTrieMap`](crate::string::trie::NaiveTrieMap) の型引数に指定し、
+/// ノードを 1 つずつ `Box` で確保するか ([`BoxNodes`]) 、 1 つの [`Arena`] にまとめて確保するか ([`ArenaNodes`]) を選べます。
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use rust_study::collections::arena::ArenaNodes;
+/// use rust_study::collections::treap::Treap;
+/// use rust_study::random::DefaultRng;
+/// let mut treap: Treap<u32, &str, DefaultRng, ArenaNodes> = Treap::default();
+/// treap.insert(2, "b");
+/// treap.insert(1, "a");
+/// assert_eq!(Some(&"b"), treap.get(&2));
+/// assert_eq!(Some("a"), treap.remove(&1));
+/// # }
+/// ```
+pub trait NodeStorage {
+    /// ノードを確保する領域の型です。
+    type Pool<T>: Default;
+    /// 確保したノードを指すリンクの型です。
+    type Link<T>;
+
+    /// ノード `node` を確保し、そのリンクを返します。
+    fn alloc<T>(pool: &mut Self::Pool<T>, node: T) -> Self::Link<T>;
+
+    /// リンク `link` のノードを解放して返します。
+    fn free<T>(pool: &mut Self::Pool<T>, link: Self::Link<T>) -> T;
+
+    /// リンク `link` のノードを参照します。
+    fn get<'a, T>(pool: &'a Self::Pool<T>, link: &'a Self::Link<T>) -> &'a T;
+
+    /// リンク `link` のノードを可変参照します。
+    fn get_mut<'a, T>(pool: &'a mut Self::Pool<T>, link: &'a mut Self::Link<T>) -> &'a mut T;
+
+    /// リンク `link` のノードから、 `next` が子を選ぶ間、 `child` の返すリンクをたどり、最後のノードを可変参照します。
+    ///
+    /// `next` は、存在する子だけを選ばなければなりません。
+    fn descend<'a, T, D>(
+        pool: &'a mut Self::Pool<T>,
+        link: &'a mut Self::Link<T>,
+        next: impl FnMut(&T) -> Option<D>,
+        child: impl FnMut(&mut T, D) -> &mut Self::Link<T>,
+    ) -> &'a mut T;
+
+    /// リンク `link` が所有するヒープ領域の大きさを、バイト単位で返します。
+    fn link_heap_size<T: SpaceUsage>(link: &Self::Link<T>) -> usize;
+
+    /// 領域 `pool` が所有するヒープ領域の大きさを、バイト単位で返します。
+    fn pool_heap_size<T: SpaceUsage>(pool: &Self::Pool<T>) -> usize;
+}
+
+/// ノードを 1 つずつ `Box` で確保します。既定の確保の仕方です。
+pub struct BoxNodes;
+
+/// ノードを 1 つの [`Arena`] にまとめて確保し、 [`Index`] でたどります。
+///
+/// 確保の回数が減り、ノードがメモリ上で近くに並びます。
+/// 取り除いたノードの位置は、次に確保するノードで再利用します。
+pub struct ArenaNodes;
+
+impl NodeStorage for BoxNodes {
+    type Pool<T> = ();
+    type Link<T> = Box<T>;
+
+    fn alloc<T>(_: &mut (), node: T) -> Box<T> {
+        Box::new(node)
+    }
+
+    fn free<T>(_: &mut (), link: Box<T>) -> T {
+        *link
+    }
+
+    fn get<'a, T>(_: &'a (), link: &'a Box<T>) -> &'a T {
+        link
+    }
+
+    fn get_mut<'a, T>(_: &'a mut (), link: &'a mut Box<T>) -> &'a mut T {
+        link
+    }
+
+    fn descend<'a, T, D>(
+        _: &'a mut (),
+        link: &'a mut Box<T>,
+        mut next: impl FnMut(&T) -> Option<D>,
+        mut child: impl FnMut(&mut T, D) -> &mut Box<T>,
+    ) -> &'a mut T {
+        let mut node: &'a mut T = link;
+        while let Some(d) = next(node) {
+            node = child(node, d);
+        }
+        node
+    }
+
+    fn link_heap_size<T: SpaceUsage>(link: &Box<T>) -> usize {
+        link.heap_size_in_bytes()
+    }
+
+    fn pool_heap_size<T: SpaceUsage>(_: &()) -> usize {
+        0
+    }
+}
+
+impl NodeStorage for ArenaNodes {
+    type Pool<T> = Arena<T>;
+    type Link<T> = Index;
+
+    fn alloc<T>(pool: &mut Arena<T>, node: T) -> Index {
+        pool.insert(node)
+    }
+
+    fn free<T>(pool: &mut Arena<T>, link: Index) -> T {
+        pool.remove(link).expect("invalid arena index")
+    }
+
+    fn get<'a, T>(pool: &'a Arena<T>, link: &'a Index) -> &'a T {
+        &pool[*link]
+    }
+
+    fn get_mut<'a, T>(pool: &'a mut Arena<T>, link: &'a mut Index) -> &'a mut T {
+        &mut pool[*link]
+    }
+
+    fn descend<'a, T, D>(
+        pool: &'a mut Arena<T>,
+        link: &'a mut Index,
+        mut next: impl FnMut(&T) -> Option<D>,
+        mut child: impl FnMut(&mut T, D) -> &mut Index,
+    ) -> &'a mut T {
+        let mut index = *link;
+        while let Some(d) = next(&pool[index]) {
+            index = *child(&mut pool[index], d);
+        }
+        &mut pool[index]
+    }
+
+    // the node is counted in the arena
+    fn link_heap_size<T: SpaceUsage>(_: &Index) -> usize {
+        0
+    }
+
+    fn pool_heap_size<T: SpaceUsage>(pool: &Arena<T>) -> usize {
+        pool.heap_size_in_bytes()
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for Entry<T> {
+    fn heap_size_in_bytes(&self) -> usize {
+        match self {
+            Entry::Occupied { value, .. } => value.heap_size_in_bytes(),
+            Entry::Free { .. } => 0,
+        }
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for Arena<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("entries", &self.entries)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut arena = Arena::new();
+        let mut alive: Vec<(Index, u32)> = vec![];
+        let mut dead: Vec<Index> = vec![];
+        for _ in 0..5000 {
+            if rng.gen_range(0, 3) == 0 && !alive.is_empty() {
+                let (index, v) = alive.swap_remove(rng.gen_range(0, alive.len()));
+                assert_eq!(Some(v), arena.remove(index));
+                assert_eq!(None, arena.remove(index));
+                dead.push(index);
+            } else {
+                let v = rng.gen();
+                alive.push((arena.insert(v), v));
+            }
+            assert_eq!(alive.len(), arena.len());
+        }
+        for (index, v) in &mut alive {
+            arena[*index] += 1;
+            *v += 1;
+        }
+        for (index, v) in &alive {
+            assert_eq!(*v, arena[*index]);
+        }
+        for index in &dead {
+            assert!(!arena.contains(*index));
+            assert_eq!(None, arena.get_mut(*index));
+        }
+        let mut expected = alive.clone();
+        expected.sort_by_key(|(index, _)| index.slot);
+        assert_eq!(expected, arena.iter().map(|(i, v)| (i, *v)).collect::<Vec<_>>());
+
+        arena.clear();
+        assert!(arena.is_empty());
+        assert!(alive.iter().all(|(index, _)| !arena.contains(*index)));
+        arena.insert(0);
+        assert_eq!(1, arena.len());
+    }
+}
@@ -0,0 +1,317 @@
+use super::hash::DefaultHashBuilder;
+use super::hash::hash_with;
+use super::hash::mix64;
+use crate::random::DefaultRng;
+use crate::space::SpaceUsage;
+
+use alloc::vec::Vec;
+use alloc::vec;
+use core::hash::BuildHasher;
+use core::hash::Hash;
+
+use rand::Rng;
+use rand::RngCore;
+
+const BUCKET_SIZE: usize = 4;
+const MAX_KICKS: usize = 500;
+const EMPTY: u16 = 0;
+
+/// カッコーフィルタ
+///
+/// 要素のフィンガープリント (16 ビット) だけを、2 つの候補のバケットのいずれかに保持する確率的な集合。
+/// 追加した要素に対しては必ず `true` を返し、追加していない要素に対しては小さな確率で誤って `true` を返します。
+/// Bloom フィルタと異なり、要素の削除が可能です。
+///
+/// 同じ要素を複数回追加した場合、同じ回数だけ削除できます。
+/// 追加していない要素を削除すると、偽陰性の原因となります。
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use rust_study::collections::cuckoo_filter::CuckooFilter;
+/// let mut filter = CuckooFilter::new(1000);
+/// assert!(filter.insert("apple"));
+/// assert!(filter.insert("banana"));
+/// assert!(filter.contains("apple"));
+/// assert_eq!(2, filter.len());
+///
+/// assert!(filter.remove("apple"));
+/// assert!(!filter.contains("apple"));
+/// assert!(filter.contains("banana"));
+/// # }
+/// ```
+///
+/// 追い出すフィンガープリントは、型引数 `R` の乱数生成器で選びます。
+/// [`Self::with_rng()`] にシードを固定した乱数生成器を渡すと、同じ操作の列から同じ内容のフィルタができます。
+///
+/// 要素のハッシュ関数は型引数 `S` の `BuildHasher` で作り、既定では鍵を固定した SipHash を使います。
+/// [`Self::with_hasher()`] で FxHash などの速いハッシュ関数に替えられます。
+/// ハッシュ値は [`mix64()`](super::hash::mix64) で撹拌してからフィンガープリントとバケットの位置に分けるため、 FxHash のような撹拌の弱いハッシュ関数でも偏りません。
+pub struct CuckooFilter<R = DefaultRng, S = DefaultHashBuilder> {
+    buckets: Vec<[u16; BUCKET_SIZE]>,
+    mask: usize,
+    len: usize,
+    victim: Option<(usize, u16)>,
+    rng: R,
+    hasher: S,
+}
+
+#[cfg(feature = "std")]
+impl CuckooFilter {
+    /// およそ `capacity` 個の要素を保持できる空のフィルタを構築します。
+    pub fn new(capacity: usize) -> Self {
+        Self::with_rng(capacity, DefaultRng)
+    }
+}
+
+impl <R: RngCore> CuckooFilter<R> {
+    /// 追い出すフィンガープリントを乱数生成器 `rng` で選ぶ、およそ `capacity` 個の要素を保持できる空のフィルタを構築します。
+    pub fn with_rng(capacity: usize, rng: R) -> Self {
+        Self::with_rng_and_hasher(capacity, rng, DefaultHashBuilder::default())
+    }
+}
+
+#[cfg(feature = "std")]
+impl <S: BuildHasher> CuckooFilter<DefaultRng, S> {
+    /// `hasher` の作るハッシュ関数を使う、およそ `capacity` 個の要素を保持できる空のフィルタを構築します。
+    pub fn with_hasher(capacity: usize, hasher: S) -> Self {
+        Self::with_rng_and_hasher(capacity, DefaultRng, hasher)
+    }
+}
+
+impl <R: RngCore, S: BuildHasher> CuckooFilter<R, S> {
+    /// 追い出すフィンガープリントを乱数生成器 `rng` で選び、 `hasher` の作るハッシュ関数を使う、
+    /// およそ `capacity` 個の要素を保持できる空のフィルタを構築します。
+    pub fn with_rng_and_hasher(capacity: usize, rng: R, hasher: S) -> Self {
+        let num_buckets = (capacity.div_ceil(BUCKET_SIZE) * 100 / 95).max(1).next_power_of_two();
+        CuckooFilter {
+            buckets: vec![[EMPTY; BUCKET_SIZE]; num_buckets],
+            mask: num_buckets - 1,
+            len: 0,
+            victim: None,
+            rng,
+            hasher,
+        }
+    }
+
+    /// 保持している要素数を返します。
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 保持できるフィンガープリントの最大数を返します。
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE
+    }
+
+    /// 要素を追加します。
+    ///
+    /// フィルタが満杯で追加できなかった場合に、 `false` を返します。
+    /// その場合もフィルタの内容は失われません。
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        if self.victim.is_some() {
+            return false;
+        }
+        let (fp, i1, i2) = self.locate(item);
+        if self.put(i1, fp) || self.put(i2, fp) {
+            self.len += 1;
+            return true;
+        }
+
+        let mut i = if self.rng.gen() { i1 } else { i2 };
+        let mut fp = fp;
+        for _ in 0..MAX_KICKS {
+            let slot = self.rng.gen_range(0, BUCKET_SIZE);
+            core::mem::swap(&mut fp, &mut self.buckets[i][slot]);
+            i = self.alt_index(i, fp);
+            if self.put(i, fp) {
+                self.len += 1;
+                return true;
+            }
+        }
+        // keep the evicted fingerprint to avoid false negatives
+        self.victim = Some((i, fp));
+        self.len += 1;
+        true
+    }
+
+    /// 要素が含まれる可能性がある場合に、 `true` を返します。
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        let (fp, i1, i2) = self.locate(item);
+        self.buckets[i1].contains(&fp)
+            || self.buckets[i2].contains(&fp)
+            || self.victim.is_some_and(|(i, v)| v == fp && (i == i1 || i == i2))
+    }
+
+    /// 要素を削除します。要素のフィンガープリントが見つかった場合に、 `true` を返します。
+    pub fn remove<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        let (fp, i1, i2) = self.locate(item);
+        if self.victim.is_some_and(|(i, v)| v == fp && (i == i1 || i == i2)) {
+            self.victim = None;
+            self.len -= 1;
+            return true;
+        }
+        for i in [i1, i2] {
+            if let Some(slot) = self.buckets[i].iter().position(|v| *v == fp) {
+                self.buckets[i][slot] = EMPTY;
+                self.len -= 1;
+                if let Some((vi, vfp)) = self.victim.take() {
+                    // retry to place the victim
+                    self.len -= 1;
+                    self.reinsert(vi, vfp);
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// すべての要素を取り除きます。
+    pub fn clear(&mut self) {
+        self.buckets.iter_mut().for_each(|b| *b = [EMPTY; BUCKET_SIZE]);
+        self.len = 0;
+        self.victim = None;
+    }
+
+    fn locate<T: Hash + ?Sized>(&self, item: &T) -> (u16, usize, usize) {
+        let h = mix64(hash_with(&self.hasher, item, 0));
+        let fp = match (h >> 48) as u16 {
+            EMPTY => 1,
+            fp => fp,
+        };
+        let i1 = (h as usize) & self.mask;
+        (fp, i1, self.alt_index(i1, fp))
+    }
+
+    fn alt_index(&self, i: usize, fp: u16) -> usize {
+        (i ^ mix64(fp as u64) as usize) & self.mask
+    }
+
+    fn put(&mut self, i: usize, fp: u16) -> bool {
+        match self.buckets[i].iter().position(|v| *v == EMPTY) {
+            Some(slot) => {
+                self.buckets[i][slot] = fp;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn reinsert(&mut self, i: usize, fp: u16) {
+        let alt = self.alt_index(i, fp);
+        if self.put(i, fp) || self.put(alt, fp) {
+            self.len += 1;
+        } else {
+            self.victim = Some((i, fp));
+            self.len += 1;
+        }
+    }
+}
+
+impl <R, S> SpaceUsage for CuckooFilter<R, S> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("buckets", &self.buckets)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn no_false_negative() {
+        let mut filter = CuckooFilter::with_rng(10000, StdRng::seed_from_u64(0));
+        for i in 0..10000 {
+            assert!(filter.insert(&i));
+        }
+        assert_eq!(10000, filter.len());
+        for i in 0..10000 {
+            assert!(filter.contains(&i));
+        }
+
+        let false_positives = (10000..20000).filter(|i| filter.contains(i)).count();
+        assert!(false_positives < 100, "false_positives = {}", false_positives);
+
+        for i in (0..10000).step_by(2) {
+            assert!(filter.remove(&i));
+        }
+        assert_eq!(5000, filter.len());
+        for i in (1..10000).step_by(2) {
+            assert!(filter.contains(&i));
+        }
+    }
+
+    #[test]
+    fn full() {
+        let mut filter = CuckooFilter::with_rng(16, StdRng::seed_from_u64(0));
+        let capacity = filter.capacity();
+        let mut inserted = vec![];
+        for i in 0..capacity * 2 {
+            if !filter.insert(&i) {
+                break;
+            }
+            inserted.push(i);
+        }
+        assert!(inserted.len() < capacity * 2);
+        assert_eq!(inserted.len(), filter.len());
+        for i in &inserted {
+            assert!(filter.contains(i));
+        }
+        for i in &inserted {
+            assert!(filter.remove(i));
+        }
+        assert!(filter.is_empty());
+
+        filter.clear();
+        assert!(filter.insert("again"));
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut filter = CuckooFilter::with_rng(100, StdRng::seed_from_u64(0));
+        assert!(filter.insert("x"));
+        assert!(filter.insert("x"));
+        assert!(filter.remove("x"));
+        assert!(filter.contains("x"));
+        assert!(filter.remove("x"));
+        assert!(!filter.contains("x"));
+        assert!(!filter.remove("x"));
+    }
+
+    #[test]
+    fn with_rng() {
+        let build = |seed| {
+            let mut filter = CuckooFilter::with_rng(64, StdRng::seed_from_u64(seed));
+            // more than fit without evictions
+            for i in 0..60 {
+                filter.insert(&i);
+            }
+            filter
+        };
+        let (a, b) = (build(1), build(1));
+        assert_eq!(a.buckets, b.buckets);
+        assert_eq!(a.victim, b.victim);
+        assert!((0..60).all(|i| a.contains(&i)));
+    }
+
+    #[test]
+    fn with_hasher() {
+        let mut filter = CuckooFilter::with_rng_and_hasher(1000, StdRng::seed_from_u64(0), rustc_hash::FxBuildHasher);
+        for i in 0..1000 {
+            assert!(filter.insert(&i));
+        }
+        assert!((0..1000).all(|i| filter.contains(&i)));
+        let false_positives = (1000..11000).filter(|i| filter.contains(i)).count();
+        assert!(false_positives < 100, "false_positives = {}", false_positives);
+        assert!(filter.remove(&0));
+        assert_eq!(999, filter.len());
+    }
+}
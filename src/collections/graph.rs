@@ -0,0 +1,318 @@
+use super::heap::Heap;
+use super::union_find::UnionFind;
+use crate::bits::fid::FID;
+use crate::bits::fid::NaiveFID;
+use crate::space::SpaceUsage;
+
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 重み付き有向グラフ (CSR 形式)
+///
+/// 各頂点から出る辺を頂点の順に 1 本の配列に並べて保持する、静的な隣接リスト。
+/// 各頂点の辺の範囲は、次数を 1 進符号 (次数個の `0` と区切りの `1`) で並べたビットベクトルの
+/// select 操作で求めます。
+///
+/// 幅優先探索・深さ優先探索・ダイクストラ法を提供します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::graph::NaiveGraph;
+/// let graph = NaiveGraph::new(4, &[(0, 1, 5), (0, 2, 1), (2, 1, 2), (1, 3, 1)]);
+/// assert_eq!(4, graph.num_vertices());
+/// assert_eq!(4, graph.num_edges());
+/// assert_eq!(vec![(1, 5), (2, 1)], graph.neighbors(0).collect::<Vec<_>>());
+///
+/// // bfs は辺の本数による距離を返します。
+/// assert_eq!(vec![Some(0), Some(1), Some(1), Some(2)], graph.bfs(0));
+///
+/// // dijkstra は辺の重みの和による最短距離を返します。
+/// assert_eq!(vec![Some(0), Some(3), Some(1), Some(4)], graph.dijkstra(0));
+/// assert_eq!(vec![None, Some(0), None, Some(1)], graph.dijkstra(1));
+/// ```
+pub struct Graph<T: FID> {
+    n: usize,
+    // deg(v) zeros followed by a one, for each vertex v
+    bounds: T,
+    targets: Vec<usize>,
+    weights: Vec<i64>,
+}
+
+pub type NaiveGraph = Graph<NaiveFID>;
+
+impl <T: FID> Graph<T> {
+    /// 頂点数 `n` と、有向辺 `(始点, 終点, 重み)` の列からグラフを構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if an endpoint is out of range.
+    pub fn new(n: usize, edges: &[(usize, usize, i64)]) -> Self {
+        let mut degree = vec![0; n];
+        for (u, v, _) in edges {
+            assert!(*u < n && *v < n);
+            degree[*u] += 1;
+        }
+        let mut bits = Vec::with_capacity(n + edges.len());
+        let mut offsets = Vec::with_capacity(n);
+        for d in &degree {
+            offsets.push(bits.len() - offsets.len());
+            bits.resize(bits.len() + d, false);
+            bits.push(true);
+        }
+        let mut targets = vec![0; edges.len()];
+        let mut weights = vec![0; edges.len()];
+        for (u, v, w) in edges {
+            targets[offsets[*u]] = *v;
+            weights[offsets[*u]] = *w;
+            offsets[*u] += 1;
+        }
+        Graph { n, bounds: T::from_bool_vec(&bits), targets, weights }
+    }
+
+    /// 頂点数 `n` と、無向辺 `(端点, 端点, 重み)` の列から、各辺を両方向の有向辺としたグラフを構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if an endpoint is out of range.
+    pub fn new_undirected(n: usize, edges: &[(usize, usize, i64)]) -> Self {
+        let both: Vec<(usize, usize, i64)> = edges.iter()
+            .flat_map(|&(u, v, w)| [(u, v, w), (v, u, w)])
+            .collect();
+        Self::new(n, &both)
+    }
+
+    /// 頂点数を返します。
+    pub fn num_vertices(&self) -> usize {
+        self.n
+    }
+
+    /// 有向辺の本数を返します。
+    pub fn num_edges(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// 頂点 `v` から出る辺の本数を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `v` is out of range.
+    pub fn degree(&self, v: usize) -> usize {
+        let (s, e) = self.edge_range(v);
+        e - s
+    }
+
+    /// 頂点 `v` から出る辺の `(終点, 重み)` を、構築時に与えた順に返すイテレータを返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `v` is out of range.
+    pub fn neighbors(&self, v: usize) -> impl Iterator<Item = (usize, i64)> + '_ {
+        let (s, e) = self.edge_range(v);
+        self.targets[s..e].iter().copied().zip(self.weights[s..e].iter().copied())
+    }
+
+    /// 頂点 `s` から各頂点への、辺の本数による最短距離を返します。到達できない頂点は `None` になります。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s` is out of range.
+    pub fn bfs(&self, s: usize) -> Vec<Option<usize>> {
+        let mut dist = vec![None; self.n];
+        dist[s] = Some(0);
+        let mut queue = VecDeque::from(vec![s]);
+        while let Some(u) = queue.pop_front() {
+            let d = dist[u].unwrap();
+            for (v, _) in self.neighbors(u) {
+                if dist[v].is_none() {
+                    dist[v] = Some(d + 1);
+                    queue.push_back(v);
+                }
+            }
+        }
+        dist
+    }
+
+    /// 頂点 `s` から深さ優先探索で到達できる頂点を、訪問順 (行きがけ順) に返します。
+    ///
+    /// 各頂点の辺は、構築時に与えた順にたどります。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s` is out of range.
+    pub fn dfs(&self, s: usize) -> Vec<usize> {
+        let mut visited = vec![false; self.n];
+        let mut order = vec![];
+        let mut stack = vec![s];
+        while let Some(u) = stack.pop() {
+            if visited[u] {
+                continue;
+            }
+            visited[u] = true;
+            order.push(u);
+            let (b, e) = self.edge_range(u);
+            stack.extend(self.targets[b..e].iter().rev().filter(|v| !visited[**v]));
+        }
+        order
+    }
+
+    /// 頂点 `s` から各頂点への、辺の重みの和による最短距離を返します。到達できない頂点は `None` になります。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s` is out of range, or the graph has a negative weight.
+    pub fn dijkstra(&self, s: usize) -> Vec<Option<i64>> {
+        assert!(self.weights.iter().all(|w| *w >= 0));
+        let mut dist = vec![None; self.n];
+        let mut heap = Heap::new();
+        dist[s] = Some(0);
+        heap.push((0, s));
+        while let Some((d, u)) = heap.pop() {
+            if dist[u].is_some_and(|du| du < d) {
+                continue;
+            }
+            for (v, w) in self.neighbors(u) {
+                let nd = d + w;
+                if dist[v].is_none_or(|dv| nd < dv) {
+                    dist[v] = Some(nd);
+                    heap.push((nd, v));
+                }
+            }
+        }
+        dist
+    }
+
+    fn edge_range(&self, v: usize) -> (usize, usize) {
+        assert!(v < self.n);
+        let s = if v == 0 { 0 } else { self.bounds.select1(v - 1) + 1 - v };
+        (s, self.bounds.select1(v) - v)
+    }
+}
+
+/// 頂点数 `n` の無向グラフの辺 `(端点, 端点, 重み)` の列から、クラスカル法で最小全域森を求めます。
+///
+/// 最小全域森に含まれる辺の添字を、重みの小さい順に返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::graph::minimum_spanning_forest;
+/// let edges = [(0, 1, 4), (1, 2, 1), (0, 2, 2), (3, 4, 7)];
+/// assert_eq!(vec![1, 2, 3], minimum_spanning_forest(5, &edges));
+/// ```
+///
+/// # Panics
+///
+/// Panics if an endpoint is out of range.
+pub fn minimum_spanning_forest(n: usize, edges: &[(usize, usize, i64)]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..edges.len()).collect();
+    order.sort_by_key(|i| edges[*i].2);
+    let mut uf = UnionFind::new(n);
+    order.into_iter().filter(|i| uf.union(edges[*i].0, edges[*i].1)).collect()
+}
+
+impl <T: FID + SpaceUsage> SpaceUsage for Graph<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("bounds", &self.bounds), ("targets", &self.targets), ("weights", &self.weights)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn random_edges(n: usize, m: usize) -> Vec<(usize, usize, i64)> {
+        let mut rng = StdRng::seed_from_u64(0);
+        (0..m).map(|_| (rng.gen_range(0, n), rng.gen_range(0, n), rng.gen_range(0, 20))).collect()
+    }
+
+    #[test]
+    fn neighbors() {
+        let n = 30;
+        let edges = random_edges(n, 100);
+        let graph = NaiveGraph::new(n, &edges);
+        assert_eq!(100, graph.num_edges());
+        for u in 0..n {
+            let expected: Vec<(usize, i64)> = edges.iter().filter(|e| e.0 == u).map(|e| (e.1, e.2)).collect();
+            assert_eq!(expected, graph.neighbors(u).collect::<Vec<_>>());
+            assert_eq!(expected.len(), graph.degree(u));
+        }
+        let undirected = NaiveGraph::new_undirected(n, &edges);
+        assert_eq!(200, undirected.num_edges());
+    }
+
+    #[test]
+    fn shortest_paths() {
+        let n = 30;
+        let edges = random_edges(n, 80);
+        let graph = NaiveGraph::new(n, &edges);
+        // Bellman-Ford style relaxation
+        let relax = |weight: &dyn Fn(i64) -> i64| {
+            let mut dist: Vec<Option<i64>> = vec![None; n];
+            dist[0] = Some(0);
+            for _ in 0..n {
+                for (u, v, w) in &edges {
+                    if let Some(du) = dist[*u] {
+                        if dist[*v].is_none_or(|dv| du + weight(*w) < dv) {
+                            dist[*v] = Some(du + weight(*w));
+                        }
+                    }
+                }
+            }
+            dist
+        };
+        assert_eq!(relax(&|w| w), graph.dijkstra(0));
+        let hops: Vec<Option<usize>> = relax(&|_| 1).into_iter().map(|d| d.map(|d| d as usize)).collect();
+        assert_eq!(hops, graph.bfs(0));
+
+        let order = graph.dfs(0);
+        let mut reachable: Vec<usize> = (0..n).filter(|v| hops[*v].is_some()).collect();
+        assert_eq!(0, order[0]);
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(reachable, sorted);
+        reachable.dedup();
+        assert_eq!(reachable.len(), order.len());
+    }
+
+    #[test]
+    fn dfs_order() {
+        let graph = NaiveGraph::new(5, &[(0, 1, 0), (0, 3, 0), (1, 2, 0), (3, 4, 0), (2, 3, 0)]);
+        assert_eq!(vec![0, 1, 2, 3, 4], graph.dfs(0));
+        assert_eq!(vec![3, 4], graph.dfs(3));
+    }
+
+    #[test]
+    fn spanning_forest() {
+        let n = 8;
+        for _ in 0..20 {
+            let edges = random_edges(n, 12);
+            let chosen = minimum_spanning_forest(n, &edges);
+            let total: i64 = chosen.iter().map(|i| edges[*i].2).sum();
+            let mut uf = UnionFind::new(n);
+            for (u, v, _) in &edges {
+                uf.union(*u, *v);
+            }
+            assert_eq!(n - uf.count(), chosen.len());
+
+            // compare with all forests of the same size
+            let m = edges.len();
+            let mut best = i64::MAX;
+            for mask in 0..1_u32 << m {
+                if mask.count_ones() as usize != chosen.len() {
+                    continue;
+                }
+                let mut uf = UnionFind::new(n);
+                let selected: Vec<usize> = (0..m).filter(|i| mask >> i & 1 == 1).collect();
+                if selected.iter().all(|i| uf.union(edges[*i].0, edges[*i].1)) {
+                    best = best.min(selected.iter().map(|i| edges[*i].2).sum());
+                }
+            }
+            assert_eq!(best, total);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use core::hash::BuildHasher;
+use core::hash::BuildHasherDefault;
+use core::hash::Hash;
+use core::hash::Hasher;
+
+/// [`hash_with_seed()`] のハッシュ関数 (鍵を固定した SipHash) を作る `BuildHasher`
+///
+/// ハッシュ値を使う確率的なデータ構造の、既定のハッシュ関数です。
+/// `RandomState` と異なり、プロセスをまたいでも同じハッシュ値を返します。
+pub type DefaultHashBuilder = BuildHasherDefault<SipHasher13>;
+
+// the hash map of the crate, randomly keyed against hash flooding with std, and keyed with a fixed key without std
+#[cfg(feature = "std")]
+pub(crate) type HashMap<K, V> = hashbrown::HashMap<K, V, std::collections::hash_map::RandomState>;
+#[cfg(not(feature = "std"))]
+pub(crate) type HashMap<K, V> = hashbrown::HashMap<K, V, DefaultHashBuilder>;
+
+/// SipHash-1-3
+///
+/// `std` なしで使えるハッシュ関数です。 [`Default`] で作ると、鍵を 0 に固定します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::hash::SipHasher13;
+/// use std::hash::Hash;
+/// use std::hash::Hasher;
+/// let mut a = SipHasher13::default();
+/// let mut b = SipHasher13::new_with_keys(0, 0);
+/// "foo".hash(&mut a);
+/// "foo".hash(&mut b);
+/// assert_eq!(b.finish(), a.finish());
+/// ```
+#[derive(Clone, Debug)]
+pub struct SipHasher13 {
+    v: [u64; 4],
+    // the bytes not compressed yet, and their number
+    tail: u64,
+    ntail: usize,
+    length: usize,
+}
+
+impl SipHasher13 {
+    /// 鍵 `k0`, `k1` の SipHash-1-3 を作ります。
+    pub fn new_with_keys(k0: u64, k1: u64) -> Self {
+        let v = [k0 ^ 0x736f6d6570736575, k1 ^ 0x646f72616e646f6d, k0 ^ 0x6c7967656e657261, k1 ^ 0x7465646279746573];
+        SipHasher13 { v, tail: 0, ntail: 0, length: 0 }
+    }
+
+    fn round(&mut self) {
+        let [v0, v1, v2, v3] = &mut self.v;
+        *v0 = v0.wrapping_add(*v1);
+        *v1 = v1.rotate_left(13) ^ *v0;
+        *v0 = v0.rotate_left(32);
+        *v2 = v2.wrapping_add(*v3);
+        *v3 = v3.rotate_left(16) ^ *v2;
+        *v0 = v0.wrapping_add(*v3);
+        *v3 = v3.rotate_left(21) ^ *v0;
+        *v2 = v2.wrapping_add(*v1);
+        *v1 = v1.rotate_left(17) ^ *v2;
+        *v2 = v2.rotate_left(32);
+    }
+
+    fn compress(&mut self, m: u64) {
+        self.v[3] ^= m;
+        self.round();
+        self.v[0] ^= m;
+    }
+}
+
+impl Default for SipHasher13 {
+    fn default() -> Self {
+        SipHasher13::new_with_keys(0, 0)
+    }
+}
+
+impl Hasher for SipHasher13 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.length = self.length.wrapping_add(bytes.len());
+        for &b in bytes {
+            self.tail |= (b as u64) << (8 * self.ntail);
+            self.ntail += 1;
+            if self.ntail == 8 {
+                self.compress(self.tail);
+                self.tail = 0;
+                self.ntail = 0;
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut state = self.clone();
+        state.compress((self.length as u64 & 0xff) << 56 | self.tail);
+        state.v[2] ^= 0xff;
+        for _ in 0..3 {
+            state.round();
+        }
+        state.v.iter().fold(0, |h, &v| h ^ v)
+    }
+}
+
+/// シード `seed` を使って `item` のハッシュ値を計算します。
+///
+/// 同じ `item` と `seed` に対しては、プロセスをまたいでも常に同じ値を返します。
+/// 異なるシードからは互いに独立とみなせるハッシュ値が得られます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::hash::hash_with_seed;
+/// assert_eq!(hash_with_seed("foo", 1), hash_with_seed("foo", 1));
+/// assert_ne!(hash_with_seed("foo", 1), hash_with_seed("foo", 2));
+/// ```
+pub fn hash_with_seed<T: Hash + ?Sized>(item: &T, seed: u64) -> u64 {
+    hash_with(&DefaultHashBuilder::default(), item, seed)
+}
+
+/// `build_hasher` の作るハッシュ関数で、シード `seed` を使って `item` のハッシュ値を計算します。
+///
+/// 異なるシードからのハッシュ値が互いに独立とみなせるかは、ハッシュ関数の質によります。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::hash::DefaultHashBuilder;
+/// use rust_study::collections::hash::hash_with;
+/// use rust_study::collections::hash::hash_with_seed;
+/// assert_eq!(hash_with_seed("foo", 1), hash_with(&DefaultHashBuilder::default(), "foo", 1));
+/// ```
+pub fn hash_with<S: BuildHasher, T: Hash + ?Sized>(build_hasher: &S, item: &T, seed: u64) -> u64 {
+    let mut hasher = build_hasher.build_hasher();
+    seed.hash(&mut hasher);
+    item.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 64 ビット整数を撹拌します。 (SplitMix64 の最終段)
+pub fn mix64(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic() {
+        assert_eq!(hash_with_seed(&42_u32, 0), hash_with_seed(&42_u32, 0));
+        assert_ne!(hash_with_seed(&42_u32, 0), hash_with_seed(&43_u32, 0));
+        assert_eq!(mix64(1), mix64(1));
+        assert_ne!(mix64(1), mix64(2));
+    }
+
+    #[test]
+    fn reference_vectors() {
+        // the SipHash-1-3 outputs of the reference implementation for the key 00 01 .. 0f and the messages 00 01 .. (n - 1)
+        let expected: [[u8; 8]; 16] = [
+            [0xdc, 0xc4, 0x0f, 0x05, 0x58, 0x01, 0xac, 0xab],
+            [0x93, 0xca, 0x57, 0x7d, 0xf3, 0x9b, 0xf4, 0xc9],
+            [0x4d, 0xd4, 0xc7, 0x4d, 0x02, 0x9b, 0xcb, 0x82],
+            [0xfb, 0xf7, 0xdd, 0xe7, 0xb8, 0x0a, 0xf8, 0x8b],
+            [0x28, 0x83, 0xd3, 0x88, 0x60, 0x57, 0x75, 0xcf],
+            [0x67, 0x3b, 0x53, 0x49, 0x2f, 0xd5, 0xf9, 0xde],
+            [0xa7, 0x22, 0x9f, 0xc5, 0x50, 0x2b, 0x0d, 0xc5],
+            [0x40, 0x11, 0xb1, 0x9b, 0x98, 0x7d, 0x92, 0xd3],
+            [0x8e, 0x9a, 0x29, 0x8d, 0x11, 0x95, 0x90, 0x36],
+            [0xe4, 0x3d, 0x06, 0x6c, 0xb3, 0x8e, 0xa4, 0x25],
+            [0x7f, 0x09, 0xff, 0x92, 0xee, 0x85, 0xde, 0x79],
+            [0x52, 0xc3, 0x4d, 0xf9, 0xc1, 0x18, 0xc1, 0x70],
+            [0xa2, 0xd9, 0xb4, 0x57, 0xb1, 0x84, 0xa3, 0x78],
+            [0xa7, 0xff, 0x29, 0x12, 0x0c, 0x76, 0x6f, 0x30],
+            [0x34, 0x5d, 0xf9, 0xc0, 0x11, 0xa1, 0x5a, 0x60],
+            [0x56, 0x99, 0x51, 0x2a, 0x6d, 0xd8, 0x20, 0xd3],
+        ];
+        let (k0, k1) = (0x0706050403020100, 0x0f0e0d0c0b0a0908);
+        let message: Vec<u8> = (0..16).collect();
+        for (n, expected) in expected.iter().enumerate() {
+            let mut hasher = SipHasher13::new_with_keys(k0, k1);
+            hasher.write(&message[..n]);
+            assert_eq!(*expected, hasher.finish().to_le_bytes(), "{} bytes", n);
+            // written in pieces
+            let mut hasher = SipHasher13::new_with_keys(k0, k1);
+            hasher.write(&message[..n / 3]);
+            hasher.write(&message[n / 3..n]);
+            assert_eq!(*expected, hasher.finish().to_le_bytes(), "{} bytes in pieces", n);
+        }
+    }
+
+    #[test]
+    fn with_build_hasher() {
+        let fx = rustc_hash::FxBuildHasher;
+        assert_eq!(hash_with(&fx, &42_u32, 0), hash_with(&fx, &42_u32, 0));
+        assert_ne!(hash_with(&fx, &42_u32, 0), hash_with(&fx, &42_u32, 1));
+        assert_ne!(hash_with(&fx, &42_u32, 0), hash_with_seed(&42_u32, 0));
+    }
+}
@@ -1,6 +1,12 @@
-use std::cmp::Ord;
-use std::cmp::Ordering;
-use std::cmp::Ordering::Less;
+use crate::error::Error;
+use crate::space::SpaceUsage;
+
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ord;
+use core::cmp::Ordering;
+use core::cmp::Ordering::Less;
 
 /// 二分ヒープ
 ///
@@ -85,12 +91,20 @@ impl <T> Heap<T> {
         self.heap.first()
     }
 
+    /// 二分ヒープの要素を、順不同で返します。
+    pub fn iter(&self) -> core::slice::Iter<'_, T> {
+        self.heap.iter()
+    }
+
     /// 二分ヒープが空の場合に、 `true` を返します。
     pub fn is_empty(&self) -> bool { self.heap.is_empty() }
 
     /// 二分ヒープの要素数を返します。
     pub fn len(&self) -> usize { self.heap.len() }
 
+    /// 要素をすべて取り除きます。確保した容量はそのまま残ります。
+    pub fn clear(&mut self) { self.heap.clear() }
+
     /// 要素を保持するための内部の配列の容量を確保します。
     ///
     /// [`Vec::reserve()`] を参照してください。
@@ -109,6 +123,16 @@ impl <T> Heap<T> {
     /// Panics if the new capacity exceeds `isize::MAX` bytes.
     pub fn reserve_exact(&mut self, additional: usize) { self.heap.reserve_exact(additional) }
 
+    /// [`Self::reserve()`] と同じですが、容量が大きすぎる場合、パニックせずに [`Error::CapacityOverflow`] を返します。
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), Error> {
+        self.heap.try_reserve(additional).map_err(|_| Error::CapacityOverflow)
+    }
+
+    /// [`Self::reserve_exact()`] と同じですが、容量が大きすぎる場合、パニックせずに [`Error::CapacityOverflow`] を返します。
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), Error> {
+        self.heap.try_reserve_exact(additional).map_err(|_| Error::CapacityOverflow)
+    }
+
     /// `num` で指定した件数を上限に、小さい順にヒープから取り除き `Vec<T>` として返します。
     pub fn drain(&mut self, num: usize) -> Vec<T> {
         let mut vec = Vec::with_capacity(self.len());
@@ -122,6 +146,16 @@ impl <T> Heap<T> {
         vec
     }
 
+    /// ヒープ条件 (各要素が親以上であること) を検証し、最初に見つけた違反を [`Error::CorruptData`] で返します。
+    ///
+    /// 比較関数が全順序でない場合や、内部可変性などでヒープの中の値の順序が変わった場合に、違反が起こります。
+    pub fn validate(&self) -> Result<(), Error> {
+        match (1..self.len()).find(|&i| (self.compare)(&self.heap[i], &self.heap[(i - 1) / 2]) == Less) {
+            Some(i) => Err(Error::CorruptData(format!("element {} is less than its parent {}", i, (i - 1) / 2))),
+            None => Ok(()),
+        }
+    }
+
     fn heap_up(&mut self, i: usize) {
         if i == 0 { return; }
         let parent = (i - 1) / 2;
@@ -145,6 +179,12 @@ impl <T> Heap<T> {
     }
 }
 
+impl <T: SpaceUsage> SpaceUsage for Heap<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("heap", &self.heap)]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,6 +205,20 @@ mod tests {
         assert_eq!(Some(5), heap.pop());    assert_eq!(0, heap.len()); assert!(heap.is_empty());
     }
 
+    #[test]
+    fn clear() {
+        let mut heap = Heap::new();
+        heap.reserve(10);
+        heap.push(2);
+        heap.push(1);
+        heap.clear();
+        assert!(heap.is_empty());
+        assert_eq!(None, heap.pop());
+        assert!(heap.heap.capacity() >= 10);
+        heap.push(3);
+        assert_eq!(Some(3), heap.pop());
+    }
+
     #[test]
     fn with_compare() {
         // Reverse order
@@ -181,4 +235,37 @@ mod tests {
         assert_eq!(Some(2), heap.pop());    assert_eq!(1, heap.len()); assert!(!heap.is_empty());
         assert_eq!(Some(1), heap.pop());    assert_eq!(0, heap.len()); assert!(heap.is_empty());
     }
+
+    #[test]
+    fn iter() {
+        let mut heap = Heap::new();
+        for v in [3, 1, 4, 1, 5] {
+            heap.push(v);
+        }
+        let mut values: Vec<i32> = heap.iter().copied().collect();
+        values.sort();
+        assert_eq!(vec![1, 1, 3, 4, 5], values);
+    }
+
+    #[test]
+    fn try_reserve() {
+        let mut heap = Heap::<i32>::new();
+        assert_eq!(Ok(()), heap.try_reserve(10));
+        assert_eq!(Ok(()), heap.try_reserve_exact(10));
+        assert_eq!(Err(Error::CapacityOverflow), heap.try_reserve(usize::MAX));
+        assert_eq!(Err(Error::CapacityOverflow), heap.try_reserve_exact(usize::MAX));
+    }
+
+    #[test]
+    fn validate() {
+        let mut heap = Heap::new();
+        assert_eq!(Ok(()), heap.validate());
+        for v in [5, 3, 8, 1, 9, 2] {
+            heap.push(core::cell::Cell::new(v));
+            assert_eq!(Ok(()), heap.validate());
+        }
+        // the root changes its value through the shared reference
+        heap.iter().next().unwrap().set(10);
+        assert_eq!(Err(Error::CorruptData("element 1 is less than its parent 0".to_string())), heap.validate());
+    }
 }
@@ -0,0 +1,328 @@
+use crate::collections::arena::BoxNodes;
+use crate::collections::treap::Augment;
+use crate::collections::treap::NodeRef;
+use crate::collections::treap::Treap;
+use crate::random::DefaultRng;
+use crate::space::SpaceUsage;
+
+use alloc::vec::Vec;
+use alloc::vec;
+use core::cmp::Ordering;
+use core::ops::Bound;
+use core::ops::Range;
+
+use rand::RngCore;
+
+// the key of an interval in the treap, ordered by the start; `id` tells apart the same intervals
+struct Key<K> {
+    interval: Range<K>,
+    id: u64,
+}
+
+impl <K: Clone> Clone for Key<K> {
+    fn clone(&self) -> Self {
+        Key { interval: self.interval.clone(), id: self.id }
+    }
+}
+
+impl <K: Ord> Ord for Key<K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.interval.start.cmp(&other.interval.start)
+            .then_with(|| self.interval.end.cmp(&other.interval.end))
+            .then_with(|| self.id.cmp(&other.id))
+    }
+}
+
+impl <K: Ord> PartialOrd for Key<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl <K: Ord> PartialEq for Key<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl <K: Ord> Eq for Key<K> {}
+
+// the maximum end of the intervals in the subtree
+struct MaxEnd<K>(K);
+
+impl <K: Ord + Clone, V> Augment<Key<K>, V> for MaxEnd<K> {
+    fn summarize(key: &Key<K>, _: &V, left: Option<&Self>, right: Option<&Self>) -> Self {
+        let mut max_end = &key.interval.end;
+        for child in [left, right].into_iter().flatten() {
+            if child.0 > *max_end {
+                max_end = &child.0;
+            }
+        }
+        MaxEnd(max_end.clone())
+    }
+}
+
+type Tree<K, V, R> = Treap<Key<K>, V, R, BoxNodes, MaxEnd<K>>;
+type Node<'a, K, V> = NodeRef<'a, Key<K>, V, BoxNodes, MaxEnd<K>>;
+
+/// 区間木
+///
+/// 半開区間 `[lo, hi)` と値の組を保持し、ある点を含む区間や、ある区間と重なる区間を列挙します。
+/// 区間の始点をキーとし、部分木中の区間の終点の最大値を要約 ([`Augment`]) に持たせた [`Treap`] により、
+/// 追加・削除を期待値 `O(log n)` 、列挙を期待値 `O(log n + 列挙する個数)` 程度で行います。
+///
+/// 同じ区間を複数回追加することもできます。
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use rust_study::collections::interval_tree::IntervalTree;
+/// let mut tree = IntervalTree::new();
+/// tree.insert(10..20, "gene A");
+/// tree.insert(15..30, "gene B");
+/// tree.insert(40..50, "gene C");
+///
+/// // query_point は点を含む区間を、始点の小さい順に返します。
+/// assert_eq!(vec!["gene A", "gene B"], tree.query_point(&17).map(|(_, v)| *v).collect::<Vec<_>>());
+/// assert_eq!(0, tree.query_point(&30).count());
+///
+/// // query_overlap は区間と重なる区間を、始点の小さい順に返します。
+/// assert_eq!(vec![&(15..30), &(40..50)], tree.query_overlap(25..45).map(|(r, _)| r).collect::<Vec<_>>());
+///
+/// assert_eq!(Some("gene B"), tree.remove(&(15..30)));
+/// assert_eq!(2, tree.len());
+/// # }
+/// ```
+///
+/// 同じ区間も別のノードになり、それぞれの優先度を型引数 `R` の乱数生成器で選びます。
+/// 乱数生成器の役割は [`Treap`] と同じです。
+pub struct IntervalTree<K, V, R = DefaultRng> {
+    treap: Tree<K, V, R>,
+    // the id of the next interval
+    next_id: u64,
+}
+
+#[cfg(feature = "std")]
+impl <K: Ord + Clone, V> IntervalTree<K, V> {
+    /// 空の区間木を構築します。
+    pub fn new() -> Self {
+        Self::with_rng(DefaultRng)
+    }
+}
+
+impl <K: Ord + Clone, V, R: RngCore> IntervalTree<K, V, R> {
+    /// Treap の優先度を乱数生成器 `rng` で選ぶ、空の区間木を構築します。
+    pub fn with_rng(rng: R) -> Self {
+        IntervalTree { treap: Treap::with_storage(rng), next_id: 0 }
+    }
+
+    /// 区間の個数を返します。
+    pub fn len(&self) -> usize {
+        self.treap.len()
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.treap.is_empty()
+    }
+
+    /// 区間 `interval` に値 `value` を対応付けて追加します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is empty.
+    pub fn insert(&mut self, interval: Range<K>, value: V) {
+        assert!(interval.start < interval.end);
+        self.treap.insert(Key { interval, id: self.next_id }, value);
+        self.next_id += 1;
+    }
+
+    /// 区間 `interval` を 1 つ取り除き、対応付けられていた値を返します。
+    pub fn remove(&mut self, interval: &Range<K>) -> Option<V> {
+        let first = Key { interval: interval.clone(), id: 0 };
+        let last = Key { interval: interval.clone(), id: u64::MAX };
+        let (key, _) = self.treap.range(first..=last).next()?;
+        let key = key.clone();
+        self.treap.remove(&key)
+    }
+
+    /// すべての区間と値の組を、始点の小さい順に返すイテレータを返します。
+    pub fn iter(&self) -> Overlap<'_, K, V> {
+        let mut iter = Overlap { stack: vec![], start: None, end: Bound::Unbounded };
+        iter.push_left(self.treap.root());
+        iter
+    }
+
+    /// 点 `point` を含む区間と値の組を、始点の小さい順に返すイテレータを返します。
+    pub fn query_point(&self, point: &K) -> Overlap<'_, K, V> {
+        let mut iter = Overlap { stack: vec![], start: Some(point.clone()), end: Bound::Included(point.clone()) };
+        iter.push_left(self.treap.root());
+        iter
+    }
+
+    /// 区間 `range` と重なる区間と値の組を、始点の小さい順に返すイテレータを返します。
+    ///
+    /// `range` が空の場合、何も返しません。
+    pub fn query_overlap(&self, range: Range<K>) -> Overlap<'_, K, V> {
+        let is_empty = range.start >= range.end;
+        let mut iter = Overlap { stack: vec![], start: Some(range.start), end: Bound::Excluded(range.end) };
+        if !is_empty {
+            iter.push_left(self.treap.root());
+        }
+        iter
+    }
+}
+
+#[cfg(feature = "std")]
+impl <K: Ord + Clone, V> Default for IntervalTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// [`IntervalTree::iter()`], [`IntervalTree::query_point()`], [`IntervalTree::query_overlap()`] の返すイテレータ
+pub struct Overlap<'a, K, V> {
+    stack: Vec<Node<'a, K, V>>,
+    // intervals must end after `start`
+    start: Option<K>,
+    // intervals must start within `end`
+    end: Bound<K>,
+}
+
+impl <'a, K: Ord, V> Overlap<'a, K, V> {
+    fn ends_after_start(&self, end: &K) -> bool {
+        self.start.as_ref().is_none_or(|s| *end > *s)
+    }
+
+    fn push_left(&mut self, mut node: Option<Node<'a, K, V>>) {
+        while let Some(n) = node {
+            if !self.ends_after_start(&n.summary().0) {
+                break;
+            }
+            self.stack.push(n);
+            node = n.left();
+        }
+    }
+}
+
+impl <'a, K: Ord, V> Iterator for Overlap<'a, K, V> {
+    type Item = (&'a Range<K>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let n = self.stack.pop()?;
+            let interval = &n.key().interval;
+            let starts_before_end = match &self.end {
+                Bound::Included(e) => interval.start <= *e,
+                Bound::Excluded(e) => interval.start < *e,
+                Bound::Unbounded => true,
+            };
+            if !starts_before_end {
+                self.stack.clear();
+                return None;
+            }
+            self.push_left(n.right());
+            if self.ends_after_start(&interval.end) {
+                return Some((interval, n.value()));
+            }
+        }
+    }
+}
+
+impl <K: SpaceUsage> SpaceUsage for Key<K> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("interval", &self.interval)]
+    }
+}
+
+impl <K: SpaceUsage> SpaceUsage for MaxEnd<K> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("max_end", &self.0)]
+    }
+}
+
+impl <K: SpaceUsage, V: SpaceUsage, R> SpaceUsage for IntervalTree<K, V, R> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("treap", &self.treap)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut tree = IntervalTree::with_rng(StdRng::seed_from_u64(1));
+        let mut expected: Vec<(Range<u32>, u32)> = vec![];
+        for i in 0..2000 {
+            if rng.gen_range(0, 4) == 0 && !expected.is_empty() {
+                let r = expected[rng.gen_range(0, expected.len())].0.clone();
+                let removed = tree.remove(&r);
+                let pos = expected.iter().position(|(x, v)| *x == r && Some(*v) == removed);
+                assert!(pos.is_some());
+                expected.remove(pos.unwrap());
+                assert_eq!(None, tree.remove(&(1000..1001)));
+            } else {
+                let lo = rng.gen_range(0, 1000);
+                let hi = lo + rng.gen_range(1, 50);
+                tree.insert(lo..hi, i);
+                expected.push((lo..hi, i));
+            }
+            assert_eq!(expected.len(), tree.len());
+
+            let sorted = |mut v: Vec<(Range<u32>, u32)>| {
+                v.sort_by_key(|(r, v)| (r.start, r.end, *v));
+                v
+            };
+            let collect = |iter: Overlap<'_, u32, u32>| {
+                let v: Vec<(Range<u32>, u32)> = iter.map(|(r, v)| (r.clone(), *v)).collect();
+                assert!(v.windows(2).all(|w| w[0].0.start <= w[1].0.start));
+                sorted(v)
+            };
+
+            let p = rng.gen_range(0, 1050);
+            let points = expected.iter().filter(|(r, _)| r.contains(&p)).cloned().collect();
+            assert_eq!(sorted(points), collect(tree.query_point(&p)));
+
+            let lo = rng.gen_range(0, 1050);
+            let hi = lo + rng.gen_range(0, 30);
+            let overlaps = expected.iter().filter(|(r, _)| lo < hi && r.start < hi && lo < r.end).cloned().collect();
+            assert_eq!(sorted(overlaps), collect(tree.query_overlap(lo..hi)));
+        }
+        assert_eq!(expected.len(), tree.iter().count());
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut tree = IntervalTree::with_rng(StdRng::seed_from_u64(0));
+        tree.insert(1..3, 'a');
+        tree.insert(1..3, 'b');
+        tree.insert(1..2, 'c');
+        assert_eq!(3, tree.query_point(&1).count());
+        assert_eq!(2, tree.query_point(&2).count());
+        assert!(tree.remove(&(1..3)).is_some());
+        assert!(tree.remove(&(1..3)).is_some());
+        assert_eq!(None, tree.remove(&(1..3)));
+        assert_eq!(vec![(&(1..2), &'c')], tree.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn with_rng() {
+        let build = |seed| {
+            let mut tree = IntervalTree::with_rng(StdRng::seed_from_u64(seed));
+            for i in 0..100 {
+                tree.insert(i..i + 10, i);
+            }
+            tree
+        };
+        let (a, b) = (build(1), build(1));
+        assert_eq!(a.treap.root().map(|n| *n.value()), b.treap.root().map(|n| *n.value()));
+        assert_eq!(10, a.query_point(&50).count());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// 2 次元の k-d 木
+///
+/// 整数座標の点と値の組をまとめて受け取って構築する静的な k-d 木。
+/// 座標を x, y 交互に中央値で分割し、点を配列上に暗黙的な二分木として並べて保持します。
+/// 長方形領域内の点の列挙と、最近傍点の探索を行います。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::kd_tree::KdTree;
+/// let tree = KdTree::new(vec![((0, 0), "a"), ((5, 2), "b"), ((3, 7), "c"), ((-4, 1), "d")]);
+/// assert_eq!(4, tree.len());
+///
+/// // range は長方形 [x.start, x.end) × [y.start, y.end) 内の点を返します。
+/// let mut found: Vec<&str> = tree.range(0..6, 0..5).into_iter().map(|(_, v)| *v).collect();
+/// found.sort();
+/// assert_eq!(vec!["a", "b"], found);
+///
+/// // nearest はユークリッド距離で最も近い点を返します。
+/// assert_eq!(Some((&(3, 7), &"c")), tree.nearest(&(2, 6)));
+/// ```
+pub struct KdTree<V> {
+    points: Vec<((i64, i64), V)>,
+}
+
+impl <V> KdTree<V> {
+    /// 点と値の組の列から k-d 木を構築します。
+    pub fn new(mut points: Vec<((i64, i64), V)>) -> Self {
+        build(&mut points, 0);
+        KdTree { points }
+    }
+
+    /// 点の個数を返します。
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// 長方形 `[x.start, x.end) × [y.start, y.end)` に含まれる点と値の組を返します。順序は不定です。
+    pub fn range(&self, x: Range<i64>, y: Range<i64>) -> Vec<(&(i64, i64), &V)> {
+        let mut result = vec![];
+        self.range_rec(0, self.points.len(), 0, &[x, y], &mut result);
+        result
+    }
+
+    /// 点 `query` にユークリッド距離で最も近い点と値の組を返します。
+    ///
+    /// 最も近い点が複数ある場合は、そのうちのいずれかを返します。空の場合、 `None` を返します。
+    pub fn nearest(&self, query: &(i64, i64)) -> Option<(&(i64, i64), &V)> {
+        let mut best = None;
+        self.nearest_rec(0, self.points.len(), 0, query, &mut best);
+        best.map(|(_, i)| {
+            let (p, v) = &self.points[i];
+            (p, v)
+        })
+    }
+
+    fn range_rec<'a>(&'a self, s: usize, e: usize, axis: usize, rect: &[Range<i64>; 2], result: &mut Vec<(&'a (i64, i64), &'a V)>) {
+        if s >= e {
+            return;
+        }
+        let m = (s + e) / 2;
+        let (p, v) = &self.points[m];
+        if rect[0].contains(&p.0) && rect[1].contains(&p.1) {
+            result.push((p, v));
+        }
+        let c = coord(p, axis);
+        if rect[axis].start <= c {
+            self.range_rec(s, m, axis ^ 1, rect, result);
+        }
+        if c < rect[axis].end {
+            self.range_rec(m + 1, e, axis ^ 1, rect, result);
+        }
+    }
+
+    fn nearest_rec(&self, s: usize, e: usize, axis: usize, query: &(i64, i64), best: &mut Option<(u128, usize)>) {
+        if s >= e {
+            return;
+        }
+        let m = (s + e) / 2;
+        let p = &self.points[m].0;
+        let d = sq(diff(p.0, query.0)).saturating_add(sq(diff(p.1, query.1)));
+        if best.is_none_or(|(b, _)| d < b) {
+            *best = Some((d, m));
+        }
+        let delta = diff(coord(query, axis), coord(p, axis));
+        let (near, far) = if delta < 0 { ((s, m), (m + 1, e)) } else { ((m + 1, e), (s, m)) };
+        self.nearest_rec(near.0, near.1, axis ^ 1, query, best);
+        // the other side may contain a closer point only if the splitting line is close enough
+        if best.is_none_or(|(b, _)| sq(delta) < b) {
+            self.nearest_rec(far.0, far.1, axis ^ 1, query, best);
+        }
+    }
+}
+
+fn coord(p: &(i64, i64), axis: usize) -> i64 {
+    if axis == 0 { p.0 } else { p.1 }
+}
+
+fn diff(a: i64, b: i64) -> i128 {
+    a as i128 - b as i128
+}
+
+fn sq(x: i128) -> u128 {
+    x.unsigned_abs() * x.unsigned_abs()
+}
+
+// place the median of `axis` at the middle, and build both sides recursively
+fn build<V>(points: &mut [((i64, i64), V)], axis: usize) {
+    if points.len() <= 1 {
+        return;
+    }
+    let m = points.len() / 2;
+    points.select_nth_unstable_by_key(m, |(p, _)| coord(p, axis));
+    let (left, right) = points.split_at_mut(m);
+    build(left, axis ^ 1);
+    build(&mut right[1..], axis ^ 1);
+}
+
+impl <V: SpaceUsage> SpaceUsage for KdTree<V> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("points", &self.points)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for n in [0, 1, 2, 10, 1000] {
+            let points: Vec<((i64, i64), usize)> = (0..n).map(|i| ((rng.gen_range(-100, 100), rng.gen_range(-100, 100)), i)).collect();
+            let tree = KdTree::new(points.clone());
+            assert_eq!(n, tree.len());
+            for _ in 0..100 {
+                let x0 = rng.gen_range(-110, 110);
+                let x1 = x0 + rng.gen_range(0, 100);
+                let y0 = rng.gen_range(-110, 110);
+                let y1 = y0 + rng.gen_range(0, 100);
+                let mut expected: Vec<usize> = points.iter()
+                    .filter(|(p, _)| x0 <= p.0 && p.0 < x1 && y0 <= p.1 && p.1 < y1)
+                    .map(|(_, v)| *v)
+                    .collect();
+                expected.sort();
+                let mut actual: Vec<usize> = tree.range(x0..x1, y0..y1).into_iter().map(|(_, v)| *v).collect();
+                actual.sort();
+                assert_eq!(expected, actual);
+
+                let q = (rng.gen_range(-150, 150), rng.gen_range(-150, 150));
+                let dist = |p: &(i64, i64)| (p.0 - q.0).pow(2) + (p.1 - q.1).pow(2);
+                let expected = points.iter().map(|(p, _)| dist(p)).min();
+                assert_eq!(expected, tree.nearest(&q).map(|(p, _)| dist(p)));
+            }
+        }
+    }
+
+    #[test]
+    fn large_coordinates() {
+        let tree = KdTree::new(vec![((i64::MIN, i64::MIN), 0), ((i64::MAX, i64::MAX), 1)]);
+        assert_eq!(Some(&1), tree.nearest(&(1, 1)).map(|(_, v)| v));
+        assert_eq!(Some(&0), tree.nearest(&(-1, -1)).map(|(_, v)| v));
+    }
+}
@@ -0,0 +1,520 @@
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Bound;
+use core::ops::RangeBounds;
+
+/// モノイド
+///
+/// 結合的な二項演算 [`Self::op()`] と、その単位元 [`Self::identity()`] の組です。
+pub trait Monoid {
+    type Value: Clone;
+
+    /// 単位元を返します。
+    fn identity() -> Self::Value;
+
+    /// 二項演算の結果を返します。
+    fn op(lhs: &Self::Value, rhs: &Self::Value) -> Self::Value;
+
+    /// [`LazySegmentTree::new()`] で各要素に置く初期値を返します。
+    ///
+    /// 既定では単位元です。区間長を持つ [`Sum`] や、単位元が番兵の `i64::MAX` である [`Min`] のように、
+    /// 単位元を初期値にすると作用が意味を持たないモノイドで上書きします。
+    fn leaf() -> Self::Value {
+        Self::identity()
+    }
+}
+
+/// モノイドに作用する写像の族
+///
+/// 写像 `f` は [`Self::mapping()`] によってモノイドの値に作用し、
+/// [`Self::composition()`] によって合成できる必要があります。
+pub trait MapMonoid {
+    type M: Monoid;
+    type Map: Clone;
+
+    /// 恒等写像を返します。
+    fn identity_map() -> Self::Map;
+
+    /// 写像 `f` を値 `x` に作用させた結果を返します。
+    fn mapping(f: &Self::Map, x: &<Self::M as Monoid>::Value) -> <Self::M as Monoid>::Value;
+
+    /// 合成写像 `f ∘ g` (先に `g` 、次に `f` を作用させる写像) を返します。
+    fn composition(f: &Self::Map, g: &Self::Map) -> Self::Map;
+}
+
+/// 遅延評価セグメント木
+///
+/// 区間への写像の作用 (区間加算、区間代入など) と、区間の畳み込み (区間和、区間最小値など) を
+/// いずれも `O(log n)` で行うデータ構造。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::lazy_segment_tree::*;
+/// let mut tree = LazySegmentTree::<RangeAddRangeSum>::from_vec(
+///     vec![1, 2, 3, 4, 5].into_iter().map(|v| (v, 1)).collect()
+/// );
+/// assert_eq!(15, tree.fold(..).0);
+///
+/// // [1, 4) に 10 を加算
+/// tree.apply(1..4, 10);
+/// assert_eq!(45, tree.fold(..).0);
+/// assert_eq!(12, tree.get(1).0);
+/// assert_eq!(27, tree.fold(2..4).0);
+///
+/// let mut tree = LazySegmentTree::<RangeAssignRangeMin>::from_vec(vec![5, 3, 8, 1, 9]);
+/// assert_eq!(1, tree.fold(..));
+///
+/// // [2, 5) を 4 に置き換え
+/// tree.apply(2.., Some(4));
+/// assert_eq!(3, tree.fold(..));
+/// assert_eq!(4, tree.fold(2..));
+/// ```
+pub struct LazySegmentTree<F: MapMonoid> {
+    n: usize,
+    size: usize,
+    log: u32,
+    data: Vec<<F::M as Monoid>::Value>,
+    lazy: Vec<F::Map>,
+}
+
+impl <F: MapMonoid> LazySegmentTree<F> {
+    /// 長さ `n` ですべての要素が [`Monoid::leaf()`] のセグメント木を構築します。
+    pub fn new(n: usize) -> Self {
+        Self::new_with(n, F::M::leaf())
+    }
+
+    /// 長さ `n` ですべての要素が `v` のセグメント木を構築します。
+    pub fn new_with(n: usize, v: <F::M as Monoid>::Value) -> Self {
+        Self::from_vec(vec![v; n])
+    }
+
+    /// 値のベクトル `vec` からセグメント木を構築します。
+    pub fn from_vec(vec: Vec<<F::M as Monoid>::Value>) -> Self {
+        let n = vec.len();
+        let size = n.next_power_of_two();
+        let log = size.trailing_zeros();
+        let mut data = vec![F::M::identity(); 2 * size];
+        for (i, v) in vec.into_iter().enumerate() {
+            data[size + i] = v;
+        }
+        let lazy = vec![F::identity_map(); size];
+        let mut tree = LazySegmentTree {
+            n,
+            size,
+            log,
+            data,
+            lazy,
+        };
+        for i in (1..size).rev() {
+            tree.update(i);
+        }
+        tree
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    /// 要素数が 0 の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// `i` 番目(0-based)の値を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds. `i` should be in `[0, len)`
+    pub fn get(&mut self, i: usize) -> <F::M as Monoid>::Value {
+        assert!(i < self.n);
+        let p = i + self.size;
+        for k in (1..=self.log).rev() {
+            self.push(p >> k);
+        }
+        self.data[p].clone()
+    }
+
+    /// `i` 番目(0-based)の値を `v` に変更します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds. `i` should be in `[0, len)`
+    pub fn set(&mut self, i: usize, v: <F::M as Monoid>::Value) {
+        assert!(i < self.n);
+        let p = i + self.size;
+        for k in (1..=self.log).rev() {
+            self.push(p >> k);
+        }
+        self.data[p] = v;
+        for k in 1..=self.log {
+            self.update(p >> k);
+        }
+    }
+
+    /// 区間 `range` の値をモノイドの演算で畳み込んだ結果を返します。
+    ///
+    /// 空の区間に対しては単位元を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds. `range` should be in `[0, len]`
+    pub fn fold<R: RangeBounds<usize>>(&mut self, range: R) -> <F::M as Monoid>::Value {
+        let (l, r) = self.bounds(range);
+        if l == r {
+            return F::M::identity();
+        }
+        let mut l = l + self.size;
+        let mut r = r + self.size;
+        for k in (1..=self.log).rev() {
+            if ((l >> k) << k) != l {
+                self.push(l >> k);
+            }
+            if ((r >> k) << k) != r {
+                self.push((r - 1) >> k);
+            }
+        }
+
+        let mut sml = F::M::identity();
+        let mut smr = F::M::identity();
+        while l < r {
+            if l & 1 == 1 {
+                sml = F::M::op(&sml, &self.data[l]);
+                l += 1;
+            }
+            if r & 1 == 1 {
+                r -= 1;
+                smr = F::M::op(&self.data[r], &smr);
+            }
+            l >>= 1;
+            r >>= 1;
+        }
+        F::M::op(&sml, &smr)
+    }
+
+    /// 区間 `range` の各値に写像 `f` を作用させます。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds. `range` should be in `[0, len]`
+    pub fn apply<R: RangeBounds<usize>>(&mut self, range: R, f: F::Map) {
+        let (l, r) = self.bounds(range);
+        if l == r {
+            return;
+        }
+        let l = l + self.size;
+        let r = r + self.size;
+        for k in (1..=self.log).rev() {
+            if ((l >> k) << k) != l {
+                self.push(l >> k);
+            }
+            if ((r >> k) << k) != r {
+                self.push((r - 1) >> k);
+            }
+        }
+
+        {
+            let mut l = l;
+            let mut r = r;
+            while l < r {
+                if l & 1 == 1 {
+                    self.apply_node(l, &f);
+                    l += 1;
+                }
+                if r & 1 == 1 {
+                    r -= 1;
+                    self.apply_node(r, &f);
+                }
+                l >>= 1;
+                r >>= 1;
+            }
+        }
+
+        for k in 1..=self.log {
+            if ((l >> k) << k) != l {
+                self.update(l >> k);
+            }
+            if ((r >> k) << k) != r {
+                self.update((r - 1) >> k);
+            }
+        }
+    }
+
+    fn bounds<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        let l = match range.start_bound() {
+            Bound::Included(&l) => l,
+            Bound::Excluded(&l) => l + 1,
+            Bound::Unbounded => 0,
+        };
+        let r = match range.end_bound() {
+            Bound::Included(&r) => r + 1,
+            Bound::Excluded(&r) => r,
+            Bound::Unbounded => self.n,
+        };
+        assert!(l <= r && r <= self.n);
+        (l, r)
+    }
+
+    fn update(&mut self, k: usize) {
+        self.data[k] = F::M::op(&self.data[2 * k], &self.data[2 * k + 1]);
+    }
+
+    fn apply_node(&mut self, k: usize, f: &F::Map) {
+        self.data[k] = F::mapping(f, &self.data[k]);
+        if k < self.size {
+            self.lazy[k] = F::composition(f, &self.lazy[k]);
+        }
+    }
+
+    fn push(&mut self, k: usize) {
+        let f = core::mem::replace(&mut self.lazy[k], F::identity_map());
+        self.apply_node(2 * k, &f);
+        self.apply_node(2 * k + 1, &f);
+    }
+}
+
+/// 区間和のモノイド
+///
+/// 値は `(区間和, 区間長)` の組です。区間長は区間加算・区間代入の作用に使われます。
+/// 単位元は `(0, 0)` 、 [`LazySegmentTree::new()`] の各要素は `(0, 1)` です。
+pub struct Sum;
+
+impl Monoid for Sum {
+    type Value = (i64, usize);
+    fn identity() -> Self::Value { (0, 0) }
+    fn op(lhs: &Self::Value, rhs: &Self::Value) -> Self::Value { (lhs.0 + rhs.0, lhs.1 + rhs.1) }
+    fn leaf() -> Self::Value { (0, 1) }
+}
+
+/// 区間最小値のモノイド
+///
+/// 単位元は `i64::MAX` 、 [`LazySegmentTree::new()`] の各要素は `0` です。
+pub struct Min;
+
+impl Monoid for Min {
+    type Value = i64;
+    fn identity() -> Self::Value { i64::MAX }
+    fn op(lhs: &Self::Value, rhs: &Self::Value) -> Self::Value { *lhs.min(rhs) }
+    fn leaf() -> Self::Value { 0 }
+}
+
+/// 区間最大値のモノイド
+///
+/// 単位元は `i64::MIN` 、 [`LazySegmentTree::new()`] の各要素は `0` です。
+pub struct Max;
+
+impl Monoid for Max {
+    type Value = i64;
+    fn identity() -> Self::Value { i64::MIN }
+    fn op(lhs: &Self::Value, rhs: &Self::Value) -> Self::Value { *lhs.max(rhs) }
+    fn leaf() -> Self::Value { 0 }
+}
+
+/// 区間加算・区間和
+pub struct RangeAddRangeSum;
+
+impl MapMonoid for RangeAddRangeSum {
+    type M = Sum;
+    type Map = i64;
+    fn identity_map() -> Self::Map { 0 }
+    fn mapping(f: &Self::Map, x: &(i64, usize)) -> (i64, usize) { (x.0 + f * x.1 as i64, x.1) }
+    fn composition(f: &Self::Map, g: &Self::Map) -> Self::Map { f + g }
+}
+
+/// 区間加算・区間最小値
+pub struct RangeAddRangeMin;
+
+impl MapMonoid for RangeAddRangeMin {
+    type M = Min;
+    type Map = i64;
+    fn identity_map() -> Self::Map { 0 }
+    fn mapping(f: &Self::Map, x: &i64) -> i64 { x + f }
+    fn composition(f: &Self::Map, g: &Self::Map) -> Self::Map { f + g }
+}
+
+/// 区間加算・区間最大値
+pub struct RangeAddRangeMax;
+
+impl MapMonoid for RangeAddRangeMax {
+    type M = Max;
+    type Map = i64;
+    fn identity_map() -> Self::Map { 0 }
+    fn mapping(f: &Self::Map, x: &i64) -> i64 { x + f }
+    fn composition(f: &Self::Map, g: &Self::Map) -> Self::Map { f + g }
+}
+
+/// 区間代入・区間和
+///
+/// 写像 `None` は恒等写像、 `Some(v)` は値 `v` への代入を表します。
+pub struct RangeAssignRangeSum;
+
+impl MapMonoid for RangeAssignRangeSum {
+    type M = Sum;
+    type Map = Option<i64>;
+    fn identity_map() -> Self::Map { None }
+    fn mapping(f: &Self::Map, x: &(i64, usize)) -> (i64, usize) {
+        match f {
+            Some(v) => (v * x.1 as i64, x.1),
+            None => *x,
+        }
+    }
+    fn composition(f: &Self::Map, g: &Self::Map) -> Self::Map { f.or(*g) }
+}
+
+/// 区間代入・区間最小値
+///
+/// 写像 `None` は恒等写像、 `Some(v)` は値 `v` への代入を表します。
+pub struct RangeAssignRangeMin;
+
+impl MapMonoid for RangeAssignRangeMin {
+    type M = Min;
+    type Map = Option<i64>;
+    fn identity_map() -> Self::Map { None }
+    fn mapping(f: &Self::Map, x: &i64) -> i64 { f.unwrap_or(*x) }
+    fn composition(f: &Self::Map, g: &Self::Map) -> Self::Map { f.or(*g) }
+}
+
+/// 区間代入・区間最大値
+///
+/// 写像 `None` は恒等写像、 `Some(v)` は値 `v` への代入を表します。
+pub struct RangeAssignRangeMax;
+
+impl MapMonoid for RangeAssignRangeMax {
+    type M = Max;
+    type Map = Option<i64>;
+    fn identity_map() -> Self::Map { None }
+    fn mapping(f: &Self::Map, x: &i64) -> i64 { f.unwrap_or(*x) }
+    fn composition(f: &Self::Map, g: &Self::Map) -> Self::Map { f.or(*g) }
+}
+
+impl <F: MapMonoid> SpaceUsage for LazySegmentTree<F> where <F::M as Monoid>::Value: SpaceUsage, F::Map: SpaceUsage {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("data", &self.data), ("lazy", &self.lazy)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn range_add_range_sum() {
+        let len = 100;
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut expected: Vec<i64> = (0..len).map(|_| rng.gen_range(-100, 100)).collect();
+        let mut tree = LazySegmentTree::<RangeAddRangeSum>::from_vec(
+            expected.iter().map(|v| (*v, 1)).collect()
+        );
+
+        for _ in 0..1000 {
+            let l = rng.gen_range(0, len + 1);
+            let r = rng.gen_range(l, len + 1);
+            if rng.gen() {
+                let v = rng.gen_range(-100, 100);
+                expected[l..r].iter_mut().for_each(|x| *x += v);
+                tree.apply(l..r, v);
+            } else {
+                assert_eq!(expected[l..r].iter().sum::<i64>(), tree.fold(l..r).0);
+            }
+        }
+        for (i, v) in expected.iter().enumerate() {
+            assert_eq!(*v, tree.get(i).0);
+        }
+    }
+
+    #[test]
+    fn range_assign_range_min() {
+        let len = 100;
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut expected: Vec<i64> = (0..len).map(|_| rng.gen_range(-100, 100)).collect();
+        let mut tree = LazySegmentTree::<RangeAssignRangeMin>::from_vec(expected.clone());
+
+        for _ in 0..1000 {
+            let l = rng.gen_range(0, len + 1);
+            let r = rng.gen_range(l, len + 1);
+            if rng.gen() {
+                let v = rng.gen_range(-100, 100);
+                expected[l..r].iter_mut().for_each(|x| *x = v);
+                tree.apply(l..r, Some(v));
+            } else {
+                let min = expected[l..r].iter().copied().min().unwrap_or(i64::MAX);
+                assert_eq!(min, tree.fold(l..r));
+            }
+        }
+    }
+
+    #[test]
+    fn set_get() {
+        let mut tree = LazySegmentTree::<RangeAddRangeMax>::new(5);
+        assert_eq!(5, tree.len());
+        assert_eq!(0, tree.fold(..));
+        tree.set(1, 3);
+        tree.set(3, 7);
+        tree.apply(..=2, 10);
+        assert_eq!(13, tree.get(1));
+        assert_eq!(13, tree.fold(..));
+        assert_eq!(10, tree.fold(2..));
+        tree.apply(.., -10);
+        assert_eq!(3, tree.get(1));
+        assert_eq!(-3, tree.get(3));
+        assert_eq!(-10, tree.get(4));
+    }
+
+    #[test]
+    fn new_apply() {
+        let mut tree = LazySegmentTree::<RangeAddRangeSum>::new(5);
+        assert_eq!((0, 5), tree.fold(..));
+        tree.apply(0..5, 10);
+        assert_eq!((50, 5), tree.fold(..));
+        tree.apply(1..3, -1);
+        assert_eq!((18, 2), tree.fold(1..3));
+        assert_eq!((10, 1), tree.get(4));
+
+        let mut tree = LazySegmentTree::<RangeAssignRangeSum>::new(4);
+        tree.apply(.., Some(3));
+        tree.apply(2.., Some(-1));
+        assert_eq!((4, 4), tree.fold(..));
+
+        let mut tree = LazySegmentTree::<RangeAddRangeMin>::new(5);
+        assert_eq!(0, tree.fold(..));
+        tree.apply(1..4, 3);
+        assert_eq!(0, tree.fold(..));
+        assert_eq!(3, tree.fold(1..4));
+        tree.apply(.., -5);
+        assert_eq!(-5, tree.fold(..));
+        assert_eq!(-2, tree.fold(2..3));
+
+        let mut tree = LazySegmentTree::<RangeAddRangeMax>::new(5);
+        tree.apply(1..4, -3);
+        assert_eq!(0, tree.fold(..));
+        assert_eq!(-3, tree.fold(1..4));
+        tree.apply(.., 5);
+        assert_eq!(5, tree.fold(..));
+
+        let mut tree = LazySegmentTree::<RangeAddRangeMin>::new_with(3, 2);
+        tree.apply(1.., 5);
+        assert_eq!(2, tree.fold(..));
+        assert_eq!(7, tree.fold(1..));
+
+        // the identity is a value like any other
+        let mut tree = LazySegmentTree::<RangeAddRangeMin>::new_with(3, i64::MAX);
+        tree.apply(1.., -1);
+        assert_eq!(i64::MAX - 1, tree.fold(..));
+        assert_eq!(i64::MAX, tree.get(0));
+    }
+
+    #[test]
+    fn empty() {
+        let mut tree = LazySegmentTree::<RangeAssignRangeSum>::new(0);
+        assert!(tree.is_empty());
+        assert_eq!((0, 0), tree.fold(..));
+        tree.apply(.., Some(1));
+        assert_eq!((0, 0), tree.fold(..));
+    }
+}
@@ -0,0 +1,282 @@
+use super::hash::HashMap;
+use crate::space::SpaceUsage;
+
+use alloc::vec::Vec;
+use alloc::vec;
+use core::hash::Hash;
+
+const NIL: usize = usize::MAX;
+
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    prev: usize,
+    next: usize,
+}
+
+/// LRU キャッシュ
+///
+/// 容量を超えたときに、最も長い間参照されていない要素を追い出すキャッシュ。
+/// ハッシュテーブルと双方向連結リストの組み合わせにより、参照・追加・削除をいずれも `O(1)` で行います。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::lru::LruCache;
+/// let mut cache = LruCache::new(2);
+/// cache.put("a", 1);
+/// cache.put("b", 2);
+/// assert_eq!(Some(&1), cache.get(&"a"));
+///
+/// // "b" が最も長い間参照されていないため追い出されます。
+/// cache.put("c", 3);
+/// assert_eq!(None, cache.get(&"b"));
+/// assert_eq!(2, cache.len());
+///
+/// // iter は最近参照された順に要素を返します。
+/// assert_eq!(vec![(&"c", &3), (&"a", &1)], cache.iter().collect::<Vec<_>>());
+/// ```
+pub struct LruCache<K, V> {
+    capacity: usize,
+    map: HashMap<K, usize>,
+    entries: Vec<Entry<K, V>>,
+    head: usize,
+    tail: usize,
+}
+
+impl <K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// 容量 `capacity` の空のキャッシュを構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        LruCache {
+            capacity,
+            map: HashMap::with_capacity_and_hasher(capacity, Default::default()),
+            entries: Vec::with_capacity(capacity),
+            head: NIL,
+            tail: NIL,
+        }
+    }
+
+    /// 容量を返します。
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// キー `key` に対応付けられた値を参照し、その要素を最近参照されたものとして扱います。
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let i = *self.map.get(key)?;
+        self.move_to_front(i);
+        Some(&self.entries[i].value)
+    }
+
+    /// キー `key` に対応付けられた値を可変参照し、その要素を最近参照されたものとして扱います。
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let i = *self.map.get(key)?;
+        self.move_to_front(i);
+        Some(&mut self.entries[i].value)
+    }
+
+    /// キー `key` に対応付けられた値を、参照順序を変えずに参照します。
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.map.get(key).map(|i| &self.entries[*i].value)
+    }
+
+    /// キー `key` が存在する場合に、 `true` を返します。参照順序は変わりません。
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// キー `key` に値 `value` を対応付け、最近参照されたものとして扱います。
+    ///
+    /// すでにキーが存在した場合、値を置き換えて古い値を返します。
+    /// 容量を超える場合は、最も長い間参照されていない要素を追い出します。
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(i) = self.map.get(&key) {
+            let i = *i;
+            self.move_to_front(i);
+            return Some(core::mem::replace(&mut self.entries[i].value, value));
+        }
+        if self.len() == self.capacity {
+            self.pop_lru();
+        }
+        let i = self.entries.len();
+        self.entries.push(Entry { key: key.clone(), value, prev: NIL, next: NIL });
+        self.map.insert(key, i);
+        self.push_front(i);
+        None
+    }
+
+    /// キー `key` の要素を取り除き、その値を返します。
+    pub fn pop(&mut self, key: &K) -> Option<V> {
+        let i = self.map.remove(key)?;
+        Some(self.remove_entry(i).1)
+    }
+
+    /// 最も長い間参照されていない要素を取り除き、そのキーと値を返します。
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        if self.tail == NIL {
+            return None;
+        }
+        let i = self.tail;
+        self.map.remove(&self.entries[i].key);
+        Some(self.remove_entry(i))
+    }
+
+    /// すべての要素を取り除きます。
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.entries.clear();
+        self.head = NIL;
+        self.tail = NIL;
+    }
+
+    /// キーと値の組を、最近参照された順に返すイテレータを返します。
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        let mut cur = self.head;
+        core::iter::from_fn(move || {
+            if cur == NIL {
+                return None;
+            }
+            let e = &self.entries[cur];
+            cur = e.next;
+            Some((&e.key, &e.value))
+        })
+    }
+
+    fn unlink(&mut self, i: usize) {
+        let (prev, next) = (self.entries[i].prev, self.entries[i].next);
+        if prev == NIL { self.head = next; } else { self.entries[prev].next = next; }
+        if next == NIL { self.tail = prev; } else { self.entries[next].prev = prev; }
+    }
+
+    fn push_front(&mut self, i: usize) {
+        self.entries[i].prev = NIL;
+        self.entries[i].next = self.head;
+        if self.head != NIL {
+            self.entries[self.head].prev = i;
+        }
+        self.head = i;
+        if self.tail == NIL {
+            self.tail = i;
+        }
+    }
+
+    fn move_to_front(&mut self, i: usize) {
+        if self.head != i {
+            self.unlink(i);
+            self.push_front(i);
+        }
+    }
+
+    // remove the entry at `i` from the list and the slab (the map must be updated by the caller)
+    fn remove_entry(&mut self, i: usize) -> (K, V) {
+        self.unlink(i);
+        let last = self.entries.len() - 1;
+        if i != last {
+            // the last entry moves to `i`
+            let (prev, next) = (self.entries[last].prev, self.entries[last].next);
+            if prev == NIL { self.head = i; } else { self.entries[prev].next = i; }
+            if next == NIL { self.tail = i; } else { self.entries[next].prev = i; }
+            *self.map.get_mut(&self.entries[last].key).unwrap() = i;
+        }
+        let e = self.entries.swap_remove(i);
+        (e.key, e.value)
+    }
+}
+
+impl <K: SpaceUsage, V: SpaceUsage> SpaceUsage for Entry<K, V> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("key", &self.key), ("value", &self.value)]
+    }
+}
+
+impl <K: SpaceUsage, V: SpaceUsage> SpaceUsage for LruCache<K, V> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("map", &self.map), ("entries", &self.entries)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn put_get() {
+        let mut cache = LruCache::new(3);
+        assert!(cache.is_empty());
+        assert_eq!(None, cache.put(1, "a"));
+        assert_eq!(None, cache.put(2, "b"));
+        assert_eq!(None, cache.put(3, "c"));
+        assert_eq!(Some("a"), cache.put(1, "A"));
+        assert_eq!(None, cache.put(4, "d"));
+        assert_eq!(3, cache.len());
+        assert!(!cache.contains_key(&2));
+        assert_eq!(Some(&"c"), cache.peek(&3));
+        assert_eq!(vec![4, 1, 3], cache.iter().map(|(k, _)| *k).collect::<Vec<_>>());
+
+        *cache.get_mut(&3).unwrap() = "C";
+        assert_eq!(vec![(&3, &"C"), (&4, &"d"), (&1, &"A")], cache.iter().collect::<Vec<_>>());
+        assert_eq!(Some((1, "A")), cache.pop_lru());
+        assert_eq!(Some("d"), cache.pop(&4));
+        assert_eq!(None, cache.pop(&4));
+        assert_eq!(1, cache.len());
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(None, cache.pop_lru());
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let capacity = 10;
+        let mut cache = LruCache::new(capacity);
+        // most recently used first
+        let mut expected: VecDeque<(u32, u32)> = VecDeque::new();
+        for _ in 0..10000 {
+            let k = rng.gen_range(0, 20);
+            let pos = expected.iter().position(|(x, _)| *x == k);
+            match rng.gen_range(0, 3) {
+                0 => {
+                    let e = pos.map(|p| expected.remove(p).unwrap());
+                    if let Some(e) = e {
+                        expected.push_front(e);
+                    }
+                    assert_eq!(e.map(|(_, v)| v), cache.get(&k).copied());
+                }
+                1 => {
+                    let e = pos.map(|p| expected.remove(p).unwrap());
+                    assert_eq!(e.map(|(_, v)| v), cache.pop(&k));
+                }
+                _ => {
+                    let v = rng.gen();
+                    let e = pos.map(|p| expected.remove(p).unwrap());
+                    expected.push_front((k, v));
+                    expected.truncate(capacity);
+                    assert_eq!(e.map(|(_, v)| v), cache.put(k, v));
+                }
+            }
+            assert_eq!(
+                expected.iter().map(|(k, v)| (k, v)).collect::<Vec<_>>(),
+                cache.iter().collect::<Vec<_>>()
+            );
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use crate::space::SpaceUsage;
+
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ord;
+use core::cmp::Ordering;
+use core::cmp::Ordering::Less;
+
+/// 単調両端キュー
+///
+/// 追加された値のうち、今後最小値になりうるものだけを単調に並べて保持する両端キュー。
+/// スライディングウィンドウの最小値 (最大値) を償却 `O(1)` で求めます。
+///
+/// 追加された値には 0 から順に番号が振られ、 [`Self::pop_expired()`] で番号がウィンドウの開始位置より
+/// 小さい値を取り除きます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::monotonic_deque::MonotonicDeque;
+/// let mut deque = MonotonicDeque::new();
+/// for v in [3, 1, 4, 1, 5] {
+///     deque.push(v);
+/// }
+/// assert_eq!(Some(&1), deque.current_extreme());
+///
+/// // 番号 4 より前の値を取り除きます。
+/// deque.pop_expired(4);
+/// assert_eq!(Some(&5), deque.current_extreme());
+/// ```
+pub struct MonotonicDeque<T> {
+    deque: VecDeque<(usize, T)>,
+    next_index: usize,
+    compare: fn(lhs: &T, rhs: &T) -> Ordering,
+}
+
+impl <T: Ord> MonotonicDeque<T> {
+    /// 最小値を求める空の両端キューを構築します。
+    ///
+    /// 比較には [`std::cmp::Ord::cmp()`] が使われます。
+    pub fn new() -> Self {
+        Self::with_compare(Ord::cmp)
+    }
+}
+
+impl <T: Ord> Default for MonotonicDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl <T> MonotonicDeque<T> {
+    /// 空の両端キューを構築します。
+    ///
+    /// 比較には与えられた関数が使われ、比較結果が最も小さい値を求めます。
+    pub fn with_compare(compare: fn(lhs: &T, rhs: &T) -> Ordering) -> Self {
+        MonotonicDeque {
+            deque: VecDeque::new(),
+            next_index: 0,
+            compare,
+        }
+    }
+
+    /// 値を追加し、その値に振られた番号を返します。
+    pub fn push(&mut self, value: T) -> usize {
+        while let Some((_, back)) = self.deque.back() {
+            if (self.compare)(back, &value) == Less {
+                break;
+            }
+            self.deque.pop_back();
+        }
+        let index = self.next_index;
+        self.deque.push_back((index, value));
+        self.next_index += 1;
+        index
+    }
+
+    /// 番号が `window_start` より小さい値を取り除きます。
+    pub fn pop_expired(&mut self, window_start: usize) {
+        while let Some((index, _)) = self.deque.front() {
+            if *index >= window_start {
+                break;
+            }
+            self.deque.pop_front();
+        }
+    }
+
+    /// 保持している値のうち、最も小さい値を参照します。空の場合、 `None` を返します。
+    pub fn current_extreme(&self) -> Option<&T> {
+        self.deque.front().map(|(_, v)| v)
+    }
+
+    /// 次に追加される値に振られる番号を返します。
+    pub fn next_index(&self) -> usize {
+        self.next_index
+    }
+
+    /// 保持している値の個数を返します。
+    pub fn len(&self) -> usize {
+        self.deque.len()
+    }
+
+    /// 保持している値がない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.deque.is_empty()
+    }
+}
+
+/// [`sliding_min()`], [`sliding_max()`] の返すイテレータ
+pub struct SlidingWindow<I: Iterator> {
+    iter: I,
+    width: usize,
+    deque: MonotonicDeque<I::Item>,
+}
+
+impl <I: Iterator> Iterator for SlidingWindow<I> where I::Item: Clone {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let index = self.deque.push(self.iter.next()?);
+            if index + 1 >= self.width {
+                self.deque.pop_expired(index + 1 - self.width);
+                return self.deque.current_extreme().cloned();
+            }
+        }
+    }
+}
+
+/// 幅 `width` のスライディングウィンドウの最小値を、先頭のウィンドウから順に返すイテレータを返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::monotonic_deque::sliding_min;
+/// assert_eq!(vec![1, 1, 1, 1, 2], sliding_min(vec![3, 1, 4, 1, 5, 9, 2], 3).collect::<Vec<i32>>());
+/// ```
+///
+/// # Panics
+///
+/// Panics if `width` is zero.
+pub fn sliding_min<I: IntoIterator>(iter: I, width: usize) -> SlidingWindow<I::IntoIter> where I::Item: Ord {
+    assert!(width > 0);
+    SlidingWindow { iter: iter.into_iter(), width, deque: MonotonicDeque::new() }
+}
+
+/// 幅 `width` のスライディングウィンドウの最大値を、先頭のウィンドウから順に返すイテレータを返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::monotonic_deque::sliding_max;
+/// assert_eq!(vec![4, 4, 5, 9, 9], sliding_max(vec![3, 1, 4, 1, 5, 9, 2], 3).collect::<Vec<i32>>());
+/// ```
+///
+/// # Panics
+///
+/// Panics if `width` is zero.
+pub fn sliding_max<I: IntoIterator>(iter: I, width: usize) -> SlidingWindow<I::IntoIter> where I::Item: Ord {
+    assert!(width > 0);
+    SlidingWindow { iter: iter.into_iter(), width, deque: MonotonicDeque::with_compare(|lhs, rhs| rhs.cmp(lhs)) }
+}
+
+impl <T: SpaceUsage> SpaceUsage for MonotonicDeque<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("deque", &self.deque)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn push_pop() {
+        let mut deque = MonotonicDeque::with_compare(|lhs: &i32, rhs| rhs.cmp(lhs));
+        assert!(deque.is_empty());
+        assert_eq!(None, deque.current_extreme());
+        assert_eq!(0, deque.push(2));
+        assert_eq!(1, deque.push(5));
+        assert_eq!(2, deque.push(3));
+        assert_eq!(Some(&5), deque.current_extreme());
+        assert_eq!(2, deque.len());
+        deque.pop_expired(2);
+        assert_eq!(Some(&3), deque.current_extreme());
+        deque.pop_expired(3);
+        assert!(deque.is_empty());
+        assert_eq!(3, deque.next_index());
+    }
+
+    #[test]
+    fn sliding() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let vec: Vec<u32> = (0..1000).map(|_| rng.gen_range(0, 100)).collect();
+        for width in [1, 2, 10, 999, 1000, 1001] {
+            let mins: Vec<u32> = vec.windows(width).map(|w| *w.iter().min().unwrap()).collect();
+            let maxs: Vec<u32> = vec.windows(width).map(|w| *w.iter().max().unwrap()).collect();
+            assert_eq!(mins, sliding_min(vec.iter().copied(), width).collect::<Vec<_>>());
+            assert_eq!(maxs, sliding_max(vec.iter().copied(), width).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use super::hash::HashMap;
+use super::hash::hash_with_seed;
+use crate::bits::fid::FID;
+use crate::bits::fid::NaiveFID;
+use crate::space::SpaceUsage;
+
+use alloc::vec::Vec;
+use alloc::vec;
+use core::hash::Hash;
+
+const MAX_LEVELS: usize = 32;
+const FALLBACK_SEED: u64 = u64::MAX;
+
+/// 最小完全ハッシュ関数 (BBHash)
+///
+/// 静的なキー集合の `n` 個のキーそれぞれに、 `[0, n)` の互いに異なる番号を割り当てるハッシュ関数。
+/// キーそのものは保持せず、 `gamma = 1` のときキーあたり 3 ビット強の領域で表現されます。
+///
+/// 各レベルで残っているキーを `gamma * (残りのキー数)` ビットの配列にハッシュし、
+/// 衝突しなかったキーのビットを立て、衝突したキーを次のレベルに回します。
+/// 番号はビットを立てた位置の rank として求まります。
+///
+/// キー集合に含まれないキーに対しては、任意の番号または `None` を返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::mphf::NaiveMphf;
+/// let keys = vec!["apple", "banana", "cherry", "durian"];
+/// let mphf = NaiveMphf::new(&keys);
+/// assert_eq!(4, mphf.len());
+///
+/// let mut ids: Vec<usize> = keys.iter().map(|k| mphf.get(k).unwrap()).collect();
+/// ids.sort();
+/// assert_eq!(vec![0, 1, 2, 3], ids);
+/// ```
+pub struct Mphf<T: FID> {
+    n: usize,
+    // the bit arrays of all levels concatenated
+    bits: T,
+    // the start position of each level in `bits`, and the end of the last level
+    offsets: Vec<usize>,
+    // keys which could not be placed in any level
+    fallback: HashMap<u64, usize>,
+}
+
+pub type NaiveMphf = Mphf<NaiveFID>;
+
+impl <T: FID> Mphf<T> {
+    /// 互いに異なるキーの列 `keys` から、 `gamma = 1` の最小完全ハッシュ関数を構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keys` contains duplicates.
+    pub fn new<K: Hash>(keys: &[K]) -> Self {
+        Self::with_gamma(keys, 1.0)
+    }
+
+    /// 互いに異なるキーの列 `keys` から、最小完全ハッシュ関数を構築します。
+    ///
+    /// `gamma` を大きくすると、構築と検索が速くなる代わりに領域が増えます。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gamma < 1.0` or `keys` contains duplicates.
+    pub fn with_gamma<K: Hash>(keys: &[K], gamma: f64) -> Self {
+        assert!(gamma >= 1.0);
+        let mut bits = vec![];
+        let mut offsets = vec![0];
+        let mut rest: Vec<&K> = keys.iter().collect();
+        for level in 0..MAX_LEVELS {
+            if rest.is_empty() {
+                break;
+            }
+            // the ceiling without std
+            let scaled = rest.len() as f64 * gamma;
+            let size = (scaled as usize + ((scaled as usize as f64) < scaled) as usize).max(64);
+            let mut placed = vec![false; size];
+            let mut collided = vec![false; size];
+            for key in &rest {
+                let i = (hash_with_seed(*key, level as u64) % size as u64) as usize;
+                collided[i] |= placed[i];
+                placed[i] = true;
+            }
+            rest.retain(|key| {
+                let i = (hash_with_seed(*key, level as u64) % size as u64) as usize;
+                collided[i]
+            });
+            for (p, c) in placed.iter_mut().zip(collided.iter()) {
+                *p &= !c;
+            }
+            bits.extend(placed);
+            offsets.push(bits.len());
+        }
+
+        let placed_count = keys.len() - rest.len();
+        let mut fallback = HashMap::default();
+        for (i, key) in rest.iter().enumerate() {
+            let prev = fallback.insert(hash_with_seed(*key, FALLBACK_SEED), placed_count + i);
+            assert!(prev.is_none(), "duplicate keys");
+        }
+        Mphf { n: keys.len(), bits: T::from_bool_vec(&bits), offsets, fallback }
+    }
+
+    /// キーの個数を返します。
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    /// キーがない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// キー `key` に割り当てられた番号を返します。
+    ///
+    /// `key` がキー集合に含まれない場合、任意の番号または `None` を返します。
+    pub fn get<K: Hash + ?Sized>(&self, key: &K) -> Option<usize> {
+        for level in 0..self.offsets.len() - 1 {
+            let (s, e) = (self.offsets[level], self.offsets[level + 1]);
+            let i = s + (hash_with_seed(key, level as u64) % (e - s) as u64) as usize;
+            if self.bits.get(i) {
+                return Some(self.bits.rank1(i));
+            }
+        }
+        self.fallback.get(&hash_with_seed(key, FALLBACK_SEED)).copied()
+    }
+
+    /// 1 キーあたりのビット数を返します。
+    pub fn bits_per_key(&self) -> f64 {
+        self.bits.len() as f64 / self.n.max(1) as f64
+    }
+}
+
+impl <T: FID + SpaceUsage> SpaceUsage for Mphf<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("bits", &self.bits), ("offsets", &self.offsets), ("fallback", &self.fallback)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimal_perfect() {
+        for n in [0, 1, 10, 1000, 20000] {
+            let keys: Vec<String> = (0..n).map(|i| format!("key{}", i)).collect();
+            let mphf = NaiveMphf::new(&keys);
+            assert_eq!(n, mphf.len());
+            let mut ids: Vec<usize> = keys.iter().map(|k| mphf.get(k.as_str()).unwrap()).collect();
+            ids.sort();
+            assert_eq!((0..n).collect::<Vec<_>>(), ids);
+            if n >= 1000 {
+                assert!(mphf.bits_per_key() < 4.0, "bits_per_key = {}", mphf.bits_per_key());
+            }
+            for i in n..n + 100 {
+                assert!(mphf.get(&format!("key{}", i)).is_none_or(|id| id < n));
+            }
+        }
+    }
+
+    #[test]
+    fn gamma() {
+        let keys: Vec<u64> = (0..5000).collect();
+        let mphf = NaiveMphf::with_gamma(&keys, 2.0);
+        let mut ids: Vec<usize> = keys.iter().map(|k| mphf.get(k).unwrap()).collect();
+        ids.sort();
+        assert_eq!((0..5000).collect::<Vec<_>>(), ids);
+    }
+
+    #[test]
+    #[should_panic]
+    fn duplicates() {
+        NaiveMphf::new(&[1, 2, 1]);
+    }
+}
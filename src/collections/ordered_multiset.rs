@@ -0,0 +1,231 @@
+use crate::collections::arena::BoxNodes;
+use crate::collections::treap::Augment;
+use crate::collections::treap::NodeRef;
+use crate::collections::treap::Treap;
+use crate::random::DefaultRng;
+use crate::space::SpaceUsage;
+
+use alloc::vec::Vec;
+use alloc::vec;
+
+use rand::RngCore;
+
+// the total multiplicity in the subtree
+struct Count(usize);
+
+impl <T> Augment<T, usize> for Count {
+    fn summarize(_: &T, count: &usize, left: Option<&Self>, right: Option<&Self>) -> Self {
+        Count(count + left.map_or(0, |c| c.0) + right.map_or(0, |c| c.0))
+    }
+}
+
+type Node<'a, T> = NodeRef<'a, T, usize, BoxNodes, Count>;
+
+fn total<T>(node: Option<Node<'_, T>>) -> usize {
+    node.map_or(0, |n| n.summary().0)
+}
+
+/// 順序付き多重集合
+///
+/// 重複を許す値の集合を、値ごとの個数を値に、部分木の個数の合計を要約 ([`Augment`]) に持たせた [`Treap`] で保持します。
+/// 追加・削除に加えて、ある値より小さい値の個数や `k` 番目に小さい値を期待値 `O(log n)` で求めます。
+/// 値の追加・削除を伴う区間の分位点 (動的な分位点) の計算に使います。
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use rust_study::collections::ordered_multiset::OrderedMultiset;
+/// let mut set = OrderedMultiset::new();
+/// for v in [3, 1, 4, 1, 5] {
+///     set.insert(v);
+/// }
+/// assert_eq!(5, set.len());
+/// assert_eq!(2, set.count(&1));
+///
+/// // count_less は値より小さい値の個数を返します。
+/// assert_eq!(3, set.count_less(&4));
+///
+/// // kth は k 番目(0-based)に小さい値を返します。
+/// assert_eq!(Some(&1), set.kth(1));
+/// assert_eq!(Some(&3), set.kth(2));
+///
+/// assert!(set.remove_one(&1));
+/// assert_eq!(vec![1, 3, 4, 5], set.iter().copied().collect::<Vec<_>>());
+/// # }
+/// ```
+///
+/// 優先度は、まだ含まれていない値の追加のときだけ型引数 `R` の乱数生成器から引きます。
+/// 乱数生成器の役割は [`Treap`] と同じです。
+pub struct OrderedMultiset<T, R = DefaultRng> {
+    treap: Treap<T, usize, R, BoxNodes, Count>,
+}
+
+#[cfg(feature = "std")]
+impl <T: Ord> OrderedMultiset<T> {
+    /// 空の多重集合を構築します。
+    pub fn new() -> Self {
+        Self::with_rng(DefaultRng)
+    }
+}
+
+impl <T: Ord, R: RngCore> OrderedMultiset<T, R> {
+    /// Treap の優先度を乱数生成器 `rng` で選ぶ、空の多重集合を構築します。
+    pub fn with_rng(rng: R) -> Self {
+        OrderedMultiset { treap: Treap::with_storage(rng) }
+    }
+
+    /// 重複を含めた要素数を返します。
+    pub fn len(&self) -> usize {
+        total(self.treap.root())
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.treap.is_empty()
+    }
+
+    /// 値 `value` を 1 つ追加します。
+    pub fn insert(&mut self, value: T) {
+        if self.treap.modify(&value, |count| *count += 1).is_none() {
+            self.treap.insert(value, 1);
+        }
+    }
+
+    /// 値 `value` を 1 つ取り除きます。値が含まれていた場合に、 `true` を返します。
+    pub fn remove_one(&mut self, value: &T) -> bool {
+        match self.treap.modify(value, |count| { *count -= 1; *count }) {
+            None => false,
+            Some(0) => {
+                self.treap.remove(value);
+                true
+            }
+            Some(_) => true,
+        }
+    }
+
+    /// 値 `value` の個数を返します。
+    pub fn count(&self, value: &T) -> usize {
+        self.treap.get(value).copied().unwrap_or(0)
+    }
+
+    /// 値 `value` が含まれる場合に、 `true` を返します。
+    pub fn contains(&self, value: &T) -> bool {
+        self.treap.contains_key(value)
+    }
+
+    /// 値 `value` より小さい値の個数を、重複を含めて返します。
+    pub fn count_less(&self, value: &T) -> usize {
+        let mut count = 0;
+        let mut node = self.treap.root();
+        while let Some(n) = node {
+            if n.key() < value {
+                count += total(n.left()) + n.value();
+                node = n.right();
+            } else {
+                node = n.left();
+            }
+        }
+        count
+    }
+
+    /// 重複を含めて `k` 番目(0-based)に小さい値を返します。 `k` が要素数以上の場合、 `None` を返します。
+    pub fn kth(&self, mut k: usize) -> Option<&T> {
+        let mut node = self.treap.root();
+        while let Some(n) = node {
+            let left = total(n.left());
+            if k < left {
+                node = n.left();
+            } else if k < left + n.value() {
+                return Some(n.key());
+            } else {
+                k -= left + n.value();
+                node = n.right();
+            }
+        }
+        None
+    }
+
+    /// 最小の値を返します。
+    pub fn min(&self) -> Option<&T> {
+        self.kth(0)
+    }
+
+    /// 最大の値を返します。
+    pub fn max(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|k| self.kth(k))
+    }
+
+    /// 値を重複を含めて小さい順に返すイテレータを返します。
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.treap.iter().flat_map(|(value, count)| core::iter::repeat_n(value, *count))
+    }
+}
+
+#[cfg(feature = "std")]
+impl <T: Ord> Default for OrderedMultiset<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpaceUsage for Count {}
+
+impl <T: SpaceUsage, R> SpaceUsage for OrderedMultiset<T, R> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("treap", &self.treap)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut set = OrderedMultiset::with_rng(StdRng::seed_from_u64(1));
+        let mut expected: Vec<u32> = vec![];
+        for _ in 0..3000 {
+            let v = rng.gen_range(0, 100);
+            if rng.gen_range(0, 3) == 0 {
+                let pos = expected.iter().position(|x| *x == v);
+                if let Some(p) = pos {
+                    expected.remove(p);
+                }
+                assert_eq!(pos.is_some(), set.remove_one(&v));
+            } else {
+                let p = expected.partition_point(|x| *x < v);
+                expected.insert(p, v);
+                set.insert(v);
+            }
+            assert_eq!(expected.len(), set.len());
+            assert_eq!(expected.partition_point(|x| *x < v), set.count_less(&v));
+            assert_eq!(expected.iter().filter(|x| **x == v).count(), set.count(&v));
+            assert_eq!(expected.contains(&v), set.contains(&v));
+            let k = rng.gen_range(0, expected.len() + 2);
+            assert_eq!(expected.get(k), set.kth(k));
+            assert_eq!(expected.first(), set.min());
+            assert_eq!(expected.last(), set.max());
+        }
+        assert_eq!(expected.iter().collect::<Vec<_>>(), set.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn with_rng() {
+        let build = |seed| {
+            let mut set = OrderedMultiset::with_rng(StdRng::seed_from_u64(seed));
+            for v in 0..100 {
+                set.insert(v % 30);
+            }
+            set
+        };
+        let (a, b) = (build(1), build(1));
+        assert_eq!(a.treap.root().map(|n| n.key()), b.treap.root().map(|n| n.key()));
+        assert_eq!(a.iter().collect::<Vec<_>>(), b.iter().collect::<Vec<_>>());
+        assert_eq!(Some(&2), a.kth(10));
+    }
+}
@@ -0,0 +1,500 @@
+use crate::collections::arena::NodeStorage;
+use crate::collections::treap::Treap;
+use crate::random::DefaultRng;
+use crate::space::SpaceUsage;
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use alloc::vec;
+
+use rand::RngCore;
+
+const BITS: usize = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+enum VecNode<T> {
+    Leaf(Vec<T>),
+    Branch(Vec<Rc<VecNode<T>>>),
+}
+
+/// 永続ベクタ
+///
+/// 32 分木のトライ (bit-partitioned trie) で要素を保持する不変なベクタ。
+/// 更新操作は自身を変更せず、変更した経路のノードだけを複製した新しい版を `O(log n)` で返します。
+/// 複製されなかったノードは古い版と共有されるため、多数の版を安価に保持できます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::persistent::PersistentVec;
+/// let v0 = PersistentVec::new();
+/// let v1 = v0.push(1).push(2).push(3);
+/// let v2 = v1.set(1, 20);
+/// let v3 = v2.pop().unwrap();
+///
+/// // 古い版はそのまま残ります。
+/// assert_eq!(0, v0.len());
+/// assert_eq!(vec![1, 2, 3], v1.iter().copied().collect::<Vec<i32>>());
+/// assert_eq!(vec![1, 20, 3], v2.iter().copied().collect::<Vec<i32>>());
+/// assert_eq!(vec![1, 20], v3.iter().copied().collect::<Vec<i32>>());
+/// ```
+pub struct PersistentVec<T> {
+    root: Option<Rc<VecNode<T>>>,
+    // the shift for the index of the root's children
+    shift: usize,
+    len: usize,
+}
+
+impl <T> Clone for PersistentVec<T> {
+    fn clone(&self) -> Self {
+        PersistentVec { root: self.root.clone(), shift: self.shift, len: self.len }
+    }
+}
+
+impl <T: Clone> PersistentVec<T> {
+    /// 空のベクタを構築します。
+    pub fn new() -> Self {
+        PersistentVec { root: None, shift: 0, len: 0 }
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// `i` 番目(0-based)の要素を参照します。範囲外の場合、 `None` を返します。
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i >= self.len {
+            return None;
+        }
+        let mut node = self.root.as_ref()?;
+        let mut shift = self.shift;
+        loop {
+            match node.as_ref() {
+                VecNode::Branch(children) => {
+                    node = &children[(i >> shift) & MASK];
+                    shift -= BITS;
+                }
+                VecNode::Leaf(values) => return values.get(i & MASK),
+            }
+        }
+    }
+
+    /// 末尾の要素を参照します。
+    pub fn last(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    /// 末尾に要素 `value` を追加した版を返します。
+    pub fn push(&self, value: T) -> Self {
+        let (root, shift) = match &self.root {
+            None => (new_path(0, value), 0),
+            Some(root) if self.len == 1 << (self.shift + BITS) => {
+                // the tree is full, so grow a new root
+                let path = new_path(self.shift, value);
+                (Rc::new(VecNode::Branch(vec![root.clone(), path])), self.shift + BITS)
+            }
+            Some(root) => (push_rec(root, self.shift, self.len, value), self.shift),
+        };
+        PersistentVec { root: Some(root), shift, len: self.len + 1 }
+    }
+
+    /// `i` 番目(0-based)の要素を `value` に置き換えた版を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of range.
+    pub fn set(&self, i: usize, value: T) -> Self {
+        assert!(i < self.len);
+        let root = set_rec(self.root.as_ref().unwrap(), self.shift, i, value);
+        PersistentVec { root: Some(root), shift: self.shift, len: self.len }
+    }
+
+    /// 末尾の要素を取り除いた版を返します。空の場合、 `None` を返します。
+    pub fn pop(&self) -> Option<Self> {
+        let root = self.root.as_ref()?;
+        let mut root = pop_rec(root, self.shift, self.len - 1);
+        let mut shift = self.shift;
+        // shrink the root while it has only one child
+        while let Some(VecNode::Branch(children)) = root.as_deref() {
+            if children.len() != 1 {
+                break;
+            }
+            root = Some(children[0].clone());
+            shift -= BITS;
+        }
+        if root.is_none() {
+            shift = 0;
+        }
+        Some(PersistentVec { root, shift, len: self.len - 1 })
+    }
+
+    /// 要素を先頭から順に返すイテレータを返します。
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        (0..self.len).map(move |i| self.get(i).unwrap())
+    }
+}
+
+impl <T: Clone> Default for PersistentVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn new_path<T>(shift: usize, value: T) -> Rc<VecNode<T>> {
+    if shift == 0 {
+        Rc::new(VecNode::Leaf(vec![value]))
+    } else {
+        Rc::new(VecNode::Branch(vec![new_path(shift - BITS, value)]))
+    }
+}
+
+fn push_rec<T: Clone>(node: &VecNode<T>, shift: usize, i: usize, value: T) -> Rc<VecNode<T>> {
+    match node {
+        VecNode::Leaf(values) => {
+            let mut values = values.clone();
+            values.push(value);
+            Rc::new(VecNode::Leaf(values))
+        }
+        VecNode::Branch(children) => {
+            let mut children = children.clone();
+            let c = (i >> shift) & MASK;
+            if c < children.len() {
+                children[c] = push_rec(&children[c], shift - BITS, i, value);
+            } else {
+                children.push(new_path(shift - BITS, value));
+            }
+            Rc::new(VecNode::Branch(children))
+        }
+    }
+}
+
+fn set_rec<T: Clone>(node: &VecNode<T>, shift: usize, i: usize, value: T) -> Rc<VecNode<T>> {
+    match node {
+        VecNode::Leaf(values) => {
+            let mut values = values.clone();
+            values[i & MASK] = value;
+            Rc::new(VecNode::Leaf(values))
+        }
+        VecNode::Branch(children) => {
+            let mut children = children.clone();
+            let c = (i >> shift) & MASK;
+            children[c] = set_rec(&children[c], shift - BITS, i, value);
+            Rc::new(VecNode::Branch(children))
+        }
+    }
+}
+
+// remove the element at `i` (the last one), and returns `None` if the node becomes empty
+fn pop_rec<T: Clone>(node: &VecNode<T>, shift: usize, i: usize) -> Option<Rc<VecNode<T>>> {
+    match node {
+        VecNode::Leaf(values) => {
+            if values.len() == 1 {
+                return None;
+            }
+            Some(Rc::new(VecNode::Leaf(values[..values.len() - 1].to_vec())))
+        }
+        VecNode::Branch(children) => {
+            let mut children = children.clone();
+            let c = (i >> shift) & MASK;
+            match pop_rec(&children[c], shift - BITS, i) {
+                Some(child) => children[c] = child,
+                None => {
+                    children.pop();
+                }
+            }
+            if children.is_empty() {
+                None
+            } else {
+                Some(Rc::new(VecNode::Branch(children)))
+            }
+        }
+    }
+}
+
+/// 永続マップ
+///
+/// ノードを `Rc` で確保し、複数の版の木で共有します。
+///
+/// 共有されているノードを書き換える前に、領域に持たせた関数でノードを複製して付け替えるため、
+/// 根から書き換えたノードまでの経路だけが複製されます (path copying) 。
+/// 領域の関数は、 [`PersistentMap`] の構築時に設定します。
+struct RcNodes;
+
+impl NodeStorage for RcNodes {
+    // copies a shared node
+    type Pool<T> = Option<fn(&T) -> T>;
+    type Link<T> = Rc<T>;
+
+    fn alloc<T>(_: &mut Option<fn(&T) -> T>, node: T) -> Rc<T> {
+        Rc::new(node)
+    }
+
+    fn free<T>(pool: &mut Option<fn(&T) -> T>, link: Rc<T>) -> T {
+        Rc::try_unwrap(link).unwrap_or_else(|link| copy_node(*pool, &link))
+    }
+
+    fn get<'a, T>(_: &'a Option<fn(&T) -> T>, link: &'a Rc<T>) -> &'a T {
+        link
+    }
+
+    fn get_mut<'a, T>(pool: &'a mut Option<fn(&T) -> T>, link: &'a mut Rc<T>) -> &'a mut T {
+        make_mut(*pool, link)
+    }
+
+    fn descend<'a, T, D>(
+        pool: &'a mut Option<fn(&T) -> T>,
+        link: &'a mut Rc<T>,
+        mut next: impl FnMut(&T) -> Option<D>,
+        mut child: impl FnMut(&mut T, D) -> &mut Rc<T>,
+    ) -> &'a mut T {
+        let copy = *pool;
+        let mut node = make_mut(copy, link);
+        while let Some(d) = next(node) {
+            node = make_mut(copy, child(node, d));
+        }
+        node
+    }
+
+    fn link_heap_size<T: SpaceUsage>(link: &Rc<T>) -> usize {
+        link.heap_size_in_bytes()
+    }
+
+    fn pool_heap_size<T: SpaceUsage>(_: &Option<fn(&T) -> T>) -> usize {
+        0
+    }
+}
+
+fn copy_node<T>(copy: Option<fn(&T) -> T>, node: &T) -> T {
+    copy.expect("a shared node is modified without a copy function")(node)
+}
+
+// copies the node if it is shared with another version
+fn make_mut<T>(copy: Option<fn(&T) -> T>, link: &mut Rc<T>) -> &mut T {
+    if Rc::get_mut(link).is_none() {
+        *link = Rc::new(copy_node(copy, link));
+    }
+    Rc::get_mut(link).unwrap()
+}
+
+/// 経路の複製 (path copying) による不変な [`Treap`] 。
+/// 更新操作は自身を変更せず、変更した経路のノードだけを複製した新しい版を期待値 `O(log n)` で返します。
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use rust_study::collections::persistent::PersistentMap;
+/// let m0 = PersistentMap::new();
+/// let m1 = m0.insert("a", 1).insert("b", 2);
+/// let m2 = m1.insert("a", 10).remove(&"b");
+///
+/// // 古い版はそのまま残ります。
+/// assert_eq!(None, m0.get(&"a"));
+/// assert_eq!(vec![(&"a", &1), (&"b", &2)], m1.iter().collect::<Vec<_>>());
+/// assert_eq!(vec![(&"a", &10)], m2.iter().collect::<Vec<_>>());
+/// # }
+/// ```
+///
+/// ノードの優先度を選ぶ型引数 `R` の乱数生成器は版ごとに持ち、 `insert()` は複製して進めた乱数生成器を新しい版に持たせます。
+/// そのため、シードを固定した乱数生成器を [`Self::with_rng()`] に渡すと、同じ操作の列から同じ形の木ができます。
+pub struct PersistentMap<K, V, R = DefaultRng> {
+    treap: Treap<K, V, R, RcNodes>,
+}
+
+impl <K, V, R: Clone> Clone for PersistentMap<K, V, R> {
+    fn clone(&self) -> Self {
+        PersistentMap { treap: self.treap.share() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl <K: Ord + Clone, V: Clone> PersistentMap<K, V> {
+    /// 空のマップを構築します。
+    pub fn new() -> Self {
+        Self::with_rng(DefaultRng)
+    }
+}
+
+impl <K: Ord + Clone, V: Clone, R: RngCore + Clone> PersistentMap<K, V, R> {
+    /// Treap の優先度を乱数生成器 `rng` で選ぶ、空のマップを構築します。
+    pub fn with_rng(rng: R) -> Self {
+        PersistentMap { treap: Treap::with_pool(rng, Some(Clone::clone as fn(&_) -> _)) }
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        self.treap.len()
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.treap.is_empty()
+    }
+
+    /// キー `key` に対応付けられた値を参照します。
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.treap.get(key)
+    }
+
+    /// キー `key` が存在する場合に、 `true` を返します。
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.treap.contains_key(key)
+    }
+
+    /// キー `key` に値 `value` を対応付けた版を返します。すでにキーが存在した場合、値を置き換えます。
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let mut treap = self.treap.share();
+        treap.insert(key, value);
+        PersistentMap { treap }
+    }
+
+    /// キー `key` を取り除いた版を返します。
+    pub fn remove(&self, key: &K) -> Self {
+        let mut treap = self.treap.share();
+        // a missing key leaves the nodes shared
+        if treap.contains_key(key) {
+            treap.remove(key);
+        }
+        PersistentMap { treap }
+    }
+
+    /// すべてのキーと値の組を、キーの小さい順に返すイテレータを返します。
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.treap.iter()
+    }
+}
+
+#[cfg(feature = "std")]
+impl <K: Ord + Clone, V: Clone> Default for PersistentMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for VecNode<T> {
+    fn heap_size_in_bytes(&self) -> usize {
+        match self {
+            VecNode::Leaf(values) => values.heap_size_in_bytes(),
+            VecNode::Branch(children) => children.heap_size_in_bytes(),
+        }
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for PersistentVec<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("root", &self.root)]
+    }
+}
+
+/// 複数の版で共有しているノードは、それぞれの版で数えます。
+impl <K: SpaceUsage, V: SpaceUsage, R> SpaceUsage for PersistentMap<K, V, R> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("treap", &self.treap)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn vec_versions() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut versions = vec![(PersistentVec::new(), vec![])];
+        for _ in 0..3000 {
+            let (v, expected) = &versions[rng.gen_range(0, versions.len())];
+            let (v, mut expected) = (v.clone(), expected.clone());
+            let v = match rng.gen_range(0, 4) {
+                0 if !expected.is_empty() => {
+                    let i = rng.gen_range(0, expected.len());
+                    let x: u32 = rng.gen();
+                    expected[i] = x;
+                    v.set(i, x)
+                }
+                1 => {
+                    let popped = v.pop();
+                    assert_eq!(expected.pop().is_some(), popped.is_some());
+                    popped.unwrap_or(v)
+                }
+                _ => {
+                    let x: u32 = rng.gen();
+                    expected.push(x);
+                    v.push(x)
+                }
+            };
+            versions.push((v, expected));
+        }
+        let (v, _) = &versions[0];
+        let mut v = v.clone();
+        let mut expected = vec![];
+        for i in 0..5000_u32 {
+            v = v.push(i);
+            expected.push(i);
+        }
+        versions.push((v.clone(), expected.clone()));
+        while let Some(w) = v.pop() {
+            v = w;
+            expected.pop();
+            assert_eq!(expected.last(), v.last());
+        }
+        assert!(v.is_empty());
+
+        for (v, expected) in &versions {
+            assert_eq!(expected.len(), v.len());
+            assert_eq!(expected.iter().collect::<Vec<_>>(), v.iter().collect::<Vec<_>>());
+            assert_eq!(None, v.get(expected.len()));
+        }
+    }
+
+    #[test]
+    fn map_versions() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut versions = vec![(PersistentMap::with_rng(StdRng::seed_from_u64(1)), BTreeMap::new())];
+        for _ in 0..3000 {
+            let (m, expected) = &versions[rng.gen_range(0, versions.len())];
+            let (m, mut expected) = (m.clone(), expected.clone());
+            let k: u32 = rng.gen_range(0, 200);
+            let m = if rng.gen_range(0, 3) == 0 {
+                expected.remove(&k);
+                m.remove(&k)
+            } else {
+                let v: u32 = rng.gen();
+                expected.insert(k, v);
+                m.insert(k, v)
+            };
+            assert_eq!(expected.get(&k), m.get(&k));
+            assert_eq!(expected.contains_key(&k), m.contains_key(&k));
+            versions.push((m, expected));
+        }
+        for (m, expected) in &versions {
+            assert_eq!(expected.len(), m.len());
+            assert_eq!(expected.iter().collect::<Vec<_>>(), m.iter().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn map_with_rng() {
+        let m0 = PersistentMap::with_rng(StdRng::seed_from_u64(1));
+        let (mut a, mut b) = (m0.clone(), m0.clone());
+        for k in 0..100 {
+            a = a.insert(k, k);
+            b = b.insert(k, k);
+        }
+        assert_eq!(a.treap.root().map(|n| n.key()), b.treap.root().map(|n| n.key()));
+        // each version advances its own copy of the generator
+        let (c, d) = (a.insert(200, 0), a.insert(200, 1));
+        assert_eq!(c.treap.root().map(|n| n.key()), d.treap.root().map(|n| n.key()));
+        assert_eq!(Some(&1), d.get(&200));
+    }
+}
@@ -0,0 +1,317 @@
+use crate::space::SpaceUsage;
+
+use alloc::boxed::Box;
+use core::mem;
+use core::mem::MaybeUninit;
+
+/// 固定長リングバッファ
+///
+/// 容量を固定した両端キュー。確保済みの領域を使い回すため、構築後に再確保を行いません。
+/// 先頭・末尾への追加と取り出しを `O(1)` で行います。
+///
+/// 満杯のときの追加は、 [`Self::new()`] で構築した場合は拒否され、
+/// [`Self::with_overwrite()`] で構築した場合は反対側の端の要素を上書きします。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::ring_buffer::RingBuffer;
+/// let mut ring = RingBuffer::new(3);
+/// assert_eq!(Ok(None), ring.push_back(1));
+/// assert_eq!(Ok(None), ring.push_back(2));
+/// assert_eq!(Ok(None), ring.push_front(0));
+/// assert!(ring.is_full());
+/// // 満杯のときは追加できません。
+/// assert_eq!(Err(3), ring.push_back(3));
+///
+/// assert_eq!(Some(2), ring.pop_back());
+/// assert_eq!(Ok(None), ring.push_back(3));
+/// // 内部の領域は折り返していますが、連続した 2 つのスライスとして参照できます。
+/// assert_eq!((&[0][..], &[1, 3][..]), ring.as_slices());
+///
+/// let mut ring = RingBuffer::with_overwrite(2);
+/// ring.push_back(1).unwrap();
+/// ring.push_back(2).unwrap();
+/// // 満杯のときは先頭の要素を上書きします。
+/// assert_eq!(Ok(Some(1)), ring.push_back(3));
+/// assert_eq!(vec![2, 3], ring.iter().copied().collect::<Vec<i32>>());
+/// ```
+pub struct RingBuffer<T> {
+    buf: Box<[MaybeUninit<T>]>,
+    head: usize,
+    len: usize,
+    overwrite: bool,
+}
+
+impl <T> RingBuffer<T> {
+    /// 容量 `capacity` の空のリングバッファを構築します。満杯のときの追加は拒否されます。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        RingBuffer {
+            buf: (0..capacity).map(|_| MaybeUninit::uninit()).collect(),
+            head: 0,
+            len: 0,
+            overwrite: false,
+        }
+    }
+
+    /// 容量 `capacity` の空のリングバッファを構築します。満杯のときの追加は反対側の端の要素を上書きします。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_overwrite(capacity: usize) -> Self {
+        let mut ring = Self::new(capacity);
+        ring.overwrite = true;
+        ring
+    }
+
+    /// 容量を返します。
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 満杯の場合に、 `true` を返します。
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity()
+    }
+
+    /// 末尾に要素を追加します。
+    ///
+    /// 満杯の場合、上書きが有効なら先頭の要素を取り除いて `Ok(Some(先頭の要素))` を、
+    /// 無効なら追加せずに `Err(value)` を返します。
+    pub fn push_back(&mut self, value: T) -> Result<Option<T>, T> {
+        let overwritten = if self.is_full() {
+            if !self.overwrite {
+                return Err(value);
+            }
+            self.pop_front()
+        } else {
+            None
+        };
+        let i = self.physical(self.len);
+        self.buf[i].write(value);
+        self.len += 1;
+        Ok(overwritten)
+    }
+
+    /// 先頭に要素を追加します。
+    ///
+    /// 満杯の場合、上書きが有効なら末尾の要素を取り除いて `Ok(Some(末尾の要素))` を、
+    /// 無効なら追加せずに `Err(value)` を返します。
+    pub fn push_front(&mut self, value: T) -> Result<Option<T>, T> {
+        let overwritten = if self.is_full() {
+            if !self.overwrite {
+                return Err(value);
+            }
+            self.pop_back()
+        } else {
+            None
+        };
+        self.head = self.physical(self.capacity() - 1);
+        self.buf[self.head].write(value);
+        self.len += 1;
+        Ok(overwritten)
+    }
+
+    /// 先頭の要素を取り出します。空の場合、 `None` を返します。
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        // SAFETY: the element at `head` is initialized while `len > 0`
+        let value = unsafe { self.buf[self.head].assume_init_read() };
+        self.head = self.physical(1);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// 末尾の要素を取り出します。空の場合、 `None` を返します。
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let i = self.physical(self.len - 1);
+        self.len -= 1;
+        // SAFETY: the element at `i` was the last initialized element
+        Some(unsafe { self.buf[i].assume_init_read() })
+    }
+
+    /// `i` 番目(0-based)の要素を参照します。範囲外の場合、 `None` を返します。
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i >= self.len {
+            return None;
+        }
+        // SAFETY: elements in `[0, len)` are initialized
+        Some(unsafe { self.buf[self.physical(i)].assume_init_ref() })
+    }
+
+    /// `i` 番目(0-based)の要素を可変参照します。範囲外の場合、 `None` を返します。
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        if i >= self.len {
+            return None;
+        }
+        let p = self.physical(i);
+        // SAFETY: elements in `[0, len)` are initialized
+        Some(unsafe { self.buf[p].assume_init_mut() })
+    }
+
+    /// 先頭の要素を参照します。
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// 末尾の要素を参照します。
+    pub fn back(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    /// 要素を先頭から順に、内部の領域上で連続した 2 つのスライスとして返します。
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let first_len = self.len.min(self.capacity() - self.head);
+        let second_len = self.len - first_len;
+        // SAFETY: `[head, head + first_len)` and `[0, second_len)` are the initialized elements
+        unsafe {
+            (
+                core::slice::from_raw_parts(self.buf[self.head..].as_ptr() as *const T, first_len),
+                core::slice::from_raw_parts(self.buf.as_ptr() as *const T, second_len),
+            )
+        }
+    }
+
+    /// 要素を先頭から順に返すイテレータを返します。
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        let (first, second) = self.as_slices();
+        first.iter().chain(second.iter())
+    }
+
+    /// すべての要素を取り除きます。
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+        self.head = 0;
+    }
+
+    fn physical(&self, i: usize) -> usize {
+        (self.head + i) % self.capacity()
+    }
+}
+
+impl <T> Drop for RingBuffer<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for RingBuffer<T> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.buf.len() * mem::size_of::<T>() + self.iter().map(|value| value.heap_size_in_bytes()).sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for overwrite in [false, true] {
+            let capacity = 7;
+            let mut ring = if overwrite { RingBuffer::with_overwrite(capacity) } else { RingBuffer::new(capacity) };
+            let mut expected = VecDeque::new();
+            for _ in 0..10000 {
+                let v: u32 = rng.gen();
+                match rng.gen_range(0, 4) {
+                    0 => {
+                        let result = if expected.len() < capacity {
+                            expected.push_back(v);
+                            Ok(None)
+                        } else if overwrite {
+                            let front = expected.pop_front();
+                            expected.push_back(v);
+                            Ok(front)
+                        } else {
+                            Err(v)
+                        };
+                        assert_eq!(result, ring.push_back(v));
+                    }
+                    1 => {
+                        let result = if expected.len() < capacity {
+                            expected.push_front(v);
+                            Ok(None)
+                        } else if overwrite {
+                            let back = expected.pop_back();
+                            expected.push_front(v);
+                            Ok(back)
+                        } else {
+                            Err(v)
+                        };
+                        assert_eq!(result, ring.push_front(v));
+                    }
+                    2 => assert_eq!(expected.pop_front(), ring.pop_front()),
+                    _ => assert_eq!(expected.pop_back(), ring.pop_back()),
+                }
+                assert_eq!(expected.len(), ring.len());
+                assert_eq!(expected.front(), ring.front());
+                assert_eq!(expected.back(), ring.back());
+                let (a, b) = ring.as_slices();
+                assert_eq!(expected.iter().collect::<Vec<_>>(), a.iter().chain(b.iter()).collect::<Vec<_>>());
+                assert_eq!(expected.iter().rev().collect::<Vec<_>>(), ring.iter().rev().collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut ring = RingBuffer::new(3);
+        ring.push_back(1).unwrap();
+        ring.push_back(2).unwrap();
+        *ring.get_mut(1).unwrap() += 10;
+        assert_eq!(Some(&12), ring.get(1));
+        assert_eq!(None, ring.get(2));
+        assert_eq!(None, ring.get_mut(2));
+    }
+
+    #[test]
+    fn drop_elements() {
+        let rc = Rc::new(());
+        {
+            let mut ring = RingBuffer::with_overwrite(3);
+            for _ in 0..5 {
+                ring.push_back(rc.clone()).unwrap();
+            }
+            assert_eq!(4, Rc::strong_count(&rc));
+            ring.pop_front();
+            assert_eq!(3, Rc::strong_count(&rc));
+        }
+        assert_eq!(1, Rc::strong_count(&rc));
+    }
+
+    #[test]
+    fn space() {
+        use crate::space::SpaceUsage;
+        let mut ring = RingBuffer::new(4);
+        assert_eq!(4 * mem::size_of::<String>(), ring.heap_size_in_bytes());
+        ring.push_back(String::from("abc")).unwrap();
+        assert_eq!(4 * mem::size_of::<String>() + 3, ring.heap_size_in_bytes());
+    }
+}
@@ -0,0 +1,207 @@
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 巻き戻し可能な Union-Find
+///
+/// 経路圧縮を行わず、大きさによる併合 (union by size) のみを行う Union-Find。
+/// 併合の履歴を保持し、直前の併合の取り消しや、 [`Self::snapshot()`] で記録した時点への巻き戻しを行います。
+/// 検索・併合は `O(log n)` 、取り消しは 1 回の併合あたり `O(1)` です。
+///
+/// オフラインの動的連結性判定のように、辺の追加を後から取り消す必要があるアルゴリズムに使います。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::rollback_union_find::RollbackUnionFind;
+/// let mut uf = RollbackUnionFind::new(4);
+/// assert!(uf.union(0, 1));
+/// let snapshot = uf.snapshot();
+/// assert!(uf.union(1, 2));
+/// assert!(uf.union(2, 3));
+/// assert!(uf.same(0, 3));
+/// assert_eq!(1, uf.count());
+///
+/// // snapshot を記録した時点に巻き戻します。
+/// uf.rollback(snapshot);
+/// assert!(uf.same(0, 1));
+/// assert!(!uf.same(1, 2));
+/// assert_eq!(3, uf.count());
+/// ```
+pub struct RollbackUnionFind {
+    // the parent, or the negated size for a root
+    parent: Vec<isize>,
+    count: usize,
+    // (the merged root, its former parent value) for each union
+    history: Vec<Option<(usize, isize)>>,
+}
+
+impl RollbackUnionFind {
+    /// 要素 `0..n` がそれぞれ単独の集合をなす Union-Find を構築します。
+    pub fn new(n: usize) -> Self {
+        RollbackUnionFind { parent: vec![-1; n], count: n, history: vec![] }
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    /// 要素がない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// 集合の個数を返します。
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// 要素 `x` の属する集合の代表元を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` is out of range.
+    pub fn find(&self, mut x: usize) -> usize {
+        while self.parent[x] >= 0 {
+            x = self.parent[x] as usize;
+        }
+        x
+    }
+
+    /// 要素 `x` と `y` が同じ集合に属する場合に、 `true` を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` or `y` is out of range.
+    pub fn same(&self, x: usize, y: usize) -> bool {
+        self.find(x) == self.find(y)
+    }
+
+    /// 要素 `x` の属する集合の大きさを返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` is out of range.
+    pub fn size(&self, x: usize) -> usize {
+        (-self.parent[self.find(x)]) as usize
+    }
+
+    /// 要素 `x` と `y` の属する集合を併合します。併合した場合に、 `true` を返します。
+    ///
+    /// すでに同じ集合に属していた場合も、取り消しの単位として履歴に記録されます。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` or `y` is out of range.
+    pub fn union(&mut self, x: usize, y: usize) -> bool {
+        let mut rx = self.find(x);
+        let mut ry = self.find(y);
+        if rx == ry {
+            self.history.push(None);
+            return false;
+        }
+        if self.parent[rx] > self.parent[ry] {
+            core::mem::swap(&mut rx, &mut ry);
+        }
+        self.history.push(Some((ry, self.parent[ry])));
+        self.parent[rx] += self.parent[ry];
+        self.parent[ry] = rx as isize;
+        self.count -= 1;
+        true
+    }
+
+    /// 直前の [`Self::union()`] を取り消します。取り消す操作がない場合、 `false` を返します。
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            None => false,
+            Some(None) => true,
+            Some(Some((ry, p))) => {
+                let rx = self.parent[ry] as usize;
+                self.parent[ry] = p;
+                self.parent[rx] -= p;
+                self.count += 1;
+                true
+            }
+        }
+    }
+
+    /// 現在の状態を表すスナップショットを返します。 [`Self::rollback()`] に渡して、この時点に巻き戻します。
+    pub fn snapshot(&self) -> usize {
+        self.history.len()
+    }
+
+    /// スナップショット `snapshot` を記録した時点に巻き戻します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `snapshot` is newer than the current state.
+    pub fn rollback(&mut self, snapshot: usize) {
+        assert!(snapshot <= self.history.len());
+        while self.history.len() > snapshot {
+            self.undo();
+        }
+    }
+}
+
+impl SpaceUsage for RollbackUnionFind {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("parent", &self.parent), ("history", &self.history)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn components(uf: &RollbackUnionFind) -> Vec<usize> {
+        (0..uf.len()).map(|x| uf.find(x)).collect()
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let n = 30;
+        let mut uf = RollbackUnionFind::new(n);
+        // states before each union
+        let mut states = vec![];
+        for _ in 0..2000 {
+            match rng.gen_range(0, 4) {
+                0 => {
+                    let expected = states.pop();
+                    assert_eq!(expected.is_some(), uf.undo());
+                    if let Some((roots, count)) = expected {
+                        assert_eq!(roots, components(&uf));
+                        assert_eq!(count, uf.count());
+                    }
+                }
+                1 if !states.is_empty() => {
+                    let snapshot = rng.gen_range(0, states.len());
+                    let (roots, count) = states[snapshot].clone();
+                    states.truncate(snapshot);
+                    uf.rollback(snapshot);
+                    assert_eq!(roots, components(&uf));
+                    assert_eq!(count, uf.count());
+                }
+                _ => {
+                    states.push((components(&uf), uf.count()));
+                    let x = rng.gen_range(0, n);
+                    let y = rng.gen_range(0, n);
+                    let same = uf.same(x, y);
+                    assert_eq!(!same, uf.union(x, y));
+                    assert!(uf.same(x, y));
+                    assert_eq!(uf.snapshot(), states.len());
+                }
+            }
+            let roots = components(&uf);
+            assert_eq!(roots.iter().enumerate().filter(|(x, r)| *x == **r).count(), uf.count());
+            for x in 0..n {
+                assert_eq!(roots.iter().filter(|r| **r == roots[x]).count(), uf.size(x));
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+// Serialization of the collections through their public operations.
+
+use super::heap::Heap;
+use super::union_find::UnionFind;
+
+use serde::de;
+use serde::de::DeserializeSeed;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+// the elements in the order of the array, which is not sorted
+impl <T: Serialize> Serialize for Heap<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+/// 比較関数は書き出せないため、読み込む要素を、比較関数を指定して構築した二分ヒープに追加します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::heap::Heap;
+/// use serde::de::DeserializeSeed;
+/// use serde::de::value::Error;
+/// use serde::de::value::SeqDeserializer;
+/// let deserializer = SeqDeserializer::<_, Error>::new(vec![1, 7, 3, 5].into_iter());
+/// let mut heap = Heap::with_compare(|a: &i32, b: &i32| b.cmp(a)).deserialize(deserializer).unwrap();
+/// assert_eq!(vec![7, 5, 3, 1], heap.drain(4));
+/// ```
+impl <'de, T: Deserialize<'de>> DeserializeSeed<'de> for Heap<T> {
+    type Value = Heap<T>;
+
+    fn deserialize<D: Deserializer<'de>>(mut self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let values = Vec::<T>::deserialize(deserializer)?;
+        self.reserve_exact(values.len());
+        for v in values {
+            self.push(v);
+        }
+        Ok(self)
+    }
+}
+
+// the representative of each element
+impl Serialize for UnionFind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq((0..self.len()).map(|x| self.root(x)))
+    }
+}
+
+impl <'de> Deserialize<'de> for UnionFind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let roots = Vec::<usize>::deserialize(deserializer)?;
+        let mut uf = UnionFind::new(roots.len());
+        for (x, &r) in roots.iter().enumerate() {
+            if roots.get(r) != Some(&r) {
+                return Err(de::Error::custom("the representative should be its own representative"));
+            }
+            uf.union(x, r);
+        }
+        Ok(uf)
+    }
+}
@@ -0,0 +1,406 @@
+use super::hash::DefaultHashBuilder;
+use super::hash::hash_with;
+use super::hash::mix64;
+use crate::space::SpaceUsage;
+
+use alloc::vec::Vec;
+use alloc::vec;
+use core::hash::BuildHasher;
+use core::hash::Hash;
+
+/// Count-Min スケッチ
+///
+/// `depth` 本のハッシュ関数それぞれについて `width` 個のカウンタを持ち、ストリーム中の要素の出現回数を
+/// 要素そのものを保持せずに推定します。
+/// 推定値は真の出現回数以上となり、幅 `width` に対して誤差は高々総出現回数の `e / width` 倍程度、
+/// その確率保証は深さ `depth` に対して `1 - exp(-depth)` 程度です。
+///
+/// [`Self::with_conservative_update()`] で構築すると、追加時に必要な分だけカウンタを増やす
+/// 保守的更新 (conservative update) を行い、過大評価を抑えます。
+///
+/// ハッシュ関数は型引数 `S` の `BuildHasher` で作り、既定では鍵を固定した SipHash を使います。
+/// 短い要素が多くハッシュ値の計算が重い場合は、 [`Self::with_hasher()`] で FxHash などの速いハッシュ関数に替えられます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::sketch::CountMin;
+/// let mut sketch = CountMin::new(1000, 4);
+/// sketch.add("apple", 3);
+/// sketch.add("banana", 1);
+/// sketch.add("apple", 2);
+/// assert!(sketch.estimate("apple") >= 5);
+/// assert!(sketch.estimate("banana") >= 1);
+/// assert_eq!(6, sketch.total());
+/// ```
+pub struct CountMin<S = DefaultHashBuilder> {
+    width: usize,
+    depth: usize,
+    counters: Vec<u64>,
+    total: u64,
+    conservative: bool,
+    hasher: S,
+}
+
+impl CountMin {
+    /// 幅 `width`, 深さ `depth` の空のスケッチを構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `depth` is zero.
+    pub fn new(width: usize, depth: usize) -> Self {
+        Self::with_hasher(width, depth, DefaultHashBuilder::default())
+    }
+
+    /// 幅 `width`, 深さ `depth` の、保守的更新を行う空のスケッチを構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `depth` is zero.
+    pub fn with_conservative_update(width: usize, depth: usize) -> Self {
+        Self::with_conservative_update_and_hasher(width, depth, DefaultHashBuilder::default())
+    }
+}
+
+impl <S: BuildHasher> CountMin<S> {
+    /// 幅 `width`, 深さ `depth` の、 `hasher` の作るハッシュ関数を使う空のスケッチを構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `depth` is zero.
+    pub fn with_hasher(width: usize, depth: usize, hasher: S) -> Self {
+        assert!(width > 0 && depth > 0);
+        CountMin {
+            width,
+            depth,
+            counters: vec![0; width * depth],
+            total: 0,
+            conservative: false,
+            hasher,
+        }
+    }
+
+    /// 幅 `width`, 深さ `depth` の、 `hasher` の作るハッシュ関数を使い保守的更新を行う空のスケッチを構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `depth` is zero.
+    pub fn with_conservative_update_and_hasher(width: usize, depth: usize, hasher: S) -> Self {
+        let mut sketch = Self::with_hasher(width, depth, hasher);
+        sketch.conservative = true;
+        sketch
+    }
+
+    /// 幅を返します。
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// 深さを返します。
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// これまでに追加された出現回数の総和を返します。
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// 要素 `item` の出現回数に `count` を加えます。
+    pub fn add<T: Hash + ?Sized>(&mut self, item: &T, count: u64) {
+        self.total += count;
+        if self.conservative {
+            let target = self.estimate(item) + count;
+            for row in 0..self.depth {
+                let i = self.index(row, item);
+                self.counters[i] = self.counters[i].max(target);
+            }
+        } else {
+            for row in 0..self.depth {
+                let i = self.index(row, item);
+                self.counters[i] += count;
+            }
+        }
+    }
+
+    /// 要素 `item` の出現回数の推定値を返します。推定値は真の出現回数を下回りません。
+    pub fn estimate<T: Hash + ?Sized>(&self, item: &T) -> u64 {
+        (0..self.depth).map(|row| self.counters[self.index(row, item)]).min().unwrap()
+    }
+
+    /// 別のスケッチ `other` の内容を加えます。
+    ///
+    /// 結果は、両方のストリームを連結したものに対するスケッチとして振る舞います。
+    /// `other` は、同じハッシュ関数を使うスケッチである必要があります。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the width or depth of `other` differs from `self`.
+    pub fn merge(&mut self, other: &CountMin<S>) {
+        assert!(self.width == other.width && self.depth == other.depth);
+        for (c, o) in self.counters.iter_mut().zip(other.counters.iter()) {
+            *c += o;
+        }
+        self.total += other.total;
+    }
+
+    /// すべてのカウンタを 0 に戻します。
+    pub fn clear(&mut self) {
+        self.counters.iter_mut().for_each(|c| *c = 0);
+        self.total = 0;
+    }
+
+    fn index<T: Hash + ?Sized>(&self, row: usize, item: &T) -> usize {
+        row * self.width + (hash_with(&self.hasher, item, row as u64) % self.width as u64) as usize
+    }
+}
+
+/// HyperLogLog
+///
+/// ストリーム中の異なる要素の個数 (カーディナリティ) を、 `2^precision` バイトの領域で推定します。
+/// 推定値の相対標準誤差はおよそ `1.04 / sqrt(2^precision)` です。
+///
+/// ハッシュ関数は型引数 `S` の `BuildHasher` で作り、既定では鍵を固定した SipHash を使います。
+/// ハッシュ値は [`mix64()`](super::hash::mix64) で撹拌してから使うため、 FxHash のような撹拌の弱いハッシュ関数でも推定値が偏りません。
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use rust_study::collections::sketch::HyperLogLog;
+/// let mut hll = HyperLogLog::new(12);
+/// for i in 0..10000 {
+///     hll.insert(&(i % 5000));
+/// }
+/// let estimate = hll.estimate();
+/// assert!(4500.0 < estimate && estimate < 5500.0);
+/// # }
+/// ```
+pub struct HyperLogLog<S = DefaultHashBuilder> {
+    precision: u32,
+    registers: Vec<u8>,
+    hasher: S,
+}
+
+impl HyperLogLog {
+    /// 精度 `precision` の空の推定器を構築します。レジスタ数は `2^precision` になります。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precision` is not in `[4, 18]`.
+    pub fn new(precision: u32) -> Self {
+        Self::with_hasher(precision, DefaultHashBuilder::default())
+    }
+}
+
+impl <S: BuildHasher> HyperLogLog<S> {
+    /// 精度 `precision` の、 `hasher` の作るハッシュ関数を使う空の推定器を構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precision` is not in `[4, 18]`.
+    pub fn with_hasher(precision: u32, hasher: S) -> Self {
+        assert!((4..=18).contains(&precision));
+        HyperLogLog {
+            precision,
+            registers: vec![0; 1 << precision],
+            hasher,
+        }
+    }
+
+    /// 精度を返します。
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+
+    /// 要素 `item` を追加します。
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        let h = mix64(hash_with(&self.hasher, item, 0));
+        let i = (h >> (64 - self.precision)) as usize;
+        // the sentinel bit bounds the rank by 64 - precision + 1
+        let rest = (h << self.precision) | (1 << (self.precision - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        self.registers[i] = self.registers[i].max(rank);
+    }
+
+    /// 異なる要素の個数の推定値を返します。
+    ///
+    /// 対数の計算に `std` を使うため、 `std` フィーチャーが必要です。
+    #[cfg(feature = "std")]
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self.registers.iter().map(|r| 1.0 / (1_u64 << r) as f64).sum();
+        let raw = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        if raw <= 2.5 * m && zeros > 0 {
+            // linear counting for small cardinalities
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        }
+    }
+
+    /// 別の推定器 `other` の内容を取り込みます。
+    ///
+    /// 結果は、両方のストリームの和集合に対する推定器として振る舞います。
+    /// `other` は、同じハッシュ関数を使う推定器である必要があります。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the precision of `other` differs from `self`.
+    pub fn merge(&mut self, other: &HyperLogLog<S>) {
+        assert_eq!(self.precision, other.precision);
+        for (r, o) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *r = (*r).max(*o);
+        }
+    }
+
+    /// 空の状態に戻します。
+    pub fn clear(&mut self) {
+        self.registers.iter_mut().for_each(|r| *r = 0);
+    }
+}
+
+impl <S> SpaceUsage for CountMin<S> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("counters", &self.counters)]
+    }
+}
+
+impl <S> SpaceUsage for HyperLogLog<S> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("registers", &self.registers)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::HashMap;
+
+    #[test]
+    fn estimate() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut plain = CountMin::new(200, 5);
+        let mut conservative = CountMin::with_conservative_update(200, 5);
+        let mut counts = HashMap::new();
+        for _ in 0..10000 {
+            let k: u32 = rng.gen_range(0, 1000);
+            let c = rng.gen_range(1, 4);
+            plain.add(&k, c);
+            conservative.add(&k, c);
+            *counts.entry(k).or_insert(0) += c;
+        }
+        let total: u64 = counts.values().sum();
+        assert_eq!(total, plain.total());
+        assert_eq!(total, conservative.total());
+
+        let mut plain_error = 0;
+        let mut conservative_error = 0;
+        for (k, c) in &counts {
+            let p = plain.estimate(k);
+            let q = conservative.estimate(k);
+            assert!(*c <= q && q <= p);
+            plain_error += p - c;
+            conservative_error += q - c;
+        }
+        // the average error is bounded by about total / width
+        assert!(plain_error / counts.len() as u64 <= total / 200);
+        assert!(conservative_error <= plain_error);
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = CountMin::new(100, 3);
+        let mut b = CountMin::new(100, 3);
+        let mut both = CountMin::new(100, 3);
+        for i in 0..500_u32 {
+            a.add(&i, 1);
+            both.add(&i, 1);
+        }
+        for i in 250..1000_u32 {
+            b.add(&i, 2);
+            both.add(&i, 2);
+        }
+        a.merge(&b);
+        assert_eq!(both.total(), a.total());
+        for i in 0..1000_u32 {
+            assert_eq!(both.estimate(&i), a.estimate(&i));
+        }
+
+        a.clear();
+        assert_eq!(0, a.total());
+        assert_eq!(0, a.estimate(&1_u32));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hyper_log_log() {
+        for precision in [4, 10, 14] {
+            let mut hll = HyperLogLog::new(precision);
+            assert_eq!(0.0, hll.estimate());
+            let error = 4.0 * 1.04 / ((1 << precision) as f64).sqrt();
+            for n in [10_u32, 1000, 100000] {
+                hll.clear();
+                for i in 0..n {
+                    hll.insert(&i);
+                    hll.insert(&i);
+                }
+                let estimate = hll.estimate();
+                assert!((estimate - n as f64).abs() <= n as f64 * error, "precision = {}, n = {}, estimate = {}", precision, n, estimate);
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hyper_log_log_merge() {
+        let mut a = HyperLogLog::new(12);
+        let mut b = HyperLogLog::new(12);
+        let mut both = HyperLogLog::new(12);
+        for i in 0..30000_u32 {
+            a.insert(&i);
+            both.insert(&i);
+        }
+        for i in 20000..50000_u32 {
+            b.insert(&i);
+            both.insert(&i);
+        }
+        a.merge(&b);
+        assert_eq!(both.estimate(), a.estimate());
+        assert!((a.estimate() - 50000.0).abs() < 50000.0 * 0.1);
+    }
+
+    #[test]
+    fn with_hasher() {
+        let mut plain = CountMin::with_hasher(100, 3, rustc_hash::FxBuildHasher);
+        let mut conservative = CountMin::with_conservative_update_and_hasher(100, 3, rustc_hash::FxBuildHasher);
+        for i in 0..1000_u32 {
+            plain.add(&i, (i % 3) as u64);
+            conservative.add(&i, (i % 3) as u64);
+        }
+        for i in 0..1000_u32 {
+            assert!((i % 3) as u64 <= conservative.estimate(&i));
+            assert!(conservative.estimate(&i) <= plain.estimate(&i));
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hyper_log_log_with_hasher() {
+        let mut hll = HyperLogLog::with_hasher(12, rustc_hash::FxBuildHasher);
+        for i in 0..50000_u32 {
+            hll.insert(&i);
+        }
+        assert!((hll.estimate() - 50000.0).abs() < 50000.0 * 0.1);
+    }
+}
@@ -0,0 +1,447 @@
+use crate::space::SpaceUsage;
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::mem;
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    size: usize,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl <K, V> Node<K, V> {
+    fn new(key: K, value: V) -> Self {
+        Node { key, value, size: 1, left: None, right: None }
+    }
+
+    fn update(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+fn size<K, V>(node: &Link<K, V>) -> usize {
+    node.as_ref().map_or(0, |n| n.size)
+}
+
+/// スプレー木
+///
+/// アクセスしたノードを根に持ち上げる (スプレー操作) ことで、償却 `O(log n)` で操作を行う二分探索木。
+/// 最近アクセスしたキーほど根の近くに置かれるため、アクセスに偏りがある場合に高速です。
+///
+/// [`Self::split()`] と [`Self::merge()`] による木の分割・連結をサポートします。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::splay_tree::SplayTree;
+/// let mut tree = SplayTree::new();
+/// for i in 0..10 {
+///     tree.insert(i, i * i);
+/// }
+/// assert_eq!(10, tree.len());
+/// assert_eq!(Some(&49), tree.get(&7));
+///
+/// // split はキー以上の要素を新しい木として切り出します。
+/// let mut right = tree.split(&5);
+/// assert_eq!(vec![0, 1, 2, 3, 4], tree.iter().map(|(k, _)| *k).collect::<Vec<i32>>());
+/// assert_eq!(vec![5, 6, 7, 8, 9], right.iter().map(|(k, _)| *k).collect::<Vec<i32>>());
+///
+/// // merge はすべてのキーが大きい木を連結します。
+/// right.remove(&6);
+/// tree.merge(right);
+/// assert_eq!(vec![0, 1, 2, 3, 4, 5, 7, 8, 9], tree.iter().map(|(k, _)| *k).collect::<Vec<i32>>());
+/// ```
+pub struct SplayTree<K, V> {
+    root: Link<K, V>,
+}
+
+impl <K: Ord, V> SplayTree<K, V> {
+    /// 空のスプレー木を構築します。
+    pub fn new() -> Self {
+        SplayTree { root: None }
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// キー `key` に対応付けられた値を参照します。
+    ///
+    /// 参照したノード (キーが存在しない場合は最後に辿ったノード) が根に移動します。
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.splay(key);
+        match &self.root {
+            Some(n) if n.key == *key => Some(&n.value),
+            _ => None,
+        }
+    }
+
+    /// キー `key` に対応付けられた値を可変参照します。
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.splay(key);
+        match &mut self.root {
+            Some(n) if n.key == *key => Some(&mut n.value),
+            _ => None,
+        }
+    }
+
+    /// キー `key` が存在する場合に、 `true` を返します。
+    pub fn contains_key(&mut self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// キー `key` に値 `value` を対応付けます。
+    ///
+    /// すでにキーが存在した場合、値を置き換えて古い値を返します。
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.splay(&key);
+        let mut node = Box::new(Node::new(key, value));
+        match self.root.take() {
+            None => {}
+            Some(mut root) => match node.key.cmp(&root.key) {
+                Ordering::Equal => {
+                    let old = core::mem::replace(&mut root.value, node.value);
+                    self.root = Some(root);
+                    return Some(old);
+                }
+                Ordering::Less => {
+                    node.left = root.left.take();
+                    root.update();
+                    node.right = Some(root);
+                }
+                Ordering::Greater => {
+                    node.right = root.right.take();
+                    root.update();
+                    node.left = Some(root);
+                }
+            },
+        }
+        node.update();
+        self.root = Some(node);
+        None
+    }
+
+    /// キー `key` を取り除き、対応付けられていた値を返します。
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.splay(key);
+        match self.root.take() {
+            Some(mut root) if root.key == *key => {
+                let mut left = SplayTree { root: root.left.take() };
+                let right = SplayTree { root: root.right.take() };
+                left.merge(right);
+                self.root = left.root.take();
+                Some(root.value)
+            }
+            root => {
+                self.root = root;
+                None
+            }
+        }
+    }
+
+    /// 最小のキーとその値を返します。
+    pub fn first(&mut self) -> Option<(&K, &V)> {
+        self.splay_by(|_| Ordering::Less);
+        self.root.as_ref().map(|n| (&n.key, &n.value))
+    }
+
+    /// 最大のキーとその値を返します。
+    pub fn last(&mut self) -> Option<(&K, &V)> {
+        self.splay_by(|_| Ordering::Greater);
+        self.root.as_ref().map(|n| (&n.key, &n.value))
+    }
+
+    /// キーが `key` 以上の要素を切り出し、新しいスプレー木として返します。
+    ///
+    /// `self` にはキーが `key` より小さい要素が残ります。
+    pub fn split(&mut self, key: &K) -> Self {
+        self.splay(key);
+        match self.root.take() {
+            None => SplayTree::new(),
+            Some(mut root) => {
+                if root.key < *key {
+                    let right = root.right.take();
+                    root.update();
+                    self.root = Some(root);
+                    SplayTree { root: right }
+                } else {
+                    self.root = root.left.take();
+                    root.update();
+                    SplayTree { root: Some(root) }
+                }
+            }
+        }
+    }
+
+    /// `other` のすべての要素を連結します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if keys of `other` are not greater than all keys of `self`.
+    pub fn merge(&mut self, mut other: Self) {
+        if other.is_empty() {
+            return;
+        }
+        if self.is_empty() {
+            self.root = other.root.take();
+            return;
+        }
+        self.splay_by(|_| Ordering::Greater);
+        other.splay_by(|_| Ordering::Less);
+        // checked before taking the root, so that `self` is left as it was on panic
+        assert!(self.root.as_ref().unwrap().key < other.root.as_ref().unwrap().key);
+        let mut root = self.root.take().unwrap();
+        root.right = other.root.take();
+        root.update();
+        self.root = Some(root);
+    }
+
+    /// すべてのキーと値の組を、キーの小さい順に返すイテレータを返します。
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: vec![] };
+        iter.push_left(&self.root);
+        iter
+    }
+
+    fn splay(&mut self, key: &K) {
+        self.splay_by(|k| key.cmp(k));
+    }
+
+    // top-down splay: `dir` returns the direction of the target from the given key
+    fn splay_by<F: Fn(&K) -> Ordering>(&mut self, dir: F) {
+        let mut t = match self.root.take() {
+            None => return,
+            Some(t) => t,
+        };
+        let mut left_parts: Vec<Box<Node<K, V>>> = vec![];
+        let mut right_parts: Vec<Box<Node<K, V>>> = vec![];
+        loop {
+            match dir(&t.key) {
+                Ordering::Equal => break,
+                Ordering::Less => {
+                    let mut l = match t.left.take() {
+                        None => break,
+                        Some(l) => l,
+                    };
+                    if dir(&l.key) == Ordering::Less {
+                        // rotate right
+                        t.left = l.right.take();
+                        t.update();
+                        l.right = Some(t);
+                        t = l;
+                        l = match t.left.take() {
+                            None => break,
+                            Some(l) => l,
+                        };
+                    }
+                    right_parts.push(t);
+                    t = l;
+                }
+                Ordering::Greater => {
+                    let mut r = match t.right.take() {
+                        None => break,
+                        Some(r) => r,
+                    };
+                    if dir(&r.key) == Ordering::Greater {
+                        // rotate left
+                        t.right = r.left.take();
+                        t.update();
+                        r.left = Some(t);
+                        t = r;
+                        r = match t.right.take() {
+                            None => break,
+                            Some(r) => r,
+                        };
+                    }
+                    left_parts.push(t);
+                    t = r;
+                }
+            }
+        }
+
+        let mut acc = t.left.take();
+        while let Some(mut n) = left_parts.pop() {
+            n.right = acc;
+            n.update();
+            acc = Some(n);
+        }
+        t.left = acc;
+
+        let mut acc = t.right.take();
+        while let Some(mut n) = right_parts.pop() {
+            n.left = acc;
+            n.update();
+            acc = Some(n);
+        }
+        t.right = acc;
+
+        t.update();
+        self.root = Some(t);
+    }
+}
+
+impl <K: Ord, V> Default for SplayTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl <K, V> Drop for SplayTree<K, V> {
+    fn drop(&mut self) {
+        // avoid deep recursion on degenerated trees
+        let mut stack: Vec<Box<Node<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut n) = stack.pop() {
+            stack.extend(n.left.take());
+            stack.extend(n.right.take());
+        }
+    }
+}
+
+/// [`SplayTree::iter()`] の返すイテレータ
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl <'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut node: &'a Link<K, V>) {
+        while let Some(n) = node {
+            self.stack.push(n);
+            node = &n.left;
+        }
+    }
+}
+
+impl <'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.stack.pop()?;
+        self.push_left(&n.right);
+        Some((&n.key, &n.value))
+    }
+}
+
+impl <K: SpaceUsage, V: SpaceUsage> SpaceUsage for SplayTree<K, V> {
+    fn heap_size_in_bytes(&self) -> usize {
+        // iterative, as the tree can be as deep as the number of the nodes
+        let mut bytes = 0;
+        let mut stack: Vec<&Node<K, V>> = self.root.iter().map(|node| &**node).collect();
+        while let Some(node) = stack.pop() {
+            bytes += mem::size_of::<Node<K, V>>() + node.key.heap_size_in_bytes() + node.value.heap_size_in_bytes();
+            stack.extend(node.left.iter().chain(node.right.iter()).map(|child| &**child));
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut tree = SplayTree::new();
+        let mut expected = BTreeMap::new();
+        for _ in 0..3000 {
+            let k: u32 = rng.gen_range(0, 500);
+            match rng.gen_range(0, 3) {
+                0 => assert_eq!(expected.remove(&k), tree.remove(&k)),
+                1 => assert_eq!(expected.get(&k), tree.get(&k)),
+                _ => {
+                    let v: u32 = rng.gen();
+                    assert_eq!(expected.insert(k, v), tree.insert(k, v));
+                }
+            }
+            assert_eq!(expected.len(), tree.len());
+        }
+        assert_eq!(expected.iter().collect::<Vec<_>>(), tree.iter().collect::<Vec<_>>());
+        assert_eq!(expected.iter().next(), tree.first());
+        assert_eq!(expected.iter().next_back(), tree.last());
+    }
+
+    #[test]
+    fn split_merge() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let mut tree = SplayTree::new();
+            for i in 0..100 {
+                tree.insert(i, ());
+            }
+            let k = rng.gen_range(0, 110);
+            let right = tree.split(&k);
+            assert_eq!(k.min(100), tree.len());
+            assert_eq!(100 - k.min(100), right.len());
+            assert!(tree.iter().all(|(x, _)| *x < k));
+            assert!(right.iter().all(|(x, _)| *x >= k));
+
+            tree.merge(right);
+            assert_eq!((0..100).collect::<Vec<_>>(), tree.iter().map(|(k, _)| *k).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn merge_overlap() {
+        let mut left = SplayTree::new();
+        left.insert(3, ());
+        let mut right = SplayTree::new();
+        right.insert(2, ());
+        left.merge(right);
+    }
+
+    #[test]
+    fn merge_overlap_keeps_self() {
+        use std::panic::AssertUnwindSafe;
+        let mut left = SplayTree::new();
+        let mut right = SplayTree::new();
+        for k in 1..=3 {
+            left.insert(k, ());
+            right.insert(k + 1, ());
+        }
+        assert!(std::panic::catch_unwind(AssertUnwindSafe(|| left.merge(right))).is_err());
+        assert_eq!(vec![1, 2, 3], left.iter().map(|(k, _)| *k).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn sequential() {
+        // sorted insertion makes a degenerated tree at first
+        let mut tree = SplayTree::new();
+        for i in 0..100000 {
+            tree.insert(i, i);
+        }
+        for i in 0..100000 {
+            assert_eq!(Some(&i), tree.get(&i));
+        }
+        assert_eq!(Some(50000), tree.remove(&50000));
+        assert_eq!(99999, tree.len());
+    }
+
+    #[test]
+    fn space() {
+        use crate::space::SpaceUsage;
+        let mut tree = SplayTree::new();
+        // increasing keys make a path as deep as the number of the nodes
+        for k in 0..100000u64 {
+            tree.insert(k, k);
+        }
+        assert_eq!(100000 * mem::size_of::<Node<u64, u64>>(), tree.heap_size_in_bytes());
+    }
+}
@@ -0,0 +1,191 @@
+use super::heap::Heap;
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ord;
+use core::cmp::Ordering;
+use core::cmp::Ordering::Less;
+
+struct Entry<T> {
+    item: T,
+    compare: fn(lhs: &T, rhs: &T) -> Ordering,
+}
+
+/// 上位 `k` 件の選択器
+///
+/// ストリームから順に値を受け取り、順位の高い `k` 件だけを保持します。
+/// 保持している中で最も順位の低い値を根に持つ二分ヒープにより、1 件あたり `O(log k)` で処理します。
+///
+/// 順位は比較関数で与え、比較結果が `Less` となる値ほど順位が高いものとします。
+/// 順位が同じ値が上位 `k` 件の境界をまたぐ場合、そのうちどれが保持されるかは規定しません。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::topk::TopK;
+/// let mut topk = TopK::new(3);
+/// for v in [3, 1, 4, 1, 5, 9, 2, 6] {
+///     topk.offer(v);
+/// }
+/// assert_eq!(vec![9, 6, 5], topk.into_sorted_vec());
+///
+/// // by_frequency は U8WaveletMatrix::topk と同じく、頻度の大きい順、値の小さい順に並べます。
+/// let mut shard1 = TopK::by_frequency(2);
+/// shard1.offer((b'a', 3));
+/// shard1.offer((b'b', 5));
+/// let mut shard2 = TopK::by_frequency(2);
+/// shard2.offer((b'c', 3));
+/// shard1.merge(shard2);
+/// assert_eq!(vec![(b'b', 5), (b'a', 3)], shard1.into_sorted_vec());
+/// ```
+pub struct TopK<T> {
+    k: usize,
+    // the lowest ranked item at the top
+    heap: Heap<Entry<T>>,
+    compare: fn(lhs: &T, rhs: &T) -> Ordering,
+}
+
+impl <T: Ord> TopK<T> {
+    /// 大きい順に上位 `k` 件を選ぶ選択器を構築します。
+    pub fn new(k: usize) -> Self {
+        Self::with_compare(k, |lhs, rhs| rhs.cmp(lhs))
+    }
+}
+
+impl <V: Ord> TopK<(V, usize)> {
+    /// `(値, 頻度)` の組を、頻度の大きい順、頻度が同じ場合は値の小さい順に上位 `k` 件選ぶ選択器を構築します。
+    ///
+    /// [`crate::bits::wavelet_matrix::U8WaveletMatrix::topk()`] と同じ順序です。
+    pub fn by_frequency(k: usize) -> Self {
+        Self::with_compare(k, |lhs, rhs| rhs.1.cmp(&lhs.1).then_with(|| lhs.0.cmp(&rhs.0)))
+    }
+}
+
+impl <T> TopK<T> {
+    /// 上位 `k` 件を選ぶ選択器を構築します。
+    ///
+    /// 比較には与えられた関数が使われ、比較結果が `Less` となる値ほど順位が高いものとします。
+    pub fn with_compare(k: usize, compare: fn(lhs: &T, rhs: &T) -> Ordering) -> Self {
+        let mut heap = Heap::with_compare(|lhs: &Entry<T>, rhs| (lhs.compare)(&rhs.item, &lhs.item));
+        heap.reserve(k);
+        TopK { k, heap, compare }
+    }
+
+    /// 保持する件数の上限 `k` を返します。
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// 保持している件数を返します。
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// 何も保持していない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// 値 `item` を受け取ります。上位 `k` 件に入り、保持された場合に `true` を返します。
+    pub fn offer(&mut self, item: T) -> bool {
+        if self.heap.len() < self.k {
+            self.heap.push(Entry { item, compare: self.compare });
+            return true;
+        }
+        match self.heap.peek() {
+            Some(worst) if (self.compare)(&item, &worst.item) == Less => {
+                self.heap.pop();
+                self.heap.push(Entry { item, compare: self.compare });
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 別の選択器 `other` が保持している値をすべて受け取ります。
+    ///
+    /// 分割したストリームそれぞれの上位 `k` 件を統合して、全体の上位 `k` 件を求めるのに使います。
+    pub fn merge(&mut self, mut other: TopK<T>) {
+        while let Some(entry) = other.heap.pop() {
+            self.offer(entry.item);
+        }
+    }
+
+    /// 保持している値を、順位の高い順に並べて返します。
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut vec: Vec<T> = core::iter::from_fn(|| self.heap.pop().map(|e| e.item)).collect();
+        vec.reverse();
+        vec
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for Entry<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("item", &self.item)]
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for TopK<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("heap", &self.heap)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for k in [0, 1, 5, 100] {
+            let values: Vec<u32> = (0..50).map(|_| rng.gen_range(0, 30)).collect();
+            let mut topk = TopK::new(k);
+            for v in &values {
+                topk.offer(*v);
+            }
+            let mut expected = values.clone();
+            expected.sort_by(|a, b| b.cmp(a));
+            expected.truncate(k);
+            assert_eq!(expected.len(), topk.len());
+            assert_eq!(expected, topk.into_sorted_vec());
+        }
+    }
+
+    #[test]
+    fn merge_shards() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let values: Vec<u32> = (0..1000).map(|_| rng.gen()).collect();
+        let mut merged = TopK::with_compare(10, |lhs: &u32, rhs| lhs.cmp(rhs));
+        for shard in values.chunks(100) {
+            let mut topk = TopK::with_compare(10, |lhs: &u32, rhs| lhs.cmp(rhs));
+            shard.iter().for_each(|v| { topk.offer(*v); });
+            merged.merge(topk);
+        }
+        let mut expected = values.clone();
+        expected.sort();
+        expected.truncate(10);
+        assert_eq!(expected, merged.into_sorted_vec());
+    }
+
+    #[test]
+    fn same_order_as_wavelet_matrix() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let u8s: Vec<u8> = (0..1000).map(|_| rng.gen_range(0, 20)).collect();
+        let wmat = NaiveU8WaveletMatrix::new(&u8s);
+        let mut counts = [0; 256];
+        u8s.iter().for_each(|v| counts[*v as usize] += 1);
+        let mut topk = TopK::by_frequency(5);
+        for (v, c) in counts.iter().enumerate().rev() {
+            if *c > 0 {
+                topk.offer((v as u8, *c));
+            }
+        }
+        assert_eq!(wmat.topk(0, u8s.len(), 5), topk.into_sorted_vec());
+    }
+}
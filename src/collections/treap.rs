@@ -0,0 +1,754 @@
+use crate::collections::arena::BoxNodes;
+use crate::collections::arena::NodeStorage;
+use crate::random::DefaultRng;
+use crate::space::SpaceUsage;
+
+use alloc::vec::Vec;
+use alloc::vec;
+use core::cmp::Ordering;
+use core::ops::Bound;
+use core::ops::RangeBounds;
+
+use rand::Rng;
+use rand::RngCore;
+
+type Link<K, V, A, M> = Option<<A as NodeStorage>::Link<Node<K, V, A, M>>>;
+type Pool<K, V, A, M> = <A as NodeStorage>::Pool<Node<K, V, A, M>>;
+type Split<K, V, A, M> = (Link<K, V, A, M>, Link<K, V, A, M>);
+
+/// 部分木の要約
+///
+/// [`Treap`] の型引数 `M` に指定すると、各ノードにそのノードを根とする部分木の要約を持たせ、
+/// 挿入・削除・回転のたびに子の側から計算し直します。
+/// 要約は [`Treap::root()`] からたどる [`NodeRef::summary()`] で参照し、部分木を読み飛ばす探索に使います。
+///
+/// 既定の `()` は何も要約しません。
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use rust_study::collections::arena::BoxNodes;
+/// use rust_study::collections::treap::Augment;
+/// use rust_study::collections::treap::Treap;
+/// use rust_study::random::DefaultRng;
+///
+/// // 部分木の値の合計
+/// struct Sum(u64);
+///
+/// impl Augment<char, u64> for Sum {
+///     fn summarize(_: &char, value: &u64, left: Option<&Self>, right: Option<&Self>) -> Self {
+///         Sum(value + left.map_or(0, |s| s.0) + right.map_or(0, |s| s.0))
+///     }
+/// }
+///
+/// let mut treap: Treap<char, u64, DefaultRng, BoxNodes, Sum> = Treap::default();
+/// for (i, c) in "treap".chars().enumerate() {
+///     treap.insert(c, i as u64);
+/// }
+/// assert_eq!(10, treap.root().unwrap().summary().0);
+/// treap.modify(&'a', |v| *v = 100);
+/// assert_eq!(107, treap.root().unwrap().summary().0);
+/// # }
+/// ```
+pub trait Augment<K, V> {
+    /// キー `key` と値 `value` のノードの要約を、左右の子の部分木の要約 `left`, `right` から計算します。
+    fn summarize(key: &K, value: &V, left: Option<&Self>, right: Option<&Self>) -> Self;
+}
+
+impl <K, V> Augment<K, V> for () {
+    fn summarize(_: &K, _: &V, _: Option<&()>, _: Option<&()>) {}
+}
+
+pub(crate) struct Node<K, V, A: NodeStorage, M> {
+    key: K,
+    value: V,
+    priority: u64,
+    size: usize,
+    summary: M,
+    left: Link<K, V, A, M>,
+    right: Link<K, V, A, M>,
+}
+
+impl <K, V, A: NodeStorage, M: Augment<K, V>> Node<K, V, A, M> {
+    fn new(key: K, value: V, priority: u64) -> Self {
+        let summary = M::summarize(&key, &value, None, None);
+        Node { key, value, priority, size: 1, summary, left: None, right: None }
+    }
+}
+
+impl <K: Clone, V: Clone, A: NodeStorage, M: Clone> Clone for Node<K, V, A, M> where Link<K, V, A, M>: Clone {
+    fn clone(&self) -> Self {
+        Node {
+            key: self.key.clone(),
+            value: self.value.clone(),
+            priority: self.priority,
+            size: self.size,
+            summary: self.summary.clone(),
+            left: self.left.clone(),
+            right: self.right.clone(),
+        }
+    }
+}
+
+fn size<K, V, A: NodeStorage, M>(pool: &Pool<K, V, A, M>, node: &Link<K, V, A, M>) -> usize {
+    node.as_ref().map_or(0, |n| A::get(pool, n).size)
+}
+
+fn summary<'a, K: 'a, V: 'a, A: NodeStorage + 'a, M>(pool: &'a Pool<K, V, A, M>, node: &'a Link<K, V, A, M>) -> Option<&'a M> {
+    node.as_ref().map(|n| &A::get(pool, n).summary)
+}
+
+fn update<K, V, A: NodeStorage, M: Augment<K, V>>(pool: &mut Pool<K, V, A, M>, link: &mut A::Link<Node<K, V, A, M>>) {
+    let n = A::get(pool, link);
+    let size = 1 + size::<K, V, A, M>(pool, &n.left) + size::<K, V, A, M>(pool, &n.right);
+    let summary = M::summarize(&n.key, &n.value, summary::<K, V, A, M>(pool, &n.left), summary::<K, V, A, M>(pool, &n.right));
+    let n = A::get_mut(pool, link);
+    n.size = size;
+    n.summary = summary;
+}
+
+/// 順序統計木 (Treap)
+///
+/// キーの順序による二分探索木と、ランダムな優先度によるヒープを兼ねた平衡二分探索木。
+/// 挿入・削除・検索に加えて、 `k` 番目のキーの取得やキーの順位の計算を期待値 `O(log n)` で行います。
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use rust_study::collections::treap::Treap;
+/// let mut treap = Treap::new();
+/// for (i, c) in "treap".chars().enumerate() {
+///     treap.insert(c, i);
+/// }
+/// assert_eq!(5, treap.len());
+/// assert_eq!(Some(&3), treap.get(&'a'));
+///
+/// // kth は k 番目(0-based)に小さいキーとその値を返します。
+/// assert_eq!(Some((&'a', &3)), treap.kth(0));
+/// assert_eq!(Some((&'t', &0)), treap.kth(4));
+///
+/// // rank はキーより小さいキーの個数を返します。
+/// assert_eq!(2, treap.rank(&'p'));
+///
+/// // range は範囲内のキーを小さい順に返します。
+/// assert_eq!(vec!['e', 'p', 'r'], treap.range('b'..'s').map(|(k, _)| *k).collect::<Vec<char>>());
+///
+/// assert_eq!(Some(1), treap.remove(&'r'));
+/// assert_eq!(None, treap.remove(&'r'));
+/// assert_eq!(4, treap.len());
+/// # }
+/// ```
+///
+/// 優先度は、型引数 `R` の乱数生成器で選びます。
+/// [`Self::with_rng()`] にシードを固定した乱数生成器を渡すと、同じ操作の列から同じ形の木ができます。
+///
+/// ノードの確保の仕方は、型引数 `A` で選べます ([`NodeStorage`] を参照してください)。
+///
+/// 型引数 `M` に [`Augment`] を指定すると、各ノードに部分木の要約を持たせられます。
+/// 区間木 ([`IntervalTree`](crate::collections::interval_tree::IntervalTree)) などは、この要約を使って Treap の上に作られています。
+pub struct Treap<K, V, R = DefaultRng, A: NodeStorage = BoxNodes, M = ()> {
+    root: Link<K, V, A, M>,
+    pool: Pool<K, V, A, M>,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl <K: Ord, V> Treap<K, V> {
+    /// 空の Treap を構築します。
+    ///
+    /// ノードの確保の仕方や要約を選ぶ場合は、 `Treap::<K, V, DefaultRng, A, M>::default()` で構築します。
+    pub fn new() -> Self {
+        Self::with_rng(DefaultRng)
+    }
+}
+
+impl <K: Ord, V, R: RngCore> Treap<K, V, R> {
+    /// 優先度を乱数生成器 `rng` で選ぶ、空の Treap を構築します。
+    pub fn with_rng(rng: R) -> Self {
+        Self::with_storage(rng)
+    }
+}
+
+impl <K: Ord, V, R: RngCore, A: NodeStorage> Treap<K, V, R, A> {
+    /// キー `key` に対応付けられた値を可変参照します。
+    ///
+    /// 値から要約を計算する Treap では、要約を計算し直す [`Self::modify()`] を使います。
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let root = self.root.as_mut()?;
+        // descends to the key, or to the node where the search falls off the tree
+        let n = A::descend(
+            &mut self.pool,
+            root,
+            |n| match key.cmp(&n.key) {
+                Ordering::Less => n.left.as_ref().map(|_| Ordering::Less),
+                Ordering::Greater => n.right.as_ref().map(|_| Ordering::Greater),
+                Ordering::Equal => None,
+            },
+            |n, ord| if ord == Ordering::Less { n.left.as_mut() } else { n.right.as_mut() }.unwrap(),
+        );
+        if n.key == *key {
+            Some(&mut n.value)
+        } else {
+            None
+        }
+    }
+}
+
+impl <K: Ord, V, R: RngCore, A: NodeStorage, M: Augment<K, V>> Treap<K, V, R, A, M> {
+    /// 優先度を乱数生成器 `rng` で選ぶ、空の Treap を構築します。
+    ///
+    /// ノードの確保の仕方と部分木の要約は、型引数 `A` と `M` で選びます。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rand::SeedableRng;
+    /// use rand::rngs::StdRng;
+    /// use rust_study::collections::arena::ArenaNodes;
+    /// use rust_study::collections::treap::Treap;
+    /// let mut treap = Treap::<_, _, _, ArenaNodes>::with_storage(StdRng::seed_from_u64(42));
+    /// treap.insert('a', 1);
+    /// assert_eq!(Some(&1), treap.get(&'a'));
+    /// ```
+    pub fn with_storage(rng: R) -> Self {
+        Self::with_pool(rng, Default::default())
+    }
+
+    // an empty treap whose storage is configured by `pool`
+    pub(crate) fn with_pool(rng: R, pool: Pool<K, V, A, M>) -> Self {
+        Treap { root: None, pool, rng }
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        size::<K, V, A, M>(&self.pool, &self.root)
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// キー `key` に値 `value` を対応付けます。
+    ///
+    /// すでにキーが存在した場合、値を置き換えて古い値を返します。
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut value = Some(value);
+        if let Some(old) = self.modify(&key, |v| core::mem::replace(v, value.take().unwrap())) {
+            return Some(old);
+        }
+        let priority = self.rng.gen();
+        let (left, right) = split::<K, V, A, M>(&mut self.pool, self.root.take(), &key);
+        let node = Some(A::alloc(&mut self.pool, Node::new(key, value.unwrap(), priority)));
+        let left = merge::<K, V, A, M>(&mut self.pool, left, node);
+        self.root = merge::<K, V, A, M>(&mut self.pool, left, right);
+        None
+    }
+
+    /// キー `key` を取り除き、対応付けられていた値を返します。
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        remove::<K, V, A, M>(&mut self.pool, &mut self.root, key)
+    }
+
+    /// キー `key` に対応付けられた値に `f` を適用し、その結果を返します。キーが存在しない場合、 `None` を返します。
+    ///
+    /// 適用後に、根からキーまでの経路の要約を計算し直します。
+    pub fn modify<T>(&mut self, key: &K, f: impl FnOnce(&mut V) -> T) -> Option<T> {
+        modify::<K, V, A, M, T>(&mut self.pool, &mut self.root, key, f)
+    }
+
+    /// キー `key` に対応付けられた値を参照します。
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut node = &self.root;
+        while let Some(n) = node {
+            let n = A::get(&self.pool, n);
+            match key.cmp(&n.key) {
+                Ordering::Less => node = &n.left,
+                Ordering::Greater => node = &n.right,
+                Ordering::Equal => return Some(&n.value),
+            }
+        }
+        None
+    }
+
+    /// キー `key` が存在する場合に、 `true` を返します。
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// `k` 番目(0-based)に小さいキーとその値を返します。 `k` が要素数以上の場合、 `None` を返します。
+    pub fn kth(&self, mut k: usize) -> Option<(&K, &V)> {
+        let mut node = &self.root;
+        while let Some(n) = node {
+            let n = A::get(&self.pool, n);
+            let left = size::<K, V, A, M>(&self.pool, &n.left);
+            match k.cmp(&left) {
+                Ordering::Less => node = &n.left,
+                Ordering::Equal => return Some((&n.key, &n.value)),
+                Ordering::Greater => {
+                    k -= left + 1;
+                    node = &n.right;
+                }
+            }
+        }
+        None
+    }
+
+    /// キー `key` より小さいキーの個数を返します。
+    ///
+    /// `key` が存在する場合、その順位(0-based)と一致します。
+    pub fn rank(&self, key: &K) -> usize {
+        let mut rank = 0;
+        let mut node = &self.root;
+        while let Some(n) = node {
+            let n = A::get(&self.pool, n);
+            if n.key < *key {
+                rank += size::<K, V, A, M>(&self.pool, &n.left) + 1;
+                node = &n.right;
+            } else {
+                node = &n.left;
+            }
+        }
+        rank
+    }
+
+    /// 根のノードへの参照を返します。空の場合、 `None` を返します。
+    pub fn root(&self) -> Option<NodeRef<'_, K, V, A, M>> {
+        self.root.as_ref().map(|link| NodeRef::new(&self.pool, link))
+    }
+
+    /// すべてのキーと値の組を、キーの小さい順に返すイテレータを返します。
+    pub fn iter(&self) -> Range<'_, K, V, A, M> {
+        Range { pool: &self.pool, stack: self.lower_bound_path(Bound::Unbounded), end: Bound::Unbounded }
+    }
+
+    /// `range` に含まれるキーと値の組を、キーの小さい順に返すイテレータを返します。
+    pub fn range<B: RangeBounds<K>>(&self, range: B) -> Range<'_, K, V, A, M> where K: Clone {
+        Range { pool: &self.pool, stack: self.lower_bound_path(range.start_bound()), end: range.end_bound().cloned() }
+    }
+
+    fn lower_bound_path(&self, start: Bound<&K>) -> Vec<&Node<K, V, A, M>> {
+        let mut stack = vec![];
+        let mut node = &self.root;
+        while let Some(n) = node {
+            let n = A::get(&self.pool, n);
+            let ge_start = match start {
+                Bound::Included(s) => n.key >= *s,
+                Bound::Excluded(s) => n.key > *s,
+                Bound::Unbounded => true,
+            };
+            if ge_start {
+                stack.push(n);
+                node = &n.left;
+            } else {
+                node = &n.right;
+            }
+        }
+        stack
+    }
+}
+
+impl <K, V, R, A: NodeStorage, M> Treap<K, V, R, A, M> {
+    // a treap sharing the nodes, for the storages whose links can be shared
+    pub(crate) fn share(&self) -> Self
+    where
+        R: Clone,
+        Link<K, V, A, M>: Clone,
+        Pool<K, V, A, M>: Clone,
+    {
+        Treap { root: self.root.clone(), pool: self.pool.clone(), rng: self.rng.clone() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl <K: Ord, V, A: NodeStorage, M: Augment<K, V>> Default for Treap<K, V, DefaultRng, A, M> {
+    fn default() -> Self {
+        Self::with_storage(DefaultRng)
+    }
+}
+
+/// [`Treap`] のノードへの参照
+///
+/// [`Treap::root()`] から子をたどり、部分木の要約 ([`Augment`]) を使って部分木を読み飛ばす探索に使います。
+pub struct NodeRef<'a, K, V, A: NodeStorage = BoxNodes, M = ()> {
+    pool: &'a Pool<K, V, A, M>,
+    node: &'a Node<K, V, A, M>,
+}
+
+impl <K, V, A: NodeStorage, M> Clone for NodeRef<'_, K, V, A, M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl <K, V, A: NodeStorage, M> Copy for NodeRef<'_, K, V, A, M> {}
+
+impl <'a, K, V, A: NodeStorage, M> NodeRef<'a, K, V, A, M> {
+    fn new(pool: &'a Pool<K, V, A, M>, link: &'a A::Link<Node<K, V, A, M>>) -> Self {
+        NodeRef { pool, node: A::get(pool, link) }
+    }
+
+    /// ノードのキーを返します。
+    pub fn key(&self) -> &'a K {
+        &self.node.key
+    }
+
+    /// ノードの値を返します。
+    pub fn value(&self) -> &'a V {
+        &self.node.value
+    }
+
+    /// ノードを根とする部分木の要約を返します。
+    pub fn summary(&self) -> &'a M {
+        &self.node.summary
+    }
+
+    /// ノードを根とする部分木の要素数を返します。
+    pub fn subtree_len(&self) -> usize {
+        self.node.size
+    }
+
+    /// 左の子を返します。
+    pub fn left(&self) -> Option<Self> {
+        self.node.left.as_ref().map(|link| NodeRef::new(self.pool, link))
+    }
+
+    /// 右の子を返します。
+    pub fn right(&self) -> Option<Self> {
+        self.node.right.as_ref().map(|link| NodeRef::new(self.pool, link))
+    }
+}
+
+/// [`Treap::range()`] の返すイテレータ
+pub struct Range<'a, K, V, A: NodeStorage = BoxNodes, M = ()> {
+    pool: &'a Pool<K, V, A, M>,
+    stack: Vec<&'a Node<K, V, A, M>>,
+    end: Bound<K>,
+}
+
+impl <'a, K: Ord, V, A: NodeStorage, M> Iterator for Range<'a, K, V, A, M> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.stack.pop()?;
+        let in_range = match &self.end {
+            Bound::Included(e) => n.key <= *e,
+            Bound::Excluded(e) => n.key < *e,
+            Bound::Unbounded => true,
+        };
+        if !in_range {
+            self.stack.clear();
+            return None;
+        }
+        let mut node = &n.right;
+        while let Some(c) = node {
+            let c = A::get(self.pool, c);
+            self.stack.push(c);
+            node = &c.left;
+        }
+        Some((&n.key, &n.value))
+    }
+}
+
+// split into (< key, >= key)
+fn split<K: Ord, V, A: NodeStorage, M: Augment<K, V>>(pool: &mut Pool<K, V, A, M>, node: Link<K, V, A, M>, key: &K) -> Split<K, V, A, M> {
+    let Some(mut link) = node else {
+        return (None, None);
+    };
+    let n = A::get_mut(pool, &mut link);
+    if n.key < *key {
+        let right = n.right.take();
+        let (l, r) = split::<K, V, A, M>(pool, right, key);
+        A::get_mut(pool, &mut link).right = l;
+        update::<K, V, A, M>(pool, &mut link);
+        (Some(link), r)
+    } else {
+        let left = n.left.take();
+        let (l, r) = split::<K, V, A, M>(pool, left, key);
+        A::get_mut(pool, &mut link).left = r;
+        update::<K, V, A, M>(pool, &mut link);
+        (l, Some(link))
+    }
+}
+
+fn merge<K, V, A: NodeStorage, M: Augment<K, V>>(pool: &mut Pool<K, V, A, M>, left: Link<K, V, A, M>, right: Link<K, V, A, M>) -> Link<K, V, A, M> {
+    match (left, right) {
+        (None, r) => r,
+        (l, None) => l,
+        (Some(mut l), Some(mut r)) => {
+            if A::get(pool, &l).priority > A::get(pool, &r).priority {
+                let lr = A::get_mut(pool, &mut l).right.take();
+                let merged = merge::<K, V, A, M>(pool, lr, Some(r));
+                A::get_mut(pool, &mut l).right = merged;
+                update::<K, V, A, M>(pool, &mut l);
+                Some(l)
+            } else {
+                let rl = A::get_mut(pool, &mut r).left.take();
+                let merged = merge::<K, V, A, M>(pool, Some(l), rl);
+                A::get_mut(pool, &mut r).left = merged;
+                update::<K, V, A, M>(pool, &mut r);
+                Some(r)
+            }
+        }
+    }
+}
+
+fn remove<K: Ord, V, A: NodeStorage, M: Augment<K, V>>(pool: &mut Pool<K, V, A, M>, node: &mut Link<K, V, A, M>, key: &K) -> Option<V> {
+    let link = node.as_mut()?;
+    let n = A::get_mut(pool, link);
+    let result = match key.cmp(&n.key) {
+        Ordering::Less => {
+            let mut left = n.left.take();
+            let result = remove::<K, V, A, M>(pool, &mut left, key);
+            A::get_mut(pool, link).left = left;
+            result
+        }
+        Ordering::Greater => {
+            let mut right = n.right.take();
+            let result = remove::<K, V, A, M>(pool, &mut right, key);
+            A::get_mut(pool, link).right = right;
+            result
+        }
+        Ordering::Equal => {
+            let n = A::free(pool, node.take().unwrap());
+            *node = merge::<K, V, A, M>(pool, n.left, n.right);
+            return Some(n.value);
+        }
+    };
+    if result.is_some() {
+        update::<K, V, A, M>(pool, link);
+    }
+    result
+}
+
+fn modify<K: Ord, V, A: NodeStorage, M: Augment<K, V>, T>(
+    pool: &mut Pool<K, V, A, M>,
+    node: &mut Link<K, V, A, M>,
+    key: &K,
+    f: impl FnOnce(&mut V) -> T,
+) -> Option<T> {
+    let link = node.as_mut()?;
+    let n = A::get_mut(pool, link);
+    let result = match key.cmp(&n.key) {
+        Ordering::Less => {
+            let mut left = n.left.take();
+            let result = modify::<K, V, A, M, T>(pool, &mut left, key, f);
+            A::get_mut(pool, link).left = left;
+            result
+        }
+        Ordering::Greater => {
+            let mut right = n.right.take();
+            let result = modify::<K, V, A, M, T>(pool, &mut right, key, f);
+            A::get_mut(pool, link).right = right;
+            result
+        }
+        Ordering::Equal => Some(f(&mut n.value)),
+    };
+    if result.is_some() {
+        update::<K, V, A, M>(pool, link);
+    }
+    result
+}
+
+impl <K: SpaceUsage, V: SpaceUsage, A: NodeStorage, M: SpaceUsage> SpaceUsage for Node<K, V, A, M> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.key.heap_size_in_bytes()
+            + self.value.heap_size_in_bytes()
+            + self.summary.heap_size_in_bytes()
+            + self.left.iter().chain(&self.right).map(|link| A::link_heap_size(link)).sum::<usize>()
+    }
+}
+
+/// ノードを [`crate::collections::arena::ArenaNodes`] で確保する場合、ノードはアリーナの領域で数えます。
+impl <K: SpaceUsage, V: SpaceUsage, R, A: NodeStorage, M: SpaceUsage> SpaceUsage for Treap<K, V, R, A, M> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.root.as_ref().map_or(0, |link| A::link_heap_size(link)) + A::pool_heap_size(&self.pool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::arena::ArenaNodes;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn insert_remove() {
+        let mut treap = Treap::with_rng(StdRng::seed_from_u64(0));
+        assert!(treap.is_empty());
+        assert_eq!(None, treap.insert(3, "c"));
+        assert_eq!(None, treap.insert(1, "a"));
+        assert_eq!(None, treap.insert(2, "b"));
+        assert_eq!(Some("c"), treap.insert(3, "C"));
+        assert_eq!(3, treap.len());
+        assert_eq!(Some(&"C"), treap.get(&3));
+        assert!(treap.contains_key(&1));
+        assert!(!treap.contains_key(&4));
+
+        *treap.get_mut(&1).unwrap() = "A";
+        assert_eq!(Some("A"), treap.remove(&1));
+        assert_eq!(None, treap.remove(&1));
+        assert_eq!(2, treap.len());
+        assert_eq!(None, treap.get(&1));
+    }
+
+    fn check_random<A: NodeStorage>() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut treap: Treap<u32, u32, _, A> = Treap::with_storage(StdRng::seed_from_u64(1));
+        let mut expected = BTreeMap::new();
+        for _ in 0..3000 {
+            let k: u32 = rng.gen_range(0, 500);
+            if rng.gen_range(0, 3) == 0 {
+                assert_eq!(expected.remove(&k), treap.remove(&k));
+            } else {
+                let v: u32 = rng.gen();
+                assert_eq!(expected.insert(k, v), treap.insert(k, v));
+            }
+            assert_eq!(expected.len(), treap.len());
+        }
+
+        let keys: Vec<u32> = expected.keys().copied().collect();
+        for (i, k) in keys.iter().enumerate() {
+            assert_eq!(Some((k, &expected[k])), treap.kth(i));
+            assert_eq!(i, treap.rank(k));
+        }
+        assert_eq!(None, treap.kth(keys.len()));
+        for k in 0..500 {
+            assert_eq!(keys.iter().filter(|x| **x < k).count(), treap.rank(&k));
+        }
+
+        assert_eq!(
+            expected.iter().collect::<Vec<_>>(),
+            treap.iter().collect::<Vec<_>>()
+        );
+        for _ in 0..100 {
+            let s = rng.gen_range(0, 500);
+            let e = rng.gen_range(s, 500);
+            assert_eq!(
+                expected.range(s..e).collect::<Vec<_>>(),
+                treap.range(s..e).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                expected.range(s..=e).collect::<Vec<_>>(),
+                treap.range(s..=e).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                expected.range((Bound::Excluded(s), Bound::Unbounded)).collect::<Vec<_>>(),
+                treap.range((Bound::Excluded(s), Bound::Unbounded)).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn random() {
+        check_random::<BoxNodes>();
+    }
+
+    #[test]
+    fn random_arena() {
+        check_random::<ArenaNodes>();
+    }
+
+    // the keys in preorder, which determine the shape of the tree
+    fn preorder<K: Copy, V, A: NodeStorage, M>(node: Option<NodeRef<'_, K, V, A, M>>, keys: &mut Vec<K>) {
+        if let Some(n) = node {
+            keys.push(*n.key());
+            preorder(n.left(), keys);
+            preorder(n.right(), keys);
+        }
+    }
+
+    #[test]
+    fn with_rng() {
+        fn shape<A: NodeStorage>(seed: u64) -> Vec<i32> {
+            let mut treap = Treap::<_, _, _, A>::with_storage(StdRng::seed_from_u64(seed));
+            for k in 0..100 {
+                treap.insert(k, ());
+            }
+            treap.remove(&50);
+            let mut keys = vec![];
+            preorder(treap.root(), &mut keys);
+            keys
+        }
+        assert_eq!(shape::<BoxNodes>(1), shape::<BoxNodes>(1));
+        assert_ne!(shape::<BoxNodes>(1), shape::<BoxNodes>(2));
+        assert_eq!(shape::<BoxNodes>(1), shape::<ArenaNodes>(1));
+    }
+
+    #[test]
+    fn arena_frees_nodes() {
+        let mut treap = Treap::<_, _, _, ArenaNodes>::with_storage(StdRng::seed_from_u64(0));
+        for k in 0..100 {
+            treap.insert(k, k);
+        }
+        for k in 0..50 {
+            treap.remove(&k);
+        }
+        for k in 100..150 {
+            treap.insert(k, k);
+        }
+        assert_eq!(100, treap.len());
+        assert_eq!(100, treap.pool.len());
+        assert!(treap.size_in_bytes() > 100 * core::mem::size_of::<Node<u32, u32, ArenaNodes, ()>>());
+    }
+
+    // the sum of the values and the largest key in the subtree
+    #[derive(Debug, PartialEq)]
+    struct SumMax(u64, u32);
+
+    impl Augment<u32, u64> for SumMax {
+        fn summarize(key: &u32, value: &u64, left: Option<&Self>, right: Option<&Self>) -> Self {
+            let sum = value + left.map_or(0, |s| s.0) + right.map_or(0, |s| s.0);
+            SumMax(sum, right.map_or(*key, |s| s.1))
+        }
+    }
+
+    fn check_augment<A: NodeStorage>() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut treap: Treap<u32, u64, _, A, SumMax> = Treap::with_storage(StdRng::seed_from_u64(1));
+        let mut expected = BTreeMap::new();
+        for _ in 0..3000 {
+            let k: u32 = rng.gen_range(0, 500);
+            match rng.gen_range(0, 4) {
+                0 => assert_eq!(expected.remove(&k), treap.remove(&k)),
+                1 => {
+                    let d: u64 = rng.gen_range(0, 100);
+                    assert_eq!(expected.get_mut(&k).map(|v| *v += d), treap.modify(&k, |v| *v += d));
+                }
+                _ => {
+                    let v: u64 = rng.gen_range(0, 100);
+                    assert_eq!(expected.insert(k, v), treap.insert(k, v));
+                }
+            }
+            let sum_max = treap.root().map(|n| n.summary());
+            let expected_sum_max = expected.last_key_value().map(|(k, _)| SumMax(expected.values().sum(), *k));
+            assert_eq!(expected_sum_max.as_ref(), sum_max);
+        }
+
+        // every subtree keeps its own summary
+        fn check<A: NodeStorage>(node: Option<NodeRef<'_, u32, u64, A, SumMax>>) -> Option<(u64, u32, usize)> {
+            let n = node?;
+            let (l, r) = (check(n.left()), check(n.right()));
+            let sum = n.value() + l.map_or(0, |l| l.0) + r.map_or(0, |r| r.0);
+            let len = 1 + l.map_or(0, |l| l.2) + r.map_or(0, |r| r.2);
+            assert_eq!(&SumMax(sum, r.map_or(*n.key(), |r| r.1)), n.summary());
+            assert_eq!(len, n.subtree_len());
+            Some((sum, n.summary().1, len))
+        }
+        check(treap.root());
+    }
+
+    #[test]
+    fn augment() {
+        check_augment::<BoxNodes>();
+    }
+
+    #[test]
+    fn augment_arena() {
+        check_augment::<ArenaNodes>();
+    }
+}
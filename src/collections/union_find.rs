@@ -0,0 +1,160 @@
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Union-Find (素集合データ構造)
+///
+/// 要素を互いに素な集合に分けて管理し、集合の併合と、2 つの要素が同じ集合に属するかの判定を
+/// 経路圧縮と大きさによる併合 (union by size) によって、ならし `O(α(n))` で行います。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::union_find::UnionFind;
+/// let mut uf = UnionFind::new(5);
+/// assert!(uf.union(0, 1));
+/// assert!(uf.union(3, 4));
+/// assert!(!uf.union(1, 0));
+/// assert!(uf.same(0, 1));
+/// assert!(!uf.same(1, 3));
+/// assert_eq!(2, uf.size(0));
+/// assert_eq!(3, uf.count());
+/// ```
+pub struct UnionFind {
+    // the parent, or the negated size for a root
+    parent: Vec<isize>,
+    count: usize,
+}
+
+impl UnionFind {
+    /// 要素 `0..n` がそれぞれ単独の集合をなす Union-Find を構築します。
+    pub fn new(n: usize) -> Self {
+        UnionFind { parent: vec![-1; n], count: n }
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    /// 要素がない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// 集合の個数を返します。
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// 要素 `x` の属する集合の代表元を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` is out of range.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut r = x;
+        while self.parent[r] >= 0 {
+            r = self.parent[r] as usize;
+        }
+        let mut x = x;
+        while self.parent[x] >= 0 {
+            let p = self.parent[x] as usize;
+            self.parent[x] = r as isize;
+            x = p;
+        }
+        r
+    }
+
+    /// 要素 `x` の属する集合の代表元を、経路圧縮をせずに返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` is out of range.
+    pub fn root(&self, x: usize) -> usize {
+        let mut r = x;
+        while self.parent[r] >= 0 {
+            r = self.parent[r] as usize;
+        }
+        r
+    }
+
+    /// 要素 `x` と `y` が同じ集合に属する場合に、 `true` を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` or `y` is out of range.
+    pub fn same(&mut self, x: usize, y: usize) -> bool {
+        self.find(x) == self.find(y)
+    }
+
+    /// 要素 `x` の属する集合の大きさを返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` is out of range.
+    pub fn size(&mut self, x: usize) -> usize {
+        let r = self.find(x);
+        (-self.parent[r]) as usize
+    }
+
+    /// 要素 `x` と `y` の属する集合を併合します。併合した場合に、 `true` を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` or `y` is out of range.
+    pub fn union(&mut self, x: usize, y: usize) -> bool {
+        let mut rx = self.find(x);
+        let mut ry = self.find(y);
+        if rx == ry {
+            return false;
+        }
+        if self.parent[rx] > self.parent[ry] {
+            core::mem::swap(&mut rx, &mut ry);
+        }
+        self.parent[rx] += self.parent[ry];
+        self.parent[ry] = rx as isize;
+        self.count -= 1;
+        true
+    }
+}
+
+impl SpaceUsage for UnionFind {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("parent", &self.parent)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let n = 50;
+        let mut uf = UnionFind::new(n);
+        let mut comp: Vec<usize> = (0..n).collect();
+        for _ in 0..1000 {
+            let x = rng.gen_range(0, n);
+            let y = rng.gen_range(0, n);
+            if rng.gen_range(0, 2) == 0 {
+                let (cx, cy) = (comp[x], comp[y]);
+                comp.iter_mut().filter(|c| **c == cy).for_each(|c| *c = cx);
+                assert_eq!(cx != cy, uf.union(x, y));
+            } else {
+                assert_eq!(comp[x] == comp[y], uf.root(x) == uf.root(y));
+                assert_eq!(comp[x] == comp[y], uf.same(x, y));
+                assert_eq!(comp.iter().filter(|c| **c == comp[x]).count(), uf.size(x));
+            }
+            let mut roots = comp.clone();
+            roots.sort();
+            roots.dedup();
+            assert_eq!(roots.len(), uf.count());
+        }
+    }
+}
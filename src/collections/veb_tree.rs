@@ -0,0 +1,446 @@
+use crate::space::SpaceUsage;
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+const LEAF_BITS: u32 = 6;
+
+enum Node {
+    Leaf(u64),
+    Inner(Box<Inner>),
+}
+
+struct Inner {
+    lo_bits: u32,
+    min: Option<u32>,
+    max: u32,
+    summary: Node,
+    clusters: Vec<Option<Node>>,
+}
+
+impl Node {
+    fn new(bits: u32) -> Self {
+        if bits <= LEAF_BITS {
+            return Node::Leaf(0);
+        }
+        let lo_bits = bits / 2;
+        let hi_bits = bits - lo_bits;
+        Node::Inner(Box::new(Inner {
+            lo_bits,
+            min: None,
+            max: 0,
+            summary: Node::new(hi_bits),
+            clusters: (0..1_usize << hi_bits).map(|_| None).collect(),
+        }))
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Node::Leaf(bm) => *bm == 0,
+            Node::Inner(n) => n.min.is_none(),
+        }
+    }
+
+    fn min(&self) -> Option<u32> {
+        match self {
+            Node::Leaf(bm) => if *bm == 0 { None } else { Some(bm.trailing_zeros()) },
+            Node::Inner(n) => n.min,
+        }
+    }
+
+    fn max(&self) -> Option<u32> {
+        match self {
+            Node::Leaf(bm) => if *bm == 0 { None } else { Some(63 - bm.leading_zeros()) },
+            Node::Inner(n) => n.min.map(|_| n.max),
+        }
+    }
+
+    fn contains(&self, x: u32) -> bool {
+        match self {
+            Node::Leaf(bm) => (bm >> x) & 1 == 1,
+            Node::Inner(n) => {
+                if n.min == Some(x) || (n.min.is_some() && n.max == x) {
+                    return true;
+                }
+                let (h, l) = n.split(x);
+                n.clusters[h].as_ref().is_some_and(|c| c.contains(l))
+            }
+        }
+    }
+
+    fn insert(&mut self, x: u32) -> bool {
+        match self {
+            Node::Leaf(bm) => {
+                let inserted = (*bm >> x) & 1 == 0;
+                *bm |= 1 << x;
+                inserted
+            }
+            Node::Inner(n) => {
+                let mut x = x;
+                match n.min {
+                    None => {
+                        n.min = Some(x);
+                        n.max = x;
+                        return true;
+                    }
+                    Some(min) if min == x => return false,
+                    Some(min) if x < min => {
+                        n.min = Some(x);
+                        x = min;
+                    }
+                    _ => {}
+                }
+                let (h, l) = n.split(x);
+                let lo_bits = n.lo_bits;
+                let cluster = n.clusters[h].get_or_insert_with(|| Node::new(lo_bits));
+                if cluster.is_empty() {
+                    n.summary.insert(h as u32);
+                }
+                let inserted = cluster.insert(l);
+                if x > n.max {
+                    n.max = x;
+                }
+                inserted
+            }
+        }
+    }
+
+    fn remove(&mut self, x: u32) -> bool {
+        match self {
+            Node::Leaf(bm) => {
+                let removed = (*bm >> x) & 1 == 1;
+                *bm &= !(1 << x);
+                removed
+            }
+            Node::Inner(n) => {
+                let mut x = x;
+                let min = match n.min {
+                    None => return false,
+                    Some(min) => min,
+                };
+                if min == n.max {
+                    if x == min {
+                        n.min = None;
+                        return true;
+                    }
+                    return false;
+                }
+                if x == min {
+                    // pull up the second smallest value as the new min
+                    let h = n.summary.min().unwrap() as usize;
+                    x = n.join(h, n.clusters[h].as_ref().unwrap().min().unwrap());
+                    n.min = Some(x);
+                }
+                let (h, l) = n.split(x);
+                let cluster = match &mut n.clusters[h] {
+                    None => return false,
+                    Some(c) => c,
+                };
+                if !cluster.remove(l) {
+                    return false;
+                }
+                if cluster.is_empty() {
+                    n.summary.remove(h as u32);
+                    n.clusters[h] = None;
+                }
+                if x == n.max {
+                    n.max = match n.summary.max() {
+                        None => n.min.unwrap(),
+                        Some(h) => n.join(h as usize, n.clusters[h as usize].as_ref().unwrap().max().unwrap()),
+                    };
+                }
+                true
+            }
+        }
+    }
+
+    // smallest value >= x
+    fn successor(&self, x: u32) -> Option<u32> {
+        match self {
+            Node::Leaf(bm) => {
+                if x >= 64 { return None; }
+                let rest = bm >> x;
+                if rest == 0 { None } else { Some(x + rest.trailing_zeros()) }
+            }
+            Node::Inner(n) => {
+                let min = n.min?;
+                if x <= min {
+                    return Some(min);
+                }
+                if x > n.max {
+                    return None;
+                }
+                let (h, l) = n.split(x);
+                if let Some(c) = &n.clusters[h] {
+                    if c.max().is_some_and(|m| l <= m) {
+                        return Some(n.join(h, c.successor(l).unwrap()));
+                    }
+                }
+                let h = n.summary.successor(h as u32 + 1)? as usize;
+                Some(n.join(h, n.clusters[h].as_ref().unwrap().min().unwrap()))
+            }
+        }
+    }
+
+    // largest value <= x
+    fn predecessor(&self, x: u32) -> Option<u32> {
+        match self {
+            Node::Leaf(bm) => {
+                let rest = if x >= 63 { *bm } else { bm & ((1 << (x + 1)) - 1) };
+                if rest == 0 { None } else { Some(63 - rest.leading_zeros()) }
+            }
+            Node::Inner(n) => {
+                let min = n.min?;
+                if x < min {
+                    return None;
+                }
+                if x >= n.max {
+                    return Some(n.max);
+                }
+                let (h, l) = n.split(x);
+                if let Some(c) = &n.clusters[h] {
+                    if c.min().is_some_and(|m| m <= l) {
+                        return Some(n.join(h, c.predecessor(l).unwrap()));
+                    }
+                }
+                if h == 0 {
+                    return Some(min);
+                }
+                match n.summary.predecessor(h as u32 - 1) {
+                    None => Some(min),
+                    Some(h) => Some(n.join(h as usize, n.clusters[h as usize].as_ref().unwrap().max().unwrap())),
+                }
+            }
+        }
+    }
+}
+
+impl Inner {
+    fn split(&self, x: u32) -> (usize, u32) {
+        ((x >> self.lo_bits) as usize, x & ((1 << self.lo_bits) - 1))
+    }
+
+    fn join(&self, h: usize, l: u32) -> u32 {
+        ((h as u32) << self.lo_bits) | l
+    }
+}
+
+/// van Emde Boas 木
+///
+/// 大きさ `U = 2^bits` の整数の集合を、 √U 個のクラスタと、空でないクラスタを管理する要約 (summary) に
+/// 再帰的に分割して管理するデータ構造。
+/// 挿入・削除・検索・前者・後者を `O(log log U)` で行います。
+///
+/// クラスタは必要になった時点で確保しますが、各段のクラスタの表は `O(√U)` の領域を使うため、
+/// `u16` や 24 ビット程度の小さな universe を想定しています。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::veb_tree::VebTree;
+/// let mut veb = VebTree::new(16);
+/// assert_eq!(65536, veb.universe());
+/// for x in [3, 1000, 65535] {
+///     assert!(veb.insert(x));
+/// }
+/// assert!(!veb.insert(3));
+/// assert_eq!(3, veb.len());
+///
+/// // predecessor は x 以下の最大の値、 successor は x 以上の最小の値を返します。
+/// assert_eq!(Some(1000), veb.successor(4));
+/// assert_eq!(Some(3), veb.predecessor(999));
+/// assert_eq!(None, veb.predecessor(2));
+///
+/// assert!(veb.remove(1000));
+/// assert_eq!(Some(65535), veb.successor(4));
+/// ```
+pub struct VebTree {
+    bits: u32,
+    len: usize,
+    root: Node,
+}
+
+impl VebTree {
+    /// 大きさ `2^bits` の universe `[0, 2^bits)` 上の空の集合を構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is greater than 32.
+    pub fn new(bits: u32) -> Self {
+        assert!(bits <= 32);
+        VebTree {
+            bits,
+            len: 0,
+            root: Node::new(bits),
+        }
+    }
+
+    /// universe の大きさを返します。
+    pub fn universe(&self) -> u64 {
+        1 << self.bits
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 最小の値を返します。
+    pub fn min(&self) -> Option<u32> {
+        self.root.min()
+    }
+
+    /// 最大の値を返します。
+    pub fn max(&self) -> Option<u32> {
+        self.root.max()
+    }
+
+    /// `x` が存在する場合に、 `true` を返します。
+    pub fn contains(&self, x: u32) -> bool {
+        (x as u64) < self.universe() && self.root.contains(x)
+    }
+
+    /// `x` を追加します。新たに追加された場合に、 `true` を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` is out of the universe.
+    pub fn insert(&mut self, x: u32) -> bool {
+        assert!((x as u64) < self.universe());
+        let inserted = self.root.insert(x);
+        if inserted {
+            self.len += 1;
+        }
+        inserted
+    }
+
+    /// `x` を取り除きます。 `x` が存在した場合に、 `true` を返します。
+    pub fn remove(&mut self, x: u32) -> bool {
+        if (x as u64) >= self.universe() {
+            return false;
+        }
+        let removed = self.root.remove(x);
+        if removed {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// `x` 以上の最小の値を返します。
+    pub fn successor(&self, x: u32) -> Option<u32> {
+        if (x as u64) >= self.universe() {
+            return None;
+        }
+        self.root.successor(x)
+    }
+
+    /// `x` 以下の最大の値を返します。
+    pub fn predecessor(&self, x: u32) -> Option<u32> {
+        let x = (x as u64).min(self.universe() - 1) as u32;
+        self.root.predecessor(x)
+    }
+
+    /// すべての値を小さい順に返すイテレータを返します。
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        let mut cur = self.min();
+        core::iter::from_fn(move || {
+            let x = cur?;
+            cur = x.checked_add(1).and_then(|y| self.successor(y));
+            Some(x)
+        })
+    }
+}
+
+impl SpaceUsage for Node {
+    fn heap_size_in_bytes(&self) -> usize {
+        match self {
+            Node::Leaf(_) => 0,
+            Node::Inner(inner) => inner.heap_size_in_bytes(),
+        }
+    }
+}
+
+impl SpaceUsage for Inner {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("summary", &self.summary), ("clusters", &self.clusters)]
+    }
+}
+
+impl SpaceUsage for VebTree {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("root", &self.root)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for bits in [1, 6, 7, 16, 24] {
+            let max = 1_u32 << bits;
+            let mut veb = VebTree::new(bits);
+            let mut expected = BTreeSet::new();
+            for _ in 0..3000 {
+                let x = rng.gen_range(0, max.min(300));
+                let x = if rng.gen() { max - 1 - x } else { x };
+                if rng.gen_range(0, 3) == 0 {
+                    assert_eq!(expected.remove(&x), veb.remove(x));
+                } else {
+                    assert_eq!(expected.insert(x), veb.insert(x));
+                }
+                assert_eq!(expected.len(), veb.len());
+
+                let y = rng.gen_range(0, max);
+                assert_eq!(expected.contains(&y), veb.contains(y));
+                assert_eq!(expected.range(..=y).next_back().copied(), veb.predecessor(y));
+                assert_eq!(expected.range(y..).next().copied(), veb.successor(y));
+            }
+            assert_eq!(expected.first().copied(), veb.min());
+            assert_eq!(expected.last().copied(), veb.max());
+            assert_eq!(expected.iter().copied().collect::<Vec<_>>(), veb.iter().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn dense() {
+        let mut veb = VebTree::new(12);
+        for x in 0..4096 {
+            assert!(veb.insert(x));
+        }
+        assert_eq!(4096, veb.len());
+        for x in (0..4096).step_by(3) {
+            assert!(veb.remove(x));
+        }
+        for x in 0..4096 {
+            assert_eq!(x % 3 != 0, veb.contains(x));
+            let succ = (x..4096).find(|y| y % 3 != 0);
+            assert_eq!(succ, veb.successor(x));
+        }
+        assert!(!veb.contains(4096));
+        assert!(!veb.remove(4096));
+        assert_eq!(Some(4094), veb.predecessor(10000));
+    }
+
+    #[test]
+    fn full_u32() {
+        let mut veb = VebTree::new(32);
+        assert!(veb.insert(u32::MAX));
+        assert!(veb.insert(0));
+        assert_eq!(vec![0, u32::MAX], veb.iter().collect::<Vec<u32>>());
+        assert_eq!(Some(u32::MAX), veb.successor(1));
+        assert_eq!(Some(0), veb.predecessor(u32::MAX - 1));
+    }
+}
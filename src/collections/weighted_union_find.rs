@@ -0,0 +1,189 @@
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// ポテンシャル付き Union-Find
+///
+/// 要素を互いに素な集合に分けて管理し、同じ集合に属する要素同士のポテンシャルの差を保持します。
+/// 「B は A より 3 大きい」のような制約を追加しながら、制約の矛盾の検出や差の問い合わせを
+/// ならし `O(α(n))` で行います。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::weighted_union_find::WeightedUnionFind;
+/// let mut uf = WeightedUnionFind::new(4);
+/// // union(x, y, w) は「y は x より w 大きい」という制約を追加します。
+/// assert!(uf.union(0, 1, 3));
+/// assert!(uf.union(1, 2, -5));
+/// assert_eq!(Some(-2), uf.diff(0, 2));
+/// assert_eq!(None, uf.diff(0, 3));
+///
+/// // 既存の制約と矛盾する制約は追加できません。
+/// assert!(!uf.union(0, 2, 1));
+/// assert!(uf.union(2, 0, 2));
+/// ```
+pub struct WeightedUnionFind {
+    // the parent, or the negated size for a root
+    parent: Vec<isize>,
+    // the potential relative to the parent
+    weight: Vec<i64>,
+}
+
+impl WeightedUnionFind {
+    /// 要素 `0..n` がそれぞれ単独の集合をなす Union-Find を構築します。
+    pub fn new(n: usize) -> Self {
+        WeightedUnionFind { parent: vec![-1; n], weight: vec![0; n] }
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    /// 要素がない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// 要素 `x` の属する集合の代表元を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` is out of range.
+    pub fn find(&mut self, x: usize) -> usize {
+        self.find_with_weight(x).0
+    }
+
+    /// 要素 `x` と `y` が同じ集合に属する場合に、 `true` を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` or `y` is out of range.
+    pub fn same(&mut self, x: usize, y: usize) -> bool {
+        self.find(x) == self.find(y)
+    }
+
+    /// 要素 `x` の属する集合の大きさを返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` is out of range.
+    pub fn size(&mut self, x: usize) -> usize {
+        let r = self.find(x);
+        (-self.parent[r]) as usize
+    }
+
+    /// 「 `y` のポテンシャルは `x` より `w` 大きい」という制約を追加し、 `x` と `y` の集合を併合します。
+    ///
+    /// 制約が既存の制約と矛盾する場合、何もせずに `false` を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` or `y` is out of range.
+    pub fn union(&mut self, x: usize, y: usize, w: i64) -> bool {
+        let (mut rx, wx) = self.find_with_weight(x);
+        let (mut ry, wy) = self.find_with_weight(y);
+        // the potential of ry relative to rx
+        let mut w = w + wx - wy;
+        if rx == ry {
+            return w == 0;
+        }
+        if self.parent[rx] > self.parent[ry] {
+            core::mem::swap(&mut rx, &mut ry);
+            w = -w;
+        }
+        self.parent[rx] += self.parent[ry];
+        self.parent[ry] = rx as isize;
+        self.weight[ry] = w;
+        true
+    }
+
+    /// 要素 `y` のポテンシャルから `x` のポテンシャルを引いた値を返します。
+    ///
+    /// `x` と `y` が異なる集合に属する場合、 `None` を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` or `y` is out of range.
+    pub fn diff(&mut self, x: usize, y: usize) -> Option<i64> {
+        let (rx, wx) = self.find_with_weight(x);
+        let (ry, wy) = self.find_with_weight(y);
+        if rx == ry { Some(wy - wx) } else { None }
+    }
+
+    // returns the root and the potential of `x` relative to the root
+    fn find_with_weight(&mut self, x: usize) -> (usize, i64) {
+        let mut path = vec![];
+        let mut r = x;
+        while self.parent[r] >= 0 {
+            path.push(r);
+            r = self.parent[r] as usize;
+        }
+        // compress the path from the node nearest to the root
+        for &v in path.iter().rev() {
+            let p = self.parent[v] as usize;
+            if p != r {
+                self.weight[v] += self.weight[p];
+            }
+            self.parent[v] = r as isize;
+        }
+        (r, if x == r { 0 } else { self.weight[x] })
+    }
+}
+
+impl SpaceUsage for WeightedUnionFind {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("parent", &self.parent), ("weight", &self.weight)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let n = 50;
+        let mut uf = WeightedUnionFind::new(n);
+        // naive: component id and potential
+        let mut comp: Vec<usize> = (0..n).collect();
+        let mut pot: Vec<i64> = vec![0; n];
+        for _ in 0..2000 {
+            let x = rng.gen_range(0, n);
+            let y = rng.gen_range(0, n);
+            if rng.gen_range(0, 2) == 0 {
+                let w = if comp[x] == comp[y] && rng.gen_range(0, 2) == 0 {
+                    pot[y] - pot[x]
+                } else {
+                    rng.gen_range(-10, 10)
+                };
+                let expected = if comp[x] == comp[y] {
+                    pot[y] - pot[x] == w
+                } else {
+                    let (cx, cy) = (comp[x], comp[y]);
+                    let shift = pot[x] + w - pot[y];
+                    for i in 0..n {
+                        if comp[i] == cy {
+                            comp[i] = cx;
+                            pot[i] += shift;
+                        }
+                    }
+                    true
+                };
+                assert_eq!(expected, uf.union(x, y, w));
+            } else {
+                let expected = if comp[x] == comp[y] { Some(pot[y] - pot[x]) } else { None };
+                assert_eq!(expected, uf.diff(x, y));
+                assert_eq!(comp[x] == comp[y], uf.same(x, y));
+                assert_eq!(comp.iter().filter(|c| **c == comp[x]).count(), uf.size(x));
+            }
+        }
+        assert_eq!(n, uf.len());
+    }
+}
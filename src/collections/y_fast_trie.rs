@@ -0,0 +1,521 @@
+use super::hash::HashMap;
+use crate::space::SpaceUsage;
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use alloc::vec;
+
+/// X-fast trie / Y-fast trie のキーとして使える符号なし整数
+pub trait FastTrieKey: Copy + Ord {
+    /// キーのビット数
+    const BITS: u32;
+
+    /// `u64` に変換します。
+    fn to_u64(self) -> u64;
+
+    /// `u64` から変換します。
+    fn from_u64(v: u64) -> Self;
+}
+
+macro_rules! impl_fast_trie_key {
+    ($($t:ty),*) => {
+        $(
+            impl FastTrieKey for $t {
+                const BITS: u32 = <$t>::BITS;
+                fn to_u64(self) -> u64 { self as u64 }
+                fn from_u64(v: u64) -> Self { v as $t }
+            }
+        )*
+    };
+}
+
+impl_fast_trie_key!(u8, u16, u32, u64);
+
+struct XNode {
+    min: u64,
+    max: u64,
+}
+
+struct Leaf {
+    prev: Option<u64>,
+    next: Option<u64>,
+}
+
+/// X-fast trie
+///
+/// キーの各プレフィックスをハッシュテーブルで管理する二分トライ。
+/// キーのビット数を `w` として、検索・前者・後者を `O(log w)` 、挿入・削除を `O(w)` で行います。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::y_fast_trie::XFastTrie;
+/// let mut trie = XFastTrie::<u32>::new();
+/// trie.insert(10);
+/// trie.insert(20);
+/// trie.insert(30);
+///
+/// // predecessor は x 以下の最大のキー、 successor は x 以上の最小のキーを返します。
+/// assert_eq!(Some(20), trie.predecessor(25));
+/// assert_eq!(Some(30), trie.successor(25));
+/// assert_eq!(None, trie.predecessor(5));
+/// assert_eq!(None, trie.successor(31));
+/// ```
+pub struct XFastTrie<K: FastTrieKey> {
+    levels: Vec<HashMap<u64, XNode>>,
+    leaves: HashMap<u64, Leaf>,
+    _key: core::marker::PhantomData<K>,
+}
+
+impl <K: FastTrieKey> XFastTrie<K> {
+    /// 空の X-fast trie を構築します。
+    pub fn new() -> Self {
+        XFastTrie {
+            levels: (0..=K::BITS).map(|_| HashMap::default()).collect(),
+            leaves: HashMap::default(),
+            _key: core::marker::PhantomData,
+        }
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// キー `x` が存在する場合に、 `true` を返します。
+    pub fn contains(&self, x: K) -> bool {
+        self.leaves.contains_key(&x.to_u64())
+    }
+
+    /// 最小のキーを返します。
+    pub fn min(&self) -> Option<K> {
+        self.levels[0].get(&0).map(|n| K::from_u64(n.min))
+    }
+
+    /// 最大のキーを返します。
+    pub fn max(&self) -> Option<K> {
+        self.levels[0].get(&0).map(|n| K::from_u64(n.max))
+    }
+
+    /// `x` 以下の最大のキーを返します。
+    pub fn predecessor(&self, x: K) -> Option<K> {
+        self.pred(x.to_u64()).map(K::from_u64)
+    }
+
+    /// `x` 以上の最小のキーを返します。
+    pub fn successor(&self, x: K) -> Option<K> {
+        self.succ(x.to_u64()).map(K::from_u64)
+    }
+
+    /// キー `x` を追加します。新たに追加された場合に、 `true` を返します。
+    pub fn insert(&mut self, x: K) -> bool {
+        let x = x.to_u64();
+        if self.leaves.contains_key(&x) {
+            return false;
+        }
+        let prev = self.pred(x);
+        let next = self.succ(x);
+        if let Some(p) = prev {
+            self.leaves.get_mut(&p).unwrap().next = Some(x);
+        }
+        if let Some(n) = next {
+            self.leaves.get_mut(&n).unwrap().prev = Some(x);
+        }
+        self.leaves.insert(x, Leaf { prev, next });
+
+        for d in 0..=K::BITS {
+            let node = self.levels[d as usize].entry(Self::prefix(x, d)).or_insert(XNode { min: x, max: x });
+            node.min = node.min.min(x);
+            node.max = node.max.max(x);
+        }
+        true
+    }
+
+    /// キー `x` を取り除きます。キーが存在した場合に、 `true` を返します。
+    pub fn remove(&mut self, x: K) -> bool {
+        let x = x.to_u64();
+        let leaf = match self.leaves.remove(&x) {
+            None => return false,
+            Some(leaf) => leaf,
+        };
+        if let Some(p) = leaf.prev {
+            self.leaves.get_mut(&p).unwrap().next = leaf.next;
+        }
+        if let Some(n) = leaf.next {
+            self.leaves.get_mut(&n).unwrap().prev = leaf.prev;
+        }
+
+        for d in 0..=K::BITS {
+            let level = &mut self.levels[d as usize];
+            let prefix = Self::prefix(x, d);
+            let node = level.get_mut(&prefix).unwrap();
+            if node.min == x && node.max == x {
+                level.remove(&prefix);
+            } else if node.min == x {
+                node.min = leaf.next.unwrap();
+            } else if node.max == x {
+                node.max = leaf.prev.unwrap();
+            }
+        }
+        true
+    }
+
+    /// すべてのキーを小さい順に返すイテレータを返します。
+    pub fn iter(&self) -> impl Iterator<Item = K> + '_ {
+        let mut cur = self.min().map(K::to_u64);
+        core::iter::from_fn(move || {
+            let x = cur?;
+            cur = self.leaves[&x].next;
+            Some(K::from_u64(x))
+        })
+    }
+
+    // next key of `x` which is in the trie
+    fn next_of(&self, x: K) -> Option<K> {
+        self.leaves[&x.to_u64()].next.map(K::from_u64)
+    }
+
+    fn prefix(x: u64, d: u32) -> u64 {
+        if d == 0 { 0 } else { x >> (K::BITS - d) }
+    }
+
+    // deepest level which has the prefix of `x`
+    fn lcp_level(&self, x: u64) -> Option<u32> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut ok = 0;
+        let mut ng = K::BITS + 1;
+        while ng - ok > 1 {
+            let mid = (ok + ng) / 2;
+            if self.levels[mid as usize].contains_key(&Self::prefix(x, mid)) {
+                ok = mid;
+            } else {
+                ng = mid;
+            }
+        }
+        Some(ok)
+    }
+
+    fn pred(&self, x: u64) -> Option<u64> {
+        let d = self.lcp_level(x)?;
+        if d == K::BITS {
+            return Some(x);
+        }
+        let node = &self.levels[d as usize][&Self::prefix(x, d)];
+        if (x >> (K::BITS - d - 1)) & 1 == 1 {
+            // only the left child exists
+            Some(node.max)
+        } else {
+            // only the right child exists
+            self.leaves[&node.min].prev
+        }
+    }
+
+    fn succ(&self, x: u64) -> Option<u64> {
+        let d = self.lcp_level(x)?;
+        if d == K::BITS {
+            return Some(x);
+        }
+        let node = &self.levels[d as usize][&Self::prefix(x, d)];
+        if (x >> (K::BITS - d - 1)) & 1 == 0 {
+            // only the right child exists
+            Some(node.min)
+        } else {
+            // only the left child exists
+            self.leaves[&node.max].next
+        }
+    }
+}
+
+impl <K: FastTrieKey> Default for XFastTrie<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Y-fast trie
+///
+/// キーを `O(w)` 個ずつのバケット (平衡二分探索木) に分割し、
+/// 各バケットの最小値を代表元として [`XFastTrie`] で管理する動的な整数集合。
+/// キーのビット数を `w` として、検索・前者・後者を `O(log w)` 、挿入・削除を償却 `O(log w)` で行います。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::y_fast_trie::YFastTrie;
+/// let mut trie = YFastTrie::<u32>::new();
+/// for x in [0x0a000000, 0x0a0000ff, 0xc0a80000, 0xc0a8ffff] {
+///     trie.insert(x);
+/// }
+/// assert_eq!(4, trie.len());
+///
+/// // predecessor は x 以下の最大のキー、 successor は x 以上の最小のキーを返します。
+/// assert_eq!(Some(0xc0a80000), trie.predecessor(0xc0a80001));
+/// assert_eq!(Some(0xc0a8ffff), trie.successor(0xc0a80001));
+/// assert_eq!(None, trie.predecessor(0x09ffffff));
+///
+/// assert!(trie.remove(0xc0a80000));
+/// assert_eq!(Some(0x0a0000ff), trie.predecessor(0xc0a80001));
+/// ```
+pub struct YFastTrie<K: FastTrieKey> {
+    reps: XFastTrie<K>,
+    buckets: HashMap<u64, BTreeSet<u64>>,
+    len: usize,
+}
+
+impl <K: FastTrieKey> YFastTrie<K> {
+    /// 空の Y-fast trie を構築します。
+    pub fn new() -> Self {
+        YFastTrie {
+            reps: XFastTrie::new(),
+            buckets: HashMap::default(),
+            len: 0,
+        }
+    }
+
+    /// 要素数を返します。
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// キー `x` が存在する場合に、 `true` を返します。
+    pub fn contains(&self, x: K) -> bool {
+        self.predecessor(x) == Some(x)
+    }
+
+    /// 最小のキーを返します。
+    pub fn min(&self) -> Option<K> {
+        self.reps.min()
+    }
+
+    /// 最大のキーを返します。
+    pub fn max(&self) -> Option<K> {
+        let r = self.reps.max()?;
+        self.buckets[&r.to_u64()].last().map(|v| K::from_u64(*v))
+    }
+
+    /// `x` 以下の最大のキーを返します。
+    pub fn predecessor(&self, x: K) -> Option<K> {
+        let r = self.reps.predecessor(x)?;
+        self.buckets[&r.to_u64()].range(..=x.to_u64()).next_back().map(|v| K::from_u64(*v))
+    }
+
+    /// `x` 以上の最小のキーを返します。
+    pub fn successor(&self, x: K) -> Option<K> {
+        if let Some(r) = self.reps.predecessor(x) {
+            if let Some(v) = self.buckets[&r.to_u64()].range(x.to_u64()..).next() {
+                return Some(K::from_u64(*v));
+            }
+        }
+        self.reps.successor(x)
+    }
+
+    /// キー `x` を追加します。新たに追加された場合に、 `true` を返します。
+    pub fn insert(&mut self, x: K) -> bool {
+        let rep = match self.reps.predecessor(x) {
+            Some(r) => {
+                if !self.buckets.get_mut(&r.to_u64()).unwrap().insert(x.to_u64()) {
+                    return false;
+                }
+                r
+            }
+            None => {
+                let mut bucket = match self.reps.successor(x) {
+                    Some(r) => {
+                        self.reps.remove(r);
+                        self.buckets.remove(&r.to_u64()).unwrap()
+                    }
+                    None => BTreeSet::new(),
+                };
+                bucket.insert(x.to_u64());
+                self.reps.insert(x);
+                self.buckets.insert(x.to_u64(), bucket);
+                x
+            }
+        };
+        self.len += 1;
+        self.split_bucket(rep);
+        true
+    }
+
+    /// キー `x` を取り除きます。キーが存在した場合に、 `true` を返します。
+    pub fn remove(&mut self, x: K) -> bool {
+        let r = match self.reps.predecessor(x) {
+            None => return false,
+            Some(r) => r,
+        };
+        let mut bucket = self.buckets.remove(&r.to_u64()).unwrap();
+        if !bucket.remove(&x.to_u64()) {
+            self.buckets.insert(r.to_u64(), bucket);
+            return false;
+        }
+        self.len -= 1;
+
+        let rep = match bucket.first() {
+            None => {
+                self.reps.remove(r);
+                return true;
+            }
+            Some(m) if *m != r.to_u64() => {
+                self.reps.remove(r);
+                let m = K::from_u64(*m);
+                self.reps.insert(m);
+                m
+            }
+            Some(_) => r,
+        };
+        self.buckets.insert(rep.to_u64(), bucket);
+        self.merge_bucket(rep);
+        true
+    }
+
+    /// すべてのキーを小さい順に返すイテレータを返します。
+    pub fn iter(&self) -> impl Iterator<Item = K> + '_ {
+        self.reps.iter().flat_map(|r| self.buckets[&r.to_u64()].iter().map(|v| K::from_u64(*v)))
+    }
+
+    fn split_bucket(&mut self, rep: K) {
+        let bucket = self.buckets.get_mut(&rep.to_u64()).unwrap();
+        if bucket.len() <= 2 * K::BITS as usize {
+            return;
+        }
+        let mid = *bucket.iter().nth(bucket.len() / 2).unwrap();
+        let upper = bucket.split_off(&mid);
+        self.reps.insert(K::from_u64(mid));
+        self.buckets.insert(mid, upper);
+    }
+
+    fn merge_bucket(&mut self, rep: K) {
+        if self.buckets[&rep.to_u64()].len() >= (K::BITS / 2) as usize {
+            return;
+        }
+        let next = match self.reps.next_of(rep) {
+            None => return,
+            Some(n) => n,
+        };
+        self.reps.remove(next);
+        let mut upper = self.buckets.remove(&next.to_u64()).unwrap();
+        self.buckets.get_mut(&rep.to_u64()).unwrap().append(&mut upper);
+        self.split_bucket(rep);
+    }
+}
+
+impl <K: FastTrieKey> Default for YFastTrie<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpaceUsage for XNode {}
+
+impl SpaceUsage for Leaf {}
+
+impl <K: FastTrieKey> SpaceUsage for XFastTrie<K> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("levels", &self.levels), ("leaves", &self.leaves)]
+    }
+}
+
+impl <K: FastTrieKey> SpaceUsage for YFastTrie<K> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("reps", &self.reps), ("buckets", &self.buckets)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    trait IntSet {
+        fn insert(&mut self, x: u64) -> bool;
+        fn remove(&mut self, x: u64) -> bool;
+        fn query(&self, x: u64) -> (Option<u64>, Option<u64>, bool);
+        fn keys(&self) -> (Vec<u64>, usize, Option<u64>, Option<u64>);
+    }
+
+    impl IntSet for XFastTrie<u64> {
+        fn insert(&mut self, x: u64) -> bool { self.insert(x) }
+        fn remove(&mut self, x: u64) -> bool { self.remove(x) }
+        fn query(&self, x: u64) -> (Option<u64>, Option<u64>, bool) {
+            (self.predecessor(x), self.successor(x), self.contains(x))
+        }
+        fn keys(&self) -> (Vec<u64>, usize, Option<u64>, Option<u64>) {
+            (self.iter().collect(), self.len(), self.min(), self.max())
+        }
+    }
+
+    impl IntSet for YFastTrie<u64> {
+        fn insert(&mut self, x: u64) -> bool { self.insert(x) }
+        fn remove(&mut self, x: u64) -> bool { self.remove(x) }
+        fn query(&self, x: u64) -> (Option<u64>, Option<u64>, bool) {
+            (self.predecessor(x), self.successor(x), self.contains(x))
+        }
+        fn keys(&self) -> (Vec<u64>, usize, Option<u64>, Option<u64>) {
+            (self.iter().collect(), self.len(), self.min(), self.max())
+        }
+    }
+
+    fn random<T: IntSet>(set: &mut T, max: u64) {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut expected = BTreeSet::new();
+        for _ in 0..3000 {
+            let x = rng.gen_range(0, max);
+            if rng.gen_range(0, 3) == 0 {
+                assert_eq!(expected.remove(&x), set.remove(x));
+            } else {
+                assert_eq!(expected.insert(x), set.insert(x));
+            }
+            let y = rng.gen_range(0, max);
+            let pred = expected.range(..=y).next_back().copied();
+            let succ = expected.range(y..).next().copied();
+            assert_eq!((pred, succ, expected.contains(&y)), set.query(y));
+        }
+        let keys: Vec<u64> = expected.iter().copied().collect();
+        let (min, max) = (keys.first().copied(), keys.last().copied());
+        assert_eq!((keys.clone(), keys.len(), min, max), set.keys());
+    }
+
+    #[test]
+    fn x_fast_trie() {
+        for max in [64, 10000, u64::MAX] {
+            random(&mut XFastTrie::new(), max);
+        }
+    }
+
+    #[test]
+    fn y_fast_trie() {
+        for max in [64, 10000, u64::MAX] {
+            random(&mut YFastTrie::new(), max);
+        }
+    }
+
+    #[test]
+    fn y_fast_trie_buckets() {
+        let mut trie = YFastTrie::<u8>::new();
+        for x in 0..=255 {
+            assert!(trie.insert(x));
+        }
+        assert!(trie.buckets.len() > 1);
+        assert!(trie.buckets.values().all(|b| b.len() <= 16));
+        for x in (0..=255).step_by(2) {
+            assert!(trie.remove(x));
+        }
+        assert_eq!((1..=255).step_by(2).collect::<Vec<u8>>(), trie.iter().collect::<Vec<u8>>());
+        assert_eq!(Some(255), trie.max());
+        assert_eq!(Some(1), trie.min());
+    }
+}
@@ -0,0 +1,115 @@
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+/// クレート共通のエラー
+///
+/// パニックする操作の `try_*` 版が、パニックの代わりに返します。
+/// [`crate::serialize::Serializable`] の読み込みで返す `io::Error` も、壊れたデータや版の不一致の場合はこのエラーを包んでいます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::Error;
+/// use rust_study::bits::fid::*;
+/// let fid = NaiveFID::from_bool_vec(&vec![true, false, true]);
+/// assert_eq!(Ok(2), fid.try_rank1(3));
+/// assert_eq!(Err(Error::OutOfBounds { index: 4, len: 4 }), fid.try_rank1(4));
+/// assert_eq!("index 4 is out of bounds for length 4", fid.try_rank1(4).unwrap_err().to_string());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// 添字 `index` が範囲 `[0, len)` の外にあります。
+    OutOfBounds { index: usize, len: usize },
+    /// 記号 `symbol` がデータに現れません。
+    SymbolAbsent { symbol: u8 },
+    /// ソートされているはずのキーの列で、 `index` 番目のキーが直前のキーより小さくなっています。
+    UnsortedKeys { index: usize },
+    /// 確保しようとした容量が大きすぎます。
+    CapacityOverflow,
+    /// 読み込んだデータが壊れています。
+    CorruptData(String),
+    /// 読み込んだデータの書式の版 `found` が、対応する版 `expected` と異なります。
+    VersionMismatch { expected: u32, found: u32 },
+    /// [`crate::progress::Progress::is_cancelled()`] により、構築が中断されました。
+    Cancelled,
+    /// 構築に `required` バイト必要で、メモリの上限 `limit` バイトに収まりません。
+    MemoryLimit { required: usize, limit: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::OutOfBounds { index, len } => write!(f, "index {} is out of bounds for length {}", index, len),
+            Error::SymbolAbsent { symbol } => write!(f, "symbol {} is absent", symbol),
+            Error::UnsortedKeys { index } => write!(f, "keys are not sorted at index {}", index),
+            Error::CapacityOverflow => write!(f, "capacity overflow"),
+            Error::CorruptData(message) => write!(f, "corrupt data: {}", message),
+            Error::VersionMismatch { expected, found } => write!(f, "unsupported version {}, expected {}", found, expected),
+            Error::Cancelled => write!(f, "cancelled"),
+            Error::MemoryLimit { required, limit } => write!(f, "{} bytes are required, over the memory limit of {} bytes", required, limit),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+#[cfg(feature = "std")]
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        let kind = match error {
+            Error::CorruptData(_) | Error::VersionMismatch { .. } | Error::UnsortedKeys { .. } => io::ErrorKind::InvalidData,
+            Error::Cancelled => io::ErrorKind::Interrupted,
+            Error::MemoryLimit { .. } => io::ErrorKind::OutOfMemory,
+            _ => io::ErrorKind::InvalidInput,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+/// `index < len` を確認します。
+pub(crate) fn check_index(index: usize, len: usize) -> Result<(), Error> {
+    if index < len { Ok(()) } else { Err(Error::OutOfBounds { index, len }) }
+}
+
+/// [`Error::CorruptData`] の説明の前に、不整合のある部分 `part` を付けます。それ以外のエラーはそのまま返します。
+pub(crate) fn in_part(error: Error, part: &str) -> Error {
+    match error {
+        Error::CorruptData(message) => Error::CorruptData(format!("{}: {}", part, message)),
+        error => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn into_io_error() {
+        let error: io::Error = Error::VersionMismatch { expected: 1, found: 2 }.into();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert_eq!(Some(&Error::VersionMismatch { expected: 1, found: 2 }), error.get_ref().and_then(|e| e.downcast_ref()));
+        let error: io::Error = Error::OutOfBounds { index: 1, len: 0 }.into();
+        assert_eq!(io::ErrorKind::InvalidInput, error.kind());
+        let error: io::Error = Error::Cancelled.into();
+        assert_eq!(io::ErrorKind::Interrupted, error.kind());
+        let error: io::Error = Error::MemoryLimit { required: 2, limit: 1 }.into();
+        assert_eq!(io::ErrorKind::OutOfMemory, error.kind());
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(Ok(()), check_index(0, 1));
+        assert_eq!(Err(Error::OutOfBounds { index: 1, len: 1 }), check_index(1, 1));
+    }
+
+    #[test]
+    fn part() {
+        assert_eq!(Error::CorruptData("level 2: broken".to_string()), in_part(Error::CorruptData("broken".to_string()), "level 2"));
+        assert_eq!(Error::CapacityOverflow, in_part(Error::CapacityOverflow, "level 2"));
+    }
+}
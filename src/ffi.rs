@@ -0,0 +1,418 @@
+use crate::serialize;
+use crate::serialize::Serializable;
+use crate::string::fm_index::NaiveFMIndex;
+use crate::string::trie::NaiveLoudsTrie;
+use crate::string::trie::Trie;
+
+use std::ffi::c_char;
+use std::ffi::CStr;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::ptr;
+use std::slice;
+
+// runs the body of an exported function, returning fallback if it panics,
+// since unwinding out of an extern "C" function aborts the whole process
+fn guard<T>(fallback: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(fallback)
+}
+
+// the slice of len items at ptr, which may be null if len is zero
+unsafe fn slice_from<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(ptr, len)
+    }
+}
+
+// the serialized bytes leaked to the caller, who frees them by rust_study_bytes_free
+unsafe fn save<T: Serializable>(value: &T, out_len: *mut usize) -> *mut u8 {
+    let bytes = Box::into_raw(serialize::to_bytes(value).into_boxed_slice());
+    *out_len = bytes.len();
+    bytes.cast()
+}
+
+/// `text` から `len` バイトの FM-index を構築し、そのポインタを返します。
+///
+/// 返したポインタは [`rust_study_fm_index_free()`] で解放します。
+///
+/// # Safety
+///
+/// `text` must point to `len` readable bytes, or may be null if `len` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::ffi::*;
+/// unsafe {
+///     let index = rust_study_fm_index_new(b"abracadabra".as_ptr(), 11);
+///     assert_eq!(2, rust_study_fm_index_count(index, b"abra".as_ptr(), 4));
+///     let mut positions = [0; 4];
+///     assert_eq!(2, rust_study_fm_index_locate(index, b"abra".as_ptr(), 4, positions.as_mut_ptr(), 4));
+///     assert_eq!([0, 7], positions[..2]);
+///     rust_study_fm_index_free(index);
+/// }
+/// ```
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_fm_index_new(text: *const u8, len: usize) -> *mut NaiveFMIndex {
+    guard(ptr::null_mut(), || Box::into_raw(Box::new(NaiveFMIndex::new(slice_from(text, len)))))
+}
+
+/// [`Serializable::serialize()`] で書き出した `len` バイトの `bytes` から FM-index を読み込み、そのポインタを返します。
+///
+/// バイト列が壊れている場合や、余分なバイトが続く場合はヌルポインタを返します。
+/// 読み込みでは [`NaiveFMIndex::validate()`] で整合性も確かめるため、返した索引の検索はパニックしません。
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes, or may be null if `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_fm_index_load(bytes: *const u8, len: usize) -> *mut NaiveFMIndex {
+    guard(ptr::null_mut(), || {
+        // deserialize() validates the index
+        serialize::from_bytes(slice_from(bytes, len)).map_or(ptr::null_mut(), |index: NaiveFMIndex| Box::into_raw(Box::new(index)))
+    })
+}
+
+/// `index` を [`Serializable::serialize()`] の形式で書き出したバイト列を返し、その長さを `out_len` に書き込みます。
+///
+/// 返したバイト列は [`rust_study_bytes_free()`] で解放します。
+///
+/// # Safety
+///
+/// `index` must be a live pointer returned by this module, and `out_len` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_fm_index_save(index: *const NaiveFMIndex, out_len: *mut usize) -> *mut u8 {
+    guard(ptr::null_mut(), || save(&*index, out_len))
+}
+
+/// `index` を解放します。ヌルポインタの場合は何もしません。
+///
+/// # Safety
+///
+/// `index` must be null or a live pointer returned by this module, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_fm_index_free(index: *mut NaiveFMIndex) {
+    guard((), || {
+        if !index.is_null() {
+            drop(Box::from_raw(index));
+        }
+    })
+}
+
+/// `index` の元のバイト列の長さを返します。
+///
+/// # Safety
+///
+/// `index` must be a live pointer returned by this module.
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_fm_index_len(index: *const NaiveFMIndex) -> usize {
+    guard(0, || (*index).len())
+}
+
+/// `len` バイトの `pattern` が元のバイト列に現れる回数を、重なりを含めて返します。
+///
+/// # Safety
+///
+/// `index` must be a live pointer returned by this module,
+/// and `pattern` must point to `len` readable bytes, or may be null if `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_fm_index_count(index: *const NaiveFMIndex, pattern: *const u8, len: usize) -> usize {
+    guard(0, || (*index).count(slice_from(pattern, len)))
+}
+
+/// `len` バイトの `pattern` が元のバイト列に現れる位置を、昇順に最大 `capacity` 個まで `out` に書き込み、すべての位置の数を返します。
+///
+/// 返り値が `capacity` より大きい場合は、十分な大きさのバッファで呼び直すと、すべての位置が得られます。
+///
+/// # Safety
+///
+/// `index` must be a live pointer returned by this module,
+/// `pattern` must point to `len` readable bytes, or may be null if `len` is zero,
+/// and `out` must point to `capacity` writable `size_t`s, or may be null if `capacity` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_fm_index_locate(index: *const NaiveFMIndex, pattern: *const u8, len: usize, out: *mut usize, capacity: usize) -> usize {
+    guard(0, || {
+        let positions = (*index).locate(slice_from(pattern, len));
+        let n = positions.len().min(capacity);
+        if n > 0 {
+            ptr::copy_nonoverlapping(positions.as_ptr(), out, n);
+        }
+        positions.len()
+    })
+}
+
+/// NUL 終端の文字列の配列 `keys` の先頭 `len` 個をキーとする LOUDS トライ木を構築し、そのポインタを返します。
+///
+/// キーの重複や順序は問いません。 UTF-8 として正しくないキーがある場合はヌルポインタを返します。
+/// 返したポインタは [`rust_study_trie_free()`] で解放します。
+///
+/// # Safety
+///
+/// `keys` must point to `len` readable pointers to NUL-terminated strings, or may be null if `len` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::ffi::*;
+/// let keys = [c"the".as_ptr(), c"their".as_ptr(), c"this".as_ptr()];
+/// unsafe {
+///     let trie = rust_study_trie_new(keys.as_ptr(), keys.len());
+///     assert!(rust_study_trie_contains(trie, c"their".as_ptr()));
+///     assert_eq!(2, rust_study_trie_count_prefix(trie, c"the".as_ptr()));
+///     rust_study_trie_free(trie);
+/// }
+/// ```
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_trie_new(keys: *const *const c_char, len: usize) -> *mut NaiveLoudsTrie {
+    guard(ptr::null_mut(), || {
+        let keys: Option<Vec<&str>> = slice_from(keys, len).iter()
+            .map(|&key| CStr::from_ptr(key).to_str().ok())
+            .collect();
+        keys.map_or(ptr::null_mut(), |keys| Box::into_raw(Box::new(NaiveLoudsTrie::new(&keys))))
+    })
+}
+
+/// [`Serializable::serialize()`] で書き出した `len` バイトの `bytes` から LOUDS トライ木を読み込み、そのポインタを返します。
+///
+/// バイト列が壊れている場合や、余分なバイトが続く場合はヌルポインタを返します。
+/// 読み込んだ後に [`NaiveLoudsTrie::validate()`] で整合性も確かめるため、返したトライ木の検索はパニックしません。
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes, or may be null if `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_trie_load(bytes: *const u8, len: usize) -> *mut NaiveLoudsTrie {
+    guard(ptr::null_mut(), || {
+        serialize::from_bytes(slice_from(bytes, len))
+            .ok()
+            .filter(|trie: &NaiveLoudsTrie| trie.validate().is_ok())
+            .map_or(ptr::null_mut(), |trie| Box::into_raw(Box::new(trie)))
+    })
+}
+
+/// `trie` を [`Serializable::serialize()`] の形式で書き出したバイト列を返し、その長さを `out_len` に書き込みます。
+///
+/// 返したバイト列は [`rust_study_bytes_free()`] で解放します。
+///
+/// # Safety
+///
+/// `trie` must be a live pointer returned by this module, and `out_len` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_trie_save(trie: *const NaiveLoudsTrie, out_len: *mut usize) -> *mut u8 {
+    guard(ptr::null_mut(), || save(&*trie, out_len))
+}
+
+/// `trie` を解放します。ヌルポインタの場合は何もしません。
+///
+/// # Safety
+///
+/// `trie` must be null or a live pointer returned by this module, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_trie_free(trie: *mut NaiveLoudsTrie) {
+    guard((), || {
+        if !trie.is_null() {
+            drop(Box::from_raw(trie));
+        }
+    })
+}
+
+/// `trie` のキーの数を返します。
+///
+/// # Safety
+///
+/// `trie` must be a live pointer returned by this module.
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_trie_len(trie: *const NaiveLoudsTrie) -> usize {
+    guard(0, || (*trie).len())
+}
+
+/// NUL 終端の文字列 `key` が `trie` のキーに含まれるかを返します。
+///
+/// UTF-8 として正しくない `key` は含まれません。
+///
+/// # Safety
+///
+/// `trie` must be a live pointer returned by this module, and `key` must point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_trie_contains(trie: *const NaiveLoudsTrie, key: *const c_char) -> bool {
+    guard(false, || CStr::from_ptr(key).to_str().is_ok_and(|key| (*trie).contains(key)))
+}
+
+/// NUL 終端の文字列 `prefix` で始まる `trie` のキーの数を返します。
+///
+/// UTF-8 として正しくない `prefix` で始まるキーはありません。
+///
+/// # Safety
+///
+/// `trie` must be a live pointer returned by this module, and `prefix` must point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_trie_count_prefix(trie: *const NaiveLoudsTrie, prefix: *const c_char) -> usize {
+    guard(0, || CStr::from_ptr(prefix).to_str().map_or(0, |prefix| (*trie).count_prefix(prefix)))
+}
+
+/// `*_save` 関数が返した `len` バイトのバイト列 `bytes` を解放します。ヌルポインタの場合は何もしません。
+///
+/// # Safety
+///
+/// `bytes` must be null or a live pointer returned by this module with its length `len`, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rust_study_bytes_free(bytes: *mut u8, len: usize) {
+    guard((), || {
+        if !bytes.is_null() {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(bytes, len)));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn saved(bytes: *mut u8, len: usize) -> Vec<u8> {
+        let v = slice_from(bytes, len).to_vec();
+        rust_study_bytes_free(bytes, len);
+        v
+    }
+
+    #[test]
+    fn fm_index() {
+        let text = b"abracadabra";
+        unsafe {
+            let index = rust_study_fm_index_new(text.as_ptr(), text.len());
+            assert_eq!(11, rust_study_fm_index_len(index));
+            assert_eq!(5, rust_study_fm_index_count(index, b"a".as_ptr(), 1));
+            assert_eq!(12, rust_study_fm_index_count(index, ptr::null(), 0));
+
+            let mut positions = [usize::MAX; 3];
+            assert_eq!(5, rust_study_fm_index_locate(index, b"a".as_ptr(), 1, positions.as_mut_ptr(), 3));
+            assert_eq!([0, 3, 5], positions);
+            assert_eq!(2, rust_study_fm_index_locate(index, b"abra".as_ptr(), 4, ptr::null_mut(), 0));
+            assert_eq!(0, rust_study_fm_index_locate(index, b"xyz".as_ptr(), 3, positions.as_mut_ptr(), 3));
+
+            let mut len = 0;
+            let bytes = saved(rust_study_fm_index_save(index, &mut len), len);
+            rust_study_fm_index_free(index);
+            let mut expected = vec![];
+            NaiveFMIndex::new(text).serialize(&mut expected).unwrap();
+            assert_eq!(expected, bytes);
+
+            let index = rust_study_fm_index_load(bytes.as_ptr(), bytes.len());
+            assert!(!index.is_null());
+            assert_eq!(2, rust_study_fm_index_count(index, b"abra".as_ptr(), 4));
+            rust_study_fm_index_free(index);
+        }
+    }
+
+    #[test]
+    fn fm_index_empty() {
+        unsafe {
+            let index = rust_study_fm_index_new(ptr::null(), 0);
+            assert_eq!(0, rust_study_fm_index_len(index));
+            assert_eq!(0, rust_study_fm_index_count(index, b"a".as_ptr(), 1));
+            rust_study_fm_index_free(index);
+            rust_study_fm_index_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn trie() {
+        let keys = [c"the".as_ptr(), c"their".as_ptr(), c"them".as_ptr(), c"this".as_ptr(), c"the".as_ptr()];
+        unsafe {
+            let trie = rust_study_trie_new(keys.as_ptr(), keys.len());
+            assert_eq!(4, rust_study_trie_len(trie));
+            assert!(rust_study_trie_contains(trie, c"them".as_ptr()));
+            assert!(!rust_study_trie_contains(trie, c"th".as_ptr()));
+            assert!(!rust_study_trie_contains(trie, c"\xff".as_ptr()));
+            assert_eq!(3, rust_study_trie_count_prefix(trie, c"the".as_ptr()));
+            assert_eq!(4, rust_study_trie_count_prefix(trie, c"".as_ptr()));
+            assert_eq!(0, rust_study_trie_count_prefix(trie, c"\xff".as_ptr()));
+
+            let mut len = 0;
+            let bytes = saved(rust_study_trie_save(trie, &mut len), len);
+            rust_study_trie_free(trie);
+            let trie = rust_study_trie_load(bytes.as_ptr(), bytes.len());
+            assert!(!trie.is_null());
+            assert_eq!(4, rust_study_trie_len(trie));
+            assert!(rust_study_trie_contains(trie, c"their".as_ptr()));
+            rust_study_trie_free(trie);
+            rust_study_trie_free(ptr::null_mut());
+
+            let trie = rust_study_trie_new(ptr::null(), 0);
+            assert_eq!(0, rust_study_trie_len(trie));
+            rust_study_trie_free(trie);
+        }
+    }
+
+    #[test]
+    fn broken() {
+        let keys = [c"a".as_ptr(), c"\xff".as_ptr()];
+        unsafe {
+            assert!(rust_study_trie_new(keys.as_ptr(), keys.len()).is_null());
+            assert!(rust_study_fm_index_load(b"NOTINDEX".as_ptr(), 8).is_null());
+            assert!(rust_study_trie_load(ptr::null(), 0).is_null());
+
+            let trie = rust_study_trie_new(keys.as_ptr(), 1);
+            let mut len = 0;
+            let mut bytes = saved(rust_study_trie_save(trie, &mut len), len);
+            rust_study_trie_free(trie);
+            assert!(rust_study_trie_load(bytes.as_ptr(), bytes.len() - 1).is_null());
+            assert!(rust_study_fm_index_load(bytes.as_ptr(), bytes.len()).is_null());
+            bytes.push(0);
+            assert!(rust_study_trie_load(bytes.as_ptr(), bytes.len()).is_null());
+        }
+    }
+
+    #[test]
+    fn broken_fm_index() {
+        let text = b"abracadabra";
+        unsafe {
+            let index = rust_study_fm_index_new(text.as_ptr(), text.len());
+            let mut len = 0;
+            let bytes = saved(rust_study_fm_index_save(index, &mut len), len);
+            rust_study_fm_index_free(index);
+
+            // a body cut short, with its length in the header fixed up to match
+            let mut truncated = bytes[..bytes.len() - 8].to_vec();
+            let body_len = truncated.len() as u64 - 20;
+            truncated[12..20].copy_from_slice(&body_len.to_le_bytes());
+            assert!(rust_study_fm_index_load(truncated.as_ptr(), truncated.len()).is_null());
+
+            // every single byte mutation is either rejected or loads an index whose queries return
+            for i in 20..bytes.len() {
+                for b in [0, 1, 0x80, 0xff] {
+                    let mut broken = bytes.clone();
+                    broken[i] = b;
+                    let index = rust_study_fm_index_load(broken.as_ptr(), broken.len());
+                    if !index.is_null() {
+                        let count = rust_study_fm_index_count(index, b"abra".as_ptr(), 4);
+                        assert_eq!(count, rust_study_fm_index_locate(index, b"abra".as_ptr(), 4, ptr::null_mut(), 0));
+                        rust_study_fm_index_free(index);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn broken_trie() {
+        let keys = [c"ab".as_ptr(), c"ac".as_ptr()];
+        unsafe {
+            let trie = rust_study_trie_new(keys.as_ptr(), keys.len());
+            let mut len = 0;
+            let mut bytes = saved(rust_study_trie_save(trie, &mut len), len);
+            rust_study_trie_free(trie);
+            // swap the labels of b and c, which only validate() rejects
+            let at = bytes.len() - 24;
+            bytes.swap(at - 2, at - 1);
+            assert!(rust_study_trie_load(bytes.as_ptr(), bytes.len()).is_null());
+        }
+    }
+
+    #[test]
+    fn guard_panics() {
+        assert_eq!(1, guard(0, || 1));
+        assert_eq!(0, guard(0, || panic!("unwinds into the guard")));
+    }
+}
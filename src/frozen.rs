@@ -0,0 +1,166 @@
+use crate::bits::succinct_rmq::NaiveSuccinctRMQ;
+use crate::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+use crate::space::SpaceUsage;
+use crate::string::fm_index::NaiveFMIndex;
+use crate::string::run_length_fm_index::NaiveRunLengthFMIndex;
+use crate::string::suffix_array::SuffixArray;
+use crate::string::trie::Dawg;
+#[cfg(feature = "std")]
+use crate::string::trie::LoudsTrieView;
+#[cfg(feature = "mmap")]
+use crate::string::trie::MappedLoudsTrie;
+use crate::string::trie::NaiveLoudsTrie;
+use crate::string::trie::NestedPatriciaTrie;
+
+use alloc::sync::Arc;
+use core::fmt;
+use core::ops::Deref;
+
+// the static indexes, which keep no scratch state across queries and can be shared between threads
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<NaiveU8WaveletMatrix>();
+    assert_send_sync::<NaiveSuccinctRMQ>();
+    assert_send_sync::<NaiveFMIndex>();
+    assert_send_sync::<NaiveRunLengthFMIndex>();
+    assert_send_sync::<SuffixArray>();
+    assert_send_sync::<NaiveLoudsTrie>();
+    #[cfg(feature = "std")]
+    assert_send_sync::<LoudsTrieView<'static>>();
+    #[cfg(feature = "mmap")]
+    assert_send_sync::<MappedLoudsTrie>();
+    assert_send_sync::<Dawg>();
+    assert_send_sync::<NestedPatriciaTrie>();
+};
+
+/// 構築を終えた索引を、複数のスレッドで共有して同時に検索するための読み取り専用の包み
+///
+/// 中身は `Arc` で保持し、 `clone()` は索引を複製せずに参照を増やすだけです。
+/// 可変参照は得られないため、共有した後に索引が書き換わることはありません。
+/// 検索は `Deref` で中身のメソッドをそのまま呼び出します。
+///
+/// 中身の型は `Send + Sync` である必要があります。
+/// ウェーブレット行列や FM-index 、 LOUDS トライ木などの静的な索引は、検索の作業領域を内部に持たず呼び出しごとに確保するため、この条件を満たします。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::frozen::Frozen;
+/// use rust_study::string::fm_index::NaiveFMIndex;
+/// use std::thread;
+/// let index = Frozen::new(NaiveFMIndex::new(b"abracadabra"));
+/// let handles: Vec<_> = (0..4).map(|_| {
+///     let index = index.clone();
+///     thread::spawn(move || index.count(b"abra"))
+/// }).collect();
+/// for handle in handles {
+///     assert_eq!(2, handle.join().unwrap());
+/// }
+/// ```
+pub struct Frozen<T> {
+    inner: Arc<T>,
+}
+
+impl <T: Send + Sync> Frozen<T> {
+    /// `value` を共有できるようにします。
+    pub fn new(value: T) -> Self {
+        Frozen { inner: Arc::new(value) }
+    }
+
+    /// 他に共有している `Frozen` がない場合、中身を取り出します。共有している場合は `this` をそのまま返します。
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        Arc::try_unwrap(this.inner).map_err(|inner| Frozen { inner })
+    }
+
+    /// `this` と `other` が同じ索引を共有している場合に、 `true` を返します。
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.inner, &other.inner)
+    }
+}
+
+impl <T> Clone for Frozen<T> {
+    fn clone(&self) -> Self {
+        Frozen { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl <T> Deref for Frozen<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl <T> AsRef<T> for Frozen<T> {
+    fn as_ref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl <T: Send + Sync> From<T> for Frozen<T> {
+    fn from(value: T) -> Self {
+        Frozen::new(value)
+    }
+}
+
+impl <T: fmt::Debug> fmt::Debug for Frozen<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Frozen").field(&*self.inner).finish()
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for Frozen<T> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.inner.heap_size_in_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::string::trie::Trie;
+
+    use std::thread;
+
+    // runs query on the shared value from several threads, and returns their results
+    fn par_query<T: Send + Sync + 'static, R: Send + 'static>(value: &Frozen<T>, query: fn(&T) -> R) -> Vec<R> {
+        let handles: Vec<_> = (0..4).map(|_| {
+            let value = value.clone();
+            thread::spawn(move || query(&value))
+        }).collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let wmat = Frozen::new(NaiveU8WaveletMatrix::new(&b"abracadabra".to_vec()));
+        assert_eq!(vec![(5, 7); 4], par_query(&wmat, |wmat| (wmat.rank(b'a', 11), wmat.select(b'a', 3))));
+        let fm = Frozen::new(NaiveFMIndex::new(b"abracadabra"));
+        assert_eq!(vec![vec![0, 7]; 4], par_query(&fm, |fm| fm.locate(b"abra")));
+        let trie = Frozen::new(NaiveLoudsTrie::new(&["the", "their", "them"]));
+        assert_eq!(vec![2; 4], par_query(&trie, |trie| trie.predict("thei").chain(trie.predict("them")).count()));
+        assert_eq!(1, Arc::strong_count(&trie.inner));
+    }
+
+    #[test]
+    fn try_unwrap() {
+        let trie = Frozen::new(NaiveLoudsTrie::new(&["a"]));
+        let shared = trie.clone();
+        assert!(Frozen::ptr_eq(&trie, &shared));
+        let trie = match Frozen::try_unwrap(trie) {
+            Ok(_) => panic!("unwrapped a shared value"),
+            Err(trie) => trie,
+        };
+        drop(shared);
+        assert!(Frozen::try_unwrap(trie).ok().unwrap().contains("a"));
+    }
+
+    #[test]
+    fn space() {
+        let fm = NaiveFMIndex::new(b"abracadabra");
+        let size = fm.size_in_bytes();
+        assert_eq!(2 * std::mem::size_of::<usize>() + size, Frozen::new(fm).heap_size_in_bytes());
+    }
+}
@@ -1,6 +1,29 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 pub mod string;
 pub mod bits;
 pub mod collections;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod frozen;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod prelude;
+pub mod progress;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod query;
+pub mod random;
+#[cfg(feature = "std")]
+pub mod serialize;
+pub mod space;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use error::Error;
 
 #[cfg(test)]
 mod tests {
@@ -31,4 +54,20 @@ mod tests {
         trie.append("that");
         assert_eq!("the", trie.prefix("theorem"));
     }
+
+    #[test]
+    fn it_works_prelude() {
+        use prelude::*;
+
+        let fid = NaiveFID::from_bool_vec(&vec![true, false, true]);
+        assert_eq!(2, fid.rank1(3));
+        let wmat = NaiveU8WaveletMatrix::new(&vec![1, 2, 1]);
+        assert_eq!(vec![(1, 2)], wmat.topk(0, 3, 1));
+        let mut heap = Heap::new();
+        heap.push(1);
+        assert_eq!(Some(1), heap.pop());
+        let mut trie = NaiveTrie::new();
+        trie.append("a");
+        assert!(trie.contains("a"));
+    }
 }
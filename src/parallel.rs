@@ -0,0 +1,92 @@
+use rayon::prelude::*;
+
+/// 並列構築の分割の単位
+///
+/// `parallel` フィーチャーで有効になる `par_*` の構築関数は、入力をこの大きさのチャンクに分けて rayon のタスクに割り当てます。
+/// チャンクを小さくするほど負荷は均等になりますが、タスクの管理のコストが増えます。
+/// 整列の段階は rayon の並列ソートに任せ、チャンクの大きさは使いません。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::bits::fid::*;
+/// use rust_study::parallel::Chunking;
+/// let bits: Vec<bool> = (0..1000).map(|i| i % 3 == 0).collect();
+/// let fid = NaiveFID::par_from_bool_vec(&bits, Chunking::new(128));
+/// assert_eq!(NaiveFID::from_bool_vec(&bits), fid);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chunking {
+    size: usize,
+}
+
+impl Chunking {
+    /// 1 つのチャンクの要素数を `size` とします。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "chunk size should be positive");
+        Chunking { size }
+    }
+
+    /// 1 つのチャンクの要素数を返します。
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// `items` を、大きさを `align` の倍数に切り上げたチャンクに分けて返します。
+    pub(crate) fn chunks<'a, T: Sync>(&self, items: &'a [T], align: usize) -> rayon::slice::Chunks<'a, T> {
+        items.par_chunks(self.size.div_ceil(align) * align)
+    }
+}
+
+impl Default for Chunking {
+    fn default() -> Self {
+        Chunking::new(1 << 16)
+    }
+}
+
+/// キーの列 `keys` を並列に整列し、重複を除いて返します。
+pub(crate) fn sorted_keys<S: AsRef<str> + Sync>(keys: &[S], chunking: Chunking) -> Vec<&str> {
+    let mut keys: Vec<&str> = keys.par_iter().with_min_len(chunking.size).map(|key| key.as_ref()).collect();
+    keys.par_sort_unstable();
+    keys.dedup();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bits::fid::FID;
+    use crate::bits::fid::NaiveFID;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn chunks() {
+        let items: Vec<usize> = (0..300).collect();
+        let sizes: Vec<usize> = Chunking::new(100).chunks(&items, 64).map(|chunk| chunk.len()).collect();
+        assert_eq!(vec![128, 128, 44], sizes);
+        assert_eq!(vec!["a", "b"], sorted_keys(&["b", "a", "b"], Chunking::new(1)));
+    }
+
+    #[test]
+    fn par_from_bool_vec() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for len in [0, 1, 63, 64, 65, 1000] {
+            let bv: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+            for size in [1, 64, 100, 4096] {
+                assert_eq!(NaiveFID::from_bool_vec(&bv), NaiveFID::par_from_bool_vec(&bv, Chunking::new(size)));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk size should be positive")]
+    fn zero() {
+        Chunking::new(0);
+    }
+}
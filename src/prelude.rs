@@ -0,0 +1,16 @@
+pub use crate::bits::fid::FID;
+pub use crate::bits::fid::NaiveFID;
+pub use crate::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+pub use crate::bits::wavelet_matrix::U8WaveletMatrix;
+pub use crate::collections::heap::Heap;
+pub use crate::frozen::Frozen;
+#[cfg(feature = "std")]
+pub use crate::serialize::Serializable;
+pub use crate::space::SpaceUsage;
+pub use crate::string::trie::NaiveTrie;
+pub use crate::string::trie::NaiveTrieMap;
+pub use crate::string::trie::Trie;
+pub use crate::string::trie::TrieCursor;
+pub use crate::string::trie::TrieId;
+pub use crate::string::trie::TrieMap;
+pub use crate::string::trie::TrieMut;
@@ -0,0 +1,279 @@
+use crate::error::Error;
+
+use alloc::sync::Arc;
+use core::fmt;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering;
+
+/// 時間のかかる構築の段階
+///
+/// [`Progress::report()`] で、どの段階の進み具合かを表します。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Phase {
+    /// キーの整列。単位はキーの個数です。
+    SortKeys,
+    /// トライ木のノードの構築。単位はキーの各文字と終端を 1 つと数えた、処理したキーの長さの合計です。
+    BuildNodes,
+    /// 接尾辞の整列。単位はテキストの長さで、整列の前後にだけ通知します。
+    SortSuffixes,
+    /// LCP 配列の構築。単位はテキストの位置です。
+    ComputeLcp,
+    /// ウェーブレット行列の各段の構築。単位は段の数です。
+    BuildLevels,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Phase::SortKeys => "sorting keys",
+            Phase::BuildNodes => "building nodes",
+            Phase::SortSuffixes => "sorting suffixes",
+            Phase::ComputeLcp => "computing LCP",
+            Phase::BuildLevels => "building levels",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 構築の進み具合の通知を受け取る型
+///
+/// `*_with_progress` の構築関数は、各段階の始めと終わり、およびその間のおおよそ [`STEP`] 単位ごとに [`Self::report()`] を呼び出します。
+/// 1 つの段階の中で `done` は減らず、段階の終わりには `done == total` で通知します。
+///
+/// `FnMut(Phase, usize, usize)` のクロージャはそのまま `Progress` として使えます。
+/// 通知が不要な場合は [`NoProgress`] を使います。
+///
+/// `try_*_with_progress` の構築関数は、通知のたびに [`Self::is_cancelled()`] を確かめ、 `true` の場合は構築をやめて [`Error::Cancelled`] を返します。
+/// 中断するには、 [`CancelToken`] を渡すか、 [`Cancellable`] で中断の条件を加えます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::progress::Phase;
+/// use rust_study::string::trie::NaiveLoudsTrie;
+/// use rust_study::string::trie::Trie;
+/// let mut reports = vec![];
+/// let trie = NaiveLoudsTrie::with_progress(&["the", "their", "them"], &mut |phase: Phase, done: usize, total: usize| {
+///     reports.push((phase, done, total));
+/// });
+/// assert_eq!(3, trie.len());
+/// assert_eq!(Some(&(Phase::SortKeys, 0, 3)), reports.first());
+/// // "the", "their" and "them" with their terminals
+/// assert_eq!(Some(&(Phase::BuildNodes, 15, 15)), reports.last());
+/// ```
+pub trait Progress {
+    /// 段階 `phase` の全体 `total` のうち、 `done` まで処理したことを通知します。
+    fn report(&mut self, phase: Phase, done: usize, total: usize);
+
+    /// 構築を中断する場合に、 `true` を返します。既定では常に `false` を返します。
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+impl <F: FnMut(Phase, usize, usize)> Progress for F {
+    fn report(&mut self, phase: Phase, done: usize, total: usize) {
+        self(phase, done, total)
+    }
+}
+
+/// 何もしない [`Progress`]
+///
+/// 進み具合を通知しない構築関数は、これを渡して `*_with_progress` の構築関数を呼び出します。
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn report(&mut self, _phase: Phase, _done: usize, _total: usize) {}
+}
+
+/// 構築を他のスレッドから中断するための目印
+///
+/// 中身は `Arc<AtomicBool>` で、 `clone()` した目印どうしは同じ状態を共有します。
+/// 1 つを構築関数に [`Progress`] として渡し、もう 1 つで [`Self::cancel()`] を呼び出すと、構築は次の通知で [`Error::Cancelled`] を返します。
+/// 進み具合も受け取る場合は、 [`Cancellable`] で通知先と組み合わせます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::Error;
+/// use rust_study::progress::CancelToken;
+/// use rust_study::string::trie::NaiveLoudsTrie;
+/// let token = CancelToken::new();
+/// assert!(NaiveLoudsTrie::try_with_progress(&["a", "b"], &mut token.clone()).is_ok());
+/// token.cancel();
+/// assert_eq!(Some(Error::Cancelled), NaiveLoudsTrie::try_with_progress(&["a", "b"], &mut token.clone()).err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// 中断されていない目印を作ります。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 目印を共有するすべての構築を中断します。
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// [`Self::cancel()`] が呼ばれている場合に、 `true` を返します。
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl From<Arc<AtomicBool>> for CancelToken {
+    fn from(cancelled: Arc<AtomicBool>) -> Self {
+        CancelToken { cancelled }
+    }
+}
+
+impl Progress for CancelToken {
+    fn report(&mut self, _phase: Phase, _done: usize, _total: usize) {}
+
+    fn is_cancelled(&self) -> bool {
+        CancelToken::is_cancelled(self)
+    }
+}
+
+/// 通知先 `progress` に、中断の条件 `cancel` を加えた [`Progress`]
+///
+/// 通知はそのまま `progress` に渡し、 `progress` が中断するか `cancel()` が `true` を返す場合に中断します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::Error;
+/// use rust_study::progress::Cancellable;
+/// use rust_study::progress::CancelToken;
+/// use rust_study::progress::Phase;
+/// use rust_study::string::suffix_array::SuffixArray;
+/// let token = CancelToken::new();
+/// let mut phases = vec![];
+/// let mut progress = Cancellable::new(|phase: Phase, done: usize, total: usize| {
+///     phases.push(phase);
+///     // gives up after sorting the suffixes
+///     if done == total {
+///         token.cancel();
+///     }
+/// }, || token.is_cancelled());
+/// assert_eq!(Some(Error::Cancelled), SuffixArray::try_with_progress(b"banana", &mut progress).err());
+/// assert_eq!(vec![Phase::SortSuffixes, Phase::SortSuffixes], phases);
+/// ```
+pub struct Cancellable<P, C> {
+    progress: P,
+    cancel: C,
+}
+
+impl <P: Progress, C: Fn() -> bool> Cancellable<P, C> {
+    /// `progress` に通知し、 `cancel()` が `true` を返す場合にも中断します。
+    pub fn new(progress: P, cancel: C) -> Self {
+        Cancellable { progress, cancel }
+    }
+
+    /// 包んでいる通知先を取り出します。
+    pub fn into_inner(self) -> P {
+        self.progress
+    }
+}
+
+impl <P: Progress, C: Fn() -> bool> Progress for Cancellable<P, C> {
+    fn report(&mut self, phase: Phase, done: usize, total: usize) {
+        self.progress.report(phase, done, total)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.progress.is_cancelled() || (self.cancel)()
+    }
+}
+
+/// `progress` に通知し、中断する場合は [`Error::Cancelled`] を返します。
+pub(crate) fn checkpoint<P: Progress + ?Sized>(progress: &mut P, phase: Phase, done: usize, total: usize) -> Result<(), Error> {
+    progress.report(phase, done, total);
+    if progress.is_cancelled() { Err(Error::Cancelled) } else { Ok(()) }
+}
+
+/// 段階の途中で通知する間隔
+///
+/// 要素ごとに進む段階では、おおよそこの個数を処理するごとに通知します。
+pub const STEP: usize = 1 << 16;
+
+/// `done` が `total` に占める割合を、切り捨てた百分率で返します。 `total` が 0 の場合は 100 を返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::progress::percent;
+/// assert_eq!(33, percent(1, 3));
+/// assert_eq!(100, percent(0, 0));
+/// ```
+pub fn percent(done: usize, total: usize) -> usize {
+    if total == 0 {
+        return 100;
+    }
+    (done as u128 * 100 / total as u128) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn closure() {
+        let mut reports = vec![];
+        let mut progress = |phase: Phase, done: usize, total: usize| reports.push((phase, done, total));
+        progress.report(Phase::BuildLevels, 1, 8);
+        NoProgress.report(Phase::BuildLevels, 2, 8);
+        assert_eq!(vec![(Phase::BuildLevels, 1, 8)], reports);
+    }
+
+    #[test]
+    fn cancel() {
+        let token = CancelToken::new();
+        let shared = token.clone();
+        assert_eq!(Ok(()), checkpoint(&mut token.clone(), Phase::SortKeys, 0, 1));
+        shared.cancel();
+        assert!(token.is_cancelled());
+        assert_eq!(Err(Error::Cancelled), checkpoint(&mut token.clone(), Phase::SortKeys, 0, 1));
+
+        let flag = Arc::new(AtomicBool::new(true));
+        assert!(Progress::is_cancelled(&CancelToken::from(flag.clone())));
+        flag.store(false, Ordering::Relaxed);
+        assert!(!Progress::is_cancelled(&CancelToken::from(flag)));
+    }
+
+    #[test]
+    fn cancellable() {
+        let mut reports = vec![];
+        let mut progress = Cancellable::new(|phase: Phase, done: usize, _: usize| reports.push((phase, done)), || false);
+        assert_eq!(Ok(()), checkpoint(&mut progress, Phase::BuildNodes, 1, 2));
+        let mut progress = Cancellable::new(progress.into_inner(), || true);
+        assert_eq!(Err(Error::Cancelled), checkpoint(&mut progress, Phase::BuildNodes, 2, 2));
+        let token = CancelToken::new();
+        token.cancel();
+        assert!(Cancellable::new(token, || false).is_cancelled());
+        assert_eq!(vec![(Phase::BuildNodes, 1), (Phase::BuildNodes, 2)], reports);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!("sorting suffixes", Phase::SortSuffixes.to_string());
+        assert_eq!("computing LCP", Phase::ComputeLcp.to_string());
+    }
+
+    #[test]
+    fn percent() {
+        assert_eq!(0, super::percent(0, 7));
+        assert_eq!(42, super::percent(3, 7));
+        assert_eq!(100, super::percent(7, 7));
+        assert_eq!(50, super::percent(usize::MAX / 2, usize::MAX - 1));
+    }
+}
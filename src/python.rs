@@ -0,0 +1,293 @@
+use crate::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+use crate::error::Error;
+use crate::serialize;
+use crate::serialize::Serializable;
+use crate::string::fm_index::NaiveFMIndex;
+use crate::string::trie::NaiveLoudsTrie;
+use crate::string::trie::Trie;
+
+use pyo3::exceptions::PyIndexError;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+impl From<Error> for PyErr {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::OutOfBounds { .. } => PyIndexError::new_err(error.to_string()),
+            _ => PyValueError::new_err(error.to_string()),
+        }
+    }
+}
+
+// the value read from the whole bytes, raising ValueError if they are broken or have trailing bytes
+fn from_bytes<T: Serializable>(bytes: &[u8]) -> PyResult<T> {
+    serialize::from_bytes(bytes).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Python の `WaveletMatrix` クラス
+///
+/// 0 から 255 の整数の列を [`NaiveU8WaveletMatrix`] で保持します。
+/// 範囲外の添字には `IndexError` を、現れない値の `select` には `ValueError` を送出します。
+#[pyclass(name = "WaveletMatrix", module = "rust_study", frozen)]
+pub struct PyWaveletMatrix(NaiveU8WaveletMatrix);
+
+#[pymethods]
+impl PyWaveletMatrix {
+    #[new]
+    fn new(values: Vec<u8>) -> Self {
+        PyWaveletMatrix(NaiveU8WaveletMatrix::new(&values))
+    }
+
+    /// [`Serializable::serialize()`] で書き出したバイト列から読み込みます。
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        let wmat: NaiveU8WaveletMatrix = from_bytes(bytes)?;
+        // the queries panic on a broken matrix, which the format checks alone do not rule out
+        wmat.validate()?;
+        Ok(PyWaveletMatrix(wmat))
+    }
+
+    /// [`Serializable::serialize()`] の形式のバイト列を返します。
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &serialize::to_bytes(&self.0))
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    fn access(&self, i: usize) -> PyResult<u8> {
+        Ok(self.0.try_access(i)?)
+    }
+
+    fn rank(&self, v: u8, i: usize) -> usize {
+        self.0.rank(v, i)
+    }
+
+    fn select(&self, v: u8, i: usize) -> PyResult<usize> {
+        Ok(self.0.try_select(v, i)?)
+    }
+
+    fn quantile(&self, s: usize, e: usize, r: usize) -> PyResult<u8> {
+        Ok(self.0.try_quantile(s, e, r)?)
+    }
+
+    fn topk(&self, s: usize, e: usize, k: usize) -> PyResult<Vec<(u8, usize)>> {
+        Ok(self.0.try_topk(s, e, k)?)
+    }
+}
+
+/// Python の `FMIndex` クラス
+///
+/// `bytes` のテキストを [`NaiveFMIndex`] で保持します。
+#[pyclass(name = "FMIndex", module = "rust_study", frozen)]
+pub struct PyFMIndex(NaiveFMIndex);
+
+#[pymethods]
+impl PyFMIndex {
+    #[new]
+    fn new(text: &[u8]) -> Self {
+        PyFMIndex(NaiveFMIndex::new(text))
+    }
+
+    /// [`Serializable::serialize()`] で書き出したバイト列から読み込みます。
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        // deserialize() validates the index
+        from_bytes(bytes).map(PyFMIndex)
+    }
+
+    /// [`Serializable::serialize()`] の形式のバイト列を返します。
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &serialize::to_bytes(&self.0))
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    fn count(&self, pattern: &[u8]) -> usize {
+        self.0.count(pattern)
+    }
+
+    fn locate(&self, pattern: &[u8]) -> Vec<usize> {
+        self.0.locate(pattern)
+    }
+}
+
+/// Python の `Trie` クラス
+///
+/// `str` のキーの集合を [`NaiveLoudsTrie`] で保持します。
+#[pyclass(name = "Trie", module = "rust_study", frozen)]
+pub struct PyTrie(NaiveLoudsTrie);
+
+#[pymethods]
+impl PyTrie {
+    #[new]
+    fn new(keys: Vec<String>) -> Self {
+        PyTrie(NaiveLoudsTrie::new(&keys))
+    }
+
+    /// [`Serializable::serialize()`] で書き出したバイト列から読み込みます。
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        let trie: NaiveLoudsTrie = from_bytes(bytes)?;
+        // the queries panic on a broken tree, which the format checks alone do not rule out
+        trie.validate()?;
+        Ok(PyTrie(trie))
+    }
+
+    /// [`Serializable::serialize()`] の形式のバイト列を返します。
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &serialize::to_bytes(&self.0))
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        self.0.contains(key)
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.0.keys().collect()
+    }
+
+    fn prefix(&self, query: &str) -> String {
+        self.0.prefix(query).to_string()
+    }
+
+    fn common_prefixes(&self, query: &str) -> Vec<String> {
+        self.0.common_prefixes(query).map(str::to_string).collect()
+    }
+
+    fn count_prefix(&self, prefix: &str) -> usize {
+        self.0.count_prefix(prefix)
+    }
+
+    fn predict(&self, prefix: &str) -> Vec<String> {
+        self.0.predict(prefix).collect()
+    }
+
+    fn fuzzy_search(&self, query: &str, max_edits: usize) -> Vec<(String, usize)> {
+        self.0.fuzzy_search(query, max_edits)
+    }
+}
+
+/// Python の `rust_study` モジュールを初期化します。
+///
+/// `python` フィーチャーを有効にして共有ライブラリとしてビルドすると、 Python から `import rust_study` で読み込めます。
+/// maturin を使う場合は、リポジトリの `pyproject.toml` で `maturin develop` や `maturin build` ができます。
+#[pymodule]
+pub fn rust_study(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyWaveletMatrix>()?;
+    m.add_class::<PyFMIndex>()?;
+    m.add_class::<PyTrie>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pyo3::types::PyDict;
+    use std::ffi::CStr;
+
+    // runs code with the rust_study module imported
+    fn run(code: &CStr) {
+        Python::initialize();
+        Python::attach(|py| {
+            let module = PyModule::new(py, "rust_study").unwrap();
+            rust_study(&module).unwrap();
+            let globals = PyDict::new(py);
+            globals.set_item("rust_study", module).unwrap();
+            if let Err(e) = py.run(code, Some(&globals), None) {
+                panic!("{}", e);
+            }
+        });
+    }
+
+    #[test]
+    fn wavelet_matrix() {
+        run(cr#"
+wmat = rust_study.WaveletMatrix([3, 1, 4, 1, 5, 9, 2, 6])
+assert len(wmat) == 8
+assert [wmat.access(i) for i in range(8)] == [3, 1, 4, 1, 5, 9, 2, 6]
+assert wmat.rank(1, 4) == 2
+assert wmat.select(1, 1) == 3
+assert wmat.quantile(0, 4, 0) == 1
+assert wmat.topk(0, 8, 1) == [(1, 2)]
+assert rust_study.WaveletMatrix.from_bytes(wmat.to_bytes()).access(5) == 9
+try:
+    wmat.access(8)
+    assert False
+except IndexError:
+    pass
+try:
+    wmat.select(7, 0)
+    assert False
+except ValueError:
+    pass
+try:
+    rust_study.WaveletMatrix([256])
+    assert False
+except OverflowError:
+    pass
+# the offset of 255 at the end, which is 8 for the absent value
+broken = bytearray(wmat.to_bytes())
+broken[-8] = 0
+try:
+    rust_study.WaveletMatrix.from_bytes(bytes(broken))
+    assert False
+except ValueError:
+    pass
+"#);
+    }
+
+    #[test]
+    fn fm_index() {
+        run(cr#"
+fm = rust_study.FMIndex(b"abracadabra")
+assert len(fm) == 11
+assert fm.count(b"abra") == 2
+assert fm.locate(b"a") == [0, 3, 5, 7, 10]
+assert rust_study.FMIndex.from_bytes(fm.to_bytes()).locate(b"abra") == [0, 7]
+try:
+    rust_study.FMIndex.from_bytes(fm.to_bytes()[:-1])
+    assert False
+except ValueError:
+    pass
+"#);
+    }
+
+    #[test]
+    fn trie() {
+        run(cr#"
+trie = rust_study.Trie(["the", "their", "them", "this", "the"])
+assert len(trie) == 4
+assert "them" in trie and "th" not in trie
+assert trie.keys() == ["the", "their", "them", "this"]
+assert trie.prefix("theirs") == "their"
+assert trie.common_prefixes("theirs") == ["the", "their"]
+assert trie.count_prefix("the") == 3
+assert trie.predict("the") == ["the", "their", "them"]
+assert trie.fuzzy_search("thus", 1) == [("this", 1)]
+assert "this" in rust_study.Trie.from_bytes(trie.to_bytes())
+try:
+    rust_study.Trie.from_bytes(trie.to_bytes() + b"\0")
+    assert False
+except ValueError:
+    pass
+# the labels "b" and "c" swapped, before the terminal bits of 24 bytes
+broken = bytearray(rust_study.Trie(["ab", "ac"]).to_bytes())
+broken[-26], broken[-25] = broken[-25], broken[-26]
+try:
+    rust_study.Trie.from_bytes(bytes(broken))
+    assert False
+except ValueError:
+    pass
+"#);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::bits::wavelet_matrix::TopKItem;
+use crate::collections::heap::Heap;
+
+use alloc::vec::Vec;
+
+/// 検索の作業領域
+///
+/// 検索のたびに確保していた作業用の配列を保持し、 `*_into` の検索で使い回します。
+/// 結果を書き込む `Vec` と合わせて使い回すと、容量が足りている限り、検索ごとの領域の確保がなくなります。
+///
+/// 中身は 1 回の検索の途中の状態だけで、前の検索の内容が結果に影響することはありません。
+/// 同時に 1 つの検索にしか使えないため、複数のスレッドで検索する場合はスレッドごとに用意します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+/// use rust_study::query::QueryBuffer;
+/// let wmat = NaiveU8WaveletMatrix::new(&b"abracadabra".to_vec());
+/// let mut buffer = QueryBuffer::new();
+/// let mut out = vec![];
+/// for (s, count) in [(0, 5), (1, 4), (4, 3), (8, 1)] {
+///     wmat.topk_into(s, wmat.len(), 1, &mut buffer, &mut out);
+///     assert_eq!(vec![(b'a', count)], out);
+/// }
+/// ```
+pub struct QueryBuffer {
+    // the heap of the nodes for U8WaveletMatrix::topk_into
+    pub(crate) topk: Heap<TopKItem>,
+    // the stack of the nodes (s, e, depth, value) for U8WaveletMatrix::range_list_into
+    pub(crate) ranges: Vec<(usize, usize, usize, usize)>,
+    // the stack of the nodes (id, depth) for LoudsTrie::predict_into
+    pub(crate) nodes: Vec<(usize, usize)>,
+    // the key of the current node for LoudsTrie::predict_into
+    pub(crate) key: Vec<u8>,
+}
+
+impl QueryBuffer {
+    /// 空の作業領域を作ります。領域は最初に使う検索で確保します。
+    pub fn new() -> Self {
+        QueryBuffer {
+            topk: Heap::with_compare(TopKItem::compare),
+            ranges: Vec::new(),
+            nodes: Vec::new(),
+            key: Vec::new(),
+        }
+    }
+}
+
+impl Default for QueryBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
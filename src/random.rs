@@ -0,0 +1,70 @@
+#[cfg(feature = "std")]
+use rand::RngCore;
+
+/// スレッドごとの乱数生成器 `rand::thread_rng()` から乱数を得る、既定の乱数生成器
+///
+/// Treap やカッコーフィルタなどのランダムな構造は、乱数生成器を型引数で受け取り、既定ではこの型を使います。
+/// 状態を持たないため、これを保持する構造も `Send` や `Sync` のままです。
+///
+/// 結果を再現したいテストやシミュレーションでは、代わりに `rand::rngs::StdRng::seed_from_u64()` などのシードを固定した乱数生成器を `with_rng()` で渡します。
+///
+/// `std` フィーチャーが無効な場合、 `thread_rng()` がないため、この型は乱数生成器として使えません。
+/// ランダムな構造は `with_rng()` に乱数生成器を渡して構築します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::collections::treap::Treap;
+/// use rust_study::string::rolling_hash::RollingHasher;
+/// use rand::SeedableRng;
+/// use rand::rngs::StdRng;
+/// let mut treap = Treap::with_rng(StdRng::seed_from_u64(42));
+/// treap.insert('a', 1);
+/// assert_eq!(Some(&1), treap.get(&'a'));
+///
+/// // 同じシードからは同じ基数が選ばれます。
+/// let h1 = RollingHasher::with_rng(b"abracadabra", &mut StdRng::seed_from_u64(42));
+/// let h2 = RollingHasher::with_rng(b"abracadabra", &mut StdRng::seed_from_u64(42));
+/// assert_eq!(h1.bases(), h2.bases());
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultRng;
+
+#[cfg(feature = "std")]
+impl RngCore for DefaultRng {
+    fn next_u32(&mut self) -> u32 {
+        rand::thread_rng().next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand::thread_rng().next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand::thread_rng().fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        rand::thread_rng().try_fill_bytes(dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    #[test]
+    fn default_rng() {
+        use super::*;
+        use rand::Rng;
+
+        const fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<DefaultRng>();
+
+        let mut rng = DefaultRng;
+        let values: Vec<u64> = (0..8).map(|_| rng.gen()).collect();
+        assert!(values.iter().any(|&v| v != values[0]));
+        let mut bytes = [0u8; 64];
+        rng.fill_bytes(&mut bytes);
+        assert!(bytes.iter().any(|&b| b != 0));
+    }
+}
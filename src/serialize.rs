@@ -0,0 +1,229 @@
+use crate::error::Error;
+
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+/// クレート共通のバイナリ形式で書き出し、読み込みができるデータ構造
+///
+/// 書き出したバイト列は、型ごとの 8 バイトの識別子 (magic) 、リトルエンディアン 4 バイトの版、
+/// 本体の長さ (リトルエンディアン 8 バイト) 、本体の順に並んだ 1 つの区画 (section) です。
+/// 複数のデータ構造を組み合わせた索引は、本体に各部品の区画を順に書き出すことで、 1 つのファイルにまとめられます。
+/// 区画は長さつきのため、読み込む側は不要な区画を読み飛ばせます。
+///
+/// 読み込むバイト列が壊れている場合や、識別子や版が一致しない場合、 [`Error`] を包んだ `ErrorKind::InvalidData` のエラーを返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+/// use rust_study::serialize::Serializable;
+/// let wmat = NaiveU8WaveletMatrix::new(&b"abracadabra".to_vec());
+/// let mut bytes = vec![];
+/// wmat.serialize(&mut bytes).unwrap();
+/// assert_eq!(b"WAVELETM", &bytes[..8]);
+///
+/// let read = NaiveU8WaveletMatrix::deserialize(&mut bytes.as_slice()).unwrap();
+/// assert_eq!(5, read.rank(b'a', read.len()));
+/// assert!(NaiveU8WaveletMatrix::deserialize(&mut &bytes[..bytes.len() - 1]).is_err());
+/// ```
+pub trait Serializable: Sized {
+    /// 型ごとに異なる 8 バイトの識別子
+    const MAGIC: [u8; 8];
+    /// 本体の書式の版
+    const VERSION: u32;
+
+    /// 本体を `w` に書き出します。
+    fn write_body<W: Write>(&self, w: &mut W) -> io::Result<()>;
+
+    /// [`Self::write_body()`] で書き出した本体を `r` から読み込みます。
+    fn read_body<R: Read>(r: &mut R) -> io::Result<Self>;
+
+    /// 識別子、版、長さつきの本体からなる区画を `w` に書き出します。
+    fn serialize<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut body = vec![];
+        self.write_body(&mut body)?;
+        w.write_all(&Self::MAGIC)?;
+        w.write_all(&Self::VERSION.to_le_bytes())?;
+        write_bytes(w, &body)
+    }
+
+    /// [`Self::serialize()`] で書き出した区画を `r` から読み込みます。
+    fn deserialize<R: Read>(r: &mut R) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if magic != Self::MAGIC {
+            return Err(invalid_data("unexpected magic number"));
+        }
+        let mut version = [0; 4];
+        r.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != Self::VERSION {
+            return Err(Error::VersionMismatch { expected: Self::VERSION, found: version }.into());
+        }
+        let body = read_bytes(r)?;
+        let mut rest = body.as_slice();
+        let value = Self::read_body(&mut rest)?;
+        if !rest.is_empty() {
+            return Err(invalid_data("trailing bytes in the section"));
+        }
+        Ok(value)
+    }
+}
+
+/// `value` の区画をバイト列として返します。
+pub fn to_bytes<T: Serializable>(value: &T) -> Vec<u8> {
+    let mut bytes = vec![];
+    value.serialize(&mut bytes).expect("writing to a vec never fails");
+    bytes
+}
+
+/// ちょうど 1 つの区画からなる `bytes` を読み込みます。区画の後にバイトが続く場合、エラーを返します。
+pub fn from_bytes<T: Serializable>(mut bytes: &[u8]) -> io::Result<T> {
+    let value = T::deserialize(&mut bytes)?;
+    if !bytes.is_empty() {
+        return Err(invalid_data("trailing bytes"));
+    }
+    Ok(value)
+}
+
+/// [`Error::CorruptData`] を包んだ `ErrorKind::InvalidData` のエラーを作ります。
+pub fn invalid_data(message: &str) -> io::Error {
+    Error::CorruptData(message.to_string()).into()
+}
+
+/// `v` をリトルエンディアン 8 バイトで書き出します。
+pub fn write_u64<W: Write>(w: &mut W, v: u64) -> io::Result<()> {
+    w.write_all(&v.to_le_bytes())
+}
+
+/// リトルエンディアン 8 バイトの整数を読み込みます。
+pub fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// リトルエンディアン 8 バイトの長さを読み込みます。 `usize` に収まらない場合、エラーを返します。
+pub fn read_len<R: Read>(r: &mut R) -> io::Result<usize> {
+    usize::try_from(read_u64(r)?).map_err(|_| invalid_data("length overflow"))
+}
+
+/// 長さつきのバイト列を書き出します。
+pub fn write_bytes<W: Write>(w: &mut W, bytes: &[u8]) -> io::Result<()> {
+    write_u64(w, bytes.len() as u64)?;
+    w.write_all(bytes)
+}
+
+/// [`write_bytes()`] で書き出したバイト列を読み込みます。
+pub fn read_bytes<R: Read>(r: &mut R) -> io::Result<Vec<u8>> {
+    let len = read_len(r)?;
+    let mut bytes = vec![];
+    r.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+/// 長さつきの `u64` の列を書き出します。
+pub fn write_u64s<W: Write>(w: &mut W, values: &[u64]) -> io::Result<()> {
+    write_u64(w, values.len() as u64)?;
+    values.iter().try_for_each(|&v| write_u64(w, v))
+}
+
+/// [`write_u64s()`] で書き出した列を読み込みます。
+pub fn read_u64s<R: Read>(r: &mut R) -> io::Result<Vec<u64>> {
+    let len = read_len(r)?;
+    let mut values = vec![];
+    for _ in 0..len {
+        values.push(read_u64(r)?);
+    }
+    Ok(values)
+}
+
+/// `value` の区画を、バイト列として serde で書き出します。
+#[cfg(feature = "serde")]
+pub(crate) fn serialize_section<T: Serializable, S: serde::Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut bytes = vec![];
+    value.serialize(&mut bytes).map_err(serde::ser::Error::custom)?;
+    serializer.serialize_bytes(&bytes)
+}
+
+/// [`serialize_section()`] で書き出したバイト列を serde で読み込みます。
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize_section<'de, T: Serializable, D: serde::Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+    let bytes = <Vec<u8> as serde::Deserialize>::deserialize(deserializer)?;
+    let mut rest = bytes.as_slice();
+    let value = T::deserialize(&mut rest).map_err(serde::de::Error::custom)?;
+    if !rest.is_empty() {
+        return Err(serde::de::Error::custom("trailing bytes after the section"));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Pair(u64, Vec<u8>);
+
+    impl Serializable for Pair {
+        const MAGIC: [u8; 8] = *b"TESTPAIR";
+        const VERSION: u32 = 2;
+
+        fn write_body<W: Write>(&self, w: &mut W) -> io::Result<()> {
+            write_u64(w, self.0)?;
+            write_bytes(w, &self.1)
+        }
+
+        fn read_body<R: Read>(r: &mut R) -> io::Result<Self> {
+            Ok(Pair(read_u64(r)?, read_bytes(r)?))
+        }
+    }
+
+    #[test]
+    fn sections() {
+        let (a, b) = (Pair(1, b"abc".to_vec()), Pair(2, vec![]));
+        let mut bytes = vec![];
+        a.serialize(&mut bytes).unwrap();
+        b.serialize(&mut bytes).unwrap();
+        let mut r = bytes.as_slice();
+        assert_eq!(a, Pair::deserialize(&mut r).unwrap());
+        assert_eq!(b, Pair::deserialize(&mut r).unwrap());
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn bytes() {
+        let pair = Pair(1, b"abc".to_vec());
+        let mut bytes = to_bytes(&pair);
+        assert_eq!(pair, from_bytes(&bytes).unwrap());
+        bytes.push(0);
+        assert_eq!(io::ErrorKind::InvalidData, from_bytes::<Pair>(&bytes).unwrap_err().kind());
+    }
+
+    #[test]
+    fn broken() {
+        let mut bytes = vec![];
+        Pair(1, b"abc".to_vec()).serialize(&mut bytes).unwrap();
+        let kind = |bytes: &[u8]| Pair::deserialize(&mut &bytes[..]).unwrap_err().kind();
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert_eq!(io::ErrorKind::InvalidData, kind(&wrong_magic));
+        let mut wrong_version = bytes.clone();
+        wrong_version[8] = 1;
+        assert_eq!(io::ErrorKind::InvalidData, kind(&wrong_version));
+        let error = Pair::deserialize(&mut wrong_version.as_slice()).unwrap_err();
+        let expected = Error::VersionMismatch { expected: 2, found: 1 };
+        assert_eq!(Some(&expected), error.get_ref().and_then(|e| e.downcast_ref()));
+        let mut trailing = bytes.clone();
+        // a longer body with a garbage byte at the end
+        trailing[12] += 1;
+        trailing.push(0);
+        assert_eq!(io::ErrorKind::InvalidData, kind(&trailing));
+        assert_eq!(io::ErrorKind::UnexpectedEof, kind(&bytes[..bytes.len() - 1]));
+    }
+}
@@ -0,0 +1,347 @@
+use alloc::borrow::Cow;
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::collections::BTreeSet;
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+
+/// 使用メモリを報告できるデータ構造
+///
+/// [`Self::size_in_bytes()`] は、値そのものの大きさと、値が所有するヒープ領域の大きさの合計です。
+/// 借用しているだけの領域は数えません。
+/// 同じ内容を素朴な表現と簡潔な表現で保持したときの大きさを、実際に比べるのに使えます。
+///
+/// 実装は、フィールドの内訳を [`Self::children()`] で返すだけで済みます。
+/// ヒープ領域の大きさは、既定では内訳の各フィールドのヒープ領域の大きさの合計になります。
+/// 内訳を [`SpaceReport`] で木の形に表示できます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::bits::fid::*;
+/// use rust_study::space::SpaceUsage;
+/// let bits: Vec<bool> = (0..10000).map(|i| i % 3 == 0).collect();
+/// let fid = NaiveFID::from_bool_vec(&bits);
+/// // a bool takes a byte, while the FID takes a bit and some overhead for rank
+/// assert!(fid.size_in_bytes() < bits.size_in_bytes() / 3);
+/// let names: Vec<&str> = fid.children().into_iter().map(|(name, _)| name).collect();
+/// assert_eq!(vec!["blocks", "popcount_offset"], names);
+/// ```
+pub trait SpaceUsage {
+    /// 値そのものと、値が所有するヒープ領域を合わせた使用メモリを、バイト単位で返します。
+    fn size_in_bytes(&self) -> usize {
+        mem::size_of_val(self) + self.heap_size_in_bytes()
+    }
+
+    /// 値が所有するヒープ領域の大きさを、バイト単位で返します。
+    ///
+    /// 既定では、 [`Self::children()`] の各フィールドのヒープ領域の大きさの合計を返します。
+    fn heap_size_in_bytes(&self) -> usize {
+        self.children().iter().map(|(_, child)| child.heap_size_in_bytes()).sum()
+    }
+
+    /// 使用メモリの内訳として、フィールドの名前と値の組を返します。既定では空です。
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        Vec::new()
+    }
+}
+
+/// 使用メモリの内訳を木の形で表示する [`fmt::Display`]
+///
+/// 各行に、名前、使用メモリのバイト数、根に対する割合を表示します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+/// use rust_study::space::SpaceReport;
+/// let wmat = NaiveU8WaveletMatrix::new(&b"abracadabra".to_vec());
+/// let report = SpaceReport::new("wavelet", &wmat).to_string();
+/// assert!(report.starts_with("wavelet: "));
+/// assert!(report.contains("── matrix: "));
+/// ```
+pub struct SpaceReport<'a> {
+    name: &'a str,
+    value: &'a dyn SpaceUsage,
+    max_depth: usize,
+}
+
+impl <'a> SpaceReport<'a> {
+    /// 値 `value` の内訳を、名前 `name` の根から表示します。
+    pub fn new(name: &'a str, value: &'a dyn SpaceUsage) -> Self {
+        SpaceReport { name, value, max_depth: usize::MAX }
+    }
+
+    /// 根からの深さが `max_depth` までの内訳だけを表示します。
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    // is_last tells, for each node on the path from the root except the root, whether it is the last child
+    fn write_node(&self, f: &mut fmt::Formatter<'_>, is_last: &mut Vec<bool>, name: &str, value: &dyn SpaceUsage, total: usize) -> fmt::Result {
+        let bytes = value.size_in_bytes();
+        if let Some((&last, ancestors)) = is_last.split_last() {
+            for &ancestor_is_last in ancestors {
+                write!(f, "{}", if ancestor_is_last { "    " } else { "│   " })?;
+            }
+            write!(f, "{}", if last { "└── " } else { "├── " })?;
+        }
+        write!(f, "{}: {} bytes", name, bytes)?;
+        if total > 0 {
+            write!(f, " ({:.1}%)", bytes as f64 * 100.0 / total as f64)?;
+        }
+        writeln!(f)?;
+        if is_last.len() < self.max_depth {
+            let children = value.children();
+            for (i, (child_name, child)) in children.iter().enumerate() {
+                is_last.push(i + 1 == children.len());
+                self.write_node(f, is_last, child_name, *child, total)?;
+                is_last.pop();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SpaceReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_node(f, &mut Vec::new(), self.name, self.value, self.value.size_in_bytes())
+    }
+}
+
+macro_rules! impl_inline {
+    ($($t:ty),*) => {
+        $(impl SpaceUsage for $t {
+            fn heap_size_in_bytes(&self) -> usize {
+                0
+            }
+        })*
+    };
+}
+
+impl_inline!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, bool, char, ());
+
+impl <T: ?Sized> SpaceUsage for PhantomData<T> {
+    fn heap_size_in_bytes(&self) -> usize {
+        0
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for [T] {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.iter().map(|item| item.heap_size_in_bytes()).sum()
+    }
+}
+
+impl <T: SpaceUsage, const N: usize> SpaceUsage for [T; N] {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.as_slice().heap_size_in_bytes()
+    }
+}
+
+impl SpaceUsage for str {
+    fn heap_size_in_bytes(&self) -> usize {
+        0
+    }
+}
+
+impl SpaceUsage for String {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl <T: SpaceUsage + ?Sized> SpaceUsage for Box<T> {
+    fn heap_size_in_bytes(&self) -> usize {
+        (**self).size_in_bytes()
+    }
+}
+
+/// 参照カウントの領域を含めて、参照先を数えます。
+/// 複数の値が参照先を共有している場合、それぞれの値で数えます。
+impl <T: SpaceUsage + ?Sized> SpaceUsage for Rc<T> {
+    fn heap_size_in_bytes(&self) -> usize {
+        2 * mem::size_of::<usize>() + (**self).size_in_bytes()
+    }
+}
+
+/// [`Rc`] と同じく、参照カウントの領域を含めて参照先を数えます。
+impl <T: SpaceUsage + ?Sized> SpaceUsage for Arc<T> {
+    fn heap_size_in_bytes(&self) -> usize {
+        2 * mem::size_of::<usize>() + (**self).size_in_bytes()
+    }
+}
+
+impl <T: SpaceUsage + ToOwned + ?Sized> SpaceUsage for Cow<'_, T> where T::Owned: SpaceUsage {
+    fn heap_size_in_bytes(&self) -> usize {
+        match self {
+            Cow::Borrowed(_) => 0,
+            Cow::Owned(owned) => owned.heap_size_in_bytes(),
+        }
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for Option<T> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.as_ref().map_or(0, |value| value.heap_size_in_bytes())
+    }
+}
+
+impl <A: SpaceUsage, B: SpaceUsage> SpaceUsage for (A, B) {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.0.heap_size_in_bytes() + self.1.heap_size_in_bytes()
+    }
+}
+
+impl <A: SpaceUsage, B: SpaceUsage, C: SpaceUsage> SpaceUsage for (A, B, C) {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.0.heap_size_in_bytes() + self.1.heap_size_in_bytes() + self.2.heap_size_in_bytes()
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for Range<T> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.start.heap_size_in_bytes() + self.end.heap_size_in_bytes()
+    }
+}
+
+/// 確保済みで未使用の領域も数えます。
+impl <T: SpaceUsage> SpaceUsage for Vec<T> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.as_slice().heap_size_in_bytes()
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for VecDeque<T> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.iter().map(|item| item.heap_size_in_bytes()).sum::<usize>()
+    }
+}
+
+/// 要素の領域だけを数えた見積もりで、 B 木のノードの余分な領域は含みません。
+impl <K: SpaceUsage, V: SpaceUsage> SpaceUsage for BTreeMap<K, V> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.iter().map(|(k, v)| mem::size_of::<(K, V)>() + k.heap_size_in_bytes() + v.heap_size_in_bytes()).sum()
+    }
+}
+
+/// 要素の領域だけを数えた見積もりで、 B 木のノードの余分な領域は含みません。
+impl <T: SpaceUsage> SpaceUsage for BTreeSet<T> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.iter().map(|item| mem::size_of::<T>() + item.heap_size_in_bytes()).sum()
+    }
+}
+
+/// 要素ごとに 1 バイトの制御領域を持つハッシュテーブルとしての見積もりです。
+#[cfg(feature = "std")]
+impl <K: SpaceUsage, V: SpaceUsage, S> SpaceUsage for HashMap<K, V, S> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.capacity() * (mem::size_of::<(K, V)>() + 1)
+            + self.iter().map(|(k, v)| k.heap_size_in_bytes() + v.heap_size_in_bytes()).sum::<usize>()
+    }
+}
+
+/// 要素ごとに 1 バイトの制御領域を持つハッシュテーブルとしての見積もりです。
+#[cfg(feature = "std")]
+impl <T: SpaceUsage, S> SpaceUsage for HashSet<T, S> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.capacity() * (mem::size_of::<T>() + 1) + self.iter().map(|item| item.heap_size_in_bytes()).sum::<usize>()
+    }
+}
+
+/// `std::collections::HashMap` と同じ見積もりです。
+impl <K: SpaceUsage, V: SpaceUsage, S> SpaceUsage for hashbrown::HashMap<K, V, S> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.capacity() * (mem::size_of::<(K, V)>() + 1)
+            + self.iter().map(|(k, v)| k.heap_size_in_bytes() + v.heap_size_in_bytes()).sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bits::fid::FID;
+    use crate::bits::fid::NaiveFID;
+    use crate::string::trie::NaiveLoudsTrie;
+    use crate::string::trie::NaiveTrie;
+
+    #[test]
+    fn containers() {
+        assert_eq!(8, 1u64.size_in_bytes());
+        let mut v: Vec<u32> = Vec::with_capacity(10);
+        v.push(1);
+        assert_eq!(mem::size_of::<Vec<u32>>() + 40, v.size_in_bytes());
+        let s = String::from("abc");
+        assert_eq!(mem::size_of::<String>() + s.capacity(), s.size_in_bytes());
+        let nested = vec![s.clone(), String::new()];
+        assert_eq!(nested.capacity() * mem::size_of::<String>() + s.capacity(), nested.heap_size_in_bytes());
+        let boxed: Box<[u16]> = vec![0; 5].into_boxed_slice();
+        assert_eq!(10, boxed.heap_size_in_bytes());
+        let some: Option<Box<u64>> = Some(Box::new(0));
+        assert_eq!(8, some.heap_size_in_bytes());
+        assert_eq!(0, None::<Box<u64>>.heap_size_in_bytes());
+        assert_eq!(3 * 8, [1u64, 2, 3].size_in_bytes());
+    }
+
+    #[test]
+    fn children() {
+        let fid = NaiveFID::from_bool_vec(&vec![true; 1000]);
+        let heap: usize = fid.children().iter().map(|(_, child)| child.heap_size_in_bytes()).sum();
+        assert_eq!(heap, fid.heap_size_in_bytes());
+        assert!(fid.heap_size_in_bytes() >= 1000 / 8);
+    }
+
+    #[test]
+    fn naive_and_succinct() {
+        let keys: Vec<String> = (0..1000).map(|i| format!("key{}", i * 7)).collect();
+        let naive: NaiveTrie = keys.iter().collect();
+        let louds = NaiveLoudsTrie::new(&keys);
+        assert!(louds.size_in_bytes() * 10 < naive.size_in_bytes());
+        assert!(louds.size_in_bytes() < keys.size_in_bytes());
+    }
+
+    #[test]
+    fn report() {
+        let fid = NaiveFID::from_bool_vec(&vec![true; 64 * 10]);
+        let total = fid.size_in_bytes();
+        let percent = |bytes: usize| bytes as f64 * 100.0 / total as f64;
+        let children = fid.children();
+        let (blocks, offsets) = (children[0].1.size_in_bytes(), children[1].1.size_in_bytes());
+        let expected = format!(
+            "fid: {} bytes (100.0%)\n├── blocks: {} bytes ({:.1}%)\n└── popcount_offset: {} bytes ({:.1}%)\n",
+            total, blocks, percent(blocks), offsets, percent(offsets),
+        );
+        assert_eq!(expected, SpaceReport::new("fid", &fid).to_string());
+        assert_eq!(format!("fid: {} bytes (100.0%)\n", total), SpaceReport::new("fid", &fid).max_depth(0).to_string());
+
+        struct Outer {
+            fid: NaiveFID,
+            names: Vec<String>,
+        }
+        impl SpaceUsage for Outer {
+            fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+                vec![("fid", &self.fid), ("names", &self.names)]
+            }
+        }
+        let outer = Outer { fid, names: vec![] };
+        let lines: Vec<String> = SpaceReport::new("outer", &outer)
+            .to_string()
+            .lines()
+            .map(|line| line.split(':').next().unwrap().to_string())
+            .collect();
+        assert_eq!(vec!["outer", "├── fid", "│   ├── blocks", "│   └── popcount_offset", "└── names"], lines);
+    }
+}
@@ -1 +1,23 @@
+pub mod aho_corasick;
+pub mod alphabet;
+pub mod bwt;
+pub mod common;
+pub mod compress;
+pub mod distance;
+pub mod document;
+pub mod fm_index;
+pub mod inverted;
+pub mod kmer;
+pub mod lyndon;
+pub mod ngram;
+pub mod palindrome;
+pub mod rolling_hash;
+pub mod run_length_fm_index;
+pub mod search;
+#[cfg(feature = "serde")]
+mod serde_impl;
+pub mod stats;
+pub mod suffix_array;
+pub mod suffix_tree;
+pub mod tokenizer;
 pub mod trie;
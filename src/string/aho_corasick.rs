@@ -0,0 +1,252 @@
+use crate::space::SpaceUsage;
+
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// パターンの出現
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match {
+    /// パターンの番号
+    pub pattern: usize,
+    /// 出現の開始位置
+    pub start: usize,
+    /// 出現の終了位置 (出現の直後の位置)
+    pub end: usize,
+}
+
+/// 複数のパターンを同時に探す Aho-Corasick 法のオートマトン
+///
+/// パターンのトライ木に失敗遷移を加え、すべてのバイトの遷移を持つ決定性オートマトンにします。
+/// テキストの長さ `n` と出現の個数 `z` に対して `O(n + z)` ですべての出現を求めます。
+/// パターンには `patterns` での順に `0` から番号を付けます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::aho_corasick::AhoCorasick;
+/// let ac = AhoCorasick::new(&["he", "she", "his", "hers"]);
+/// let matches: Vec<(usize, usize)> = ac.find_iter(b"ushers").map(|m| (m.pattern, m.start)).collect();
+/// assert_eq!(vec![(1, 1), (0, 2), (3, 2)], matches);
+/// assert_eq!(b"u[she]rs".to_vec(), ac.replace_all(b"ushers", &["[he]", "[she]", "[his]", "[hers]"]));
+///
+/// // the matches across the chunks
+/// let mut scanner = ac.scanner();
+/// assert!(scanner.feed(b"us").is_empty());
+/// assert_eq!(3, scanner.feed(b"hers").len());
+/// ```
+pub struct AhoCorasick {
+    // the transitions of the complete automaton
+    next: Vec<[u32; 256]>,
+    // the patterns recognized at each state, longest first
+    outputs: Vec<Vec<usize>>,
+    lens: Vec<usize>,
+}
+
+impl AhoCorasick {
+    /// パターンの列 `patterns` のオートマトンを構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if any pattern is empty.
+    pub fn new<P: AsRef<[u8]>>(patterns: &[P]) -> Self {
+        const NONE: u32 = u32::MAX;
+        let mut next = vec![[NONE; 256]];
+        let mut outputs = vec![vec![]];
+        let mut lens = Vec::with_capacity(patterns.len());
+        for (id, pattern) in patterns.iter().enumerate() {
+            let pattern = pattern.as_ref();
+            assert!(!pattern.is_empty(), "patterns should be non-empty");
+            let mut state = 0;
+            for &b in pattern {
+                if next[state][b as usize] == NONE {
+                    next[state][b as usize] = next.len() as u32;
+                    next.push([NONE; 256]);
+                    outputs.push(vec![]);
+                }
+                state = next[state][b as usize] as usize;
+            }
+            outputs[state].push(id);
+            lens.push(pattern.len());
+        }
+
+        // fill the missing transitions with those of the failure in the breadth first order
+        let mut fail = vec![0; next.len()];
+        let mut queue = VecDeque::new();
+        for child in next[0].iter_mut() {
+            match *child {
+                NONE => *child = 0,
+                _ => queue.push_back(*child as usize),
+            }
+        }
+        while let Some(state) = queue.pop_front() {
+            let suffix = outputs[fail[state]].clone();
+            outputs[state].extend(suffix);
+            let fail_next = next[fail[state]];
+            for (child, &via_fail) in next[state].iter_mut().zip(&fail_next) {
+                if *child == NONE {
+                    *child = via_fail;
+                } else {
+                    fail[*child as usize] = via_fail as usize;
+                    queue.push_back(*child as usize);
+                }
+            }
+        }
+        AhoCorasick { next, outputs, lens }
+    }
+
+    /// パターンの個数を返します。
+    pub fn patterns_len(&self) -> usize {
+        self.lens.len()
+    }
+
+    /// `text` のすべてのパターンの出現を、重なりを含めて返します。
+    ///
+    /// 出現は終了位置の順に、終了位置が同じものは長い順に返します。
+    pub fn find_iter<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = Match> + 'a {
+        text.iter()
+            .scan(0, move |state, &b| {
+                *state = self.next[*state][b as usize] as usize;
+                Some(*state)
+            })
+            .enumerate()
+            .flat_map(move |(i, state)| self.matches(state, i + 1))
+    }
+
+    /// `text` の中のパターンの出現を、左から重ならないように `replacements` の同じ番号の列で置き換えます。
+    ///
+    /// 同じ位置から始まる出現は、最も長いものを置き換えます (leftmost-longest) 。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of `replacements` differs from that of the patterns.
+    pub fn replace_all<R: AsRef<[u8]>>(&self, text: &[u8], replacements: &[R]) -> Vec<u8> {
+        assert_eq!(self.patterns_len(), replacements.len(), "replacements should be as many as patterns");
+        let mut matches: Vec<Match> = self.find_iter(text).collect();
+        matches.sort_by_key(|m| (m.start, core::cmp::Reverse(m.end)));
+        let mut result = Vec::with_capacity(text.len());
+        let mut copied = 0;
+        for m in matches {
+            if m.start >= copied {
+                result.extend_from_slice(&text[copied..m.start]);
+                result.extend_from_slice(replacements[m.pattern].as_ref());
+                copied = m.end;
+            }
+        }
+        result.extend_from_slice(&text[copied..]);
+        result
+    }
+
+    /// テキストを分割して少しずつ与えられる [`Scanner`] を返します。
+    pub fn scanner(&self) -> Scanner<'_> {
+        Scanner { automaton: self, state: 0, offset: 0 }
+    }
+
+    fn matches(&self, state: usize, end: usize) -> impl Iterator<Item = Match> + '_ {
+        self.outputs[state].iter().map(move |&pattern| Match { pattern, start: end - self.lens[pattern], end })
+    }
+}
+
+/// テキストを分割して少しずつ与えながら、 [`AhoCorasick`] でパターンの出現を探す走査器
+///
+/// オートマトンの状態を保持するため、分割の境界をまたぐ出現も求めます。
+/// 出現の位置は、最初に与えたバイトを `0` とする位置です。
+pub struct Scanner<'a> {
+    automaton: &'a AhoCorasick,
+    state: usize,
+    offset: usize,
+}
+
+impl Scanner<'_> {
+    /// 続きのテキスト `chunk` を与え、 `chunk` の中で終わる出現を返します。
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Match> {
+        let mut matches = Vec::new();
+        for &b in chunk {
+            self.state = self.automaton.next[self.state][b as usize] as usize;
+            self.offset += 1;
+            matches.extend(self.automaton.matches(self.state, self.offset));
+        }
+        matches
+    }
+
+    /// これまでに与えたバイトの個数を返します。
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl SpaceUsage for AhoCorasick {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("next", &self.next), ("outputs", &self.outputs), ("lens", &self.lens)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn naive_matches(patterns: &[Vec<u8>], text: &[u8]) -> Vec<Match> {
+        let mut matches = Vec::new();
+        for end in 1..=text.len() {
+            let mut at: Vec<Match> = patterns.iter()
+                .enumerate()
+                .filter(|(_, p)| text[..end].ends_with(p))
+                .map(|(pattern, p)| Match { pattern, start: end - p.len(), end })
+                .collect();
+            at.sort_by_key(|m| (m.start, m.pattern));
+            matches.extend(at);
+        }
+        matches
+    }
+
+    fn random_bytes(rng: &mut StdRng, min: usize, max: usize) -> Vec<u8> {
+        (0..rng.gen_range(min, max)).map(|_| b"abc"[rng.gen_range(0, 3)]).collect()
+    }
+
+    #[test]
+    fn same_as_naive() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let mut patterns: Vec<Vec<u8>> = (0..rng.gen_range(1, 8)).map(|_| random_bytes(&mut rng, 1, 5)).collect();
+            patterns.sort();
+            patterns.dedup();
+            let text = random_bytes(&mut rng, 0, 50);
+            let ac = AhoCorasick::new(&patterns);
+            let expected = naive_matches(&patterns, &text);
+            let mut actual: Vec<Match> = ac.find_iter(&text).collect();
+            assert_eq!(expected.len(), actual.len());
+            actual.sort_by_key(|m| (m.end, m.start, m.pattern));
+            assert_eq!(expected, actual, "patterns = {:?}, text = {:?}", patterns, text);
+
+            let mut scanner = ac.scanner();
+            let mut streamed = Vec::new();
+            let mut rest = &text[..];
+            while !rest.is_empty() {
+                let (chunk, tail) = rest.split_at(rng.gen_range(0, rest.len() + 1));
+                streamed.extend(scanner.feed(chunk));
+                rest = tail;
+            }
+            assert_eq!(text.len(), scanner.offset());
+            assert_eq!(ac.find_iter(&text).collect::<Vec<_>>(), streamed);
+        }
+    }
+
+    #[test]
+    fn replace_all() {
+        let ac = AhoCorasick::new(&["a", "ab", "bc", "c"]);
+        // "ab" is longer than "a", and "bc" overlaps "ab"
+        assert_eq!(b"[AB][C]d".to_vec(), ac.replace_all(b"abcd", &["[A]", "[AB]", "[BC]", "[C]"]));
+        assert_eq!(b"xyz".to_vec(), ac.replace_all(b"xyz", &["", "", "", ""]));
+        let ac = AhoCorasick::new(&["password=secret"]);
+        assert_eq!(b"user password=*** end".to_vec(), ac.replace_all(b"user password=secret end", &["password=***"]));
+    }
+
+    #[test]
+    #[should_panic(expected = "patterns should be non-empty")]
+    fn empty_pattern() {
+        AhoCorasick::new(&["a", ""]);
+    }
+}
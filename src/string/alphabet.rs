@@ -0,0 +1,151 @@
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// テキストに現れるバイトの集合と、その密な符号
+///
+/// テキストを走査して各バイトの出現回数を数え、現れるバイトに小さい順に `0` から符号を付けます。
+/// 実効的なアルファベットの大きさ `σ` から、ウェーブレット行列の段数 `⌈log σ⌉` を決めるのに使います。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+/// use rust_study::string::alphabet::Alphabet;
+/// let text = b"GATTACA";
+/// let alphabet = Alphabet::new(text);
+/// assert_eq!(4, alphabet.size());
+/// assert_eq!(2, alphabet.bits());
+/// assert_eq!(Some(1), alphabet.code(b'C'));
+/// assert_eq!(None, alphabet.code(b'N'));
+/// assert_eq!(3, alphabet.frequency(b'A'));
+///
+/// let codes = alphabet.encode(text).unwrap();
+/// assert_eq!(vec![2, 0, 3, 3, 0, 1, 0], codes);
+/// let wmat = NaiveU8WaveletMatrix::with_levels(&codes, alphabet.bits());
+/// assert_eq!(b'T', alphabet.symbol(wmat.access(3)));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alphabet {
+    // the bytes in the text in ascending order
+    symbols: Vec<u8>,
+    codes: [Option<u8>; 256],
+    frequencies: [usize; 256],
+}
+
+impl Alphabet {
+    /// テキスト `text` を走査して、アルファベットを構築します。
+    pub fn new(text: &[u8]) -> Self {
+        let mut frequencies = [0; 256];
+        for &b in text {
+            frequencies[b as usize] += 1;
+        }
+        Self::from_frequencies(&frequencies)
+    }
+
+    /// 各バイトの出現回数の表 `frequencies` から、アルファベットを構築します。
+    pub fn from_frequencies(frequencies: &[usize; 256]) -> Self {
+        let frequencies = *frequencies;
+        let symbols: Vec<u8> = (0..=255).filter(|&b| frequencies[b as usize] > 0).collect();
+        let mut codes = [None; 256];
+        for (code, &b) in symbols.iter().enumerate() {
+            codes[b as usize] = Some(code as u8);
+        }
+        Alphabet { symbols, codes, frequencies }
+    }
+
+    /// テキストに現れるバイトの種類の数 (実効的なアルファベットの大きさ) を返します。
+    pub fn size(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// 符号を表すのに必要なビット数を返します。少なくとも `1` です。
+    pub fn bits(&self) -> usize {
+        (usize::BITS - self.size().saturating_sub(1).leading_zeros()).max(1) as usize
+    }
+
+    /// テキストに現れるバイトを、昇順に返します。
+    pub fn symbols(&self) -> &[u8] {
+        &self.symbols
+    }
+
+    /// バイト `b` の符号を返します。 `b` がテキストに現れない場合、 `None` を返します。
+    pub fn code(&self, b: u8) -> Option<u8> {
+        self.codes[b as usize]
+    }
+
+    /// 符号 `code` のバイトを返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `code` is not less than the size.
+    pub fn symbol(&self, code: u8) -> u8 {
+        assert!((code as usize) < self.size(), "code should be less than the size");
+        self.symbols[code as usize]
+    }
+
+    /// バイト `b` がテキストに現れる回数を返します。
+    pub fn frequency(&self, b: u8) -> usize {
+        self.frequencies[b as usize]
+    }
+
+    /// 各バイトがテキストに現れる回数の表を返します。
+    pub fn frequencies(&self) -> &[usize; 256] {
+        &self.frequencies
+    }
+
+    /// バイト列 `text` を符号の列に変換します。テキストに現れないバイトを含む場合、 `None` を返します。
+    pub fn encode(&self, text: &[u8]) -> Option<Vec<u8>> {
+        text.iter().map(|&b| self.code(b)).collect()
+    }
+
+    /// 符号の列 `codes` をバイト列に戻します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if any code is not less than the size.
+    pub fn decode(&self, codes: &[u8]) -> Vec<u8> {
+        codes.iter().map(|&code| self.symbol(code)).collect()
+    }
+}
+
+impl SpaceUsage for Alphabet {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("symbols", &self.symbols), ("codes", &self.codes), ("frequencies", &self.frequencies)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [1, 2, 3, 5, 200, 256] {
+            let text: Vec<u8> = (0..300).map(|_| rng.gen_range(0, max) as u8).collect();
+            let alphabet = Alphabet::new(&text);
+            assert!(alphabet.size() <= max);
+            assert!(alphabet.size() <= 1 << alphabet.bits());
+            assert!(alphabet.bits() == 1 || alphabet.size() > 1 << (alphabet.bits() - 1));
+            assert_eq!(text.len(), alphabet.frequencies().iter().sum::<usize>());
+            let codes = alphabet.encode(&text).unwrap();
+            assert!(codes.iter().all(|&c| (c as usize) < alphabet.size()));
+            assert_eq!(text, alphabet.decode(&codes));
+            assert_eq!(alphabet, Alphabet::from_frequencies(alphabet.frequencies()));
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let alphabet = Alphabet::new(b"");
+        assert_eq!(0, alphabet.size());
+        assert_eq!(1, alphabet.bits());
+        assert_eq!(None, alphabet.encode(b"a"));
+        assert_eq!(Some(vec![]), alphabet.encode(b""));
+    }
+}
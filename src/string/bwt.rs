@@ -0,0 +1,136 @@
+use super::suffix_array::suffix_array;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// バイト列 `text` の Burrows-Wheeler 変換を、接尾辞配列から `O(n)` で求めます。
+///
+/// `text` の末尾に、どのバイトよりも小さい番兵 `$` を加えた列の巡回シフトを辞書順に並べ、各行の末尾の文字を並べた列を変換とします。
+/// 番兵はバイトで表せないため、変換から番兵を取り除いた `text.len()` バイトの列と、
+/// 番兵の位置 (primary index) の組を返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::bwt::bwt;
+/// use rust_study::string::bwt::inverse_bwt;
+/// // $banana, a$banan, ana$ban, anana$b, banana$, na$bana, nana$ba
+/// let (transformed, primary) = bwt(b"banana");
+/// assert_eq!(b"annbaa", &transformed[..]);
+/// assert_eq!(4, primary);
+/// assert_eq!(b"banana", &inverse_bwt(&transformed, primary)[..]);
+/// ```
+pub fn bwt(text: &[u8]) -> (Vec<u8>, usize) {
+    bwt_with_suffix_array(text, &suffix_array(text))
+}
+
+// bwt() from the suffix array sa of text, for the callers that keep the suffix array
+pub(super) fn bwt_with_suffix_array(text: &[u8], sa: &[usize]) -> (Vec<u8>, usize) {
+    let n = text.len();
+    if n == 0 {
+        return (vec![], 0);
+    }
+    let mut transformed = Vec::with_capacity(n);
+    let mut primary = 0;
+    // the first row starts with the sentinel
+    transformed.push(text[n - 1]);
+    for (k, &i) in sa.iter().enumerate() {
+        if i == 0 {
+            primary = k + 1;
+        } else {
+            transformed.push(text[i - 1]);
+        }
+    }
+    (transformed, primary)
+}
+
+/// [`bwt()`] の変換 `transformed` と番兵の位置 `primary` から、元のバイト列を `O(n)` で復元します。
+///
+/// # Panics
+///
+/// Panics if `primary > transformed.len()`, or if `primary` is zero while `transformed` is not empty.
+pub fn inverse_bwt(transformed: &[u8], primary: usize) -> Vec<u8> {
+    let n = transformed.len();
+    assert!(primary <= n, "primary should be at most the length");
+    assert!(primary > 0 || n == 0, "the first row should not end with the sentinel");
+    // the last column of the rows, including the sentinel
+    let last = |row: usize| if row < primary { transformed[row] } else { transformed[row - 1] };
+
+    // the first row of each byte in the first column, following the row of the sentinel
+    let mut first = [0; 257];
+    for &b in transformed {
+        first[b as usize + 1] += 1;
+    }
+    first[0] = 1;
+    for c in 1..257 {
+        first[c] += first[c - 1];
+    }
+    // the number of the same bytes in the last column before each row
+    let mut occ = [0; 256];
+    let mut rank = Vec::with_capacity(n + 1);
+    for row in 0..=n {
+        if row == primary {
+            rank.push(0);
+        } else {
+            let b = last(row) as usize;
+            rank.push(occ[b]);
+            occ[b] += 1;
+        }
+    }
+
+    // follow the LF mapping from the row starting with the sentinel
+    let mut text = vec![0; n];
+    let mut row = 0;
+    for t in (0..n).rev() {
+        let b = last(row);
+        text[t] = b;
+        row = first[b as usize] + rank[row];
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn naive_bwt(text: &[u8]) -> (Vec<u8>, usize) {
+        // the sentinel as -1
+        let s: Vec<i32> = text.iter().map(|&b| b as i32).chain(std::iter::once(-1)).collect();
+        let mut rotations: Vec<Vec<i32>> = (0..s.len()).map(|i| [&s[i..], &s[..i]].concat()).collect();
+        rotations.sort();
+        let last: Vec<i32> = rotations.iter().map(|rotation| *rotation.last().unwrap()).collect();
+        let primary = last.iter().position(|&c| c == -1).unwrap();
+        (last.into_iter().filter(|&c| c >= 0).map(|c| c as u8).collect(), primary)
+    }
+
+    #[test]
+    fn small() {
+        for text in [&b""[..], b"a", b"aaa", b"ab", b"ba", b"abracadabra", b"\x00\x00\xff"] {
+            let (transformed, primary) = bwt(text);
+            assert_eq!(naive_bwt(text), (transformed.clone(), primary), "text = {:?}", text);
+            assert_eq!(text, &inverse_bwt(&transformed, primary)[..]);
+        }
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [2, 256] {
+            for len in [1, 10, 300] {
+                let text: Vec<u8> = (0..len).map(|_| rng.gen_range(0, max) as u8).collect();
+                let (transformed, primary) = bwt(&text);
+                assert_eq!(naive_bwt(&text), (transformed.clone(), primary));
+                assert_eq!(text, inverse_bwt(&transformed, primary));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "primary should be at most the length")]
+    fn invalid_primary() {
+        inverse_bwt(b"ab", 3);
+    }
+}
@@ -0,0 +1,122 @@
+use super::suffix_array::kasai;
+use super::suffix_array::sa_is;
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// バイト列 `a` と `b` の最長共通部分列 (連続した部分列) の、それぞれでの範囲を返します。
+///
+/// `a` と `b` を区切り記号で連結した列の接尾辞配列と LCP 配列を構築し、
+/// 辞書順で隣り合う `a` の接尾辞と `b` の接尾辞の最長共通接頭辞のうち、最長のものを `O(n + m)` で求めます。
+/// 最長のものが複数ある場合、辞書順で最小のものを返します。
+/// 共通するバイトが存在しない場合、 `None` を返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::common::longest_common_substring;
+/// let (a, b) = (b"xabcdey", b"zzbcdeabc");
+/// let (in_a, in_b) = longest_common_substring(a, b).unwrap();
+/// assert_eq!(b"bcde", &a[in_a]);
+/// assert_eq!(2..6, in_b);
+/// assert_eq!(None, longest_common_substring(b"abc", b"xyz"));
+/// ```
+pub fn longest_common_substring(a: &[u8], b: &[u8]) -> Option<(Range<usize>, Range<usize>)> {
+    // the separator is larger than any byte
+    let s: Vec<usize> = a.iter()
+        .map(|&x| x as usize)
+        .chain(core::iter::once(256))
+        .chain(b.iter().map(|&x| x as usize))
+        .collect();
+    let sa = sa_is(&s, 256);
+    // no common prefix goes over the unique separator
+    let lcp = kasai(&s, &sa);
+    let in_a = |i: usize| i < a.len();
+    let k = (1..s.len())
+        .filter(|&k| in_a(sa[k - 1]) != in_a(sa[k]))
+        .max_by_key(|&k| (lcp[k], core::cmp::Reverse(k)))?;
+    let len = lcp[k];
+    if len == 0 {
+        return None;
+    }
+    let (i, j) = if in_a(sa[k - 1]) { (sa[k - 1], sa[k]) } else { (sa[k], sa[k - 1]) };
+    let j = j - a.len() - 1;
+    Some((i..i + len, j..j + len))
+}
+
+/// 文字列の列 `strs` の最長共通接頭辞を返します。
+///
+/// 接頭辞は文字の境界で区切ります。 `strs` が空の場合、空文字列を返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::common::longest_common_prefix;
+/// assert_eq!("inter", longest_common_prefix(["interview", "internet", "interval"]));
+/// // "あ" and "い" share the first two bytes in UTF-8
+/// assert_eq!("", longest_common_prefix(["あ", "い"]));
+/// assert_eq!("", longest_common_prefix(Vec::<String>::new()));
+/// ```
+pub fn longest_common_prefix<I: IntoIterator<Item = S>, S: AsRef<str>>(strs: I) -> String {
+    let mut strs = strs.into_iter();
+    let mut prefix = match strs.next() {
+        Some(first) => first.as_ref().to_string(),
+        None => return String::new(),
+    };
+    for s in strs {
+        let len = prefix.char_indices()
+            .zip(s.as_ref().chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(prefix.len().min(s.as_ref().len()), |((i, _), _)| i);
+        prefix.truncate(len);
+        if prefix.is_empty() {
+            break;
+        }
+    }
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn naive_longest_common_substring(a: &[u8], b: &[u8]) -> usize {
+        (0..a.len())
+            .flat_map(|i| (0..b.len()).map(move |j| (i, j)))
+            .map(|(i, j)| a[i..].iter().zip(&b[j..]).take_while(|(x, y)| x == y).count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn random_longest_common_substring() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [2, 4, 256] {
+            for _ in 0..300 {
+                let a: Vec<u8> = (0..rng.gen_range(0, 30)).map(|_| rng.gen_range(0, max) as u8).collect();
+                let b: Vec<u8> = (0..rng.gen_range(0, 30)).map(|_| rng.gen_range(0, max) as u8).collect();
+                let expected = naive_longest_common_substring(&a, &b);
+                match longest_common_substring(&a, &b) {
+                    None => assert_eq!(0, expected, "a = {:?}, b = {:?}", a, b),
+                    Some((in_a, in_b)) => {
+                        assert_eq!(expected, in_a.len(), "a = {:?}, b = {:?}", a, b);
+                        assert_eq!(a[in_a], b[in_b]);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn longest_common_prefix_of_strs() {
+        assert_eq!("abc", longest_common_prefix(["abc"]));
+        assert_eq!("ab", longest_common_prefix(["abc", "ab", "abd"]));
+        assert_eq!("", longest_common_prefix(["abc", ""]));
+        assert_eq!("かき", longest_common_prefix(vec!["かきく".to_string(), "かきけ".to_string()]));
+    }
+}
@@ -0,0 +1,404 @@
+use super::bwt::bwt;
+use super::bwt::inverse_bwt;
+use crate::collections::heap::Heap;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// ゼロの連続の長さの、全単射 2 進表記の桁 `1` を表す記号
+const RUN_A: u16 = 0;
+/// ゼロの連続の長さの、全単射 2 進表記の桁 `2` を表す記号
+const RUN_B: u16 = 1;
+/// 記号の種類の数。 `RUN_A`, `RUN_B` と、 `1` から `255` のバイトを 1 ずらしたもの
+const ALPHABET: usize = 257;
+/// 符号長の上限。これ以下の符号長なら、正準 Huffman 符号は `u64` に収まります。
+/// Huffman 符号長がこれを超えるには、記号の個数がフィボナッチ数 `F(65)` (約 `1.7 * 10^13`) 以上必要です。
+const MAX_CODE_LENGTH: u8 = 63;
+
+/// バイト列 `data` を move-to-front 変換します。
+///
+/// 各バイトを、直近に使った順に並べたバイトの表での位置に置き換え、そのバイトを表の先頭に移します。
+/// 同じバイトが近くに繰り返し現れる列は、小さい値の多い列になります。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::compress::inverse_move_to_front;
+/// use rust_study::string::compress::move_to_front;
+/// assert_eq!(vec![98, 98, 0, 1, 0, 0], move_to_front(b"baabbb"));
+/// assert_eq!(b"baabbb".to_vec(), inverse_move_to_front(&[98, 98, 0, 1, 0, 0]));
+/// ```
+pub fn move_to_front(data: &[u8]) -> Vec<u8> {
+    let mut table: Vec<u8> = (0..=255).collect();
+    data.iter()
+        .map(|&b| {
+            let i = table.iter().position(|&c| c == b).unwrap();
+            table[..=i].rotate_right(1);
+            i as u8
+        })
+        .collect()
+}
+
+/// [`move_to_front()`] の変換を元に戻します。
+pub fn inverse_move_to_front(data: &[u8]) -> Vec<u8> {
+    let mut table: Vec<u8> = (0..=255).collect();
+    data.iter()
+        .map(|&i| {
+            let i = i as usize;
+            table[..=i].rotate_right(1);
+            table[0]
+        })
+        .collect()
+}
+
+/// バイト列 `data` のゼロの連続を、その長さの全単射 2 進表記に置き換えます (bzip2 の RUNA/RUNB) 。
+///
+/// ゼロの連続の長さを、下の桁から `1` を `0` 、 `2` を `1` の記号で表します。
+/// `1` 以上のバイト `b` は `b + 1` の記号にします。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::compress::run_length_decode;
+/// use rust_study::string::compress::run_length_encode;
+/// // 5 = 1 + 2 * 2, and 3 = 1 + 2 * 1
+/// assert_eq!(vec![0, 1, 8, 0, 0], run_length_encode(&[0, 0, 0, 0, 0, 7, 0, 0, 0]));
+/// assert_eq!(Some(vec![0, 0, 0, 0, 0, 7, 0, 0, 0]), run_length_decode(&[0, 1, 8, 0, 0]));
+/// ```
+pub fn run_length_encode(data: &[u8]) -> Vec<u16> {
+    let mut symbols = Vec::new();
+    let mut run = 0;
+    for &b in data.iter().chain(core::iter::once(&1)) {
+        if b == 0 {
+            run += 1;
+            continue;
+        }
+        while run > 0 {
+            if run % 2 == 1 {
+                symbols.push(RUN_A);
+                run = (run - 1) / 2;
+            } else {
+                symbols.push(RUN_B);
+                run = (run - 2) / 2;
+            }
+        }
+        symbols.push(b as u16 + 1);
+    }
+    // the sentinel 1 that flushes the last run
+    symbols.pop();
+    symbols
+}
+
+/// [`run_length_encode()`] の変換を元に戻します。
+///
+/// 記号の種類の数を超える記号を含む場合や、ゼロの連続の長さが `usize` に収まらない場合、 `None` を返します。
+pub fn run_length_decode(symbols: &[u16]) -> Option<Vec<u8>> {
+    decode_runs(symbols, usize::MAX)
+}
+
+// decodes the runs, or returns None if the decoded bytes exceed max_len before the last byte
+fn decode_runs(symbols: &[u16], max_len: usize) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut run: usize = 0;
+    let mut weight: usize = 1;
+    for &s in symbols.iter().chain(core::iter::once(&2)) {
+        match s {
+            RUN_A | RUN_B => {
+                run = run.checked_add(weight.checked_mul(s as usize + 1)?)?;
+                weight = weight.saturating_mul(2);
+            },
+            _ if (s as usize) < ALPHABET => {
+                let len = data.len().checked_add(run).filter(|&len| len <= max_len)?;
+                data.resize(len, 0);
+                data.push((s - 1) as u8);
+                run = 0;
+                weight = 1;
+            },
+            _ => return None,
+        }
+    }
+    data.pop();
+    Some(data)
+}
+
+/// BWT 、 move-to-front 、ゼロの連続の符号化、 Huffman 符号を順に適用して、 bzip2 のようにバイト列 `data` を圧縮します。
+///
+/// 全体を 1 つのブロックとして扱います。
+/// 出力は、元の長さ、 BWT の番兵の位置、記号の個数の LEB128 と、各記号の符号長、符号のビット列の順に並べたものです。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::compress::compress;
+/// use rust_study::string::compress::decompress;
+/// let data = b"abracadabra ".repeat(100);
+/// let compressed = compress(&data);
+/// assert!(compressed.len() < data.len() / 3);
+/// assert_eq!(Some(data), decompress(&compressed));
+/// ```
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let (transformed, primary) = bwt(data);
+    let symbols = run_length_encode(&move_to_front(&transformed));
+
+    let mut frequencies = [0; ALPHABET];
+    for &s in &symbols {
+        frequencies[s as usize] += 1;
+    }
+    let lengths = code_lengths(&frequencies);
+    let codes = canonical_codes(&lengths);
+
+    let mut out = Vec::new();
+    write_varint(&mut out, data.len());
+    write_varint(&mut out, primary);
+    write_varint(&mut out, symbols.len());
+    out.extend_from_slice(&lengths);
+    let mut writer = BitWriter { out, buffer: 0, bits: 0 };
+    for &s in &symbols {
+        writer.write(codes[s as usize], lengths[s as usize]);
+    }
+    writer.finish()
+}
+
+/// [`compress()`] で圧縮したバイト列 `data` を復元します。
+///
+/// `data` が壊れている場合、 `None` を返します。
+pub fn decompress(data: &[u8]) -> Option<Vec<u8>> {
+    let mut pos = 0;
+    let n = read_varint(data, &mut pos)?;
+    let primary = read_varint(data, &mut pos)?;
+    let m = read_varint(data, &mut pos)?;
+    let lengths: [u8; ALPHABET] = data.get(pos..pos + ALPHABET)?.try_into().ok()?;
+    pos += ALPHABET;
+    if !is_prefix_code(&lengths) {
+        return None;
+    }
+
+    // the symbols sorted by the code, and the first code and the number of the symbols of each length
+    let mut sorted: Vec<usize> = (0..ALPHABET).filter(|&s| lengths[s] > 0).collect();
+    sorted.sort_by_key(|&s| (lengths[s], s));
+    let codes = canonical_codes(&lengths);
+    let max_len = lengths.iter().copied().max().unwrap_or(0) as usize;
+    let mut first = vec![0; max_len + 1];
+    let mut count = vec![0; max_len + 1];
+    let mut offset = vec![0; max_len + 1];
+    for (k, &s) in sorted.iter().enumerate() {
+        let len = lengths[s] as usize;
+        if count[len] == 0 {
+            first[len] = codes[s];
+            offset[len] = k;
+        }
+        count[len] += 1;
+    }
+
+    let mut reader = BitReader { data, pos: pos * 8 };
+    let mut symbols = Vec::new();
+    while symbols.len() < m {
+        let mut code = 0;
+        let mut len = 0;
+        loop {
+            code = (code << 1) | reader.read()? as u64;
+            len += 1;
+            if len > max_len {
+                return None;
+            }
+            if count[len] > 0 && code >= first[len] && code - first[len] < count[len] as u64 {
+                symbols.push(sorted[offset[len] + (code - first[len]) as usize] as u16);
+                break;
+            }
+        }
+    }
+
+    let transformed = inverse_move_to_front(&decode_runs(&symbols, n)?);
+    if transformed.len() != n || primary > n || (primary == 0 && n > 0) {
+        return None;
+    }
+    Some(inverse_bwt(&transformed, primary))
+}
+
+/// 各記号の出現回数 `frequencies` から、 Huffman 符号の符号長を求めます。
+///
+/// 出現しない記号の符号長は `0` です。出現する記号が 1 種類の場合、その符号長は `1` です。
+fn code_lengths(frequencies: &[usize; ALPHABET]) -> [u8; ALPHABET] {
+    // the weight and the node of the trees, where the leaves are the symbols
+    let mut heap = Heap::with_compare(|lhs: &(usize, usize), rhs| lhs.cmp(rhs));
+    let mut parent = vec![usize::MAX; ALPHABET];
+    for (s, &f) in frequencies.iter().enumerate() {
+        if f > 0 {
+            heap.push((f, s));
+        }
+    }
+    while heap.len() > 1 {
+        let (w1, a) = heap.pop().unwrap();
+        let (w2, b) = heap.pop().unwrap();
+        let node = parent.len();
+        parent.push(usize::MAX);
+        parent[a] = node;
+        parent[b] = node;
+        heap.push((w1 + w2, node));
+    }
+
+    let mut lengths = [0; ALPHABET];
+    for s in 0..ALPHABET {
+        if frequencies[s] > 0 {
+            let mut node = s;
+            let mut len = 0;
+            while parent[node] != usize::MAX {
+                node = parent[node];
+                len += 1;
+            }
+            lengths[s] = len.max(1);
+        }
+    }
+    lengths
+}
+
+/// 符号長 `lengths` がすべて [`MAX_CODE_LENGTH`] 以下で、 Kraft の不等式 `Σ 2^-len <= 1` を満たす場合に、 `true` を返します。
+///
+/// このとき、 [`canonical_codes()`] で割り当てた符号は語頭符号になります。
+fn is_prefix_code(lengths: &[u8; ALPHABET]) -> bool {
+    if lengths.iter().any(|&len| len > MAX_CODE_LENGTH) {
+        return false;
+    }
+    let kraft: u128 = lengths.iter().filter(|&&len| len > 0).map(|&len| 1_u128 << (MAX_CODE_LENGTH - len)).sum();
+    kraft <= 1 << MAX_CODE_LENGTH
+}
+
+/// 符号長 `lengths` から、符号長と記号の順に符号を割り当てた正準 Huffman 符号を求めます。
+fn canonical_codes(lengths: &[u8; ALPHABET]) -> [u64; ALPHABET] {
+    let mut sorted: Vec<usize> = (0..ALPHABET).filter(|&s| lengths[s] > 0).collect();
+    sorted.sort_by_key(|&s| (lengths[s], s));
+    let mut codes = [0; ALPHABET];
+    let mut code = 0;
+    let mut prev = 0;
+    for s in sorted {
+        code <<= lengths[s] - prev;
+        codes[s] = code;
+        code += 1;
+        prev = lengths[s];
+    }
+    codes
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u8,
+    bits: u8,
+}
+
+impl BitWriter {
+    /// `value` の下位 `len` ビットを上位から書き込みます。
+    fn write(&mut self, value: u64, len: u8) {
+        for i in (0..len).rev() {
+            self.buffer = (self.buffer << 1) | ((value >> i) & 1) as u8;
+            self.bits += 1;
+            if self.bits == 8 {
+                self.out.push(self.buffer);
+                self.buffer = 0;
+                self.bits = 0;
+            }
+        }
+    }
+
+    /// 端数のビットを `0` で埋めて書き込み、出力を返します。
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.out.push(self.buffer << (8 - self.bits));
+        }
+        self.out
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    // the position in bits
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self) -> Option<bool> {
+        let byte = *self.data.get(self.pos / 8)?;
+        let bit = (byte >> (7 - self.pos % 8)) & 1 == 1;
+        self.pos += 1;
+        Some(bit)
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Option<usize> {
+    let mut value = 0;
+    for shift in (0..usize::BITS).step_by(7) {
+        let b = *data.get(*pos)?;
+        *pos += 1;
+        value |= ((b & 0x7f) as usize).checked_shl(shift)?;
+        if b & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [1, 2, 4, 256] {
+            for _ in 0..50 {
+                let data: Vec<u8> = (0..rng.gen_range(0, 300)).map(|_| rng.gen_range(0, max) as u8).collect();
+                assert_eq!(data, inverse_move_to_front(&move_to_front(&data)));
+                assert_eq!(Some(data.clone()), run_length_decode(&run_length_encode(&data)));
+                assert_eq!(Some(data.clone()), decompress(&compress(&data)), "data = {:?}", data);
+            }
+        }
+    }
+
+    #[test]
+    fn repetitive() {
+        let data = b"to be or not to be, that is the question. ".repeat(1000);
+        let compressed = compress(&data);
+        assert!(compressed.len() * 20 < data.len(), "compressed = {}", compressed.len());
+        assert_eq!(Some(data), decompress(&compressed));
+    }
+
+    #[test]
+    fn corrupted() {
+        let compressed = compress(b"banana bandana");
+        assert_eq!(None, decompress(&[]));
+        assert_eq!(None, decompress(&compressed[..compressed.len() - 1]));
+        let mut wrong_length = compressed.clone();
+        wrong_length[0] += 1;
+        assert_eq!(None, decompress(&wrong_length));
+        assert_eq!(None, run_length_decode(&[257]));
+        assert_eq!(None, run_length_decode(&[RUN_B; 65]));
+
+        // the code lengths follow the three varints of the lengths, each of one byte here
+        let mut too_long = compressed.clone();
+        too_long[3 + RUN_A as usize] = 64;
+        assert_eq!(None, decompress(&too_long));
+        // a code length of 1 for every symbol, which is not a prefix code
+        let mut oversubscribed = compressed.clone();
+        oversubscribed[3..3 + ALPHABET].fill(1);
+        assert_eq!(None, decompress(&oversubscribed));
+
+        // every single bit flip is either rejected or decoded without panicking
+        for i in 0..compressed.len() {
+            for bit in 0..8 {
+                let mut broken = compressed.clone();
+                broken[i] ^= 1 << bit;
+                decompress(&broken);
+            }
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use crate::collections::hash::HashMap;
+
+use alloc::vec::Vec;
+use alloc::vec;
+use core::hash::Hash;
+
+/// 編集距離の各操作のコスト
+///
+/// 既定値はすべて `1` で、 Levenshtein 距離になります。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EditCosts {
+    /// 1 文字を挿入するコスト
+    pub insert: usize,
+    /// 1 文字を削除するコスト
+    pub delete: usize,
+    /// 1 文字を置換するコスト
+    pub substitute: usize,
+}
+
+impl Default for EditCosts {
+    fn default() -> Self {
+        EditCosts { insert: 1, delete: 1, substitute: 1 }
+    }
+}
+
+/// `a` を `b` に変換する編集操作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditOp {
+    /// `a` の文字をそのまま残します。
+    Match,
+    /// `a` の文字を `b` の文字に置換します。
+    Substitute,
+    /// `b` の文字を挿入します。
+    Insert,
+    /// `a` の文字を削除します。
+    Delete,
+}
+
+/// `a` と `b` の Levenshtein 距離を、 `O(nm)` の動的計画法で求めます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::distance::levenshtein;
+/// assert_eq!(3, levenshtein(b"kitten", b"sitting"));
+/// let (a, b): (Vec<char>, Vec<char>) = ("かたつむり".chars().collect(), "かたむき".chars().collect());
+/// assert_eq!(2, levenshtein(&a, &b));
+/// ```
+pub fn levenshtein<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    weighted_levenshtein(a, b, EditCosts::default())
+}
+
+/// `a` を `b` に変換する編集操作のコストの和の最小値を、 `O(nm)` の動的計画法で求めます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::distance::EditCosts;
+/// use rust_study::string::distance::weighted_levenshtein;
+/// // a substitution costs more than a deletion and an insertion
+/// let costs = EditCosts { insert: 1, delete: 1, substitute: 3 };
+/// assert_eq!(2, weighted_levenshtein(b"abc", b"axc", costs));
+/// ```
+pub fn weighted_levenshtein<T: PartialEq>(a: &[T], b: &[T], costs: EditCosts) -> usize {
+    // the costs to convert a[..i] into b[..j] for the current i
+    let mut row: Vec<usize> = (0..=b.len()).map(|j| j * costs.insert).collect();
+    for (i, x) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = (i + 1) * costs.delete;
+        for (j, y) in b.iter().enumerate() {
+            let substitute = diagonal + if x == y { 0 } else { costs.substitute };
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + costs.insert).min(diagonal + costs.delete);
+        }
+    }
+    row[b.len()]
+}
+
+/// `a` を `b` に変換する編集操作のコストの和の最小値と、その編集操作の列を求めます。
+///
+/// `O(nm)` の表を保持するため、長い列には向きません。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::distance::EditCosts;
+/// use rust_study::string::distance::EditOp;
+/// use rust_study::string::distance::alignment;
+/// let (distance, ops) = alignment(b"abc", b"bd", EditCosts::default());
+/// assert_eq!(2, distance);
+/// assert_eq!(vec![EditOp::Delete, EditOp::Match, EditOp::Substitute], ops);
+/// ```
+pub fn alignment<T: PartialEq>(a: &[T], b: &[T], costs: EditCosts) -> (usize, Vec<EditOp>) {
+    let (n, m) = (a.len(), b.len());
+    let mut dp = vec![vec![0; m + 1]; n + 1];
+    for i in 0..=n {
+        for j in 0..=m {
+            dp[i][j] = if i == 0 {
+                j * costs.insert
+            } else if j == 0 {
+                i * costs.delete
+            } else {
+                let substitute = dp[i - 1][j - 1] + if a[i - 1] == b[j - 1] { 0 } else { costs.substitute };
+                substitute.min(dp[i][j - 1] + costs.insert).min(dp[i - 1][j] + costs.delete)
+            };
+        }
+    }
+    // trace back from the end
+    let mut ops = vec![];
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 {
+            let same = a[i - 1] == b[j - 1];
+            if dp[i][j] == dp[i - 1][j - 1] + if same { 0 } else { costs.substitute } {
+                ops.push(if same { EditOp::Match } else { EditOp::Substitute });
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+        }
+        if i > 0 && dp[i][j] == dp[i - 1][j] + costs.delete {
+            ops.push(EditOp::Delete);
+            i -= 1;
+        } else {
+            ops.push(EditOp::Insert);
+            j -= 1;
+        }
+    }
+    ops.reverse();
+    (dp[n][m], ops)
+}
+
+/// `a` と `b` の Levenshtein 距離が `max` 以下の場合に、その距離を返します。
+///
+/// 対角線から `max` 以内の帯だけを `O(n * max)` で計算し、距離が `max` を超えることが分かった時点で打ち切ります。
+/// 多くの候補から距離の近いものを探す場合に向きます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::distance::levenshtein_within;
+/// assert_eq!(Some(3), levenshtein_within(b"kitten", b"sitting", 3));
+/// assert_eq!(None, levenshtein_within(b"kitten", b"sitting", 2));
+/// ```
+pub fn levenshtein_within<T: PartialEq>(a: &[T], b: &[T], max: usize) -> Option<usize> {
+    let (n, m) = (a.len(), b.len());
+    if n.abs_diff(m) > max {
+        return None;
+    }
+    // beyond the band
+    let inf = max + 1;
+    let mut row: Vec<usize> = (0..=m).map(|j| if j <= max { j } else { inf }).collect();
+    for i in 1..=n {
+        let lo = i.saturating_sub(max);
+        let hi = (i + max).min(m);
+        let mut diagonal = if lo == 0 { row[0] } else { row[lo - 1] };
+        if lo == 0 {
+            row[0] = if i <= max { i } else { inf };
+        } else {
+            row[lo - 1] = inf;
+        }
+        let mut row_min = if lo == 0 { row[0] } else { inf };
+        for j in lo.max(1)..=hi {
+            let substitute = diagonal + if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            diagonal = row[j];
+            row[j] = substitute.min(row[j - 1] + 1).min(diagonal + 1).min(inf);
+            row_min = row_min.min(row[j]);
+        }
+        if row_min > max {
+            return None;
+        }
+    }
+    Some(row[m]).filter(|&d| d <= max)
+}
+
+/// `a` と `b` の Damerau-Levenshtein 距離を、 `O(nm)` の動的計画法で求めます。
+///
+/// 挿入・削除・置換に加えて、隣接する 2 文字の入れ替えを 1 回の操作とします。
+/// 入れ替えた文字の間にさらに編集を加えることも許す、制限のない距離です。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::distance::damerau_levenshtein;
+/// assert_eq!(1, damerau_levenshtein(b"abcd", b"acbd"));
+/// // "ca" -> "ac" -> "abc"
+/// assert_eq!(2, damerau_levenshtein(b"ca", b"abc"));
+/// ```
+pub fn damerau_levenshtein<T: Eq + Hash>(a: &[T], b: &[T]) -> usize {
+    let (n, m) = (a.len(), b.len());
+    let inf = n + m;
+    // shifted by one, with the row and the column of inf
+    let mut d = vec![vec![inf; m + 2]; n + 2];
+    for i in 0..=n {
+        d[i + 1][1] = i;
+    }
+    for j in 0..=m {
+        d[1][j + 1] = j;
+    }
+    // the last row of each symbol in a
+    let mut last_row: HashMap<&T, usize> = HashMap::default();
+    for i in 1..=n {
+        // the last column matching a[i - 1] in this row
+        let mut last_col = 0;
+        for j in 1..=m {
+            let k = last_row.get(&b[j - 1]).copied().unwrap_or(0);
+            let l = last_col;
+            let cost = if a[i - 1] == b[j - 1] {
+                last_col = j;
+                0
+            } else {
+                1
+            };
+            d[i + 1][j + 1] = (d[i][j] + cost)
+                .min(d[i + 1][j] + 1)
+                .min(d[i][j + 1] + 1)
+                .min(d[k][l] + (i - k - 1) + 1 + (j - l - 1));
+        }
+        last_row.insert(&a[i - 1], i);
+    }
+    d[n + 1][m + 1]
+}
+
+/// `a` と `b` の Levenshtein 距離を、 Myers のビット並列アルゴリズムで `O(m)` で求めます。
+///
+/// 動的計画法の表の列を `a` の長さのビット列で表し、 `b` の 1 文字ごとに 1 列を定数回のビット演算で更新します。
+///
+/// # Panics
+///
+/// Panics if `a` is longer than 64.
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::distance::myers;
+/// assert_eq!(3, myers(b"kitten", b"sitting"));
+/// ```
+pub fn myers<T: Eq + Hash>(a: &[T], b: &[T]) -> usize {
+    let n = a.len();
+    assert!(n <= 64, "a should be at most 64 long");
+    if n == 0 {
+        return b.len();
+    }
+    // the positions of each symbol in a
+    let mut peq: HashMap<&T, u64> = HashMap::default();
+    for (i, x) in a.iter().enumerate() {
+        *peq.entry(x).or_insert(0) |= 1 << i;
+    }
+    let high = 1 << (n - 1);
+    // the vertical deltas of the column, +1 and -1
+    let mut pv: u64 = !0;
+    let mut mv: u64 = 0;
+    let mut score = n;
+    for y in b {
+        let eq = peq.get(y).copied().unwrap_or(0);
+        let xv = eq | mv;
+        let xh = ((eq & pv).wrapping_add(pv) ^ pv) | eq;
+        let mut ph = mv | !(xh | pv);
+        let mut mh = pv & xh;
+        if ph & high != 0 {
+            score += 1;
+        } else if mh & high != 0 {
+            score -= 1;
+        }
+        // the first row grows by one in each column
+        ph = (ph << 1) | 1;
+        mh <<= 1;
+        pv = mh | !(xv | ph);
+        mv = ph & xv;
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn random_bytes(rng: &mut StdRng, max_len: usize) -> Vec<u8> {
+        (0..rng.gen_range(0, max_len + 1)).map(|_| b"abc"[rng.gen_range(0, 3)]).collect()
+    }
+
+    fn apply(a: &[u8], b: &[u8], ops: &[EditOp]) -> Vec<u8> {
+        let (mut i, mut j) = (0, 0);
+        let mut result = vec![];
+        for op in ops {
+            match op {
+                EditOp::Match => {
+                    assert_eq!(a[i], b[j]);
+                    result.push(a[i]);
+                    i += 1;
+                    j += 1;
+                }
+                EditOp::Substitute => {
+                    assert_ne!(a[i], b[j]);
+                    result.push(b[j]);
+                    i += 1;
+                    j += 1;
+                }
+                EditOp::Insert => {
+                    result.push(b[j]);
+                    j += 1;
+                }
+                EditOp::Delete => i += 1,
+            }
+        }
+        assert_eq!(a.len(), i);
+        result
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..1000 {
+            let a = random_bytes(&mut rng, 10);
+            let b = random_bytes(&mut rng, 10);
+            let distance = levenshtein(&a, &b);
+            assert_eq!(distance, levenshtein(&b, &a));
+            assert_eq!(distance, myers(&a, &b), "a = {:?}, b = {:?}", a, b);
+            for max in 0..5 {
+                assert_eq!(Some(distance).filter(|&d| d <= max), levenshtein_within(&a, &b, max), "a = {:?}, b = {:?}", a, b);
+            }
+            let damerau = damerau_levenshtein(&a, &b);
+            assert!(damerau <= distance);
+            assert_eq!(damerau, damerau_levenshtein(&b, &a));
+
+            let costs = EditCosts { insert: rng.gen_range(1, 4), delete: rng.gen_range(1, 4), substitute: rng.gen_range(1, 6) };
+            let (cost, ops) = alignment(&a, &b, costs);
+            assert_eq!(weighted_levenshtein(&a, &b, costs), cost);
+            assert_eq!(b, apply(&a, &b, &ops));
+            let sum: usize = ops.iter().map(|op| match op {
+                EditOp::Match => 0,
+                EditOp::Substitute => costs.substitute,
+                EditOp::Insert => costs.insert,
+                EditOp::Delete => costs.delete,
+            }).sum();
+            assert_eq!(cost, sum);
+        }
+    }
+
+    #[test]
+    fn long_pattern() {
+        let a = vec![b'a'; 64];
+        let b = vec![b'b'; 100];
+        assert_eq!(100, myers(&a, &b));
+        assert_eq!(levenshtein(&b"abc"[..], &a), myers(&a, b"abc"));
+    }
+
+    #[test]
+    fn damerau() {
+        assert_eq!(0, damerau_levenshtein(b"", b""));
+        assert_eq!(3, damerau_levenshtein(b"abc", b""));
+        assert_eq!(1, damerau_levenshtein(b"ab", b"ba"));
+        assert_eq!(3, damerau_levenshtein(b"abcdef", b"badcfe"));
+        assert_eq!(3, levenshtein(b"ca", b"abc"));
+    }
+
+    #[test]
+    #[should_panic(expected = "a should be at most 64 long")]
+    fn too_long() {
+        myers(&[0; 65], &[0]);
+    }
+}
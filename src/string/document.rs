@@ -0,0 +1,174 @@
+use super::suffix_array::sa_is;
+use crate::bits::fid::FID;
+use crate::bits::fid::NaiveFID;
+use crate::bits::wavelet_matrix::U8WaveletMatrix;
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// 文書の集合の、パターンを含む文書の列挙 (document listing) の索引
+///
+/// 文書を区切り記号で連結した列 (一般化接尾辞配列) の接尾辞配列と、各接尾辞がどの文書に属するかの列 (文書配列) を保持します。
+/// 文書配列はウェーブレット行列で保持し、パターンの出現に対応する接尾辞配列の区間に [`U8WaveletMatrix::topk()`] を使って、
+/// 出現回数の多い文書を求めます。
+/// 文書には `docs` での順に `0` から番号を付けます。文書配列の要素は `u8` のため、文書は 256 個までです。
+///
+/// 空のパターンは各文書の `0` から長さまでのすべての位置に出現するとみなします。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::document::NaiveDocumentIndex;
+/// let index = NaiveDocumentIndex::new(&["banana", "bandana", "cabana", "apple"]);
+/// assert_eq!(4, index.len());
+/// // "ana" twice in "banana", and once in the others
+/// assert_eq!(vec![(0, 2), (1, 1)], index.top_docs(b"ana", 2));
+/// assert_eq!(vec![0, 1, 2], index.documents(b"ban"));
+/// assert!(index.documents(b"nab").is_empty());
+/// ```
+pub struct DocumentIndex<T: FID> {
+    // the documents concatenated, each followed by the separator 0 and the bytes shifted by 1
+    text: Vec<usize>,
+    sa: Vec<usize>,
+    // the document of each suffix in the suffix array
+    docs: U8WaveletMatrix<T>,
+    len: usize,
+}
+
+impl <T: FID> DocumentIndex<T> {
+    /// 文書の列 `docs` の索引を構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 256 documents.
+    pub fn new<D: AsRef<[u8]>>(docs: &[D]) -> Self {
+        assert!(docs.len() <= 256, "there should be at most 256 documents");
+        let mut text = Vec::new();
+        let mut owners = Vec::new();
+        for (id, doc) in docs.iter().enumerate() {
+            text.extend(doc.as_ref().iter().map(|&b| b as usize + 1));
+            text.push(0);
+            owners.resize(text.len(), id as u8);
+        }
+        let sa = sa_is(&text, 256);
+        let docs_array = sa.iter().map(|&i| owners[i]).collect();
+        DocumentIndex { text, sa, docs: U8WaveletMatrix::new(&docs_array), len: docs.len() }
+    }
+
+    /// 文書の個数を返します。
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 文書が存在しない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// `pattern` の出現回数が多い順に、最大 `k` 個の文書の番号と出現回数の組を返します。
+    ///
+    /// 出現回数が同じ文書は、番号が小さい順に返します。 `pattern` が出現しない文書は返しません。
+    pub fn top_docs(&self, pattern: &[u8], k: usize) -> Vec<(usize, usize)> {
+        let range = self.range(pattern);
+        self.docs.topk(range.start, range.end, k)
+            .into_iter()
+            .map(|(id, count)| (id as usize, count))
+            .collect()
+    }
+
+    /// `pattern` を含む文書の番号を、昇順に返します。
+    pub fn documents(&self, pattern: &[u8]) -> Vec<usize> {
+        let mut ids: Vec<usize> = self.top_docs(pattern, self.len).into_iter().map(|(id, _)| id).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// `pattern` で始まる接尾辞の、接尾辞配列での区間を二分探索で求めます。
+    fn range(&self, pattern: &[u8]) -> Range<usize> {
+        let pattern: Vec<usize> = pattern.iter().map(|&b| b as usize + 1).collect();
+        // compare only the first pattern.len() symbols of the suffixes
+        let prefix = |k: usize| {
+            let i = self.sa[k];
+            &self.text[i..(i + pattern.len()).min(self.text.len())]
+        };
+        let start = partition_point(0..self.sa.len(), |k| prefix(k) < &pattern[..]);
+        let end = partition_point(start..self.sa.len(), |k| prefix(k) == &pattern[..]);
+        start..end
+    }
+}
+
+/// `range` のうち `pred` が `false` になる最初の位置を返します。 `pred` は `true` が先に並んでいるものとします。
+fn partition_point<F: Fn(usize) -> bool>(range: Range<usize>, pred: F) -> usize {
+    let (mut lo, mut hi) = (range.start, range.end);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+impl <T: FID + SpaceUsage> SpaceUsage for DocumentIndex<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("text", &self.text), ("sa", &self.sa), ("docs", &self.docs)]
+    }
+}
+
+pub type NaiveDocumentIndex = DocumentIndex<NaiveFID>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn naive_count(doc: &[u8], pattern: &[u8]) -> usize {
+        (0..=doc.len()).filter(|&i| doc[i..].starts_with(pattern)).count()
+    }
+
+    #[test]
+    fn same_as_naive() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [1, 2, 4] {
+            let docs: Vec<Vec<u8>> = (0..rng.gen_range(1, 50))
+                .map(|_| (0..rng.gen_range(0, 20)).map(|_| rng.gen_range(0, max) as u8).collect())
+                .collect();
+            let index = NaiveDocumentIndex::new(&docs);
+            assert_eq!(docs.len(), index.len());
+            for _ in 0..100 {
+                let pattern: Vec<u8> = (0..rng.gen_range(0, 4)).map(|_| rng.gen_range(0, max) as u8).collect();
+                let mut expected: Vec<(usize, usize)> = docs.iter()
+                    .enumerate()
+                    .map(|(id, doc)| (id, naive_count(doc, &pattern)))
+                    .filter(|&(_, count)| count > 0)
+                    .collect();
+                let ids: Vec<usize> = expected.iter().map(|&(id, _)| id).collect();
+                assert_eq!(ids, index.documents(&pattern), "docs = {:?}, pattern = {:?}", docs, pattern);
+                expected.sort_by_key(|&(id, count)| (std::cmp::Reverse(count), id));
+                let k = rng.gen_range(0, 10);
+                expected.truncate(k);
+                assert_eq!(expected, index.top_docs(&pattern, k), "docs = {:?}, pattern = {:?}", docs, pattern);
+            }
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let index = NaiveDocumentIndex::new::<&[u8]>(&[]);
+        assert!(index.is_empty());
+        assert!(index.top_docs(b"a", 1).is_empty());
+        assert!(index.documents(b"").is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "there should be at most 256 documents")]
+    fn too_many() {
+        NaiveDocumentIndex::new(&vec![b"a"; 257]);
+    }
+}
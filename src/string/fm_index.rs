@@ -0,0 +1,513 @@
+use super::alphabet::Alphabet;
+use super::bwt::bwt_with_suffix_array;
+use super::suffix_array::suffix_array;
+use crate::bits::fid::FID;
+use crate::bits::fid::NaiveFID;
+use crate::bits::wavelet_matrix::U8WaveletMatrix;
+use crate::error;
+use crate::error::Error;
+#[cfg(feature = "std")]
+use crate::serialize;
+#[cfg(feature = "std")]
+use crate::serialize::Serializable;
+use crate::space::SpaceUsage;
+
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+/// 接尾辞配列の標本化の方法
+///
+/// 間隔 `rate` を大きくするほど索引は小さくなり、出現位置の列挙は遅くなります。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sampling {
+    /// 元のバイト列の位置が `rate` の倍数である接尾辞を標本とします。
+    /// 標本の行はビットベクトルで記録します。 1 つの出現位置を LF 写像 `rate - 1` 回以内で求めます。
+    TextOrder(usize),
+    /// 接尾辞配列の行が `rate` の倍数である接尾辞を標本とします。
+    /// ビットベクトルは不要ですが、 LF 写像の回数に上限はありません。
+    SuffixOrder(usize),
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Sampling::TextOrder(32)
+    }
+}
+
+/// FM-index
+///
+/// Burrows-Wheeler 変換をウェーブレット行列で保持し、後方探索でパターンの出現回数を数えます。
+/// ウェーブレット行列にはテキストの [`Alphabet`] の符号を格納し、段数をアルファベットの大きさに合わせます。
+/// 出現位置は、標本化した接尾辞配列の値に LF 写像で辿り着くまでの回数を足して求めます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::fm_index::NaiveFMIndex;
+/// use rust_study::string::fm_index::Sampling;
+/// let text = b"abracadabra";
+/// let index = NaiveFMIndex::with_sampling(text, Sampling::TextOrder(4));
+/// assert_eq!(2, index.count(b"abra"));
+/// assert_eq!(vec![0, 7], index.locate(b"abra"));
+/// assert_eq!(vec![0, 3, 5, 7, 10], NaiveFMIndex::with_sampling(text, Sampling::SuffixOrder(4)).locate(b"a"));
+/// ```
+pub struct FMIndex<T: FID> {
+    n: usize,
+    // the row of the sentinel in the BWT
+    primary: usize,
+    alphabet: Alphabet,
+    // the codes of the BWT in the alphabet
+    bwt: U8WaveletMatrix<T>,
+    // the number of bytes smaller than each byte
+    smaller: [usize; 257],
+    sampling: Sampling,
+    // the sampled rows, only for the text order
+    marked: Option<T>,
+    // the suffix array values of the sampled rows in the row order
+    samples: Vec<usize>,
+}
+
+impl <T: FID> FMIndex<T> {
+    /// バイト列 `text` の索引を、既定の標本化の方法で構築します。
+    pub fn new(text: &[u8]) -> Self {
+        Self::with_sampling(text, Sampling::default())
+    }
+
+    /// バイト列 `text` の索引を、標本化の方法 `sampling` で構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rate of `sampling` is zero.
+    pub fn with_sampling(text: &[u8], sampling: Sampling) -> Self {
+        let n = text.len();
+        let sa = suffix_array(text);
+        let (transformed, primary) = bwt_with_suffix_array(text, &sa);
+        let alphabet = Alphabet::new(text);
+        let mut smaller = [0; 257];
+        for b in 0..256 {
+            smaller[b + 1] = smaller[b] + alphabet.frequencies()[b];
+        }
+
+        // the first row starts with the sentinel
+        let rows = || core::iter::once(n).chain(sa.iter().copied());
+        let (marked, samples) = match sampling {
+            Sampling::TextOrder(rate) => {
+                assert!(rate > 0, "rate should be positive");
+                let marked: Vec<bool> = rows().map(|i| i.is_multiple_of(rate)).collect();
+                let samples = rows().filter(|&i| i.is_multiple_of(rate)).collect();
+                (Some(T::from_bool_vec(&marked)), samples)
+            },
+            Sampling::SuffixOrder(rate) => {
+                assert!(rate > 0, "rate should be positive");
+                (None, rows().step_by(rate).collect())
+            },
+        };
+        // free the suffix array before building the wavelet matrix
+        drop(sa);
+
+        FMIndex {
+            n,
+            primary,
+            bwt: U8WaveletMatrix::with_levels(&alphabet.encode(&transformed).unwrap(), alphabet.bits()),
+            alphabet,
+            smaller,
+            sampling,
+            marked,
+            samples,
+        }
+    }
+
+    /// 元のバイト列の長さを返します。
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    /// 元のバイト列が空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// 元のバイト列のアルファベットを返します。
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// 標本化の方法を返します。
+    pub fn sampling(&self) -> Sampling {
+        self.sampling
+    }
+
+    /// `pattern` が元のバイト列に現れる回数を、重なりを含めて数えます。
+    ///
+    /// `pattern` が空の場合、 `len + 1` を返します。
+    pub fn count(&self, pattern: &[u8]) -> usize {
+        let (s, e) = self.rows(pattern);
+        e - s
+    }
+
+    /// `pattern` が元のバイト列に現れる位置を、重なりを含めて昇順に返します。
+    ///
+    /// `pattern` が空の場合、 `0` から `len` までのすべての位置を返します。
+    pub fn locate(&self, pattern: &[u8]) -> Vec<usize> {
+        let mut positions = vec![];
+        self.locate_into(pattern, &mut positions);
+        positions
+    }
+
+    /// [`Self::locate()`] の結果を、 `out` を空にしてから書き込みます。
+    ///
+    /// 同じ `out` で繰り返し検索すると、容量が足りている限り領域を確保しません。
+    pub fn locate_into(&self, pattern: &[u8], out: &mut Vec<usize>) {
+        let (s, e) = self.rows(pattern);
+        out.clear();
+        out.extend((s..e).map(|row| self.position(row)));
+        out.sort_unstable();
+    }
+
+    /// `pattern` で始まる行の範囲を、後方探索で求めます。
+    fn rows(&self, pattern: &[u8]) -> (usize, usize) {
+        let mut s = 0;
+        let mut e = self.n + 1;
+        for &b in pattern.iter().rev() {
+            s = self.lf(b, s);
+            e = self.lf(b, e);
+            if s >= e {
+                return (0, 0);
+            }
+        }
+        (s, e)
+    }
+
+    /// 行 `row` の接尾辞の開始位置を、標本に辿り着くまで LF 写像を繰り返して求めます。
+    fn position(&self, mut row: usize) -> usize {
+        let mut steps = 0;
+        loop {
+            if let Some(k) = self.sample(row) {
+                return self.samples[k] + steps;
+            }
+            if row == self.primary {
+                // the suffix starting at 0 precedes no byte
+                return steps;
+            }
+            let code = self.bwt.access(if row > self.primary { row - 1 } else { row });
+            let b = self.alphabet.symbol(code);
+            row = self.lf(b, row);
+            steps += 1;
+        }
+    }
+
+    /// 行 `row` が標本である場合に、標本の中での番号を返します。
+    fn sample(&self, row: usize) -> Option<usize> {
+        match (self.sampling, &self.marked) {
+            (Sampling::TextOrder(_), Some(marked)) => {
+                if marked.access(row) { Some(marked.rank1(row)) } else { None }
+            },
+            (Sampling::SuffixOrder(rate), _) => {
+                if row.is_multiple_of(rate) { Some(row / rate) } else { None }
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// 行 `row` より前にある、末尾が `b` の行の個数を数え、 `b` で始まる行の中での対応する位置を返します (LF 写像) 。
+    fn lf(&self, b: u8, row: usize) -> usize {
+        // the sentinel row ends with no byte, and the first row starts with the sentinel
+        let i = if row > self.primary { row - 1 } else { row };
+        1 + self.smaller[b as usize] + self.alphabet.code(b).map_or(0, |code| self.bwt.rank(code, i))
+    }
+}
+
+#[cfg(feature = "std")]
+impl <T: FID + Serializable> Serializable for FMIndex<T> {
+    const MAGIC: [u8; 8] = *b"FMINDEX_";
+    const VERSION: u32 = 1;
+
+    fn write_body<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        serialize::write_u64(w, self.n as u64)?;
+        serialize::write_u64(w, self.primary as u64)?;
+        let (tag, rate) = match self.sampling {
+            Sampling::TextOrder(rate) => (0, rate),
+            Sampling::SuffixOrder(rate) => (1, rate),
+        };
+        serialize::write_u64(w, tag)?;
+        serialize::write_u64(w, rate as u64)?;
+        for &frequency in self.alphabet.frequencies() {
+            serialize::write_u64(w, frequency as u64)?;
+        }
+        self.bwt.serialize(w)?;
+        if let Some(marked) = &self.marked {
+            marked.serialize(w)?;
+        }
+        let samples: Vec<u64> = self.samples.iter().map(|&i| i as u64).collect();
+        serialize::write_u64s(w, &samples)
+    }
+
+    fn read_body<R: Read>(r: &mut R) -> io::Result<Self> {
+        let n = serialize::read_len(r)?;
+        let primary = serialize::read_len(r)?;
+        let tag = serialize::read_u64(r)?;
+        let rate = serialize::read_len(r)?;
+        let sampling = match (tag, rate) {
+            (_, 0) => return Err(serialize::invalid_data("rate should be positive")),
+            (0, rate) => Sampling::TextOrder(rate),
+            (1, rate) => Sampling::SuffixOrder(rate),
+            _ => return Err(serialize::invalid_data("unknown sampling")),
+        };
+        let mut frequencies = [0; 256];
+        for frequency in frequencies.iter_mut() {
+            *frequency = serialize::read_len(r)?;
+        }
+        let alphabet = Alphabet::from_frequencies(&frequencies);
+        let bwt = U8WaveletMatrix::<T>::deserialize(r)?;
+        let marked = match sampling {
+            Sampling::TextOrder(_) => Some(T::deserialize(r)?),
+            Sampling::SuffixOrder(_) => None,
+        };
+        let samples: Vec<usize> = serialize::read_u64s(r)?.into_iter().map(|i| i as usize).collect();
+
+        let mut smaller = [0_usize; 257];
+        for b in 0..256 {
+            smaller[b + 1] = smaller[b].checked_add(frequencies[b]).ok_or_else(|| serialize::invalid_data("frequencies overflow"))?;
+        }
+        if smaller[256] != n || primary > n || bwt.len() != n {
+            return Err(serialize::invalid_data("inconsistent sizes"));
+        }
+        let index = FMIndex { n, primary, alphabet, bwt, smaller, sampling, marked, samples };
+        index.validate()?;
+        Ok(index)
+    }
+}
+
+impl <T: FID> FMIndex<T> {
+    /// 内部の整合性を検証し、最初に見つけた不整合を [`Error::CorruptData`] で返します。
+    ///
+    /// BWT のウェーブレット行列の [`U8WaveletMatrix::validate()`] に加えて、 BWT の記号の出現回数がアルファベットの表と一致すること、
+    /// 番兵の行から LF 写像を辿ると全行を 1 周して先頭の接尾辞の行に着くこと、標本の行と値が正しいことを `O(n log σ)` で確かめます。
+    /// [`Serializable::deserialize()`] は、読み込んだ後にこれを呼び出します。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_study::string::fm_index::NaiveFMIndex;
+    /// let index = NaiveFMIndex::new(b"abracadabra");
+    /// assert_eq!(Ok(()), index.validate());
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        self.bwt.validate().map_err(|e| error::in_part(e, "bwt"))?;
+        let mut counts = [0_usize; 256];
+        for i in 0..self.n {
+            counts[self.bwt.access(i) as usize] += 1;
+        }
+        for (code, &count) in counts.iter().enumerate() {
+            let expected = self.alphabet.symbols().get(code).map_or(0, |&b| self.alphabet.frequency(b));
+            if count != expected {
+                return Err(Error::CorruptData(format!("code {} appears {} times in the bwt, expected {}", code, count, expected)));
+            }
+        }
+
+        let expected_samples = match (self.sampling, &self.marked) {
+            (Sampling::TextOrder(_), Some(marked)) if marked.len() == self.n + 1 => {
+                marked.validate().map_err(|e| error::in_part(e, "sampled rows"))?;
+                marked.rank1(self.n + 1)
+            },
+            (Sampling::SuffixOrder(rate), None) => self.n / rate + 1,
+            _ => return Err(Error::CorruptData("the sampled rows do not match the sampling".to_string())),
+        };
+        if self.samples.len() != expected_samples {
+            return Err(Error::CorruptData(format!("{} samples, expected {}", self.samples.len(), expected_samples)));
+        }
+
+        // walk the rows from the sentinel suffix back to the first suffix
+        let mut row = 0;
+        let mut position = self.n;
+        loop {
+            let sampled = match self.sampling {
+                Sampling::TextOrder(rate) => {
+                    let marked = self.marked.as_ref().expect("checked above");
+                    if marked.access(row) != position.is_multiple_of(rate) {
+                        return Err(Error::CorruptData(format!("row {} of position {} is wrongly sampled", row, position)));
+                    }
+                    self.sample(row)
+                },
+                Sampling::SuffixOrder(_) => self.sample(row),
+            };
+            if let Some(k) = sampled {
+                if self.samples[k] != position {
+                    return Err(Error::CorruptData(format!("sample {} is {}, expected {}", k, self.samples[k], position)));
+                }
+            }
+            if row == self.primary {
+                break;
+            }
+            if position == 0 {
+                return Err(Error::CorruptData(format!("the LF mapping does not reach the primary row {}", self.primary)));
+            }
+            let code = self.bwt.access(if row > self.primary { row - 1 } else { row });
+            row = self.lf(self.alphabet.symbol(code), row);
+            position -= 1;
+        }
+        if position != 0 {
+            return Err(Error::CorruptData(format!("the primary row {} is reached at position {}, expected 0", self.primary, position)));
+        }
+        Ok(())
+    }
+}
+
+impl <T: FID + SpaceUsage> SpaceUsage for FMIndex<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("alphabet", &self.alphabet), ("bwt", &self.bwt), ("smaller", &self.smaller), ("marked", &self.marked), ("samples", &self.samples)]
+    }
+}
+
+pub type NaiveFMIndex = FMIndex<NaiveFID>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn naive_locate(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+        (0..=text.len()).filter(|&i| text[i..].starts_with(pattern)).collect()
+    }
+
+    #[test]
+    fn small() {
+        // reused across the queries
+        let mut out = vec![];
+        for text in [&b""[..], b"a", b"aaaa", b"banana", b"\x00\xff\x00\xff\x00"] {
+            for sampling in [Sampling::TextOrder(1), Sampling::TextOrder(3), Sampling::SuffixOrder(1), Sampling::SuffixOrder(3)] {
+                let index = NaiveFMIndex::with_sampling(text, sampling);
+                assert_eq!(text.len(), index.len());
+                for pattern in [&b""[..], b"a", b"aa", b"an", b"ana", b"nab", b"\x00", b"\xff\x00"] {
+                    let expected = naive_locate(text, pattern);
+                    assert_eq!(expected.len(), index.count(pattern), "text = {:?}, pattern = {:?}", text, pattern);
+                    assert_eq!(expected, index.locate(pattern), "text = {:?}, pattern = {:?}, sampling = {:?}", text, pattern, sampling);
+                    index.locate_into(pattern, &mut out);
+                    assert_eq!(expected, out, "text = {:?}, pattern = {:?}, sampling = {:?}", text, pattern, sampling);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [2, 4, 256] {
+            let text: Vec<u8> = (0..300).map(|_| rng.gen_range(0, max) as u8).collect();
+            for sampling in [Sampling::default(), Sampling::TextOrder(5), Sampling::SuffixOrder(7)] {
+                let index = NaiveFMIndex::with_sampling(&text, sampling);
+                assert_eq!(sampling, index.sampling());
+                assert!(index.alphabet().size() <= max);
+                for _ in 0..100 {
+                    let len = rng.gen_range(0, 5);
+                    let pattern: Vec<u8> = (0..len).map(|_| rng.gen_range(0, max) as u8).collect();
+                    assert_eq!(naive_locate(&text, &pattern), index.locate(&pattern), "pattern = {:?}", pattern);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn serialize() {
+        let text = b"mississippi";
+        for sampling in [Sampling::TextOrder(3), Sampling::SuffixOrder(2)] {
+            let index = NaiveFMIndex::with_sampling(text, sampling);
+            let mut bytes = vec![];
+            index.serialize(&mut bytes).unwrap();
+            let read = NaiveFMIndex::deserialize(&mut bytes.as_slice()).unwrap();
+            assert_eq!(sampling, read.sampling());
+            assert_eq!(index.alphabet(), read.alphabet());
+            for pattern in [&b""[..], b"i", b"ss", b"issi", b"x"] {
+                assert_eq!(index.locate(pattern), read.locate(pattern));
+            }
+            assert!(NaiveFMIndex::deserialize(&mut &bytes[..bytes.len() - 1]).is_err());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn serialize_broken() {
+        for sampling in [Sampling::TextOrder(3), Sampling::SuffixOrder(2)] {
+            let index = NaiveFMIndex::with_sampling(b"abracadabra", sampling);
+            let mut bytes = vec![];
+            index.serialize(&mut bytes).unwrap();
+            // every single bit flip is either rejected or still answers the queries without panicking
+            for i in 0..bytes.len() {
+                for bit in 0..8 {
+                    let mut broken = bytes.clone();
+                    broken[i] ^= 1 << bit;
+                    if let Ok(read) = NaiveFMIndex::deserialize(&mut broken.as_slice()) {
+                        for pattern in [&b""[..], b"a", b"abra", b"c"] {
+                            assert_eq!(read.locate(pattern).len(), read.count(pattern));
+                        }
+                    }
+                }
+            }
+        }
+
+        // a frequency large enough to overflow the sum, and one that does not match the bwt
+        let index = NaiveFMIndex::with_sampling(b"abracadabra", Sampling::SuffixOrder(2));
+        let mut bytes = vec![];
+        index.serialize(&mut bytes).unwrap();
+        let error = |bytes: &[u8]| NaiveFMIndex::deserialize(&mut &bytes[..]).err().expect("should be rejected");
+        // the frequencies follow the outer header, n, primary, the tag and the rate
+        let at = 20 + 32 + 8 * b'z' as usize;
+        let mut broken = bytes.clone();
+        broken[at..at + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(io::ErrorKind::InvalidData, error(&broken).kind());
+        let at = 20 + 32 + 8 * b'a' as usize;
+        broken = bytes.clone();
+        broken[at] -= 1;
+        broken[at + 8] += 1;
+        assert!(error(&broken).to_string().contains("times in the bwt"));
+
+        // a sample which points to a wrong position
+        let mut broken = bytes.clone();
+        let at = bytes.len() - 8;
+        broken[at] ^= 1;
+        assert!(error(&broken).to_string().contains("sample 5"));
+    }
+
+    #[test]
+    fn validate() {
+        for sampling in [Sampling::TextOrder(3), Sampling::SuffixOrder(2)] {
+            assert_eq!(Ok(()), NaiveFMIndex::with_sampling(b"mississippi", sampling).validate());
+            assert_eq!(Ok(()), NaiveFMIndex::with_sampling(b"", sampling).validate());
+        }
+
+        let broken = |sampling: Sampling, f: fn(&mut NaiveFMIndex)| {
+            let mut index = NaiveFMIndex::with_sampling(b"banana", sampling);
+            f(&mut index);
+            index.validate().unwrap_err().to_string()
+        };
+        // the rows are $, a$, ana$, anana$, banana$, na$, nana$, and the bwt is "annb$aa" with the sentinel at row 4
+        assert_eq!("corrupt data: sample 1 is 5, expected 3", broken(Sampling::SuffixOrder(2), |index| index.samples[1] = 5));
+        assert_eq!("corrupt data: 3 samples, expected 4", broken(Sampling::SuffixOrder(2), |index| { index.samples.pop(); }));
+        assert_eq!("corrupt data: row 1 of position 5 is wrongly sampled", broken(Sampling::TextOrder(3), |index| {
+            let marked = index.marked.as_mut().unwrap();
+            marked.set(1, true);
+            marked.set(2, false);
+        }));
+        assert_eq!("corrupt data: the primary row 3 is reached at position 1, expected 0", broken(Sampling::SuffixOrder(2), |index| index.primary = 3));
+        assert!(broken(Sampling::SuffixOrder(2), |index| index.alphabet = Alphabet::new(b"bananb")).contains("times in the bwt"));
+        assert!(broken(Sampling::SuffixOrder(2), |index| index.bwt = NaiveU8WaveletMatrix::with_levels(&[0, 1, 1, 2, 0, 0], 2)).contains("times in the bwt"));
+    }
+
+    #[test]
+    #[should_panic(expected = "rate should be positive")]
+    fn zero_rate() {
+        NaiveFMIndex::with_sampling(b"abc", Sampling::SuffixOrder(0));
+    }
+}
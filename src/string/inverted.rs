@@ -0,0 +1,276 @@
+use crate::bits::gamma::GammaVector;
+use crate::collections::heap::Heap;
+use crate::space::SpaceUsage;
+use crate::string::trie::NaiveTrieMap;
+use crate::string::trie::TrieMap;
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 単語の出現位置つきの転置索引
+///
+/// 文書を空白で区切った単語の列とみなし、単語ごとに、その単語を含む文書と文書の中での位置 (単語の番号) の一覧を保持します。
+/// 単語から一覧への対応はトライ木で、一覧は文書番号と位置の差分を [`GammaVector`] で符号化して保持します。
+/// 複数の一覧の AND/OR は、ヒープによる k-way マージで求めます。
+/// 文書は追加した順に `0` から番号を付けます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::inverted::InvertedIndex;
+/// let mut index = InvertedIndex::new();
+/// index.add("to be or not to be");
+/// index.add("not to worry");
+/// index.add("let it be");
+/// assert_eq!(vec![(0, vec![0, 4]), (1, vec![1])], index.postings("to"));
+/// assert_eq!(vec![0, 1], index.and(&["to", "not"]));
+/// assert_eq!(vec![0, 1, 2], index.or(&["be", "worry"]));
+/// assert_eq!(vec![0, 1], index.phrase("not to"));
+/// assert_eq!(vec![2], index.phrase("it be"));
+/// assert_eq!(vec![0, 2], index.or_prefix("b"));
+/// ```
+#[derive(Default)]
+pub struct InvertedIndex {
+    // the posting list of each term
+    terms: NaiveTrieMap<usize>,
+    postings: Vec<Postings>,
+    len: usize,
+}
+
+/// 1 つの単語の、文書番号の差分、位置の個数、位置の差分を順に符号化した一覧
+#[derive(Default)]
+struct Postings {
+    gamma: GammaVector,
+    docs: usize,
+    last_doc: usize,
+}
+
+impl InvertedIndex {
+    /// 空の索引を構築します。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 文書の個数を返します。
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 文書が存在しない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 異なる単語の個数を返します。
+    pub fn terms(&self) -> usize {
+        self.postings.len()
+    }
+
+    /// 文書 `doc` を追加し、その番号を返します。
+    pub fn add(&mut self, doc: &str) -> usize {
+        let id = self.len;
+        let mut positions: NaiveTrieMap<Vec<usize>> = NaiveTrieMap::new();
+        for (pos, term) in doc.split_whitespace().enumerate() {
+            match positions.get_mut(term) {
+                Some(list) => list.push(pos),
+                None => {
+                    positions.insert(term, vec![pos]);
+                },
+            }
+        }
+        for (term, list) in positions.iter() {
+            let k = match self.terms.get(&term) {
+                Some(&k) => k,
+                None => {
+                    self.terms.insert(&term, self.postings.len());
+                    self.postings.push(Postings::default());
+                    self.postings.len() - 1
+                },
+            };
+            let postings = &mut self.postings[k];
+            let gap = if postings.docs == 0 { id } else { id - postings.last_doc };
+            postings.gamma.push(gap as u64);
+            postings.gamma.push(list.len() as u64 - 1);
+            let mut last = 0;
+            for &pos in list {
+                postings.gamma.push((pos - last) as u64);
+                last = pos;
+            }
+            postings.docs += 1;
+            postings.last_doc = id;
+        }
+        self.len += 1;
+        id
+    }
+
+    /// 単語 `term` を含む文書の番号と、文書の中での位置の一覧の組を、文書の番号の昇順に返します。
+    pub fn postings(&self, term: &str) -> Vec<(usize, Vec<usize>)> {
+        let postings = match self.terms.get(term) {
+            Some(&k) => &self.postings[k],
+            None => return vec![],
+        };
+        let mut values = postings.gamma.iter().map(|x| x as usize);
+        let mut result = Vec::with_capacity(postings.docs);
+        let mut doc = 0;
+        for _ in 0..postings.docs {
+            doc += values.next().unwrap();
+            let count = values.next().unwrap() + 1;
+            let positions = values.by_ref()
+                .take(count)
+                .scan(0, |pos, gap| {
+                    *pos += gap;
+                    Some(*pos)
+                })
+                .collect();
+            result.push((doc, positions));
+        }
+        result
+    }
+
+    /// 単語 `terms` をすべて含む文書の番号を、昇順に返します。
+    ///
+    /// `terms` が空の場合、すべての文書を返します。
+    pub fn and<S: AsRef<str>>(&self, terms: &[S]) -> Vec<usize> {
+        if terms.is_empty() {
+            return (0..self.len).collect();
+        }
+        merge(terms.iter().map(|term| self.docs(term.as_ref())).collect())
+            .into_iter()
+            .filter(|&(_, count)| count == terms.len())
+            .map(|(doc, _)| doc)
+            .collect()
+    }
+
+    /// 単語 `terms` のいずれかを含む文書の番号を、昇順に返します。
+    pub fn or<S: AsRef<str>>(&self, terms: &[S]) -> Vec<usize> {
+        merge(terms.iter().map(|term| self.docs(term.as_ref())).collect())
+            .into_iter()
+            .map(|(doc, _)| doc)
+            .collect()
+    }
+
+    /// `prefix` で始まる単語のいずれかを含む文書の番号を、昇順に返します。
+    pub fn or_prefix(&self, prefix: &str) -> Vec<usize> {
+        let terms: Vec<String> = self.terms.iter_prefix(prefix).map(|(term, _)| term).collect();
+        self.or(&terms)
+    }
+
+    /// 空白で区切った単語の列 `phrase` を、連続した部分列として含む文書の番号を、昇順に返します。
+    ///
+    /// `phrase` に単語がない場合、すべての文書を返します。
+    pub fn phrase(&self, phrase: &str) -> Vec<usize> {
+        let terms: Vec<&str> = phrase.split_whitespace().collect();
+        if terms.is_empty() {
+            return (0..self.len).collect();
+        }
+        let postings: Vec<Vec<(usize, Vec<usize>)>> = terms.iter().map(|term| self.postings(term)).collect();
+        let positions = |k: usize, doc: usize| -> &[usize] {
+            match postings[k].binary_search_by_key(&doc, |&(d, _)| d) {
+                Ok(i) => &postings[k][i].1,
+                Err(_) => &[],
+            }
+        };
+        self.and(&terms)
+            .into_iter()
+            .filter(|&doc| {
+                positions(0, doc).iter().any(|&start| {
+                    (1..terms.len()).all(|k| positions(k, doc).binary_search(&(start + k)).is_ok())
+                })
+            })
+            .collect()
+    }
+
+    /// 単語 `term` を含む文書の番号を、昇順に返します。
+    fn docs(&self, term: &str) -> Vec<usize> {
+        self.postings(term).into_iter().map(|(doc, _)| doc).collect()
+    }
+}
+
+/// 昇順の列 `lists` を k-way マージし、各値とそれを含む列の個数の組を、値の昇順に返します。
+fn merge(lists: Vec<Vec<usize>>) -> Vec<(usize, usize)> {
+    // the value, the list, and the position in the list
+    let mut heap = Heap::with_compare(|lhs: &(usize, usize, usize), rhs| lhs.cmp(rhs));
+    for (k, list) in lists.iter().enumerate() {
+        if let Some(&v) = list.first() {
+            heap.push((v, k, 0));
+        }
+    }
+    let mut result: Vec<(usize, usize)> = Vec::new();
+    while let Some((v, k, i)) = heap.pop() {
+        match result.last_mut() {
+            Some((last, count)) if *last == v => *count += 1,
+            _ => result.push((v, 1)),
+        }
+        if let Some(&next) = lists[k].get(i + 1) {
+            heap.push((next, k, i + 1));
+        }
+    }
+    result
+}
+
+impl SpaceUsage for Postings {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("gamma", &self.gamma)]
+    }
+}
+
+impl SpaceUsage for InvertedIndex {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("terms", &self.terms), ("postings", &self.postings)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn same_as_naive() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let words = ["a", "b", "c", "ab", "ba", "abc"];
+        let docs: Vec<Vec<&str>> = (0..50)
+            .map(|_| (0..rng.gen_range(0, 30)).map(|_| words[rng.gen_range(0, words.len())]).collect())
+            .collect();
+        let mut index = InvertedIndex::new();
+        for doc in &docs {
+            index.add(&doc.join(" "));
+        }
+        assert_eq!(docs.len(), index.len());
+        assert_eq!(words.len(), index.terms());
+        for term in words {
+            let expected: Vec<(usize, Vec<usize>)> = docs.iter()
+                .enumerate()
+                .map(|(id, doc)| (id, (0..doc.len()).filter(|&i| doc[i] == term).collect::<Vec<_>>()))
+                .filter(|(_, positions)| !positions.is_empty())
+                .collect();
+            assert_eq!(expected, index.postings(term));
+        }
+        for _ in 0..100 {
+            let terms: Vec<&str> = (0..rng.gen_range(0, 4)).map(|_| words[rng.gen_range(0, words.len())]).collect();
+            let and: Vec<usize> = (0..docs.len()).filter(|&id| terms.iter().all(|t| docs[id].contains(t))).collect();
+            let or: Vec<usize> = (0..docs.len()).filter(|&id| terms.iter().any(|t| docs[id].contains(t))).collect();
+            let phrase: Vec<usize> = (0..docs.len())
+                .filter(|&id| terms.is_empty() || docs[id].windows(terms.len()).any(|w| w == &terms[..]))
+                .collect();
+            assert_eq!(and, index.and(&terms), "terms = {:?}", terms);
+            assert_eq!(or, index.or(&terms), "terms = {:?}", terms);
+            assert_eq!(phrase, index.phrase(&terms.join(" ")), "terms = {:?}", terms);
+        }
+        let prefixed: Vec<usize> = (0..docs.len()).filter(|&id| docs[id].iter().any(|t| t.starts_with('a'))).collect();
+        assert_eq!(prefixed, index.or_prefix("a"));
+    }
+
+    #[test]
+    fn unknown_terms() {
+        let mut index = InvertedIndex::new();
+        index.add("hello world");
+        assert!(index.postings("bye").is_empty());
+        assert!(index.and(&["hello", "bye"]).is_empty());
+        assert_eq!(vec![0], index.or(&["hello", "bye"]));
+        assert!(index.phrase("world hello").is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+use alloc::vec::Vec;
+
+/// 1 つの `u64` に詰められる k-mer の最大の長さ
+pub const MAX_K: usize = 32;
+
+/// 塩基 `b` の 2 ビットの符号を返します。 `A`, `C`, `G`, `T` (小文字を含む) 以外の場合、 `None` を返します。
+fn encode_base(b: u8) -> Option<u64> {
+    match b {
+        b'A' | b'a' => Some(0),
+        b'C' | b'c' => Some(1),
+        b'G' | b'g' => Some(2),
+        b'T' | b't' => Some(3),
+        _ => None,
+    }
+}
+
+fn mask(k: usize) -> u64 {
+    if k == MAX_K { !0 } else { (1 << (2 * k)) - 1 }
+}
+
+fn check(k: usize) {
+    assert!(k > 0 && k <= MAX_K, "k should be in [1, 32]");
+}
+
+/// k-mer `kmer` を、先頭の塩基を上位に 1 塩基あたり 2 ビットで詰めた値に符号化します。
+///
+/// `A`, `C`, `G`, `T` をそれぞれ `0`, `1`, `2`, `3` とし、小文字も受け付けます。
+/// それ以外のバイトを含む場合、 `None` を返します。
+///
+/// # Panics
+///
+/// Panics if `kmer` is empty or longer than [`MAX_K`].
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::kmer::decode;
+/// use rust_study::string::kmer::encode;
+/// assert_eq!(Some(0b00_01_10_11), encode(b"ACGT"));
+/// assert_eq!(None, encode(b"ACNT"));
+/// assert_eq!(b"ACGT".to_vec(), decode(0b00_01_10_11, 4));
+/// ```
+pub fn encode(kmer: &[u8]) -> Option<u64> {
+    check(kmer.len());
+    kmer.iter().try_fold(0, |code, &b| Some((code << 2) | encode_base(b)?))
+}
+
+/// [`encode()`] で符号化した長さ `k` の k-mer を、大文字の塩基の列に復元します。
+///
+/// # Panics
+///
+/// Panics if `k` is zero or greater than [`MAX_K`].
+pub fn decode(code: u64, k: usize) -> Vec<u8> {
+    check(k);
+    (0..k).rev().map(|i| b"ACGT"[((code >> (2 * i)) & 3) as usize]).collect()
+}
+
+/// 符号化した長さ `k` の k-mer の逆相補鎖を返します。
+///
+/// # Panics
+///
+/// Panics if `k` is zero or greater than [`MAX_K`].
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::kmer::encode;
+/// use rust_study::string::kmer::reverse_complement;
+/// assert_eq!(encode(b"CGGT"), Some(reverse_complement(encode(b"ACCG").unwrap(), 4)));
+/// ```
+pub fn reverse_complement(code: u64, k: usize) -> u64 {
+    check(k);
+    // the complement of a base is 3 minus the base
+    let mut rest = !code;
+    let mut result = 0;
+    for _ in 0..k {
+        result = (result << 2) | (rest & 3);
+        rest >>= 2;
+    }
+    result
+}
+
+/// 塩基配列の正規 k-mer (k-mer とその逆相補鎖のうち小さい方) を、開始位置の順に列挙するイテレータ
+///
+/// 1 塩基ずつずらしながら正方向と逆相補鎖の符号を更新するため、 1 つの k-mer あたり `O(1)` です。
+/// `A`, `C`, `G`, `T` 以外のバイトを含む k-mer は飛ばします。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::kmer::CanonicalKmers;
+/// use rust_study::string::kmer::decode;
+/// let kmers: Vec<(usize, Vec<u8>)> = CanonicalKmers::new(b"TTGNACG", 3)
+///     .map(|(i, code)| (i, decode(code, 3)))
+///     .collect();
+/// // CAA is the reverse complement of TTG, and ACG is its own
+/// assert_eq!(vec![(0, b"CAA".to_vec()), (4, b"ACG".to_vec())], kmers);
+/// ```
+pub struct CanonicalKmers<'a> {
+    seq: &'a [u8],
+    k: usize,
+    // the next position to read
+    i: usize,
+    // the number of the valid bases read last
+    valid: usize,
+    forward: u64,
+    reverse: u64,
+}
+
+impl <'a> CanonicalKmers<'a> {
+    /// 塩基配列 `seq` の長さ `k` の正規 k-mer を列挙します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero or greater than [`MAX_K`].
+    pub fn new(seq: &'a [u8], k: usize) -> Self {
+        check(k);
+        CanonicalKmers { seq, k, i: 0, valid: 0, forward: 0, reverse: 0 }
+    }
+}
+
+impl <'a> Iterator for CanonicalKmers<'a> {
+    /// k-mer の開始位置と、符号化した正規 k-mer の組
+    type Item = (usize, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.i < self.seq.len() {
+            let b = self.seq[self.i];
+            self.i += 1;
+            match encode_base(b) {
+                Some(c) => {
+                    self.forward = ((self.forward << 2) | c) & mask(self.k);
+                    self.reverse = (self.reverse >> 2) | ((3 - c) << (2 * (self.k - 1)));
+                    self.valid += 1;
+                    if self.valid >= self.k {
+                        return Some((self.i - self.k, self.forward.min(self.reverse)));
+                    }
+                },
+                None => self.valid = 0,
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn naive_reverse_complement(kmer: &[u8]) -> Vec<u8> {
+        kmer.iter().rev().map(|&b| match b {
+            b'A' => b'T',
+            b'C' => b'G',
+            b'G' => b'C',
+            _ => b'A',
+        }).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for k in 1..=MAX_K {
+            let kmer: Vec<u8> = (0..k).map(|_| b"ACGT"[rng.gen_range(0, 4)]).collect();
+            let code = encode(&kmer).unwrap();
+            assert_eq!(kmer, decode(code, k));
+            assert_eq!(encode(&naive_reverse_complement(&kmer)), Some(reverse_complement(code, k)));
+            assert_eq!(code, encode(&kmer.to_ascii_lowercase()).unwrap());
+        }
+    }
+
+    #[test]
+    fn same_as_naive() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for k in [1, 2, 5, 31, 32] {
+            let seq: Vec<u8> = (0..200)
+                .map(|_| if rng.gen_range(0, 50) == 0 { b'N' } else { b"ACGT"[rng.gen_range(0, 4)] })
+                .collect();
+            let expected: Vec<(usize, u64)> = seq.windows(k)
+                .enumerate()
+                .filter_map(|(i, w)| {
+                    let forward = encode(w)?;
+                    let reverse = encode(&naive_reverse_complement(w)).unwrap();
+                    Some((i, forward.min(reverse)))
+                })
+                .collect();
+            assert_eq!(expected, CanonicalKmers::new(&seq, k).collect::<Vec<_>>(), "seq = {:?}, k = {}", seq, k);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "k should be in [1, 32]")]
+    fn too_long() {
+        encode(&[b'A'; 33]);
+    }
+}
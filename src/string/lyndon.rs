@@ -0,0 +1,129 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// `text` の Lyndon 分解を、 Duval のアルゴリズムで `O(n)` で求めます。
+///
+/// Lyndon 語は、自身のどの真の接尾辞よりも辞書順で真に小さい空でない列です。
+/// `text` を辞書順で広義単調減少する Lyndon 語の列に一意に分解し、各語の範囲を返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::lyndon::lyndon_factorization;
+/// // b, an, an, a
+/// assert_eq!(vec![0..1, 1..3, 3..5, 5..6], lyndon_factorization(b"banana"));
+/// assert_eq!(vec![0..4], lyndon_factorization(b"aabb"));
+/// ```
+pub fn lyndon_factorization<T: Ord>(text: &[T]) -> Vec<Range<usize>> {
+    let n = text.len();
+    let mut factors = Vec::new();
+    let mut i = 0;
+    while i < n {
+        // text[i..j] is a power of a Lyndon word of the length j - k, followed by its prefix
+        let mut k = i;
+        let mut j = i + 1;
+        while j < n && text[k] <= text[j] {
+            k = if text[k] < text[j] { i } else { k + 1 };
+            j += 1;
+        }
+        while i <= k {
+            factors.push(i..i + j - k);
+            i += j - k;
+        }
+    }
+    factors
+}
+
+/// `text` が Lyndon 語である場合に、 `true` を返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::lyndon::is_lyndon;
+/// assert!(is_lyndon(b"aab"));
+/// assert!(!is_lyndon(b"aba"));
+/// assert!(!is_lyndon(b"abab"));
+/// ```
+pub fn is_lyndon<T: Ord>(text: &[T]) -> bool {
+    lyndon_factorization(text).len() == 1
+}
+
+/// `text` の巡回シフトのうち、辞書順で最小のものの開始位置を `O(n)` で求めます。
+///
+/// 環状の列 (環状 DNA やネックレス) の正規形を求めるのに使います。
+/// 最小の巡回シフトが複数の位置から始まる場合、一番小さい位置を返します。 `text` が空の場合、 `0` を返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::lyndon::least_rotation;
+/// // abc
+/// assert_eq!(1, least_rotation(b"cab"));
+/// assert_eq!(0, least_rotation(b"abab"));
+/// ```
+pub fn least_rotation<T: Ord>(text: &[T]) -> usize {
+    let n = text.len();
+    let doubled: Vec<&T> = text.iter().chain(text).collect();
+    // the last run of the equal Lyndon factors of the doubled text starting before n starts the least rotation
+    let mut start = 0;
+    let mut i = 0;
+    while i < n {
+        start = i;
+        let mut k = i;
+        let mut j = i + 1;
+        while j < 2 * n && doubled[k] <= doubled[j] {
+            k = if doubled[k] < doubled[j] { i } else { k + 1 };
+            j += 1;
+        }
+        while i <= k {
+            i += j - k;
+        }
+    }
+    start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn naive_is_lyndon(text: &[u8]) -> bool {
+        !text.is_empty() && (1..text.len()).all(|i| text < &text[i..])
+    }
+
+    #[test]
+    fn random_factorization() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [1, 2, 3] {
+            for _ in 0..300 {
+                let text: Vec<u8> = (0..rng.gen_range(0, 20)).map(|_| rng.gen_range(0, max) as u8).collect();
+                let factors = lyndon_factorization(&text);
+                assert_eq!(text.len(), factors.iter().map(|f| f.len()).sum::<usize>());
+                for (k, f) in factors.iter().enumerate() {
+                    assert!(naive_is_lyndon(&text[f.clone()]), "text = {:?}, factors = {:?}", text, factors);
+                    if k > 0 {
+                        assert_eq!(factors[k - 1].end, f.start);
+                        assert!(text[factors[k - 1].clone()] >= text[f.clone()], "text = {:?}, factors = {:?}", text, factors);
+                    }
+                }
+                assert_eq!(naive_is_lyndon(&text), is_lyndon(&text), "text = {:?}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn random_least_rotation() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [1, 2, 3] {
+            for _ in 0..300 {
+                let text: Vec<u8> = (0..rng.gen_range(0, 20)).map(|_| rng.gen_range(0, max) as u8).collect();
+                let n = text.len();
+                let rotation = |i: usize| -> Vec<u8> { text[i..].iter().chain(&text[..i]).copied().collect() };
+                let expected = (0..n).min_by_key(|&i| rotation(i)).unwrap_or(0);
+                assert_eq!(expected, least_rotation(&text), "text = {:?}", text);
+            }
+        }
+    }
+}
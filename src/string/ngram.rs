@@ -0,0 +1,217 @@
+use crate::collections::hash::HashMap;
+use crate::space::SpaceUsage;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use alloc::vec;
+
+/// N-gram を切り出す単位
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GramUnit {
+    /// UTF-8 のバイトごとに切り出します。
+    Byte,
+    /// 文字ごとに切り出します。
+    #[default]
+    Char,
+}
+
+/// 文書の集合の N-gram の転置索引
+///
+/// 文書ごとに N-gram の出現回数を保持し、検索語と共通する N-gram の個数で候補の文書を絞り込みます。
+/// 文書は追加した順に `0` から番号を付けます。
+/// 長さが `n` に満たない文書や検索語は N-gram を持ちません。
+///
+/// 編集距離が `k` 以下の 2 つの列は、少なくとも `(長さ - n + 1) - k * n` 個の N-gram を共有します (q-gram lemma) 。
+/// [`Self::candidates_within()`] はこれを使って、編集距離が `k` 以下の文書を取りこぼさずに候補を絞り込みます。
+/// 候補の編集距離は [`distance`](super::distance) で確かめます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::distance::levenshtein;
+/// use rust_study::string::ngram::GramUnit;
+/// use rust_study::string::ngram::NgramIndex;
+/// let docs = ["hello world", "hello word", "goodbye world", "help"];
+/// let mut index = NgramIndex::new(2, GramUnit::Char);
+/// for doc in docs {
+///     index.add(doc);
+/// }
+/// assert_eq!(vec![0, 1, 2], index.candidates("world", 2));
+///
+/// let query = "hallo world";
+/// let similar: Vec<usize> = index.candidates_within(query, 2)
+///     .into_iter()
+///     .filter(|&id| levenshtein(docs[id].as_bytes(), query.as_bytes()) <= 2)
+///     .collect();
+/// assert_eq!(vec![0, 1], similar);
+/// ```
+pub struct NgramIndex {
+    n: usize,
+    unit: GramUnit,
+    // the documents and the number of the gram in them, sorted by the documents
+    postings: HashMap<Box<[u8]>, Vec<(usize, usize)>>,
+    len: usize,
+}
+
+impl NgramIndex {
+    /// `unit` を単位とする `n`-gram の、空の索引を構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn new(n: usize, unit: GramUnit) -> Self {
+        assert!(n > 0, "n should be positive");
+        NgramIndex { n, unit, postings: HashMap::default(), len: 0 }
+    }
+
+    /// N-gram の長さを返します。
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// N-gram を切り出す単位を返します。
+    pub fn unit(&self) -> GramUnit {
+        self.unit
+    }
+
+    /// 文書の個数を返します。
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 文書が存在しない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 文書 `doc` を追加し、その番号を返します。
+    pub fn add(&mut self, doc: &str) -> usize {
+        let id = self.len;
+        for (gram, count) in self.grams(doc) {
+            self.postings.entry(gram.into()).or_default().push((id, count));
+        }
+        self.len += 1;
+        id
+    }
+
+    /// 検索語 `query` と共通する N-gram が `min_common` 個以上ある文書の番号を、昇順に返します。
+    ///
+    /// 同じ N-gram が複数回現れる場合、少ない方の回数だけ共通するとみなします。
+    pub fn candidates(&self, query: &str, min_common: usize) -> Vec<usize> {
+        if min_common == 0 {
+            return (0..self.len).collect();
+        }
+        let mut common: HashMap<usize, usize> = HashMap::default();
+        for (gram, count) in self.grams(query) {
+            for &(id, doc_count) in self.postings.get(gram).into_iter().flatten() {
+                *common.entry(id).or_insert(0) += count.min(doc_count);
+            }
+        }
+        let mut ids: Vec<usize> = common.into_iter().filter(|&(_, c)| c >= min_common).map(|(id, _)| id).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// 検索語 `query` との編集距離が `distance` 以下になりうる文書の番号を、昇順に返します。
+    ///
+    /// 編集距離は [`Self::unit()`] を単位として数えます。
+    /// 返す文書には編集距離が `distance` を超えるものも含まれますが、 `distance` 以下の文書はすべて含まれます。
+    pub fn candidates_within(&self, query: &str, distance: usize) -> Vec<usize> {
+        let len = match self.unit {
+            GramUnit::Byte => query.len(),
+            GramUnit::Char => query.chars().count(),
+        };
+        let grams = (len + 1).saturating_sub(self.n);
+        self.candidates(query, grams.saturating_sub(distance * self.n))
+    }
+
+    /// `text` の N-gram とその出現回数を返します。
+    fn grams<'a>(&self, text: &'a str) -> HashMap<&'a [u8], usize> {
+        let bytes = text.as_bytes();
+        // the boundaries of the units
+        let boundaries: Vec<usize> = match self.unit {
+            GramUnit::Byte => (0..=bytes.len()).collect(),
+            GramUnit::Char => text.char_indices().map(|(i, _)| i).chain(core::iter::once(bytes.len())).collect(),
+        };
+        let mut grams = HashMap::default();
+        for w in boundaries.windows(self.n + 1) {
+            *grams.entry(&bytes[w[0]..w[self.n]]).or_insert(0) += 1;
+        }
+        grams
+    }
+}
+
+impl SpaceUsage for NgramIndex {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("postings", &self.postings)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::distance::levenshtein;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn random_doc(rng: &mut StdRng) -> String {
+        (0..rng.gen_range(0, 10)).map(|_| ['a', 'b', 'c', 'あ'][rng.gen_range(0, 4)]).collect()
+    }
+
+    #[test]
+    fn no_false_negatives() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for unit in [GramUnit::Byte, GramUnit::Char] {
+            for n in 1..4 {
+                let docs: Vec<String> = (0..100).map(|_| random_doc(&mut rng)).collect();
+                let mut index = NgramIndex::new(n, unit);
+                for doc in &docs {
+                    index.add(doc);
+                }
+                assert_eq!(docs.len(), index.len());
+                for _ in 0..20 {
+                    let query = random_doc(&mut rng);
+                    let distance = rng.gen_range(0, 3);
+                    let candidates = index.candidates_within(&query, distance);
+                    for (id, doc) in docs.iter().enumerate() {
+                        let d = match unit {
+                            GramUnit::Byte => levenshtein(doc.as_bytes(), query.as_bytes()),
+                            GramUnit::Char => levenshtein(&doc.chars().collect::<Vec<_>>(), &query.chars().collect::<Vec<_>>()),
+                        };
+                        if d <= distance {
+                            assert!(candidates.contains(&id), "doc = {:?}, query = {:?}, n = {}", doc, query, n);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn candidates() {
+        let mut index = NgramIndex::new(2, GramUnit::Byte);
+        index.add("aaaa");
+        index.add("aab");
+        index.add("b");
+        // "aa" three times, and once
+        assert_eq!(vec![0, 1], index.candidates("aaa", 1));
+        assert_eq!(vec![0], index.candidates("aaa", 2));
+        assert_eq!(vec![0, 1, 2], index.candidates("zz", 0));
+        assert!(index.candidates("b", 1).is_empty());
+
+        let mut index = NgramIndex::new(1, GramUnit::Char);
+        index.add("あい");
+        assert_eq!(vec![0], index.candidates("いう", 1));
+        let mut index = NgramIndex::new(1, GramUnit::Byte);
+        index.add("あい");
+        // the first two bytes of "あ" and "い" are the same
+        assert_eq!(vec![0], index.candidates("いう", 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "n should be positive")]
+    fn zero() {
+        NgramIndex::new(0, GramUnit::Byte);
+    }
+}
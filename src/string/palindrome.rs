@@ -0,0 +1,108 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// `text` の各位置を中心とする最長の回文の半径を、 Manacher のアルゴリズムで `O(n)` で求めます。
+///
+/// 奇数長の回文の半径の配列と、偶数長の回文の半径の配列の組を返します。
+///
+/// - 奇数長の配列の `i` 番目の要素 `r` は、 `text[i + 1 - r..i + r]` が `i` を中心とする最長の回文であることを表します。長さは `text.len()` です。
+/// - 偶数長の配列の `i` 番目の要素 `r` は、 `text[i - r..i + r]` が `i - 1` と `i` の間を中心とする最長の回文であることを表します。長さは `text.len() + 1` です。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::palindrome::manacher;
+/// let (odd, even) = manacher(b"abaab");
+/// assert_eq!(vec![1, 2, 1, 1, 1], odd);
+/// // "baab" is centered between 2 and 3
+/// assert_eq!(vec![0, 0, 0, 2, 0, 0], even);
+/// ```
+pub fn manacher<T: PartialEq>(text: &[T]) -> (Vec<usize>, Vec<usize>) {
+    let n = text.len();
+    // the text interleaved with separators, where the bytes are at the odd positions
+    let m = 2 * n + 1;
+    let same = |a: usize, b: usize| a.is_multiple_of(2) || text[a / 2] == text[b / 2];
+    let mut radius = vec![0; m];
+    // the palindrome reaching the rightmost, centered at c and ending at r
+    let (mut c, mut r) = (0, 0);
+    for k in 0..m {
+        let mut rad = if k < r { radius[2 * c - k].min(r - k) } else { 0 };
+        while rad < k && k + rad + 1 < m && same(k - rad - 1, k + rad + 1) {
+            rad += 1;
+        }
+        radius[k] = rad;
+        if k + rad > r {
+            c = k;
+            r = k + rad;
+        }
+    }
+    let odd = (0..n).map(|i| radius[2 * i + 1].div_ceil(2)).collect();
+    let even = (0..=n).map(|i| radius[2 * i] / 2).collect();
+    (odd, even)
+}
+
+/// `text` の最長の回文である部分列の範囲を返します。
+///
+/// 最長のものが複数ある場合、一番左のものを返します。 `text` が空の場合、空の範囲を返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::palindrome::longest_palindrome;
+/// let text: Vec<char> = "たけやぶやけた!".chars().collect();
+/// assert_eq!(0..7, longest_palindrome(&text));
+/// // "aba" and the longer "baab"
+/// assert_eq!(1..5, longest_palindrome(b"abaabc"));
+/// ```
+pub fn longest_palindrome<T: PartialEq>(text: &[T]) -> Range<usize> {
+    let (odd, even) = manacher(text);
+    let odd = odd.iter().enumerate().map(|(i, &r)| (i + 1 - r)..(i + r));
+    let even = even.iter().enumerate().map(|(i, &r)| (i - r)..(i + r));
+    odd.chain(even).fold(0..0, |longest, range| {
+        if range.len() > longest.len() || (range.len() == longest.len() && range.start < longest.start) {
+            range
+        } else {
+            longest
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn is_palindrome(text: &[u8]) -> bool {
+        text.iter().eq(text.iter().rev())
+    }
+
+    #[test]
+    fn same_as_naive() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [1, 2, 3] {
+            for _ in 0..300 {
+                let text: Vec<u8> = (0..rng.gen_range(0, 20)).map(|_| rng.gen_range(0, max) as u8).collect();
+                let n = text.len();
+                let (odd, even) = manacher(&text);
+                for i in 0..n {
+                    let expected = (1..=i.min(n - 1 - i) + 1).rev().find(|&r| is_palindrome(&text[i + 1 - r..i + r])).unwrap();
+                    assert_eq!(expected, odd[i], "text = {:?}, i = {}", text, i);
+                }
+                for i in 0..=n {
+                    let expected = (0..=i.min(n - i)).rev().find(|&r| is_palindrome(&text[i - r..i + r])).unwrap();
+                    assert_eq!(expected, even[i], "text = {:?}, i = {}", text, i);
+                }
+                let longest = longest_palindrome(&text);
+                assert!(is_palindrome(&text[longest.clone()]));
+                let expected = (0..=n).rev()
+                    .flat_map(|len| (0..=n - len).map(move |s| s..s + len))
+                    .find(|range| is_palindrome(&text[range.clone()]))
+                    .unwrap();
+                assert_eq!(expected, longest, "text = {:?}", text);
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::space::SpaceUsage;
+
+use alloc::vec::Vec;
+use alloc::vec;
+use core::ops::Range;
+
+use rand::Rng;
+use rand::RngCore;
+
+const MOD: u64 = (1 << 61) - 1;
+
+/// バイト列の部分列のハッシュ値を `O(1)` で求めるローリングハッシュ
+///
+/// 法を `2^61 - 1` とする多項式ハッシュを、ランダムに選んだ 2 つの基数で求め、組にした 128 ビットの値をハッシュ値とします。
+/// 接頭辞のハッシュ値を前計算しておき、部分列のハッシュ値の比較で部分列の一致を判定します。
+/// 異なる部分列のハッシュ値が一致する確率は、 1 回の比較あたりおよそ `n / 2^122` 以下です。
+///
+/// 異なるバイト列のハッシュ値を比べる場合は、 [`Self::with_bases()`] で同じ基数を使います。
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use rust_study::string::rolling_hash::RollingHasher;
+/// let hasher = RollingHasher::new(b"abracadabra");
+/// assert!(hasher.eq(0..4, 7..11));
+/// assert!(!hasher.eq(0..4, 1..5));
+/// // "abra" and "acad"
+/// assert_eq!(1, hasher.lcp(0..4, 3..7));
+///
+/// let pattern = RollingHasher::with_bases(b"cad", hasher.bases());
+/// assert_eq!(hasher.hash(4..7), pattern.hash(0..3));
+/// # }
+/// ```
+pub struct RollingHasher {
+    bases: [u64; 2],
+    // the hash values of the prefixes
+    prefixes: [Vec<u64>; 2],
+    // the powers of the bases
+    powers: [Vec<u64>; 2],
+}
+
+impl RollingHasher {
+    /// バイト列 `text` の接頭辞のハッシュ値を、ランダムに選んだ基数で前計算します。
+    #[cfg(feature = "std")]
+    pub fn new(text: &[u8]) -> Self {
+        Self::with_rng(text, &mut rand::thread_rng())
+    }
+
+    /// バイト列 `text` の接頭辞のハッシュ値を、乱数生成器 `rng` で選んだ基数で前計算します。
+    ///
+    /// シードを固定した乱数生成器を渡すと、同じ基数が選ばれます。
+    pub fn with_rng<R: RngCore + ?Sized>(text: &[u8], rng: &mut R) -> Self {
+        Self::with_bases(text, [rng.gen_range(257, MOD), rng.gen_range(257, MOD)])
+    }
+
+    /// バイト列 `text` の接頭辞のハッシュ値を、基数 `bases` で前計算します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of `bases` is not in `[2, 2^61 - 1)`.
+    pub fn with_bases(text: &[u8], bases: [u64; 2]) -> Self {
+        assert!(bases.iter().all(|&base| (2..MOD).contains(&base)), "bases should be in [2, 2^61 - 1)");
+        let mut prefixes = [vec![0; text.len() + 1], vec![0; text.len() + 1]];
+        let mut powers = [vec![1; text.len() + 1], vec![1; text.len() + 1]];
+        for k in 0..2 {
+            for (i, &b) in text.iter().enumerate() {
+                // shifted so that the zero byte does not vanish
+                prefixes[k][i + 1] = add(mul(prefixes[k][i], bases[k]), b as u64 + 1);
+                powers[k][i + 1] = mul(powers[k][i], bases[k]);
+            }
+        }
+        RollingHasher { bases, prefixes, powers }
+    }
+
+    /// 基数を返します。
+    pub fn bases(&self) -> [u64; 2] {
+        self.bases
+    }
+
+    /// 元のバイト列の長さを返します。
+    pub fn len(&self) -> usize {
+        self.prefixes[0].len() - 1
+    }
+
+    /// 元のバイト列が空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 部分列 `range` のハッシュ値を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn hash(&self, range: Range<usize>) -> u128 {
+        self.check(&range);
+        let [h0, h1] = [0, 1].map(|k| {
+            let prefixes = &self.prefixes[k];
+            sub(prefixes[range.end], mul(prefixes[range.start], self.powers[k][range.len()]))
+        });
+        ((h0 as u128) << 64) | h1 as u128
+    }
+
+    /// 部分列 `a` と部分列 `b` が一致する場合に、 `true` を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` or `b` is out of bounds.
+    pub fn eq(&self, a: Range<usize>, b: Range<usize>) -> bool {
+        a.len() == b.len() && self.hash(a) == self.hash(b)
+    }
+
+    /// 部分列 `a` と部分列 `b` の最長共通接頭辞の長さを、二分探索で `O(log n)` で求めます。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` or `b` is out of bounds.
+    pub fn lcp(&self, a: Range<usize>, b: Range<usize>) -> usize {
+        // the common prefix of the length lo matches, and of hi + 1 does not
+        let mut lo = 0;
+        let mut hi = a.len().min(b.len());
+        self.check(&a);
+        self.check(&b);
+        while lo < hi {
+            let mid = (lo + hi).div_ceil(2);
+            if self.eq(a.start..a.start + mid, b.start..b.start + mid) {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        lo
+    }
+
+    fn check(&self, range: &Range<usize>) {
+        assert!(range.start <= range.end && range.end <= self.len(), "range should be in bounds");
+    }
+}
+
+fn add(a: u64, b: u64) -> u64 {
+    let c = a + b;
+    if c >= MOD { c - MOD } else { c }
+}
+
+fn sub(a: u64, b: u64) -> u64 {
+    if a >= b { a - b } else { a + MOD - b }
+}
+
+fn mul(a: u64, b: u64) -> u64 {
+    let c = a as u128 * b as u128;
+    add((c >> 61) as u64, c as u64 & MOD)
+}
+
+impl SpaceUsage for RollingHasher {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("prefixes", &self.prefixes), ("powers", &self.powers)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn same_as_naive() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [1, 2, 256] {
+            let text: Vec<u8> = (0..200).map(|_| rng.gen_range(0, max) as u8).collect();
+            let hasher = RollingHasher::with_rng(&text, &mut rng);
+            assert_eq!(text.len(), hasher.len());
+            for _ in 0..1000 {
+                let s1 = rng.gen_range(0, text.len() + 1);
+                let s2 = rng.gen_range(0, text.len() + 1);
+                let len = rng.gen_range(0, text.len() + 1 - s1.max(s2));
+                let (a, b) = (s1..s1 + len, s2..s2 + rng.gen_range(len, text.len() + 1 - s2));
+                assert_eq!(text[a.clone()] == text[b.clone()], hasher.eq(a.clone(), b.clone()));
+                assert_eq!(text[a.clone()] == text[b.start..b.start + len], hasher.hash(a.clone()) == hasher.hash(b.start..b.start + len));
+                let lcp = text[a.clone()].iter().zip(&text[b.clone()]).take_while(|(x, y)| x == y).count();
+                assert_eq!(lcp, hasher.lcp(a, b));
+            }
+        }
+    }
+
+    #[test]
+    fn with_rng() {
+        let h1 = RollingHasher::with_rng(b"abracadabra", &mut StdRng::seed_from_u64(1));
+        let h2 = RollingHasher::with_rng(b"abracadabra", &mut StdRng::seed_from_u64(1));
+        assert_eq!(h1.bases(), h2.bases());
+        assert_eq!(h1.hash(0..11), h2.hash(0..11));
+        assert!(h1.eq(0..4, 7..11));
+    }
+
+    #[test]
+    fn zero_bytes() {
+        let hasher = RollingHasher::with_rng(b"\0\0a\0a", &mut StdRng::seed_from_u64(0));
+        assert!(!hasher.eq(0..1, 1..3));
+        assert_ne!(hasher.hash(1..3), hasher.hash(2..3));
+        assert!(hasher.eq(1..3, 3..5));
+        assert_eq!(hasher.hash(0..0), hasher.hash(5..5));
+    }
+
+    #[test]
+    #[should_panic(expected = "range should be in bounds")]
+    fn out_of_bounds() {
+        RollingHasher::with_rng(b"abc", &mut StdRng::seed_from_u64(0)).hash(2..4);
+    }
+}
@@ -0,0 +1,209 @@
+use super::bwt::bwt;
+use crate::bits::fid::FID;
+use crate::bits::fid::NaiveFID;
+use crate::bits::wavelet_matrix::U8WaveletMatrix;
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// ランレングス圧縮した FM-index (RLFM-index)
+///
+/// Burrows-Wheeler 変換を同じバイトの連続 (ラン) に分け、ランの先頭のバイトの列をウェーブレット行列で、
+/// ランの開始位置をビットベクトルで保持します。
+/// 使用メモリは元の長さ `n` ではなくランの個数 `r` に比例する部分が大半を占めるため、
+/// 版を重ねた文書やゲノムの集合のような、繰り返しの多いテキストで小さくなります。
+///
+/// 後方探索でパターンの出現回数を数えます。出現位置の列挙 (locate) には対応していないため、
+/// 出現位置が必要な場合は [`FMIndex`](super::fm_index::FMIndex) を使います。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::run_length_fm_index::NaiveRunLengthFMIndex;
+/// let index = NaiveRunLengthFMIndex::new(b"abracadabra abracadabra abracadabra");
+/// assert_eq!(35, index.len());
+/// assert_eq!(6, index.count(b"abra"));
+/// assert_eq!(3, index.count(b"cad"));
+/// assert_eq!(0, index.count(b"abba"));
+/// // far fewer runs than bytes
+/// assert_eq!(8, index.runs());
+/// ```
+pub struct RunLengthFMIndex<T: FID> {
+    n: usize,
+    // the row of the sentinel in the BWT
+    primary: usize,
+    // the byte of each run
+    heads: U8WaveletMatrix<T>,
+    // the start of each run in the BWT
+    starts: T,
+    // the start of each run in the BWT stably sorted by byte
+    sorted_starts: T,
+    // the number of bytes smaller than each byte
+    smaller: [usize; 257],
+    // the number of runs of bytes smaller than each byte
+    smaller_runs: [usize; 256],
+}
+
+impl <T: FID> RunLengthFMIndex<T> {
+    /// バイト列 `text` の索引を構築します。
+    pub fn new(text: &[u8]) -> Self {
+        let n = text.len();
+        let (transformed, primary) = bwt(text);
+        let mut heads = Vec::new();
+        let mut starts = vec![false; n];
+        let mut lengths: Vec<Vec<usize>> = vec![vec![]; 256];
+        for (i, &b) in transformed.iter().enumerate() {
+            if i == 0 || transformed[i - 1] != b {
+                heads.push(b);
+                starts[i] = true;
+                lengths[b as usize].push(0);
+            }
+            *lengths[b as usize].last_mut().unwrap() += 1;
+        }
+
+        let mut smaller = [0; 257];
+        let mut smaller_runs = [0; 256];
+        let mut sorted_starts = vec![false; n];
+        for b in 0..256 {
+            let mut p = smaller[b];
+            for len in &lengths[b] {
+                sorted_starts[p] = true;
+                p += len;
+            }
+            smaller[b + 1] = p;
+            if b + 1 < 256 {
+                smaller_runs[b + 1] = smaller_runs[b] + lengths[b].len();
+            }
+        }
+
+        RunLengthFMIndex {
+            n,
+            primary,
+            heads: U8WaveletMatrix::new(&heads),
+            starts: T::from_bool_vec(&starts),
+            sorted_starts: T::from_bool_vec(&sorted_starts),
+            smaller,
+            smaller_runs,
+        }
+    }
+
+    /// 元のバイト列の長さを返します。
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    /// 元のバイト列が空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Burrows-Wheeler 変換のランの個数を返します。
+    pub fn runs(&self) -> usize {
+        self.heads.len()
+    }
+
+    /// `pattern` が元のバイト列に現れる回数を、重なりを含めて数えます。
+    ///
+    /// `pattern` が空の場合、 `len + 1` を返します。
+    pub fn count(&self, pattern: &[u8]) -> usize {
+        // the range of the rows starting with the suffix of the pattern
+        let mut s = 0;
+        let mut e = self.n + 1;
+        for &b in pattern.iter().rev() {
+            s = self.lf(b, s);
+            e = self.lf(b, e);
+            if s >= e {
+                return 0;
+            }
+        }
+        e - s
+    }
+
+    /// 行 `row` より前にある、末尾が `b` の行の個数を数え、 `b` で始まる行の中での対応する位置を返します (LF 写像) 。
+    fn lf(&self, b: u8, row: usize) -> usize {
+        // the sentinel row ends with no byte, and the first row starts with the sentinel
+        let i = if row > self.primary { row - 1 } else { row };
+        1 + self.smaller[b as usize] + self.occ(b, i)
+    }
+
+    /// Burrows-Wheeler 変換の `[0, i)` の中の `b` の個数を数えます。
+    fn occ(&self, b: u8, i: usize) -> usize {
+        if i == 0 {
+            return 0;
+        }
+        // the run containing i - 1, and the number of the runs of b up to it
+        let run = self.starts.rank1(i) - 1;
+        let k = self.heads.rank(b, run + 1);
+        if k == 0 {
+            return 0;
+        }
+        let first = self.smaller_runs[b as usize];
+        if self.heads.access(run) == b {
+            // the last run of b is cut at i
+            let before = self.sorted_starts.select1(first + k - 1) - self.smaller[b as usize];
+            before + i - self.starts.select1(run)
+        } else {
+            self.sorted_starts.select1(first + k) - self.smaller[b as usize]
+        }
+    }
+}
+
+impl <T: FID + SpaceUsage> SpaceUsage for RunLengthFMIndex<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("heads", &self.heads), ("starts", &self.starts), ("sorted_starts", &self.sorted_starts), ("smaller", &self.smaller), ("smaller_runs", &self.smaller_runs)]
+    }
+}
+
+pub type NaiveRunLengthFMIndex = RunLengthFMIndex<NaiveFID>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn naive_count(text: &[u8], pattern: &[u8]) -> usize {
+        (0..=text.len()).filter(|&i| text[i..].starts_with(pattern)).count()
+    }
+
+    #[test]
+    fn small() {
+        for text in [&b""[..], b"a", b"aaaa", b"banana", b"\x00\xff\x00\xff\x00"] {
+            let index = NaiveRunLengthFMIndex::new(text);
+            assert_eq!(text.len(), index.len());
+            for pattern in [&b""[..], b"a", b"aa", b"an", b"ana", b"nab", b"\x00", b"\xff\x00"] {
+                assert_eq!(naive_count(text, pattern), index.count(pattern), "text = {:?}, pattern = {:?}", text, pattern);
+            }
+        }
+    }
+
+    #[test]
+    fn repetitive() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let base: Vec<u8> = (0..200).map(|_| b"ACGT"[rng.gen_range(0, 4)]).collect();
+        let mut text = Vec::new();
+        for _ in 0..20 {
+            // a copy of the base with a few mutations
+            let mut copy = base.clone();
+            for _ in 0..2 {
+                let i = rng.gen_range(0, copy.len());
+                copy[i] = b"ACGT"[rng.gen_range(0, 4)];
+            }
+            text.extend(copy);
+        }
+        let index = NaiveRunLengthFMIndex::new(&text);
+        assert!(index.runs() < text.len() / 4, "runs = {}", index.runs());
+        for _ in 0..200 {
+            let len = rng.gen_range(1, 12);
+            let pattern: Vec<u8> = if rng.gen() {
+                let s = rng.gen_range(0, text.len() - len);
+                text[s..s + len].to_vec()
+            } else {
+                (0..len).map(|_| b"ACGT"[rng.gen_range(0, 4)]).collect()
+            };
+            assert_eq!(naive_count(&text, &pattern), index.count(&pattern), "pattern = {:?}", pattern);
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 索引を作らずに、1 つのパターンをバイト列から探す照合器
+///
+/// パターンの前処理を構築時に済ませておき、複数のテキストに使い回せます。
+/// 空のパターンは、テキストの `0` から `text.len()` までのすべての位置に現れるとみなします。
+pub trait Matcher {
+    /// 探すパターンを返します。
+    fn pattern(&self) -> &[u8];
+
+    /// `text` の中のパターンの出現位置を、重なりを含めて昇順に返すイテレータを返します。
+    fn find_iter<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = usize> + 'a;
+
+    /// `text` の中のパターンの最初の出現位置を返します。
+    fn find(&self, text: &[u8]) -> Option<usize> {
+        self.find_iter(text).next()
+    }
+
+    /// `text` の中のパターンの出現回数を、重なりを含めて数えます。
+    fn count(&self, text: &[u8]) -> usize {
+        self.find_iter(text).count()
+    }
+}
+
+/// `text` の接頭辞関数 (prefix function) を `O(n)` で求めます。
+///
+/// `k` 番目の要素は、 `text` の長さ `k + 1` の接頭辞の、自身を除く最長の境界 (接頭辞かつ接尾辞) の長さです。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::search::prefix_function;
+/// assert_eq!(vec![0, 1, 0, 1, 2, 3, 4], prefix_function(b"aabaaba"));
+/// ```
+pub fn prefix_function(text: &[u8]) -> Vec<usize> {
+    let mut pi = vec![0; text.len()];
+    let mut j = 0;
+    for i in 1..text.len() {
+        while j > 0 && text[i] != text[j] {
+            j = pi[j - 1];
+        }
+        if text[i] == text[j] {
+            j += 1;
+        }
+        pi[i] = j;
+    }
+    pi
+}
+
+/// `text` の Z 配列を `O(n)` で求めます。
+///
+/// `i` 番目の要素は、 `text` と `text[i..]` の最長共通接頭辞の長さです。 `0` 番目の要素は `text.len()` です。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::search::z_array;
+/// assert_eq!(vec![7, 1, 0, 4, 1, 0, 1], z_array(b"aabaaba"));
+/// ```
+pub fn z_array(text: &[u8]) -> Vec<usize> {
+    let n = text.len();
+    let mut z = vec![0; n];
+    if n == 0 {
+        return z;
+    }
+    z[0] = n;
+    // text[l..r] is the prefix of the text, with the largest r so far
+    let (mut l, mut r) = (0, 0);
+    for i in 1..n {
+        let mut k = if i < r { z[i - l].min(r - i) } else { 0 };
+        while i + k < n && text[k] == text[i + k] {
+            k += 1;
+        }
+        if i + k > r {
+            l = i;
+            r = i + k;
+        }
+        z[i] = k;
+    }
+    z
+}
+
+/// `text` の境界 (自身を除く、接頭辞かつ接尾辞である部分列) の長さを、長い順に返します。空の境界 `0` を含みます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::search::borders;
+/// // "abaab", "ab" and ""
+/// assert_eq!(vec![2, 0], borders(b"abaab"));
+/// assert_eq!(vec![3, 2, 1, 0], borders(b"aaaa"));
+/// ```
+pub fn borders(text: &[u8]) -> Vec<usize> {
+    let pi = prefix_function(text);
+    let mut borders = vec![];
+    let mut len = text.len();
+    while len > 0 {
+        len = pi[len - 1];
+        borders.push(len);
+    }
+    borders
+}
+
+/// `text` の最小の周期、すなわち `text[i] == text[i + p]` がすべての `i` で成り立つ最小の正の `p` を返します。
+///
+/// `text` が空の場合、 `0` を返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::search::period;
+/// assert_eq!(3, period(b"abcabca"));
+/// assert_eq!(5, period(b"abcde"));
+/// ```
+pub fn period(text: &[u8]) -> usize {
+    text.len() - prefix_function(text).last().copied().unwrap_or(0)
+}
+
+/// Knuth-Morris-Pratt 法による照合器
+///
+/// パターンの各接頭辞の最長の境界 (失敗関数) を前処理し、テキストを後戻りせずに 1 度だけ走査します。
+/// 最悪でも `O(n + m)` で、繰り返しの多いパターンやストリームに向きます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::search::KmpMatcher;
+/// use rust_study::string::search::Matcher;
+/// let matcher = KmpMatcher::new(b"abab");
+/// assert_eq!(&[0, 0, 1, 2], matcher.failure());
+/// assert_eq!(vec![0, 2, 7], matcher.find_iter(b"abababxabab").collect::<Vec<_>>());
+/// assert_eq!(None, matcher.find(b"aabba"));
+/// ```
+pub struct KmpMatcher {
+    pattern: Vec<u8>,
+    failure: Vec<usize>,
+}
+
+impl KmpMatcher {
+    /// パターン `pattern` の照合器を構築します。
+    pub fn new(pattern: &[u8]) -> Self {
+        KmpMatcher { pattern: pattern.to_vec(), failure: prefix_function(pattern) }
+    }
+
+    /// 失敗関数、すなわちパターンの [`prefix_function()`] を返します。
+    pub fn failure(&self) -> &[usize] {
+        &self.failure
+    }
+}
+
+impl Matcher for KmpMatcher {
+    fn pattern(&self) -> &[u8] {
+        &self.pattern
+    }
+
+    fn find_iter<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let m = self.pattern.len();
+        let mut i = 0;
+        // the length of the matched prefix of the pattern
+        let mut j = 0;
+        core::iter::from_fn(move || {
+            if m == 0 {
+                if i > text.len() {
+                    return None;
+                }
+                i += 1;
+                return Some(i - 1);
+            }
+            while i < text.len() {
+                let b = text[i];
+                i += 1;
+                while j > 0 && b != self.pattern[j] {
+                    j = self.failure[j - 1];
+                }
+                if b == self.pattern[j] {
+                    j += 1;
+                }
+                if j == m {
+                    j = self.failure[m - 1];
+                    return Some(i - m);
+                }
+            }
+            None
+        })
+    }
+}
+
+/// Z アルゴリズムによる照合器
+///
+/// パターンの Z 配列を前処理し、テキストの各位置とパターンの最長共通接頭辞の長さを `O(n + m)` で求めます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::search::Matcher;
+/// use rust_study::string::search::ZMatcher;
+/// let matcher = ZMatcher::new(b"aba");
+/// assert_eq!(vec![3, 0, 1, 2, 0, 0], matcher.prefix_lengths(b"abaabx").collect::<Vec<_>>());
+/// assert_eq!(vec![0, 2], matcher.find_iter(b"ababa").collect::<Vec<_>>());
+/// ```
+pub struct ZMatcher {
+    pattern: Vec<u8>,
+    z: Vec<usize>,
+}
+
+impl ZMatcher {
+    /// パターン `pattern` の照合器を構築します。
+    pub fn new(pattern: &[u8]) -> Self {
+        ZMatcher { pattern: pattern.to_vec(), z: z_array(pattern) }
+    }
+
+    /// `text` の各位置 `i` について、 `text[i..]` とパターンの最長共通接頭辞の長さを返すイテレータを返します。
+    pub fn prefix_lengths<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let m = self.pattern.len();
+        // text[l..r] is the prefix of the pattern, with the largest r so far
+        let (mut l, mut r) = (0, 0);
+        (0..text.len()).map(move |i| {
+            let mut k = if i < r { self.z[i - l].min(r - i) } else { 0 };
+            while k < m && i + k < text.len() && self.pattern[k] == text[i + k] {
+                k += 1;
+            }
+            if i + k > r {
+                l = i;
+                r = i + k;
+            }
+            k
+        })
+    }
+}
+
+impl Matcher for ZMatcher {
+    fn pattern(&self) -> &[u8] {
+        &self.pattern
+    }
+
+    fn find_iter<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let m = self.pattern.len();
+        // the empty pattern also matches at the end
+        let end = if m == 0 { Some(text.len()) } else { None };
+        self.prefix_lengths(text)
+            .enumerate()
+            .filter(move |&(_, k)| k == m)
+            .map(|(i, _)| i)
+            .chain(end)
+    }
+}
+
+/// Boyer-Moore-Horspool 法による照合器
+///
+/// パターンをテキストの窓に末尾から照合し、窓の末尾のバイトに応じて窓を読み飛ばします。
+/// 最悪では `O(nm)` ですが、バイトの種類が多くパターンが長い場合、平均では `O(n / m)` 程度のバイトしか調べません。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::search::HorspoolMatcher;
+/// use rust_study::string::search::Matcher;
+/// let matcher = HorspoolMatcher::new(b"needle");
+/// assert_eq!(Some(14), matcher.find(b"haystack with needles"));
+/// assert_eq!(0, matcher.count(b"haystack"));
+/// ```
+pub struct HorspoolMatcher {
+    pattern: Vec<u8>,
+    // the shift of the window by its last byte
+    shift: [usize; 256],
+}
+
+impl HorspoolMatcher {
+    /// パターン `pattern` の照合器を構築します。
+    pub fn new(pattern: &[u8]) -> Self {
+        let m = pattern.len();
+        let mut shift = [m.max(1); 256];
+        for (i, &b) in pattern.iter().enumerate().take(m.saturating_sub(1)) {
+            shift[b as usize] = m - 1 - i;
+        }
+        HorspoolMatcher { pattern: pattern.to_vec(), shift }
+    }
+}
+
+impl Matcher for HorspoolMatcher {
+    fn pattern(&self) -> &[u8] {
+        &self.pattern
+    }
+
+    fn find_iter<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let m = self.pattern.len();
+        // the start of the window
+        let mut pos = 0;
+        core::iter::from_fn(move || {
+            while pos + m <= text.len() {
+                let start = pos;
+                if m == 0 {
+                    pos += 1;
+                    return Some(start);
+                }
+                pos += self.shift[text[start + m - 1] as usize];
+                if text[start..start + m].iter().rev().eq(self.pattern.iter().rev()) {
+                    return Some(start);
+                }
+            }
+            None
+        })
+    }
+}
+
+impl SpaceUsage for KmpMatcher {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("pattern", &self.pattern), ("failure", &self.failure)]
+    }
+}
+
+impl SpaceUsage for ZMatcher {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("pattern", &self.pattern), ("z", &self.z)]
+    }
+}
+
+impl SpaceUsage for HorspoolMatcher {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("pattern", &self.pattern), ("shift", &self.shift)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn naive_find_iter(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+        (0..=text.len()).filter(|&i| text[i..].starts_with(pattern)).collect()
+    }
+
+    fn same_as_naive<M: Matcher>(new: fn(&[u8]) -> M) {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [1, 2, 256] {
+            for _ in 0..300 {
+                let text: Vec<u8> = (0..rng.gen_range(0, 100)).map(|_| rng.gen_range(0, max) as u8).collect();
+                let pattern: Vec<u8> = (0..rng.gen_range(0, 5)).map(|_| rng.gen_range(0, max) as u8).collect();
+                let matcher = new(&pattern);
+                assert_eq!(&pattern[..], matcher.pattern());
+                let expected = naive_find_iter(&text, &pattern);
+                assert_eq!(expected, matcher.find_iter(&text).collect::<Vec<_>>(), "text = {:?}, pattern = {:?}", text, pattern);
+                assert_eq!(expected.first().copied(), matcher.find(&text));
+                assert_eq!(expected.len(), matcher.count(&text));
+            }
+        }
+    }
+
+    #[test]
+    fn kmp() {
+        same_as_naive(KmpMatcher::new);
+        assert_eq!(&[0, 1, 0, 1, 2, 3, 4], KmpMatcher::new(b"aabaaba").failure());
+    }
+
+    #[test]
+    fn z() {
+        same_as_naive(ZMatcher::new);
+    }
+
+    #[test]
+    fn arrays() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..300 {
+            let text: Vec<u8> = (0..rng.gen_range(0, 30)).map(|_| rng.gen_range(0, 2) as u8).collect();
+            let n = text.len();
+            let is_border = |len: usize| text[..len] == text[n - len..];
+            let pi = prefix_function(&text);
+            for k in 0..n {
+                let expected = (0..=k).rev().find(|&len| text[..len] == text[k + 1 - len..=k]).unwrap();
+                assert_eq!(expected, pi[k]);
+            }
+            let z = z_array(&text);
+            for i in 0..n {
+                assert_eq!(text.iter().zip(&text[i..]).take_while(|(a, b)| a == b).count(), z[i]);
+            }
+            let expected: Vec<usize> = (0..n).rev().filter(|&len| is_border(len)).collect();
+            assert_eq!(expected, borders(&text));
+            let expected = (1..=n).find(|&p| (0..n - p).all(|i| text[i] == text[i + p])).unwrap_or(0);
+            assert_eq!(expected, period(&text));
+        }
+    }
+
+    #[test]
+    fn horspool() {
+        same_as_naive(HorspoolMatcher::new);
+    }
+}
@@ -0,0 +1,23 @@
+// Serialization of the string indexes as the bytes of their sections.
+
+use super::fm_index::FMIndex;
+use crate::bits::fid::FID;
+use crate::serialize;
+use crate::serialize::Serializable;
+
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+impl <T: FID + Serializable> Serialize for FMIndex<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize::serialize_section(self, serializer)
+    }
+}
+
+impl <'de, T: FID + Serializable> Deserialize<'de> for FMIndex<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serialize::deserialize_section(deserializer)
+    }
+}
@@ -0,0 +1,198 @@
+use crate::bits::fid::FID;
+use crate::bits::fid::NaiveFID;
+use crate::bits::wavelet_matrix::U8WaveletMatrix;
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// バイト列の区間ごとの記号の統計
+///
+/// バイト列をウェーブレット行列で保持し、任意の区間の記号の出現回数を `rank` で、
+/// 出現回数の多い記号を `topk` で求めます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::stats::NaiveSymbolStats;
+/// let stats = NaiveSymbolStats::new(b"AAAACGCGTT");
+/// assert_eq!(2, stats.frequency(b'C', 0..10));
+/// assert_eq!(vec![(b'A', 4), (b'C', 2), (b'G', 2), (b'T', 2)], stats.profile(0..10));
+/// let windows: Vec<u8> = stats.most_frequent_windows(4).map(|(b, _)| b).collect();
+/// assert_eq!(b"AAACCGT", &windows[..]);
+/// ```
+pub struct SymbolStats<T: FID> {
+    matrix: U8WaveletMatrix<T>,
+}
+
+impl <T: FID> SymbolStats<T> {
+    /// バイト列 `text` の統計を構築します。
+    pub fn new(text: &[u8]) -> Self {
+        SymbolStats { matrix: U8WaveletMatrix::new(&text.to_vec()) }
+    }
+
+    /// 元のバイト列の長さを返します。
+    pub fn len(&self) -> usize {
+        self.matrix.len()
+    }
+
+    /// 元のバイト列が空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 区間 `range` の中の `b` の個数を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn frequency(&self, b: u8, range: Range<usize>) -> usize {
+        self.check(&range);
+        self.matrix.rank(b, range.end) - self.matrix.rank(b, range.start)
+    }
+
+    /// 区間 `range` に現れる記号とその個数の組を、個数の多い順に返します。
+    ///
+    /// 個数が同じ記号は、小さい順に返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn profile(&self, range: Range<usize>) -> Vec<(u8, usize)> {
+        self.check(&range);
+        self.matrix.topk(range.start, range.end, 256)
+    }
+
+    /// 区間 `range` で最も多く現れる記号とその個数の組を返します。
+    ///
+    /// 最も多いものが複数ある場合、一番小さい記号を返します。 `range` が空の場合、 `None` を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn most_frequent(&self, range: Range<usize>) -> Option<(u8, usize)> {
+        self.check(&range);
+        self.matrix.topk(range.start, range.end, 1).pop()
+    }
+
+    /// 区間 `range` の記号の経験エントロピーを、ビット単位で返します。
+    ///
+    /// `range` が空の場合、 `0` を返します。
+    /// 対数の計算に `std` を使うため、 `std` フィーチャーが必要です。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_study::string::stats::NaiveSymbolStats;
+    /// let stats = NaiveSymbolStats::new(b"AAAACGCGTT");
+    /// assert_eq!(0.0, stats.entropy(0..4));
+    /// assert_eq!(1.0, stats.entropy(4..8));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn entropy(&self, range: Range<usize>) -> f64 {
+        let n = range.len() as f64;
+        self.profile(range)
+            .into_iter()
+            .map(|(_, count)| {
+                let p = count as f64 / n;
+                -p * p.log2()
+            })
+            .sum::<f64>()
+            .max(0.0)
+    }
+
+    /// 幅 `width` の窓を 1 つずつずらしながら、各窓で最も多く現れる記号とその個数の組を返します。
+    ///
+    /// 窓は `0..width` から `len - width..len` までです。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is zero.
+    pub fn most_frequent_windows(&self, width: usize) -> impl Iterator<Item = (u8, usize)> + '_ {
+        self.windows(width).map(move |range| self.most_frequent(range).unwrap())
+    }
+
+    /// 幅 `width` の窓を 1 つずつずらしながら、各窓の記号の経験エントロピーを返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is zero.
+    #[cfg(feature = "std")]
+    pub fn entropy_windows(&self, width: usize) -> impl Iterator<Item = f64> + '_ {
+        self.windows(width).map(move |range| self.entropy(range))
+    }
+
+    fn windows(&self, width: usize) -> impl Iterator<Item = Range<usize>> {
+        assert!(width > 0, "width should be positive");
+        (0..(self.len() + 1).saturating_sub(width)).map(move |s| s..s + width)
+    }
+
+    fn check(&self, range: &Range<usize>) {
+        assert!(range.start <= range.end && range.end <= self.len(), "range should be in bounds");
+    }
+}
+
+impl <T: FID + SpaceUsage> SpaceUsage for SymbolStats<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("matrix", &self.matrix)]
+    }
+}
+
+pub type NaiveSymbolStats = SymbolStats<NaiveFID>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn same_as_naive() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [1, 4, 256] {
+            let text: Vec<u8> = (0..200).map(|_| rng.gen_range(0, max) as u8).collect();
+            let stats = NaiveSymbolStats::new(&text);
+            assert_eq!(text.len(), stats.len());
+            for _ in 0..100 {
+                let s = rng.gen_range(0, text.len() + 1);
+                let e = rng.gen_range(s, text.len() + 1);
+                let mut counts = [0; 256];
+                for &b in &text[s..e] {
+                    counts[b as usize] += 1;
+                }
+                let b = rng.gen_range(0, max) as u8;
+                assert_eq!(counts[b as usize], stats.frequency(b, s..e));
+                let mut expected: Vec<(u8, usize)> = (0..256).filter(|&b| counts[b] > 0).map(|b| (b as u8, counts[b])).collect();
+                expected.sort_by_key(|&(b, count)| (std::cmp::Reverse(count), b));
+                assert_eq!(expected, stats.profile(s..e));
+                assert_eq!(expected.first().copied(), stats.most_frequent(s..e));
+                #[cfg(feature = "std")]
+                {
+                    let entropy: f64 = expected.iter().map(|&(_, c)| -(c as f64 / (e - s) as f64) * (c as f64 / (e - s) as f64).log2()).sum();
+                    assert!((entropy - stats.entropy(s..e)).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn windows() {
+        let stats = NaiveSymbolStats::new(b"aabbb");
+        assert_eq!(vec![(b'a', 2), (b'b', 2), (b'b', 3)], stats.most_frequent_windows(3).collect::<Vec<_>>());
+        #[cfg(feature = "std")]
+        assert_eq!(vec![0.0, 1.0, 0.0, 0.0], stats.entropy_windows(2).collect::<Vec<_>>());
+        assert_eq!(0, stats.most_frequent_windows(6).count());
+    }
+
+    #[test]
+    #[should_panic(expected = "range should be in bounds")]
+    fn out_of_bounds() {
+        NaiveSymbolStats::new(b"abc").frequency(b'a', 2..4);
+    }
+}
@@ -0,0 +1,665 @@
+#[cfg(feature = "std")]
+mod external;
+#[cfg(feature = "std")]
+pub use external::write_suffix_array;
+
+use crate::bits::succinct_rmq::NaiveSuccinctRMQ;
+use crate::error::Error;
+#[cfg(feature = "parallel")]
+use crate::parallel::Chunking;
+use crate::progress;
+use crate::progress::NoProgress;
+use crate::progress::Phase;
+use crate::progress::Progress;
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::mem;
+use core::ops::Range;
+
+/// バイト列 `text` の接尾辞配列を、 SA-IS (induced sorting) で `O(n)` で構築します。
+///
+/// 返り値の `k` 番目の要素は、辞書順で `k` 番目に小さい接尾辞の開始位置です。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::suffix_array::suffix_array;
+/// // a, ana, anana, banana, na, nana
+/// assert_eq!(vec![5, 3, 1, 0, 4, 2], suffix_array(b"banana"));
+/// ```
+pub fn suffix_array(text: &[u8]) -> Vec<usize> {
+    let s: Vec<usize> = text.iter().map(|&b| b as usize).collect();
+    sa_is(&s, u8::MAX as usize)
+}
+
+/// [`suffix_array()`] と同じ接尾辞配列を、接頭辞の長さを倍にしながら並列ソートする prefix doubling で構築します。
+///
+/// 計算量は `O(n log^2 n)` ですが、各段の整列と順位の付け直しを並列に処理します。
+/// 順位の付け直しは `chunking` の大きさのチャンクごとにタスクに分けます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::parallel::Chunking;
+/// use rust_study::string::suffix_array::par_suffix_array;
+/// assert_eq!(vec![5, 3, 1, 0, 4, 2], par_suffix_array(b"banana", Chunking::default()));
+/// ```
+#[cfg(feature = "parallel")]
+pub fn par_suffix_array(text: &[u8], chunking: Chunking) -> Vec<usize> {
+    use rayon::prelude::*;
+
+    let n = text.len();
+    let mut sa: Vec<usize> = (0..n).collect();
+    // the ranks start from 1, and 0 is past the end of the text
+    let mut rank: Vec<usize> = text.iter().map(|&b| b as usize + 1).collect();
+    let mut k = 1;
+    loop {
+        let key = |i: usize| (rank[i], rank.get(i + k).copied().unwrap_or(0));
+        sa.par_sort_unstable_by_key(|&i| key(i));
+        // whether each suffix differs from the previous one in the first 2k bytes
+        let starts: Vec<bool> = (0..n).into_par_iter()
+            .with_min_len(chunking.size())
+            .map(|j| j == 0 || key(sa[j - 1]) != key(sa[j]))
+            .collect();
+        let mut next = vec![0; n];
+        let mut r = 0;
+        for (&i, &start) in sa.iter().zip(&starts) {
+            r += start as usize;
+            next[i] = r;
+        }
+        rank = next;
+        if r == n {
+            return sa;
+        }
+        k *= 2;
+    }
+}
+
+/// 整数列 `text` の接尾辞配列を、 SA-IS で構築します。
+///
+/// トークン ID の列のような、バイトに収まらない記号の列に使います。
+/// 要素の最大値が長さ以上の場合、値の順序を保って `0` からの連番に付け替えるため `O(n log n)` になります。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::suffix_array::int_suffix_array;
+/// // the tokens of "to be or not to be"
+/// let tokens = [7, 1, 5, 4, 7, 1];
+/// // be, be or..., not..., or..., to be, to be or...
+/// assert_eq!(vec![5, 1, 3, 2, 4, 0], int_suffix_array(&tokens));
+/// assert_eq!(vec![1, 0], int_suffix_array(&[u32::MAX, 0]));
+/// ```
+pub fn int_suffix_array(text: &[u32]) -> Vec<usize> {
+    let upper = text.iter().copied().max().unwrap_or(0) as usize;
+    if upper < text.len() {
+        let s: Vec<usize> = text.iter().map(|&c| c as usize).collect();
+        return sa_is(&s, upper);
+    }
+    let mut values = text.to_vec();
+    values.sort_unstable();
+    values.dedup();
+    let s: Vec<usize> = text.iter().map(|c| values.binary_search(c).unwrap()).collect();
+    sa_is(&s, values.len().saturating_sub(1))
+}
+
+/// 各要素が `upper` 以下の列 `s` の接尾辞配列を、 SA-IS で `O(n + upper)` で構築します。
+///
+/// LMS 部分文字列に名前を付けて縮約した列に対して、再帰的に自身を呼び出します。
+///
+/// # Panics
+///
+/// Panics if any element of `s` is greater than `upper`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::suffix_array::sa_is;
+/// assert_eq!(vec![3, 1, 0, 2], sa_is(&[1, 0, 2, 0], 2));
+/// ```
+pub fn sa_is(s: &[usize], upper: usize) -> Vec<usize> {
+    assert!(s.iter().all(|&c| c <= upper), "elements should be at most upper");
+    let n = s.len();
+    match n {
+        0 => return vec![],
+        1 => return vec![0],
+        2 => return if s[0] < s[1] { vec![0, 1] } else { vec![1, 0] },
+        _ => {}
+    }
+    // whether the suffix is smaller than the next suffix (S-type), or larger (L-type)
+    let mut ls = vec![false; n];
+    for i in (0..n - 1).rev() {
+        ls[i] = if s[i] == s[i + 1] { ls[i + 1] } else { s[i] < s[i + 1] };
+    }
+    // the start of the L-type and S-type buckets of each char
+    let mut sum_l = vec![0; upper + 1];
+    let mut sum_s = vec![0; upper + 1];
+    for i in 0..n {
+        if ls[i] {
+            sum_l[s[i] + 1] += 1;
+        } else {
+            sum_s[s[i]] += 1;
+        }
+    }
+    for c in 0..=upper {
+        sum_s[c] += sum_l[c];
+        if c < upper {
+            sum_l[c + 1] += sum_s[c];
+        }
+    }
+
+    // the leftmost S-type positions
+    let lms: Vec<usize> = (1..n).filter(|&i| !ls[i - 1] && ls[i]).collect();
+    let mut lms_index = vec![usize::MAX; n];
+    for (k, &i) in lms.iter().enumerate() {
+        lms_index[i] = k;
+    }
+    let mut sa = vec![usize::MAX; n];
+    induce(s, &ls, &sum_l, &sum_s, &lms, &mut sa);
+
+    let m = lms.len();
+    if m > 0 {
+        // name the LMS substrings in sorted order, and sort them recursively if some share the name
+        let sorted_lms: Vec<usize> = sa.iter().copied().filter(|&i| lms_index[i] != usize::MAX).collect();
+        let mut reduced = vec![0; m];
+        let mut name = 0;
+        for k in 1..m {
+            let (mut l, mut r) = (sorted_lms[k - 1], sorted_lms[k]);
+            let end_l = lms.get(lms_index[l] + 1).copied().unwrap_or(n);
+            let end_r = lms.get(lms_index[r] + 1).copied().unwrap_or(n);
+            let mut same = end_l - l == end_r - r;
+            if same {
+                while l < end_l && s[l] == s[r] {
+                    l += 1;
+                    r += 1;
+                }
+                same = l < n && r < n && s[l] == s[r];
+            }
+            if !same {
+                name += 1;
+            }
+            reduced[lms_index[sorted_lms[k]]] = name;
+        }
+        let reduced_sa = sa_is(&reduced, name);
+        let sorted_lms: Vec<usize> = reduced_sa.into_iter().map(|k| lms[k]).collect();
+        induce(s, &ls, &sum_l, &sum_s, &sorted_lms, &mut sa);
+    }
+    sa
+}
+
+/// ソートされた LMS の位置 `lms` から、 L 型と S 型の接尾辞を順に誘導してソートします。
+fn induce(s: &[usize], ls: &[bool], sum_l: &[usize], sum_s: &[usize], lms: &[usize], sa: &mut [usize]) {
+    let n = s.len();
+    sa.fill(usize::MAX);
+    let mut buf = sum_s.to_vec();
+    for &i in lms {
+        sa[buf[s[i]]] = i;
+        buf[s[i]] += 1;
+    }
+    buf.copy_from_slice(sum_l);
+    sa[buf[s[n - 1]]] = n - 1;
+    buf[s[n - 1]] += 1;
+    for k in 0..n {
+        let i = sa[k];
+        if i != usize::MAX && i > 0 && !ls[i - 1] {
+            sa[buf[s[i - 1]]] = i - 1;
+            buf[s[i - 1]] += 1;
+        }
+    }
+    buf.copy_from_slice(sum_l);
+    for k in (0..n).rev() {
+        let i = sa[k];
+        if i != usize::MAX && i > 0 && ls[i - 1] {
+            buf[s[i - 1] + 1] -= 1;
+            sa[buf[s[i - 1] + 1]] = i - 1;
+        }
+    }
+}
+
+/// バイト列 `text` とその接尾辞配列 `sa` から、 LCP 配列を Kasai のアルゴリズムで `O(n)` で構築します。
+///
+/// 返り値の `k` 番目の要素は、接尾辞 `sa[k - 1]` と `sa[k]` の最長共通接頭辞の長さです。 `0` 番目の要素は `0` です。
+///
+/// # Panics
+///
+/// Panics if `sa` is not a permutation of `0..text.len()`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::suffix_array::lcp_array;
+/// use rust_study::string::suffix_array::suffix_array;
+/// let text = b"banana";
+/// let sa = suffix_array(text);
+/// // a, ana, anana, banana, na, nana
+/// assert_eq!(vec![0, 1, 3, 0, 0, 2], lcp_array(text, &sa));
+/// ```
+pub fn lcp_array(text: &[u8], sa: &[usize]) -> Vec<usize> {
+    kasai(text, sa)
+}
+
+/// 列 `s` とその接尾辞配列 `sa` から、 LCP 配列を構築します。
+pub(super) fn kasai<T: PartialEq>(text: &[T], sa: &[usize]) -> Vec<usize> {
+    kasai_with_progress(text, sa, &mut NoProgress).unwrap_or_else(|e| panic!("{}", e))
+}
+
+// kasai() reporting Phase::ComputeLcp by the positions
+fn kasai_with_progress<T: PartialEq, P: Progress + ?Sized>(text: &[T], sa: &[usize], progress: &mut P) -> Result<Vec<usize>, Error> {
+    assert_eq!(text.len(), sa.len(), "sa should have the same length as text");
+    kasai_with_rank(text, sa, &inverse(sa), progress)
+}
+
+// kasai_with_progress() with the rank of each suffix given
+fn kasai_with_rank<T: PartialEq, P: Progress + ?Sized>(text: &[T], sa: &[usize], rank: &[usize], progress: &mut P) -> Result<Vec<usize>, Error> {
+    let n = text.len();
+    let mut lcp = vec![0; n];
+    let mut h: usize = 0;
+    // the lcp with the previous suffix shrinks at most one as the start moves right
+    progress::checkpoint(progress, Phase::ComputeLcp, 0, n)?;
+    for i in 0..n {
+        if i > 0 && i % progress::STEP == 0 {
+            progress::checkpoint(progress, Phase::ComputeLcp, i, n)?;
+        }
+        if rank[i] == 0 {
+            h = 0;
+            continue;
+        }
+        let j = sa[rank[i] - 1];
+        while i + h < n && j + h < n && text[i + h] == text[j + h] {
+            h += 1;
+        }
+        lcp[rank[i]] = h;
+        h = h.saturating_sub(1);
+    }
+    progress::checkpoint(progress, Phase::ComputeLcp, n, n)?;
+    Ok(lcp)
+}
+
+// the number of the keys of the first two bytes, where a suffix of the single byte c has the key of c and no next byte
+const BUCKETS: usize = 256 * 257;
+
+// the suffix array and the rank of each suffix by prefix doubling in O(n log^2 n),
+// starting from the first two bytes sorted with a counting sort
+fn doubling_suffix_array(text: &[u8]) -> (Vec<usize>, Vec<usize>) {
+    let n = text.len();
+    let key = |i: usize| text[i] as usize * 257 + text.get(i + 1).map_or(0, |&b| b as usize + 1);
+    let mut bucket = vec![0; BUCKETS];
+    for i in 0..n {
+        bucket[key(i)] += 1;
+    }
+    let mut sum = 0;
+    for start in bucket.iter_mut() {
+        let count = *start;
+        *start = sum;
+        sum += count;
+    }
+    let mut sa = vec![0; n];
+    for i in 0..n {
+        sa[bucket[key(i)]] = i;
+        bucket[key(i)] += 1;
+    }
+    // the rank of a suffix is the start of its group, the suffixes sharing the first k bytes
+    let mut rank = vec![0; n];
+    let mut start = 0;
+    for &end in bucket.iter() {
+        for &i in &sa[start..end] {
+            rank[i] = start;
+        }
+        start = end;
+    }
+    drop(bucket);
+
+    let mut next = vec![0; n];
+    let mut k = 2;
+    loop {
+        let mut sorted = true;
+        let mut start = 0;
+        while start < n {
+            let end = (start + 1..n).find(|&j| rank[sa[j]] != start).unwrap_or(n);
+            if end - start == 1 {
+                next[sa[start]] = start;
+            } else {
+                sorted = false;
+                // the group is sorted by the next k bytes, where a suffix ending there comes first
+                let second = |i: usize| rank.get(i + k).map_or(0, |&r| r + 1);
+                sa[start..end].sort_unstable_by_key(|&i| second(i));
+                let mut group = start;
+                for j in start..end {
+                    if j > start && second(sa[j - 1]) != second(sa[j]) {
+                        group = j;
+                    }
+                    next[sa[j]] = group;
+                }
+            }
+            start = end;
+        }
+        if sorted {
+            return (sa, rank);
+        }
+        mem::swap(&mut rank, &mut next);
+        k *= 2;
+    }
+}
+
+// returns the rank of each suffix
+fn inverse(sa: &[usize]) -> Vec<usize> {
+    let mut rank = vec![usize::MAX; sa.len()];
+    for (k, &i) in sa.iter().enumerate() {
+        assert!(rank[i] == usize::MAX, "sa should be a permutation");
+        rank[i] = k;
+    }
+    rank
+}
+
+/// 接尾辞配列と LCP 配列、 LCP 配列の RMQ の組
+///
+/// 任意の 2 つの接尾辞の最長共通接頭辞の長さを、 LCP 配列の区間の最小値として求めます。
+/// RMQ には [`NaiveSuccinctRMQ`] を使います。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::suffix_array::SuffixArray;
+/// let sa = SuffixArray::new(b"mississippi");
+/// assert_eq!(&[10, 7, 4, 1, 0, 9, 8, 6, 3, 5, 2], sa.as_slice());
+/// // "issippi" and "ississippi"
+/// assert_eq!(4, sa.lcp(4, 1));
+/// // "ssissippi" and "sissippi"
+/// assert_eq!(1, sa.lcp(2, 3));
+/// assert_eq!(Some(1..5), sa.longest_repeated_substring());
+/// ```
+pub struct SuffixArray {
+    sa: Vec<usize>,
+    rank: Vec<usize>,
+    lcp: Vec<usize>,
+    rmq: NaiveSuccinctRMQ,
+}
+
+impl SuffixArray {
+    /// バイト列 `text` の接尾辞配列と LCP 配列を構築します。
+    pub fn new(text: &[u8]) -> Self {
+        Self::with_progress(text, &mut NoProgress)
+    }
+
+    /// [`Self::new()`] と同じく構築し、 [`Phase::SortSuffixes`] と [`Phase::ComputeLcp`] の進み具合を `progress` に通知します。
+    ///
+    /// SA-IS は再帰的に整列するため、接尾辞の整列は始めと終わりにだけ通知します。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_study::progress::Phase;
+    /// use rust_study::string::suffix_array::SuffixArray;
+    /// let mut phases = vec![];
+    /// let sa = SuffixArray::with_progress(b"banana", &mut |phase: Phase, done: usize, total: usize| {
+    ///     if done == total {
+    ///         phases.push(phase);
+    ///     }
+    /// });
+    /// assert_eq!(&[5, 3, 1, 0, 4, 2], sa.as_slice());
+    /// assert_eq!(vec![Phase::SortSuffixes, Phase::ComputeLcp], phases);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `progress` cancels the construction.
+    pub fn with_progress<P: Progress + ?Sized>(text: &[u8], progress: &mut P) -> Self {
+        Self::try_with_progress(text, progress).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [`Self::with_progress()`] と同じですが、 `progress` が中断した場合、パニックせずに [`Error::Cancelled`] を返します。
+    ///
+    /// 中断は通知のたびに確かめるため、接尾辞の整列の途中では中断しません。
+    pub fn try_with_progress<P: Progress + ?Sized>(text: &[u8], progress: &mut P) -> Result<Self, Error> {
+        progress::checkpoint(progress, Phase::SortSuffixes, 0, text.len())?;
+        let sa = suffix_array(text);
+        progress::checkpoint(progress, Phase::SortSuffixes, text.len(), text.len())?;
+        let lcp = kasai_with_progress(text, &sa, progress)?;
+        let rank = inverse(&sa);
+        let rmq = NaiveSuccinctRMQ::new(&lcp);
+        Ok(SuffixArray { sa, rank, lcp, rmq })
+    }
+
+    /// [`Self::new()`] と同じく、作業領域を含めておよそ `bytes` バイトのメモリで構築します。
+    ///
+    /// 通常の構築は、構築する接尾辞配列と順位、 LCP 配列の 3 ワードに加えて、 SA-IS と LCP 配列の構築の作業領域におよそ 5 ワードを位置ごとに使います。
+    /// その見積もりが `bytes` を超える場合、接尾辞を先頭の 2 バイトで数え上げソートしてから、比べる長さを倍々に伸ばして整列し (prefix doubling) 、
+    /// 作業領域を位置ごとに 1 ワードと 2 バイト、それにバケットの個数分に抑えます。整列は繰り返しの多いテキストでも `O(n log^2 n)` です。
+    ///
+    /// 省メモリの構築でも `bytes` に収まらない場合、構築せずに [`Error::MemoryLimit`] を返します。
+    /// 接尾辞配列だけが必要な場合、 [`write_suffix_array()`] でファイルに書き出しながら構築できます。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_study::error::Error;
+    /// use rust_study::string::suffix_array::SuffixArray;
+    /// let text = b"mississippi".repeat(10000);
+    /// let sa = SuffixArray::with_memory_limit(&text, 5 << 20).unwrap();
+    /// assert_eq!(SuffixArray::new(&text).as_slice(), sa.as_slice());
+    /// assert!(matches!(SuffixArray::with_memory_limit(&text, 1 << 20), Err(Error::MemoryLimit { .. })));
+    /// ```
+    pub fn with_memory_limit(text: &[u8], bytes: usize) -> Result<Self, Error> {
+        let n = text.len();
+        let word = mem::size_of::<usize>();
+        if n.saturating_mul(8 * word) <= bytes {
+            return Ok(Self::new(text));
+        }
+        // the suffix array, the ranks and the LCP array, the stack and the bits building the RMQ, and the buckets
+        let required = n.saturating_mul(4 * word + 2).saturating_add(BUCKETS * word);
+        if required > bytes {
+            return Err(Error::MemoryLimit { required, limit: bytes });
+        }
+        let (sa, rank) = doubling_suffix_array(text);
+        let lcp = kasai_with_rank(text, &sa, &rank, &mut NoProgress)?;
+        let rmq = NaiveSuccinctRMQ::new(&lcp);
+        Ok(SuffixArray { sa, rank, lcp, rmq })
+    }
+
+    /// 元のバイト列の長さを返します。
+    pub fn len(&self) -> usize {
+        self.sa.len()
+    }
+
+    /// 元のバイト列が空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.sa.is_empty()
+    }
+
+    /// 接尾辞配列を参照します。
+    pub fn as_slice(&self) -> &[usize] {
+        &self.sa
+    }
+
+    /// LCP 配列を参照します。 [`lcp_array()`] を参照してください。
+    pub fn lcp_array(&self) -> &[usize] {
+        &self.lcp
+    }
+
+    /// 位置 `i` から始まる接尾辞が、辞書順で何番目に小さいかを返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= len`.
+    pub fn rank(&self, i: usize) -> usize {
+        self.rank[i]
+    }
+
+    /// 位置 `i` と位置 `j` から始まる接尾辞の、最長共通接頭辞の長さを返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= len` or `j >= len`.
+    pub fn lcp(&self, i: usize, j: usize) -> usize {
+        if i == j {
+            assert!(i < self.len());
+            return self.len() - i;
+        }
+        let (a, b) = if self.rank[i] < self.rank[j] { (self.rank[i], self.rank[j]) } else { (self.rank[j], self.rank[i]) };
+        self.lcp[self.rmq.rmq(a + 1, b + 1)]
+    }
+
+    /// 2 回以上現れる最長の部分列の、最初に現れる位置の範囲を返します。
+    ///
+    /// 最長のものが複数ある場合、辞書順で最小のものを返します。2 回以上現れる部分列が空列だけの場合、 `None` を返します。
+    pub fn longest_repeated_substring(&self) -> Option<Range<usize>> {
+        let k = (1..self.len()).max_by_key(|&k| (self.lcp[k], Reverse(k)))?;
+        let len = self.lcp[k];
+        if len == 0 {
+            return None;
+        }
+        let start = (k - 1..self.len()).take_while(|&l| l == k - 1 || self.lcp[l] >= len).map(|l| self.sa[l]).min().unwrap();
+        Some(start..start + len)
+    }
+}
+
+impl SpaceUsage for SuffixArray {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("sa", &self.sa), ("rank", &self.rank), ("lcp", &self.lcp), ("rmq", &self.rmq)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::Cancellable;
+    use core::cell::Cell;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn naive_suffix_array<T: Ord>(text: &[T]) -> Vec<usize> {
+        let mut sa: Vec<usize> = (0..text.len()).collect();
+        sa.sort_by_key(|&i| &text[i..]);
+        sa
+    }
+
+    fn naive_lcp(text: &[u8], i: usize, j: usize) -> usize {
+        text[i..].iter().zip(&text[j..]).take_while(|(a, b)| a == b).count()
+    }
+
+    #[test]
+    fn small() {
+        for text in [&b""[..], b"a", b"ab", b"ba", b"aaaa", b"abab", b"banana", b"mississippi", b"\xff\x00\xff\x00"] {
+            assert_eq!(naive_suffix_array(text), suffix_array(text), "text = {:?}", text);
+        }
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [2, 4, 256] {
+            for len in [10, 100, 3000] {
+                let text: Vec<u8> = (0..len).map(|_| rng.gen_range(0, max) as u8).collect();
+                let sa = SuffixArray::new(&text);
+                assert_eq!(naive_suffix_array(&text), sa.as_slice());
+                for k in 1..len {
+                    assert_eq!(naive_lcp(&text, sa.as_slice()[k - 1], sa.as_slice()[k]), sa.lcp_array()[k]);
+                }
+                for _ in 0..100 {
+                    let i = rng.gen_range(0, len);
+                    let j = rng.gen_range(0, len);
+                    assert_eq!(naive_lcp(&text, i, j), sa.lcp(i, j), "i = {}, j = {}", i, j);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn par_suffix_array() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for text in [&b""[..], b"a", b"aaaa", b"banana", b"mississippi"] {
+            assert_eq!(suffix_array(text), super::par_suffix_array(text, Chunking::new(1)));
+        }
+        for max in [2, 256] {
+            let text: Vec<u8> = (0..3000).map(|_| rng.gen_range(0, max) as u8).collect();
+            assert_eq!(suffix_array(&text), super::par_suffix_array(&text, Chunking::new(100)));
+        }
+    }
+
+    #[test]
+    fn random_int() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [2, 1000, u32::MAX] {
+            for _ in 0..100 {
+                let text: Vec<u32> = (0..rng.gen_range(0, 100)).map(|_| rng.gen_range(0, max)).collect();
+                assert_eq!(naive_suffix_array(&text), int_suffix_array(&text), "text = {:?}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn with_progress() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let n = 3 * progress::STEP + 5;
+        let text: Vec<u8> = (0..n).map(|_| rng.gen_range(0, 4) as u8).collect();
+        let mut reports = vec![];
+        let sa = SuffixArray::with_progress(&text, &mut |phase: Phase, done: usize, total: usize| {
+            reports.push((phase, done, total));
+        });
+        assert_eq!(suffix_array(&text), sa.as_slice());
+        let lcp: Vec<usize> = [0, 1, 2, 3].iter().map(|k| k * progress::STEP).chain([n]).collect();
+        let expected: Vec<(Phase, usize, usize)> = [(Phase::SortSuffixes, 0, n), (Phase::SortSuffixes, n, n)].into_iter()
+            .chain(lcp.into_iter().map(|done| (Phase::ComputeLcp, done, n)))
+            .collect();
+        assert_eq!(expected, reports);
+    }
+
+    #[test]
+    fn with_memory_limit() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for text in [&b""[..], b"a", b"aa", b"ab\0a\0", b"banana", b"mississippi"] {
+            assert_eq!((suffix_array(text), inverse(&suffix_array(text))), doubling_suffix_array(text), "text = {:?}", text);
+        }
+        for max in [1, 2, 256] {
+            // long enough for the low memory path to fit under the fast one
+            let text: Vec<u8> = (0..20000).map(|_| rng.gen_range(0, max) as u8).collect();
+            assert_eq!(suffix_array(&text), doubling_suffix_array(&text).0);
+            let required = 20000 * (4 * mem::size_of::<usize>() + 2) + BUCKETS * mem::size_of::<usize>();
+            assert_eq!(Some(Error::MemoryLimit { required, limit: required - 1 }), SuffixArray::with_memory_limit(&text, required - 1).err());
+            for bytes in [required, usize::MAX] {
+                let sa = SuffixArray::with_memory_limit(&text, bytes).unwrap();
+                let expected = SuffixArray::new(&text);
+                assert_eq!(expected.as_slice(), sa.as_slice());
+                assert_eq!(expected.lcp_array(), sa.lcp_array());
+                assert_eq!(expected.rank, sa.rank);
+            }
+        }
+    }
+
+    #[test]
+    fn cancel() {
+        let text = vec![b'a'; 2 * progress::STEP];
+        let reports = Cell::new(0);
+        let mut progress = Cancellable::new(|_: Phase, _: usize, _: usize| reports.set(reports.get() + 1), || reports.get() >= 4);
+        assert_eq!(Some(Error::Cancelled), SuffixArray::try_with_progress(&text, &mut progress).err());
+        // sorting the suffixes, then the start and the first step of computing the LCP
+        assert_eq!(4, reports.get());
+    }
+
+    #[test]
+    #[should_panic(expected = "elements should be at most upper")]
+    fn too_large() {
+        sa_is(&[0, 3, 1], 2);
+    }
+
+    #[test]
+    fn longest_repeated_substring() {
+        assert_eq!(None, SuffixArray::new(b"").longest_repeated_substring());
+        assert_eq!(None, SuffixArray::new(b"abc").longest_repeated_substring());
+        assert_eq!(Some(0..3), SuffixArray::new(b"aaaa").longest_repeated_substring());
+        // "cd" and "ab" both appear twice
+        assert_eq!(Some(6..8), SuffixArray::new(b"cdxcdyabzab").longest_repeated_substring());
+        assert_eq!(Some(1..4), SuffixArray::new(b"banana").longest_repeated_substring());
+    }
+
+    #[test]
+    #[should_panic(expected = "sa should be a permutation")]
+    fn invalid_sa() {
+        lcp_array(b"ab", &[0, 0]);
+    }
+}
@@ -0,0 +1,295 @@
+use crate::error::Error;
+use crate::serialize;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::mem;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic;
+use std::sync::atomic::AtomicUsize;
+use std::vec;
+
+// the size of the buffer of each temporary file
+const BUFFER: usize = 8 << 10;
+// the number of the runs merged at once
+const FAN_IN: usize = 32;
+// the readers of a merge, and as much for the sorted runs
+const MIN_BYTES: usize = 2 * FAN_IN * BUFFER;
+
+/// バイト列 `text` の接尾辞配列を、作業領域をおよそ `bytes` バイトのメモリに抑えて構築し、 `w` に書き出します。
+///
+/// 接尾辞配列の要素を、先頭から順にリトルエンディアン 8 バイトで書き出します。
+/// 比べる長さを倍々に伸ばしながら (prefix doubling) 、各回で接尾辞の順位の組を外部ソートで整列します。
+/// 整列する列が `bytes` に収まらない分はディレクトリ `dir` の一時ファイルに書き出してからマージし、一時ファイルは返る前に削除します。
+/// `text` 自体はメモリに置くため、大きなファイルはメモリマップして渡してください。
+///
+/// `bytes` が一時ファイルのバッファにも足りない場合、 [`Error::MemoryLimit`] を包んだ `ErrorKind::OutOfMemory` のエラーを返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::suffix_array::suffix_array;
+/// use rust_study::string::suffix_array::write_suffix_array;
+/// let text = b"mississippi";
+/// let mut out = vec![];
+/// write_suffix_array(text, 1 << 20, &std::env::temp_dir(), &mut out).unwrap();
+/// let sa: Vec<usize> = out.chunks(8).map(|b| u64::from_le_bytes(b.try_into().unwrap()) as usize).collect();
+/// assert_eq!(suffix_array(text), sa);
+/// assert!(write_suffix_array(text, 1 << 10, &std::env::temp_dir(), &mut out).is_err());
+/// ```
+pub fn write_suffix_array<W: Write>(text: &[u8], bytes: usize, dir: &Path, w: &mut W) -> io::Result<()> {
+    if bytes < MIN_BYTES {
+        return Err(Error::MemoryLimit { required: MIN_BYTES, limit: bytes }.into());
+    }
+    // the two sorters alive at once share what the readers leave
+    write_with_sort_bytes(text, (bytes - FAN_IN * BUFFER) / 2, dir, w)
+}
+
+fn write_with_sort_bytes<W: Write>(text: &[u8], sort_bytes: usize, dir: &Path, w: &mut W) -> io::Result<()> {
+    let n = text.len();
+    // the rank of the first k bytes of each suffix in the text order, where 0 is past the end of the text
+    let (mut ranks, mut out) = SpillFile::create(dir)?;
+    for &b in text {
+        serialize::write_u64(&mut out, b as u64 + 1)?;
+    }
+    out.flush()?;
+    drop(out);
+
+    let mut k = 1;
+    loop {
+        // the ranks of the first k bytes and the next k bytes, and the position
+        let mut pairs = ExternalSort::<3>::new(dir, sort_bytes);
+        let mut first = ranks.open()?;
+        let mut second = ranks.open()?;
+        second.seek(SeekFrom::Start(8 * k as u64))?;
+        for i in 0..n {
+            let r1 = serialize::read_u64(&mut first)?;
+            let r2 = if i + k < n { serialize::read_u64(&mut second)? } else { 0 };
+            pairs.push([r1, r2, i as u64])?;
+        }
+        drop((first, second));
+
+        // the new rank of each position, and the positions in the sorted order
+        let mut new_ranks = ExternalSort::<2>::new(dir, sort_bytes);
+        let (order, mut out) = SpillFile::create(dir)?;
+        let mut distinct = true;
+        let mut last = None;
+        let mut rank = 0;
+        for (j, record) in pairs.finish()?.enumerate() {
+            let [r1, r2, i] = record?;
+            if last == Some((r1, r2)) {
+                distinct = false;
+            } else {
+                last = Some((r1, r2));
+                rank = j as u64 + 1;
+            }
+            new_ranks.push([i, rank])?;
+            serialize::write_u64(&mut out, i)?;
+        }
+        out.flush()?;
+        drop(out);
+        if distinct {
+            io::copy(&mut order.open()?, w)?;
+            return Ok(());
+        }
+
+        let (next, mut out) = SpillFile::create(dir)?;
+        for record in new_ranks.finish()? {
+            let [_, rank] = record?;
+            serialize::write_u64(&mut out, rank)?;
+        }
+        out.flush()?;
+        ranks = next;
+        k *= 2;
+    }
+}
+
+// a temporary file, removed when dropped
+struct SpillFile {
+    path: PathBuf,
+}
+
+impl SpillFile {
+    fn create(dir: &Path) -> io::Result<(Self, BufWriter<File>)> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT.fetch_add(1, atomic::Ordering::Relaxed);
+        let path = dir.join(format!("suffix_array_{}_{}.tmp", process::id(), id));
+        let file = OpenOptions::new().write(true).create_new(true).open(&path)?;
+        Ok((SpillFile { path }, BufWriter::with_capacity(BUFFER, file)))
+    }
+
+    fn open(&self) -> io::Result<BufReader<File>> {
+        Ok(BufReader::with_capacity(BUFFER, File::open(&self.path)?))
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn write_record<W: Write, const K: usize>(w: &mut W, record: &[u64; K]) -> io::Result<()> {
+    record.iter().try_for_each(|&v| serialize::write_u64(w, v))
+}
+
+fn read_record<R: Read, const K: usize>(r: &mut R) -> io::Result<[u64; K]> {
+    let mut record = [0; K];
+    for v in record.iter_mut() {
+        *v = serialize::read_u64(r)?;
+    }
+    Ok(record)
+}
+
+// sorts the records of K words, spilling each sorted run of sort_bytes into a temporary file
+struct ExternalSort<'a, const K: usize> {
+    dir: &'a Path,
+    run_len: usize,
+    buf: Vec<[u64; K]>,
+    // the sorted runs with their numbers of the records
+    runs: Vec<(SpillFile, usize)>,
+}
+
+impl <'a, const K: usize> ExternalSort<'a, K> {
+    fn new(dir: &'a Path, sort_bytes: usize) -> Self {
+        let run_len = (sort_bytes / mem::size_of::<[u64; K]>()).max(1);
+        ExternalSort { dir, run_len, buf: vec![], runs: vec![] }
+    }
+
+    fn push(&mut self, record: [u64; K]) -> io::Result<()> {
+        self.buf.push(record);
+        if self.buf.len() >= self.run_len {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.buf.sort_unstable();
+        let (file, mut out) = SpillFile::create(self.dir)?;
+        for record in self.buf.iter() {
+            write_record(&mut out, record)?;
+        }
+        out.flush()?;
+        self.runs.push((file, self.buf.len()));
+        self.buf.clear();
+        Ok(())
+    }
+
+    // returns the records in the sorted order, merging the runs at most FAN_IN at once
+    fn finish(mut self) -> io::Result<Merge<K>> {
+        if self.runs.is_empty() {
+            self.buf.sort_unstable();
+            return Merge::new(mem::take(&mut self.buf), vec![]);
+        }
+        if !self.buf.is_empty() {
+            self.spill()?;
+        }
+        self.buf = vec![];
+        while self.runs.len() > FAN_IN {
+            let group: Vec<(SpillFile, usize)> = self.runs.drain(..FAN_IN).collect();
+            let len = group.iter().map(|&(_, len)| len).sum();
+            let (file, mut out) = SpillFile::create(self.dir)?;
+            for record in Merge::<K>::new(vec![], group)? {
+                write_record(&mut out, &record?)?;
+            }
+            out.flush()?;
+            self.runs.push((file, len));
+        }
+        Merge::new(vec![], mem::take(&mut self.runs))
+    }
+}
+
+// the k-way merge of the sorted runs, or the records sorted in memory
+struct Merge<const K: usize> {
+    memory: vec::IntoIter<[u64; K]>,
+    // the reader of each run with the number of the records left, and the file to remove
+    runs: Vec<(BufReader<File>, usize, SpillFile)>,
+    heap: BinaryHeap<Reverse<([u64; K], usize)>>,
+}
+
+impl <const K: usize> Merge<K> {
+    fn new(memory: Vec<[u64; K]>, files: Vec<(SpillFile, usize)>) -> io::Result<Self> {
+        let mut merge = Merge { memory: memory.into_iter(), runs: vec![], heap: BinaryHeap::new() };
+        for (file, len) in files {
+            merge.runs.push((file.open()?, len, file));
+            merge.refill(merge.runs.len() - 1)?;
+        }
+        Ok(merge)
+    }
+
+    fn refill(&mut self, run: usize) -> io::Result<()> {
+        let (reader, left, _) = &mut self.runs[run];
+        if *left > 0 {
+            *left -= 1;
+            let record = read_record(reader)?;
+            self.heap.push(Reverse((record, run)));
+        }
+        Ok(())
+    }
+}
+
+impl <const K: usize> Iterator for Merge<K> {
+    type Item = io::Result<[u64; K]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(record) = self.memory.next() {
+            return Some(Ok(record));
+        }
+        let Reverse((record, run)) = self.heap.pop()?;
+        Some(self.refill(run).map(|_| record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::suffix_array::suffix_array;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn read_suffix_array(bytes: &[u8]) -> Vec<usize> {
+        bytes.chunks(8).map(|b| u64::from_le_bytes(b.try_into().unwrap()) as usize).collect()
+    }
+
+    #[test]
+    fn spill() {
+        let dir = std::env::temp_dir().join(format!("suffix_array_spill_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [1, 2, 256] {
+            let text: Vec<u8> = (0..1000).map(|_| rng.gen_range(0, max) as u8).collect();
+            // a few records per run, more runs than merged at once
+            for sort_bytes in [0, 100, 1 << 20] {
+                let mut out = vec![];
+                write_with_sort_bytes(&text, sort_bytes, &dir, &mut out).unwrap();
+                assert_eq!(suffix_array(&text), read_suffix_array(&out), "max = {}, sort_bytes = {}", max, sort_bytes);
+            }
+        }
+        let mut out = vec![];
+        write_with_sort_bytes(b"", 0, &dir, &mut out).unwrap();
+        assert!(out.is_empty());
+        // every temporary file is removed
+        assert_eq!(0, fs::read_dir(&dir).unwrap().count());
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn memory_limit() {
+        let error = write_suffix_array(b"abc", MIN_BYTES - 1, &std::env::temp_dir(), &mut vec![]).unwrap_err();
+        assert_eq!(io::ErrorKind::OutOfMemory, error.kind());
+    }
+}
@@ -0,0 +1,364 @@
+use crate::space::SpaceUsage;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+const ROOT: usize = 0;
+// the end of the leaves while constructing
+const OPEN: usize = usize::MAX;
+// the terminal symbol, which is larger than any byte
+const TERMINAL: u16 = 256;
+
+/// Ukkonen のアルゴリズムで `O(n)` で構築する接尾辞木
+///
+/// 末尾にどのバイトよりも大きい終端記号を加えた列の、すべての接尾辞を葉とするパトリシア木です。
+/// 辺のラベルは元のバイト列の範囲で表し、内部ノードは接尾辞リンクを持ちます。
+/// ノードはポインタの代わりに番号で表し、根の番号は `0` です。
+///
+/// 接尾辞配列などの簡潔な索引と比べて使用メモリは大きくなりますが、部分列の検索をパターンの長さに比例する時間で行えます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::suffix_tree::SuffixTree;
+/// let tree = SuffixTree::new(b"mississippi");
+/// assert!(tree.contains(b"ssi"));
+/// assert_eq!(vec![2, 5], tree.positions(b"ssi"));
+/// assert_eq!(Some(1..5), tree.longest_repeated_substring());
+///
+/// // the suffix link of "issi" leads to "ssi", then "si", and the root
+/// let node = tree.find_node(b"issi").unwrap();
+/// let labels: Vec<&[u8]> = tree.suffix_links(node).map(|node| tree.label(node)).collect();
+/// assert_eq!(vec![&b"issi"[..], b"ssi", b"si", b"i", b""], labels);
+/// ```
+pub struct SuffixTree {
+    text: Vec<u8>,
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    // the label of the edge from the parent is `[start, end)` of the text with the terminal
+    start: usize,
+    end: usize,
+    // the length of the path label from the root, including the terminal for the leaves
+    depth: usize,
+    link: usize,
+    // sorted by the first symbol of the edge
+    children: Vec<(u16, usize)>,
+}
+
+impl Node {
+    fn new(start: usize, end: usize) -> Self {
+        Node { start, end, depth: 0, link: ROOT, children: vec![] }
+    }
+
+    fn child(&self, c: u16) -> Option<usize> {
+        self.children.binary_search_by_key(&c, |(k, _)| *k).ok().map(|i| self.children[i].1)
+    }
+
+    fn set_child(&mut self, c: u16, child: usize) {
+        match self.children.binary_search_by_key(&c, |(k, _)| *k) {
+            Ok(i) => self.children[i].1 = child,
+            Err(i) => self.children.insert(i, (c, child)),
+        }
+    }
+}
+
+impl SuffixTree {
+    /// バイト列 `text` の接尾辞木を構築します。
+    pub fn new(text: &[u8]) -> Self {
+        let s: Vec<u16> = text.iter().map(|&b| b as u16).chain(core::iter::once(TERMINAL)).collect();
+        let mut nodes = vec![Node::new(0, 0)];
+        // the active point, where the next suffix is inserted
+        let mut active_node = ROOT;
+        let mut active_edge = 0;
+        let mut active_len = 0;
+        // the number of the suffixes not yet inserted as leaves
+        let mut remainder = 0;
+        for i in 0..s.len() {
+            remainder += 1;
+            // the internal node created last in this phase, waiting for its suffix link
+            let mut last_new: Option<usize> = None;
+            while remainder > 0 {
+                if active_len == 0 {
+                    active_edge = i;
+                }
+                match nodes[active_node].child(s[active_edge]) {
+                    None => {
+                        nodes.push(Node::new(i, OPEN));
+                        let leaf = nodes.len() - 1;
+                        nodes[active_node].set_child(s[i], leaf);
+                        if let Some(last) = last_new.take() {
+                            nodes[last].link = active_node;
+                        }
+                    }
+                    Some(next) => {
+                        let edge_len = nodes[next].end.min(i + 1) - nodes[next].start;
+                        if active_len >= edge_len {
+                            // walk down to the next node
+                            active_edge += edge_len;
+                            active_len -= edge_len;
+                            active_node = next;
+                            continue;
+                        }
+                        if s[nodes[next].start + active_len] == s[i] {
+                            // the suffix is already in the tree
+                            if let Some(last) = last_new.take() {
+                                if active_node != ROOT {
+                                    nodes[last].link = active_node;
+                                }
+                            }
+                            active_len += 1;
+                            break;
+                        }
+                        // split the edge and add a leaf
+                        let start = nodes[next].start;
+                        nodes.push(Node::new(start, start + active_len));
+                        let split = nodes.len() - 1;
+                        nodes[active_node].set_child(s[active_edge], split);
+                        nodes.push(Node::new(i, OPEN));
+                        let leaf = nodes.len() - 1;
+                        nodes[split].set_child(s[i], leaf);
+                        nodes[next].start += active_len;
+                        let c = s[nodes[next].start];
+                        nodes[split].set_child(c, next);
+                        if let Some(last) = last_new.replace(split) {
+                            nodes[last].link = split;
+                        }
+                    }
+                }
+                remainder -= 1;
+                if active_node == ROOT && active_len > 0 {
+                    active_len -= 1;
+                    active_edge = i + 1 - remainder;
+                } else if active_node != ROOT {
+                    active_node = nodes[active_node].link;
+                }
+            }
+        }
+
+        let mut stack = vec![ROOT];
+        while let Some(node) = stack.pop() {
+            for k in 0..nodes[node].children.len() {
+                let child = nodes[node].children[k].1;
+                if nodes[child].end == OPEN {
+                    nodes[child].end = s.len();
+                }
+                nodes[child].depth = nodes[node].depth + nodes[child].end - nodes[child].start;
+                stack.push(child);
+            }
+        }
+        SuffixTree { text: text.to_vec(), nodes }
+    }
+
+    /// 元のバイト列の長さを返します。
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    /// 元のバイト列が空の場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// 根と葉を含むノードの個数を返します。
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// `pattern` が元のバイト列に現れる場合に、 `true` を返します。
+    pub fn contains(&self, pattern: &[u8]) -> bool {
+        self.locate(pattern).is_some()
+    }
+
+    /// `pattern` が元のバイト列に現れる位置を、昇順に返します。
+    ///
+    /// `pattern` が空の場合、 `0` から `len` までのすべての位置を返します。
+    pub fn positions(&self, pattern: &[u8]) -> Vec<usize> {
+        let mut positions = match self.locate(pattern) {
+            Some(node) => self.leaves(node).collect(),
+            None => vec![],
+        };
+        positions.sort_unstable();
+        positions
+    }
+
+    /// 2 回以上現れる最長の部分列の、最初に現れる位置の範囲を返します。
+    ///
+    /// 最長のものが複数ある場合、辞書順で最小のものを返します。2 回以上現れる部分列が空列だけの場合、 `None` を返します。
+    pub fn longest_repeated_substring(&self) -> Option<Range<usize>> {
+        // the deepest internal node, visited in lexicographic order
+        let mut deepest = ROOT;
+        let mut stack = vec![ROOT];
+        while let Some(node) = stack.pop() {
+            if self.is_leaf(node) {
+                continue;
+            }
+            if self.nodes[node].depth > self.nodes[deepest].depth {
+                deepest = node;
+            }
+            stack.extend(self.nodes[node].children.iter().rev().map(|(_, child)| *child));
+        }
+        if deepest == ROOT {
+            return None;
+        }
+        let start = self.leaves(deepest).min().unwrap();
+        Some(start..start + self.nodes[deepest].depth)
+    }
+
+    /// 経路のラベルが `pattern` と一致するノードを返します。
+    ///
+    /// `pattern` が辺の途中で終わる場合や、元のバイト列に現れない場合は `None` を返します。
+    pub fn find_node(&self, pattern: &[u8]) -> Option<usize> {
+        let node = self.locate(pattern)?;
+        if self.nodes[node].depth == pattern.len() { Some(node) } else { None }
+    }
+
+    /// ノード `node` が葉の場合に、 `true` を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node >= size`.
+    pub fn is_leaf(&self, node: usize) -> bool {
+        self.nodes[node].children.is_empty()
+    }
+
+    /// 根からノード `node` までの経路のラベルを返します。葉のラベルは終端記号を含みません。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node >= size`.
+    pub fn label(&self, node: usize) -> &[u8] {
+        let Node { end, depth, .. } = self.nodes[node];
+        &self.text[end - depth..end.min(self.text.len())]
+    }
+
+    /// 内部ノード `node` の接尾辞リンク、すなわちラベルの先頭の 1 バイトを取り除いたラベルのノードを返します。
+    ///
+    /// `node` が根か葉の場合、 `None` を返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node >= size`.
+    pub fn suffix_link(&self, node: usize) -> Option<usize> {
+        if node == ROOT || self.is_leaf(node) {
+            None
+        } else {
+            Some(self.nodes[node].link)
+        }
+    }
+
+    /// 内部ノード `node` から接尾辞リンクをたどり、根に至るまでのノードを順に返します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node >= size`.
+    pub fn suffix_links(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        core::iter::successors(Some(node), move |&node| self.suffix_link(node))
+    }
+
+    // the highest node whose path label starts with the pattern
+    fn locate(&self, pattern: &[u8]) -> Option<usize> {
+        let mut node = ROOT;
+        let mut i = 0;
+        while i < pattern.len() {
+            node = self.nodes[node].child(pattern[i] as u16)?;
+            let Node { start, end, .. } = self.nodes[node];
+            for p in start..end.min(self.text.len()) {
+                if i == pattern.len() {
+                    break;
+                }
+                if self.text[p] != pattern[i] {
+                    return None;
+                }
+                i += 1;
+            }
+            if i < pattern.len() && end > self.text.len() {
+                // reached the terminal
+                return None;
+            }
+        }
+        Some(node)
+    }
+
+    // the start positions of the suffixes in the subtree
+    fn leaves(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut stack = vec![node];
+        core::iter::from_fn(move || {
+            while let Some(node) = stack.pop() {
+                if self.is_leaf(node) {
+                    return Some(self.text.len() + 1 - self.nodes[node].depth);
+                }
+                stack.extend(self.nodes[node].children.iter().map(|(_, child)| *child));
+            }
+            None
+        })
+    }
+}
+
+impl SpaceUsage for Node {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("children", &self.children)]
+    }
+}
+
+impl SpaceUsage for SuffixTree {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("text", &self.text), ("nodes", &self.nodes)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::suffix_array::SuffixArray;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn naive_positions(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+        (0..=text.len()).filter(|&i| text[i..].starts_with(pattern)).collect()
+    }
+
+    fn check(text: &[u8]) {
+        let tree = SuffixTree::new(text);
+        assert_eq!(text.len() + 1, (0..tree.size()).filter(|&node| tree.is_leaf(node)).count());
+        for node in 1..tree.size() {
+            if let Some(link) = tree.suffix_link(node) {
+                assert_eq!(&tree.label(node)[1..], tree.label(link), "text = {:?}", text);
+                assert_eq!(Some(node), tree.find_node(tree.label(node)));
+            }
+        }
+        assert_eq!(SuffixArray::new(text).longest_repeated_substring(), tree.longest_repeated_substring(), "text = {:?}", text);
+    }
+
+    #[test]
+    fn small() {
+        for text in [&b""[..], b"a", b"aa", b"ab", b"abab", b"banana", b"mississippi", b"\xff\xff\x00"] {
+            check(text);
+            let tree = SuffixTree::new(text);
+            for pattern in [&b""[..], b"a", b"aa", b"an", b"ana", b"nab", b"ssi", b"\xff"] {
+                assert_eq!(naive_positions(text, pattern), tree.positions(pattern), "text = {:?}, pattern = {:?}", text, pattern);
+                assert_eq!(!naive_positions(text, pattern).is_empty(), tree.contains(pattern));
+            }
+        }
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for max in [2, 4, 256] {
+            for len in [10, 100, 1000] {
+                let text: Vec<u8> = (0..len).map(|_| rng.gen_range(0, max) as u8).collect();
+                check(&text);
+                let tree = SuffixTree::new(&text);
+                assert!(tree.size() <= 2 * (len + 1));
+                for _ in 0..100 {
+                    let pattern: Vec<u8> = (0..rng.gen_range(1, 6)).map(|_| rng.gen_range(0, max) as u8).collect();
+                    assert_eq!(naive_positions(&text, &pattern), tree.positions(&pattern));
+                }
+            }
+        }
+    }
+}
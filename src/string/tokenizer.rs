@@ -0,0 +1,204 @@
+use crate::space::SpaceUsage;
+use crate::string::trie::Trie;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// 辞書にない部分の扱い
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Fallback {
+    /// 1 文字ずつトークンにします。
+    #[default]
+    Char,
+    /// 次に辞書の語が始まる位置までを、1 つのトークンにまとめます。
+    Merge,
+    /// トークンにせず読み飛ばします。
+    Skip,
+}
+
+/// 辞書のトライ木による最長一致のトークナイザ
+///
+/// 先頭から順に、辞書に含まれる最長の語をトークンとして切り出します。
+/// 辞書にない部分は [`Fallback`] にしたがって扱います。
+/// 空文字列のキーは、辞書の語として扱いません。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::tokenizer::*;
+/// use rust_study::string::trie::NaiveTrie;
+/// let dictionary: NaiveTrie = ["東京", "東京都", "京都", "に", "住む"].iter().collect();
+/// let tokenizer = LongestMatchTokenizer::new(dictionary);
+/// assert_eq!(vec![("東京都", 0..9), ("に", 9..12), ("住む", 12..18)],
+///            tokenizer.tokenize("東京都に住む").collect::<Vec<_>>());
+///
+/// let tokenizer = tokenizer.with_fallback(Fallback::Merge);
+/// assert_eq!(vec!["京都", "へ行く"], tokenizer.tokenize("京都へ行く").map(|(token, _)| token).collect::<Vec<_>>());
+/// ```
+pub struct LongestMatchTokenizer<T> {
+    dictionary: T,
+    fallback: Fallback,
+}
+
+impl <T> LongestMatchTokenizer<T> {
+    /// 辞書 `dictionary` から、辞書にない部分を 1 文字ずつトークンにするトークナイザを作成します。
+    pub fn new(dictionary: T) -> Self {
+        LongestMatchTokenizer { dictionary, fallback: Fallback::default() }
+    }
+
+    /// 辞書にない部分の扱いを `fallback` にします。
+    pub fn with_fallback(self, fallback: Fallback) -> Self {
+        LongestMatchTokenizer { fallback, ..self }
+    }
+
+    /// 辞書を参照します。
+    pub fn dictionary(&self) -> &T {
+        &self.dictionary
+    }
+
+    /// 辞書にない部分の扱いを返します。
+    pub fn fallback(&self) -> Fallback {
+        self.fallback
+    }
+
+    /// 辞書を返します。
+    pub fn into_dictionary(self) -> T {
+        self.dictionary
+    }
+}
+
+impl <T: Trie> LongestMatchTokenizer<T> {
+    /// `text` を先頭から分割し、トークンと `text` でのバイト単位の範囲の組を順に返すイテレータを返します。
+    pub fn tokenize<'a, 's>(&'a self, text: &'s str) -> Tokens<'a, 's, T> {
+        Tokens { tokenizer: self, text, pos: 0 }
+    }
+
+    /// `text` の位置 `pos` から始まる、辞書の最長の語のバイト数を返します。
+    fn match_len(&self, text: &str, pos: usize) -> usize {
+        self.dictionary.prefix(&text[pos..]).len()
+    }
+}
+
+/// [`LongestMatchTokenizer::tokenize`] が返すイテレータ
+pub struct Tokens<'a, 's, T> {
+    tokenizer: &'a LongestMatchTokenizer<T>,
+    text: &'s str,
+    pos: usize,
+}
+
+impl <'s, T: Trie> Iterator for Tokens<'_, 's, T> {
+    type Item = (&'s str, Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = self.text;
+        loop {
+            let start = self.pos;
+            let c = text[start..].chars().next()?;
+            let len = self.tokenizer.match_len(text, start);
+            if len > 0 {
+                self.pos += len;
+                return Some((&text[start..self.pos], start..self.pos));
+            }
+            self.pos += c.len_utf8();
+            match self.tokenizer.fallback {
+                Fallback::Char => {}
+                Fallback::Merge => {
+                    while let Some(c) = text[self.pos..].chars().next() {
+                        if self.tokenizer.match_len(text, self.pos) > 0 {
+                            break;
+                        }
+                        self.pos += c.len_utf8();
+                    }
+                }
+                Fallback::Skip => continue,
+            }
+            return Some((&text[start..self.pos], start..self.pos));
+        }
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for LongestMatchTokenizer<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("dictionary", &self.dictionary)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::trie::ByteTrie;
+    use crate::string::trie::NaiveTrie;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn fallback() {
+        let dictionary: NaiveTrie = ["", "ab", "abc", "cd"].iter().collect();
+        let tokenizer = LongestMatchTokenizer::new(dictionary);
+        fn tokens<'s>(tokenizer: &LongestMatchTokenizer<NaiveTrie>, text: &'s str) -> Vec<(&'s str, Range<usize>)> {
+            tokenizer.tokenize(text).collect()
+        }
+        assert_eq!(vec![("abc", 0..3), ("d", 3..4), ("x", 4..5), ("y", 5..6), ("cd", 6..8)], tokens(&tokenizer, "abcdxycd"));
+        assert_eq!(Vec::<(&str, Range<usize>)>::new(), tokens(&tokenizer, ""));
+
+        let tokenizer = tokenizer.with_fallback(Fallback::Merge);
+        assert_eq!(Fallback::Merge, tokenizer.fallback());
+        assert_eq!(vec![("abc", 0..3), ("dxy", 3..6), ("cd", 6..8)], tokens(&tokenizer, "abcdxycd"));
+        assert_eq!(vec![("xyz", 0..3)], tokens(&tokenizer, "xyz"));
+
+        let tokenizer = tokenizer.with_fallback(Fallback::Skip);
+        assert_eq!(vec![("abc", 0..3), ("cd", 6..8)], tokens(&tokenizer, "abcdxycd"));
+        assert_eq!(Vec::<(&str, Range<usize>)>::new(), tokens(&tokenizer, "xyz"));
+    }
+
+    #[test]
+    fn longest_match() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let random_key = |rng: &mut StdRng, max_len| -> String {
+            let len = rng.gen_range(0, max_len);
+            (0..len).map(|_| ['a', 'b', 'あ', 'い'][rng.gen_range(0, 4)]).collect()
+        };
+        let keys: Vec<String> = (0..30).map(|_| random_key(&mut rng, 4)).collect();
+        let mut dictionary = ByteTrie::new();
+        keys.iter().for_each(|key| { dictionary.append(key); });
+        let mut tokenizer = LongestMatchTokenizer::new(dictionary);
+        for _ in 0..100 {
+            let text = random_key(&mut rng, 20);
+            for fallback in [Fallback::Char, Fallback::Merge, Fallback::Skip] {
+                tokenizer = tokenizer.with_fallback(fallback);
+                let mut pos = 0;
+                let mut unknown = None;
+                for (token, span) in tokenizer.tokenize(&text) {
+                    assert_eq!(&text[span.clone()], token);
+                    assert!(pos <= span.start);
+                    if fallback != Fallback::Skip {
+                        assert_eq!(pos, span.start);
+                    }
+                    // the token is the longest key starting at the span
+                    let longest = keys.iter()
+                        .filter(|key| !key.is_empty() && text[span.start..].starts_with(key.as_str()))
+                        .max_by_key(|key| key.len());
+                    match longest {
+                        Some(key) => assert_eq!(key, token),
+                        None => {
+                            assert_ne!(Fallback::Skip, fallback);
+                            if fallback == Fallback::Char {
+                                assert_eq!(1, token.chars().count());
+                            } else {
+                                // unknown spans are merged up to the next key
+                                assert_ne!(Some(span.start), unknown);
+                                unknown = Some(span.end);
+                            }
+                        }
+                    }
+                    pos = span.end;
+                }
+                if fallback != Fallback::Skip {
+                    assert_eq!(text.len(), pos);
+                }
+            }
+        }
+    }
+}
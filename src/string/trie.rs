@@ -1,7 +1,650 @@
+mod automaton;
+#[cfg(feature = "std")]
+mod binary;
+pub mod burst_trie;
+pub mod byte_trie;
+pub mod children;
+/// トライ木の実装が、キーの `BTreeSet` から総当たりで求めた結果と同じ結果を返すことを proptest で確かめる、共通のテスト
+///
+/// `test-utils` フィーチャで公開され、このクレートの外で実装したトライ木のテストにも使えます。
+#[cfg(any(test, feature = "test-utils"))]
+pub mod conformance;
+pub mod critbit_tree;
+pub mod dawg;
+mod dot;
+pub mod louds_trie;
 pub mod naive_trie;
+pub mod naive_trie_map;
+pub mod nested_patricia_trie;
+pub mod normalized;
+pub mod online_dawg;
+pub mod prefix_sum_trie_map;
+pub mod radix_trie;
+pub mod reverse_trie;
+#[cfg(feature = "serde")]
+mod serde_impl;
+pub mod stats;
+pub mod ternary_search_tree;
+pub mod weighted_trie;
+pub use burst_trie::BurstTrie;
+pub use byte_trie::ByteTrie;
+pub use critbit_tree::CritbitTree;
+pub use dawg::Dawg;
+pub use louds_trie::LoudsTrie;
+#[cfg(feature = "std")]
+pub use louds_trie::LoudsTrieView;
+#[cfg(feature = "mmap")]
+pub use louds_trie::MappedLoudsTrie;
+pub use louds_trie::NaiveLoudsTrie;
 pub use naive_trie::NaiveTrie;
+pub use naive_trie_map::NaiveTrieMap;
+pub use nested_patricia_trie::NestedPatriciaTrie;
+pub use normalized::AsciiCaseFold;
+#[cfg(feature = "nfkc")]
+pub use normalized::Nfkc;
+pub use normalized::Normalized;
+pub use normalized::Normalizer;
+pub use online_dawg::OnlineDawg;
+pub use prefix_sum_trie_map::PrefixSumTrieMap;
+pub use radix_trie::RadixTrie;
+pub use radix_trie::RadixTrieMap;
+pub use reverse_trie::ReverseTrie;
+pub use stats::TrieStats;
+pub use ternary_search_tree::TernarySearchTree;
+pub use weighted_trie::WeightedTrie;
+
+use automaton::Hamming;
+use automaton::Levenshtein;
+use automaton::Wildcard;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::RangeBounds;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+#[cfg(feature = "std")]
+const KEYS_MAGIC: &[u8; 8] = b"TRIEKEYS";
 
 pub trait Trie {
     fn contains(&self, s: &str) -> bool;
     fn prefix<'a>(&self, s:&'a str) -> &'a str;
+
+    /// 保持しているキーの個数を返します。
+    fn len(&self) -> usize;
+
+    /// キーを保持していない場合に、 `true` を返します。
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `query` の接頭辞となっているキーを、短い順にすべて返すイテレータを返します。
+    ///
+    /// [`Self::prefix()`] はこのうち最長のものを返します。
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a;
+
+    /// `prefix` から始まるキーの個数を返します。
+    fn count_prefix(&self, prefix: &str) -> usize;
+
+    /// `prefix` から始まるキーを、辞書順に返すイテレータを返します。
+    ///
+    /// キーは必要になった時点で順に列挙されます。
+    fn predict(&self, prefix: &str) -> impl Iterator<Item = String> + '_;
+
+    /// 根を指すカーソルを返します。
+    fn cursor(&self) -> impl TrieCursor<'_>;
+
+    /// 保持しているキーを辞書順に返すイテレータを返します。
+    fn keys(&self) -> impl Iterator<Item = String> + '_ {
+        self.predict("")
+    }
+
+    /// 保持しているキーを辞書順に返すイテレータを返します。 [`Self::keys()`] と同じです。
+    fn iter(&self) -> impl Iterator<Item = String> + '_ {
+        self.keys()
+    }
+
+    /// `query` との編集距離 (レーベンシュタイン距離) が `max_edits` 以下のキーと、その距離の組を、キーの辞書順にすべて返します。
+    ///
+    /// 既定の実装はすべてのキーを列挙します。
+    /// このモジュールのトライ木は、距離が `max_edits` を超えることが確定した部分木を枝刈りしながら探索します。
+    fn fuzzy_search(&self, query: &str, max_edits: usize) -> Vec<(String, usize)> {
+        automaton::filter(Levenshtein::new(query, max_edits), self.keys().map(|key| (key, ())))
+            .into_iter()
+            .map(|(key, _, distance)| (key, distance))
+            .collect()
+    }
+
+    /// `query` と同じ文字数で、異なる文字が `max_mismatches` 個以下のキーと、異なる文字の個数の組を、キーの辞書順にすべて返します。
+    ///
+    /// バーコードや k-mer のように長さのそろったキーを、置換だけを許して検索します。
+    /// 既定の実装はすべてのキーを列挙します。
+    /// このモジュールのトライ木は、異なる文字が `max_mismatches` 個を超えた部分木を枝刈りしながら探索します。
+    fn hamming_search(&self, query: &str, max_mismatches: usize) -> Vec<(String, usize)> {
+        automaton::filter(Hamming::new(query, max_mismatches), self.keys().map(|key| (key, ())))
+            .into_iter()
+            .map(|(key, _, mismatches)| (key, mismatches))
+            .collect()
+    }
+
+    /// ワイルドカードを含むパターン `pattern` にマッチするキーを、辞書順にすべて返します。
+    ///
+    /// `?` は任意の 1 文字に、 `*` は空文字列を含む任意の文字列にマッチします。
+    /// 既定の実装はすべてのキーを列挙します。
+    /// このモジュールのトライ木は、マッチしないことが確定した部分木を枝刈りしながら探索します。
+    fn search_pattern(&self, pattern: &str) -> Vec<String> {
+        automaton::filter(Wildcard::new(pattern), self.keys().map(|key| (key, ())))
+            .into_iter()
+            .map(|(key, _, _)| key)
+            .collect()
+    }
+
+    /// 範囲 `range` に含まれるキーを、辞書順に返すイテレータを返します。
+    ///
+    /// 既定の実装はすべてのキーを列挙します。
+    /// このモジュールのトライ木は、範囲の外にあることが確定した部分木を枝刈りしながら探索します。
+    fn range<'a, 'b, R: RangeBounds<&'b str> + 'a>(&'a self, range: R) -> impl Iterator<Item = String> + 'a {
+        self.keys().filter(move |key| range.contains(&key.as_str()))
+    }
+
+    /// 保持しているキーを文字単位のトライ木で表したときの、ノードの深さや子の個数の分布を返します。
+    fn stats(&self) -> TrieStats {
+        TrieStats::from_sorted_keys(self.keys())
+    }
+
+    /// 保持しているキーを辞書順に、バイト列として `w` に書き出します。
+    ///
+    /// 書き出したキーは [`read_keys()`] で読み込めます。
+    #[cfg(feature = "std")]
+    fn write_keys<W: Write>(&self, w: &mut W) -> io::Result<()> where Self: Sized {
+        w.write_all(KEYS_MAGIC)?;
+        binary::write_u64(w, self.len() as u64)?;
+        for key in self.keys() {
+            binary::write_bytes(w, key.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// [`Trie::write_keys()`] で書き出したキーの列を `r` から読み込みます。
+///
+/// キーは辞書順に並んでいるため、各トライ木の `from_sorted_keys` でそのまま構築できます。
+/// バイト列が壊れている場合、 `ErrorKind::InvalidData` のエラーを返します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::*;
+/// let mut trie = NaiveTrie::new();
+/// trie.append("foo");
+/// trie.append("bar");
+/// let mut bytes = vec![];
+/// trie.write_keys(&mut bytes).unwrap();
+///
+/// let keys = read_keys(&mut bytes.as_slice()).unwrap();
+/// assert_eq!(vec!["bar", "foo"], keys);
+/// let dawg = Dawg::from_sorted_keys(&keys);
+/// assert!(dawg.contains("foo"));
+/// ```
+#[cfg(feature = "std")]
+pub fn read_keys<R: Read>(r: &mut R) -> io::Result<Vec<String>> {
+    binary::check_magic(r, KEYS_MAGIC)?;
+    let len = binary::read_len(r)?;
+    let mut keys = vec![];
+    for _ in 0..len {
+        let key = String::from_utf8(binary::read_bytes(r)?).map_err(|_| binary::invalid_data("invalid UTF-8"))?;
+        keys.push(key);
+    }
+    Ok(keys)
+}
+
+// the set operation combining two tries by traversing them together
+#[derive(Clone, Copy)]
+enum SetOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl SetOp {
+    // whether to descend into the subtree existing in self (a) and / or in the other (b)
+    fn visits(self, a: bool, b: bool) -> bool {
+        match self {
+            SetOp::Union => a || b,
+            SetOp::Intersection => a && b,
+            SetOp::Difference => a,
+        }
+    }
+
+    // whether to keep the key contained in self (a) and / or in the other (b)
+    fn keeps(self, a: bool, b: bool) -> bool {
+        match self {
+            SetOp::Union => a || b,
+            SetOp::Intersection => a && b,
+            SetOp::Difference => a && !b,
+        }
+    }
+}
+
+/// トライ木のノードを指し、文字を 1 つずつたどるカーソル
+///
+/// カーソルを複製して途中の位置を覚えておけるため、入力の各位置から最長一致を探す場合などに、
+/// 毎回根からたどり直す必要がありません。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::*;
+/// let trie = Dawg::new(&["東京", "東京都", "京都", "都"]);
+/// let text = "東京都庁";
+/// // the longest key starting at each position
+/// let mut longest = vec![];
+/// for (start, _) in text.char_indices() {
+///     let mut cursor = trie.cursor();
+///     let mut end = None;
+///     for (i, c) in text[start..].char_indices() {
+///         if !cursor.step(c) {
+///             break;
+///         }
+///         if cursor.is_leaf() {
+///             end = Some(start + i + c.len_utf8());
+///         }
+///     }
+///     longest.push(end.map(|end| &text[start..end]));
+/// }
+/// assert_eq!(vec![Some("東京都"), Some("京都"), Some("都"), None], longest);
+/// ```
+pub trait TrieCursor<'a>: Clone {
+    /// キーに対応付けられた値の型です。値を持たないトライ木では `()` です。
+    type Value: 'a;
+
+    /// 文字 `c` の辺をたどります。辺が存在しない場合、カーソルを動かさずに `false` を返します。
+    fn step(&mut self, c: char) -> bool;
+
+    /// 根からたどった文字列がキーである場合に、その値を返します。
+    fn value(&self) -> Option<&'a Self::Value>;
+
+    /// 根からたどった文字列がキーである場合に、 `true` を返します。
+    fn is_leaf(&self) -> bool {
+        self.value().is_some()
+    }
+}
+
+/// キーを追加・削除できるトライ木
+pub trait TrieMut: Trie {
+    /// キー `s` を追加します。新たに追加した場合に、 `true` を返します。
+    fn insert(&mut self, s: &str) -> bool;
+
+    /// キー `s` を取り除きます。キーが存在した場合に、 `true` を返します。
+    ///
+    /// 取り除いたことで子も持たずキーでもなくなったノードは、削除されます。
+    fn remove(&mut self, s: &str) -> bool;
+}
+
+/// キーごとに値 `V` を保持するトライ木
+pub trait TrieMap<V> {
+    /// キー `key` に値 `value` を対応付けます。
+    ///
+    /// すでにキーが存在した場合、値を置き換えて古い値を返します。
+    fn insert(&mut self, key: &str, value: V) -> Option<V>;
+
+    /// キー `key` に対応付けられた値を参照します。
+    fn get(&self, key: &str) -> Option<&V>;
+
+    /// キー `key` に対応付けられた値を可変参照します。
+    fn get_mut(&mut self, key: &str) -> Option<&mut V>;
+
+    /// キー `key` が存在する場合に、 `true` を返します。
+    fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// キー `key` を取り除き、対応付けられていた値を返します。
+    ///
+    /// 取り除いたことで子も持たずキーでもなくなったノードは、削除されます。
+    fn remove(&mut self, key: &str) -> Option<V>;
+
+    /// 保持しているキーの個数を返します。
+    fn len(&self) -> usize;
+
+    /// キーを保持していない場合に、 `true` を返します。
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `prefix` から始まるキーの個数を返します。
+    fn count_prefix(&self, prefix: &str) -> usize;
+
+    /// `query` の接頭辞となっているキーと値の組を、キーの短い順にすべて返すイテレータを返します。
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = (&'a str, &'a V)> + 'a where V: 'a;
+
+    /// `query` の接頭辞となっているキーのうち最長のものと、その値の組を返します。
+    fn longest_prefix<'a>(&'a self, query: &'a str) -> Option<(&'a str, &'a V)> where V: 'a {
+        self.common_prefixes(query).last()
+    }
+
+    /// `prefix` から始まるキーと値の組を、キーの辞書順に返すイテレータを返します。
+    fn iter_prefix<'a>(&'a self, prefix: &str) -> impl Iterator<Item = (String, &'a V)> + 'a where V: 'a;
+
+    /// 根を指すカーソルを返します。
+    fn cursor<'a>(&'a self) -> impl TrieCursor<'a, Value = V> + 'a where V: 'a;
+
+    /// 保持しているキーと値の組を、キーの辞書順に返すイテレータを返します。
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (String, &'a V)> + 'a where V: 'a {
+        self.iter_prefix("")
+    }
+
+    /// `query` との編集距離が `max_edits` 以下のキーと値、その距離の組を、キーの辞書順にすべて返します。
+    ///
+    /// 既定の実装はすべてのキーを列挙します。
+    fn fuzzy_search<'a>(&'a self, query: &str, max_edits: usize) -> Vec<(String, &'a V, usize)> where V: 'a {
+        automaton::filter(Levenshtein::new(query, max_edits), self.iter())
+    }
+
+    /// `query` と同じ文字数で、異なる文字が `max_mismatches` 個以下のキーと値、異なる文字の個数の組を、キーの辞書順にすべて返します。
+    ///
+    /// 既定の実装はすべてのキーを列挙します。
+    fn hamming_search<'a>(&'a self, query: &str, max_mismatches: usize) -> Vec<(String, &'a V, usize)> where V: 'a {
+        automaton::filter(Hamming::new(query, max_mismatches), self.iter())
+    }
+
+    /// ワイルドカードを含むパターン `pattern` にマッチするキーと値の組を、キーの辞書順にすべて返します。
+    ///
+    /// `?` は任意の 1 文字に、 `*` は空文字列を含む任意の文字列にマッチします。
+    /// 既定の実装はすべてのキーを列挙します。
+    fn search_pattern<'a>(&'a self, pattern: &str) -> Vec<(String, &'a V)> where V: 'a {
+        automaton::filter(Wildcard::new(pattern), self.iter())
+            .into_iter()
+            .map(|(key, value, _)| (key, value))
+            .collect()
+    }
+
+    /// 範囲 `range` に含まれるキーと値の組を、キーの辞書順に返すイテレータを返します。
+    ///
+    /// 既定の実装はすべてのキーを列挙します。
+    fn range<'a, 'b, R: RangeBounds<&'b str> + 'a>(&'a self, range: R) -> impl Iterator<Item = (String, &'a V)> + 'a where V: 'a {
+        self.iter().filter(move |(key, _)| range.contains(&key.as_str()))
+    }
+}
+
+/// キーと `[0, len)` の番号を相互に変換できる静的なトライ木
+///
+/// 番号は構築に使ったキーの集合だけで決まり、同じキーから構築し直しても、書き出して読み込んでも変わりません。
+/// キーの代わりに番号を添字として、別の配列などに値を持たせられます (キーの最小完全ハッシュ)。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::*;
+/// fn lookup<'a, T: TrieId>(trie: &T, values: &'a [u32], key: &str) -> Option<&'a u32> {
+///     values.get(trie.key_to_id(key)?)
+/// }
+///
+/// let dawg = Dawg::new(&["apple", "banana", "cherry"]);
+/// let mut values = vec![0; dawg.len()];
+/// for (key, value) in [("apple", 100), ("banana", 200), ("cherry", 300)] {
+///     values[dawg.key_to_id(key).unwrap()] = value;
+/// }
+/// assert_eq!(Some(&200), lookup(&dawg, &values, "banana"));
+/// assert_eq!(None, lookup(&dawg, &values, "durian"));
+/// ```
+pub trait TrieId: Trie {
+    /// キー `s` の番号を返します。キーが存在しない場合、 `None` を返します。
+    fn key_to_id(&self, s: &str) -> Option<usize>;
+
+    /// 番号 `id` のキーを返します。 `id` が範囲外の場合、 `None` を返します。
+    fn id_to_key(&self, id: usize) -> Option<String>;
+}
+
+#[cfg(test)]
+#[generic_tests::define]
+mod tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+    use std::ops::Bound;
+
+    // the keys of the few chars, sharing many prefixes
+    fn keys() -> impl Strategy<Value = Vec<String>> {
+        vec(conformance::key_of(&['a', 'b', 'あ'], 6), 0..300)
+    }
+
+    fn build<T: TrieMut + Default>(keys: &[String]) -> T {
+        let mut trie = T::default();
+        keys.iter().for_each(|key| { trie.insert(key); });
+        trie
+    }
+
+    #[instantiate_tests(<NaiveTrie>)]
+    mod naive {}
+
+    #[instantiate_tests(<NaiveTrie<children::ArrayChildren>>)]
+    mod naive_array {}
+
+    #[cfg(feature = "std")]
+    #[instantiate_tests(<NaiveTrie<children::HashChildren>>)]
+    mod naive_hash {}
+
+    #[cfg(feature = "std")]
+    #[instantiate_tests(<NaiveTrie<children::HashChildren<rustc_hash::FxBuildHasher>>>)]
+    mod naive_fx_hash {}
+
+    #[instantiate_tests(<NaiveTrie<children::SortedVecChildren>>)]
+    mod naive_sorted_vec {}
+
+    #[instantiate_tests(<NaiveTrie<children::AdaptiveChildren, crate::collections::arena::ArenaNodes>>)]
+    mod naive_arena {}
+
+    #[instantiate_tests(<ByteTrie>)]
+    mod byte {}
+
+    #[instantiate_tests(<BurstTrie>)]
+    mod burst {}
+
+    #[instantiate_tests(<OnlineDawg>)]
+    mod online_dawg {}
+
+    #[instantiate_tests(<RadixTrie>)]
+    mod radix {}
+
+    #[instantiate_tests(<TernarySearchTree>)]
+    mod ternary_search_tree {}
+
+    #[test]
+    fn search<T: TrieMut + Default>() {
+        let mut trie = T::default();
+        for key in ["the", "they", "their", "them", "theirs", "this", "あいう"] {
+            assert!(trie.insert(key));
+        }
+        assert!(!trie.insert("the"));
+        assert_eq!(7, trie.len());
+        assert!(trie.contains("their"));
+        assert!(!trie.contains("thei"));
+        assert!(!trie.contains(""));
+        assert_eq!("their", trie.prefix("theiry"));
+        assert_eq!("", trie.prefix("th"));
+        assert_eq!("あいう", trie.prefix("あいうえお"));
+        assert_eq!(vec!["the", "their", "theirs"], trie.common_prefixes("theirs").collect::<Vec<_>>());
+        assert_eq!(vec!["the", "their", "theirs", "them", "they"], trie.predict("the").collect::<Vec<_>>());
+        assert_eq!(5, trie.count_prefix("the"));
+        assert_eq!(0, trie.count_prefix("x"));
+    }
+
+    #[test]
+    fn same_as_btree_set<T: TrieMut + Default>() {
+        conformance::check_dynamic(T::default);
+    }
+
+    #[test]
+    fn fuzzy_search<T: TrieMut + Default>() {
+        let mut trie = T::default();
+        for key in ["", "cat", "cart", "cut", "act", "coat", "dog", "ねこ", "ねずみ"] {
+            trie.insert(key);
+        }
+        let found = trie.fuzzy_search("cat", 1);
+        assert_eq!(vec![("cart".to_string(), 1), ("cat".to_string(), 0), ("coat".to_string(), 1), ("cut".to_string(), 1)], found);
+        assert_eq!(vec![("".to_string(), 1), ("ねこ".to_string(), 1)], trie.fuzzy_search("ね", 1));
+        assert_eq!(vec![("ねずみ".to_string(), 1)], trie.fuzzy_search("ねすみ", 1));
+
+        let queries = vec((conformance::key_of(&['a', 'b', 'あ'], 6), 0..3_usize), 1..20);
+        proptest!(ProptestConfig::with_cases(32), |(keys in keys(), queries in queries)| {
+            let trie: T = build(&keys);
+            for (query, max_edits) in queries {
+                let expected: Vec<(String, usize)> = trie.keys()
+                    .map(|key| { let distance = conformance::edit_distance(&key, &query); (key, distance) })
+                    .filter(|(_, distance)| *distance <= max_edits)
+                    .collect();
+                prop_assert_eq!(&expected, &trie.fuzzy_search(&query, max_edits));
+                let by_default: Vec<(String, usize)> = automaton::filter(Levenshtein::new(&query, max_edits), trie.keys().map(|key| (key, ())))
+                    .into_iter()
+                    .map(|(key, _, distance)| (key, distance))
+                    .collect();
+                prop_assert_eq!(expected, by_default);
+            }
+        });
+    }
+
+    #[test]
+    fn hamming_search<T: TrieMut + Default>() {
+        let mut trie = T::default();
+        for key in ["", "ACGT", "ACGA", "TCGA", "ACG", "ACGTA", "あいう"] {
+            trie.insert(key);
+        }
+        assert_eq!(vec![("ACGA".to_string(), 1), ("ACGT".to_string(), 0)], trie.hamming_search("ACGT", 1));
+        assert_eq!(vec![("ACGA".to_string(), 1), ("ACGT".to_string(), 2), ("TCGA".to_string(), 0)], trie.hamming_search("TCGA", 2));
+        assert_eq!(vec![("".to_string(), 0)], trie.hamming_search("", 3));
+        assert_eq!(vec![("あいう".to_string(), 1)], trie.hamming_search("あえう", 1));
+
+        let queries = vec((conformance::key_of(&['a', 'b', 'あ'], 6), 0..3_usize), 1..20);
+        proptest!(ProptestConfig::with_cases(32), |(keys in keys(), queries in queries)| {
+            let trie: T = build(&keys);
+            for (query, max_mismatches) in queries {
+                let expected: Vec<(String, usize)> = trie.keys()
+                    .filter_map(|key| conformance::hamming_distance(&key, &query).map(|mismatches| (key, mismatches)))
+                    .filter(|(_, mismatches)| *mismatches <= max_mismatches)
+                    .collect();
+                prop_assert_eq!(&expected, &trie.hamming_search(&query, max_mismatches));
+                let by_default: Vec<(String, usize)> = automaton::filter(Hamming::new(&query, max_mismatches), trie.keys().map(|key| (key, ())))
+                    .into_iter()
+                    .map(|(key, _, mismatches)| (key, mismatches))
+                    .collect();
+                prop_assert_eq!(expected, by_default);
+            }
+        });
+    }
+
+    #[test]
+    fn search_pattern<T: TrieMut + Default>() {
+        let mut trie = T::default();
+        for key in ["", "cat", "cart", "cut", "act", "coat", "dog", "ねこ", "ねずみ"] {
+            trie.insert(key);
+        }
+        assert_eq!(vec!["cat", "cut"], trie.search_pattern("c?t"));
+        assert_eq!(vec!["cart", "cat", "coat", "cut"], trie.search_pattern("c*t"));
+        assert_eq!(vec!["act", "cart", "cat", "coat", "cut"], trie.search_pattern("*t"));
+        assert_eq!(vec!["ねずみ"], trie.search_pattern("ね?み"));
+        assert_eq!(vec![""], trie.search_pattern(""));
+        assert_eq!(trie.keys().collect::<Vec<_>>(), trie.search_pattern("**"));
+        assert!(trie.search_pattern("c?").is_empty());
+
+        let patterns = vec(conformance::key_of(&['a', 'あ', '?', '*'], 6), 1..20);
+        proptest!(ProptestConfig::with_cases(32), |(keys in keys(), patterns in patterns)| {
+            let trie: T = build(&keys);
+            for pattern in patterns {
+                let chars: Vec<char> = pattern.chars().collect();
+                let expected: Vec<String> = trie.keys()
+                    .filter(|key| conformance::glob(&chars, &key.chars().collect::<Vec<_>>()))
+                    .collect();
+                prop_assert_eq!(&expected, &trie.search_pattern(&pattern));
+                let by_default: Vec<String> = automaton::filter(Wildcard::new(&pattern), trie.keys().map(|key| (key, ())))
+                    .into_iter()
+                    .map(|(key, _, _)| key)
+                    .collect();
+                prop_assert_eq!(expected, by_default);
+            }
+        });
+    }
+
+    #[test]
+    fn cursor<T: TrieMut + Default>() {
+        let mut trie = T::default();
+        for key in ["a", "ab", "abcd", "b", "あい"] {
+            trie.insert(key);
+        }
+        let mut cursor = trie.cursor();
+        assert!(!cursor.is_leaf());
+        assert!(cursor.step('a'));
+        assert!(cursor.is_leaf());
+        let saved = cursor.clone();
+        assert!(cursor.step('b'));
+        assert!(cursor.step('c'));
+        assert!(!cursor.is_leaf());
+        assert!(!cursor.step('x'));
+        assert!(cursor.step('d'));
+        assert!(cursor.is_leaf());
+        assert!(!cursor.step('e'));
+        assert!(cursor.is_leaf());
+
+        let mut cursor = saved;
+        assert!(!cursor.step('c'));
+        assert!(cursor.step('b'));
+        assert!(cursor.is_leaf());
+
+        let mut cursor = trie.cursor();
+        assert!(cursor.step('あ'));
+        assert!(!cursor.step('う'));
+        assert!(cursor.step('い'));
+        assert!(cursor.is_leaf());
+
+        let queries = vec(conformance::key_of(&['a', 'b', 'あ'], 6), 1..20);
+        proptest!(ProptestConfig::with_cases(32), |(keys in keys(), queries in queries)| {
+            let trie: T = build(&keys);
+            for query in queries {
+                let mut cursor = trie.cursor();
+                prop_assert_eq!(trie.contains(""), cursor.is_leaf());
+                for (i, c) in query.char_indices() {
+                    let end = i + c.len_utf8();
+                    prop_assert_eq!(trie.count_prefix(&query[0..end]) > 0, cursor.step(c));
+                    if trie.count_prefix(&query[0..end]) == 0 {
+                        break;
+                    }
+                    prop_assert_eq!(trie.contains(&query[0..end]), cursor.is_leaf());
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn range<T: TrieMut + Default>() {
+        let mut trie = T::default();
+        for key in ["", "a", "ab", "abc", "b", "ba", "c", "あ", "あい"] {
+            trie.insert(key);
+        }
+        assert_eq!(vec!["ab", "abc", "b"], trie.range("ab".."ba").collect::<Vec<_>>());
+        assert_eq!(vec!["ab", "abc", "b", "ba"], trie.range("aa"..="ba").collect::<Vec<_>>());
+        assert_eq!(vec!["", "a"], trie.range(.."ab").collect::<Vec<_>>());
+        assert_eq!(vec!["c", "あ", "あい"], trie.range("bb"..).collect::<Vec<_>>());
+        assert_eq!(trie.keys().collect::<Vec<_>>(), trie.range(..).collect::<Vec<_>>());
+        assert_eq!(0, trie.range("b".."b").count());
+
+        let bound = prop_oneof![
+            conformance::key_of(&['a', 'b', 'あ'], 5).prop_map(Bound::Included),
+            conformance::key_of(&['a', 'b', 'あ'], 5).prop_map(Bound::Excluded),
+            Just(Bound::Unbounded),
+        ];
+        proptest!(ProptestConfig::with_cases(32), |(keys in keys(), ranges in vec((bound.clone(), bound.clone()), 1..20))| {
+            let trie: T = build(&keys);
+            let expected: BTreeSet<String> = keys.into_iter().collect();
+            for (start, end) in ranges {
+                let bounds = (start.as_ref().map(|s| s.as_str()), end.as_ref().map(|s| s.as_str()));
+                let in_range: Vec<String> = expected.iter().filter(|key| bounds.contains(&key.as_str())).cloned().collect();
+                prop_assert_eq!(in_range, trie.range(bounds).collect::<Vec<_>>());
+            }
+        });
+    }
 }
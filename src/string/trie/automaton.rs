@@ -0,0 +1,324 @@
+// Automata run along the keys of a trie in depth-first order, keeping a state for each unit (char or byte)
+// of the current key so that the traversal can backtrack to any depth.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::Bound;
+use core::ops::RangeBounds;
+
+pub trait Automaton {
+    // the value reported for an accepted key
+    type Output;
+
+    // the number of units of the current key
+    fn depth(&self) -> usize;
+
+    fn truncate(&mut self, depth: usize);
+
+    // appends c to the current key, and returns whether a key starting with it can be accepted
+    fn push(&mut self, c: char) -> bool;
+
+    // appends a unit without changing the state, for a byte in the middle of a char
+    fn repeat(&mut self);
+
+    fn accept(&self) -> Option<Self::Output>;
+
+    // appends the last byte of key, which is the current key bytes, pushing a multibyte char when it completes
+    fn push_byte(&mut self, key: &[u8]) -> bool {
+        let start = key.iter().rposition(|b| b & 0xc0 != 0x80).unwrap_or(0);
+        match core::str::from_utf8(&key[start..]) {
+            Ok(s) => self.push(s.chars().next().unwrap()),
+            Err(_) => {
+                self.repeat();
+                true
+            }
+        }
+    }
+}
+
+// runs the automaton along entries given in any order, reusing the states for the common prefix with the previous key
+pub fn filter<A: Automaton, T, I: Iterator<Item = (String, T)>>(mut automaton: A, entries: I) -> Vec<(String, T, A::Output)> {
+    let mut last: Vec<char> = vec![];
+    let mut found = vec![];
+    for (key, value) in entries {
+        let chars: Vec<char> = key.chars().collect();
+        let common = last.iter().zip(&chars).take_while(|(a, b)| a == b).count();
+        automaton.truncate(common);
+        let start = automaton.depth();
+        if chars[start..].iter().all(|c| automaton.push(*c)) {
+            if let Some(output) = automaton.accept() {
+                found.push((key, value, output));
+            }
+        }
+        last = chars;
+    }
+    found
+}
+
+// the rows of the dynamic programming of the edit distance to a query
+pub struct Levenshtein {
+    query: Vec<char>,
+    max_edits: usize,
+    // rows[d][j] is the edit distance between the first d units of the key and query[0..j]
+    rows: Vec<Vec<usize>>,
+}
+
+impl Levenshtein {
+    pub fn new(query: &str, max_edits: usize) -> Self {
+        let query: Vec<char> = query.chars().collect();
+        let first = (0..=query.len()).collect();
+        Levenshtein { query, max_edits, rows: vec![first] }
+    }
+}
+
+impl Automaton for Levenshtein {
+    type Output = usize;
+
+    fn depth(&self) -> usize {
+        self.rows.len() - 1
+    }
+
+    fn truncate(&mut self, depth: usize) {
+        self.rows.truncate(depth + 1);
+    }
+
+    fn push(&mut self, c: char) -> bool {
+        let last = self.rows.last().unwrap();
+        let mut row = Vec::with_capacity(last.len());
+        row.push(last[0] + 1);
+        for (j, q) in self.query.iter().enumerate() {
+            let replace = last[j] + (*q != c) as usize;
+            row.push(replace.min(last[j + 1] + 1).min(row[j] + 1));
+        }
+        let reachable = row.iter().any(|d| *d <= self.max_edits);
+        self.rows.push(row);
+        reachable
+    }
+
+    fn repeat(&mut self) {
+        let row = self.rows.last().unwrap().clone();
+        self.rows.push(row);
+    }
+
+    // the edit distance, if within max_edits
+    fn accept(&self) -> Option<usize> {
+        let d = *self.rows.last().unwrap().last().unwrap();
+        if d <= self.max_edits {
+            Some(d)
+        } else {
+            None
+        }
+    }
+}
+
+// the number of mismatches against a query of the same length
+pub struct Hamming {
+    query: Vec<char>,
+    max_mismatches: usize,
+    // states[d] is the number of chars and mismatches in the first d units of the key
+    states: Vec<(usize, usize)>,
+}
+
+impl Hamming {
+    pub fn new(query: &str, max_mismatches: usize) -> Self {
+        Hamming { query: query.chars().collect(), max_mismatches, states: vec![(0, 0)] }
+    }
+}
+
+impl Automaton for Hamming {
+    type Output = usize;
+
+    fn depth(&self) -> usize {
+        self.states.len() - 1
+    }
+
+    fn truncate(&mut self, depth: usize) {
+        self.states.truncate(depth + 1);
+    }
+
+    fn push(&mut self, c: char) -> bool {
+        let (len, mismatches) = *self.states.last().unwrap();
+        let mismatches = match self.query.get(len) {
+            Some(q) => mismatches + (*q != c) as usize,
+            // longer than the query
+            None => usize::MAX,
+        };
+        self.states.push((len + 1, mismatches));
+        mismatches <= self.max_mismatches
+    }
+
+    fn repeat(&mut self) {
+        let state = *self.states.last().unwrap();
+        self.states.push(state);
+    }
+
+    // the number of mismatches, if the key is as long as the query
+    fn accept(&self) -> Option<usize> {
+        let (len, mismatches) = *self.states.last().unwrap();
+        if len == self.query.len() && mismatches <= self.max_mismatches {
+            Some(mismatches)
+        } else {
+            None
+        }
+    }
+}
+
+// the sets of positions in a glob pattern with `?` and `*`, reachable by the prefixes of the key
+pub struct Wildcard {
+    pattern: Vec<char>,
+    // sets[d][i] is whether the first d units of the key match pattern[0..i]
+    sets: Vec<Vec<bool>>,
+}
+
+impl Wildcard {
+    pub fn new(pattern: &str) -> Self {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let mut first = vec![false; pattern.len() + 1];
+        first[0] = true;
+        let mut wildcard = Wildcard { pattern, sets: vec![] };
+        wildcard.close(&mut first);
+        wildcard.sets.push(first);
+        wildcard
+    }
+
+    // a star matches the empty string
+    fn close(&self, set: &mut [bool]) {
+        for i in 0..self.pattern.len() {
+            if set[i] && self.pattern[i] == '*' {
+                set[i + 1] = true;
+            }
+        }
+    }
+}
+
+impl Automaton for Wildcard {
+    type Output = ();
+
+    fn depth(&self) -> usize {
+        self.sets.len() - 1
+    }
+
+    fn truncate(&mut self, depth: usize) {
+        self.sets.truncate(depth + 1);
+    }
+
+    fn push(&mut self, c: char) -> bool {
+        let last = self.sets.last().unwrap();
+        let mut set = vec![false; last.len()];
+        for (i, p) in self.pattern.iter().enumerate() {
+            if last[i] {
+                match p {
+                    '*' => set[i] = true,
+                    '?' => set[i + 1] = true,
+                    _ => set[i + 1] |= *p == c,
+                }
+            }
+        }
+        self.close(&mut set);
+        let reachable = set.contains(&true);
+        self.sets.push(set);
+        reachable
+    }
+
+    fn repeat(&mut self) {
+        let set = self.sets.last().unwrap().clone();
+        self.sets.push(set);
+    }
+
+    fn accept(&self) -> Option<()> {
+        if *self.sets.last().unwrap().last().unwrap() {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
+
+// the bounds of a range of keys, compared with the prefixes of the key
+pub struct KeyRange {
+    start: Bound<Vec<char>>,
+    end: Bound<Vec<char>>,
+    // for each depth, the order of the current key against the prefix of start of the same length,
+    // that for end, and the number of chars of the key
+    states: Vec<(Ordering, Ordering, usize)>,
+}
+
+impl KeyRange {
+    pub fn new<'a, R: RangeBounds<&'a str>>(range: R) -> Self {
+        let start = range.start_bound().map(|s| s.chars().collect::<Vec<_>>());
+        let end = range.end_bound().map(|s| s.chars().collect::<Vec<_>>());
+        // an unbounded side is satisfied by any key
+        let first = (
+            if let Bound::Unbounded = start { Ordering::Greater } else { Ordering::Equal },
+            if let Bound::Unbounded = end { Ordering::Less } else { Ordering::Equal },
+            0,
+        );
+        KeyRange { start, end, states: vec![first] }
+    }
+}
+
+// the order of the key with c appended, given the order of the key of len chars
+fn compare_prefix(order: Ordering, bound: &Bound<Vec<char>>, len: usize, c: char) -> Ordering {
+    match (order, bound) {
+        (Ordering::Equal, Bound::Included(bound) | Bound::Excluded(bound)) => match bound.get(len) {
+            Some(b) => c.cmp(b),
+            // longer than the bound, which is a prefix of the key
+            None => Ordering::Greater,
+        },
+        _ => order,
+    }
+}
+
+impl Automaton for KeyRange {
+    type Output = ();
+
+    fn depth(&self) -> usize {
+        self.states.len() - 1
+    }
+
+    fn truncate(&mut self, depth: usize) {
+        self.states.truncate(depth + 1);
+    }
+
+    fn push(&mut self, c: char) -> bool {
+        let (start, end, len) = *self.states.last().unwrap();
+        let start = compare_prefix(start, &self.start, len, c);
+        let end = compare_prefix(end, &self.end, len, c);
+        self.states.push((start, end, len + 1));
+        // no extension of a key less than a prefix of start reaches start, and so on
+        let reachable_to_end = match (end, &self.end) {
+            (Ordering::Less, _) => true,
+            (Ordering::Equal, Bound::Excluded(end)) => len + 1 < end.len(),
+            (Ordering::Equal, _) => true,
+            (Ordering::Greater, _) => false,
+        };
+        start != Ordering::Less && reachable_to_end
+    }
+
+    fn repeat(&mut self) {
+        let state = *self.states.last().unwrap();
+        self.states.push(state);
+    }
+
+    fn accept(&self) -> Option<()> {
+        let (start, end, len) = *self.states.last().unwrap();
+        let after_start = match (start, &self.start) {
+            (Ordering::Greater, _) => true,
+            (Ordering::Equal, Bound::Included(start)) => len == start.len(),
+            _ => false,
+        };
+        let before_end = match (end, &self.end) {
+            (Ordering::Less, _) => true,
+            (Ordering::Equal, Bound::Included(_)) => true,
+            (Ordering::Equal, Bound::Excluded(end)) => len < end.len(),
+            _ => false,
+        };
+        if after_start && before_end {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,45 @@
+// Helpers for the little-endian binary format of the tries.
+
+use std::io;
+use std::io::Read;
+
+pub use crate::serialize::invalid_data;
+pub use crate::serialize::read_bytes;
+pub use crate::serialize::read_len;
+pub use crate::serialize::read_u64;
+pub use crate::serialize::write_bytes;
+pub use crate::serialize::write_u64;
+
+pub fn check_magic<R: Read>(r: &mut R, magic: &[u8; 8]) -> io::Result<()> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    if &buf != magic {
+        return Err(invalid_data("unexpected magic number"));
+    }
+    Ok(())
+}
+
+// a cursor over a byte slice, borrowing the parts instead of copying them
+pub struct Slice<'a> {
+    pub rest: &'a [u8],
+}
+
+impl <'a> Slice<'a> {
+    pub fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.rest.len() < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let (head, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Ok(head)
+    }
+
+    pub fn u64(&mut self) -> io::Result<u64> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn len(&mut self) -> io::Result<usize> {
+        usize::try_from(self.u64()?).map_err(|_| invalid_data("length overflow"))
+    }
+}
@@ -0,0 +1,454 @@
+use super::Trie;
+use super::TrieCursor;
+use super::TrieMut;
+use crate::space::SpaceUsage;
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+const DEFAULT_THRESHOLD: usize = 32;
+
+enum Node {
+    // the sorted suffixes of the keys under the node
+    Bucket(Vec<Vec<u8>>),
+    Inner {
+        // sorted by the byte
+        children: Vec<(u8, Node)>,
+        is_leaf: bool,
+        // the number of keys in the subtree
+        count: usize,
+    },
+}
+
+impl Node {
+    fn count(&self) -> usize {
+        match self {
+            Node::Bucket(suffixes) => suffixes.len(),
+            Node::Inner { count, .. } => *count,
+        }
+    }
+
+    fn child(&self, b: u8) -> Option<&Node> {
+        match self {
+            Node::Bucket(_) => None,
+            Node::Inner { children, .. } => {
+                let i = children.binary_search_by_key(&b, |(label, _)| *label).ok()?;
+                Some(&children[i].1)
+            }
+        }
+    }
+
+    // returns whether the key is newly inserted
+    fn insert(&mut self, key: &[u8], threshold: usize) -> bool {
+        let inserted = match self {
+            Node::Bucket(suffixes) => {
+                match suffixes.binary_search_by(|suffix| suffix.as_slice().cmp(key)) {
+                    Ok(_) => return false,
+                    Err(i) => suffixes.insert(i, key.to_vec()),
+                }
+                if suffixes.len() > threshold {
+                    self.burst(threshold);
+                }
+                return true;
+            }
+            Node::Inner { children, is_leaf, .. } => match key.split_first() {
+                None => !core::mem::replace(is_leaf, true),
+                Some((b, rest)) => {
+                    let i = match children.binary_search_by_key(b, |(label, _)| *label) {
+                        Ok(i) => i,
+                        Err(i) => {
+                            children.insert(i, (*b, Node::Bucket(vec![])));
+                            i
+                        }
+                    };
+                    children[i].1.insert(rest, threshold)
+                }
+            },
+        };
+        if let (true, Node::Inner { count, .. }) = (inserted, self) {
+            *count += 1;
+        }
+        inserted
+    }
+
+    // returns whether the key existed
+    fn remove(&mut self, key: &[u8]) -> bool {
+        match self {
+            Node::Bucket(suffixes) => {
+                match suffixes.binary_search_by(|suffix| suffix.as_slice().cmp(key)) {
+                    Ok(i) => {
+                        suffixes.remove(i);
+                        true
+                    }
+                    Err(_) => false,
+                }
+            }
+            Node::Inner { children, is_leaf, count } => {
+                let removed = match key.split_first() {
+                    None => core::mem::replace(is_leaf, false),
+                    Some((b, rest)) => match children.binary_search_by_key(b, |(label, _)| *label) {
+                        Ok(i) => {
+                            let removed = children[i].1.remove(rest);
+                            if children[i].1.count() == 0 {
+                                children.remove(i);
+                            }
+                            removed
+                        }
+                        Err(_) => false,
+                    },
+                };
+                if removed {
+                    *count -= 1;
+                }
+                removed
+            }
+        }
+    }
+
+    // replaces the bucket with a node having a bucket for each first byte of the suffixes
+    fn burst(&mut self, threshold: usize) {
+        let suffixes = match self {
+            Node::Bucket(suffixes) => core::mem::take(suffixes),
+            Node::Inner { .. } => return,
+        };
+        let count = suffixes.len();
+        let mut is_leaf = false;
+        let mut children: Vec<(u8, Node)> = vec![];
+        for suffix in suffixes {
+            match suffix.split_first() {
+                None => is_leaf = true,
+                Some((b, rest)) => {
+                    if children.last().is_none_or(|(label, _)| label != b) {
+                        children.push((*b, Node::Bucket(vec![])));
+                    }
+                    if let Some((_, Node::Bucket(bucket))) = children.last_mut() {
+                        bucket.push(rest.to_vec());
+                    }
+                }
+            }
+        }
+        for (_, child) in &mut children {
+            if child.count() > threshold {
+                child.burst(threshold);
+            }
+        }
+        *self = Node::Inner { children, is_leaf, count };
+    }
+}
+
+// the suffixes in the bucket starting with prefix
+fn bucket_range<'a>(suffixes: &'a [Vec<u8>], prefix: &[u8]) -> &'a [Vec<u8>] {
+    let s = suffixes.partition_point(|suffix| suffix.as_slice() < prefix);
+    let e = s + suffixes[s..].partition_point(|suffix| suffix.starts_with(prefix));
+    &suffixes[s..e]
+}
+
+/// 少数のキーをソートした配列 (バケット) にまとめ、大きくなったバケットをトライ木のノードに分割 (バースト) するトライ木
+///
+/// キーの末尾部分はバケットの中に連続して並ぶため、1 文字ごとにノードを持つ [`super::NaiveTrie`] に比べて
+/// ノードの個数と使用メモリが小さく、ランダムな順序でキーを追加する場合もキャッシュの効率が良くなります。
+/// バケットの大きさが閾値を超えると、先頭のバイトごとのバケットに分割します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::BurstTrie;
+/// use rust_study::string::trie::Trie;
+/// use rust_study::string::trie::TrieMut;
+/// let mut trie = BurstTrie::with_threshold(2);
+/// assert!(trie.insert("tea"));
+/// assert!(trie.insert("ten"));
+/// assert_eq!(1, trie.size());
+/// // the bucket bursts into a node, and so does the bucket under "t"
+/// assert!(trie.insert("to"));
+/// assert_eq!(4, trie.size());
+/// assert!(!trie.insert("ten"));
+/// assert!(trie.contains("to"));
+/// assert_eq!(vec!["tea", "ten"], trie.predict("te").collect::<Vec<_>>());
+/// assert!(trie.remove("tea"));
+/// assert_eq!(2, trie.len());
+/// ```
+pub struct BurstTrie {
+    root: Node,
+    threshold: usize,
+}
+
+impl BurstTrie {
+    /// バケットの大きさの閾値を 32 として、空のトライ木を構築します。
+    pub fn new() -> Self {
+        Self::with_threshold(DEFAULT_THRESHOLD)
+    }
+
+    /// バケットの大きさの閾値を `threshold` として、空のトライ木を構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is zero.
+    pub fn with_threshold(threshold: usize) -> Self {
+        assert!(threshold > 0, "threshold should be positive");
+        BurstTrie { root: Node::Bucket(vec![]), threshold }
+    }
+
+    /// バケットの大きさの閾値を返します。
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// ノードとバケットの個数を返します。
+    pub fn size(&self) -> usize {
+        let mut stack = vec![&self.root];
+        let mut size = 0;
+        while let Some(node) = stack.pop() {
+            size += 1;
+            if let Node::Inner { children, .. } = node {
+                stack.extend(children.iter().map(|(_, child)| child));
+            }
+        }
+        size
+    }
+
+    // the deepest node on the path of s, and the rest of s in its bucket
+    fn find<'a, 'b>(&'a self, s: &'b [u8]) -> (&'a Node, &'b [u8]) {
+        let mut node = &self.root;
+        let mut rest = s;
+        while let Some((b, tail)) = rest.split_first() {
+            match node.child(*b) {
+                Some(child) => {
+                    node = child;
+                    rest = tail;
+                }
+                None => break,
+            }
+        }
+        (node, rest)
+    }
+}
+
+impl Default for BurstTrie {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Trie for BurstTrie {
+    fn contains(&self, s: &str) -> bool {
+        match self.find(s.as_bytes()) {
+            (Node::Bucket(suffixes), rest) => suffixes.binary_search_by(|suffix| suffix.as_slice().cmp(rest)).is_ok(),
+            (Node::Inner { is_leaf, .. }, rest) => rest.is_empty() && *is_leaf,
+        }
+    }
+
+    fn prefix<'a>(&self, s:&'a str) -> &'a str {
+        let len = self.common_prefixes(s).last().map_or(0, |key| key.len());
+        &s[0..len]
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let bytes = query.as_bytes();
+        let mut found = vec![];
+        let mut node = &self.root;
+        let mut depth = 0;
+        loop {
+            match node {
+                Node::Bucket(suffixes) => {
+                    // the prefixes of the rest are sorted by the length
+                    let rest = &bytes[depth..];
+                    let end = suffixes.partition_point(|suffix| suffix.as_slice() <= rest);
+                    found.extend(suffixes[0..end].iter()
+                        .filter(|suffix| rest.starts_with(suffix))
+                        .map(|suffix| &query[0..depth + suffix.len()]));
+                    break;
+                }
+                Node::Inner { is_leaf, .. } => {
+                    // a stored key always ends at a char boundary of the query
+                    if *is_leaf {
+                        found.push(&query[0..depth]);
+                    }
+                    match bytes.get(depth).and_then(|b| node.child(*b)) {
+                        Some(child) => {
+                            node = child;
+                            depth += 1;
+                        }
+                        None => break,
+                    }
+                }
+            }
+        }
+        found.into_iter()
+    }
+
+    fn len(&self) -> usize {
+        self.root.count()
+    }
+
+    fn count_prefix(&self, prefix: &str) -> usize {
+        match self.find(prefix.as_bytes()) {
+            (Node::Bucket(suffixes), rest) => bucket_range(suffixes, rest).len(),
+            (node, []) => node.count(),
+            _ => 0,
+        }
+    }
+
+    fn predict(&self, prefix: &str) -> impl Iterator<Item = String> + '_ {
+        // (the node and its path) or the key in a bucket
+        let mut stack: Vec<(Option<&Node>, Vec<u8>)> = vec![];
+        let bytes = prefix.as_bytes();
+        match self.find(bytes) {
+            (Node::Bucket(suffixes), rest) => {
+                let path = &bytes[0..bytes.len() - rest.len()];
+                for suffix in bucket_range(suffixes, rest).iter().rev() {
+                    stack.push((None, [path, suffix].concat()));
+                }
+            }
+            (node, []) => stack.push((Some(node), bytes.to_vec())),
+            _ => {}
+        }
+        core::iter::from_fn(move || {
+            while let Some((node, key)) = stack.pop() {
+                match node {
+                    None => return Some(String::from_utf8(key).expect("keys are valid UTF-8")),
+                    Some(Node::Bucket(suffixes)) => {
+                        for suffix in suffixes.iter().rev() {
+                            stack.push((None, [key.as_slice(), suffix].concat()));
+                        }
+                    }
+                    Some(Node::Inner { children, is_leaf, .. }) => {
+                        for (b, child) in children.iter().rev() {
+                            let mut child_key = key.clone();
+                            child_key.push(*b);
+                            stack.push((Some(child), child_key));
+                        }
+                        if *is_leaf {
+                            return Some(String::from_utf8(key).expect("keys are valid UTF-8"));
+                        }
+                    }
+                }
+            }
+            None
+        })
+    }
+
+    fn cursor(&self) -> impl TrieCursor<'_> {
+        Cursor { node: &self.root, rest: vec![] }
+    }
+}
+
+impl TrieMut for BurstTrie {
+    fn insert(&mut self, s: &str) -> bool {
+        self.root.insert(s.as_bytes(), self.threshold)
+    }
+
+    fn remove(&mut self, s: &str) -> bool {
+        self.root.remove(s.as_bytes())
+    }
+}
+
+#[derive(Clone)]
+struct Cursor<'a> {
+    node: &'a Node,
+    // the bytes passed in the bucket
+    rest: Vec<u8>,
+}
+
+impl <'a> TrieCursor<'a> for Cursor<'a> {
+    type Value = ();
+
+    fn step(&mut self, c: char) -> bool {
+        let mut buf = [0; 4];
+        let mut node = self.node;
+        let mut rest = self.rest.clone();
+        for b in c.encode_utf8(&mut buf).bytes() {
+            match node {
+                Node::Inner { .. } => match node.child(b) {
+                    Some(child) => node = child,
+                    None => return false,
+                },
+                Node::Bucket(_) => rest.push(b),
+            }
+        }
+        if let Node::Bucket(suffixes) = node {
+            if bucket_range(suffixes, &rest).is_empty() {
+                return false;
+            }
+        }
+        self.node = node;
+        self.rest = rest;
+        true
+    }
+
+    fn value(&self) -> Option<&'a ()> {
+        let found = match self.node {
+            Node::Bucket(suffixes) => suffixes.binary_search(&self.rest).is_ok(),
+            Node::Inner { is_leaf, .. } => *is_leaf,
+        };
+        if found {
+            Some(&())
+        } else {
+            None
+        }
+    }
+}
+
+impl SpaceUsage for Node {
+    fn heap_size_in_bytes(&self) -> usize {
+        match self {
+            Node::Bucket(suffixes) => suffixes.heap_size_in_bytes(),
+            Node::Inner { children, .. } => children.heap_size_in_bytes(),
+        }
+    }
+}
+
+impl SpaceUsage for BurstTrie {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("root", &self.root)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::trie::NaiveTrie;
+    use crate::string::trie::conformance;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    #[test]
+    fn same_as_btree_set() {
+        for threshold in [1, 4, 32] {
+            conformance::check_dynamic(|| BurstTrie::with_threshold(threshold));
+        }
+    }
+
+    #[test]
+    fn smaller_than_naive_trie() {
+        proptest!(|(keys in vec(conformance::key(5), 100..300))| {
+            let trie: BurstTrie = keys.iter().fold(BurstTrie::new(), |mut trie, key| { trie.insert(key); trie });
+            let naive: NaiveTrie = keys.iter().collect();
+            prop_assert!(trie.size() < naive.size());
+        });
+    }
+
+    #[test]
+    fn burst() {
+        let mut trie = BurstTrie::with_threshold(3);
+        for key in ["", "a", "ab", "b"] {
+            trie.insert(key);
+        }
+        // the root with the empty key, and the buckets of "a" and "b"
+        assert_eq!(3, trie.size());
+        assert!(trie.contains(""));
+        assert_eq!(vec!["", "a", "ab"], trie.common_prefixes("abc").collect::<Vec<_>>());
+        for key in ["", "a", "ab", "b"] {
+            assert!(trie.remove(key));
+        }
+        assert!(trie.is_empty());
+        assert_eq!(1, trie.size());
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_threshold() {
+        BurstTrie::with_threshold(0);
+    }
+}
@@ -0,0 +1,475 @@
+use super::automaton::Automaton;
+use super::automaton::Hamming;
+use super::automaton::KeyRange;
+use super::automaton::Levenshtein;
+use super::automaton::Wildcard;
+use super::SetOp;
+use super::Trie;
+use super::TrieCursor;
+use super::TrieMut;
+use super::dot;
+use super::dot::DotWriter;
+use super::dot::TreeWriter;
+use crate::space::SpaceUsage;
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::RangeBounds;
+
+struct Node {
+    // sorted by the byte
+    children: Vec<(u8, u32)>,
+    is_leaf: bool,
+    // the number of keys in the subtree
+    count: usize,
+}
+
+impl Node {
+    fn new() -> Self {
+        Node { children: Vec::new(), is_leaf: false, count: 0 }
+    }
+}
+
+const ROOT: u32 = 0;
+
+/// UTF-8 のバイト列を単位とするトライ木
+///
+/// ノードを 1 本の配列に並べ、各ノードの子をバイトでソートした `(バイト, ノード番号)` の配列で保持します。
+/// 子の検索は二分探索で行います。
+/// ノードごとに連想配列を持つ [`super::NaiveTrie`] に比べて使用メモリが小さく、
+/// キーは常に辞書順 (UTF-8 のバイト列の順序は文字の順序と一致します) に列挙されます。
+///
+/// 取り除いたノードの番号は再利用されます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::ByteTrie;
+/// use rust_study::string::trie::Trie;
+/// use rust_study::string::trie::TrieMut;
+/// let mut trie = ByteTrie::new();
+/// assert!(trie.append("foo"));
+/// assert!(trie.append("foobar"));
+/// assert!(trie.append("あいう"));
+/// assert!(!trie.append("foo"));
+/// assert!(trie.contains("foobar"));
+/// assert_eq!("foo", trie.prefix("foobaz"));
+/// assert_eq!(vec!["foo", "foobar"], trie.predict("fo").collect::<Vec<_>>());
+/// assert!(trie.remove("foobar"));
+/// assert_eq!(2, trie.len());
+/// ```
+pub struct ByteTrie {
+    nodes: Vec<Node>,
+    // ids of the removed nodes
+    free: Vec<u32>,
+}
+
+impl ByteTrie {
+    /// 空のトライ木を構築します。
+    pub fn new() -> Self {
+        ByteTrie { nodes: vec![Node::new()], free: vec![] }
+    }
+
+    /// キー `s` を追加します。新たに追加した場合に、 `true` を返します。
+    pub fn append(&mut self, s: &str) -> bool {
+        if self.contains(s) {
+            return false;
+        }
+        let mut id = ROOT;
+        self.nodes[id as usize].count += 1;
+        for b in s.bytes() {
+            id = match self.child_index(id, b) {
+                Ok(i) => self.nodes[id as usize].children[i].1,
+                Err(i) => {
+                    let child = self.alloc();
+                    self.nodes[id as usize].children.insert(i, (b, child));
+                    child
+                }
+            };
+            self.nodes[id as usize].count += 1;
+        }
+        self.nodes[id as usize].is_leaf = true;
+        true
+    }
+
+    /// ノードの個数を返します。
+    pub fn size(&self) -> usize {
+        self.nodes.len() - self.free.len()
+    }
+
+    /// `self` と `other` の少なくとも一方に含まれるキーからなるトライ木を返します。
+    ///
+    /// 2 つのトライ木を根から同時にたどって構築するため、キーを取り出して追加し直す必要はありません。
+    pub fn union(&self, other: &ByteTrie) -> ByteTrie {
+        self.combine(other, SetOp::Union)
+    }
+
+    /// `self` と `other` の両方に含まれるキーからなるトライ木を返します。
+    pub fn intersection(&self, other: &ByteTrie) -> ByteTrie {
+        self.combine(other, SetOp::Intersection)
+    }
+
+    /// `self` に含まれ `other` に含まれないキーからなるトライ木を返します。
+    pub fn difference(&self, other: &ByteTrie) -> ByteTrie {
+        self.combine(other, SetOp::Difference)
+    }
+
+    fn combine(&self, other: &ByteTrie, op: SetOp) -> ByteTrie {
+        let mut trie = ByteTrie::new();
+        trie.fill(ROOT, Some((self, ROOT)), Some((other, ROOT)), op);
+        trie
+    }
+
+    // builds the subtree of id from the node a of a trie and the node b of the other
+    fn fill(&mut self, id: u32, a: Option<(&ByteTrie, u32)>, b: Option<(&ByteTrie, u32)>, op: SetOp) {
+        let node_a = a.map(|(trie, id)| &trie.nodes[id as usize]);
+        let node_b = b.map(|(trie, id)| &trie.nodes[id as usize]);
+        let is_leaf = op.keeps(node_a.is_some_and(|node| node.is_leaf), node_b.is_some_and(|node| node.is_leaf));
+        let mut count = is_leaf as usize;
+        let mut labels: Vec<u8> = node_a.into_iter().chain(node_b)
+            .flat_map(|node| node.children.iter().map(|(label, _)| *label))
+            .collect();
+        labels.sort();
+        labels.dedup();
+        for label in labels {
+            let child_a = a.and_then(|(trie, id)| Some((trie, trie.child(id, label)?)));
+            let child_b = b.and_then(|(trie, id)| Some((trie, trie.child(id, label)?)));
+            if !op.visits(child_a.is_some(), child_b.is_some()) {
+                continue;
+            }
+            // the nodes are allocated in preorder, so an empty subtree is at the end
+            let child = self.nodes.len();
+            self.nodes.push(Node::new());
+            self.fill(child as u32, child_a, child_b, op);
+            if self.nodes[child].count == 0 {
+                self.nodes.truncate(child);
+            } else {
+                count += self.nodes[child].count;
+                self.nodes[id as usize].children.push((label, child as u32));
+            }
+        }
+        let node = &mut self.nodes[id as usize];
+        node.is_leaf = is_leaf;
+        node.count = count;
+    }
+
+    fn alloc(&mut self) -> u32 {
+        match self.free.pop() {
+            Some(id) => {
+                self.nodes[id as usize] = Node::new();
+                id
+            }
+            None => {
+                self.nodes.push(Node::new());
+                (self.nodes.len() - 1) as u32
+            }
+        }
+    }
+
+    fn child_index(&self, id: u32, b: u8) -> Result<usize, usize> {
+        self.nodes[id as usize].children.binary_search_by_key(&b, |(c, _)| *c)
+    }
+
+    fn child(&self, id: u32, b: u8) -> Option<u32> {
+        let i = self.child_index(id, b).ok()?;
+        Some(self.nodes[id as usize].children[i].1)
+    }
+
+    fn find(&self, s: &[u8]) -> Option<u32> {
+        let mut id = ROOT;
+        for b in s {
+            id = self.child(id, *b)?;
+        }
+        Some(id)
+    }
+
+    // runs the automaton along the keys, pruning the subtrees it rejects
+    fn search_by<'a, A: Automaton + 'a>(&'a self, mut automaton: A) -> impl Iterator<Item = (String, A::Output)> + 'a {
+        let mut stack: Vec<(u32, Vec<u8>)> = vec![(ROOT, vec![])];
+        core::iter::from_fn(move || {
+            while let Some((id, key)) = stack.pop() {
+                if !key.is_empty() {
+                    automaton.truncate(key.len() - 1);
+                    if !automaton.push_byte(&key) {
+                        continue;
+                    }
+                }
+                let node = &self.nodes[id as usize];
+                for (b, child) in node.children.iter().rev() {
+                    let mut child_key = key.clone();
+                    child_key.push(*b);
+                    stack.push((*child, child_key));
+                }
+                if node.is_leaf {
+                    if let Some(output) = automaton.accept() {
+                        return Some((String::from_utf8(key).expect("keys are valid UTF-8"), output));
+                    }
+                }
+            }
+            None
+        })
+    }
+}
+
+impl Trie for ByteTrie {
+    fn contains(&self, s: &str) -> bool {
+        self.find(s.as_bytes()).is_some_and(|id| self.nodes[id as usize].is_leaf)
+    }
+
+    fn prefix<'a>(&self, s:&'a str) -> &'a str {
+        let len = self.common_prefixes(s).last().map_or(0, |key| key.len());
+        &s[0..len]
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let bytes = query.as_bytes();
+        let mut node = Some(ROOT);
+        // node corresponds to query[0..end]
+        let mut end = 0;
+        core::iter::from_fn(move || {
+            loop {
+                let current = node?;
+                let len = end;
+                node = bytes.get(end).and_then(|b| self.child(current, *b));
+                end += 1;
+                // a stored key always ends at a char boundary of the query
+                if self.nodes[current as usize].is_leaf {
+                    return Some(&query[0..len]);
+                }
+            }
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.nodes[ROOT as usize].count
+    }
+
+    fn count_prefix(&self, prefix: &str) -> usize {
+        self.find(prefix.as_bytes()).map_or(0, |id| self.nodes[id as usize].count)
+    }
+
+    fn predict(&self, prefix: &str) -> impl Iterator<Item = String> + '_ {
+        let mut stack: Vec<(u32, Vec<u8>)> = self.find(prefix.as_bytes())
+            .map(|id| (id, prefix.as_bytes().to_vec()))
+            .into_iter()
+            .collect();
+        core::iter::from_fn(move || {
+            while let Some((id, key)) = stack.pop() {
+                let node = &self.nodes[id as usize];
+                for (b, child) in node.children.iter().rev() {
+                    let mut child_key = key.clone();
+                    child_key.push(*b);
+                    stack.push((*child, child_key));
+                }
+                if node.is_leaf {
+                    return Some(String::from_utf8(key).expect("keys are valid UTF-8"));
+                }
+            }
+            None
+        })
+    }
+
+    fn cursor(&self) -> impl TrieCursor<'_> {
+        Cursor { trie: self, id: ROOT }
+    }
+
+    fn fuzzy_search(&self, query: &str, max_edits: usize) -> Vec<(String, usize)> {
+        self.search_by(Levenshtein::new(query, max_edits)).collect()
+    }
+
+    fn hamming_search(&self, query: &str, max_mismatches: usize) -> Vec<(String, usize)> {
+        self.search_by(Hamming::new(query, max_mismatches)).collect()
+    }
+
+    fn search_pattern(&self, pattern: &str) -> Vec<String> {
+        self.search_by(Wildcard::new(pattern)).map(|(key, _)| key).collect()
+    }
+
+    fn range<'a, 'b, R: RangeBounds<&'b str> + 'a>(&'a self, range: R) -> impl Iterator<Item = String> + 'a {
+        self.search_by(KeyRange::new(range)).map(|(key, _)| key)
+    }
+}
+
+#[derive(Clone)]
+struct Cursor<'a> {
+    trie: &'a ByteTrie,
+    id: u32,
+}
+
+impl <'a> TrieCursor<'a> for Cursor<'a> {
+    type Value = ();
+
+    fn step(&mut self, c: char) -> bool {
+        let mut buf = [0; 4];
+        match c.encode_utf8(&mut buf).bytes().try_fold(self.id, |id, b| self.trie.child(id, b)) {
+            Some(id) => {
+                self.id = id;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn value(&self) -> Option<&'a ()> {
+        if self.trie.nodes[self.id as usize].is_leaf {
+            Some(&())
+        } else {
+            None
+        }
+    }
+}
+
+impl TrieMut for ByteTrie {
+    fn insert(&mut self, s: &str) -> bool {
+        self.append(s)
+    }
+
+    fn remove(&mut self, s: &str) -> bool {
+        if !self.contains(s) {
+            return false;
+        }
+        let mut id = ROOT;
+        self.nodes[id as usize].count -= 1;
+        for b in s.bytes() {
+            let i = self.child_index(id, b).unwrap();
+            let child = self.nodes[id as usize].children[i].1;
+            self.nodes[child as usize].count -= 1;
+            if self.nodes[child as usize].count == 0 {
+                // the rest of the path holds no other keys
+                self.nodes[id as usize].children.remove(i);
+                let mut dead = Some(child);
+                while let Some(d) = dead {
+                    self.free.push(d);
+                    dead = core::mem::take(&mut self.nodes[d as usize].children).pop().map(|(_, c)| c);
+                }
+                return true;
+            }
+            id = child;
+        }
+        self.nodes[id as usize].is_leaf = false;
+        true
+    }
+}
+
+impl ByteTrie {
+    /// 木構造を Graphviz の DOT 言語で返します。
+    ///
+    /// ノードの番号は内部の番号で、キーのノードを二重丸で描きます。
+    /// 辺のラベルは印字できる ASCII の文字で、それ以外のバイトは `\xE3` のように 16 進数で表します。
+    pub fn to_dot(&self) -> String {
+        let mut dot = DotWriter::new();
+        let mut stack = vec![ROOT];
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id as usize];
+            dot.node(id as usize, node.is_leaf);
+            for (b, child) in &node.children {
+                dot.edge(id as usize, *child as usize, &dot::byte_label(*b));
+                stack.push(*child);
+            }
+        }
+        dot.finish()
+    }
+
+    fn write_tree(&self, w: &mut TreeWriter, id: u32, depth: usize, is_last: bool, label: &str) -> fmt::Result {
+        let node = &self.nodes[id as usize];
+        w.node(depth, is_last, label, node.is_leaf)?;
+        for (i, (b, child)) in node.children.iter().enumerate() {
+            self.write_tree(w, *child, depth + 1, i + 1 == node.children.len(), &dot::byte_label(*b))?;
+        }
+        Ok(())
+    }
+}
+
+/// 木構造を、各ノードを 1 行とする木として書き出します。キーのノードには `*` を付けます。
+///
+/// 辺のラベルは [`ByteTrie::to_dot()`] と同じく、バイトごとに表します。
+impl fmt::Display for ByteTrie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_tree(&mut TreeWriter::new(f), ROOT, 0, true, "")
+    }
+}
+
+impl Default for ByteTrie {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpaceUsage for Node {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("children", &self.children)]
+    }
+}
+
+impl SpaceUsage for ByteTrie {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("nodes", &self.nodes), ("free", &self.free)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::trie::conformance;
+    use proptest::collection::btree_set;
+    use proptest::prelude::*;
+
+    #[test]
+    fn dot() {
+        let mut trie = ByteTrie::new();
+        trie.insert("a\"");
+        trie.insert("é");
+        let dot = trie.to_dot();
+        assert!(dot.starts_with("digraph trie {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains(r#"[label="\""]"#));
+        assert!(dot.contains(r#"[label="\\xC3"]"#));
+        assert_eq!(2, dot.matches("doublecircle").count());
+        assert_eq!(trie.size() - 1, dot.matches(" -> ").count());
+        assert_eq!(".\n├── a\n│   └── \" *\n└── \\xC3\n    └── \\xA9 *\n", trie.to_string());
+    }
+
+    #[test]
+    fn set_operations() {
+        let keys = || btree_set(conformance::key_of(&['a', 'b', 'あ', 'い'], 5), 0..100);
+        proptest!(|(keys_a in keys(), keys_b in keys())| {
+            let mut a = ByteTrie::new();
+            keys_a.iter().for_each(|key| { a.append(key); });
+            let mut b = ByteTrie::new();
+            keys_b.iter().for_each(|key| { b.append(key); });
+
+            let check = |expected: Vec<&String>, trie: ByteTrie| {
+                let mut appended = ByteTrie::new();
+                expected.iter().for_each(|key| { appended.append(key); });
+                prop_assert_eq!(appended.keys().collect::<Vec<_>>(), trie.keys().collect::<Vec<_>>());
+                prop_assert_eq!(expected.len(), trie.len());
+                // no nodes without keys
+                prop_assert_eq!(appended.size(), trie.size());
+                Ok(())
+            };
+            check(keys_a.union(&keys_b).collect(), a.union(&b))?;
+            check(keys_a.intersection(&keys_b).collect(), a.intersection(&b))?;
+            check(keys_a.difference(&keys_b).collect(), a.difference(&b))?;
+            check(vec![], a.difference(&a))?;
+        });
+    }
+
+    #[test]
+    fn remove_reuses_nodes() {
+        let mut trie = ByteTrie::new();
+        trie.append("foo");
+        trie.append("foobar");
+        assert_eq!(7, trie.size());
+        assert!(trie.remove("foobar"));
+        assert!(!trie.remove("foobar"));
+        assert_eq!(4, trie.size());
+        assert!(trie.remove("foo"));
+        assert_eq!(1, trie.size());
+        assert!(trie.is_empty());
+        trie.append("bar");
+        assert_eq!(4, trie.size());
+        assert_eq!(7, trie.nodes.len());
+    }
+}
@@ -0,0 +1,432 @@
+use crate::collections::adaptive_map::AdaptiveMap;
+use crate::space::SpaceUsage;
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::hash::BuildHasher;
+#[cfg(feature = "std")]
+use core::marker::PhantomData;
+use core::mem;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+
+/// トライ木のノードが、子を文字ごとに保持する連想配列
+pub trait ChildMap<T>: Default {
+    /// 文字 `c` の子を参照します。
+    fn get(&self, c: char) -> Option<&T>;
+
+    /// 文字 `c` の子を可変参照します。
+    fn get_mut(&mut self, c: char) -> Option<&mut T>;
+
+    /// 文字 `c` の子を可変参照します。子が存在しない場合、 `f()` の値を追加します。
+    fn get_or_insert_with<F: FnOnce() -> T>(&mut self, c: char, f: F) -> &mut T;
+
+    /// 文字 `c` の子を `child` にします。すでに子が存在した場合、古い子を返します。
+    fn insert(&mut self, c: char, child: T) -> Option<T>;
+
+    /// 文字 `c` の子を取り除いて返します。
+    fn remove(&mut self, c: char) -> Option<T>;
+
+    /// 子の個数を返します。
+    fn len(&self) -> usize;
+
+    /// 子が存在しない場合に、 `true` を返します。
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 文字と子の組を、文字の小さい順に返します。
+    fn sorted_iter<'a>(&'a self) -> impl DoubleEndedIterator<Item = (char, &'a T)> + 'a where T: 'a;
+
+    /// 子が所有するヒープ領域を除いた、連想配列が確保している領域の大きさを、バイト単位で返します。
+    ///
+    /// [`SpaceUsage`](crate::space::SpaceUsage) でトライ木の使用メモリを数えるのに使います。
+    fn table_size_in_bytes(&self) -> usize;
+}
+
+/// 子を保持する連想配列の選び方
+///
+/// [`NaiveTrie`](super::NaiveTrie) や [`NaiveTrieMap`](super::NaiveTrieMap) の型引数に指定し、
+/// 辞書ごとに使用メモリと検索の速さを選べます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::NaiveTrie;
+/// use rust_study::string::trie::Trie;
+/// use rust_study::string::trie::children::ArrayChildren;
+/// let mut trie: NaiveTrie<ArrayChildren> = NaiveTrie::default();
+/// trie.append("ACGT");
+/// trie.append("ACCA");
+/// assert_eq!(vec!["ACCA", "ACGT"], trie.predict("AC").collect::<Vec<_>>());
+/// ```
+pub trait ChildStorage {
+    /// 子を保持する連想配列の型です。
+    type Map<T>: ChildMap<T>;
+}
+
+/// 子が少ない間はソートした配列で、多くなるとハッシュテーブルで保持します。既定の選び方です。
+///
+/// [`AdaptiveMap`] を使います。
+pub struct AdaptiveChildren;
+
+/// 子をハッシュテーブルで保持します。
+///
+/// 子の多いノードでも定数時間で検索できますが、ノードごとの使用メモリは大きくなります。
+///
+/// ハッシュ関数は型引数 `S` の `BuildHasher` で作り、既定では `HashMap` と同じ SipHash を使います。
+/// キーは 1 文字なので、 FxHash などの速いハッシュ関数に替えると検索が速くなります。
+///
+/// ```
+/// use rust_study::string::trie::NaiveTrie;
+/// use rust_study::string::trie::Trie;
+/// use rust_study::string::trie::children::HashChildren;
+/// use rustc_hash::FxBuildHasher;
+/// let mut trie: NaiveTrie<HashChildren<FxBuildHasher>> = NaiveTrie::default();
+/// trie.append("the");
+/// trie.append("this");
+/// assert!(trie.contains("this"));
+/// ```
+#[cfg(feature = "std")]
+pub struct HashChildren<S = RandomState>(PhantomData<S>);
+
+/// 子を文字でソートした配列で保持し、二分探索で検索します。
+///
+/// 使用メモリは最も小さく、子の少ないノードが多い辞書に向きます。
+pub struct SortedVecChildren;
+
+/// U+00FF 以下の文字の子を 256 要素の配列で、それ以外の文字の子をソートした配列で保持します。
+///
+/// 子を持つノードごとに 256 要素の配列を確保するため、使用メモリは大きくなりますが、
+/// ASCII や Latin-1 の文字は配列を引くだけで検索できます。
+pub struct ArrayChildren;
+
+impl ChildStorage for AdaptiveChildren {
+    type Map<T> = AdaptiveMap<char, T>;
+}
+
+#[cfg(feature = "std")]
+impl <S: BuildHasher + Default> ChildStorage for HashChildren<S> {
+    type Map<T> = HashMap<char, T, S>;
+}
+
+impl ChildStorage for SortedVecChildren {
+    type Map<T> = SortedVec<T>;
+}
+
+impl ChildStorage for ArrayChildren {
+    type Map<T> = Array<T>;
+}
+
+impl <T> ChildMap<T> for AdaptiveMap<char, T> {
+    fn get(&self, c: char) -> Option<&T> {
+        AdaptiveMap::get(self, &c)
+    }
+
+    fn get_mut(&mut self, c: char) -> Option<&mut T> {
+        AdaptiveMap::get_mut(self, &c)
+    }
+
+    fn get_or_insert_with<F: FnOnce() -> T>(&mut self, c: char, f: F) -> &mut T {
+        AdaptiveMap::get_or_insert_with(self, c, f)
+    }
+
+    fn insert(&mut self, c: char, child: T) -> Option<T> {
+        AdaptiveMap::insert(self, c, child)
+    }
+
+    fn remove(&mut self, c: char) -> Option<T> {
+        AdaptiveMap::remove(self, &c)
+    }
+
+    fn len(&self) -> usize {
+        AdaptiveMap::len(self)
+    }
+
+    fn sorted_iter<'a>(&'a self) -> impl DoubleEndedIterator<Item = (char, &'a T)> + 'a where T: 'a {
+        AdaptiveMap::sorted_iter(self).map(|(c, child)| (*c, child))
+    }
+
+    fn table_size_in_bytes(&self) -> usize {
+        AdaptiveMap::table_size_in_bytes(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl <T, S: BuildHasher + Default> ChildMap<T> for HashMap<char, T, S> {
+    fn get(&self, c: char) -> Option<&T> {
+        HashMap::get(self, &c)
+    }
+
+    fn get_mut(&mut self, c: char) -> Option<&mut T> {
+        HashMap::get_mut(self, &c)
+    }
+
+    fn get_or_insert_with<F: FnOnce() -> T>(&mut self, c: char, f: F) -> &mut T {
+        self.entry(c).or_insert_with(f)
+    }
+
+    fn insert(&mut self, c: char, child: T) -> Option<T> {
+        HashMap::insert(self, c, child)
+    }
+
+    fn remove(&mut self, c: char) -> Option<T> {
+        HashMap::remove(self, &c)
+    }
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+
+    fn sorted_iter<'a>(&'a self) -> impl DoubleEndedIterator<Item = (char, &'a T)> + 'a where T: 'a {
+        let mut children: Vec<(char, &T)> = self.iter().map(|(c, child)| (*c, child)).collect();
+        children.sort_by_key(|(c, _)| *c);
+        children.into_iter()
+    }
+
+    fn table_size_in_bytes(&self) -> usize {
+        self.capacity() * (mem::size_of::<(char, T)>() + 1)
+    }
+}
+
+/// 文字でソートした `(文字, 子)` の配列
+pub struct SortedVec<T> {
+    children: Vec<(char, T)>,
+}
+
+impl <T> SortedVec<T> {
+    fn position(&self, c: char) -> Result<usize, usize> {
+        self.children.binary_search_by_key(&c, |(k, _)| *k)
+    }
+}
+
+impl <T> Default for SortedVec<T> {
+    fn default() -> Self {
+        SortedVec { children: Vec::new() }
+    }
+}
+
+impl <T> ChildMap<T> for SortedVec<T> {
+    fn get(&self, c: char) -> Option<&T> {
+        self.position(c).ok().map(|i| &self.children[i].1)
+    }
+
+    fn get_mut(&mut self, c: char) -> Option<&mut T> {
+        self.position(c).ok().map(|i| &mut self.children[i].1)
+    }
+
+    fn get_or_insert_with<F: FnOnce() -> T>(&mut self, c: char, f: F) -> &mut T {
+        let i = match self.position(c) {
+            Ok(i) => i,
+            Err(i) => {
+                self.children.insert(i, (c, f()));
+                i
+            }
+        };
+        &mut self.children[i].1
+    }
+
+    fn insert(&mut self, c: char, child: T) -> Option<T> {
+        match self.position(c) {
+            Ok(i) => Some(core::mem::replace(&mut self.children[i].1, child)),
+            Err(i) => {
+                self.children.insert(i, (c, child));
+                None
+            }
+        }
+    }
+
+    fn remove(&mut self, c: char) -> Option<T> {
+        let i = self.position(c).ok()?;
+        Some(self.children.remove(i).1)
+    }
+
+    fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    fn sorted_iter<'a>(&'a self) -> impl DoubleEndedIterator<Item = (char, &'a T)> + 'a where T: 'a {
+        self.children.iter().map(|(c, child)| (*c, child))
+    }
+
+    fn table_size_in_bytes(&self) -> usize {
+        self.children.capacity() * mem::size_of::<(char, T)>()
+    }
+}
+
+/// U+00FF 以下の文字で引く 256 要素の配列と、それ以外の文字の [`SortedVec`]
+pub struct Array<T> {
+    // allocated when the first child of a char up to U+00FF is added
+    low: Option<Box<[Option<T>; 256]>>,
+    low_len: usize,
+    high: SortedVec<T>,
+}
+
+impl <T> Default for Array<T> {
+    fn default() -> Self {
+        Array { low: None, low_len: 0, high: SortedVec::default() }
+    }
+}
+
+impl <T> ChildMap<T> for Array<T> {
+    fn get(&self, c: char) -> Option<&T> {
+        match u8::try_from(c) {
+            Ok(b) => self.low.as_ref()?[b as usize].as_ref(),
+            Err(_) => self.high.get(c),
+        }
+    }
+
+    fn get_mut(&mut self, c: char) -> Option<&mut T> {
+        match u8::try_from(c) {
+            Ok(b) => self.low.as_mut()?[b as usize].as_mut(),
+            Err(_) => self.high.get_mut(c),
+        }
+    }
+
+    fn get_or_insert_with<F: FnOnce() -> T>(&mut self, c: char, f: F) -> &mut T {
+        match u8::try_from(c) {
+            Ok(b) => {
+                let low = self.low.get_or_insert_with(|| Box::new(core::array::from_fn(|_| None)));
+                let slot = &mut low[b as usize];
+                if slot.is_none() {
+                    self.low_len += 1;
+                }
+                slot.get_or_insert_with(f)
+            }
+            Err(_) => self.high.get_or_insert_with(c, f),
+        }
+    }
+
+    fn insert(&mut self, c: char, child: T) -> Option<T> {
+        match u8::try_from(c) {
+            Ok(b) => {
+                let low = self.low.get_or_insert_with(|| Box::new(core::array::from_fn(|_| None)));
+                let old = low[b as usize].replace(child);
+                if old.is_none() {
+                    self.low_len += 1;
+                }
+                old
+            }
+            Err(_) => self.high.insert(c, child),
+        }
+    }
+
+    fn remove(&mut self, c: char) -> Option<T> {
+        match u8::try_from(c) {
+            Ok(b) => {
+                let removed = self.low.as_mut()?[b as usize].take()?;
+                self.low_len -= 1;
+                if self.low_len == 0 {
+                    self.low = None;
+                }
+                Some(removed)
+            }
+            Err(_) => self.high.remove(c),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.low_len + self.high.len()
+    }
+
+    fn sorted_iter<'a>(&'a self) -> impl DoubleEndedIterator<Item = (char, &'a T)> + 'a where T: 'a {
+        let low = self.low.iter().flat_map(|low| {
+            low.iter().enumerate().filter_map(|(b, child)| child.as_ref().map(|child| (char::from(b as u8), child)))
+        });
+        low.chain(self.high.sorted_iter())
+    }
+
+    fn table_size_in_bytes(&self) -> usize {
+        self.low.as_ref().map_or(0, |_| mem::size_of::<[Option<T>; 256]>()) + self.high.table_size_in_bytes()
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for SortedVec<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("children", &self.children)]
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for Array<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("low", &self.low), ("high", &self.high)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::BTreeMap;
+
+    fn same_as_btree_map<M: ChildMap<u32>>() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let alphabet = ['a', 'b', 'z', 'é', '\u{ff}', '\u{100}', 'あ'];
+        let mut map = M::default();
+        let mut expected = BTreeMap::new();
+        for i in 0..1000 {
+            let c = alphabet[rng.gen_range(0, alphabet.len())];
+            match rng.gen_range(0, 4) {
+                0 => assert_eq!(expected.remove(&c), map.remove(c)),
+                1 => assert_eq!(expected.insert(c, i), map.insert(c, i)),
+                2 => assert_eq!(*expected.entry(c).or_insert(i), *map.get_or_insert_with(c, || i)),
+                _ => {
+                    if let Some(value) = map.get_mut(c) {
+                        *value += 1;
+                    }
+                    if let Some(value) = expected.get_mut(&c) {
+                        *value += 1;
+                    }
+                }
+            }
+            assert_eq!(expected.get(&c), map.get(c));
+            assert_eq!(expected.len(), map.len());
+            assert_eq!(expected.is_empty(), map.is_empty());
+            assert_eq!(expected.iter().map(|(c, value)| (*c, value)).collect::<Vec<_>>(), map.sorted_iter().collect::<Vec<_>>());
+            assert_eq!(expected.iter().rev().map(|(c, value)| (*c, value)).collect::<Vec<_>>(), map.sorted_iter().rev().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn adaptive() {
+        same_as_btree_map::<<AdaptiveChildren as ChildStorage>::Map<u32>>();
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn hash() {
+        same_as_btree_map::<<HashChildren as ChildStorage>::Map<u32>>();
+        same_as_btree_map::<<HashChildren<rustc_hash::FxBuildHasher> as ChildStorage>::Map<u32>>();
+    }
+
+    #[test]
+    fn sorted_vec() {
+        same_as_btree_map::<<SortedVecChildren as ChildStorage>::Map<u32>>();
+    }
+
+    #[test]
+    fn array() {
+        same_as_btree_map::<<ArrayChildren as ChildStorage>::Map<u32>>();
+    }
+
+    #[test]
+    fn table_size() {
+        use crate::space::SpaceUsage;
+        use crate::string::trie::NaiveTrie;
+        let keys: Vec<String> = (0..300).map(|i| format!("{}", i * 13)).collect();
+        let sorted_vec: NaiveTrie<SortedVecChildren> = keys.iter().collect();
+        let array: NaiveTrie<ArrayChildren> = keys.iter().collect();
+        assert!(sorted_vec.size_in_bytes() * 10 < array.size_in_bytes());
+
+        let mut map: SortedVec<Box<u64>> = SortedVec::default();
+        map.insert('a', Box::new(1));
+        assert_eq!(map.children.capacity() * mem::size_of::<(char, Box<u64>)>(), map.table_size_in_bytes());
+        assert_eq!(map.table_size_in_bytes() + 8, map.heap_size_in_bytes());
+    }
+}
@@ -0,0 +1,180 @@
+use super::Trie;
+use super::TrieCursor;
+use super::TrieMut;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::select;
+use std::collections::BTreeSet;
+use std::ops::Bound;
+
+/// ランダムなキーに使う文字
+///
+/// 1 バイトから 3 バイトの UTF-8 の文字を含み、バイト単位のトライ木の文字の境界も確かめられます。
+pub const ALPHABET: [char; 5] = ['a', 'b', 'é', 'あ', 'い'];
+
+/// `chars` の文字からなる、 `max_len` 文字未満のキーを生成する戦略を返します。
+pub fn key_of(chars: &'static [char], max_len: usize) -> impl Strategy<Value = String> {
+    vec(select(chars), 0..max_len).prop_map(|cs| cs.into_iter().collect())
+}
+
+/// [`ALPHABET`] の文字からなる、 `max_len` 文字未満のキーを生成する戦略を返します。
+pub fn key(max_len: usize) -> impl Strategy<Value = String> {
+    key_of(&ALPHABET, max_len)
+}
+
+/// トライ木に対する 1 回の更新
+#[derive(Clone, Debug)]
+pub enum Op {
+    Insert(String),
+    Remove(String),
+}
+
+impl Op {
+    /// `trie` と、同じキーを持つ `expected` を同じように更新し、結果が一致することを確かめます。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the results differ.
+    pub fn apply<T: TrieMut>(&self, trie: &mut T, expected: &mut BTreeSet<String>) {
+        match self {
+            Op::Insert(key) => assert_eq!(expected.insert(key.clone()), trie.insert(key), "insert({:?})", key),
+            Op::Remove(key) => assert_eq!(expected.remove(key), trie.remove(key), "remove({:?})", key),
+        }
+        assert_eq!(expected.len(), trie.len());
+    }
+}
+
+/// [`key()`] のキーを追加と削除が 2 対 1 の割合で含む、 `max_ops` 個未満の更新の列を生成する戦略を返します。
+pub fn ops(max_len: usize, max_ops: usize) -> impl Strategy<Value = Vec<Op>> {
+    vec(prop_oneof![2 => key(max_len).prop_map(Op::Insert), 1 => key(max_len).prop_map(Op::Remove)], 0..max_ops)
+}
+
+// the edit distance between a and b in chars
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, c) in a.chars().enumerate() {
+        let mut next = vec![i + 1];
+        for j in 0..b.len() {
+            next.push((row[j] + (b[j] != c) as usize).min(row[j + 1] + 1).min(next[j] + 1));
+        }
+        row = next;
+    }
+    row[b.len()]
+}
+
+// the number of mismatched chars between a and b, if they have the same number of chars
+pub(crate) fn hamming_distance(a: &str, b: &str) -> Option<usize> {
+    (a.chars().count() == b.chars().count()).then(|| a.chars().zip(b.chars()).filter(|(a, b)| a != b).count())
+}
+
+// whether s matches the glob pattern
+pub(crate) fn glob(pattern: &[char], s: &[char]) -> bool {
+    match pattern.split_first() {
+        None => s.is_empty(),
+        Some(('*', rest)) => (0..=s.len()).any(|i| glob(rest, &s[i..])),
+        Some((p, rest)) => s.split_first().is_some_and(|(c, s)| (*p == '?' || p == c) && glob(rest, s)),
+    }
+}
+
+/// `trie` のクエリ `query` に対する検索結果が、同じキーの集合 `expected` から総当たりで求めた結果と一致することを確かめます。
+///
+/// # Panics
+///
+/// Panics if any result differs.
+pub fn assert_same_query<T: Trie>(expected: &BTreeSet<String>, trie: &T, query: &str) {
+    let prefixes: Vec<&str> = (0..=query.len())
+        .filter(|&i| query.is_char_boundary(i) && expected.contains(&query[0..i]))
+        .map(|i| &query[0..i])
+        .collect();
+    let predicted: Vec<String> = expected.range::<str, _>((Bound::Included(query), Bound::Unbounded))
+        .take_while(|key| key.starts_with(query))
+        .cloned()
+        .collect();
+    assert_eq!(expected.contains(query), trie.contains(query), "contains({:?})", query);
+    assert_eq!(prefixes.last().copied().unwrap_or(""), trie.prefix(query), "prefix({:?})", query);
+    assert_eq!(prefixes, trie.common_prefixes(query).collect::<Vec<_>>(), "common_prefixes({:?})", query);
+    assert_eq!(predicted.len(), trie.count_prefix(query), "count_prefix({:?})", query);
+    assert_eq!(predicted, trie.predict(query).collect::<Vec<_>>(), "predict({:?})", query);
+
+    let fuzzy: Vec<(String, usize)> = expected.iter()
+        .map(|key| (key.clone(), edit_distance(key, query)))
+        .filter(|(_, distance)| *distance <= 1)
+        .collect();
+    assert_eq!(fuzzy, trie.fuzzy_search(query, 1), "fuzzy_search({:?}, 1)", query);
+    let hamming: Vec<(String, usize)> = expected.iter()
+        .filter_map(|key| hamming_distance(key, query).map(|mismatches| (key.clone(), mismatches)))
+        .filter(|(_, mismatches)| *mismatches <= 1)
+        .collect();
+    assert_eq!(hamming, trie.hamming_search(query, 1), "hamming_search({:?}, 1)", query);
+    let pattern: Vec<char> = query.chars().enumerate().map(|(i, c)| if i % 2 == 0 { c } else { '?' }).chain(['*']).collect();
+    let matched: Vec<String> = expected.iter().filter(|key| glob(&pattern, &key.chars().collect::<Vec<_>>())).cloned().collect();
+    let pattern: String = pattern.into_iter().collect();
+    assert_eq!(matched, trie.search_pattern(&pattern), "search_pattern({:?})", pattern);
+
+    let from: Vec<String> = expected.range::<str, _>((Bound::Included(query), Bound::Unbounded)).cloned().collect();
+    assert_eq!(from, trie.range(query..).collect::<Vec<_>>(), "range({:?}..)", query);
+    let to: Vec<String> = expected.range::<str, _>((Bound::Unbounded, Bound::Included(query))).cloned().collect();
+    assert_eq!(to, trie.range(..=query).collect::<Vec<_>>(), "range(..={:?})", query);
+
+    // the cursor reaches the same keys as common_prefixes
+    let mut cursor = trie.cursor();
+    let mut leaves: Vec<&str> = vec![];
+    if cursor.is_leaf() {
+        leaves.push("");
+    }
+    for (i, c) in query.char_indices() {
+        if !cursor.step(c) {
+            break;
+        }
+        if cursor.is_leaf() {
+            leaves.push(&query[0..i + c.len_utf8()]);
+        }
+    }
+    assert_eq!(prefixes, leaves, "cursor along {:?}", query);
+}
+
+/// キーの列から構築する静的なトライ木が、同じキーの `BTreeSet` から総当たりで求めた検索結果を返すことを、 proptest で確かめます。
+///
+/// 空のキーの列や、重複したキー、ソートされていないキーの列も `build` に渡します。
+/// 失敗した場合は、失敗する最小のキーの列とクエリを探してから panic します。
+///
+/// # Panics
+///
+/// Panics if any result differs.
+pub fn check_static<T: Trie, F: Fn(&[String]) -> T>(build: F) {
+    proptest!(ProptestConfig::with_cases(32), |(keys in vec(key(6), 0..300), queries in vec(key(6), 1..20))| {
+        let trie = build(&keys);
+        let expected: BTreeSet<String> = keys.iter().cloned().collect();
+        assert_eq!(expected.len(), trie.len());
+        assert_eq!(expected.is_empty(), trie.is_empty());
+        assert_eq!(expected.iter().cloned().collect::<Vec<_>>(), trie.keys().collect::<Vec<_>>());
+        // the queries hitting some keys, too
+        for query in queries.iter().chain(keys.iter().take(10)) {
+            assert_same_query(&expected, &trie, query);
+        }
+    });
+}
+
+/// `new` で作ったキーを追加・削除できるトライ木が、同じ操作をした `BTreeSet` から総当たりで求めた結果を返すことを、 proptest で確かめます。
+///
+/// 失敗した場合は、失敗する最小の操作の列とクエリを探してから panic します。
+///
+/// # Panics
+///
+/// Panics if any result differs.
+pub fn check_dynamic<T: TrieMut, F: Fn() -> T>(new: F) {
+    proptest!(ProptestConfig::with_cases(32), |(ops in ops(5, 300), queries in vec(key(5), 1..20))| {
+        let mut trie = new();
+        let mut expected = BTreeSet::new();
+        for (i, op) in ops.iter().enumerate() {
+            op.apply(&mut trie, &mut expected);
+            assert_same_query(&expected, &trie, &queries[i % queries.len()]);
+        }
+        assert_eq!(expected.iter().cloned().collect::<Vec<_>>(), trie.keys().collect::<Vec<_>>());
+        for query in &queries {
+            assert_same_query(&expected, &trie, query);
+        }
+    });
+}
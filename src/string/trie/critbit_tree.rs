@@ -0,0 +1,347 @@
+use crate::space::SpaceUsage;
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+enum Node<V> {
+    Leaf(Box<[u8]>, V),
+    Internal {
+        // the critical bit: the index of the byte, and the bit of its symbol
+        index: usize,
+        mask: u16,
+        // the subtrees whose keys have the bit 0 and 1, never None
+        children: [Option<Box<Node<V>>>; 2],
+    },
+}
+
+impl <V> Node<V> {
+    fn children(&self) -> Option<[&Node<V>; 2]> {
+        match self {
+            Node::Leaf(..) => None,
+            Node::Internal { children: [Some(left), Some(right)], .. } => Some([left, right]),
+            Node::Internal { .. } => unreachable!("an internal node has two children"),
+        }
+    }
+}
+
+// the 9-bit symbol of the byte at i, which sorts the end of the key before any byte
+fn symbol(key: &[u8], i: usize) -> u16 {
+    key.get(i).map_or(0, |b| 0x100 | *b as u16)
+}
+
+fn direction(key: &[u8], index: usize, mask: u16) -> usize {
+    (symbol(key, index) & mask != 0) as usize
+}
+
+/// 任意のバイト列をキーとして値を保持する、2 分岐のパトリシア木 (crit-bit 木)
+///
+/// 内部ノードは 2 つのキーが初めて異なるビット (臨界ビット) の位置だけを持ち、キーは葉にだけ保持します。
+/// キーが `n` 個のとき内部ノードはちょうど `n - 1` 個で、検索は臨界ビットで分岐しながら葉まで下り、最後に 1 度だけキーを比較します。
+///
+/// 各バイトを、キーの終端を表す `0` より大きい 9 ビットの記号として扱うため、
+/// あるキーが別のキーの接頭辞であってもよく、キーはバイト列の辞書順に列挙されます。
+/// 文字列を扱う他のトライ木と異なり、シリアライズした ID やハッシュ値など UTF-8 でないキーを扱えます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::CritbitTree;
+/// let mut tree = CritbitTree::new();
+/// assert_eq!(None, tree.insert(&[0x12, 0x34], "a"));
+/// assert_eq!(None, tree.insert(&[0x12], "b"));
+/// assert_eq!(None, tree.insert(&[0x00, 0xff], "c"));
+/// assert_eq!(Some("a"), tree.insert(&[0x12, 0x34], "d"));
+/// assert_eq!(Some(&"b"), tree.get(&[0x12]));
+/// assert!(!tree.contains_key(&[0x00]));
+///
+/// let keys: Vec<&[u8]> = tree.iter().map(|(key, _)| key).collect();
+/// assert_eq!(vec![&[0x00, 0xff][..], &[0x12], &[0x12, 0x34]], keys);
+/// assert_eq!(2, tree.iter_prefix(&[0x12]).count());
+///
+/// assert_eq!(Some("c"), tree.remove(&[0x00, 0xff]));
+/// assert_eq!(2, tree.len());
+/// ```
+pub struct CritbitTree<V> {
+    root: Option<Box<Node<V>>>,
+    len: usize,
+}
+
+impl <V> CritbitTree<V> {
+    /// 空の木を構築します。
+    pub fn new() -> Self {
+        CritbitTree { root: None, len: 0 }
+    }
+
+    /// 保持しているキーの個数を返します。
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// キーを保持していない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// キー `key` に値 `value` を対応付けます。
+    ///
+    /// すでにキーが存在した場合、値を置き換えて古い値を返します。
+    pub fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        let leaf = match self.best_leaf(key) {
+            None => {
+                self.root = Some(Box::new(Node::Leaf(key.into(), value)));
+                self.len += 1;
+                return None;
+            }
+            Some((leaf, _)) => leaf,
+        };
+        // the first symbol differing from the key in the tree
+        let index = match (0..=key.len().max(leaf.len())).find(|i| symbol(key, *i) != symbol(leaf, *i)) {
+            None => return self.get_mut(key).map(|old| core::mem::replace(old, value)),
+            Some(index) => index,
+        };
+        let diff = symbol(key, index) ^ symbol(leaf, index);
+        let mask = 1 << (15 - diff.leading_zeros());
+        // the new node is placed above the nodes with a later critical bit
+        let mut path = vec![];
+        let mut node = self.root.as_deref();
+        while let Some(Node::Internal { index: i, mask: m, children }) = node {
+            if (*i, core::cmp::Reverse(*m)) > (index, core::cmp::Reverse(mask)) {
+                break;
+            }
+            let d = direction(key, *i, *m);
+            path.push(d);
+            node = children[d].as_deref();
+        }
+        let slot = self.slot(&path);
+        let other = slot.take();
+        let new = Some(Box::new(Node::Leaf(key.into(), value)));
+        let children = if direction(key, index, mask) == 0 { [new, other] } else { [other, new] };
+        *slot = Some(Box::new(Node::Internal { index, mask, children }));
+        self.len += 1;
+        None
+    }
+
+    /// キー `key` に対応する値を返します。キーが存在しない場合、 `None` を返します。
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        match self.best_leaf(key)? {
+            (leaf, value) if leaf == key => Some(value),
+            _ => None,
+        }
+    }
+
+    /// キー `key` に対応する値を可変参照で返します。キーが存在しない場合、 `None` を返します。
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut V> {
+        let path = self.path_to_leaf(key);
+        match self.slot(&path).as_deref_mut() {
+            Some(Node::Leaf(leaf, value)) if leaf.as_ref() == key => Some(value),
+            _ => None,
+        }
+    }
+
+    /// キー `key` を保持している場合に、 `true` を返します。
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// キー `key` を取り除き、その値を返します。キーが存在しない場合、 `None` を返します。
+    pub fn remove(&mut self, key: &[u8]) -> Option<V> {
+        self.get(key)?;
+        let mut path = self.path_to_leaf(key);
+        let removed = match path.pop() {
+            // the leaf is the root
+            None => self.root.take(),
+            Some(d) => {
+                // replace the parent with the sibling of the leaf
+                let slot = self.slot(&path);
+                match slot.take().map(|node| *node) {
+                    Some(Node::Internal { children: [left, right], .. }) => {
+                        let (leaf, sibling) = if d == 0 { (left, right) } else { (right, left) };
+                        *slot = sibling;
+                        leaf
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        };
+        self.len -= 1;
+        match removed.map(|node| *node) {
+            Some(Node::Leaf(_, value)) => Some(value),
+            _ => unreachable!(),
+        }
+    }
+
+    /// キーと値の組を、キーの辞書順に返すイテレータを返します。
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &V)> + '_ {
+        Self::leaves(self.root.as_deref())
+    }
+
+    /// `prefix` から始まるキーと値の組を、キーの辞書順に返すイテレータを返します。
+    pub fn iter_prefix(&self, prefix: &[u8]) -> impl Iterator<Item = (&[u8], &V)> + '_ {
+        // the keys under the first node branching after the prefix share the bytes before it
+        let mut node = self.root.as_deref();
+        while let Some(Node::Internal { index, mask, children }) = node {
+            if *index >= prefix.len() {
+                break;
+            }
+            node = children[direction(prefix, *index, *mask)].as_deref();
+        }
+        let top = node.filter(|top| {
+            let mut leaf = *top;
+            while let Some([left, _]) = leaf.children() {
+                leaf = left;
+            }
+            matches!(leaf, Node::Leaf(key, _) if key.starts_with(prefix))
+        });
+        Self::leaves(top)
+    }
+
+    fn leaves(top: Option<&Node<V>>) -> impl Iterator<Item = (&[u8], &V)> + '_ {
+        let mut stack: Vec<&Node<V>> = top.into_iter().collect();
+        core::iter::from_fn(move || {
+            while let Some(node) = stack.pop() {
+                match node {
+                    Node::Leaf(key, value) => return Some((key.as_ref(), value)),
+                    Node::Internal { .. } => {
+                        let [left, right] = node.children().unwrap();
+                        stack.push(right);
+                        stack.push(left);
+                    }
+                }
+            }
+            None
+        })
+    }
+
+    // the leaf reached by following the critical bits of key
+    fn best_leaf(&self, key: &[u8]) -> Option<(&[u8], &V)> {
+        let mut node = self.root.as_deref()?;
+        loop {
+            match node {
+                Node::Leaf(leaf, value) => return Some((leaf, value)),
+                Node::Internal { index, mask, children } => {
+                    node = children[direction(key, *index, *mask)].as_deref().unwrap();
+                }
+            }
+        }
+    }
+
+    // the directions from the root to the best leaf of key
+    fn path_to_leaf(&self, key: &[u8]) -> Vec<usize> {
+        let mut path = vec![];
+        let mut node = self.root.as_deref();
+        while let Some(Node::Internal { index, mask, children }) = node {
+            let d = direction(key, *index, *mask);
+            path.push(d);
+            node = children[d].as_deref();
+        }
+        path
+    }
+
+    fn slot(&mut self, path: &[usize]) -> &mut Option<Box<Node<V>>> {
+        let mut slot = &mut self.root;
+        for d in path {
+            slot = match slot.as_deref_mut() {
+                Some(Node::Internal { children, .. }) => &mut children[*d],
+                _ => unreachable!(),
+            };
+        }
+        slot
+    }
+}
+
+impl <V> Default for CritbitTree<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl <K: AsRef<[u8]>, V> FromIterator<(K, V)> for CritbitTree<V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut tree = Self::new();
+        for (key, value) in iter {
+            tree.insert(key.as_ref(), value);
+        }
+        tree
+    }
+}
+
+impl <V: SpaceUsage> SpaceUsage for Node<V> {
+    fn heap_size_in_bytes(&self) -> usize {
+        match self {
+            Node::Leaf(key, value) => key.heap_size_in_bytes() + value.heap_size_in_bytes(),
+            Node::Internal { children, .. } => children.heap_size_in_bytes(),
+        }
+    }
+}
+
+impl <V: SpaceUsage> SpaceUsage for CritbitTree<V> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("root", &self.root)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn same_as_btree_map() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let random_key = |rng: &mut StdRng| -> Vec<u8> {
+            let len = rng.gen_range(0, 4);
+            (0..len).map(|_| [0x00, 0x01, 0x7f, 0x80, 0xff][rng.gen_range(0, 5)]).collect()
+        };
+        let mut tree = CritbitTree::new();
+        let mut expected = BTreeMap::new();
+        for _ in 0..3000 {
+            let key = random_key(&mut rng);
+            match rng.gen_range(0, 4) {
+                0 => assert_eq!(expected.remove(&key), tree.remove(&key)),
+                1 => {
+                    if let Some(v) = expected.get_mut(&key) {
+                        *v += 1;
+                    }
+                    if let Some(v) = tree.get_mut(&key) {
+                        *v += 1;
+                    }
+                }
+                _ => {
+                    let v: u32 = rng.gen_range(0, 1000);
+                    assert_eq!(expected.insert(key.clone(), v), tree.insert(&key, v));
+                }
+            }
+            assert_eq!(expected.len(), tree.len());
+            let query = random_key(&mut rng);
+            assert_eq!(expected.get(&query), tree.get(&query));
+            let under: Vec<(&[u8], &u32)> = expected.iter()
+                .filter(|(key, _)| key.starts_with(&query))
+                .map(|(key, value)| (key.as_slice(), value))
+                .collect();
+            assert_eq!(under, tree.iter_prefix(&query).collect::<Vec<_>>());
+        }
+        let all: Vec<(&[u8], &u32)> = expected.iter().map(|(key, value)| (key.as_slice(), value)).collect();
+        assert_eq!(all, tree.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn prefix_keys() {
+        let tree: CritbitTree<usize> = [&b""[..], b"\0", b"\0\0", b"a"].iter().enumerate().map(|(i, key)| (key, i)).collect();
+        assert_eq!(4, tree.len());
+        assert_eq!(vec![0, 1, 2, 3], tree.iter().map(|(_, value)| *value).collect::<Vec<_>>());
+        assert_eq!(vec![1, 2], tree.iter_prefix(b"\0").map(|(_, value)| *value).collect::<Vec<_>>());
+        assert_eq!(Some(&0), tree.get(b""));
+        assert_eq!(None, tree.get(b"\0\0\0"));
+
+        let mut tree = CritbitTree::new();
+        assert_eq!(None, tree.remove(b""));
+        tree.insert(b"", ());
+        assert_eq!(Some(()), tree.remove(b""));
+        assert!(tree.is_empty());
+        assert_eq!(0, tree.iter().count());
+    }
+}
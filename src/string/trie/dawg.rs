@@ -0,0 +1,774 @@
+#[cfg(feature = "std")]
+use super::binary;
+use super::automaton::Automaton;
+use super::automaton::Hamming;
+use super::automaton::KeyRange;
+use super::automaton::Levenshtein;
+use super::automaton::Wildcard;
+use super::Trie;
+use super::TrieCursor;
+use super::TrieId;
+use crate::error::Error;
+#[cfg(feature = "parallel")]
+use crate::parallel;
+#[cfg(feature = "parallel")]
+use crate::parallel::Chunking;
+#[cfg(feature = "std")]
+use crate::serialize::Serializable;
+use super::dot;
+use super::dot::DotWriter;
+use super::dot::TreeWriter;
+use crate::space::SpaceUsage;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::RangeBounds;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+#[cfg(feature = "std")]
+const MAGIC: &[u8; 8] = b"DAWGSTR1";
+
+struct State {
+    // sorted by the byte
+    edges: Vec<(u8, u32)>,
+    is_final: bool,
+    // the number of keys accepted from the state
+    count: usize,
+}
+
+// a state on the path of the last added key, not yet minimized
+#[derive(Default)]
+struct Pending {
+    edges: Vec<(u8, u32)>,
+    is_final: bool,
+}
+
+/// 最小の非巡回決定性有限オートマトン (DAWG / DAFSA)
+///
+/// ソートしたキーを順に追加しながら、追加済みのキーと共通しない部分を等価な状態にまとめていく
+/// 逐次最小化 (Daciuk らの方法) により構築します。
+/// 接頭辞だけを共有するトライ木と異なり共通の接尾辞も共有するため、
+/// 活用形を並べた単語リストなどでは状態数がトライ木のノード数より大幅に小さくなります。
+///
+/// 各状態から受理されるキーの個数を保持しており、キーとその辞書順の番号 (最小完全ハッシュ) を相互に変換できます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::Dawg;
+/// use rust_study::string::trie::Trie;
+/// let dawg = Dawg::new(&["tap", "taps", "top", "tops"]);
+/// assert_eq!(5, dawg.size());
+/// assert!(dawg.contains("tops"));
+/// assert!(!dawg.contains("to"));
+/// assert_eq!(vec!["top", "tops"], dawg.predict("to").collect::<Vec<_>>());
+///
+/// // 番号はキーの辞書順です。
+/// assert_eq!(Some(2), dawg.key_to_id("top"));
+/// assert_eq!(Some("taps".to_string()), dawg.id_to_key(1));
+/// ```
+pub struct Dawg {
+    states: Vec<State>,
+    root: u32,
+}
+
+impl Dawg {
+    /// キーの列 `keys` から構築します。重複したキーは 1 つにまとめられます。
+    pub fn new<S: AsRef<str>>(keys: &[S]) -> Self {
+        let mut keys: Vec<&str> = keys.iter().map(|key| key.as_ref()).collect();
+        keys.sort();
+        Self::from_sorted_keys(keys)
+    }
+
+    /// [`Self::new()`] と同じオートマトンを、キーの整列を並列に処理して構築します。
+    #[cfg(feature = "parallel")]
+    pub fn par_new<S: AsRef<str> + Sync>(keys: &[S], chunking: Chunking) -> Self {
+        Self::from_sorted_keys(parallel::sorted_keys(keys, chunking))
+    }
+
+    /// 辞書順にソートされたキーの列 `keys` から構築します。重複したキーは 1 つにまとめられます。
+    ///
+    /// キーを 1 つずつ処理するため、すべてのキーを同時にメモリ上に置く必要はありません。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keys` are not sorted.
+    pub fn from_sorted_keys<I: IntoIterator<Item = S>, S: AsRef<str>>(keys: I) -> Self {
+        Self::try_from_sorted_keys(keys).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [`Self::from_sorted_keys()`] と同じですが、 `keys` がソートされていない場合、
+    /// パニックせずに [`Error::UnsortedKeys`] を返します。
+    pub fn try_from_sorted_keys<I: IntoIterator<Item = S>, S: AsRef<str>>(keys: I) -> Result<Self, Error> {
+        let mut builder = Builder { states: vec![], register: BTreeMap::new() };
+        let mut path = vec![Pending::default()];
+        // labels[i] is the label of the edge from path[i] to path[i + 1], i.e. the last key
+        let mut labels: Vec<u8> = vec![];
+        let mut first = true;
+        for (index, key) in keys.into_iter().enumerate() {
+            let key = key.as_ref().as_bytes();
+            if !first {
+                if labels.as_slice() > key {
+                    return Err(Error::UnsortedKeys { index });
+                }
+                if labels.as_slice() == key {
+                    continue;
+                }
+            }
+            first = false;
+            let common = labels.iter().zip(key).take_while(|(a, b)| a == b).count();
+            builder.freeze(&mut path, &mut labels, common);
+            for b in &key[common..] {
+                labels.push(*b);
+                path.push(Pending::default());
+            }
+            path.last_mut().unwrap().is_final = true;
+        }
+        builder.freeze(&mut path, &mut labels, 0);
+        let root = builder.intern(path.pop().unwrap());
+        Ok(Dawg { states: builder.states, root })
+    }
+
+    /// 状態の個数を返します。
+    pub fn size(&self) -> usize {
+        self.states.len()
+    }
+
+    /// キー `s` の辞書順の番号を返します。キーが存在しない場合、 `None` を返します。
+    pub fn key_to_id(&self, s: &str) -> Option<usize> {
+        let mut id = 0;
+        let mut state = self.state(self.root);
+        for b in s.bytes() {
+            if state.is_final {
+                id += 1;
+            }
+            let mut next = None;
+            for (label, target) in &state.edges {
+                if *label == b {
+                    next = Some(*target);
+                    break;
+                }
+                id += self.state(*target).count;
+            }
+            state = self.state(next?);
+        }
+        if state.is_final {
+            Some(id)
+        } else {
+            None
+        }
+    }
+
+    /// 辞書順で `id` 番目のキーを返します。 `id` が範囲外の場合、 `None` を返します。
+    pub fn id_to_key(&self, mut id: usize) -> Option<String> {
+        if id >= self.len() {
+            return None;
+        }
+        let mut key = vec![];
+        let mut state = self.state(self.root);
+        loop {
+            if state.is_final {
+                if id == 0 {
+                    break;
+                }
+                id -= 1;
+            }
+            for (label, target) in &state.edges {
+                let count = self.state(*target).count;
+                if id < count {
+                    key.push(*label);
+                    state = self.state(*target);
+                    break;
+                }
+                id -= count;
+            }
+        }
+        Some(String::from_utf8(key).expect("keys are valid UTF-8"))
+    }
+
+    /// オートマトンをバイト列として `w` に書き出します。
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        binary::write_u64(w, self.states.len() as u64)?;
+        binary::write_u64(w, self.root as u64)?;
+        for state in &self.states {
+            binary::write_u64(w, state.is_final as u64)?;
+            binary::write_u64(w, state.edges.len() as u64)?;
+            for (label, target) in &state.edges {
+                binary::write_u64(w, (*label as u64) << 32 | *target as u64)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// [`Self::write_to()`] で書き出したバイト列を `r` から読み込みます。
+    ///
+    /// バイト列が壊れている場合、 `ErrorKind::InvalidData` のエラーを返します。
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        binary::check_magic(r, MAGIC)?;
+        let len = binary::read_len(r)?;
+        let root = binary::read_u64(r)?;
+        if len == 0 || len > u32::MAX as usize || root >= len as u64 {
+            return Err(binary::invalid_data("inconsistent sizes"));
+        }
+        let mut states: Vec<State> = vec![];
+        for _ in 0..len {
+            let is_final = match binary::read_u64(r)? {
+                0 => false,
+                1 => true,
+                _ => return Err(binary::invalid_data("invalid flag")),
+            };
+            let num_edges = binary::read_len(r)?;
+            if num_edges > 256 {
+                return Err(binary::invalid_data("too many edges"));
+            }
+            let mut edges: Vec<(u8, u32)> = vec![];
+            for _ in 0..num_edges {
+                let edge = binary::read_u64(r)?;
+                let (label, target) = (edge >> 32, edge & u32::MAX as u64);
+                // states are stored in topological order, and edges are sorted by the label
+                if label > u8::MAX as u64 || target >= states.len() as u64 || edges.last().is_some_and(|e| e.0 as u64 >= label) {
+                    return Err(binary::invalid_data("invalid edge"));
+                }
+                edges.push((label as u8, target as u32));
+            }
+            let count = edges.iter()
+                .try_fold(is_final as usize, |count, (_, target)| count.checked_add(states[*target as usize].count))
+                .ok_or_else(|| binary::invalid_data("too many keys"))?;
+            states.push(State { edges, is_final, count });
+        }
+        let dawg = Dawg { states, root: root as u32 };
+        dawg.validate()?;
+        Ok(dawg)
+    }
+
+    /// 内部の整合性を検証し、最初に見つけた不整合を [`Error::CorruptData`] で返します。
+    ///
+    /// 状態が位相順に並び辺のラベルが昇順であること、各状態の受理するキーの個数が正しいこと、
+    /// 受理するキーがすべて UTF-8 であることを、共有された状態を展開せずに `O(辺の数)` で確かめます。
+    /// [`Self::read_from()`] は、読み込んだ後にこれを呼び出します。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_study::string::trie::Dawg;
+    /// let dawg = Dawg::new(&["tap", "taps", "top", "tops"]);
+    /// assert_eq!(Ok(()), dawg.validate());
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.states.is_empty() || self.states.len() > u32::MAX as usize || self.root as usize >= self.states.len() {
+            return Err(Error::CorruptData(format!("{} states with the root {}", self.states.len(), self.root)));
+        }
+        for (id, state) in self.states.iter().enumerate() {
+            // the targets precede the source, so that the counts are computed in order
+            if state.edges.iter().any(|(_, target)| *target as usize >= id) {
+                return Err(Error::CorruptData(format!("state {} has an edge to a state not preceding it", id)));
+            }
+            if state.edges.windows(2).any(|w| w[0].0 >= w[1].0) {
+                return Err(Error::CorruptData(format!("the labels of the edges from state {} are not strictly increasing", id)));
+            }
+            let count = state.edges.iter()
+                .try_fold(state.is_final as usize, |count, (_, target)| count.checked_add(self.states[*target as usize].count))
+                .ok_or_else(|| Error::CorruptData(format!("the number of the keys from state {} overflows", id)))?;
+            if count != state.count {
+                return Err(Error::CorruptData(format!("state {} has {} keys, expected {}", id, state.count, count)));
+            }
+        }
+        self.validate_keys()
+    }
+
+    // checks that the keys accepted from the root are UTF-8, by running a UTF-8 decoder over the states in topological order
+    fn validate_keys(&self) -> Result<(), Error> {
+        // the set of the decoder states with which each state is reached, as a bit mask
+        let mut reached = vec![0_u16; self.states.len()];
+        reached[self.root as usize] = 1 << UTF8_BOUNDARY;
+        // the targets of the edges precede the source
+        for id in (0..=self.root as usize).rev() {
+            let state = &self.states[id];
+            if state.is_final && reached[id] & !(1 << UTF8_BOUNDARY) != 0 {
+                return Err(Error::CorruptData(format!("a key accepted at state {} is not valid UTF-8", id)));
+            }
+            let decoders = reached[id];
+            for decoder in (0..UTF8_STATES).filter(|d| decoders & 1 << d != 0) {
+                for (b, target) in &state.edges {
+                    reached[*target as usize] |= 1 << utf8_step(decoder, *b);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn state(&self, id: u32) -> &State {
+        &self.states[id as usize]
+    }
+
+    fn child(&self, id: u32, b: u8) -> Option<u32> {
+        let edges = &self.state(id).edges;
+        let i = edges.binary_search_by_key(&b, |(label, _)| *label).ok()?;
+        Some(edges[i].1)
+    }
+
+    fn find(&self, s: &[u8]) -> Option<u32> {
+        let mut id = self.root;
+        for b in s {
+            id = self.child(id, *b)?;
+        }
+        Some(id)
+    }
+
+    // runs the automaton along the keys, pruning the subtrees it rejects
+    fn search_by<'a, A: Automaton + 'a>(&'a self, mut automaton: A) -> impl Iterator<Item = (String, A::Output)> + 'a {
+        let mut stack: Vec<(u32, Vec<u8>)> = vec![(self.root, vec![])];
+        core::iter::from_fn(move || {
+            while let Some((id, key)) = stack.pop() {
+                if !key.is_empty() {
+                    automaton.truncate(key.len() - 1);
+                    if !automaton.push_byte(&key) {
+                        continue;
+                    }
+                }
+                let state = self.state(id);
+                for (b, target) in state.edges.iter().rev() {
+                    let mut next_key = key.clone();
+                    next_key.push(*b);
+                    stack.push((*target, next_key));
+                }
+                if state.is_final {
+                    if let Some(output) = automaton.accept() {
+                        return Some((String::from_utf8(key).expect("keys are valid UTF-8"), output));
+                    }
+                }
+            }
+            None
+        })
+    }
+}
+
+// the states of a UTF-8 decoder: at a char boundary, in a char with the ranges of the following bytes restricted, or broken
+const UTF8_BOUNDARY: u8 = 0;
+const UTF8_INVALID: u8 = 8;
+const UTF8_STATES: u8 = 9;
+
+// the next state of the decoder after the byte b, following the well-formed byte sequences of RFC 3629
+fn utf8_step(decoder: u8, b: u8) -> u8 {
+    match (decoder, b) {
+        (0, 0x00..=0x7f) => UTF8_BOUNDARY,
+        (0, 0xc2..=0xdf) => 1,
+        (0, 0xe1..=0xec | 0xee..=0xef) => 2,
+        (0, 0xf1..=0xf3) => 3,
+        (0, 0xe0) => 4,
+        (0, 0xed) => 5,
+        (0, 0xf0) => 6,
+        (0, 0xf4) => 7,
+        // a continuation byte, then 0, 1 or 2 more
+        (1..=3, 0x80..=0xbf) => decoder - 1,
+        (4, 0xa0..=0xbf) | (5, 0x80..=0x9f) => 1,
+        (6, 0x90..=0xbf) | (7, 0x80..=0x8f) => 2,
+        _ => UTF8_INVALID,
+    }
+}
+
+struct Builder {
+    states: Vec<State>,
+    // (is_final, edges) to the id of the equivalent state
+    register: BTreeMap<(bool, Vec<(u8, u32)>), u32>,
+}
+
+impl Builder {
+    // minimizes the states of the path deeper than depth
+    fn freeze(&mut self, path: &mut Vec<Pending>, labels: &mut Vec<u8>, depth: usize) {
+        while path.len() > depth + 1 {
+            let id = self.intern(path.pop().unwrap());
+            let label = labels.pop().unwrap();
+            path.last_mut().unwrap().edges.push((label, id));
+        }
+    }
+
+    fn intern(&mut self, pending: Pending) -> u32 {
+        let signature = (pending.is_final, pending.edges);
+        if let Some(id) = self.register.get(&signature) {
+            return *id;
+        }
+        let id = self.states.len() as u32;
+        let count = signature.1.iter().map(|(_, target)| self.states[*target as usize].count).sum::<usize>()
+            + signature.0 as usize;
+        self.states.push(State { edges: signature.1.clone(), is_final: signature.0, count });
+        self.register.insert(signature, id);
+        id
+    }
+}
+
+impl Dawg {
+    /// 状態遷移図を Graphviz の DOT 言語で返します。
+    ///
+    /// 状態の番号は内部の番号で、受理状態を二重丸で描きます。
+    /// 共有された状態は、複数の辺が入る 1 つのノードとして描かれます。
+    /// 辺のラベルは印字できる ASCII の文字で、それ以外のバイトは `\xE3` のように 16 進数で表します。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_study::string::trie::Dawg;
+    /// let dawg = Dawg::new(&["tap", "top"]);
+    /// let dot = dawg.to_dot();
+    /// assert!(dot.starts_with("digraph trie {"));
+    /// // "a" and "o" lead to the same state
+    /// assert_eq!(4, dot.matches(" -> ").count());
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut dot = DotWriter::new();
+        let mut visited = vec![false; self.states.len()];
+        visited[self.root as usize] = true;
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            let state = &self.states[id as usize];
+            dot.node(id as usize, state.is_final);
+            for (b, next) in &state.edges {
+                dot.edge(id as usize, *next as usize, &dot::byte_label(*b));
+                if !visited[*next as usize] {
+                    visited[*next as usize] = true;
+                    stack.push(*next);
+                }
+            }
+        }
+        dot.finish()
+    }
+
+    fn write_tree(&self, w: &mut TreeWriter, id: u32, depth: usize, is_last: bool, label: &str) -> fmt::Result {
+        let state = &self.states[id as usize];
+        w.node(depth, is_last, label, state.is_final)?;
+        for (i, (b, next)) in state.edges.iter().enumerate() {
+            self.write_tree(w, *next, depth + 1, i + 1 == state.edges.len(), &dot::byte_label(*b))?;
+        }
+        Ok(())
+    }
+}
+
+/// 状態遷移を根から展開した木として書き出します。受理状態には `*` を付けます。
+///
+/// 共有された状態は展開されるため、共有の様子を見るには [`Dawg::to_dot()`] を使います。
+#[cfg(feature = "std")]
+impl Serializable for Dawg {
+    const MAGIC: [u8; 8] = *b"DAWGSTRI";
+    const VERSION: u32 = 1;
+
+    fn write_body<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_to(w)
+    }
+
+    fn read_body<R: Read>(r: &mut R) -> io::Result<Self> {
+        Self::read_from(r)
+    }
+}
+
+impl fmt::Display for Dawg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_tree(&mut TreeWriter::new(f), self.root, 0, true, "")
+    }
+}
+
+impl Trie for Dawg {
+    fn contains(&self, s: &str) -> bool {
+        self.find(s.as_bytes()).is_some_and(|id| self.state(id).is_final)
+    }
+
+    fn prefix<'a>(&self, s:&'a str) -> &'a str {
+        let len = self.common_prefixes(s).last().map_or(0, |key| key.len());
+        &s[0..len]
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let bytes = query.as_bytes();
+        let mut state = Some(self.root);
+        // state corresponds to query[0..end]
+        let mut end = 0;
+        core::iter::from_fn(move || {
+            loop {
+                let current = state?;
+                let len = end;
+                state = bytes.get(end).and_then(|b| self.child(current, *b));
+                end += 1;
+                // an accepted key always ends at a char boundary of the query
+                if self.state(current).is_final {
+                    return Some(&query[0..len]);
+                }
+            }
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.state(self.root).count
+    }
+
+    fn count_prefix(&self, prefix: &str) -> usize {
+        self.find(prefix.as_bytes()).map_or(0, |id| self.state(id).count)
+    }
+
+    fn predict(&self, prefix: &str) -> impl Iterator<Item = String> + '_ {
+        let mut stack: Vec<(u32, Vec<u8>)> = self.find(prefix.as_bytes())
+            .map(|id| (id, prefix.as_bytes().to_vec()))
+            .into_iter()
+            .collect();
+        core::iter::from_fn(move || {
+            while let Some((id, key)) = stack.pop() {
+                let state = self.state(id);
+                for (b, target) in state.edges.iter().rev() {
+                    let mut next_key = key.clone();
+                    next_key.push(*b);
+                    stack.push((*target, next_key));
+                }
+                if state.is_final {
+                    return Some(String::from_utf8(key).expect("keys are valid UTF-8"));
+                }
+            }
+            None
+        })
+    }
+
+    fn cursor(&self) -> impl TrieCursor<'_> {
+        Cursor { dawg: self, id: self.root }
+    }
+
+    fn fuzzy_search(&self, query: &str, max_edits: usize) -> Vec<(String, usize)> {
+        self.search_by(Levenshtein::new(query, max_edits)).collect()
+    }
+
+    fn hamming_search(&self, query: &str, max_mismatches: usize) -> Vec<(String, usize)> {
+        self.search_by(Hamming::new(query, max_mismatches)).collect()
+    }
+
+    fn search_pattern(&self, pattern: &str) -> Vec<String> {
+        self.search_by(Wildcard::new(pattern)).map(|(key, _)| key).collect()
+    }
+
+    fn range<'a, 'b, R: RangeBounds<&'b str> + 'a>(&'a self, range: R) -> impl Iterator<Item = String> + 'a {
+        self.search_by(KeyRange::new(range)).map(|(key, _)| key)
+    }
+}
+
+/// 番号はキーの辞書順です。
+impl TrieId for Dawg {
+    fn key_to_id(&self, s: &str) -> Option<usize> {
+        Dawg::key_to_id(self, s)
+    }
+
+    fn id_to_key(&self, id: usize) -> Option<String> {
+        Dawg::id_to_key(self, id)
+    }
+}
+
+#[derive(Clone)]
+struct Cursor<'a> {
+    dawg: &'a Dawg,
+    id: u32,
+}
+
+impl <'a> TrieCursor<'a> for Cursor<'a> {
+    type Value = ();
+
+    fn step(&mut self, c: char) -> bool {
+        let mut buf = [0; 4];
+        match c.encode_utf8(&mut buf).bytes().try_fold(self.id, |id, b| self.dawg.child(id, b)) {
+            Some(id) => {
+                self.id = id;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn value(&self) -> Option<&'a ()> {
+        if self.dawg.state(self.id).is_final {
+            Some(&())
+        } else {
+            None
+        }
+    }
+}
+
+impl SpaceUsage for State {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("edges", &self.edges)]
+    }
+}
+
+impl SpaceUsage for Dawg {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("states", &self.states)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::trie::ByteTrie;
+    use crate::string::trie::conformance;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    #[test]
+    fn dot() {
+        // "ab" and "b" share the final state
+        let dawg = Dawg::new(&["ab", "b"]);
+        let dot = dawg.to_dot();
+        assert_eq!(3, dawg.size());
+        assert_eq!(1, dot.matches("doublecircle").count());
+        assert_eq!(3, dot.matches(" -> ").count());
+        assert_eq!(".\n├── a\n│   └── b *\n└── b *\n", dawg.to_string());
+    }
+
+    #[test]
+    fn conformance() {
+        conformance::check_static(Dawg::new);
+    }
+
+    #[test]
+    fn same_as_byte_trie() {
+        proptest!(|(keys in vec(conformance::key_of(&['a', 'b', 'あ', 'い'], 6), 0..300))| {
+            let dawg = Dawg::new(&keys);
+            let mut trie = ByteTrie::new();
+            keys.iter().for_each(|key| { trie.append(key); });
+            prop_assert_eq!(trie.len(), dawg.len());
+            prop_assert!(dawg.size() <= trie.size());
+            let sorted: Vec<String> = trie.keys().collect();
+            prop_assert_eq!(&sorted, &dawg.keys().collect::<Vec<_>>());
+            for (id, key) in sorted.iter().enumerate() {
+                prop_assert_eq!(Some(id), dawg.key_to_id(key));
+                prop_assert_eq!(Some(key.clone()), dawg.id_to_key(id));
+            }
+            prop_assert_eq!(None, dawg.id_to_key(sorted.len()));
+        });
+    }
+
+    #[test]
+    fn share_suffixes() {
+        let words = ["walk", "walks", "walked", "walking", "talk", "talks", "talked", "talking"];
+        let dawg = Dawg::new(&words);
+        // root -w/t-> . -a-> . -l-> . -k-> (final) -s/ed/ing-> (final), with 3 states for "e", "i" and "in"
+        assert_eq!(9, dawg.size());
+        assert_eq!(8, dawg.len());
+
+        let empty = Dawg::new::<&str>(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(1, empty.size());
+        assert_eq!(None, empty.key_to_id(""));
+        assert_eq!(None, empty.id_to_key(0));
+    }
+
+    #[test]
+    fn from_sorted_keys() {
+        let mut keys = vec!["b", "", "a", "ab", "b", "あ", "ba"];
+        let expected = Dawg::new(&keys);
+        keys.sort();
+        let trie = Dawg::from_sorted_keys(keys.iter());
+        assert_eq!(expected.keys().collect::<Vec<_>>(), trie.keys().collect::<Vec<_>>());
+        assert_eq!(expected.size(), trie.size());
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_unsorted_keys() {
+        Dawg::from_sorted_keys(["b", "a"]);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn par_new() {
+        let keys = ["walks", "walk", "talk", "walk", "", "あ"];
+        let dawg = Dawg::par_new(&keys, Chunking::new(2));
+        assert_eq!(Dawg::new(&keys).keys().collect::<Vec<_>>(), dawg.keys().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn try_from_sorted_keys() {
+        assert_eq!(2, Dawg::try_from_sorted_keys(["a", "b", "b"]).unwrap().keys().count());
+        assert!(matches!(Dawg::try_from_sorted_keys(["b", "a"]), Err(Error::UnsortedKeys { index: 1 })));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn write_and_read() {
+        let dawg = Dawg::new(&["walk", "walks", "talk", "talks", "", "あ"]);
+        let mut bytes = vec![];
+        dawg.write_to(&mut bytes).unwrap();
+        let read = Dawg::read_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(dawg.size(), read.size());
+        assert_eq!(dawg.keys().collect::<Vec<_>>(), read.keys().collect::<Vec<_>>());
+        assert_eq!(dawg.key_to_id("talks"), read.key_to_id("talks"));
+
+        bytes[0] = b'X';
+        assert!(Dawg::read_from(&mut bytes.as_slice()).is_err());
+        assert!(Dawg::read_from(&mut &bytes[0..20]).is_err());
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(Ok(()), Dawg::new(&["walk", "walks", "talk", "talks", "", "あ"]).validate());
+        assert_eq!(Ok(()), Dawg::new::<&str>(&[]).validate());
+
+        let broken = |f: fn(&mut Dawg)| {
+            // the states are the final one shared by "ab" and "b", the one after "a", and the root
+            let mut dawg = Dawg::new(&["ab", "b"]);
+            f(&mut dawg);
+            dawg.validate().unwrap_err().to_string()
+        };
+        assert_eq!("corrupt data: state 1 has 2 keys, expected 1", broken(|dawg| dawg.states[1].count = 2));
+        assert_eq!("corrupt data: the labels of the edges from state 2 are not strictly increasing", broken(|dawg| dawg.states[2].edges.swap(0, 1)));
+        assert_eq!("corrupt data: state 1 has an edge to a state not preceding it", broken(|dawg| dawg.states[1].edges[0].1 = 1));
+        assert_eq!("corrupt data: a key accepted at state 0 is not valid UTF-8", broken(|dawg| dawg.states[2].edges[1].0 = 0xe3));
+        assert_eq!("corrupt data: 3 states with the root 3", broken(|dawg| dawg.root = 3));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn read_broken() {
+        // the states as (is_final, edges) in topological order, with the last one as the root
+        let write = |states: &[(bool, Vec<(u8, u32)>)]| {
+            let mut bytes = MAGIC.to_vec();
+            binary::write_u64(&mut bytes, states.len() as u64).unwrap();
+            binary::write_u64(&mut bytes, states.len() as u64 - 1).unwrap();
+            for (is_final, edges) in states {
+                binary::write_u64(&mut bytes, *is_final as u64).unwrap();
+                binary::write_u64(&mut bytes, edges.len() as u64).unwrap();
+                for (label, target) in edges {
+                    binary::write_u64(&mut bytes, (*label as u64) << 32 | *target as u64).unwrap();
+                }
+            }
+            bytes
+        };
+        let error = |bytes: Vec<u8>| Dawg::read_from(&mut bytes.as_slice()).err().expect("should be rejected").to_string();
+
+        // each state doubles the number of keys, which overflows after 64 states
+        let chain: Vec<(bool, Vec<(u8, u32)>)> = (0..70).map(|i| if i == 0 { (true, vec![]) } else { (false, vec![(b'a', i - 1), (b'b', i - 1)]) }).collect();
+        assert_eq!(1 << 40, Dawg::read_from(&mut write(&chain[..41]).as_slice()).unwrap().len());
+        assert_eq!("corrupt data: too many keys", error(write(&chain)));
+
+        // "\xe3\x81\x82", with the last byte or the whole char cut short, or with an overlong encoding
+        let key = |labels: &[u8]| -> Vec<(bool, Vec<(u8, u32)>)> {
+            let mut states = vec![(true, vec![])];
+            for (i, &b) in labels.iter().rev().enumerate() {
+                states.push((false, vec![(b, i as u32)]));
+            }
+            states
+        };
+        assert_eq!(vec!["あ"], Dawg::read_from(&mut write(&key(b"\xe3\x81\x82")).as_slice()).unwrap().keys().collect::<Vec<_>>());
+        assert_eq!("corrupt data: a key accepted at state 0 is not valid UTF-8", error(write(&key(b"\xe3\x81"))));
+        assert!(error(write(&key(b"a\xff"))).contains("not valid UTF-8"));
+        assert!(error(write(&key(b"\xe0\x81\x82"))).contains("not valid UTF-8"));
+        // "\x81\x82" shares its states with "あ"
+        let mut shared = key(b"\xe3\x81\x82");
+        shared[3].1.insert(0, (0x81, 1));
+        assert!(error(write(&shared)).contains("not valid UTF-8"));
+    }
+}
@@ -0,0 +1,78 @@
+// Rendering of the tries for debugging: Graphviz DOT, and an indented tree for Display.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Write;
+
+// builds a directed graph in the DOT language, drawing the nodes of keys with double circles
+pub struct DotWriter {
+    out: String,
+}
+
+impl DotWriter {
+    pub fn new() -> Self {
+        DotWriter { out: String::from("digraph trie {\n    node [shape=circle, label=\"\"];\n") }
+    }
+
+    pub fn node(&mut self, id: usize, is_key: bool) {
+        if is_key {
+            writeln!(self.out, "    {} [shape=doublecircle];", id).unwrap();
+        } else {
+            writeln!(self.out, "    {};", id).unwrap();
+        }
+    }
+
+    pub fn edge(&mut self, from: usize, to: usize, label: &str) {
+        let escaped = label.replace('\\', "\\\\").replace('"', "\\\"");
+        writeln!(self.out, "    {} -> {} [label=\"{}\"];", from, to, escaped).unwrap();
+    }
+
+    pub fn finish(mut self) -> String {
+        self.out.push_str("}\n");
+        self.out
+    }
+}
+
+// the label of an edge of a byte, as the char if it is printable ASCII
+pub fn byte_label(b: u8) -> String {
+    if b.is_ascii_graphic() || b == b' ' {
+        char::from(b).to_string()
+    } else {
+        format!("\\x{:02X}", b)
+    }
+}
+
+// writes the nodes in preorder as a tree with box-drawing lines, marking the nodes of keys with `*`
+pub struct TreeWriter<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    // whether the ancestor at each depth has following siblings
+    open: Vec<bool>,
+}
+
+impl <'a, 'b> TreeWriter<'a, 'b> {
+    pub fn new(f: &'a mut fmt::Formatter<'b>) -> Self {
+        TreeWriter { f, open: vec![] }
+    }
+
+    // the root is at depth 0, and is_last tells whether the node is the last child of its parent
+    pub fn node(&mut self, depth: usize, is_last: bool, label: &str, is_key: bool) -> fmt::Result {
+        self.open.truncate(depth);
+        if depth == 0 {
+            write!(self.f, ".")?;
+        } else {
+            for open in &self.open[1..] {
+                write!(self.f, "{}", if *open { "│   " } else { "    " })?;
+            }
+            write!(self.f, "{}{}", if is_last { "└── " } else { "├── " }, label)?;
+        }
+        self.open.push(!is_last);
+        if is_key {
+            write!(self.f, " *")?;
+        }
+        writeln!(self.f)
+    }
+}
@@ -0,0 +1,1323 @@
+#[cfg(feature = "std")]
+use super::binary;
+use super::automaton::Automaton;
+use super::automaton::Hamming;
+use super::automaton::KeyRange;
+use super::automaton::Levenshtein;
+use super::automaton::Wildcard;
+use super::Trie;
+use super::TrieCursor;
+use super::TrieId;
+use crate::bits::fid::FID;
+use crate::bits::fid::NaiveFID;
+use crate::error;
+use crate::error::Error;
+#[cfg(feature = "parallel")]
+use crate::parallel;
+#[cfg(feature = "parallel")]
+use crate::parallel::Chunking;
+use crate::progress;
+use crate::progress::NoProgress;
+use crate::progress::Phase;
+use crate::progress::Progress;
+use crate::query::QueryBuffer;
+#[cfg(feature = "std")]
+use crate::serialize::Serializable;
+use crate::space::SpaceUsage;
+
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::Range;
+use core::ops::RangeBounds;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+#[cfg(feature = "std")]
+const MAGIC: &[u8; 8] = b"LOUDSTR1";
+
+/// LOUDS (Level-Order Unary Degree Sequence) による静的なトライ木
+///
+/// ノードを幅優先順に番号付けし、各ノードの子の数を 1 進符号 (子の数個の `1` と区切りの `0`) で並べた
+/// ビットベクトルの rank/select 操作で木をたどります。
+/// 各ノードが持つのは入ってくる辺のラベル 1 バイトと、キーの終端かどうかの 1 ビットだけで、
+/// ノードごとにポインタを持つトライ木に比べて使用メモリを大きく減らせます。
+///
+/// 構築後にキーを追加・削除することはできません。
+/// 各キーには `[0, len)` の番号が割り当てられ、キーと番号を相互に変換できます。
+///
+/// [`Self::write_to()`] で書き出したバイト列は、 [`Self::read_from()`] で読み込むほか、
+/// [`LoudsTrieView`] で複製せずにそのまま検索に使えます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::NaiveLoudsTrie;
+/// use rust_study::string::trie::Trie;
+/// let trie = NaiveLoudsTrie::new(&["the", "their", "them", "this", "あいう"]);
+/// assert_eq!(5, trie.len());
+/// assert!(trie.contains("them"));
+/// assert!(!trie.contains("th"));
+/// assert_eq!("their", trie.prefix("theirs"));
+/// assert_eq!(vec!["the", "their", "them"], trie.predict("the").collect::<Vec<_>>());
+///
+/// let id = trie.key_to_id("their").unwrap();
+/// assert_eq!(Some("their".to_string()), trie.id_to_key(id));
+/// assert_eq!(None, trie.key_to_id("thei"));
+/// ```
+pub struct LoudsTrie<T: FID> {
+    // "10" for the super root, then deg(v) ones followed by a zero, for each node v in level order
+    louds: T,
+    // the label of the edge into each node
+    labels: Vec<u8>,
+    terminals: T,
+}
+
+pub type NaiveLoudsTrie = LoudsTrie<NaiveFID>;
+
+impl <T: FID> LoudsTrie<T> {
+    /// キーの列 `keys` からトライ木を構築します。重複したキーは 1 つにまとめられます。
+    pub fn new<S: AsRef<str>>(keys: &[S]) -> Self {
+        Self::with_progress(keys, &mut NoProgress)
+    }
+
+    /// [`Self::new()`] と同じく構築し、 [`Phase::SortKeys`] と [`Phase::BuildNodes`] の進み具合を `progress` に通知します。
+    ///
+    /// キーの整列は始めと終わりにだけ通知します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `progress` cancels the construction.
+    pub fn with_progress<S: AsRef<str>, P: Progress + ?Sized>(keys: &[S], progress: &mut P) -> Self {
+        Self::try_with_progress(keys, progress).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [`Self::with_progress()`] と同じですが、 `progress` が中断した場合、パニックせずに [`Error::Cancelled`] を返します。
+    pub fn try_with_progress<S: AsRef<str>, P: Progress + ?Sized>(keys: &[S], progress: &mut P) -> Result<Self, Error> {
+        progress::checkpoint(progress, Phase::SortKeys, 0, keys.len())?;
+        let mut keys: Vec<&[u8]> = keys.iter().map(|key| key.as_ref().as_bytes()).collect();
+        keys.sort();
+        progress::checkpoint(progress, Phase::SortKeys, keys.len(), keys.len())?;
+        keys.dedup();
+        Self::build::<Vec<bool>, P>(&keys, progress)
+    }
+
+    /// [`Self::new()`] と同じトライ木を、作業領域を含めておよそ `bytes` バイトのメモリで構築します。
+    ///
+    /// 通常の構築は、 LOUDS と終端のビットを 1 バイトずつ持つ列に集めてからビットベクトルにします。
+    /// キーの整列の作業領域とノードごとのこれらの列の見積もりが `bytes` を超える場合、ビットを 64 ビットの語に詰めながら集めて、ノードごとの作業領域を 1 バイト足らずに抑えます。
+    /// 省メモリの構築でも `bytes` に収まらない場合、構築せずに [`Error::MemoryLimit`] を返します。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_study::string::trie::NaiveLoudsTrie;
+    /// use rust_study::string::trie::Trie;
+    /// let trie = NaiveLoudsTrie::with_memory_limit(&["the", "their", "them"], 100).unwrap();
+    /// assert_eq!(vec!["the", "their", "them"], trie.keys().collect::<Vec<_>>());
+    /// assert!(NaiveLoudsTrie::with_memory_limit(&["the", "their", "them"], 0).is_err());
+    /// ```
+    pub fn with_memory_limit<S: AsRef<str>>(keys: &[S], bytes: usize) -> Result<Self, Error> {
+        let total: usize = keys.iter().map(|key| key.as_ref().len() + 1).sum();
+        let sorting = keys.len() * mem::size_of::<&[u8]>();
+        if sorting + 5 * total <= bytes {
+            return Ok(Self::new(keys));
+        }
+        // the labels, and the packed bits of the LOUDS and the terminals with their output
+        let required = sorting.saturating_add(2 * total);
+        if required > bytes {
+            return Err(Error::MemoryLimit { required, limit: bytes });
+        }
+        let mut keys: Vec<&[u8]> = keys.iter().map(|key| key.as_ref().as_bytes()).collect();
+        keys.sort();
+        keys.dedup();
+        Self::build::<PackedBits, _>(&keys, &mut NoProgress)
+    }
+
+    /// [`Self::new()`] と同じトライ木を、キーの整列を並列に処理して構築します。
+    #[cfg(feature = "parallel")]
+    pub fn par_new<S: AsRef<str> + Sync>(keys: &[S], chunking: Chunking) -> Self {
+        let keys: Vec<&[u8]> = parallel::sorted_keys(keys, chunking).into_iter().map(str::as_bytes).collect();
+        Self::build::<Vec<bool>, _>(&keys, &mut NoProgress).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// 辞書順にソートされたキーの列 `keys` からトライ木を構築します。重複したキーは 1 つにまとめられます。
+    ///
+    /// [`Self::new()`] と異なり、キーを並べ替えません。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keys` are not sorted.
+    pub fn from_sorted_keys<I: IntoIterator<Item = S>, S: AsRef<str>>(keys: I) -> Self {
+        Self::try_from_sorted_keys(keys).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [`Self::from_sorted_keys()`] と同じですが、 `keys` がソートされていない場合、
+    /// パニックせずに [`Error::UnsortedKeys`] を返します。
+    pub fn try_from_sorted_keys<I: IntoIterator<Item = S>, S: AsRef<str>>(keys: I) -> Result<Self, Error> {
+        let mut sorted: Vec<String> = vec![];
+        for (index, key) in keys.into_iter().enumerate() {
+            let key = key.as_ref();
+            if let Some(last) = sorted.last() {
+                if last.as_str() > key {
+                    return Err(Error::UnsortedKeys { index });
+                }
+                if last == key {
+                    continue;
+                }
+            }
+            sorted.push(key.to_string());
+        }
+        let keys: Vec<&[u8]> = sorted.iter().map(|key| key.as_bytes()).collect();
+        Self::build::<Vec<bool>, _>(&keys, &mut NoProgress)
+    }
+
+    // keys should be sorted and deduplicated, and B collects the bits of the LOUDS and the terminals
+    fn build<B: BitBuf, P: Progress + ?Sized>(keys: &[&[u8]], progress: &mut P) -> Result<Self, Error> {
+        let mut louds = B::default();
+        louds.push(true);
+        louds.push(false);
+        let mut labels = vec![0];
+        let mut terminals = B::default();
+        // each key is under a node at every depth from 0 to its length
+        let total = keys.iter().map(|key| key.len() + 1).sum();
+        let mut done = 0;
+        let mut next = progress::STEP;
+        progress::checkpoint(progress, Phase::BuildNodes, 0, total)?;
+        // (the range of keys under the node, the depth of the node)
+        let mut queue = VecDeque::from(vec![(0, keys.len(), 0)]);
+        while let Some((s, e, depth)) = queue.pop_front() {
+            done += e - s;
+            if done >= next && done < total {
+                progress::checkpoint(progress, Phase::BuildNodes, done, total)?;
+                next = done + progress::STEP;
+            }
+            let mut i = s;
+            let terminal = i < e && keys[i].len() == depth;
+            terminals.push(terminal);
+            if terminal {
+                i += 1;
+            }
+            while i < e {
+                let label = keys[i][depth];
+                let j = i + keys[i..e].partition_point(|key| key[depth] == label);
+                louds.push(true);
+                labels.push(label);
+                queue.push_back((i, j, depth + 1));
+                i = j;
+            }
+            louds.push(false);
+        }
+        progress::checkpoint(progress, Phase::BuildNodes, total, total)?;
+        Ok(LoudsTrie {
+            louds: louds.into_fid(),
+            labels,
+            terminals: terminals.into_fid(),
+        })
+    }
+
+    /// キー `s` の番号を返します。キーが存在しない場合、 `None` を返します。
+    pub fn key_to_id(&self, s: &str) -> Option<usize> {
+        Louds::key_to_id(self, s)
+    }
+
+    /// 番号 `id` のキーを返します。 `id` が範囲外の場合、 `None` を返します。
+    pub fn id_to_key(&self, id: usize) -> Option<String> {
+        Louds::id_to_key(self, id)
+    }
+
+    /// `prefix` から始まるキーを、 [`Trie::predict()`] と同じく辞書順に `out` に書き込みます。
+    ///
+    /// 探索のスタックには `buffer` を使い、 `out` に残っている `String` の領域はキーの書き込みに再利用します。
+    /// 同じ `buffer` と `out` で繰り返し検索すると、容量が足りている限り領域を確保しません。
+    pub fn predict_into(&self, prefix: &str, buffer: &mut QueryBuffer, out: &mut Vec<String>) {
+        self.keys_under_into(prefix, buffer, out)
+    }
+
+    /// ノードの個数を返します。
+    pub fn size(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// 内部の整合性を検証し、最初に見つけた不整合を [`Error::CorruptData`] で返します。
+    ///
+    /// 各ビットベクトルの長さと [`FID::validate()`] に加えて、 LOUDS のビット列が幅優先順の木を表すこと、
+    /// 兄弟のラベルが昇順に並ぶこと、葉がすべてキーの終端であること、キーがすべて UTF-8 であることを `O(ノード数)` で確かめます。
+    /// 信頼できないファイルや壊れている恐れのあるファイルから読み込んだ後に呼び出します。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_study::string::trie::NaiveLoudsTrie;
+    /// let trie = NaiveLoudsTrie::new(&["the", "their", "them"]);
+    /// assert_eq!(Ok(()), trie.validate());
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        let nodes = self.labels.len();
+        if nodes == 0 || self.terminals.len() != nodes || self.louds.len() != 2 * nodes + 1 {
+            return Err(Error::CorruptData(format!(
+                "{} labels, {} terminal bits and {} LOUDS bits, expected 2 * nodes + 1 LOUDS bits and as many terminal bits as labels",
+                nodes, self.terminals.len(), self.louds.len(),
+            )));
+        }
+        self.louds.validate().map_err(|e| error::in_part(e, "LOUDS bits"))?;
+        self.terminals.validate().map_err(|e| error::in_part(e, "terminal bits"))?;
+        if self.louds.rank1(self.louds.len()) != nodes {
+            return Err(Error::CorruptData(format!("{} ones in the LOUDS bits for {} nodes", self.louds.rank1(self.louds.len()), nodes)));
+        }
+        self.validate_tree()
+    }
+
+    /// トライ木をバイト列として `w` に書き出します。
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        write_bits(w, &self.louds)?;
+        binary::write_bytes(w, &self.labels)?;
+        write_bits(w, &self.terminals)
+    }
+
+    /// [`Self::write_to()`] で書き出したバイト列を `r` から読み込みます。
+    ///
+    /// バイト列が壊れている場合、 `ErrorKind::InvalidData` のエラーを返します。
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        let mut bytes = vec![];
+        r.read_to_end(&mut bytes)?;
+        let view = LoudsTrieView::new(&bytes)?;
+        Ok(LoudsTrie {
+            louds: T::from_bool_vec(&view.louds.to_bool_vec()),
+            labels: view.labels.to_vec(),
+            terminals: T::from_bool_vec(&view.terminals.to_bool_vec()),
+        })
+    }
+}
+
+// the bits collected while building a trie, turned into a bit vector at the end
+trait BitBuf: Default {
+    fn push(&mut self, bit: bool);
+
+    fn into_fid<T: FID>(self) -> T;
+}
+
+impl BitBuf for Vec<bool> {
+    fn push(&mut self, bit: bool) {
+        Vec::push(self, bit)
+    }
+
+    fn into_fid<T: FID>(self) -> T {
+        T::from_bool_vec(&self)
+    }
+}
+
+// the bits packed into words, from the least significant bit
+#[derive(Default)]
+struct PackedBits {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitBuf for PackedBits {
+    fn push(&mut self, bit: bool) {
+        if self.len.is_multiple_of(64) {
+            self.words.push(0);
+        }
+        self.words[self.len / 64] |= (bit as u64) << (self.len % 64);
+        self.len += 1;
+    }
+
+    fn into_fid<T: FID>(self) -> T {
+        T::from_bool_iter((0..self.len).map(|i| (self.words[i / 64] >> (i % 64)) & 1 != 0))
+    }
+}
+
+#[cfg(feature = "std")]
+impl <T: FID> Serializable for LoudsTrie<T> {
+    const MAGIC: [u8; 8] = *b"LOUDSTRI";
+    const VERSION: u32 = 1;
+
+    fn write_body<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_to(w)
+    }
+
+    fn read_body<R: Read>(r: &mut R) -> io::Result<Self> {
+        Self::read_from(r)
+    }
+}
+
+#[cfg(feature = "std")]
+fn write_bits<W: Write, T: FID>(w: &mut W, bits: &T) -> io::Result<()> {
+    let n = bits.len();
+    let mut words = vec![0_u64; n / 64 + 1];
+    for i in (0..n).filter(|i| bits.get(*i)) {
+        words[i / 64] |= 1 << (i % 64);
+    }
+    binary::write_u64(w, n as u64)?;
+    let mut rank = 0;
+    for word in &words {
+        binary::write_u64(w, rank)?;
+        binary::write_u64(w, *word)?;
+        rank += word.count_ones() as u64;
+    }
+    Ok(())
+}
+
+// a bit vector in the binary format: for each 64 bits, the number of ones before them and the bits
+#[cfg(feature = "std")]
+struct BitsView<'a> {
+    n: usize,
+    blocks: &'a [u8],
+}
+
+#[cfg(feature = "std")]
+impl <'a> BitsView<'a> {
+    fn parse(slice: &mut binary::Slice<'a>) -> io::Result<Self> {
+        let n = slice.len()?;
+        let len = (n / 64 + 1).checked_mul(16).ok_or_else(|| binary::invalid_data("length overflow"))?;
+        let view = BitsView { n, blocks: slice.take(len)? };
+        let mut rank = 0;
+        for b in 0..view.blocks.len() / 16 {
+            if view.rank_before(b) != rank {
+                return Err(binary::invalid_data("broken rank directory"));
+            }
+            rank += view.word(b).count_ones() as usize;
+        }
+        if view.word(n / 64) >> (n % 64) != 0 {
+            return Err(binary::invalid_data("bits out of range"));
+        }
+        Ok(view)
+    }
+
+    fn rank_before(&self, b: usize) -> usize {
+        u64::from_le_bytes(self.blocks[b * 16..b * 16 + 8].try_into().unwrap()) as usize
+    }
+
+    fn word(&self, b: usize) -> u64 {
+        u64::from_le_bytes(self.blocks[b * 16 + 8..b * 16 + 16].try_into().unwrap())
+    }
+
+    fn num_blocks(&self) -> usize {
+        self.blocks.len() / 16
+    }
+
+    fn access(&self, i: usize) -> bool {
+        assert!(i < self.n);
+        self.word(i / 64) >> (i % 64) & 1 == 1
+    }
+
+    fn rank1(&self, i: usize) -> usize {
+        assert!(i <= self.n);
+        let mask = (1_u64 << (i % 64)) - 1;
+        self.rank_before(i / 64) + (self.word(i / 64) & mask).count_ones() as usize
+    }
+
+    fn rank0(&self, i: usize) -> usize {
+        i - self.rank1(i)
+    }
+
+    fn select1(&self, k: usize) -> usize {
+        let b = self.last_block(|b| self.rank_before(b) <= k);
+        b * 64 + nth_one(self.word(b), k - self.rank_before(b))
+    }
+
+    fn select0(&self, k: usize) -> usize {
+        let zeros_before = |b: usize| b * 64 - self.rank_before(b);
+        let b = self.last_block(|b| zeros_before(b) <= k);
+        b * 64 + nth_one(!self.word(b), k - zeros_before(b))
+    }
+
+    // the last block satisfying a monotone predicate, which the first block satisfies
+    fn last_block<F: Fn(usize) -> bool>(&self, f: F) -> usize {
+        let (mut lo, mut hi) = (0, self.num_blocks());
+        while hi - lo > 1 {
+            let mid = (lo + hi) / 2;
+            if f(mid) {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    fn to_bool_vec(&self) -> Vec<bool> {
+        (0..self.n).map(|i| self.access(i)).collect()
+    }
+}
+
+// the position of the k-th (0-based) one in word
+#[cfg(feature = "std")]
+fn nth_one(mut word: u64, k: usize) -> usize {
+    for _ in 0..k {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+/// [`LoudsTrie::write_to()`] で書き出したバイト列を、複製せずにそのまま参照する LOUDS トライ木
+///
+/// メモリマップしたファイルの上に構築すれば、辞書を読み込み直すことなく検索できます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::LoudsTrieView;
+/// use rust_study::string::trie::NaiveLoudsTrie;
+/// use rust_study::string::trie::Trie;
+/// let mut bytes = vec![];
+/// NaiveLoudsTrie::new(&["foo", "foobar", "bar"]).write_to(&mut bytes).unwrap();
+///
+/// let view = LoudsTrieView::new(&bytes).unwrap();
+/// assert!(view.contains("foobar"));
+/// assert_eq!(vec!["foo", "foobar"], view.predict("fo").collect::<Vec<_>>());
+/// ```
+#[cfg(feature = "std")]
+pub struct LoudsTrieView<'a> {
+    louds: BitsView<'a>,
+    labels: &'a [u8],
+    terminals: BitsView<'a>,
+}
+
+#[cfg(feature = "std")]
+impl <'a> LoudsTrieView<'a> {
+    /// [`LoudsTrie::write_to()`] で書き出したバイト列 `bytes` を参照します。
+    ///
+    /// バイト列の形式と木の形を [`LoudsTrie::validate()`] と同じく検証し、壊れている場合は `ErrorKind::InvalidData` のエラーを返します。
+    /// 検証にはノード数 `n` に対して `O(n log n)` の時間がかかります。
+    pub fn new(bytes: &'a [u8]) -> io::Result<Self> {
+        let layout = Layout::parse(bytes)?;
+        Ok(Self::with_layout(bytes, &layout))
+    }
+
+    fn with_layout(bytes: &'a [u8], layout: &Layout) -> Self {
+        let bits = |(n, blocks): &(usize, Range<usize>)| BitsView { n: *n, blocks: &bytes[blocks.clone()] };
+        LoudsTrieView { louds: bits(&layout.louds), labels: &bytes[layout.labels.clone()], terminals: bits(&layout.terminals) }
+    }
+
+    /// キー `s` の番号を返します。キーが存在しない場合、 `None` を返します。
+    pub fn key_to_id(&self, s: &str) -> Option<usize> {
+        Louds::key_to_id(self, s)
+    }
+
+    /// 番号 `id` のキーを返します。 `id` が範囲外の場合、 `None` を返します。
+    pub fn id_to_key(&self, id: usize) -> Option<String> {
+        Louds::id_to_key(self, id)
+    }
+
+    /// `prefix` から始まるキーを、 [`Trie::predict()`] と同じく辞書順に `out` に書き込みます。
+    ///
+    /// 探索のスタックには `buffer` を使い、 `out` に残っている `String` の領域はキーの書き込みに再利用します。
+    /// 同じ `buffer` と `out` で繰り返し検索すると、容量が足りている限り領域を確保しません。
+    pub fn predict_into(&self, prefix: &str, buffer: &mut QueryBuffer, out: &mut Vec<String>) {
+        self.keys_under_into(prefix, buffer, out)
+    }
+
+    /// ノードの個数を返します。
+    pub fn size(&self) -> usize {
+        self.labels.len()
+    }
+}
+
+// the positions of the parts in the bytes written by LoudsTrie::write_to(), with the number of bits of the bit vectors
+#[cfg(feature = "std")]
+struct Layout {
+    louds: (usize, Range<usize>),
+    labels: Range<usize>,
+    terminals: (usize, Range<usize>),
+}
+
+#[cfg(feature = "std")]
+impl Layout {
+    // parses and validates the bytes, including the shape of the tree
+    fn parse(bytes: &[u8]) -> io::Result<Self> {
+        let mut slice = binary::Slice { rest: bytes };
+        if slice.take(MAGIC.len())? != MAGIC {
+            return Err(binary::invalid_data("unexpected magic number"));
+        }
+        let offset = |slice: &binary::Slice| bytes.len() - slice.rest.len();
+        let bits = |slice: &mut binary::Slice| -> io::Result<(usize, Range<usize>)> {
+            let view = BitsView::parse(slice)?;
+            let end = offset(slice);
+            Ok((view.n, end - view.blocks.len()..end))
+        };
+        let louds = bits(&mut slice)?;
+        let labels_len = slice.len()?;
+        let labels_start = offset(&slice);
+        slice.take(labels_len)?;
+        let labels = labels_start..labels_start + labels_len;
+        let terminals = bits(&mut slice)?;
+        if !slice.rest.is_empty() {
+            return Err(binary::invalid_data("trailing bytes"));
+        }
+        let layout = Layout { louds, labels, terminals };
+        let view = LoudsTrieView::with_layout(bytes, &layout);
+        let nodes = view.labels.len();
+        let ones = view.louds.rank1(view.louds.n);
+        if nodes == 0 || view.terminals.n != nodes || view.louds.n != 2 * nodes + 1 || ones != nodes {
+            return Err(binary::invalid_data("inconsistent sizes"));
+        }
+        view.validate_tree()?;
+        Ok(layout)
+    }
+}
+
+// the tree operations shared by LoudsTrie and LoudsTrieView
+trait Louds {
+    fn louds_rank0(&self, i: usize) -> usize;
+    fn louds_rank1(&self, i: usize) -> usize;
+    fn louds_select0(&self, k: usize) -> usize;
+    fn louds_select1(&self, k: usize) -> usize;
+    fn labels(&self) -> &[u8];
+    fn is_terminal(&self, v: usize) -> bool;
+    fn terminal_rank1(&self, v: usize) -> usize;
+    fn terminal_select1(&self, id: usize) -> usize;
+    fn num_keys(&self) -> usize;
+
+    // the ids of the children of node v
+    fn children(&self, v: usize) -> Range<usize> {
+        let s = self.louds_select0(v) + 1;
+        let e = self.louds_select0(v + 1);
+        let first = self.louds_rank1(s);
+        first..first + (e - s)
+    }
+
+    fn parent(&self, v: usize) -> usize {
+        self.louds_rank0(self.louds_select1(v)) - 1
+    }
+
+    fn child(&self, v: usize, label: u8) -> Option<usize> {
+        let children = self.children(v);
+        let labels = &self.labels()[children.clone()];
+        labels.binary_search(&label).ok().map(|i| children.start + i)
+    }
+
+    fn find(&self, s: &[u8]) -> Option<usize> {
+        let mut v = 0;
+        for b in s {
+            v = self.child(v, *b)?;
+        }
+        Some(v)
+    }
+
+    fn key_to_id(&self, s: &str) -> Option<usize> {
+        let v = self.find(s.as_bytes())?;
+        if self.is_terminal(v) {
+            Some(self.terminal_rank1(v))
+        } else {
+            None
+        }
+    }
+
+    fn id_to_key(&self, id: usize) -> Option<String> {
+        if id >= self.num_keys() {
+            return None;
+        }
+        let mut v = self.terminal_select1(id);
+        let mut key = vec![];
+        while v != 0 {
+            key.push(self.labels()[v]);
+            v = self.parent(v);
+        }
+        key.reverse();
+        Some(String::from_utf8(key).expect("keys are valid UTF-8"))
+    }
+
+    fn common_prefixes_of<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let bytes = query.as_bytes();
+        let mut node = Some(0);
+        // node corresponds to query[0..end]
+        let mut end = 0;
+        core::iter::from_fn(move || {
+            loop {
+                let current = node?;
+                let len = end;
+                node = bytes.get(end).and_then(|b| self.child(current, *b));
+                end += 1;
+                // a stored key always ends at a char boundary of the query
+                if self.is_terminal(current) {
+                    return Some(&query[0..len]);
+                }
+            }
+        })
+    }
+
+    fn count_under(&self, prefix: &str) -> usize {
+        let mut stack: Vec<usize> = self.find(prefix.as_bytes()).into_iter().collect();
+        let mut count = 0;
+        while let Some(v) = stack.pop() {
+            if self.is_terminal(v) {
+                count += 1;
+            }
+            stack.extend(self.children(v));
+        }
+        count
+    }
+
+    fn keys_under(&self, prefix: &str) -> impl Iterator<Item = String> + '_ {
+        let mut stack: Vec<(usize, Vec<u8>)> = self.find(prefix.as_bytes())
+            .map(|v| (v, prefix.as_bytes().to_vec()))
+            .into_iter()
+            .collect();
+        core::iter::from_fn(move || {
+            while let Some((v, key)) = stack.pop() {
+                for child in self.children(v).rev() {
+                    let mut child_key = key.clone();
+                    child_key.push(self.labels()[child]);
+                    stack.push((child, child_key));
+                }
+                if self.is_terminal(v) {
+                    return Some(String::from_utf8(key).expect("keys are valid UTF-8"));
+                }
+            }
+            None
+        })
+    }
+
+    fn keys_under_into(&self, prefix: &str, buffer: &mut QueryBuffer, out: &mut Vec<String>) {
+        let (stack, key) = (&mut buffer.nodes, &mut buffer.key);
+        stack.clear();
+        stack.extend(self.find(prefix.as_bytes()).map(|v| (v, prefix.len())));
+        key.clear();
+        key.extend_from_slice(prefix.as_bytes());
+        // the number of the keys written, overwriting the strings left in out
+        let mut n = 0;
+        while let Some((v, depth)) = stack.pop() {
+            if depth > prefix.len() {
+                key.truncate(depth - 1);
+                key.push(self.labels()[v]);
+            }
+            if self.is_terminal(v) {
+                let s = core::str::from_utf8(key).expect("keys are valid UTF-8");
+                match out.get_mut(n) {
+                    Some(slot) => {
+                        slot.clear();
+                        slot.push_str(s);
+                    },
+                    None => out.push(s.to_string()),
+                }
+                n += 1;
+            }
+            stack.extend(self.children(v).rev().map(|child| (child, depth + 1)));
+        }
+        out.truncate(n);
+    }
+
+    // checks that the bits form a tree in level order with sorted siblings, whose leaves are terminals and whose keys are UTF-8,
+    // assuming the sizes are consistent
+    fn validate_tree(&self) -> Result<(), Error> {
+        let labels = self.labels();
+        if self.louds_rank1(1) != 1 || self.louds_select0(0) != 1 {
+            return Err(Error::CorruptData("the LOUDS bits should start with 10 for the super root".to_string()));
+        }
+        for v in 0..labels.len() {
+            // the one for node v should precede the list of its children, so that the parents precede their children
+            if self.louds_rank1(self.louds_select0(v)) <= v {
+                return Err(Error::CorruptData(format!("the children of node {} precede the node itself", v)));
+            }
+            let children = self.children(v);
+            if labels[children.clone()].windows(2).any(|w| w[0] >= w[1]) {
+                return Err(Error::CorruptData(format!("the labels of the children of node {} are not strictly increasing", v)));
+            }
+            if children.is_empty() && v != 0 && !self.is_terminal(v) {
+                return Err(Error::CorruptData(format!("leaf node {} is not a terminal", v)));
+            }
+        }
+        let (mut stack, mut key) = (vec![(0, 0)], vec![]);
+        while let Some((v, depth)) = stack.pop() {
+            if depth > 0 {
+                key.truncate(depth - 1);
+                key.push(labels[v]);
+            }
+            if self.is_terminal(v) && core::str::from_utf8(&key).is_err() {
+                return Err(Error::CorruptData(format!("the key of node {} is not valid UTF-8", v)));
+            }
+            stack.extend(self.children(v).map(|child| (child, depth + 1)));
+        }
+        Ok(())
+    }
+
+    fn search_by<'a, A: Automaton + 'a>(&'a self, mut automaton: A) -> impl Iterator<Item = (String, A::Output)> + 'a {
+        let mut stack: Vec<(usize, Vec<u8>)> = vec![(0, vec![])];
+        core::iter::from_fn(move || {
+            while let Some((v, key)) = stack.pop() {
+                if !key.is_empty() {
+                    automaton.truncate(key.len() - 1);
+                    if !automaton.push_byte(&key) {
+                        continue;
+                    }
+                }
+                for child in self.children(v).rev() {
+                    let mut child_key = key.clone();
+                    child_key.push(self.labels()[child]);
+                    stack.push((child, child_key));
+                }
+                if self.is_terminal(v) {
+                    if let Some(output) = automaton.accept() {
+                        return Some((String::from_utf8(key).expect("keys are valid UTF-8"), output));
+                    }
+                }
+            }
+            None
+        })
+    }
+}
+
+struct Cursor<'a, L> {
+    trie: &'a L,
+    v: usize,
+}
+
+impl <L> Clone for Cursor<'_, L> {
+    fn clone(&self) -> Self {
+        Cursor { trie: self.trie, v: self.v }
+    }
+}
+
+impl <'a, L: Louds> TrieCursor<'a> for Cursor<'a, L> {
+    type Value = ();
+
+    fn step(&mut self, c: char) -> bool {
+        let mut buf = [0; 4];
+        match c.encode_utf8(&mut buf).bytes().try_fold(self.v, |v, b| self.trie.child(v, b)) {
+            Some(v) => {
+                self.v = v;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn value(&self) -> Option<&'a ()> {
+        if self.trie.is_terminal(self.v) {
+            Some(&())
+        } else {
+            None
+        }
+    }
+}
+
+impl <T: FID> Louds for LoudsTrie<T> {
+    fn louds_rank0(&self, i: usize) -> usize { self.louds.rank0(i) }
+    fn louds_rank1(&self, i: usize) -> usize { self.louds.rank1(i) }
+    fn louds_select0(&self, k: usize) -> usize { self.louds.select0(k) }
+    fn louds_select1(&self, k: usize) -> usize { self.louds.select1(k) }
+    fn labels(&self) -> &[u8] { &self.labels }
+    fn is_terminal(&self, v: usize) -> bool { self.terminals.access(v) }
+    fn terminal_rank1(&self, v: usize) -> usize { self.terminals.rank1(v) }
+    fn terminal_select1(&self, id: usize) -> usize { self.terminals.select1(id) }
+    fn num_keys(&self) -> usize { self.terminals.rank1(self.terminals.len()) }
+}
+
+#[cfg(feature = "std")]
+impl Louds for LoudsTrieView<'_> {
+    fn louds_rank0(&self, i: usize) -> usize { self.louds.rank0(i) }
+    fn louds_rank1(&self, i: usize) -> usize { self.louds.rank1(i) }
+    fn louds_select0(&self, k: usize) -> usize { self.louds.select0(k) }
+    fn louds_select1(&self, k: usize) -> usize { self.louds.select1(k) }
+    fn labels(&self) -> &[u8] { self.labels }
+    fn is_terminal(&self, v: usize) -> bool { self.terminals.access(v) }
+    fn terminal_rank1(&self, v: usize) -> usize { self.terminals.rank1(v) }
+    fn terminal_select1(&self, id: usize) -> usize { self.terminals.select1(id) }
+    fn num_keys(&self) -> usize { self.terminals.rank1(self.terminals.n) }
+}
+
+impl <T: FID> Trie for LoudsTrie<T> {
+    fn contains(&self, s: &str) -> bool {
+        self.find(s.as_bytes()).is_some_and(|v| self.is_terminal(v))
+    }
+
+    fn prefix<'a>(&self, s:&'a str) -> &'a str {
+        let len = self.common_prefixes_of(s).last().map_or(0, |key| key.len());
+        &s[0..len]
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.common_prefixes_of(query)
+    }
+
+    fn len(&self) -> usize {
+        self.num_keys()
+    }
+
+    /// `prefix` から始まるキーの個数を返します。
+    ///
+    /// 部分木のノードをすべてたどるため、部分木の大きさに比例する時間がかかります。
+    fn count_prefix(&self, prefix: &str) -> usize {
+        self.count_under(prefix)
+    }
+
+    fn predict(&self, prefix: &str) -> impl Iterator<Item = String> + '_ {
+        self.keys_under(prefix)
+    }
+
+    fn cursor(&self) -> impl TrieCursor<'_> {
+        Cursor { trie: self, v: 0 }
+    }
+
+    fn fuzzy_search(&self, query: &str, max_edits: usize) -> Vec<(String, usize)> {
+        self.search_by(Levenshtein::new(query, max_edits)).collect()
+    }
+
+    fn hamming_search(&self, query: &str, max_mismatches: usize) -> Vec<(String, usize)> {
+        self.search_by(Hamming::new(query, max_mismatches)).collect()
+    }
+
+    fn search_pattern(&self, pattern: &str) -> Vec<String> {
+        self.search_by(Wildcard::new(pattern)).map(|(key, _)| key).collect()
+    }
+
+    fn range<'a, 'b, R: RangeBounds<&'b str> + 'a>(&'a self, range: R) -> impl Iterator<Item = String> + 'a {
+        self.search_by(KeyRange::new(range)).map(|(key, _)| key)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Trie for LoudsTrieView<'_> {
+    fn contains(&self, s: &str) -> bool {
+        self.find(s.as_bytes()).is_some_and(|v| self.is_terminal(v))
+    }
+
+    fn prefix<'a>(&self, s:&'a str) -> &'a str {
+        let len = self.common_prefixes_of(s).last().map_or(0, |key| key.len());
+        &s[0..len]
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.common_prefixes_of(query)
+    }
+
+    fn len(&self) -> usize {
+        self.num_keys()
+    }
+
+    /// `prefix` から始まるキーの個数を返します。
+    ///
+    /// 部分木のノードをすべてたどるため、部分木の大きさに比例する時間がかかります。
+    fn count_prefix(&self, prefix: &str) -> usize {
+        self.count_under(prefix)
+    }
+
+    fn predict(&self, prefix: &str) -> impl Iterator<Item = String> + '_ {
+        self.keys_under(prefix)
+    }
+
+    fn cursor(&self) -> impl TrieCursor<'_> {
+        Cursor { trie: self, v: 0 }
+    }
+
+    fn fuzzy_search(&self, query: &str, max_edits: usize) -> Vec<(String, usize)> {
+        self.search_by(Levenshtein::new(query, max_edits)).collect()
+    }
+
+    fn hamming_search(&self, query: &str, max_mismatches: usize) -> Vec<(String, usize)> {
+        self.search_by(Hamming::new(query, max_mismatches)).collect()
+    }
+
+    fn search_pattern(&self, pattern: &str) -> Vec<String> {
+        self.search_by(Wildcard::new(pattern)).map(|(key, _)| key).collect()
+    }
+
+    fn range<'a, 'b, R: RangeBounds<&'b str> + 'a>(&'a self, range: R) -> impl Iterator<Item = String> + 'a {
+        self.search_by(KeyRange::new(range)).map(|(key, _)| key)
+    }
+}
+
+/// 番号はキーの幅優先順で、辞書順ではありません。
+impl <T: FID> TrieId for LoudsTrie<T> {
+    fn key_to_id(&self, s: &str) -> Option<usize> {
+        Louds::key_to_id(self, s)
+    }
+
+    fn id_to_key(&self, id: usize) -> Option<String> {
+        Louds::id_to_key(self, id)
+    }
+}
+
+/// 番号はキーの幅優先順で、辞書順ではありません。
+#[cfg(feature = "std")]
+impl TrieId for LoudsTrieView<'_> {
+    fn key_to_id(&self, s: &str) -> Option<usize> {
+        Louds::key_to_id(self, s)
+    }
+
+    fn id_to_key(&self, id: usize) -> Option<String> {
+        Louds::id_to_key(self, id)
+    }
+}
+
+impl <T: FID + SpaceUsage> SpaceUsage for LoudsTrie<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("louds", &self.louds), ("labels", &self.labels), ("terminals", &self.terminals)]
+    }
+}
+
+/// メモリマップしたファイル上の LOUDS トライ木
+///
+/// [`LoudsTrie::write_to()`] で書き出したファイルを、読み込まずにそのまま検索に使います。
+#[cfg(feature = "mmap")]
+pub struct MappedLoudsTrie {
+    mmap: memmap2::Mmap,
+    layout: Layout,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedLoudsTrie {
+    /// ファイル `path` をメモリマップします。
+    ///
+    /// ファイルの形式と木の形を [`LoudsTrieView::new()`] と同じく検証し、壊れている場合は `ErrorKind::InvalidData` のエラーを返します。
+    /// マップしている間にファイルが書き換えられた場合の動作は未定義です。
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the file is assumed not to be modified while it is mapped, as documented
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        let layout = Layout::parse(&mmap)?;
+        Ok(MappedLoudsTrie { mmap, layout })
+    }
+
+    /// トライ木を参照します。
+    ///
+    /// ファイルは [`Self::open()`] で検証済みのため、定数時間で参照できます。
+    pub fn view(&self) -> LoudsTrieView<'_> {
+        LoudsTrieView::with_layout(&self.mmap, &self.layout)
+    }
+}
+
+/// マップしたファイルの大きさを、ヒープ領域の代わりに数えます。
+#[cfg(feature = "mmap")]
+impl SpaceUsage for MappedLoudsTrie {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.mmap.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::CancelToken;
+    use crate::progress::Cancellable;
+    use crate::string::trie::ByteTrie;
+    use crate::string::trie::conformance;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    #[test]
+    fn conformance() {
+        conformance::check_static(NaiveLoudsTrie::new);
+    }
+
+    #[test]
+    fn same_as_byte_trie() {
+        proptest!(|(keys in vec(conformance::key(5), 0..300))| {
+            let louds = NaiveLoudsTrie::new(&keys);
+            let mut trie = ByteTrie::new();
+            keys.iter().for_each(|key| { trie.append(key); });
+            prop_assert_eq!(trie.len(), louds.len());
+            prop_assert_eq!(trie.size(), louds.size());
+            prop_assert_eq!(trie.keys().collect::<Vec<_>>(), louds.keys().collect::<Vec<_>>());
+        });
+    }
+
+    #[test]
+    fn predict_into() {
+        proptest!(|(keys in vec(conformance::key(5), 0..300), prefixes in vec(conformance::key(3), 1..10))| {
+            let trie = NaiveLoudsTrie::new(&keys);
+            // reused across the queries, starting with stale strings
+            let mut buffer = QueryBuffer::new();
+            let mut out = vec!["stale".to_string(); 3];
+            for prefix in &prefixes {
+                trie.predict_into(prefix, &mut buffer, &mut out);
+                prop_assert_eq!(&trie.predict(prefix).collect::<Vec<_>>(), &out, "predict_into({:?})", prefix);
+            }
+        });
+    }
+
+    #[test]
+    fn key_and_id() {
+        let louds = NaiveLoudsTrie::new(&["b", "", "a", "ab", "b", "あ"]);
+        assert_eq!(5, louds.len());
+        let mut ids: Vec<usize> = louds.keys().map(|key| louds.key_to_id(&key).unwrap()).collect();
+        for id in &ids {
+            let key = louds.id_to_key(*id).unwrap();
+            assert_eq!(Some(*id), louds.key_to_id(&key));
+        }
+        ids.sort();
+        assert_eq!(vec![0, 1, 2, 3, 4], ids);
+        assert_eq!(None, louds.id_to_key(5));
+        assert_eq!(None, louds.key_to_id("c"));
+        assert_eq!(None, louds.key_to_id("\u{3040}"));
+
+        let empty = NaiveLoudsTrie::new::<&str>(&[]);
+        assert!(empty.is_empty());
+        assert!(!empty.contains(""));
+        assert_eq!(0, empty.keys().count());
+    }
+
+    #[test]
+    fn trie_id() {
+        // the ids are a bijection between the keys and [0, len)
+        fn check<T: TrieId>(trie: &T) -> Vec<usize> {
+            let ids: Vec<usize> = trie.keys().map(|key| trie.key_to_id(&key).unwrap()).collect();
+            for id in 0..trie.len() {
+                assert_eq!(Some(id), trie.key_to_id(&trie.id_to_key(id).unwrap()));
+            }
+            assert_eq!(None, trie.id_to_key(trie.len()));
+            assert_eq!(None, trie.key_to_id("c"));
+            ids
+        }
+        let keys = ["b", "", "a", "ab", "b", "あ", "ba"];
+        let louds = NaiveLoudsTrie::new(&keys);
+        check(&louds);
+        #[cfg(feature = "std")]
+        {
+            let ids = check(&louds);
+            let mut bytes = vec![];
+            louds.write_to(&mut bytes).unwrap();
+            assert_eq!(ids, check(&LoudsTrieView::new(&bytes).unwrap()));
+            assert_eq!(ids, check(&NaiveLoudsTrie::read_from(&mut bytes.as_slice()).unwrap()));
+        }
+        assert_eq!(vec![0, 1, 2, 3, 4, 5], check(&crate::string::trie::Dawg::new(&keys)));
+        check(&crate::string::trie::NestedPatriciaTrie::new(&keys));
+    }
+
+    #[test]
+    fn from_sorted_keys() {
+        let mut keys = vec!["b", "", "a", "ab", "b", "あ", "ba"];
+        let expected = NaiveLoudsTrie::new(&keys);
+        keys.sort();
+        let trie = NaiveLoudsTrie::from_sorted_keys(keys.iter());
+        assert_eq!(expected.keys().collect::<Vec<_>>(), trie.keys().collect::<Vec<_>>());
+        assert_eq!(expected.size(), trie.size());
+    }
+
+    #[test]
+    fn with_progress() {
+        let keys: Vec<String> = (0..3 * progress::STEP as u32).rev().map(|i| format!("{:x}", i)).collect();
+        let mut reports = vec![];
+        let trie = NaiveLoudsTrie::with_progress(&keys, &mut |phase: Phase, done: usize, total: usize| {
+            reports.push((phase, done, total));
+        });
+        assert_eq!(keys.len(), trie.len());
+        assert_eq!(vec![(Phase::SortKeys, 0, keys.len()), (Phase::SortKeys, keys.len(), keys.len())], reports[..2]);
+        let total: usize = keys.iter().map(|key| key.len() + 1).sum();
+        let nodes: Vec<usize> = reports[2..].iter().map(|&(phase, done, t)| {
+            assert_eq!((Phase::BuildNodes, total), (phase, t));
+            done
+        }).collect();
+        assert!(nodes.len() > 3);
+        assert_eq!((0, total), (nodes[0], nodes[nodes.len() - 1]));
+        assert!(nodes.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn with_memory_limit() {
+        let keys = ["b", "", "a", "ab", "b", "あ", "ba", "their", "the"];
+        let expected = NaiveLoudsTrie::new(&keys);
+        // 9 references to the keys, and 27 nodes at most
+        let sorting = 9 * mem::size_of::<&[u8]>();
+        for bytes in [sorting + 54, sorting + 100, usize::MAX] {
+            let trie = NaiveLoudsTrie::with_memory_limit(&keys, bytes).unwrap();
+            assert_eq!(expected.louds, trie.louds);
+            assert_eq!(expected.labels, trie.labels);
+            assert_eq!(expected.terminals, trie.terminals);
+        }
+        assert_eq!(Some(Error::MemoryLimit { required: sorting + 54, limit: 100 }), NaiveLoudsTrie::with_memory_limit(&keys, 100).err());
+        let keys: Vec<String> = (0..1000).map(|i| format!("{:b}", i * 7)).collect();
+        let required = match NaiveLoudsTrie::with_memory_limit(&keys, 0) {
+            Err(Error::MemoryLimit { required, .. }) => required,
+            _ => panic!("should be over the limit"),
+        };
+        let trie = NaiveLoudsTrie::with_memory_limit(&keys, required).unwrap();
+        assert_eq!(Ok(()), trie.validate());
+        assert_eq!(NaiveLoudsTrie::new(&keys).keys().collect::<Vec<_>>(), trie.keys().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn cancel() {
+        let keys: Vec<String> = (0..2 * progress::STEP as u32).map(|i| format!("{:x}", i)).collect();
+        let mut last = None;
+        let token = CancelToken::new();
+        let mut progress = Cancellable::new(|phase: Phase, done: usize, _: usize| {
+            last = Some((phase, done));
+            if phase == Phase::BuildNodes && done > 0 {
+                token.cancel();
+            }
+        }, || token.is_cancelled());
+        assert_eq!(Some(Error::Cancelled), NaiveLoudsTrie::try_with_progress(&keys, &mut progress).err());
+        let (phase, done) = last.unwrap();
+        assert_eq!(Phase::BuildNodes, phase);
+        assert!(done < keys.iter().map(|key| key.len() + 1).sum());
+        assert!(NaiveLoudsTrie::try_with_progress(&keys, &mut CancelToken::new()).is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_unsorted_keys() {
+        NaiveLoudsTrie::from_sorted_keys(["b", "a"]);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn par_new() {
+        let keys = ["them", "the", "their", "the", "", "あいう"];
+        let trie = NaiveLoudsTrie::par_new(&keys, Chunking::new(2));
+        assert_eq!(NaiveLoudsTrie::new(&keys).keys().collect::<Vec<_>>(), trie.keys().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn try_from_sorted_keys() {
+        let trie = NaiveLoudsTrie::try_from_sorted_keys(["a", "a", "b"]).unwrap();
+        assert_eq!(2, trie.keys().count());
+        assert_eq!(Some(Error::UnsortedKeys { index: 2 }), NaiveLoudsTrie::try_from_sorted_keys(["a", "c", "b"]).err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn write_and_read() {
+        let trie = NaiveLoudsTrie::new(&["the", "their", "them", "this", "", "あいう"]);
+        let mut bytes = vec![];
+        trie.write_to(&mut bytes).unwrap();
+
+        let read = NaiveLoudsTrie::read_from(&mut bytes.as_slice()).unwrap();
+        let view = LoudsTrieView::new(&bytes).unwrap();
+        assert_eq!(trie.size(), read.size());
+        assert_eq!(trie.size(), view.size());
+        assert_eq!(trie.keys().collect::<Vec<_>>(), read.keys().collect::<Vec<_>>());
+        assert_eq!(trie.keys().collect::<Vec<_>>(), view.keys().collect::<Vec<_>>());
+        for key in trie.keys() {
+            let id = trie.key_to_id(&key);
+            assert_eq!(id, view.key_to_id(&key));
+            assert_eq!(Some(key), view.id_to_key(id.unwrap()));
+        }
+        assert_eq!(trie.prefix("theirs"), view.prefix("theirs"));
+        assert_eq!(trie.count_prefix("th"), view.count_prefix("th"));
+
+        assert!(LoudsTrieView::new(&bytes[0..bytes.len() - 1]).is_err());
+        assert!(LoudsTrieView::new(&[&bytes[..], &[0]].concat()).is_err());
+        let mut broken = bytes.clone();
+        broken[20] ^= 1;
+        assert!(LoudsTrieView::new(&broken).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn view_of_corrupt_bytes() {
+        let trie = NaiveLoudsTrie::new(&["the", "their", "them", "", "あいう"]);
+        let mut bytes = vec![];
+        trie.write_to(&mut bytes).unwrap();
+        // every view accepted after flipping a bit should answer the queries without panicking
+        for i in 0..bytes.len() * 8 {
+            let mut broken = bytes.clone();
+            broken[i / 8] ^= 1 << (i % 8);
+            if let Ok(view) = LoudsTrieView::new(&broken) {
+                assert_eq!(view.len(), view.keys().count());
+                for id in 0..view.len() {
+                    let key = view.id_to_key(id).unwrap();
+                    assert_eq!(Some(id), view.key_to_id(&key));
+                }
+                view.fuzzy_search("them", 1);
+            }
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let trie = NaiveLoudsTrie::new(&["the", "their", "them", "", "あいう"]);
+        assert_eq!(Ok(()), trie.validate());
+        assert_eq!(Ok(()), NaiveLoudsTrie::new::<&str>(&[]).validate());
+
+        let broken = |f: fn(&mut NaiveLoudsTrie)| {
+            let mut trie = NaiveLoudsTrie::new(&["ab", "ac", "あ"]);
+            f(&mut trie);
+            trie.validate().unwrap_err().to_string()
+        };
+        // the nodes are the root, a, \xe3, b, c, \x81, \x82 in level order
+        assert_eq!("corrupt data: the labels of the children of node 1 are not strictly increasing", broken(|trie| trie.labels.swap(3, 4)));
+        assert_eq!("corrupt data: leaf node 3 is not a terminal", broken(|trie| trie.terminals.set(3, false)));
+        assert_eq!("corrupt data: the key of node 6 is not valid UTF-8", broken(|trie| trie.labels[6] = b'a'));
+        assert_eq!("corrupt data: the LOUDS bits should start with 10 for the super root", broken(|trie| {
+            trie.louds.set(1, true);
+            trie.louds.set(2, false);
+        }));
+        assert!(broken(|trie| trie.labels.push(0)).contains("expected 2 * nodes + 1 LOUDS bits"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn validate_view() {
+        let mut bytes = vec![];
+        NaiveLoudsTrie::new(&["ab", "ac"]).write_to(&mut bytes).unwrap();
+        assert!(LoudsTrieView::new(&bytes).is_ok());
+        // swap the labels of b and c, which the queries assume to be sorted
+        // the labels are followed by the terminal bits, 8 bytes of the length and 16 bytes of a block
+        let at = bytes.len() - 24;
+        assert_eq!(b"abc", &bytes[at - 3..at]);
+        bytes.swap(at - 2, at - 1);
+        let error = LoudsTrieView::new(&bytes).err().unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert_eq!("corrupt data: the labels of the children of node 1 are not strictly increasing", error.to_string());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn serialize() {
+        // a trie and a DAWG in one stream
+        let trie = NaiveLoudsTrie::new(&["the", "their", "them"]);
+        let dawg = crate::string::trie::Dawg::new(&["walk", "walks"]);
+        let mut bytes = vec![];
+        trie.serialize(&mut bytes).unwrap();
+        dawg.serialize(&mut bytes).unwrap();
+
+        let mut r = bytes.as_slice();
+        let read = NaiveLoudsTrie::deserialize(&mut r).unwrap();
+        assert_eq!(trie.keys().collect::<Vec<_>>(), read.keys().collect::<Vec<_>>());
+        assert!(NaiveLoudsTrie::deserialize(&mut &r[..]).is_err());
+        let read = crate::string::trie::Dawg::deserialize(&mut r).unwrap();
+        assert_eq!(dawg.keys().collect::<Vec<_>>(), read.keys().collect::<Vec<_>>());
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn view_of_large_trie() {
+        let keys: Vec<String> = (0..2000).map(|i| format!("{}", i * 7)).collect();
+        let trie = NaiveLoudsTrie::new(&keys);
+        let mut bytes = vec![];
+        trie.write_to(&mut bytes).unwrap();
+        let view = LoudsTrieView::new(&bytes).unwrap();
+        assert_eq!(trie.keys().collect::<Vec<_>>(), view.keys().collect::<Vec<_>>());
+        for id in (0..keys.len()).step_by(37) {
+            assert_eq!(trie.id_to_key(id), view.id_to_key(id));
+        }
+        let (mut buffer, mut out) = (QueryBuffer::new(), vec![]);
+        view.predict_into("14", &mut buffer, &mut out);
+        assert_eq!(trie.predict("14").collect::<Vec<_>>(), out);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap() {
+        let trie = NaiveLoudsTrie::new(&["foo", "foobar", "bar"]);
+        let path = std::env::temp_dir().join(format!("louds_trie_mmap_{}", std::process::id()));
+        trie.write_to(&mut std::fs::File::create(&path).unwrap()).unwrap();
+        let mapped = MappedLoudsTrie::open(&path).unwrap();
+        assert_eq!(trie.keys().collect::<Vec<_>>(), mapped.view().keys().collect::<Vec<_>>());
+
+        // a leaf node which is not a terminal
+        let mut bytes = vec![];
+        NaiveLoudsTrie::new(&["ab", "ac"]).write_to(&mut bytes).unwrap();
+        let last = bytes.len() - 8;
+        bytes[last] &= !0b1000;
+        std::fs::write(&path, &bytes).unwrap();
+        let error = MappedLoudsTrie::open(&path).err().unwrap();
+        assert_eq!("corrupt data: leaf node 3 is not a terminal", error.to_string());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
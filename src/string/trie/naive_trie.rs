@@ -1,72 +1,195 @@
+use super::children::AdaptiveChildren;
+use super::children::ChildStorage;
+use super::NaiveTrieMap;
 use super::Trie;
+use super::TrieCursor;
+use super::TrieMap;
+use super::TrieMut;
+use crate::collections::arena::BoxNodes;
+use crate::collections::arena::NodeStorage;
+use crate::space::SpaceUsage;
 
-use std::collections::HashMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::RangeBounds;
 
 
-pub struct NaiveTrie {
-    children: HashMap<char, Box<NaiveTrie>>,
-    is_leaf: bool,
+pub struct NaiveTrie<S: ChildStorage = AdaptiveChildren, A: NodeStorage = BoxNodes> {
+    map: NaiveTrieMap<(), S, A>,
 }
 
 impl NaiveTrie {
     pub fn new() -> Self {
-        let children = HashMap::new();
-        NaiveTrie {
-            children,
-            is_leaf: false,
-        }
+        Self::default()
+    }
+
+    /// 辞書順にソートされたキーの列 `keys` から構築します。重複したキーは 1 つにまとめられます。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keys` are not sorted.
+    pub fn from_sorted_keys<I: IntoIterator<Item = S>, S: AsRef<str>>(keys: I) -> Self {
+        NaiveTrie { map: NaiveTrieMap::from_sorted_iter(keys.into_iter().map(|key| (key, ()))) }
     }
+}
 
+impl <S: ChildStorage, A: NodeStorage> NaiveTrie<S, A> {
     pub fn append(&mut self, s: &str) -> bool {
-        let mut node = self;
-        for c in s.chars() {
-            let entry = node.children.entry(c);
-            node = entry.or_insert(Box::new(NaiveTrie::new()));
-        }
-        let is_new = !node.is_leaf;
-        node.is_leaf = true;
-        is_new
+        self.map.insert(s, ()).is_none()
+    }
+
+    /// キーの列 `keys` をすべて追加し、新たに追加したキーの個数を返します。
+    pub fn append_all<I: IntoIterator<Item = K>, K: AsRef<str>>(&mut self, keys: I) -> usize {
+        keys.into_iter().filter(|key| self.append(key.as_ref())).count()
     }
 
     pub fn size(&self) -> usize {
-        1_usize + self.children.values().map(|node| node.size()).sum::<usize>() as usize
+        self.map.size()
+    }
+
+    /// 木構造を Graphviz の DOT 言語で返します。 [`NaiveTrieMap::to_dot()`] を参照してください。
+    pub fn to_dot(&self) -> String {
+        self.map.to_dot()
+    }
+
+    /// `self` と `other` の少なくとも一方に含まれるキーからなるトライ木を返します。
+    pub fn union(&self, other: &Self) -> Self {
+        Self { map: self.map.union(&other.map) }
+    }
+
+    /// `self` と `other` の両方に含まれるキーからなるトライ木を返します。
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self { map: self.map.intersection(&other.map) }
+    }
+
+    /// `self` に含まれ `other` に含まれないキーからなるトライ木を返します。
+    pub fn difference(&self, other: &Self) -> Self {
+        Self { map: self.map.difference(&other.map) }
     }
 }
 
-impl Trie for NaiveTrie {
+/// 木構造を、各ノードを 1 行とする木として書き出します。キーのノードには `*` を付けます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::NaiveTrie;
+/// let trie: NaiveTrie = ["to", "tea", "i"].iter().collect();
+/// assert_eq!(".\n├── i *\n└── t\n    ├── e\n    │   └── a *\n    └── o *\n", trie.to_string());
+/// ```
+impl <S: ChildStorage, A: NodeStorage> fmt::Display for NaiveTrie<S, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.map, f)
+    }
+}
+
+impl <S: ChildStorage, A: NodeStorage> Default for NaiveTrie<S, A> {
+    fn default() -> Self {
+        NaiveTrie { map: NaiveTrieMap::default() }
+    }
+}
+
+impl <K: AsRef<str>, S: ChildStorage, A: NodeStorage> FromIterator<K> for NaiveTrie<S, A> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut trie = Self::default();
+        trie.append_all(iter);
+        trie
+    }
+}
+
+impl <K: AsRef<str>, S: ChildStorage, A: NodeStorage> Extend<K> for NaiveTrie<S, A> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        self.append_all(iter);
+    }
+}
+
+impl <S: ChildStorage, A: NodeStorage> Trie for NaiveTrie<S, A> {
     fn contains(&self, s: &str) -> bool {
-        let mut node = self;
-        for c in s.chars() {
-            if let Some(v) = node.children.get(&c) {
-                node = v;
-            } else {
-                return false;
-            }
-        }
-        node.is_leaf == true
+        self.map.contains_key(s)
     }
 
     fn prefix<'a>(&self, s:&'a str) -> &'a str {
-        let mut len = 0;
-        let mut node = self;
-        for (i, c) in s.chars().enumerate() {
-            if let Some(v) = node.children.get(&c) {
-                node = v;
-                if node.is_leaf {
-                    len = i + 1;
-                }
-            } else {
-                return &s[0..len];
-            }
-        }
+        let len = self.common_prefixes(s).last().map_or(0, |key| key.len());
         &s[0..len]
     }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.map.common_prefixes(query).map(|(key, _)| key)
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn count_prefix(&self, prefix: &str) -> usize {
+        self.map.count_prefix(prefix)
+    }
+
+    fn predict(&self, prefix: &str) -> impl Iterator<Item = String> + '_ {
+        self.map.iter_prefix(prefix).map(|(key, _)| key)
+    }
+
+    fn cursor(&self) -> impl TrieCursor<'_> {
+        self.map.cursor()
+    }
+
+    fn fuzzy_search(&self, query: &str, max_edits: usize) -> Vec<(String, usize)> {
+        self.map.fuzzy_search(query, max_edits).into_iter().map(|(key, _, distance)| (key, distance)).collect()
+    }
+
+    fn hamming_search(&self, query: &str, max_mismatches: usize) -> Vec<(String, usize)> {
+        self.map.hamming_search(query, max_mismatches).into_iter().map(|(key, _, mismatches)| (key, mismatches)).collect()
+    }
+
+    fn search_pattern(&self, pattern: &str) -> Vec<String> {
+        self.map.search_pattern(pattern).into_iter().map(|(key, _)| key).collect()
+    }
+
+    fn range<'a, 'b, R: RangeBounds<&'b str> + 'a>(&'a self, range: R) -> impl Iterator<Item = String> + 'a {
+        self.map.range(range).map(|(key, _)| key)
+    }
+}
+
+impl <S: ChildStorage, A: NodeStorage> TrieMut for NaiveTrie<S, A> {
+    fn insert(&mut self, s: &str) -> bool {
+        self.append(s)
+    }
+
+    fn remove(&mut self, s: &str) -> bool {
+        self.map.remove(s).is_some()
+    }
+}
+
+impl <S: ChildStorage, A: NodeStorage> SpaceUsage for NaiveTrie<S, A> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("map", &self.map)]
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn dot() {
+        let trie: NaiveTrie = ["", "ab", "b"].iter().collect();
+        assert_eq!(concat!(
+            "digraph trie {\n",
+            "    node [shape=circle, label=\"\"];\n",
+            "    0 [shape=doublecircle];\n",
+            "    1;\n",
+            "    2 [shape=doublecircle];\n",
+            "    1 -> 2 [label=\"b\"];\n",
+            "    0 -> 1 [label=\"a\"];\n",
+            "    3 [shape=doublecircle];\n",
+            "    0 -> 3 [label=\"b\"];\n",
+            "}\n",
+        ), trie.to_dot());
+        assert_eq!(". *\n├── a\n│   └── b *\n└── b *\n", trie.to_string());
+    }
+
     #[test]
     fn contains() {
         let mut node = NaiveTrie::new();
@@ -112,5 +235,125 @@ mod tests {
         assert_eq!("foo", node.prefix("fooba"));
         assert_eq!("foobar", node.prefix("foobar"));
         assert_eq!("foobar", node.prefix("foobarbaz"));
+        assert_eq!("あいうえお", node.prefix("あいうえおか"));
+    }
+
+    #[test]
+    fn remove() {
+        let mut node = NaiveTrie::new();
+        node.append("foo");
+        node.append("foobar");
+        node.append("bar");
+        assert_eq!(10, node.size());
+
+        assert!(!node.remove("fo"));
+        assert!(!node.remove("xxx"));
+        assert!(!node.remove("foobarbaz"));
+        assert_eq!(10, node.size());
+
+        assert!(node.remove("foobar"));
+        assert!(!node.remove("foobar"));
+        assert!(!node.contains("foobar"));
+        assert!(node.contains("foo"));
+        assert_eq!(7, node.size());
+
+        assert!(node.remove("foo"));
+        assert_eq!(4, node.size());
+        assert!(node.remove("bar"));
+        assert_eq!(1, node.size());
+
+        assert!(node.append(""));
+        assert!(node.remove(""));
+        assert!(!node.contains(""));
+        assert!(node.is_empty());
+    }
+
+    #[test]
+    fn count_prefix() {
+        let mut node = NaiveTrie::new();
+        assert_eq!(0, node.len());
+        node.append("foo");
+        node.append("foobar");
+        node.append("foobaz");
+        node.append("bar");
+        node.append("foo");
+        assert_eq!(4, node.len());
+
+        assert_eq!(4, node.count_prefix(""));
+        assert_eq!(3, node.count_prefix("f"));
+        assert_eq!(3, node.count_prefix("foo"));
+        assert_eq!(2, node.count_prefix("foob"));
+        assert_eq!(1, node.count_prefix("foobar"));
+        assert_eq!(0, node.count_prefix("foobarr"));
+        assert_eq!(0, node.count_prefix("x"));
+
+        node.remove("foobar");
+        node.remove("xxx");
+        assert_eq!(3, node.len());
+        assert_eq!(1, node.count_prefix("foob"));
+        assert_eq!(0, node.count_prefix("foobar"));
+    }
+
+    #[test]
+    fn keys() {
+        let mut node = NaiveTrie::new();
+        assert_eq!(0, node.keys().count());
+        let mut keys = vec!["foo", "foobar", "bar", "baz", "", "あいうえお", "ba"];
+        // many children to switch the representation of the children
+        keys.extend(["a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"]);
+        for key in &keys {
+            node.append(key);
+        }
+        keys.sort();
+        assert_eq!(keys, node.keys().collect::<Vec<_>>());
+        assert_eq!(keys, node.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn predict() {
+        let mut node = NaiveTrie::new();
+        for key in ["foo", "foobar", "foobaz", "fob", "bar", "あいう", "あお"] {
+            node.append(key);
+        }
+        assert_eq!(vec!["foo", "foobar", "foobaz"], node.predict("foo").collect::<Vec<_>>());
+        assert_eq!(vec!["fob", "foo", "foobar", "foobaz"], node.predict("f").collect::<Vec<_>>());
+        assert_eq!(vec!["foobar"], node.predict("foobar").collect::<Vec<_>>());
+        assert_eq!(vec!["あいう", "あお"], node.predict("あ").collect::<Vec<_>>());
+        assert_eq!(0, node.predict("foobarbaz").count());
+        assert_eq!(0, node.predict("x").count());
+        assert_eq!(node.len(), node.predict("").count());
+        assert_eq!(Some("foo".to_string()), node.predict("fo").nth(1));
+    }
+
+    #[test]
+    fn common_prefixes() {
+        let mut node = NaiveTrie::new();
+        for key in ["f", "foo", "foobar", "bar", "あ", "あいう"] {
+            node.append(key);
+        }
+        assert_eq!(vec!["f", "foo", "foobar"], node.common_prefixes("foobarbaz").collect::<Vec<_>>());
+        assert_eq!(vec!["f", "foo"], node.common_prefixes("fooba").collect::<Vec<_>>());
+        assert_eq!(vec!["f"], node.common_prefixes("fo").collect::<Vec<_>>());
+        assert_eq!(vec!["あ", "あいう"], node.common_prefixes("あいうえお").collect::<Vec<_>>());
+        assert_eq!(0, node.common_prefixes("xyz").count());
+        assert_eq!(0, node.common_prefixes("").count());
+
+        node.append("");
+        assert_eq!(vec!["", "f"], node.common_prefixes("fo").collect::<Vec<_>>());
+        assert_eq!(vec![""], node.common_prefixes("").collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn from_iter() {
+        let mut trie: NaiveTrie = ["foo", "bar", "foo"].into_iter().collect();
+        assert_eq!(vec!["bar", "foo"], trie.keys().collect::<Vec<_>>());
+        trie.extend(vec!["baz".to_string(), "bar".to_string()]);
+        assert_eq!(3, trie.len());
+        assert_eq!(1, trie.append_all(["foo", "qux", "qux"]));
+        assert_eq!(vec!["bar", "baz", "foo", "qux"], trie.keys().collect::<Vec<_>>());
+
+        let keys = ["あ".to_string(), "い".to_string()];
+        let trie = keys.iter().collect::<NaiveTrie>();
+        assert!(trie.contains("い"));
     }
 }
@@ -0,0 +1,662 @@
+use super::automaton::Automaton;
+use super::automaton::Hamming;
+use super::automaton::KeyRange;
+use super::automaton::Levenshtein;
+use super::automaton::Wildcard;
+use super::children::AdaptiveChildren;
+use super::children::ChildMap;
+use super::children::ChildStorage;
+use super::dot::DotWriter;
+use super::dot::TreeWriter;
+use super::SetOp;
+use super::TrieCursor;
+use super::TrieMap;
+use crate::collections::arena::BoxNodes;
+use crate::collections::arena::NodeStorage;
+use crate::space::SpaceUsage;
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::RangeBounds;
+
+/// 文字列をキーとして値を保持するトライ木
+///
+/// 各ノードが子ノードを文字ごとに持つ、素朴な実装です。
+/// 子を保持する連想配列は、型引数 `S` で選べます ([`ChildStorage`] を参照してください)。
+/// ノードの確保の仕方は、型引数 `A` で選べます ([`NodeStorage`] を参照してください)。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::NaiveTrieMap;
+/// use rust_study::string::trie::TrieMap;
+/// let mut map = NaiveTrieMap::new();
+/// assert_eq!(None, map.insert("foo", 1));
+/// assert_eq!(None, map.insert("foobar", 2));
+/// assert_eq!(None, map.insert("bar", 3));
+/// assert_eq!(Some(1), map.insert("foo", 10));
+/// assert_eq!(Some(&10), map.get("foo"));
+/// *map.get_mut("bar").unwrap() += 1;
+///
+/// let under_foo: Vec<(String, &i32)> = map.iter_prefix("foo").collect();
+/// assert_eq!(vec![("foo".to_string(), &10), ("foobar".to_string(), &2)], under_foo);
+///
+/// assert_eq!(Some(4), map.remove("bar"));
+/// assert_eq!(2, map.len());
+/// ```
+pub struct NaiveTrieMap<V, S: ChildStorage = AdaptiveChildren, A: NodeStorage = BoxNodes> {
+    root: Node<V, S, A>,
+    pool: A::Pool<Node<V, S, A>>,
+}
+
+struct Node<V, S: ChildStorage, A: NodeStorage> {
+    children: S::Map<A::Link<Node<V, S, A>>>,
+    value: Option<V>,
+    // the number of keys in the subtree
+    count: usize,
+}
+
+impl <V> NaiveTrieMap<V> {
+    /// 空のトライ木を構築します。
+    ///
+    /// 子を保持する連想配列やノードの確保の仕方を選ぶ場合は、 `NaiveTrieMap::<V, S, A>::default()` で構築します。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// キーの辞書順にソートされた `(キー, 値)` の列 `iter` から構築します。
+    ///
+    /// キーが重複した場合、後の値で置き換えます。
+    /// 既存のキーの確認を省くため、キーを 1 つずつ [`TrieMap::insert()`] するより高速です。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not sorted.
+    pub fn from_sorted_iter<I: IntoIterator<Item = (S, V)>, S: AsRef<str>>(iter: I) -> Self {
+        let mut map = Self::new();
+        let mut last = String::new();
+        for (key, value) in iter {
+            let key = key.as_ref();
+            if map.root.count > 0 {
+                assert!(last.as_str() <= key, "keys are not sorted");
+                if last == key {
+                    if let Some(old) = map.get_mut(key) {
+                        *old = value;
+                    }
+                    continue;
+                }
+            }
+            map.insert_new(key, value);
+            last.clear();
+            last.push_str(key);
+        }
+        map
+    }
+}
+
+impl <V, S: ChildStorage, A: NodeStorage> NaiveTrieMap<V, S, A> {
+    /// ノードの個数を返します。
+    pub fn size(&self) -> usize {
+        self.node_size(&self.root)
+    }
+
+    fn node_size(&self, node: &Node<V, S, A>) -> usize {
+        1 + self.children(node).map(|(_, child)| self.node_size(child)).sum::<usize>()
+    }
+
+    // the children of the node, sorted by the char
+    fn children<'a>(&'a self, node: &'a Node<V, S, A>) -> impl DoubleEndedIterator<Item = (char, &'a Node<V, S, A>)> + 'a {
+        node.children.sorted_iter().map(|(c, link)| (c, A::get(&self.pool, link)))
+    }
+
+    fn child<'a>(&'a self, node: &'a Node<V, S, A>, c: char) -> Option<&'a Node<V, S, A>> {
+        node.children.get(c).map(|link| A::get(&self.pool, link))
+    }
+
+    /// 木構造を Graphviz の DOT 言語で返します。
+    ///
+    /// ノードには根からの先行順に番号を付け、キーのノードを二重丸で描きます。
+    pub fn to_dot(&self) -> String {
+        let mut dot = DotWriter::new();
+        self.write_dot(&self.root, &mut dot, &mut 0);
+        dot.finish()
+    }
+
+    // returns the id of the node
+    fn write_dot(&self, node: &Node<V, S, A>, dot: &mut DotWriter, next: &mut usize) -> usize {
+        let id = *next;
+        *next += 1;
+        dot.node(id, node.value.is_some());
+        for (c, child) in self.children(node) {
+            let child_id = self.write_dot(child, dot, next);
+            dot.edge(id, child_id, &c.to_string());
+        }
+        id
+    }
+
+    fn write_tree(&self, node: &Node<V, S, A>, w: &mut TreeWriter, depth: usize, is_last: bool, label: char) -> fmt::Result {
+        w.node(depth, is_last, &label.to_string(), node.value.is_some())?;
+        let last = node.children.len().saturating_sub(1);
+        for (i, (c, child)) in self.children(node).enumerate() {
+            self.write_tree(child, w, depth + 1, i == last, c)?;
+        }
+        Ok(())
+    }
+
+    /// `self` と `other` の少なくとも一方に含まれるキーからなるトライ木を返します。
+    ///
+    /// 両方に含まれるキーには `self` の値を複製します。
+    /// 2 つのトライ木を根から同時にたどって構築するため、キーを取り出して追加し直す必要はありません。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_study::string::trie::NaiveTrieMap;
+    /// use rust_study::string::trie::TrieMap;
+    /// let a: NaiveTrieMap<i32> = [("foo", 1), ("bar", 2)].into_iter().collect();
+    /// let b: NaiveTrieMap<i32> = [("foo", 10), ("baz", 30)].into_iter().collect();
+    /// let union = a.union(&b);
+    /// assert_eq!(vec![("bar".to_string(), &2), ("baz".to_string(), &30), ("foo".to_string(), &1)], union.iter().collect::<Vec<_>>());
+    /// assert_eq!(vec![("foo".to_string(), &1)], a.intersection(&b).iter().collect::<Vec<_>>());
+    /// assert_eq!(vec![("bar".to_string(), &2)], a.difference(&b).iter().collect::<Vec<_>>());
+    /// ```
+    pub fn union(&self, other: &Self) -> Self where V: Clone {
+        self.combine(other, SetOp::Union, &|a, b| a.or(b).cloned())
+    }
+
+    /// `self` と `other` の両方に含まれるキーからなるトライ木を、 `self` の値を複製して返します。
+    pub fn intersection<W>(&self, other: &NaiveTrieMap<W, S, A>) -> Self where V: Clone {
+        self.combine(other, SetOp::Intersection, &|a, b| b.and(a).cloned())
+    }
+
+    /// `self` に含まれ `other` に含まれないキーからなるトライ木を、 `self` の値を複製して返します。
+    pub fn difference<W>(&self, other: &NaiveTrieMap<W, S, A>) -> Self where V: Clone {
+        self.combine(other, SetOp::Difference, &|a, b| if b.is_some() { None } else { a.cloned() })
+    }
+
+    fn combine<W, F>(&self, other: &NaiveTrieMap<W, S, A>, op: SetOp, value: &F) -> Self
+        where F: Fn(Option<&V>, Option<&W>) -> Option<V> {
+        let mut pool = Default::default();
+        let root = self.combine_nodes(other, &mut pool, Some(&self.root), Some(&other.root), op, value);
+        NaiveTrieMap { root, pool }
+    }
+
+    // builds the subtree from the node a of self and the node b of other, allocating the nodes from pool
+    fn combine_nodes<W, F>(
+        &self,
+        other: &NaiveTrieMap<W, S, A>,
+        pool: &mut A::Pool<Node<V, S, A>>,
+        a: Option<&Node<V, S, A>>,
+        b: Option<&Node<W, S, A>>,
+        op: SetOp,
+        value: &F,
+    ) -> Node<V, S, A>
+        where F: Fn(Option<&V>, Option<&W>) -> Option<V> {
+        let mut node: Node<V, S, A> = Node::default();
+        node.value = value(a.and_then(|a| a.value.as_ref()), b.and_then(|b| b.value.as_ref()));
+        node.count = node.value.is_some() as usize;
+        let mut labels: Vec<char> = a.into_iter().flat_map(|a| a.children.sorted_iter().map(|(c, _)| c))
+            .chain(b.into_iter().flat_map(|b| b.children.sorted_iter().map(|(c, _)| c)))
+            .collect();
+        labels.sort();
+        labels.dedup();
+        for c in labels {
+            let child_a = a.and_then(|a| self.child(a, c));
+            let child_b = b.and_then(|b| other.child(b, c));
+            if !op.visits(child_a.is_some(), child_b.is_some()) {
+                continue;
+            }
+            let child = self.combine_nodes(other, pool, child_a, child_b, op, value);
+            if child.count > 0 {
+                node.count += child.count;
+                node.children.insert(c, A::alloc(pool, child));
+            }
+        }
+        node
+    }
+
+    // follows the chars given by next from the root, calling visit on each node it leaves
+    // next should only give the chars of existing children
+    fn descend_mut(
+        &mut self,
+        mut next: impl FnMut(&Node<V, S, A>) -> Option<char>,
+        mut visit: impl FnMut(&mut Node<V, S, A>),
+    ) -> &mut Node<V, S, A> {
+        let root = &mut self.root;
+        let Some(c) = next(root) else {
+            return root;
+        };
+        visit(root);
+        let link = root.children.get_mut(c).unwrap();
+        A::descend(&mut self.pool, link, next, |node, c| {
+            visit(node);
+            node.children.get_mut(c).unwrap()
+        })
+    }
+
+    // key should not exist
+    fn insert_new(&mut self, key: &str, value: V) {
+        // the longest prefix of the key in the trie
+        let mut node = &self.root;
+        let mut end = 0;
+        for c in key.chars() {
+            match self.child(node, c) {
+                Some(child) => node = child,
+                None => break,
+            }
+            end += c.len_utf8();
+        }
+        let (prefix, suffix) = key.split_at(end);
+        // the new nodes below the prefix, built from the deepest one
+        let mut value = Some(value);
+        let mut below = None;
+        for c in suffix.chars().rev() {
+            let mut node: Node<V, S, A> = Node { count: 1, value: value.take(), ..Node::default() };
+            if let Some((c, link)) = below.take() {
+                node.children.insert(c, link);
+            }
+            below = Some((c, A::alloc(&mut self.pool, node)));
+        }
+        let mut chars = prefix.chars();
+        let node = self.descend_mut(|_| chars.next(), |node| node.count += 1);
+        node.count += 1;
+        match below {
+            Some((c, link)) => {
+                node.children.insert(c, link);
+            }
+            None => node.value = value,
+        }
+    }
+
+    fn find(&self, s: &str) -> Option<&Node<V, S, A>> {
+        let mut node = &self.root;
+        for c in s.chars() {
+            node = self.child(node, c)?;
+        }
+        Some(node)
+    }
+
+    fn find_mut(&mut self, s: &str) -> Option<&mut Node<V, S, A>> {
+        let mut chars = s.chars();
+        let mut found = true;
+        let node = self.descend_mut(
+            |node| {
+                let c = chars.next()?;
+                found = node.children.get(c).is_some();
+                found.then_some(c)
+            },
+            |_| {},
+        );
+        if found {
+            Some(node)
+        } else {
+            None
+        }
+    }
+
+    // returns the removed value
+    fn remove_key(&mut self, key: &str) -> Option<V> {
+        // the first char of the key whose node has no other key, where the key is cut off from the trie
+        let mut node = &self.root;
+        let mut cut = None;
+        for (i, c) in key.char_indices() {
+            node = self.child(node, c)?;
+            if node.count == 1 && cut.is_none() {
+                cut = Some(i);
+            }
+        }
+        node.value.as_ref()?;
+        let (kept, cut) = key.split_at(cut.unwrap_or(key.len()));
+        let mut chars = kept.chars();
+        let node = self.descend_mut(|_| chars.next(), |node| node.count -= 1);
+        node.count -= 1;
+        let Some(c) = cut.chars().next() else {
+            return node.value.take();
+        };
+        // the nodes below have no other key, so that they form a chain down to the key
+        let mut link = node.children.remove(c).unwrap();
+        loop {
+            let mut node = A::free(&mut self.pool, link);
+            if node.value.is_some() {
+                return node.value;
+            }
+            let (c, _) = node.children.sorted_iter().next().unwrap();
+            link = node.children.remove(c).unwrap();
+        }
+    }
+
+    // runs the automaton along the keys, pruning the subtrees it rejects
+    fn search_by<'a, M: Automaton + 'a>(&'a self, mut automaton: M) -> impl Iterator<Item = (String, &'a V, M::Output)> + 'a {
+        // (node, key, the number of chars of the key)
+        let mut stack: Vec<(&Node<V, S, A>, String, usize)> = vec![(&self.root, String::new(), 0)];
+        core::iter::from_fn(move || {
+            while let Some((node, key, depth)) = stack.pop() {
+                if let Some(c) = key.chars().next_back() {
+                    automaton.truncate(depth - 1);
+                    if !automaton.push(c) {
+                        continue;
+                    }
+                }
+                for (c, child) in self.children(node).rev() {
+                    let mut child_key = key.clone();
+                    child_key.push(c);
+                    stack.push((child, child_key, depth + 1));
+                }
+                if let (Some(value), Some(output)) = (&node.value, automaton.accept()) {
+                    return Some((key, value, output));
+                }
+            }
+            None
+        })
+    }
+}
+
+impl <V, S: ChildStorage, A: NodeStorage> TrieMap<V> for NaiveTrieMap<V, S, A> {
+    fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        if let Some(old) = self.get_mut(key) {
+            return Some(core::mem::replace(old, value));
+        }
+        self.insert_new(key, value);
+        None
+    }
+
+    fn get(&self, key: &str) -> Option<&V> {
+        self.find(key)?.value.as_ref()
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.find_mut(key)?.value.as_mut()
+    }
+
+    fn remove(&mut self, key: &str) -> Option<V> {
+        self.remove_key(key)
+    }
+
+    fn len(&self) -> usize {
+        self.root.count
+    }
+
+    fn count_prefix(&self, prefix: &str) -> usize {
+        self.find(prefix).map_or(0, |node| node.count)
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = (&'a str, &'a V)> + 'a where V: 'a {
+        let mut node = Some(&self.root);
+        let mut chars = query.char_indices();
+        // node corresponds to query[0..end]
+        let mut end = 0;
+        core::iter::from_fn(move || {
+            loop {
+                let current = node?;
+                let len = end;
+                node = chars.next().and_then(|(i, c)| {
+                    end = i + c.len_utf8();
+                    self.child(current, c)
+                });
+                if let Some(value) = &current.value {
+                    return Some((&query[0..len], value));
+                }
+            }
+        })
+    }
+
+    fn cursor<'a>(&'a self) -> impl TrieCursor<'a, Value = V> + 'a where V: 'a {
+        Cursor { trie: self, node: &self.root }
+    }
+
+    fn iter_prefix<'a>(&'a self, prefix: &str) -> impl Iterator<Item = (String, &'a V)> + 'a where V: 'a {
+        let mut stack: Vec<(&Node<V, S, A>, String)> = self.find(prefix)
+            .map(|node| (node, prefix.to_string()))
+            .into_iter()
+            .collect();
+        core::iter::from_fn(move || {
+            while let Some((node, key)) = stack.pop() {
+                for (c, child) in self.children(node).rev() {
+                    let mut child_key = key.clone();
+                    child_key.push(c);
+                    stack.push((child, child_key));
+                }
+                if let Some(value) = &node.value {
+                    return Some((key, value));
+                }
+            }
+            None
+        })
+    }
+
+    fn fuzzy_search<'a>(&'a self, query: &str, max_edits: usize) -> Vec<(String, &'a V, usize)> where V: 'a {
+        self.search_by(Levenshtein::new(query, max_edits)).collect()
+    }
+
+    fn hamming_search<'a>(&'a self, query: &str, max_mismatches: usize) -> Vec<(String, &'a V, usize)> where V: 'a {
+        self.search_by(Hamming::new(query, max_mismatches)).collect()
+    }
+
+    fn search_pattern<'a>(&'a self, pattern: &str) -> Vec<(String, &'a V)> where V: 'a {
+        self.search_by(Wildcard::new(pattern)).map(|(key, value, _)| (key, value)).collect()
+    }
+
+    fn range<'a, 'b, R: RangeBounds<&'b str> + 'a>(&'a self, range: R) -> impl Iterator<Item = (String, &'a V)> + 'a where V: 'a {
+        self.search_by(KeyRange::new(range)).map(|(key, value, _)| (key, value))
+    }
+}
+
+struct Cursor<'a, V, S: ChildStorage, A: NodeStorage> {
+    trie: &'a NaiveTrieMap<V, S, A>,
+    node: &'a Node<V, S, A>,
+}
+
+impl <V, S: ChildStorage, A: NodeStorage> Clone for Cursor<'_, V, S, A> {
+    fn clone(&self) -> Self {
+        Cursor { trie: self.trie, node: self.node }
+    }
+}
+
+impl <'a, V, S: ChildStorage, A: NodeStorage> TrieCursor<'a> for Cursor<'a, V, S, A> {
+    type Value = V;
+
+    fn step(&mut self, c: char) -> bool {
+        match self.trie.child(self.node, c) {
+            Some(child) => {
+                self.node = child;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn value(&self) -> Option<&'a V> {
+        self.node.value.as_ref()
+    }
+}
+
+impl <K: AsRef<str>, V, S: ChildStorage, A: NodeStorage> FromIterator<(K, V)> for NaiveTrieMap<V, S, A> {
+    /// キーが重複した場合、後の値で置き換えます。
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl <K: AsRef<str>, V, S: ChildStorage, A: NodeStorage> Extend<(K, V)> for NaiveTrieMap<V, S, A> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key.as_ref(), value);
+        }
+    }
+}
+
+/// 木構造を、各ノードを 1 行とする木として書き出します。キーのノードには `*` を付けます。
+impl <V, S: ChildStorage, A: NodeStorage> fmt::Display for NaiveTrieMap<V, S, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_tree(&self.root, &mut TreeWriter::new(f), 0, true, ' ')
+    }
+}
+
+impl <V, S: ChildStorage, A: NodeStorage> Default for NaiveTrieMap<V, S, A> {
+    fn default() -> Self {
+        NaiveTrieMap { root: Node::default(), pool: Default::default() }
+    }
+}
+
+impl <V, S: ChildStorage, A: NodeStorage> Default for Node<V, S, A> {
+    fn default() -> Self {
+        Node {
+            children: S::Map::default(),
+            value: None,
+            count: 0,
+        }
+    }
+}
+
+impl <V: SpaceUsage, S: ChildStorage, A: NodeStorage> SpaceUsage for Node<V, S, A> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.children.table_size_in_bytes()
+            + self.children.sorted_iter().map(|(_, child)| A::link_heap_size(child)).sum::<usize>()
+            + self.value.heap_size_in_bytes()
+    }
+}
+
+/// ノードを [`crate::collections::arena::ArenaNodes`] で確保する場合、根以外のノードはアリーナの領域で数えます。
+impl <V: SpaceUsage, S: ChildStorage, A: NodeStorage> SpaceUsage for NaiveTrieMap<V, S, A> {
+    fn heap_size_in_bytes(&self) -> usize {
+        self.root.heap_size_in_bytes() + A::pool_heap_size(&self.pool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::arena::ArenaNodes;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut map = NaiveTrieMap::new();
+        let mut expected = BTreeMap::new();
+        for _ in 0..2000 {
+            let len = rng.gen_range(0, 4);
+            let key: String = (0..len).map(|_| ['a', 'b', 'c', 'あ'][rng.gen_range(0, 4)]).collect();
+            match rng.gen_range(0, 3) {
+                0 => assert_eq!(expected.remove(&key), map.remove(&key)),
+                1 => {
+                    if let Some(v) = expected.get_mut(&key) {
+                        *v += 1;
+                    }
+                    if let Some(v) = map.get_mut(&key) {
+                        *v += 1;
+                    }
+                }
+                _ => {
+                    let v: u32 = rng.gen_range(0, 1000);
+                    assert_eq!(expected.insert(key.clone(), v), map.insert(&key, v));
+                }
+            }
+            assert_eq!(expected.len(), map.len());
+            assert_eq!(expected.get(&key), map.get(&key));
+            assert_eq!(expected.contains_key(&key), map.contains_key(&key));
+        }
+        let prefix = "a";
+        let under: Vec<(String, &u32)> = expected.iter()
+            .filter(|(k, _)| k.starts_with(prefix))
+            .map(|(k, v)| (k.clone(), v))
+            .collect();
+        assert_eq!(under, map.iter_prefix(prefix).collect::<Vec<_>>());
+        assert_eq!(under.len(), map.count_prefix(prefix));
+        let all: Vec<(String, &u32)> = expected.iter().map(|(k, v)| (k.clone(), v)).collect();
+        assert_eq!(all, map.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn arena() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut map: NaiveTrieMap<u32, AdaptiveChildren, ArenaNodes> = NaiveTrieMap::default();
+        let mut expected: NaiveTrieMap<u32> = NaiveTrieMap::new();
+        for _ in 0..2000 {
+            let len = rng.gen_range(0, 4);
+            let key: String = (0..len).map(|_| ['a', 'b', 'c', 'あ'][rng.gen_range(0, 4)]).collect();
+            if rng.gen_range(0, 3) == 0 {
+                assert_eq!(expected.remove(&key), map.remove(&key));
+            } else {
+                let v: u32 = rng.gen_range(0, 1000);
+                assert_eq!(expected.insert(&key, v), map.insert(&key, v));
+            }
+            assert_eq!(expected.get(&key), map.get(&key));
+            // the removed nodes are freed from the arena
+            assert_eq!(expected.size(), map.size());
+            assert_eq!(map.size() - 1, map.pool.len());
+        }
+        assert_eq!(expected.iter().collect::<Vec<_>>(), map.iter().collect::<Vec<_>>());
+        assert_eq!(expected.to_dot(), map.to_dot());
+        assert_eq!(expected.union(&expected).size(), map.union(&map).size());
+        assert!(map.size_in_bytes() > map.size() * core::mem::size_of::<Node<u32, AdaptiveChildren, ArenaNodes>>());
+    }
+
+    #[test]
+    fn common_prefixes() {
+        let mut map = NaiveTrieMap::new();
+        map.insert("f", 1);
+        map.insert("foo", 2);
+        map.insert("foobar", 3);
+        assert_eq!(vec![("f", &1), ("foo", &2)], map.common_prefixes("fooba").collect::<Vec<_>>());
+        assert_eq!(0, map.common_prefixes("bar").count());
+    }
+
+    #[test]
+    fn from_sorted_iter() {
+        let map = NaiveTrieMap::from_sorted_iter([("", 0), ("a", 1), ("ab", 2), ("ab", 3), ("b", 4)]);
+        assert_eq!(4, map.len());
+        assert_eq!(Some(&3), map.get("ab"));
+        assert_eq!(vec![("".to_string(), &0), ("a".to_string(), &1), ("ab".to_string(), &3), ("b".to_string(), &4)],
+                   map.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_unsorted_iter() {
+        NaiveTrieMap::from_sorted_iter([("b", 0), ("a", 1)]);
+    }
+
+    #[test]
+    fn from_iter() {
+        let mut map: NaiveTrieMap<i32> = [("foo", 1), ("bar", 2), ("foo", 3)].into_iter().collect();
+        assert_eq!(vec![("bar".to_string(), &2), ("foo".to_string(), &3)], map.iter().collect::<Vec<_>>());
+        map.extend(vec![("baz".to_string(), 4)]);
+        assert_eq!(Some(&4), map.get("baz"));
+        assert_eq!(3, map.len());
+    }
+
+    #[test]
+    fn set_operations() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let random_map = |rng: &mut StdRng| -> BTreeMap<String, u32> {
+            (0..100).map(|_| {
+                let len = rng.gen_range(0, 4);
+                let key: String = (0..len).map(|_| ['a', 'b', 'c', 'あ'][rng.gen_range(0, 4)]).collect();
+                (key, rng.gen_range(0, 1000))
+            }).collect()
+        };
+        let (expected_a, expected_b) = (random_map(&mut rng), random_map(&mut rng));
+        let a: NaiveTrieMap<u32> = expected_a.iter().map(|(k, v)| (k, *v)).collect();
+        let b: NaiveTrieMap<u32> = expected_b.iter().map(|(k, v)| (k, *v)).collect();
+
+        let mut union = expected_b.clone();
+        union.extend(expected_a.clone());
+        let intersection: BTreeMap<String, u32> = expected_a.clone().into_iter().filter(|(k, _)| expected_b.contains_key(k)).collect();
+        let difference: BTreeMap<String, u32> = expected_a.clone().into_iter().filter(|(k, _)| !expected_b.contains_key(k)).collect();
+        for (expected, map) in [(union, a.union(&b)), (intersection, a.intersection(&b)), (difference, a.difference(&b))] {
+            assert_eq!(expected.iter().map(|(k, v)| (k.clone(), v)).collect::<Vec<_>>(), map.iter().collect::<Vec<_>>());
+            assert_eq!(expected.len(), map.len());
+            let rebuilt: NaiveTrieMap<u32> = expected.into_iter().collect();
+            assert_eq!(rebuilt.size(), map.size());
+        }
+    }
+}
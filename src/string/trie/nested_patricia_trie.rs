@@ -0,0 +1,482 @@
+use super::Trie;
+use super::TrieCursor;
+use super::TrieId;
+use crate::bits::fid::FID;
+use crate::bits::fid::NaiveFID;
+use crate::space::SpaceUsage;
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+const DEFAULT_LEVELS: usize = 3;
+
+// where the labels of two or more bytes are stored
+enum Labels {
+    // the reversed labels are the keys of the next trie
+    Nested(Box<NestedPatriciaTrie>),
+    // the labels concatenated, sharing the common suffixes
+    Tail {
+        bytes: Vec<u8>,
+        // the last byte of each label
+        ends: NaiveFID,
+    },
+}
+
+/// 辺のラベルを別のトライ木のキーとして持つ、入れ子のパトリシア木 (marisa-trie 風)
+///
+/// 分岐のない部分をまとめたパトリシア木を LOUDS で表し、2 バイト以上のラベルは反転して次の段のトライ木のキーとします。
+/// 反転したラベルの共通の接頭辞、つまり元のラベルの共通の接尾辞が次の段で共有されるため、
+/// URL やファイルパスのように長い共通部分を持つ大きな辞書を小さく表せます。
+/// 最後の段のラベルは、共通の接尾辞を共有しながら 1 本のバイト列に並べます。
+///
+/// 構築後にキーを追加・削除することはできません。
+/// 各キーには `[0, len)` の番号が割り当てられ、キーと番号を相互に変換できます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::NestedPatriciaTrie;
+/// use rust_study::string::trie::Trie;
+/// let trie = NestedPatriciaTrie::new(&[
+///     "https://example.com/index.html",
+///     "https://example.com/about.html",
+///     "https://example.org/index.html",
+/// ]);
+/// assert!(trie.contains("https://example.org/index.html"));
+/// assert!(!trie.contains("https://example.org/"));
+/// assert_eq!(2, trie.count_prefix("https://example.com/"));
+/// assert_eq!(vec!["https://example.com/about.html", "https://example.com/index.html"],
+///            trie.predict("https://example.c").collect::<Vec<_>>());
+///
+/// let id = trie.key_to_id("https://example.com/about.html").unwrap();
+/// assert_eq!(Some("https://example.com/about.html".to_string()), trie.id_to_key(id));
+/// ```
+pub struct NestedPatriciaTrie {
+    // "10" for the super root, then deg(v) ones followed by a zero, for each node v in level order
+    louds: NaiveFID,
+    terminals: NaiveFID,
+    // the first byte of the label of the edge into each node
+    bases: Vec<u8>,
+    // whether the label of the edge into each node has two or more bytes
+    links: NaiveFID,
+    // for each node with a link, the node of the reversed label in the next trie, or the offset in the tail
+    extras: Vec<usize>,
+    labels: Labels,
+}
+
+impl NestedPatriciaTrie {
+    /// キーの列 `keys` から 3 段のトライ木を構築します。重複したキーは 1 つにまとめられます。
+    pub fn new<S: AsRef<str>>(keys: &[S]) -> Self {
+        Self::with_levels(keys, DEFAULT_LEVELS)
+    }
+
+    /// キーの列 `keys` から `levels` 段のトライ木を構築します。重複したキーは 1 つにまとめられます。
+    ///
+    /// 段数を増やすとラベルがより細かく共有されて小さくなりますが、ラベルの復元に時間がかかります。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is zero.
+    pub fn with_levels<S: AsRef<str>>(keys: &[S], levels: usize) -> Self {
+        assert!(levels > 0, "levels should be positive");
+        let mut keys: Vec<&[u8]> = keys.iter().map(|key| key.as_ref().as_bytes()).collect();
+        keys.sort();
+        keys.dedup();
+        Self::build(&keys, levels)
+    }
+
+    // keys should be sorted and deduplicated
+    fn build(keys: &[&[u8]], levels: usize) -> Self {
+        let mut louds = vec![true, false];
+        let mut bases = vec![0];
+        let mut links = vec![false];
+        let mut terminals = vec![];
+        // the labels of two or more bytes, in the order of the nodes
+        let mut linked: Vec<&[u8]> = vec![];
+        // (the range of keys under the node, the depth of the node)
+        let mut queue = VecDeque::from(vec![(0, keys.len(), 0)]);
+        while let Some((s, e, depth)) = queue.pop_front() {
+            let mut i = s;
+            terminals.push(i < e && keys[i].len() == depth);
+            if terminals[terminals.len() - 1] {
+                i += 1;
+            }
+            while i < e {
+                let base = keys[i][depth];
+                let j = i + keys[i..e].partition_point(|key| key[depth] == base);
+                // the keys in [i, j) share the common prefix of the first and the last one
+                let len = keys[i][depth..].iter().zip(&keys[j - 1][depth..]).take_while(|(a, b)| a == b).count();
+                louds.push(true);
+                bases.push(base);
+                links.push(len > 1);
+                if len > 1 {
+                    linked.push(&keys[i][depth..depth + len]);
+                }
+                queue.push_back((i, j, depth + len));
+                i = j;
+            }
+            louds.push(false);
+        }
+        let (extras, labels) = if levels > 1 {
+            let reversed: Vec<Vec<u8>> = linked.iter().map(|label| label.iter().rev().copied().collect()).collect();
+            let mut sorted: Vec<&[u8]> = reversed.iter().map(|label| label.as_slice()).collect();
+            sorted.sort();
+            sorted.dedup();
+            let next = Self::build(&sorted, levels - 1);
+            let extras = reversed.iter().map(|label| next.find(label).unwrap()).collect();
+            (extras, Labels::Nested(Box::new(next)))
+        } else {
+            Self::build_tail(&linked)
+        };
+        NestedPatriciaTrie {
+            louds: NaiveFID::from_bool_vec(&louds),
+            terminals: NaiveFID::from_bool_vec(&terminals),
+            bases,
+            links: NaiveFID::from_bool_vec(&links),
+            extras,
+            labels,
+        }
+    }
+
+    fn build_tail(linked: &[&[u8]]) -> (Vec<usize>, Labels) {
+        // in the descending order of the reversed labels, a label follows the labels ending with it
+        let mut order: Vec<usize> = (0..linked.len()).collect();
+        order.sort_by(|a, b| linked[*b].iter().rev().cmp(linked[*a].iter().rev()));
+        let mut offsets = vec![0; linked.len()];
+        let mut bytes = vec![];
+        let mut ends = vec![];
+        // the last stored label and its offset
+        let mut last: Option<(&[u8], usize)> = None;
+        for i in order {
+            let label = linked[i];
+            offsets[i] = match last {
+                Some((stored, offset)) if stored.ends_with(label) => offset + stored.len() - label.len(),
+                _ => {
+                    let offset = bytes.len();
+                    bytes.extend_from_slice(label);
+                    ends.extend((0..label.len()).map(|j| j + 1 == label.len()));
+                    last = Some((label, offset));
+                    offset
+                }
+            };
+        }
+        (offsets, Labels::Tail { bytes, ends: NaiveFID::from_bool_vec(&ends) })
+    }
+
+    /// キー `s` の番号を返します。キーが存在しない場合、 `None` を返します。
+    pub fn key_to_id(&self, s: &str) -> Option<usize> {
+        let v = self.find(s.as_bytes())?;
+        if self.terminals.access(v) {
+            Some(self.terminals.rank1(v))
+        } else {
+            None
+        }
+    }
+
+    /// 番号 `id` のキーを返します。 `id` が範囲外の場合、 `None` を返します。
+    pub fn id_to_key(&self, id: usize) -> Option<String> {
+        if id >= self.len() {
+            return None;
+        }
+        let mut key = vec![];
+        self.push_path(self.terminals.select1(id), &mut key);
+        Some(String::from_utf8(key).expect("keys are valid UTF-8"))
+    }
+
+    /// 最も外側のトライ木のノードの個数を返します。
+    pub fn size(&self) -> usize {
+        self.bases.len()
+    }
+
+    /// 入れ子になったトライ木の段数を返します。
+    pub fn levels(&self) -> usize {
+        match &self.labels {
+            Labels::Nested(next) => next.levels() + 1,
+            Labels::Tail { .. } => 1,
+        }
+    }
+
+    // the ids of the children of node v
+    fn children(&self, v: usize) -> Range<usize> {
+        let s = self.louds.select0(v) + 1;
+        let e = self.louds.select0(v + 1);
+        let first = self.louds.rank1(s);
+        first..first + (e - s)
+    }
+
+    fn parent(&self, v: usize) -> usize {
+        self.louds.rank0(self.louds.select1(v)) - 1
+    }
+
+    // the child of v whose label starts with the byte b
+    fn child(&self, v: usize, b: u8) -> Option<usize> {
+        let children = self.children(v);
+        let bases = &self.bases[children.clone()];
+        bases.binary_search(&b).ok().map(|i| children.start + i)
+    }
+
+    // appends the label of the edge into v
+    fn push_label(&self, v: usize, out: &mut Vec<u8>) {
+        if !self.links.access(v) {
+            out.push(self.bases[v]);
+            return;
+        }
+        let extra = self.extras[self.links.rank1(v)];
+        match &self.labels {
+            Labels::Nested(next) => {
+                let start = out.len();
+                next.push_path(extra, out);
+                out[start..].reverse();
+            }
+            Labels::Tail { bytes, ends } => {
+                let end = ends.select1(ends.rank1(extra));
+                out.extend_from_slice(&bytes[extra..=end]);
+            }
+        }
+    }
+
+    // appends the bytes on the path from the root to v
+    fn push_path(&self, mut v: usize, out: &mut Vec<u8>) {
+        let mut path = vec![];
+        while v != 0 {
+            path.push(v);
+            v = self.parent(v);
+        }
+        for v in path.into_iter().rev() {
+            self.push_label(v, out);
+        }
+    }
+
+    // the highest node whose path starts with prefix, and the path
+    fn find_prefix(&self, prefix: &[u8]) -> Option<(usize, Vec<u8>)> {
+        let mut v = 0;
+        let mut path = vec![];
+        while path.len() < prefix.len() {
+            let start = path.len();
+            v = self.child(v, prefix[start])?;
+            self.push_label(v, &mut path);
+            let end = path.len().min(prefix.len());
+            if path[start..end] != prefix[start..end] {
+                return None;
+            }
+        }
+        Some((v, path))
+    }
+
+    fn find(&self, s: &[u8]) -> Option<usize> {
+        self.find_prefix(s).filter(|(_, path)| path.len() == s.len()).map(|(v, _)| v)
+    }
+}
+
+impl Trie for NestedPatriciaTrie {
+    fn contains(&self, s: &str) -> bool {
+        self.key_to_id(s).is_some()
+    }
+
+    fn prefix<'a>(&self, s:&'a str) -> &'a str {
+        let len = self.common_prefixes(s).last().map_or(0, |key| key.len());
+        &s[0..len]
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let bytes = query.as_bytes();
+        let mut node = Some(0);
+        // node corresponds to query[0..end]
+        let mut end = 0;
+        let mut label = vec![];
+        core::iter::from_fn(move || {
+            loop {
+                let current = node?;
+                let len = end;
+                node = bytes.get(end).and_then(|b| self.child(current, *b)).filter(|v| {
+                    label.clear();
+                    self.push_label(*v, &mut label);
+                    bytes[end..].starts_with(&label)
+                });
+                end += label.len();
+                // a stored key always ends at a char boundary of the query
+                if self.terminals.access(current) {
+                    return Some(&query[0..len]);
+                }
+            }
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.terminals.rank1(self.terminals.len())
+    }
+
+    /// `prefix` から始まるキーの個数を返します。
+    ///
+    /// 部分木のノードをすべてたどるため、部分木の大きさに比例する時間がかかります。
+    fn count_prefix(&self, prefix: &str) -> usize {
+        let mut stack: Vec<usize> = self.find_prefix(prefix.as_bytes()).map(|(v, _)| v).into_iter().collect();
+        let mut count = 0;
+        while let Some(v) = stack.pop() {
+            if self.terminals.access(v) {
+                count += 1;
+            }
+            stack.extend(self.children(v));
+        }
+        count
+    }
+
+    fn predict(&self, prefix: &str) -> impl Iterator<Item = String> + '_ {
+        let mut stack: Vec<(usize, Vec<u8>)> = self.find_prefix(prefix.as_bytes()).into_iter().collect();
+        core::iter::from_fn(move || {
+            while let Some((v, key)) = stack.pop() {
+                for child in self.children(v).rev() {
+                    let mut child_key = key.clone();
+                    self.push_label(child, &mut child_key);
+                    stack.push((child, child_key));
+                }
+                if self.terminals.access(v) {
+                    return Some(String::from_utf8(key).expect("keys are valid UTF-8"));
+                }
+            }
+            None
+        })
+    }
+
+    fn cursor(&self) -> impl TrieCursor<'_> {
+        Cursor { trie: self, v: 0, label: vec![], offset: 0 }
+    }
+}
+
+/// 番号はキーの幅優先順で、辞書順ではありません。
+impl TrieId for NestedPatriciaTrie {
+    fn key_to_id(&self, s: &str) -> Option<usize> {
+        NestedPatriciaTrie::key_to_id(self, s)
+    }
+
+    fn id_to_key(&self, id: usize) -> Option<String> {
+        NestedPatriciaTrie::id_to_key(self, id)
+    }
+}
+
+#[derive(Clone)]
+struct Cursor<'a> {
+    trie: &'a NestedPatriciaTrie,
+    v: usize,
+    // the label of the edge into v, and the number of its bytes passed
+    label: Vec<u8>,
+    offset: usize,
+}
+
+impl <'a> TrieCursor<'a> for Cursor<'a> {
+    type Value = ();
+
+    fn step(&mut self, c: char) -> bool {
+        let mut next = self.clone();
+        let mut buf = [0; 4];
+        for b in c.encode_utf8(&mut buf).bytes() {
+            if next.offset == next.label.len() {
+                match self.trie.child(next.v, b) {
+                    Some(child) => {
+                        next.v = child;
+                        next.label.clear();
+                        self.trie.push_label(child, &mut next.label);
+                        next.offset = 1;
+                    }
+                    None => return false,
+                }
+            } else if next.label[next.offset] == b {
+                next.offset += 1;
+            } else {
+                return false;
+            }
+        }
+        *self = next;
+        true
+    }
+
+    fn value(&self) -> Option<&'a ()> {
+        if self.offset == self.label.len() && self.trie.terminals.access(self.v) {
+            Some(&())
+        } else {
+            None
+        }
+    }
+}
+
+impl SpaceUsage for Labels {
+    fn heap_size_in_bytes(&self) -> usize {
+        match self {
+            Labels::Nested(trie) => trie.heap_size_in_bytes(),
+            Labels::Tail { bytes, ends } => bytes.heap_size_in_bytes() + ends.heap_size_in_bytes(),
+        }
+    }
+}
+
+impl SpaceUsage for NestedPatriciaTrie {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("louds", &self.louds), ("terminals", &self.terminals), ("bases", &self.bases), ("links", &self.links), ("extras", &self.extras), ("labels", &self.labels)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::trie::ByteTrie;
+    use crate::string::trie::conformance;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    #[test]
+    fn same_as_byte_trie() {
+        proptest!(|(keys in vec(conformance::key_of(&['a', 'b', '/', 'あ'], 12), 0..300), levels in 1..=4_usize)| {
+            let mut trie = ByteTrie::new();
+            keys.iter().for_each(|key| { trie.append(key); });
+            let nested = NestedPatriciaTrie::with_levels(&keys, levels);
+            prop_assert_eq!(levels, nested.levels());
+            prop_assert_eq!(trie.len(), nested.len());
+            prop_assert_eq!(trie.keys().collect::<Vec<_>>(), nested.keys().collect::<Vec<_>>());
+            for id in 0..nested.len() {
+                let key = nested.id_to_key(id).unwrap();
+                prop_assert_eq!(Some(id), nested.key_to_id(&key));
+            }
+            prop_assert_eq!(None, nested.id_to_key(nested.len()));
+        });
+    }
+
+    #[test]
+    fn conformance() {
+        for levels in 1..=4 {
+            conformance::check_static(|keys| NestedPatriciaTrie::with_levels(keys, levels));
+        }
+    }
+
+    #[test]
+    fn share_labels() {
+        let trie = NestedPatriciaTrie::with_levels(&["/usr/local/lib", "/usr/local/bin", "/opt/lib", "/opt/bin"], 1);
+        // the root, "/", "usr/local/", "opt/", and "lib" and "bin" under each
+        assert_eq!(8, trie.size());
+        match &trie.labels {
+            // "usr/local/", "opt/", "lib" and "bin"
+            Labels::Tail { bytes, .. } => assert_eq!(20, bytes.len()),
+            Labels::Nested(_) => unreachable!(),
+        }
+
+        let trie = NestedPatriciaTrie::with_levels(&["foobar", "bar", "obar"], 1);
+        match &trie.labels {
+            // "bar" and "obar" are stored as the suffixes of "foobar"
+            Labels::Tail { bytes, .. } => assert_eq!(b"foobar", bytes.as_slice()),
+            Labels::Nested(_) => unreachable!(),
+        }
+
+        let empty = NestedPatriciaTrie::new::<&str>(&[]);
+        assert!(empty.is_empty());
+        assert!(!empty.contains(""));
+        assert_eq!(None, empty.id_to_key(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_levels() {
+        NestedPatriciaTrie::with_levels(&["foo"], 0);
+    }
+}
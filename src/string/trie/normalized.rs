@@ -0,0 +1,295 @@
+use super::Trie;
+use super::TrieCursor;
+use super::TrieMap;
+use super::TrieMut;
+use crate::space::SpaceUsage;
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Bound;
+use core::ops::RangeBounds;
+
+/// キーの正規化
+///
+/// 正規化した文字列が等しいキーは、同じキーとして扱われます。
+pub trait Normalizer {
+    /// 文字列 `s` を正規化します。
+    fn normalize<'a>(&self, s: &'a str) -> Cow<'a, str>;
+}
+
+/// ASCII の大文字を小文字にそろえる正規化
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AsciiCaseFold;
+
+impl Normalizer for AsciiCaseFold {
+    fn normalize<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        if s.bytes().any(|b| b.is_ascii_uppercase()) {
+            Cow::Owned(s.to_ascii_lowercase())
+        } else {
+            Cow::Borrowed(s)
+        }
+    }
+}
+
+/// Unicode の NFKC による正規化
+///
+/// 全角英数字と半角英数字、半角カナと全角カナなど、互換等価な文字列をそろえます。
+#[cfg(feature = "nfkc")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Nfkc;
+
+#[cfg(feature = "nfkc")]
+impl Normalizer for Nfkc {
+    fn normalize<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        use unicode_normalization::UnicodeNormalization;
+        if unicode_normalization::is_nfkc_quick(s.chars()) == unicode_normalization::IsNormalized::Yes {
+            Cow::Borrowed(s)
+        } else {
+            Cow::Owned(s.nfkc().collect())
+        }
+    }
+}
+
+/// 2 つの正規化を順に適用します。
+impl <A: Normalizer, B: Normalizer> Normalizer for (A, B) {
+    fn normalize<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        match self.0.normalize(s) {
+            Cow::Borrowed(s) => self.1.normalize(s),
+            Cow::Owned(s) => Cow::Owned(self.1.normalize(&s).into_owned()),
+        }
+    }
+}
+
+/// 追加・検索するキーを正規化するトライ木
+///
+/// 内部のトライ木 `T` には正規化したキーを保持するため、列挙されるキーは正規化した形になります。
+/// [`Trie::prefix()`] や [`Trie::common_prefixes()`] は、正規化した接頭辞がキーである元のクエリの接頭辞を返します。
+/// 正規化によって文字数が変わりうるため、クエリの接頭辞ごとに検索します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::*;
+/// let mut trie = Normalized::new(NaiveTrie::new(), AsciiCaseFold);
+/// assert!(trie.insert("Rust"));
+/// assert!(!trie.insert("RUST"));
+/// assert!(trie.contains("rUsT"));
+/// assert_eq!("RUST", trie.prefix("RUSTACEAN"));
+/// assert_eq!(vec!["rust"], trie.keys().collect::<Vec<_>>());
+/// ```
+pub struct Normalized<T, N> {
+    trie: T,
+    normalizer: N,
+}
+
+impl <T, N: Normalizer> Normalized<T, N> {
+    /// トライ木 `trie` に、正規化 `normalizer` を適用します。
+    ///
+    /// `trie` がすでに保持しているキーは、正規化されているものとして扱います。
+    pub fn new(trie: T, normalizer: N) -> Self {
+        Normalized { trie, normalizer }
+    }
+
+    /// 内部のトライ木を参照します。
+    pub fn inner(&self) -> &T {
+        &self.trie
+    }
+
+    /// 内部のトライ木を返します。
+    pub fn into_inner(self) -> T {
+        self.trie
+    }
+
+    /// 文字列 `s` を正規化します。
+    pub fn normalize<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        self.normalizer.normalize(s)
+    }
+
+    // the prefixes of query at char boundaries, from the shortest
+    fn query_prefixes<'a>(query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        query.char_indices().map(|(i, _)| i).chain([query.len()]).map(move |i| &query[0..i])
+    }
+
+    fn normalize_bounds<'b, R: RangeBounds<&'b str>>(&self, range: R) -> (Bound<String>, Bound<String>) {
+        let normalize = |s: &&str| self.normalize(s).into_owned();
+        (range.start_bound().map(normalize), range.end_bound().map(normalize))
+    }
+}
+
+impl <T: Trie, N: Normalizer> Trie for Normalized<T, N> {
+    fn contains(&self, s: &str) -> bool {
+        self.trie.contains(&self.normalize(s))
+    }
+
+    fn prefix<'a>(&self, s:&'a str) -> &'a str {
+        let len = self.common_prefixes(s).last().map_or(0, |key| key.len());
+        &s[0..len]
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        Self::query_prefixes(query).filter(move |prefix| self.contains(prefix))
+    }
+
+    fn len(&self) -> usize {
+        self.trie.len()
+    }
+
+    fn count_prefix(&self, prefix: &str) -> usize {
+        self.trie.count_prefix(&self.normalize(prefix))
+    }
+
+    fn predict(&self, prefix: &str) -> impl Iterator<Item = String> + '_ {
+        // the iterator of the inner trie borrows the normalized prefix
+        self.trie.predict(&self.normalize(prefix)).collect::<Vec<_>>().into_iter()
+    }
+
+    /// 根を指す内部のトライ木のカーソルを返します。カーソルは正規化した文字をたどります。
+    fn cursor(&self) -> impl TrieCursor<'_> {
+        self.trie.cursor()
+    }
+
+    fn fuzzy_search(&self, query: &str, max_edits: usize) -> Vec<(String, usize)> {
+        self.trie.fuzzy_search(&self.normalize(query), max_edits)
+    }
+
+    fn hamming_search(&self, query: &str, max_mismatches: usize) -> Vec<(String, usize)> {
+        self.trie.hamming_search(&self.normalize(query), max_mismatches)
+    }
+
+    fn search_pattern(&self, pattern: &str) -> Vec<String> {
+        self.trie.search_pattern(&self.normalize(pattern))
+    }
+
+    fn range<'a, 'b, R: RangeBounds<&'b str> + 'a>(&'a self, range: R) -> impl Iterator<Item = String> + 'a {
+        let bounds = self.normalize_bounds(range);
+        self.trie.keys().filter(move |key| bounds.contains(key))
+    }
+}
+
+impl <T: TrieMut, N: Normalizer> TrieMut for Normalized<T, N> {
+    fn insert(&mut self, s: &str) -> bool {
+        let key = self.normalizer.normalize(s);
+        self.trie.insert(&key)
+    }
+
+    fn remove(&mut self, s: &str) -> bool {
+        let key = self.normalizer.normalize(s);
+        self.trie.remove(&key)
+    }
+}
+
+impl <V, T: TrieMap<V>, N: Normalizer> TrieMap<V> for Normalized<T, N> {
+    fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        let key = self.normalizer.normalize(key);
+        self.trie.insert(&key, value)
+    }
+
+    fn get(&self, key: &str) -> Option<&V> {
+        self.trie.get(&self.normalize(key))
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        let key = self.normalizer.normalize(key);
+        self.trie.get_mut(&key)
+    }
+
+    fn remove(&mut self, key: &str) -> Option<V> {
+        let key = self.normalizer.normalize(key);
+        self.trie.remove(&key)
+    }
+
+    fn len(&self) -> usize {
+        self.trie.len()
+    }
+
+    fn count_prefix(&self, prefix: &str) -> usize {
+        self.trie.count_prefix(&self.normalize(prefix))
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = (&'a str, &'a V)> + 'a where V: 'a {
+        Self::query_prefixes(query).filter_map(move |prefix| Some((prefix, self.get(prefix)?)))
+    }
+
+    fn iter_prefix<'a>(&'a self, prefix: &str) -> impl Iterator<Item = (String, &'a V)> + 'a where V: 'a {
+        self.trie.iter_prefix(&self.normalize(prefix)).collect::<Vec<_>>().into_iter()
+    }
+
+    fn cursor<'a>(&'a self) -> impl TrieCursor<'a, Value = V> + 'a where V: 'a {
+        self.trie.cursor()
+    }
+
+    fn fuzzy_search<'a>(&'a self, query: &str, max_edits: usize) -> Vec<(String, &'a V, usize)> where V: 'a {
+        self.trie.fuzzy_search(&self.normalize(query), max_edits)
+    }
+
+    fn hamming_search<'a>(&'a self, query: &str, max_mismatches: usize) -> Vec<(String, &'a V, usize)> where V: 'a {
+        self.trie.hamming_search(&self.normalize(query), max_mismatches)
+    }
+
+    fn search_pattern<'a>(&'a self, pattern: &str) -> Vec<(String, &'a V)> where V: 'a {
+        self.trie.search_pattern(&self.normalize(pattern))
+    }
+
+    fn range<'a, 'b, R: RangeBounds<&'b str> + 'a>(&'a self, range: R) -> impl Iterator<Item = (String, &'a V)> + 'a where V: 'a {
+        let bounds = self.normalize_bounds(range);
+        self.trie.iter().filter(move |(key, _)| bounds.contains(key))
+    }
+}
+
+impl <T: SpaceUsage, N> SpaceUsage for Normalized<T, N> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("trie", &self.trie)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::trie::NaiveTrieMap;
+    use crate::string::trie::RadixTrie;
+
+    #[test]
+    fn case_fold() {
+        let mut trie = Normalized::new(RadixTrie::new(), AsciiCaseFold);
+        for key in ["New", "NEWS", "york", "Ärger"] {
+            trie.insert(key);
+        }
+        assert_eq!(4, trie.len());
+        assert!(trie.contains("news"));
+        assert!(trie.contains("ÄRGER"));
+        // only ASCII letters are folded
+        assert!(!trie.contains("ärger"));
+        assert_eq!(vec!["NeW", "NeWs"], trie.common_prefixes("NeWsPaPeR").collect::<Vec<_>>());
+        assert_eq!(2, trie.count_prefix("NE"));
+        assert_eq!(vec!["new", "news"], trie.predict("N").collect::<Vec<_>>());
+        assert_eq!(vec!["news", "york", "Ärger"], trie.range("NEWS"..).collect::<Vec<_>>());
+        assert_eq!(vec![("york".to_string(), 0)], trie.fuzzy_search("YORK", 0));
+        assert!(trie.remove("NEW"));
+        assert!(!trie.contains("new"));
+    }
+
+    #[test]
+    fn map() {
+        let mut map = Normalized::new(NaiveTrieMap::new(), AsciiCaseFold);
+        assert_eq!(None, map.insert("Tokyo", 1));
+        assert_eq!(Some(1), map.insert("TOKYO", 2));
+        *map.get_mut("tokyo").unwrap() += 10;
+        assert_eq!(Some(&12), map.get("ToKyO"));
+        assert_eq!(Some(("TOKYO", &12)), map.longest_prefix("TOKYOTO"));
+        assert_eq!(Some(12), map.remove("tokyo"));
+        assert!(map.is_empty());
+    }
+
+    #[cfg(feature = "nfkc")]
+    #[test]
+    fn nfkc() {
+        let mut trie = Normalized::new(NaiveTrieMap::new(), (Nfkc, AsciiCaseFold));
+        trie.insert("ｶﾀｶﾅ", 1);
+        trie.insert("Ｒｕｓｔ", 2);
+        assert_eq!(Some(&1), trie.get("カタカナ"));
+        assert_eq!(Some(&2), trie.get("rust"));
+        assert_eq!(Some(("ｒｕｓｔ", &2)), trie.longest_prefix("ｒｕｓｔacean"));
+    }
+}
@@ -0,0 +1,298 @@
+use super::ByteTrie;
+use super::Dawg;
+use super::Trie;
+use super::TrieCursor;
+use super::TrieMut;
+use crate::space::SpaceUsage;
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::Peekable;
+
+const DEFAULT_THRESHOLD: usize = 1024;
+
+/// キーを任意の順に追加・削除でき、定期的に最小化する DAWG
+///
+/// 最小化した [`Dawg`] と、その後に追加したキーを保持する [`ByteTrie`] 、 `Dawg` から削除したキーを保持する `ByteTrie` からなります。
+/// 追加・削除したキーの個数がしきい値に達すると、 `Dawg` のキーと追加したキーをソート済みのまま併合して `Dawg` を作り直します。
+/// キーを先にすべて集めてソートする必要がないため、キーを追加し続けるサービスでも使用メモリを小さく保てます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::OnlineDawg;
+/// use rust_study::string::trie::Trie;
+/// use rust_study::string::trie::TrieMut;
+/// let mut dawg = OnlineDawg::with_threshold(4);
+/// dawg.insert("tops");
+/// dawg.insert("tap");
+/// dawg.insert("taps");
+/// assert_eq!(3, dawg.pending());
+/// assert_eq!(vec!["tap", "taps", "tops"], dawg.keys().collect::<Vec<_>>());
+///
+/// // minimized when the fourth key is added
+/// dawg.insert("top");
+/// assert_eq!(0, dawg.pending());
+/// assert_eq!(5, dawg.dawg().size());
+///
+/// dawg.remove("tap");
+/// assert_eq!(vec!["taps", "top", "tops"], dawg.keys().collect::<Vec<_>>());
+/// ```
+pub struct OnlineDawg {
+    dawg: Dawg,
+    // keys not in the dawg
+    inserted: ByteTrie,
+    // keys in the dawg but removed
+    removed: ByteTrie,
+    threshold: usize,
+}
+
+impl OnlineDawg {
+    /// 空の DAWG を構築します。
+    pub fn new() -> Self {
+        Self::with_threshold(DEFAULT_THRESHOLD)
+    }
+
+    /// 追加・削除したキーが `threshold` 個に達するたびに最小化する、空の DAWG を構築します。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is zero.
+    pub fn with_threshold(threshold: usize) -> Self {
+        assert!(threshold > 0, "threshold should be positive");
+        OnlineDawg {
+            dawg: Dawg::from_sorted_keys(Vec::<&str>::new()),
+            inserted: ByteTrie::new(),
+            removed: ByteTrie::new(),
+            threshold,
+        }
+    }
+
+    /// 最小化する、追加・削除したキーの個数のしきい値を返します。
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// 最小化した DAWG を参照します。最後の最小化の後に追加・削除したキーは反映されていません。
+    pub fn dawg(&self) -> &Dawg {
+        &self.dawg
+    }
+
+    /// 最後の最小化の後に追加・削除したキーの個数を返します。
+    pub fn pending(&self) -> usize {
+        self.inserted.len() + self.removed.len()
+    }
+
+    /// 追加・削除したキーを反映して、 DAWG を最小化し直します。
+    pub fn minimize(&mut self) {
+        if self.pending() == 0 {
+            return;
+        }
+        let kept = self.dawg.keys().filter(|key| !self.removed.contains(key));
+        let dawg = Dawg::from_sorted_keys(merge(kept, self.inserted.keys()));
+        self.dawg = dawg;
+        self.inserted = ByteTrie::new();
+        self.removed = ByteTrie::new();
+    }
+
+    /// 追加・削除したキーがしきい値に達した場合に、最小化します。
+    fn minimize_if_needed(&mut self) {
+        if self.pending() >= self.threshold {
+            self.minimize();
+        }
+    }
+}
+
+/// 互いに素な、昇順の 2 つの列 `a` と `b` を、昇順に併合します。
+fn merge<T: Ord, A: Iterator<Item = T>, B: Iterator<Item = T>>(a: A, b: B) -> impl Iterator<Item = T> {
+    struct Merge<A: Iterator, B: Iterator> {
+        a: Peekable<A>,
+        b: Peekable<B>,
+    }
+    impl <T: Ord, A: Iterator<Item = T>, B: Iterator<Item = T>> Iterator for Merge<A, B> {
+        type Item = T;
+
+        fn next(&mut self) -> Option<T> {
+            match (self.a.peek(), self.b.peek()) {
+                (Some(a), Some(b)) if b < a => self.b.next(),
+                (Some(_), _) => self.a.next(),
+                (None, _) => self.b.next(),
+            }
+        }
+    }
+    Merge { a: a.peekable(), b: b.peekable() }
+}
+
+impl Default for OnlineDawg {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Dawg> for OnlineDawg {
+    fn from(dawg: Dawg) -> Self {
+        OnlineDawg { dawg, ..Self::new() }
+    }
+}
+
+impl Trie for OnlineDawg {
+    fn contains(&self, s: &str) -> bool {
+        (self.dawg.contains(s) && !self.removed.contains(s)) || self.inserted.contains(s)
+    }
+
+    fn prefix<'a>(&self, s: &'a str) -> &'a str {
+        let len = self.common_prefixes(s).last().map_or(0, |key| key.len());
+        &s[0..len]
+    }
+
+    fn len(&self) -> usize {
+        self.dawg.len() - self.removed.len() + self.inserted.len()
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let kept = self.dawg.common_prefixes(query).filter(|key| !self.removed.contains(key));
+        merge(kept, self.inserted.common_prefixes(query))
+    }
+
+    fn count_prefix(&self, prefix: &str) -> usize {
+        self.dawg.count_prefix(prefix) - self.removed.count_prefix(prefix) + self.inserted.count_prefix(prefix)
+    }
+
+    fn predict(&self, prefix: &str) -> impl Iterator<Item = String> + '_ {
+        let kept = self.dawg.predict(prefix).filter(|key| !self.removed.contains(key));
+        merge(kept, self.inserted.predict(prefix))
+    }
+
+    fn cursor(&self) -> impl TrieCursor<'_> {
+        Cursor {
+            dawg: Some(self.dawg.cursor()),
+            inserted: Some(self.inserted.cursor()),
+            removed: Some(self.removed.cursor()),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Cursor<D, I, R> {
+    dawg: Option<D>,
+    inserted: Option<I>,
+    removed: Option<R>,
+}
+
+impl <'a, D: TrieCursor<'a>, I: TrieCursor<'a>, R: TrieCursor<'a>> TrieCursor<'a> for Cursor<D, I, R> {
+    type Value = ();
+
+    fn step(&mut self, c: char) -> bool {
+        // a failed step leaves the cursor as it is
+        let dawg = self.dawg.as_mut().is_some_and(|cursor| cursor.step(c));
+        let inserted = self.inserted.as_mut().is_some_and(|cursor| cursor.step(c));
+        if !dawg && !inserted {
+            return false;
+        }
+        if !dawg {
+            self.dawg = None;
+        }
+        if !inserted {
+            self.inserted = None;
+        }
+        if !self.removed.as_mut().is_some_and(|cursor| cursor.step(c)) {
+            self.removed = None;
+        }
+        true
+    }
+
+    fn value(&self) -> Option<&'a ()> {
+        if self.is_leaf() {
+            Some(&())
+        } else {
+            None
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        let removed = self.removed.as_ref().is_some_and(|cursor| cursor.is_leaf());
+        (self.dawg.as_ref().is_some_and(|cursor| cursor.is_leaf()) && !removed)
+            || self.inserted.as_ref().is_some_and(|cursor| cursor.is_leaf())
+    }
+}
+
+impl TrieMut for OnlineDawg {
+    fn insert(&mut self, s: &str) -> bool {
+        let added = if self.dawg.contains(s) {
+            self.removed.remove(s)
+        } else {
+            self.inserted.insert(s)
+        };
+        if added {
+            self.minimize_if_needed();
+        }
+        added
+    }
+
+    fn remove(&mut self, s: &str) -> bool {
+        let removed = if self.dawg.contains(s) {
+            self.removed.insert(s)
+        } else {
+            self.inserted.remove(s)
+        };
+        if removed {
+            self.minimize_if_needed();
+        }
+        removed
+    }
+}
+
+impl SpaceUsage for OnlineDawg {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("dawg", &self.dawg), ("inserted", &self.inserted), ("removed", &self.removed)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::trie::conformance;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn same_as_btree_set() {
+        // the default threshold is checked with the other tries, and minimizing after every update by same_as_dawg
+        conformance::check_dynamic(|| OnlineDawg::with_threshold(8));
+    }
+
+    #[test]
+    fn same_as_dawg() {
+        proptest!(ProptestConfig::with_cases(16), |(threshold in prop_oneof![Just(1), Just(8), Just(1024)], ops in conformance::ops(5, 500))| {
+            let mut dawg = OnlineDawg::with_threshold(threshold);
+            let mut expected = BTreeSet::new();
+            for op in &ops {
+                op.apply(&mut dawg, &mut expected);
+                prop_assert!(dawg.pending() < threshold);
+            }
+            dawg.minimize();
+            prop_assert_eq!(0, dawg.pending());
+            let keys: Vec<String> = expected.into_iter().collect();
+            prop_assert_eq!(Dawg::new(&keys).size(), dawg.dawg().size());
+            prop_assert_eq!(keys, dawg.keys().collect::<Vec<_>>());
+        });
+    }
+
+    #[test]
+    fn from_dawg() {
+        let mut dawg = OnlineDawg::from(Dawg::new(&["a", "b"]));
+        assert!(!dawg.insert("a"));
+        assert!(dawg.remove("a"));
+        assert!(!dawg.contains("a"));
+        assert!(dawg.insert("a"));
+        assert_eq!(0, dawg.pending());
+        assert_eq!(2, dawg.len());
+    }
+
+    #[test]
+    #[should_panic(expected = "threshold should be positive")]
+    fn zero_threshold() {
+        OnlineDawg::with_threshold(0);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::collections::adaptive_map::AdaptiveMap;
+use crate::space::SpaceUsage;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Add;
+use core::ops::Sub;
+
+/// 各ノードに部分木の値の合計を保持するトライ木
+///
+/// キーを追加・削除するたびに根からの経路上の合計を更新するため、
+/// 接頭辞 `prefix` から始まるキーの値の合計を、部分木をたどらずに `O(|prefix|)` で求められます。
+/// URL のパスごとのアクセス数のような、階層的なカウンタに使えます。
+///
+/// 合計を保つため、値は [`Self::insert()`] と [`Self::add()`] でだけ更新できます。
+/// 値が浮動小数点数の場合、更新を繰り返すと合計に丸め誤差が蓄積します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::PrefixSumTrieMap;
+/// let mut traffic = PrefixSumTrieMap::new();
+/// traffic.add("/api/users", 120);
+/// traffic.add("/api/items", 80);
+/// traffic.add("/static/app.js", 300);
+/// traffic.add("/api/users", 30);
+/// assert_eq!(Some(&150), traffic.get("/api/users"));
+/// assert_eq!(230, traffic.prefix_sum("/api/"));
+/// assert_eq!(530, traffic.prefix_sum(""));
+///
+/// assert_eq!(Some(80), traffic.remove("/api/items"));
+/// assert_eq!(150, traffic.prefix_sum("/api/"));
+/// assert_eq!(0, traffic.prefix_sum("/admin/"));
+/// ```
+pub struct PrefixSumTrieMap<V> {
+    children: AdaptiveMap<char, Box<PrefixSumTrieMap<V>>>,
+    value: Option<V>,
+    // the number of keys in the subtree
+    count: usize,
+    // the sum of the values in the subtree
+    sum: V,
+}
+
+impl <V: Copy + Default + Add<Output = V> + Sub<Output = V>> PrefixSumTrieMap<V> {
+    /// 空のトライ木を構築します。
+    pub fn new() -> Self {
+        PrefixSumTrieMap {
+            children: AdaptiveMap::new(),
+            value: None,
+            count: 0,
+            sum: V::default(),
+        }
+    }
+
+    /// キー `key` に値 `value` を対応付けます。キーがすでに存在した場合、以前の値を返します。
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        self.insert_chars(key.chars(), value)
+    }
+
+    /// キー `key` の値に `delta` を加え、加えた後の値を返します。
+    ///
+    /// キーが存在しない場合、 `V::default()` に `delta` を加えた値で追加します。
+    pub fn add(&mut self, key: &str, delta: V) -> V {
+        let value = self.get(key).copied().unwrap_or_default() + delta;
+        self.insert(key, value);
+        value
+    }
+
+    /// キー `key` の値を返します。
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.find(key)?.value.as_ref()
+    }
+
+    /// キー `key` が存在する場合に、 `true` を返します。
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// キー `key` を取り除き、その値を返します。
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        self.remove_chars(key.chars())
+    }
+
+    /// 保持しているキーの個数を返します。
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// キーを保持していない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// `prefix` から始まるキーの個数を返します。
+    pub fn count_prefix(&self, prefix: &str) -> usize {
+        self.find(prefix).map_or(0, |node| node.count)
+    }
+
+    /// `prefix` から始まるキーの値の合計を返します。該当するキーが存在しない場合、 `V::default()` を返します。
+    pub fn prefix_sum(&self, prefix: &str) -> V {
+        self.find(prefix).map_or_else(V::default, |node| node.sum)
+    }
+
+    /// `prefix` から始まるキーと値の組を、キーの辞書順に返すイテレータを返します。
+    pub fn iter_prefix<'a>(&'a self, prefix: &str) -> impl Iterator<Item = (String, &'a V)> + 'a {
+        let mut stack: Vec<(&PrefixSumTrieMap<V>, String)> = self.find(prefix).map(|node| (node, prefix.to_string())).into_iter().collect();
+        core::iter::from_fn(move || {
+            while let Some((node, key)) = stack.pop() {
+                for (c, child) in node.children.sorted_iter().rev() {
+                    let mut child_key = key.clone();
+                    child_key.push(*c);
+                    stack.push((child, child_key));
+                }
+                if let Some(value) = &node.value {
+                    return Some((key, value));
+                }
+            }
+            None
+        })
+    }
+
+    /// 保持しているキーと値の組を、キーの辞書順に返すイテレータを返します。
+    pub fn iter(&self) -> impl Iterator<Item = (String, &V)> + '_ {
+        self.iter_prefix("")
+    }
+
+    fn find(&self, s: &str) -> Option<&PrefixSumTrieMap<V>> {
+        let mut node = self;
+        for c in s.chars() {
+            node = node.children.get(&c)?;
+        }
+        Some(node)
+    }
+
+    // returns the replaced value
+    fn insert_chars(&mut self, mut chars: core::str::Chars, value: V) -> Option<V> {
+        let old = match chars.next() {
+            None => self.value.replace(value),
+            Some(c) => self.children.get_or_insert_with(c, || Box::new(PrefixSumTrieMap::new())).insert_chars(chars, value),
+        };
+        if old.is_none() {
+            self.count += 1;
+        }
+        self.sum = self.sum + value - old.unwrap_or_default();
+        old
+    }
+
+    // returns the removed value
+    fn remove_chars(&mut self, mut chars: core::str::Chars) -> Option<V> {
+        let removed = match chars.next() {
+            None => self.value.take(),
+            Some(c) => {
+                let child = self.children.get_mut(&c)?;
+                let removed = child.remove_chars(chars);
+                if child.count == 0 {
+                    self.children.remove(&c);
+                }
+                removed
+            }
+        };
+        if let Some(value) = removed {
+            self.count -= 1;
+            self.sum = self.sum - value;
+        }
+        removed
+    }
+}
+
+impl <V: Copy + Default + Add<Output = V> + Sub<Output = V>> Default for PrefixSumTrieMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl <S: AsRef<str>, V: Copy + Default + Add<Output = V> + Sub<Output = V>> FromIterator<(S, V)> for PrefixSumTrieMap<V> {
+    fn from_iter<I: IntoIterator<Item = (S, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (key, value) in iter {
+            map.insert(key.as_ref(), value);
+        }
+        map
+    }
+}
+
+impl <V: SpaceUsage> SpaceUsage for PrefixSumTrieMap<V> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("children", &self.children), ("value", &self.value), ("sum", &self.sum)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn same_as_btree_map() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let random_key = |rng: &mut StdRng| -> String {
+            let len = rng.gen_range(0, 5);
+            (0..len).map(|_| ['a', 'b', '/', 'あ'][rng.gen_range(0, 4)]).collect()
+        };
+        let mut map = PrefixSumTrieMap::new();
+        let mut expected = BTreeMap::new();
+        for _ in 0..1000 {
+            let key = random_key(&mut rng);
+            match rng.gen_range(0, 3) {
+                0 => assert_eq!(expected.remove(&key), map.remove(&key)),
+                1 => {
+                    let value: i64 = rng.gen_range(-10, 10);
+                    assert_eq!(expected.insert(key.clone(), value), map.insert(&key, value));
+                }
+                _ => {
+                    let delta: i64 = rng.gen_range(-10, 10);
+                    let value = expected.entry(key.clone()).or_insert(0);
+                    *value += delta;
+                    assert_eq!(*value, map.add(&key, delta));
+                }
+            }
+            assert_eq!(expected.len(), map.len());
+            let prefix = random_key(&mut rng);
+            let under: Vec<(String, i64)> = expected.iter()
+                .filter(|(key, _)| key.starts_with(&prefix))
+                .map(|(key, value)| (key.clone(), *value))
+                .collect();
+            assert_eq!(under.iter().map(|(_, value)| value).sum::<i64>(), map.prefix_sum(&prefix));
+            assert_eq!(under.len(), map.count_prefix(&prefix));
+            assert_eq!(under, map.iter_prefix(&prefix).map(|(key, value)| (key, *value)).collect::<Vec<_>>());
+        }
+        assert_eq!(expected.values().sum::<i64>(), map.prefix_sum(""));
+        assert_eq!(expected.into_iter().collect::<Vec<_>>(), map.iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn empty_prefix() {
+        let mut map: PrefixSumTrieMap<f64> = [("", 1.5), ("a", 2.0)].into_iter().collect();
+        assert_eq!(3.5, map.prefix_sum(""));
+        assert_eq!(Some(1.5), map.remove(""));
+        assert!(!map.contains_key(""));
+        assert_eq!(2.0, map.prefix_sum(""));
+        assert_eq!(None, map.remove("ab"));
+        assert_eq!(1, map.len());
+    }
+}
@@ -0,0 +1,612 @@
+use super::automaton::Automaton;
+use super::automaton::Hamming;
+use super::automaton::KeyRange;
+use super::automaton::Levenshtein;
+use super::automaton::Wildcard;
+use super::Trie;
+use super::TrieCursor;
+use super::TrieMap;
+use super::TrieMut;
+use super::dot::DotWriter;
+use super::dot::TreeWriter;
+use crate::space::SpaceUsage;
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::RangeBounds;
+
+struct Node<V> {
+    // the label of the edge into the node
+    label: String,
+    // sorted by the first char of the label
+    children: Vec<Node<V>>,
+    value: Option<V>,
+    // the number of keys in the subtree
+    count: usize,
+}
+
+impl <V> Node<V> {
+    fn new(label: &str) -> Self {
+        Node { label: label.to_string(), children: vec![], value: None, count: 0 }
+    }
+
+    fn first_char(&self) -> char {
+        self.label.chars().next().unwrap()
+    }
+
+    fn child_index(&self, rest: &str) -> Result<usize, usize> {
+        let c = rest.chars().next().unwrap();
+        self.children.binary_search_by(|child| child.first_char().cmp(&c))
+    }
+
+    // the child whose label is a prefix of rest
+    fn child(&self, rest: &str) -> Option<&Node<V>> {
+        let child = &self.children[self.child_index(rest).ok()?];
+        if rest.starts_with(child.label.as_str()) {
+            Some(child)
+        } else {
+            None
+        }
+    }
+
+    fn insert(&mut self, rest: &str, value: V) -> Option<V> {
+        if rest.is_empty() {
+            let old = self.value.replace(value);
+            if old.is_none() {
+                self.count += 1;
+            }
+            return old;
+        }
+        let i = match self.child_index(rest) {
+            Ok(i) => i,
+            Err(i) => {
+                let mut leaf = Node::new(rest);
+                leaf.value = Some(value);
+                leaf.count = 1;
+                self.children.insert(i, leaf);
+                self.count += 1;
+                return None;
+            }
+        };
+        let child = &mut self.children[i];
+        let len = common_prefix_len(&child.label, rest);
+        if len < child.label.len() {
+            // split the edge
+            let mut mid = Node::new(&child.label[0..len]);
+            mid.count = child.count;
+            child.label.drain(0..len);
+            let rest_of_edge = core::mem::replace(child, mid);
+            child.children.push(rest_of_edge);
+        }
+        let old = child.insert(&rest[len..], value);
+        if old.is_none() {
+            self.count += 1;
+        }
+        old
+    }
+
+    fn remove(&mut self, rest: &str) -> Option<V> {
+        if rest.is_empty() {
+            let removed = self.value.take();
+            if removed.is_some() {
+                self.count -= 1;
+            }
+            return removed;
+        }
+        let i = self.child_index(rest).ok()?;
+        let child = &mut self.children[i];
+        if !rest.starts_with(child.label.as_str()) {
+            return None;
+        }
+        let removed = child.remove(&rest[child.label.len()..]);
+        if removed.is_some() {
+            self.count -= 1;
+            if child.count == 0 {
+                self.children.remove(i);
+            } else if child.value.is_none() && child.children.len() == 1 {
+                // merge the edge with the only child
+                let grandchild = child.children.pop().unwrap();
+                child.label.push_str(&grandchild.label);
+                child.children = grandchild.children;
+                child.value = grandchild.value;
+            }
+        }
+        removed
+    }
+
+    fn size(&self) -> usize {
+        1 + self.children.iter().map(|child| child.size()).sum::<usize>()
+    }
+
+    // returns the id of the node
+    fn write_dot(&self, dot: &mut DotWriter, next: &mut usize) -> usize {
+        let id = *next;
+        *next += 1;
+        dot.node(id, self.value.is_some());
+        for child in &self.children {
+            let child_id = child.write_dot(dot, next);
+            dot.edge(id, child_id, &child.label);
+        }
+        id
+    }
+
+    fn write_tree(&self, w: &mut TreeWriter, depth: usize, is_last: bool) -> fmt::Result {
+        w.node(depth, is_last, &self.label, self.value.is_some())?;
+        for (i, child) in self.children.iter().enumerate() {
+            child.write_tree(w, depth + 1, i + 1 == self.children.len())?;
+        }
+        Ok(())
+    }
+}
+
+// the length of the longest common prefix of a and b, on a char boundary
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    let mut len = a.bytes().zip(b.bytes()).take_while(|(x, y)| x == y).count();
+    while !a.is_char_boundary(len) {
+        len -= 1;
+    }
+    len
+}
+
+/// 一本道の辺をまとめて 1 本の文字列ラベルとする、値付きのトライ木 (パトリシア木)
+///
+/// 子を 1 つしか持たずキーでもないノードを作らないため、
+/// URL やファイルパスのような長いキーでもノード数はキーの個数の高々 2 倍に収まります。
+/// 各ノードの子は、ラベルの先頭の文字でソートした配列で保持します。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::RadixTrieMap;
+/// use rust_study::string::trie::TrieMap;
+/// let mut routes = RadixTrieMap::new();
+/// routes.insert("/", "root");
+/// routes.insert("/users", "users");
+/// routes.insert("/users/admin", "admin");
+/// assert_eq!(Some(("/users", &"users")), routes.longest_prefix("/users/alice"));
+/// assert_eq!(Some(("/", &"root")), routes.longest_prefix("/items"));
+/// assert_eq!(Some(&"admin"), routes.get("/users/admin"));
+/// assert_eq!(4, routes.size());
+/// ```
+pub struct RadixTrieMap<V> {
+    root: Node<V>,
+}
+
+impl <V> RadixTrieMap<V> {
+    /// 空のトライ木を構築します。
+    pub fn new() -> Self {
+        RadixTrieMap { root: Node::new("") }
+    }
+
+    /// ノードの個数を返します。
+    pub fn size(&self) -> usize {
+        self.root.size()
+    }
+
+    /// 木構造を Graphviz の DOT 言語で返します。
+    ///
+    /// ノードには根からの先行順に番号を付け、キーのノードを二重丸で描きます。辺のラベルは圧縮した文字列です。
+    pub fn to_dot(&self) -> String {
+        let mut dot = DotWriter::new();
+        self.root.write_dot(&mut dot, &mut 0);
+        dot.finish()
+    }
+
+    fn find(&self, key: &str) -> Option<&Node<V>> {
+        let mut node = &self.root;
+        let mut rest = key;
+        while !rest.is_empty() {
+            node = node.child(rest)?;
+            rest = &rest[node.label.len()..];
+        }
+        Some(node)
+    }
+
+    fn find_mut(&mut self, key: &str) -> Option<&mut Node<V>> {
+        let mut node = &mut self.root;
+        let mut rest = key;
+        while !rest.is_empty() {
+            let i = node.child_index(rest).ok()?;
+            node = &mut node.children[i];
+            rest = rest.strip_prefix(node.label.as_str())?;
+        }
+        Some(node)
+    }
+
+    // the highest node whose path starts with prefix, and its path
+    fn locate(&self, prefix: &str) -> Option<(&Node<V>, String)> {
+        let mut node = &self.root;
+        let mut rest = prefix;
+        while !rest.is_empty() {
+            let child = &node.children[node.child_index(rest).ok()?];
+            if child.label.starts_with(rest) {
+                let path = prefix[0..prefix.len() - rest.len()].to_string() + &child.label;
+                return Some((child, path));
+            }
+            node = node.child(rest)?;
+            rest = &rest[node.label.len()..];
+        }
+        Some((node, prefix.to_string()))
+    }
+
+    // runs the automaton along the keys, pruning the subtrees it rejects
+    fn search_by<'a, A: Automaton + 'a>(&'a self, mut automaton: A) -> impl Iterator<Item = (String, &'a V, A::Output)> + 'a {
+        // (node, the path to the node, the number of chars of the path without the label of the node)
+        let mut stack: Vec<(&Node<V>, String, usize)> = vec![(&self.root, String::new(), 0)];
+        core::iter::from_fn(move || {
+            while let Some((node, key, depth)) = stack.pop() {
+                automaton.truncate(depth);
+                if !node.label.chars().all(|c| automaton.push(c)) {
+                    continue;
+                }
+                for child in node.children.iter().rev() {
+                    stack.push((child, key.clone() + &child.label, automaton.depth()));
+                }
+                if let (Some(value), Some(output)) = (&node.value, automaton.accept()) {
+                    return Some((key, value, output));
+                }
+            }
+            None
+        })
+    }
+}
+
+impl <V> TrieMap<V> for RadixTrieMap<V> {
+    fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        self.root.insert(key, value)
+    }
+
+    fn get(&self, key: &str) -> Option<&V> {
+        self.find(key)?.value.as_ref()
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.find_mut(key)?.value.as_mut()
+    }
+
+    fn remove(&mut self, key: &str) -> Option<V> {
+        self.root.remove(key)
+    }
+
+    fn len(&self) -> usize {
+        self.root.count
+    }
+
+    fn count_prefix(&self, prefix: &str) -> usize {
+        self.locate(prefix).map_or(0, |(node, _)| node.count)
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = (&'a str, &'a V)> + 'a where V: 'a {
+        let mut node = Some(&self.root);
+        // node corresponds to query[0..end]
+        let mut end = 0;
+        core::iter::from_fn(move || {
+            loop {
+                let current = node?;
+                let len = end;
+                node = if end < query.len() { current.child(&query[end..]) } else { None };
+                end += node.map_or(0, |child| child.label.len());
+                if let Some(value) = &current.value {
+                    return Some((&query[0..len], value));
+                }
+            }
+        })
+    }
+
+    fn cursor<'a>(&'a self) -> impl TrieCursor<'a, Value = V> + 'a where V: 'a {
+        Cursor { node: &self.root, offset: 0 }
+    }
+
+    fn iter_prefix<'a>(&'a self, prefix: &str) -> impl Iterator<Item = (String, &'a V)> + 'a where V: 'a {
+        let mut stack: Vec<(&Node<V>, String)> = self.locate(prefix).into_iter().collect();
+        core::iter::from_fn(move || {
+            while let Some((node, key)) = stack.pop() {
+                for child in node.children.iter().rev() {
+                    stack.push((child, key.clone() + &child.label));
+                }
+                if let Some(value) = &node.value {
+                    return Some((key, value));
+                }
+            }
+            None
+        })
+    }
+
+    fn fuzzy_search<'a>(&'a self, query: &str, max_edits: usize) -> Vec<(String, &'a V, usize)> where V: 'a {
+        self.search_by(Levenshtein::new(query, max_edits)).collect()
+    }
+
+    fn hamming_search<'a>(&'a self, query: &str, max_mismatches: usize) -> Vec<(String, &'a V, usize)> where V: 'a {
+        self.search_by(Hamming::new(query, max_mismatches)).collect()
+    }
+
+    fn search_pattern<'a>(&'a self, pattern: &str) -> Vec<(String, &'a V)> where V: 'a {
+        self.search_by(Wildcard::new(pattern)).map(|(key, value, _)| (key, value)).collect()
+    }
+
+    fn range<'a, 'b, R: RangeBounds<&'b str> + 'a>(&'a self, range: R) -> impl Iterator<Item = (String, &'a V)> + 'a where V: 'a {
+        self.search_by(KeyRange::new(range)).map(|(key, value, _)| (key, value))
+    }
+}
+
+// a position in the label of a node
+struct Cursor<'a, V> {
+    node: &'a Node<V>,
+    // the length of the consumed part of the label
+    offset: usize,
+}
+
+impl <V> Clone for Cursor<'_, V> {
+    fn clone(&self) -> Self {
+        Cursor { node: self.node, offset: self.offset }
+    }
+}
+
+impl <'a, V> TrieCursor<'a> for Cursor<'a, V> {
+    type Value = V;
+
+    fn step(&mut self, c: char) -> bool {
+        if self.offset < self.node.label.len() {
+            if !self.node.label[self.offset..].starts_with(c) {
+                return false;
+            }
+            self.offset += c.len_utf8();
+            return true;
+        }
+        match self.node.children.binary_search_by(|child| child.first_char().cmp(&c)) {
+            Ok(i) => {
+                self.node = &self.node.children[i];
+                self.offset = c.len_utf8();
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn value(&self) -> Option<&'a V> {
+        if self.offset == self.node.label.len() {
+            self.node.value.as_ref()
+        } else {
+            None
+        }
+    }
+}
+
+/// 木構造を、各ノードを 1 行とする木として書き出します。キーのノードには `*` を付けます。
+impl <V> fmt::Display for RadixTrieMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.root.write_tree(&mut TreeWriter::new(f), 0, true)
+    }
+}
+
+impl <V> Default for RadixTrieMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 一本道の辺をまとめて 1 本の文字列ラベルとするトライ木 (パトリシア木)
+///
+/// [`RadixTrieMap`] の値を持たない版です。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::RadixTrie;
+/// use rust_study::string::trie::Trie;
+/// use rust_study::string::trie::TrieMut;
+/// let mut trie = RadixTrie::new();
+/// trie.insert("https://example.com/a/b/c");
+/// trie.insert("https://example.com/a/d");
+/// assert_eq!(4, trie.size());
+/// assert!(trie.contains("https://example.com/a/d"));
+/// assert_eq!(2, trie.count_prefix("https://example.com/a/"));
+/// ```
+pub struct RadixTrie {
+    map: RadixTrieMap<()>,
+}
+
+impl RadixTrie {
+    /// 空のトライ木を構築します。
+    pub fn new() -> Self {
+        RadixTrie { map: RadixTrieMap::new() }
+    }
+
+    /// ノードの個数を返します。
+    pub fn size(&self) -> usize {
+        self.map.size()
+    }
+
+    /// 木構造を Graphviz の DOT 言語で返します。 [`RadixTrieMap::to_dot()`] を参照してください。
+    pub fn to_dot(&self) -> String {
+        self.map.to_dot()
+    }
+}
+
+/// 木構造を、各ノードを 1 行とする木として書き出します。キーのノードには `*` を付けます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::RadixTrie;
+/// use rust_study::string::trie::TrieMut;
+/// let mut trie = RadixTrie::new();
+/// trie.insert("/users");
+/// trie.insert("/users/admin");
+/// trie.insert("/items");
+/// assert_eq!(".\n└── /\n    ├── items *\n    └── users *\n        └── /admin *\n", trie.to_string());
+/// ```
+impl fmt::Display for RadixTrie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.map, f)
+    }
+}
+
+impl Trie for RadixTrie {
+    fn contains(&self, s: &str) -> bool {
+        self.map.contains_key(s)
+    }
+
+    fn prefix<'a>(&self, s:&'a str) -> &'a str {
+        let len = self.common_prefixes(s).last().map_or(0, |key| key.len());
+        &s[0..len]
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.map.common_prefixes(query).map(|(key, _)| key)
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn count_prefix(&self, prefix: &str) -> usize {
+        self.map.count_prefix(prefix)
+    }
+
+    fn predict(&self, prefix: &str) -> impl Iterator<Item = String> + '_ {
+        self.map.iter_prefix(prefix).map(|(key, _)| key)
+    }
+
+    fn cursor(&self) -> impl TrieCursor<'_> {
+        self.map.cursor()
+    }
+
+    fn fuzzy_search(&self, query: &str, max_edits: usize) -> Vec<(String, usize)> {
+        self.map.fuzzy_search(query, max_edits).into_iter().map(|(key, _, distance)| (key, distance)).collect()
+    }
+
+    fn hamming_search(&self, query: &str, max_mismatches: usize) -> Vec<(String, usize)> {
+        self.map.hamming_search(query, max_mismatches).into_iter().map(|(key, _, mismatches)| (key, mismatches)).collect()
+    }
+
+    fn search_pattern(&self, pattern: &str) -> Vec<String> {
+        self.map.search_pattern(pattern).into_iter().map(|(key, _)| key).collect()
+    }
+
+    fn range<'a, 'b, R: RangeBounds<&'b str> + 'a>(&'a self, range: R) -> impl Iterator<Item = String> + 'a {
+        self.map.range(range).map(|(key, _)| key)
+    }
+}
+
+impl TrieMut for RadixTrie {
+    fn insert(&mut self, s: &str) -> bool {
+        self.map.insert(s, ()).is_none()
+    }
+
+    fn remove(&mut self, s: &str) -> bool {
+        self.map.remove(s).is_some()
+    }
+}
+
+impl Default for RadixTrie {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl <V: SpaceUsage> SpaceUsage for Node<V> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("label", &self.label), ("children", &self.children), ("value", &self.value)]
+    }
+}
+
+impl <V: SpaceUsage> SpaceUsage for RadixTrieMap<V> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("root", &self.root)]
+    }
+}
+
+impl SpaceUsage for RadixTrie {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("map", &self.map)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::trie::NaiveTrieMap;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn dot() {
+        let mut trie = RadixTrie::new();
+        trie.insert("abc");
+        trie.insert("abd");
+        let dot = trie.to_dot();
+        assert!(dot.contains(r#"0 -> 1 [label="ab"]"#));
+        assert!(dot.contains(r#"1 -> 2 [label="c"]"#));
+        assert!(dot.contains(r#"1 -> 3 [label="d"]"#));
+        assert_eq!(2, dot.matches("doublecircle").count());
+    }
+
+    fn random_key(rng: &mut StdRng) -> String {
+        let len = rng.gen_range(0, 6);
+        (0..len).map(|_| ['a', 'b', 'あ', 'い'][rng.gen_range(0, 4)]).collect()
+    }
+
+    #[test]
+    fn same_as_naive_trie_map() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut map = RadixTrieMap::new();
+        let mut naive = NaiveTrieMap::new();
+        for _ in 0..3000 {
+            let key = random_key(&mut rng);
+            match rng.gen_range(0, 3) {
+                0 => assert_eq!(naive.remove(&key), map.remove(&key)),
+                _ => {
+                    let v: u32 = rng.gen_range(0, 1000);
+                    assert_eq!(naive.insert(&key, v), map.insert(&key, v));
+                }
+            }
+            assert_eq!(naive.len(), map.len());
+            assert!(map.size() <= 2 * map.len() + 1);
+            let query = random_key(&mut rng);
+            assert_eq!(naive.get(&query), map.get(&query));
+            assert_eq!(naive.count_prefix(&query), map.count_prefix(&query));
+            assert_eq!(naive.longest_prefix(&query), map.longest_prefix(&query));
+            assert_eq!(naive.common_prefixes(&query).collect::<Vec<_>>(), map.common_prefixes(&query).collect::<Vec<_>>());
+            assert_eq!(naive.iter_prefix(&query).collect::<Vec<_>>(), map.iter_prefix(&query).collect::<Vec<_>>());
+            assert_eq!(naive.fuzzy_search(&query, 1), map.fuzzy_search(&query, 1));
+            assert_eq!(naive.range(..=query.as_str()).collect::<Vec<_>>(), map.range(..=query.as_str()).collect::<Vec<_>>());
+            let (mut naive_cursor, mut cursor) = (naive.cursor(), map.cursor());
+            for c in query.chars() {
+                assert_eq!(naive_cursor.step(c), cursor.step(c));
+                assert_eq!(naive_cursor.value(), cursor.value());
+            }
+            assert_eq!(naive.search_pattern(&("*".to_string() + &query)), map.search_pattern(&("*".to_string() + &query)));
+        }
+        assert_eq!(naive.iter().collect::<Vec<_>>(), map.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn compress_and_merge() {
+        let mut trie = RadixTrie::new();
+        assert!(trie.insert("foobar"));
+        assert_eq!(2, trie.size());
+        assert!(trie.insert("foobaz"));
+        assert_eq!(4, trie.size());
+        assert!(trie.insert("foo"));
+        assert_eq!(5, trie.size());
+        assert!(!trie.insert("foo"));
+        assert_eq!(vec!["foo", "foobar", "foobaz"], trie.predict("fo").collect::<Vec<_>>());
+        assert_eq!(vec!["foobar", "foobaz"], trie.predict("foob").collect::<Vec<_>>());
+        assert_eq!("foo", trie.prefix("foob"));
+
+        assert!(trie.remove("foobaz"));
+        assert_eq!(3, trie.size());
+        assert!(trie.remove("foo"));
+        assert_eq!(2, trie.size());
+        assert!(!trie.remove("foo"));
+        assert!(trie.contains("foobar"));
+        assert!(trie.remove("foobar"));
+        assert_eq!(1, trie.size());
+        assert!(trie.is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+use super::Trie;
+use super::TrieMut;
+use crate::space::SpaceUsage;
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// キーを反転して保持し、接尾辞で検索するトライ木
+///
+/// 内部のトライ木 `T` には文字単位で反転したキーを保持するため、接尾辞による検索が接頭辞による検索になります。
+/// メールアドレスのドメインやファイルの拡張子のように、末尾で分類する文字列の検索に使えます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::*;
+/// let mut trie = ReverseTrie::new(NaiveTrie::new());
+/// trie.insert("alice@example.com");
+/// trie.insert("bob@example.org");
+/// trie.insert("carol@example.com");
+/// assert!(trie.ends_with("@example.com"));
+/// assert!(!trie.ends_with("@example.net"));
+/// assert_eq!(vec!["alice@example.com", "carol@example.com"], trie.keys_with_suffix("example.com"));
+///
+/// let mut domains = ReverseTrie::new(NaiveTrie::new());
+/// domains.insert("example.com");
+/// domains.insert("com");
+/// assert_eq!(vec!["com", "example.com"], domains.suffixes_of("www.example.com"));
+/// ```
+pub struct ReverseTrie<T> {
+    trie: T,
+}
+
+impl <T> ReverseTrie<T> {
+    /// トライ木 `trie` を包みます。
+    ///
+    /// `trie` がすでに保持しているキーは、反転されているものとして扱います。
+    pub fn new(trie: T) -> Self {
+        ReverseTrie { trie }
+    }
+
+    /// 反転したキーを保持する内部のトライ木を参照します。
+    pub fn inner(&self) -> &T {
+        &self.trie
+    }
+
+    /// 反転したキーを保持する内部のトライ木を返します。
+    pub fn into_inner(self) -> T {
+        self.trie
+    }
+
+    /// 文字列 `s` を文字単位で反転します。
+    pub fn reverse(s: &str) -> String {
+        s.chars().rev().collect()
+    }
+}
+
+impl <T: Trie> ReverseTrie<T> {
+    /// キー `s` を保持している場合に、 `true` を返します。
+    pub fn contains(&self, s: &str) -> bool {
+        self.trie.contains(&Self::reverse(s))
+    }
+
+    /// 保持しているキーの個数を返します。
+    pub fn len(&self) -> usize {
+        self.trie.len()
+    }
+
+    /// キーを保持していない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.trie.is_empty()
+    }
+
+    /// `suffix` で終わるキーが存在する場合に、 `true` を返します。
+    pub fn ends_with(&self, suffix: &str) -> bool {
+        self.count_suffix(suffix) > 0
+    }
+
+    /// `suffix` で終わるキーの個数を返します。
+    pub fn count_suffix(&self, suffix: &str) -> usize {
+        self.trie.count_prefix(&Self::reverse(suffix))
+    }
+
+    /// `suffix` で終わるキーを、反転したキーの辞書順にすべて返します。
+    pub fn keys_with_suffix(&self, suffix: &str) -> Vec<String> {
+        self.trie.predict(&Self::reverse(suffix)).map(|key| Self::reverse(&key)).collect()
+    }
+
+    /// `query` の接尾辞となっているキーを、短い順にすべて返します。
+    pub fn suffixes_of<'a>(&self, query: &'a str) -> Vec<&'a str> {
+        let reversed = Self::reverse(query);
+        self.trie.common_prefixes(&reversed).map(|key| &query[query.len() - key.len()..]).collect()
+    }
+
+    /// `query` の接尾辞となっているキーのうち、最長のものを返します。
+    ///
+    /// 該当するキーが存在しない場合、空文字列を返します。
+    pub fn longest_suffix<'a>(&self, query: &'a str) -> &'a str {
+        let reversed = Self::reverse(query);
+        let len = self.trie.prefix(&reversed).len();
+        &query[query.len() - len..]
+    }
+
+    /// 保持しているキーを、反転したキーの辞書順に返すイテレータを返します。
+    pub fn keys(&self) -> impl Iterator<Item = String> + '_ {
+        self.trie.keys().map(|key| Self::reverse(&key))
+    }
+}
+
+impl <T: TrieMut> ReverseTrie<T> {
+    /// キー `s` を追加します。新たに追加した場合に、 `true` を返します。
+    pub fn insert(&mut self, s: &str) -> bool {
+        self.trie.insert(&Self::reverse(s))
+    }
+
+    /// キー `s` を取り除きます。キーが存在した場合に、 `true` を返します。
+    pub fn remove(&mut self, s: &str) -> bool {
+        self.trie.remove(&Self::reverse(s))
+    }
+}
+
+impl <T: Default> Default for ReverseTrie<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl <T: TrieMut + Default, S: AsRef<str>> FromIterator<S> for ReverseTrie<T> {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut trie = Self::default();
+        for key in iter {
+            trie.insert(key.as_ref());
+        }
+        trie
+    }
+}
+
+impl <T: SpaceUsage> SpaceUsage for ReverseTrie<T> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("trie", &self.trie)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::trie::ByteTrie;
+    use crate::string::trie::Dawg;
+    use crate::string::trie::conformance;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    #[test]
+    fn same_as_ends_with() {
+        let key = || conformance::key_of(&['a', 'b', '.', 'あ'], 5);
+        proptest!(|(keys in vec(key(), 0..200), queries in vec(key(), 1..20))| {
+            let trie: ReverseTrie<ByteTrie> = keys.iter().collect();
+            for query in queries {
+                prop_assert_eq!(keys.contains(&query), trie.contains(&query));
+                let mut expected: Vec<String> = keys.iter().filter(|key| key.ends_with(&query)).cloned().collect();
+                expected.sort_by_key(|key| ReverseTrie::<ByteTrie>::reverse(key));
+                expected.dedup();
+                prop_assert_eq!(!expected.is_empty(), trie.ends_with(&query));
+                prop_assert_eq!(expected.len(), trie.count_suffix(&query));
+                prop_assert_eq!(expected, trie.keys_with_suffix(&query));
+
+                let mut suffixes: Vec<&str> = (0..=query.len()).rev()
+                    .filter(|i| query.is_char_boundary(*i))
+                    .map(|i| &query[i..])
+                    .filter(|suffix| trie.contains(suffix))
+                    .collect();
+                prop_assert_eq!(&suffixes, &trie.suffixes_of(&query));
+                prop_assert_eq!(suffixes.pop().unwrap_or(""), trie.longest_suffix(&query));
+            }
+        });
+    }
+
+    #[test]
+    fn static_trie() {
+        let keys: Vec<String> = ["index.html", "style.css", "app.js", "vendor.js"].iter()
+            .map(|key| ReverseTrie::<Dawg>::reverse(key))
+            .collect();
+        let trie = ReverseTrie::new(Dawg::new(&keys));
+        assert_eq!(vec!["app.js", "vendor.js"], trie.keys_with_suffix(".js"));
+        assert!(!trie.ends_with(".png"));
+        assert_eq!("app.js", trie.longest_suffix("my-app.js"));
+    }
+}
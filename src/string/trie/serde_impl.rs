@@ -0,0 +1,272 @@
+// Serialization of the tries as a sequence of keys, or a map from keys to values.
+// The parameters of the construction are serialized with the keys, in a tuple.
+
+use super::children::ChildStorage;
+use super::critbit_tree::CritbitTree;
+use super::normalized::Normalized;
+use super::normalized::Normalizer;
+use super::prefix_sum_trie_map::PrefixSumTrieMap;
+use super::reverse_trie::ReverseTrie;
+use super::weighted_trie::WeightedTrie;
+use super::BurstTrie;
+use super::ByteTrie;
+use super::Dawg;
+use super::LoudsTrie;
+use super::NaiveTrie;
+use super::NaiveTrieMap;
+use super::NestedPatriciaTrie;
+use super::OnlineDawg;
+use super::RadixTrie;
+use super::RadixTrieMap;
+use super::TernarySearchTree;
+use super::Trie;
+use super::TrieMap;
+use super::TrieMut;
+use crate::bits::fid::FID;
+use crate::collections::arena::NodeStorage;
+
+use serde::de;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+use alloc::collections::BTreeMap;
+use core::ops::Add;
+use core::ops::Sub;
+
+macro_rules! impl_serde_for_trie_mut {
+    ($($t:ty),*) => {$(
+        impl Serialize for $t {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.keys())
+            }
+        }
+
+        impl <'de> Deserialize<'de> for $t {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let keys = Vec::<String>::deserialize(deserializer)?;
+                let mut trie = <$t>::default();
+                for key in &keys {
+                    trie.insert(key);
+                }
+                Ok(trie)
+            }
+        }
+    )*};
+}
+
+impl_serde_for_trie_mut!(ByteTrie, RadixTrie, TernarySearchTree);
+
+impl <S: ChildStorage, A: NodeStorage> Serialize for NaiveTrie<S, A> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serializer.collect_seq(self.keys())
+    }
+}
+
+impl <'de, S: ChildStorage, A: NodeStorage> Deserialize<'de> for NaiveTrie<S, A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let keys = Vec::<String>::deserialize(deserializer)?;
+        Ok(keys.iter().collect())
+    }
+}
+
+// the threshold of the size of the buckets, and the keys
+impl Serialize for BurstTrie {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.threshold(), self.keys().collect::<Vec<_>>()).serialize(serializer)
+    }
+}
+
+impl <'de> Deserialize<'de> for BurstTrie {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (threshold, keys) = <(usize, Vec<String>)>::deserialize(deserializer)?;
+        if threshold == 0 {
+            return Err(de::Error::custom("the threshold should be positive"));
+        }
+        let mut trie = BurstTrie::with_threshold(threshold);
+        for key in &keys {
+            trie.insert(key);
+        }
+        Ok(trie)
+    }
+}
+
+// the threshold of the pending keys, and the keys
+impl Serialize for OnlineDawg {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.threshold(), self.keys().collect::<Vec<_>>()).serialize(serializer)
+    }
+}
+
+impl <'de> Deserialize<'de> for OnlineDawg {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (threshold, keys) = <(usize, Vec<String>)>::deserialize(deserializer)?;
+        if threshold == 0 {
+            return Err(de::Error::custom("the threshold should be positive"));
+        }
+        let mut dawg = OnlineDawg::with_threshold(threshold);
+        for key in &keys {
+            dawg.insert(key);
+        }
+        dawg.minimize();
+        Ok(dawg)
+    }
+}
+
+// the number of the levels, and the keys
+impl Serialize for NestedPatriciaTrie {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.levels(), self.keys().collect::<Vec<_>>()).serialize(serializer)
+    }
+}
+
+impl <'de> Deserialize<'de> for NestedPatriciaTrie {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (levels, keys) = <(usize, Vec<String>)>::deserialize(deserializer)?;
+        if levels == 0 {
+            return Err(de::Error::custom("the number of the levels should be positive"));
+        }
+        Ok(NestedPatriciaTrie::with_levels(&keys, levels))
+    }
+}
+
+// the keys as they are stored, reversed
+impl <T: Serialize> Serialize for ReverseTrie<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.inner().serialize(serializer)
+    }
+}
+
+impl <'de, T: Deserialize<'de>> Deserialize<'de> for ReverseTrie<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(ReverseTrie::new(T::deserialize(deserializer)?))
+    }
+}
+
+// the normalized keys, with the normalizer restored by `Default`
+impl <T: Serialize, N: Normalizer> Serialize for Normalized<T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.inner().serialize(serializer)
+    }
+}
+
+impl <'de, T: Deserialize<'de>, N: Normalizer + Default> Deserialize<'de> for Normalized<T, N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Normalized::new(T::deserialize(deserializer)?, N::default()))
+    }
+}
+
+impl <T: FID> Serialize for LoudsTrie<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.keys())
+    }
+}
+
+impl <'de, T: FID> Deserialize<'de> for LoudsTrie<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let keys = Vec::<String>::deserialize(deserializer)?;
+        Ok(LoudsTrie::new(&keys))
+    }
+}
+
+impl Serialize for Dawg {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.keys())
+    }
+}
+
+impl <'de> Deserialize<'de> for Dawg {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let keys = Vec::<String>::deserialize(deserializer)?;
+        Ok(Dawg::new(&keys))
+    }
+}
+
+macro_rules! impl_serde_for_trie_map {
+    ($($t:ident),*) => {$(
+        impl <V: Serialize> Serialize for $t<V> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_map(self.iter())
+            }
+        }
+
+        impl <'de, V: Deserialize<'de>> Deserialize<'de> for $t<V> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let entries = BTreeMap::<String, V>::deserialize(deserializer)?;
+                let mut map = $t::new();
+                for (key, v) in entries {
+                    map.insert(&key, v);
+                }
+                Ok(map)
+            }
+        }
+    )*};
+}
+
+impl_serde_for_trie_map!(RadixTrieMap);
+
+impl <V: Serialize, S: ChildStorage, A: NodeStorage> Serialize for NaiveTrieMap<V, S, A> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl <'de, V: Deserialize<'de>, S: ChildStorage, A: NodeStorage> Deserialize<'de> for NaiveTrieMap<V, S, A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = BTreeMap::<String, V>::deserialize(deserializer)?;
+        let mut map = NaiveTrieMap::default();
+        for (key, v) in entries {
+            map.insert(&key, v);
+        }
+        Ok(map)
+    }
+}
+
+impl <V: Copy + Default + Add<Output = V> + Sub<Output = V> + Serialize> Serialize for PrefixSumTrieMap<V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl <'de, V: Copy + Default + Add<Output = V> + Sub<Output = V> + Deserialize<'de>> Deserialize<'de> for PrefixSumTrieMap<V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = BTreeMap::<String, V>::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
+// the map from the keys to the weights
+impl <W: Ord + Copy + Serialize> Serialize for WeightedTrie<W> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.keys().map(|key| {
+            let weight = self.weight(&key).expect("the weight of a key");
+            (key, weight)
+        }))
+    }
+}
+
+impl <'de, W: Ord + Copy + Deserialize<'de>> Deserialize<'de> for WeightedTrie<W> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = BTreeMap::<String, W>::deserialize(deserializer)?;
+        let mut trie = WeightedTrie::new();
+        for (key, weight) in entries {
+            trie.insert_with_weight(&key, weight);
+        }
+        Ok(trie)
+    }
+}
+
+// the pairs of the key and the value, as the keys are not strings
+impl <V: Serialize> Serialize for CritbitTree<V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl <'de, V: Deserialize<'de>> Deserialize<'de> for CritbitTree<V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = Vec::<(Vec<u8>, V)>::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
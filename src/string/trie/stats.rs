@@ -0,0 +1,267 @@
+use super::NaiveTrieMap;
+use crate::space::SpaceUsage;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+
+/// キーの集合を文字単位のトライ木で表したときの統計
+///
+/// ノードの深さや子の個数の分布と、いくつかの表現での使用メモリの見積もりを返します。
+/// 辞書をどのトライ木で保持するかを決める目安に使えます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::NaiveTrie;
+/// use rust_study::string::trie::Trie;
+/// let trie: NaiveTrie = ["to", "tea", "ten", "i", "in", "inn"].iter().collect();
+/// let stats = trie.stats();
+/// assert_eq!(trie.size(), stats.nodes());
+/// assert_eq!(&[1, 2, 3, 3], stats.nodes_by_depth());
+/// // four leaves, "i" and "in" have one child, and the root, "t" and "te" have two
+/// assert_eq!(&[4, 2, 3], stats.branching());
+/// assert_eq!(3, stats.max_depth());
+/// assert_eq!(1.6, stats.average_branching());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrieStats {
+    len: usize,
+    total_chars: usize,
+    total_bytes: usize,
+    nodes_by_depth: Vec<usize>,
+    branching: Vec<usize>,
+    byte_nodes: usize,
+}
+
+impl TrieStats {
+    /// 辞書順にソートされたキーの列 `keys` の統計を求めます。重複したキーは 1 つとして数えます。
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keys` are not sorted.
+    pub fn from_sorted_keys<I: IntoIterator<Item = S>, S: AsRef<str>>(keys: I) -> Self {
+        let mut stats = TrieStats {
+            len: 0,
+            total_chars: 0,
+            total_bytes: 0,
+            nodes_by_depth: vec![1],
+            branching: vec![],
+            byte_nodes: 1,
+        };
+        let mut last = String::new();
+        // the number of children of each node on the path to the last key
+        let mut path = vec![0];
+        for key in keys {
+            let key = key.as_ref();
+            if stats.len > 0 {
+                assert!(last.as_str() <= key, "keys are not sorted");
+                if last == key {
+                    continue;
+                }
+            }
+            let lcp = last.chars().zip(key.chars()).take_while(|(a, b)| a == b).count();
+            let lcp_bytes = last.bytes().zip(key.bytes()).take_while(|(a, b)| a == b).count();
+            while path.len() > lcp + 1 {
+                stats.close(path.pop().unwrap());
+            }
+            let depth = key.chars().count();
+            if depth > lcp {
+                path[lcp] += 1;
+            }
+            for d in lcp + 1..=depth {
+                if stats.nodes_by_depth.len() <= d {
+                    stats.nodes_by_depth.push(0);
+                }
+                stats.nodes_by_depth[d] += 1;
+                path.push(if d < depth { 1 } else { 0 });
+            }
+            stats.len += 1;
+            stats.total_chars += depth;
+            stats.total_bytes += key.len();
+            stats.byte_nodes += key.len() - lcp_bytes;
+            last.clear();
+            last.push_str(key);
+        }
+        while let Some(children) = path.pop() {
+            stats.close(children);
+        }
+        stats
+    }
+
+    /// 子を `children` 個持つノードを数えます。
+    fn close(&mut self, children: usize) {
+        if self.branching.len() <= children {
+            self.branching.resize(children + 1, 0);
+        }
+        self.branching[children] += 1;
+    }
+
+    /// キーの個数を返します。
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// キーが存在しない場合に、 `true` を返します。
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 根を含むノードの個数を返します。
+    pub fn nodes(&self) -> usize {
+        self.nodes_by_depth.iter().sum()
+    }
+
+    /// 深さ (根からの文字数) ごとのノードの個数を返します。
+    pub fn nodes_by_depth(&self) -> &[usize] {
+        &self.nodes_by_depth
+    }
+
+    /// 子の個数ごとのノードの個数を返します。
+    ///
+    /// `branching()[k]` は子を `k` 個持つノードの個数で、 `branching()[0]` は葉の個数です。
+    pub fn branching(&self) -> &[usize] {
+        &self.branching
+    }
+
+    /// 最も深いノードの深さ、すなわち最長のキーの文字数を返します。
+    pub fn max_depth(&self) -> usize {
+        self.nodes_by_depth.len() - 1
+    }
+
+    /// キーを終端とするノードの深さ、すなわちキーの文字数の平均を返します。
+    ///
+    /// キーが存在しない場合、 `0.0` を返します。
+    pub fn average_depth(&self) -> f64 {
+        if self.len == 0 {
+            0.0
+        } else {
+            self.total_chars as f64 / self.len as f64
+        }
+    }
+
+    /// 葉以外のノードの子の個数の平均を返します。
+    ///
+    /// 1 に近いほど分岐のない長い経路が多く、 [`RadixTrie`](super::RadixTrie) などの経路を圧縮する表現が有効です。
+    /// 葉以外のノードが存在しない場合、 `0.0` を返します。
+    pub fn average_branching(&self) -> f64 {
+        let inner = self.nodes() - self.branching[0];
+        if inner == 0 {
+            0.0
+        } else {
+            (self.nodes() - 1) as f64 / inner as f64
+        }
+    }
+
+    /// UTF-8 のバイト単位のトライ木で表したときの、根を含むノードの個数を返します。
+    ///
+    /// [`ByteTrie`](super::ByteTrie) や [`LoudsTrie`](super::LoudsTrie) のノードの個数と一致します。
+    pub fn byte_nodes(&self) -> usize {
+        self.byte_nodes
+    }
+
+    /// キーを [`String`] の列として保持したときの使用メモリを、バイト単位で見積もります。
+    pub fn keys_bytes(&self) -> usize {
+        self.total_bytes + self.len * mem::size_of::<String>()
+    }
+
+    /// [`NaiveTrie`](super::NaiveTrie) で保持したときの使用メモリを、バイト単位で見積もります。
+    ///
+    /// ノードごとに、ノードそのものと親からの辺 (文字とポインタ) の領域を数えます。
+    pub fn naive_trie_bytes(&self) -> usize {
+        let node = mem::size_of::<NaiveTrieMap<()>>();
+        let edge = mem::size_of::<(char, Box<NaiveTrieMap<()>>)>();
+        self.nodes() * node + (self.nodes() - 1) * edge
+    }
+
+    /// [`NaiveLoudsTrie`](super::NaiveLoudsTrie) で保持したときの使用メモリを、バイト単位で見積もります。
+    ///
+    /// LOUDS のビット列と終端を表すビット列、辺のラベルを数え、 rank/select の索引は含みません。
+    pub fn louds_trie_bytes(&self) -> usize {
+        let bits = (2 * self.byte_nodes + 1) + self.byte_nodes;
+        bits.div_ceil(8) + self.byte_nodes
+    }
+}
+
+impl SpaceUsage for TrieStats {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("nodes_by_depth", &self.nodes_by_depth), ("branching", &self.branching)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::trie::ByteTrie;
+    use crate::string::trie::NaiveLoudsTrie;
+    use crate::string::trie::NaiveTrie;
+    use crate::string::trie::Trie;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn same_as_tries() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let random_key = |rng: &mut StdRng| -> String {
+            let len = rng.gen_range(0, 6);
+            (0..len).map(|_| ['a', 'b', 'あ', 'い'][rng.gen_range(0, 4)]).collect()
+        };
+        for num_keys in [0, 1, 10, 300] {
+            let keys: Vec<String> = (0..num_keys).map(|_| random_key(&mut rng)).collect();
+            let trie: NaiveTrie = keys.iter().collect();
+            let stats = trie.stats();
+            assert_eq!(trie.len(), stats.len());
+            assert_eq!(trie.size(), stats.nodes());
+            assert_eq!(stats.nodes(), stats.branching().iter().sum::<usize>());
+            // every node except the root has a parent
+            let edges: usize = stats.branching().iter().enumerate().map(|(k, n)| k * n).sum();
+            assert_eq!(stats.nodes() - 1, edges);
+            let mut byte_trie = ByteTrie::new();
+            keys.iter().for_each(|key| { byte_trie.append(key); });
+            assert_eq!(byte_trie.size(), stats.byte_nodes());
+            assert_eq!(NaiveLoudsTrie::new(&keys).size(), stats.byte_nodes());
+
+            let max_depth = trie.keys().map(|key| key.chars().count()).max().unwrap_or(0);
+            assert_eq!(max_depth, stats.max_depth());
+            for (depth, &nodes) in stats.nodes_by_depth().iter().enumerate() {
+                let mut prefixes: Vec<String> = trie.keys()
+                    .filter(|key| key.chars().count() >= depth)
+                    .map(|key| key.chars().take(depth).collect())
+                    .collect();
+                prefixes.dedup();
+                assert_eq!(prefixes.len().max(if depth == 0 { 1 } else { 0 }), nodes);
+            }
+        }
+    }
+
+    #[test]
+    fn from_sorted_keys() {
+        let stats = TrieStats::from_sorted_keys(["", "a", "a", "ab", "b"]);
+        assert_eq!(4, stats.len());
+        assert_eq!(&[1, 2, 1], stats.nodes_by_depth());
+        assert_eq!(&[2, 1, 1], stats.branching());
+        assert_eq!(1.0, stats.average_depth());
+        assert_eq!(1.5, stats.average_branching());
+        assert_eq!(4, stats.byte_nodes());
+        assert_eq!(4 + 4 * mem::size_of::<String>(), stats.keys_bytes());
+        // 9 bits of LOUDS, 4 bits of terminals and 4 labels
+        assert_eq!(6, stats.louds_trie_bytes());
+        assert!(stats.louds_trie_bytes() < stats.naive_trie_bytes());
+
+        let empty = TrieStats::from_sorted_keys(Vec::<String>::new());
+        assert!(empty.is_empty());
+        assert_eq!(1, empty.nodes());
+        assert_eq!(0, empty.max_depth());
+        assert_eq!(0.0, empty.average_depth());
+        assert_eq!(0.0, empty.average_branching());
+    }
+
+    #[test]
+    #[should_panic(expected = "keys are not sorted")]
+    fn unsorted() {
+        TrieStats::from_sorted_keys(["b", "a"]);
+    }
+}
@@ -0,0 +1,428 @@
+use super::automaton::Automaton;
+use super::automaton::Hamming;
+use super::automaton::KeyRange;
+use super::automaton::Levenshtein;
+use super::automaton::Wildcard;
+use super::Trie;
+use super::TrieCursor;
+use super::TrieMut;
+use crate::space::SpaceUsage;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::RangeBounds;
+
+struct Node {
+    c: char,
+    lo: Option<Box<Node>>,
+    eq: Option<Box<Node>>,
+    hi: Option<Box<Node>>,
+    is_leaf: bool,
+    // the number of keys through the node
+    count: usize,
+}
+
+impl Node {
+    fn new(c: char) -> Self {
+        Node { c, lo: None, eq: None, hi: None, is_leaf: false, count: 0 }
+    }
+
+    fn size(&self) -> usize {
+        [&self.lo, &self.eq, &self.hi].iter()
+            .map(|link| link.as_ref().map_or(0, |node| node.size()))
+            .sum::<usize>() + 1
+    }
+}
+
+enum Visit<'a> {
+    Node(&'a Node, String),
+    Key(String),
+}
+
+// a node to visit, or a node whose char is to be pushed after its lo subtree is searched
+enum Search<'a> {
+    Node(&'a Node),
+    Char(&'a Node),
+}
+
+/// 3 分探索木
+///
+/// 各ノードが 1 文字と 3 つの子 (その文字より小さい・等しい・大きい) を持つトライ木です。
+/// 同じ深さの文字を二分探索木で並べるため、各ノードは子の数によらず固定の大きさで、
+/// 文字の種類が多い場合でも [`super::NaiveTrie`] のように子ごとの連想配列を持つ必要がありません。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::TernarySearchTree;
+/// use rust_study::string::trie::Trie;
+/// use rust_study::string::trie::TrieMut;
+/// let mut tst = TernarySearchTree::new();
+/// assert!(tst.insert("cute"));
+/// assert!(tst.insert("cup"));
+/// assert!(tst.insert("at"));
+/// assert!(!tst.insert("cup"));
+/// assert!(tst.contains("cup"));
+/// assert!(!tst.contains("cu"));
+/// assert_eq!(vec!["cup", "cute"], tst.predict("cu").collect::<Vec<_>>());
+/// assert!(tst.remove("cute"));
+/// assert_eq!(2, tst.len());
+/// ```
+pub struct TernarySearchTree {
+    root: Option<Box<Node>>,
+    // whether the empty key is stored
+    has_empty: bool,
+    len: usize,
+}
+
+impl TernarySearchTree {
+    /// 空の木を構築します。
+    pub fn new() -> Self {
+        TernarySearchTree { root: None, has_empty: false, len: 0 }
+    }
+
+    /// ノードの個数を返します。
+    pub fn size(&self) -> usize {
+        self.root.as_ref().map_or(0, |node| node.size())
+    }
+
+    // the node of the last char of a non-empty string s
+    fn find(&self, s: &str) -> Option<&Node> {
+        let mut link = &self.root;
+        let mut found = None;
+        for c in s.chars() {
+            let node = find_in_level(link.as_deref()?, c)?;
+            link = &node.eq;
+            found = Some(node);
+        }
+        found
+    }
+
+    // runs the automaton along the keys, pruning the subtrees it rejects
+    fn search_by<'a, A: Automaton + 'a>(&'a self, mut automaton: A) -> impl Iterator<Item = (String, A::Output)> + 'a {
+        let mut empty = self.has_empty;
+        // (the visit, the key before the node, the number of chars of the key)
+        let mut stack: Vec<(Search, String, usize)> = self.root.iter().map(|root| (Search::Node(root), String::new(), 0)).collect();
+        core::iter::from_fn(move || {
+            if core::mem::take(&mut empty) {
+                if let Some(output) = automaton.accept() {
+                    return Some((String::new(), output));
+                }
+            }
+            while let Some((visit, key, depth)) = stack.pop() {
+                let node = match visit {
+                    Search::Node(node) => {
+                        // in-order: lo, the node itself and eq, hi
+                        if let Some(hi) = &node.hi {
+                            stack.push((Search::Node(hi), key.clone(), depth));
+                        }
+                        stack.push((Search::Char(node), key.clone(), depth));
+                        if let Some(lo) = &node.lo {
+                            stack.push((Search::Node(lo), key, depth));
+                        }
+                        continue;
+                    }
+                    Search::Char(node) => node,
+                };
+                automaton.truncate(depth);
+                if !automaton.push(node.c) {
+                    continue;
+                }
+                let mut next_key = key;
+                next_key.push(node.c);
+                // visited next, while the state of the node is on the top
+                if let Some(eq) = &node.eq {
+                    stack.push((Search::Node(eq), next_key.clone(), depth + 1));
+                }
+                if node.is_leaf {
+                    if let Some(output) = automaton.accept() {
+                        return Some((next_key, output));
+                    }
+                }
+            }
+            None
+        })
+    }
+}
+
+// finds the node of c among the nodes in the same level
+fn find_in_level(mut node: &Node, c: char) -> Option<&Node> {
+    loop {
+        node = match c.cmp(&node.c) {
+            Ordering::Less => node.lo.as_deref()?,
+            Ordering::Greater => node.hi.as_deref()?,
+            Ordering::Equal => return Some(node),
+        };
+    }
+}
+
+// returns whether the key is added
+fn insert(link: &mut Option<Box<Node>>, key: &[char]) -> bool {
+    let node = link.get_or_insert_with(|| Box::new(Node::new(key[0])));
+    match key[0].cmp(&node.c) {
+        Ordering::Less => insert(&mut node.lo, key),
+        Ordering::Greater => insert(&mut node.hi, key),
+        Ordering::Equal => {
+            let added = if key.len() == 1 {
+                !core::mem::replace(&mut node.is_leaf, true)
+            } else {
+                insert(&mut node.eq, &key[1..])
+            };
+            if added {
+                node.count += 1;
+            }
+            added
+        }
+    }
+}
+
+// returns whether the key is removed
+fn remove(link: &mut Option<Box<Node>>, key: &[char]) -> bool {
+    let node = match link {
+        None => return false,
+        Some(node) => node,
+    };
+    match key[0].cmp(&node.c) {
+        Ordering::Less => remove(&mut node.lo, key),
+        Ordering::Greater => remove(&mut node.hi, key),
+        Ordering::Equal => {
+            let removed = if key.len() == 1 {
+                core::mem::replace(&mut node.is_leaf, false)
+            } else {
+                remove(&mut node.eq, &key[1..])
+            };
+            if removed {
+                node.count -= 1;
+                if node.count == 0 {
+                    let (lo, hi) = (node.lo.take(), node.hi.take());
+                    *link = join(lo, hi);
+                }
+            }
+            removed
+        }
+    }
+}
+
+// joins two trees of the same level, all chars in lo being less than those in hi
+fn join(lo: Option<Box<Node>>, hi: Option<Box<Node>>) -> Option<Box<Node>> {
+    let mut lo = match lo {
+        None => return hi,
+        Some(lo) => lo,
+    };
+    let mut rightmost = &mut lo;
+    while rightmost.hi.is_some() {
+        rightmost = rightmost.hi.as_mut().unwrap();
+    }
+    rightmost.hi = hi;
+    Some(lo)
+}
+
+impl Trie for TernarySearchTree {
+    fn contains(&self, s: &str) -> bool {
+        if s.is_empty() {
+            return self.has_empty;
+        }
+        self.find(s).is_some_and(|node| node.is_leaf)
+    }
+
+    fn prefix<'a>(&self, s:&'a str) -> &'a str {
+        let len = self.common_prefixes(s).last().map_or(0, |key| key.len());
+        &s[0..len]
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let mut empty = self.has_empty;
+        let mut level = self.root.as_deref();
+        let mut chars = query.char_indices();
+        core::iter::from_fn(move || {
+            if core::mem::take(&mut empty) {
+                return Some("");
+            }
+            loop {
+                let (i, c) = chars.next()?;
+                let node = find_in_level(level?, c)?;
+                level = node.eq.as_deref();
+                if node.is_leaf {
+                    return Some(&query[0..i + c.len_utf8()]);
+                }
+            }
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn count_prefix(&self, prefix: &str) -> usize {
+        if prefix.is_empty() {
+            return self.len();
+        }
+        self.find(prefix).map_or(0, |node| node.count)
+    }
+
+    fn predict(&self, prefix: &str) -> impl Iterator<Item = String> + '_ {
+        let mut stack = vec![];
+        if prefix.is_empty() {
+            if let Some(root) = &self.root {
+                stack.push(Visit::Node(root, String::new()));
+            }
+            if self.has_empty {
+                stack.push(Visit::Key(String::new()));
+            }
+        } else if let Some(node) = self.find(prefix) {
+            if let Some(eq) = &node.eq {
+                stack.push(Visit::Node(eq, prefix.to_string()));
+            }
+            if node.is_leaf {
+                stack.push(Visit::Key(prefix.to_string()));
+            }
+        }
+        core::iter::from_fn(move || {
+            while let Some(visit) = stack.pop() {
+                let (node, key) = match visit {
+                    Visit::Key(key) => return Some(key),
+                    Visit::Node(node, key) => (node, key),
+                };
+                // in-order: lo, the node itself, eq, hi
+                if let Some(hi) = &node.hi {
+                    stack.push(Visit::Node(hi, key.clone()));
+                }
+                let mut next_key = key.clone();
+                next_key.push(node.c);
+                if let Some(eq) = &node.eq {
+                    stack.push(Visit::Node(eq, next_key.clone()));
+                }
+                if node.is_leaf {
+                    stack.push(Visit::Key(next_key));
+                }
+                if let Some(lo) = &node.lo {
+                    stack.push(Visit::Node(lo, key));
+                }
+            }
+            None
+        })
+    }
+
+    fn cursor(&self) -> impl TrieCursor<'_> {
+        Cursor { level: self.root.as_deref(), is_leaf: self.has_empty }
+    }
+
+    fn fuzzy_search(&self, query: &str, max_edits: usize) -> Vec<(String, usize)> {
+        self.search_by(Levenshtein::new(query, max_edits)).collect()
+    }
+
+    fn hamming_search(&self, query: &str, max_mismatches: usize) -> Vec<(String, usize)> {
+        self.search_by(Hamming::new(query, max_mismatches)).collect()
+    }
+
+    fn search_pattern(&self, pattern: &str) -> Vec<String> {
+        self.search_by(Wildcard::new(pattern)).map(|(key, _)| key).collect()
+    }
+
+    fn range<'a, 'b, R: RangeBounds<&'b str> + 'a>(&'a self, range: R) -> impl Iterator<Item = String> + 'a {
+        self.search_by(KeyRange::new(range)).map(|(key, _)| key)
+    }
+}
+
+#[derive(Clone)]
+struct Cursor<'a> {
+    // the nodes of the next char
+    level: Option<&'a Node>,
+    is_leaf: bool,
+}
+
+impl <'a> TrieCursor<'a> for Cursor<'a> {
+    type Value = ();
+
+    fn step(&mut self, c: char) -> bool {
+        match self.level.and_then(|level| find_in_level(level, c)) {
+            Some(node) => {
+                self.level = node.eq.as_deref();
+                self.is_leaf = node.is_leaf;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn value(&self) -> Option<&'a ()> {
+        if self.is_leaf {
+            Some(&())
+        } else {
+            None
+        }
+    }
+}
+
+impl TrieMut for TernarySearchTree {
+    fn insert(&mut self, s: &str) -> bool {
+        let key: Vec<char> = s.chars().collect();
+        let added = if key.is_empty() {
+            !core::mem::replace(&mut self.has_empty, true)
+        } else {
+            insert(&mut self.root, &key)
+        };
+        if added {
+            self.len += 1;
+        }
+        added
+    }
+
+    fn remove(&mut self, s: &str) -> bool {
+        let key: Vec<char> = s.chars().collect();
+        let removed = if key.is_empty() {
+            core::mem::replace(&mut self.has_empty, false)
+        } else {
+            remove(&mut self.root, &key)
+        };
+        if removed {
+            self.len -= 1;
+        }
+        removed
+    }
+}
+
+impl Default for TernarySearchTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpaceUsage for Node {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("lo", &self.lo), ("eq", &self.eq), ("hi", &self.hi)]
+    }
+}
+
+impl SpaceUsage for TernarySearchTree {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("root", &self.root)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_prunes_nodes() {
+        let mut tst = TernarySearchTree::new();
+        for key in ["m", "c", "x", "a", "d", "mo", "ma"] {
+            tst.insert(key);
+        }
+        assert_eq!(7, tst.size());
+        assert!(tst.remove("c"));
+        assert_eq!(6, tst.size());
+        assert_eq!(vec!["a", "d", "m", "ma", "mo", "x"], tst.keys().collect::<Vec<_>>());
+        assert!(tst.remove("m"));
+        assert_eq!(6, tst.size());
+        assert!(tst.remove("ma"));
+        assert!(tst.remove("mo"));
+        assert_eq!(3, tst.size());
+        assert_eq!(vec!["a", "d", "x"], tst.keys().collect::<Vec<_>>());
+    }
+}
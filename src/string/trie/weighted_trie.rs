@@ -0,0 +1,352 @@
+use super::Trie;
+use super::TrieCursor;
+
+use crate::collections::heap::Heap;
+use crate::space::SpaceUsage;
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+struct Node<W> {
+    // sorted by the char
+    children: Vec<(char, Node<W>)>,
+    weight: Option<W>,
+    // the maximum weight in the subtree
+    max: Option<W>,
+    // the number of keys in the subtree
+    count: usize,
+}
+
+impl <W: Ord + Copy> Node<W> {
+    fn new() -> Self {
+        Node { children: vec![], weight: None, max: None, count: 0 }
+    }
+
+    fn child(&self, c: char) -> Option<&Node<W>> {
+        let i = self.children.binary_search_by_key(&c, |(label, _)| *label).ok()?;
+        Some(&self.children[i].1)
+    }
+
+    fn update_max(&mut self) {
+        self.max = self.children.iter().filter_map(|(_, child)| child.max).chain(self.weight).max();
+    }
+
+    // returns the old weight
+    fn insert(&mut self, mut chars: core::str::Chars, weight: W) -> Option<W> {
+        let old = match chars.next() {
+            None => self.weight.replace(weight),
+            Some(c) => {
+                let i = match self.children.binary_search_by_key(&c, |(label, _)| *label) {
+                    Ok(i) => i,
+                    Err(i) => {
+                        self.children.insert(i, (c, Node::new()));
+                        i
+                    }
+                };
+                self.children[i].1.insert(chars, weight)
+            }
+        };
+        if old.is_none() {
+            self.count += 1;
+        }
+        self.update_max();
+        old
+    }
+
+    // returns the removed weight
+    fn remove(&mut self, mut chars: core::str::Chars) -> Option<W> {
+        let removed = match chars.next() {
+            None => self.weight.take(),
+            Some(c) => {
+                let i = self.children.binary_search_by_key(&c, |(label, _)| *label).ok()?;
+                let removed = self.children[i].1.remove(chars);
+                if self.children[i].1.count == 0 {
+                    self.children.remove(i);
+                }
+                removed
+            }
+        };
+        if removed.is_some() {
+            self.count -= 1;
+            self.update_max();
+        }
+        removed
+    }
+}
+
+/// キーごとに重みを持ち、接頭辞に続く重みの大きいキーを取り出せるトライ木
+///
+/// 各ノードに部分木の中の重みの最大値を持たせ、 [`Self::top_completions()`] では
+/// 最大値の大きい部分木から順にヒープで展開します。
+/// 入力途中の文字列から人気の高い候補を提示する、検索語の補完などに使えます。
+///
+/// # Examples
+///
+/// ```
+/// use rust_study::string::trie::Trie;
+/// use rust_study::string::trie::WeightedTrie;
+/// let mut trie = WeightedTrie::new();
+/// trie.insert_with_weight("rust", 100);
+/// trie.insert_with_weight("ruby", 80);
+/// trie.insert_with_weight("rush", 30);
+/// trie.insert_with_weight("python", 120);
+/// assert_eq!(vec![("rust".to_string(), 100), ("ruby".to_string(), 80)], trie.top_completions("ru", 2));
+///
+/// assert_eq!(Some(100), trie.insert_with_weight("rust", 10));
+/// assert_eq!(vec![("ruby".to_string(), 80), ("rush".to_string(), 30)], trie.top_completions("ru", 2));
+/// assert_eq!(4, trie.len());
+/// ```
+pub struct WeightedTrie<W> {
+    root: Node<W>,
+}
+
+impl <W: Ord + Copy> WeightedTrie<W> {
+    /// 空のトライ木を構築します。
+    pub fn new() -> Self {
+        WeightedTrie { root: Node::new() }
+    }
+
+    /// キー `key` を重み `weight` で追加します。
+    ///
+    /// すでにキーが存在した場合、重みを置き換えて古い重みを返します。
+    pub fn insert_with_weight(&mut self, key: &str, weight: W) -> Option<W> {
+        self.root.insert(key.chars(), weight)
+    }
+
+    /// キー `key` の重みを返します。キーが存在しない場合、 `None` を返します。
+    pub fn weight(&self, key: &str) -> Option<W> {
+        self.find(key)?.weight
+    }
+
+    /// キー `key` を取り除き、その重みを返します。
+    pub fn remove(&mut self, key: &str) -> Option<W> {
+        self.root.remove(key.chars())
+    }
+
+    /// `prefix` から始まるキーのうち、重みの大きいものから `k` 個を重みとの組で返します。
+    ///
+    /// 重みが等しいキーは辞書順に並べます。
+    pub fn top_completions(&self, prefix: &str, k: usize) -> Vec<(String, W)> {
+        let mut found = vec![];
+        let root = match self.find(prefix) {
+            Some(node) if k > 0 => node,
+            _ => return found,
+        };
+        // the nodes to be expanded
+        let mut nodes = vec![root];
+        // (the maximum weight, the path, the index of the node to be expanded, or None for the key of the path)
+        let mut heap: Heap<(Reverse<W>, String, Option<usize>)> = Heap::new();
+        if let Some(max) = root.max {
+            heap.push((Reverse(max), prefix.to_string(), Some(0)));
+        }
+        while let Some((Reverse(weight), key, index)) = heap.pop() {
+            let node = match index {
+                None => {
+                    found.push((key, weight));
+                    if found.len() == k {
+                        break;
+                    }
+                    continue;
+                }
+                Some(index) => nodes[index],
+            };
+            if let Some(weight) = node.weight {
+                heap.push((Reverse(weight), key.clone(), None));
+            }
+            for (c, child) in &node.children {
+                let mut child_key = key.clone();
+                child_key.push(*c);
+                heap.push((Reverse(child.max.unwrap()), child_key, Some(nodes.len())));
+                nodes.push(child);
+            }
+        }
+        found
+    }
+
+    /// ノードの個数を返します。
+    pub fn size(&self) -> usize {
+        let mut stack = vec![&self.root];
+        let mut size = 0;
+        while let Some(node) = stack.pop() {
+            size += 1;
+            stack.extend(node.children.iter().map(|(_, child)| child));
+        }
+        size
+    }
+
+    fn find(&self, s: &str) -> Option<&Node<W>> {
+        let mut node = &self.root;
+        for c in s.chars() {
+            node = node.child(c)?;
+        }
+        Some(node)
+    }
+}
+
+impl <W: Ord + Copy> Trie for WeightedTrie<W> {
+    fn contains(&self, s: &str) -> bool {
+        self.weight(s).is_some()
+    }
+
+    fn prefix<'a>(&self, s:&'a str) -> &'a str {
+        let len = self.common_prefixes(s).last().map_or(0, |key| key.len());
+        &s[0..len]
+    }
+
+    fn common_prefixes<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let mut node = Some(&self.root);
+        let mut chars = query.char_indices();
+        // node corresponds to query[0..end]
+        let mut end = 0;
+        core::iter::from_fn(move || {
+            loop {
+                let current = node?;
+                let len = end;
+                node = chars.next().and_then(|(i, c)| {
+                    end = i + c.len_utf8();
+                    current.child(c)
+                });
+                if current.weight.is_some() {
+                    return Some(&query[0..len]);
+                }
+            }
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.root.count
+    }
+
+    fn count_prefix(&self, prefix: &str) -> usize {
+        self.find(prefix).map_or(0, |node| node.count)
+    }
+
+    fn predict(&self, prefix: &str) -> impl Iterator<Item = String> + '_ {
+        let mut stack: Vec<(&Node<W>, String)> = self.find(prefix)
+            .map(|node| (node, prefix.to_string()))
+            .into_iter()
+            .collect();
+        core::iter::from_fn(move || {
+            while let Some((node, key)) = stack.pop() {
+                for (c, child) in node.children.iter().rev() {
+                    let mut child_key = key.clone();
+                    child_key.push(*c);
+                    stack.push((child, child_key));
+                }
+                if node.weight.is_some() {
+                    return Some(key);
+                }
+            }
+            None
+        })
+    }
+
+    fn cursor(&self) -> impl TrieCursor<'_> {
+        Cursor { node: &self.root }
+    }
+}
+
+struct Cursor<'a, W> {
+    node: &'a Node<W>,
+}
+
+impl <W> Clone for Cursor<'_, W> {
+    fn clone(&self) -> Self {
+        Cursor { node: self.node }
+    }
+}
+
+impl <'a, W: Ord + Copy> TrieCursor<'a> for Cursor<'a, W> {
+    // the weight of the key
+    type Value = W;
+
+    fn step(&mut self, c: char) -> bool {
+        match self.node.child(c) {
+            Some(child) => {
+                self.node = child;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn value(&self) -> Option<&'a W> {
+        self.node.weight.as_ref()
+    }
+}
+
+impl <W: Ord + Copy> Default for WeightedTrie<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl <W: SpaceUsage> SpaceUsage for Node<W> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("children", &self.children), ("weight", &self.weight), ("max", &self.max)]
+    }
+}
+
+impl <W: SpaceUsage> SpaceUsage for WeightedTrie<W> {
+    fn children(&self) -> Vec<(&'static str, &dyn SpaceUsage)> {
+        vec![("root", &self.root)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn same_as_sorting() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let random_key = |rng: &mut StdRng| -> String {
+            let len = rng.gen_range(0, 5);
+            (0..len).map(|_| ['a', 'b', 'c', 'あ'][rng.gen_range(0, 4)]).collect()
+        };
+        let mut trie = WeightedTrie::new();
+        let mut expected = BTreeMap::new();
+        for _ in 0..2000 {
+            let key = random_key(&mut rng);
+            if rng.gen_range(0, 3) == 0 {
+                assert_eq!(expected.remove(&key), trie.remove(&key));
+            } else {
+                let weight: u32 = rng.gen_range(0, 50);
+                assert_eq!(expected.insert(key.clone(), weight), trie.insert_with_weight(&key, weight));
+            }
+            assert_eq!(expected.len(), trie.len());
+
+            let prefix = random_key(&mut rng);
+            let k = rng.gen_range(0, 6);
+            let mut completions: Vec<(String, u32)> = expected.iter()
+                .filter(|(key, _)| key.starts_with(&prefix))
+                .map(|(key, weight)| (key.clone(), *weight))
+                .collect();
+            completions.sort_by_key(|(key, weight)| (Reverse(*weight), key.clone()));
+            completions.truncate(k);
+            assert_eq!(completions, trie.top_completions(&prefix, k));
+            assert_eq!(expected.get(&prefix).copied(), trie.weight(&prefix));
+        }
+        assert_eq!(expected.into_keys().collect::<Vec<_>>(), trie.keys().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn remove_prunes_nodes() {
+        let mut trie = WeightedTrie::new();
+        trie.insert_with_weight("ab", 1);
+        trie.insert_with_weight("abcd", 5);
+        assert_eq!(5, trie.size());
+        assert_eq!(Some(5), trie.remove("abcd"));
+        assert_eq!(3, trie.size());
+        assert_eq!(vec![("ab".to_string(), 1)], trie.top_completions("", 3));
+        assert_eq!(None, trie.remove("a"));
+        assert_eq!("ab", trie.prefix("abc"));
+    }
+}
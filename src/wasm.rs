@@ -0,0 +1,147 @@
+use crate::bits::wavelet_matrix::NaiveU8WaveletMatrix;
+use crate::serialize;
+use crate::string::trie::NaiveLoudsTrie;
+use crate::string::trie::Trie;
+
+use wasm_bindgen::prelude::*;
+
+/// JavaScript の `Trie` クラス
+///
+/// `string` のキーの集合を [`NaiveLoudsTrie`] で保持し、入力補完のための接頭辞検索を提供します。
+/// 辞書は `rsindex build-trie` などで事前に構築して保存し、ブラウザでは `Trie.fromBytes()` で読み込むと、構築の時間を省けます。
+#[wasm_bindgen(js_name = Trie)]
+pub struct WasmTrie(NaiveLoudsTrie);
+
+#[wasm_bindgen(js_class = Trie)]
+impl WasmTrie {
+    /// `keys` をキーとするトライ木を構築します。キーの重複や順序は問いません。
+    #[wasm_bindgen(constructor)]
+    pub fn new(keys: Vec<String>) -> WasmTrie {
+        WasmTrie(NaiveLoudsTrie::new(&keys))
+    }
+
+    /// [`crate::serialize::Serializable::serialize()`] で書き出したバイト列から読み込みます。
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<WasmTrie, JsError> {
+        Ok(WasmTrie(serialize::from_bytes(bytes)?))
+    }
+
+    /// [`crate::serialize::Serializable::serialize()`] の形式のバイト列を返します。
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serialize::to_bytes(&self.0)
+    }
+
+    /// キーの個数を返します。
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.0.contains(key)
+    }
+
+    /// `query` の接頭辞となっている最長のキーを返します。
+    pub fn prefix(&self, query: &str) -> String {
+        self.0.prefix(query).to_string()
+    }
+
+    /// `prefix` から始まるキーの個数を返します。
+    #[wasm_bindgen(js_name = countPrefix)]
+    pub fn count_prefix(&self, prefix: &str) -> usize {
+        self.0.count_prefix(prefix)
+    }
+
+    /// `prefix` から始まるキーを、辞書順に最大 `limit` 個返します。
+    pub fn predict(&self, prefix: &str, limit: usize) -> Vec<String> {
+        self.0.predict(prefix).take(limit).collect()
+    }
+}
+
+/// JavaScript の `WaveletMatrix` クラス
+///
+/// 0 から 255 の整数の列を [`NaiveU8WaveletMatrix`] で保持します。
+/// 範囲外の添字や現れない値の `select` には、例外を投げます。
+#[wasm_bindgen(js_name = WaveletMatrix)]
+pub struct WasmWaveletMatrix(NaiveU8WaveletMatrix);
+
+#[wasm_bindgen(js_class = WaveletMatrix)]
+impl WasmWaveletMatrix {
+    /// `values` の列を保持します。
+    #[wasm_bindgen(constructor)]
+    pub fn new(values: &[u8]) -> WasmWaveletMatrix {
+        WasmWaveletMatrix(NaiveU8WaveletMatrix::with_levels(values, 8))
+    }
+
+    /// [`crate::serialize::Serializable::serialize()`] で書き出したバイト列から読み込みます。
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<WasmWaveletMatrix, JsError> {
+        Ok(WasmWaveletMatrix(serialize::from_bytes(bytes)?))
+    }
+
+    /// [`crate::serialize::Serializable::serialize()`] の形式のバイト列を返します。
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serialize::to_bytes(&self.0)
+    }
+
+    /// 列の長さを返します。
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.0.len()
+    }
+
+    /// `i` 番目(0-based)の値を返します。
+    pub fn access(&self, i: usize) -> Result<u8, JsError> {
+        Ok(self.0.try_access(i)?)
+    }
+
+    /// 先頭から `i` 個の値に含まれる `v` の個数を返します。
+    pub fn rank(&self, v: u8, i: usize) -> usize {
+        self.0.rank(v, i)
+    }
+
+    /// `i` 番目(0-based)の `v` の位置を返します。
+    pub fn select(&self, v: u8, i: usize) -> Result<usize, JsError> {
+        Ok(self.0.try_select(v, i)?)
+    }
+
+    /// 範囲 `[s, e)` の値のうち、小さい方から `r` 番目(0-based)の値を返します。
+    pub fn quantile(&self, s: usize, e: usize, r: usize) -> Result<u8, JsError> {
+        Ok(self.0.try_quantile(s, e, r)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trie() {
+        let trie = WasmTrie::new(vec!["the".to_string(), "this".to_string(), "their".to_string(), "the".to_string()]);
+        assert_eq!(3, trie.length());
+        assert!(trie.contains("this"));
+        assert!(!trie.contains("th"));
+        assert_eq!("the", trie.prefix("then"));
+        assert_eq!(2, trie.count_prefix("the"));
+        assert_eq!(vec!["the", "their"], trie.predict("th", 2));
+        assert_eq!(vec!["the", "their", "this"], trie.predict("", 10));
+
+        let trie = WasmTrie::from_bytes(&trie.to_bytes()).ok().unwrap();
+        assert_eq!(3, trie.length());
+    }
+
+    #[test]
+    fn wavelet_matrix() {
+        let wmat = WasmWaveletMatrix::new(&[3, 1, 4, 1, 5, 9, 2, 6]);
+        assert_eq!(8, wmat.length());
+        assert_eq!(9, wmat.access(5).ok().unwrap());
+        assert_eq!(2, wmat.rank(1, 4));
+        assert_eq!(3, wmat.select(1, 1).ok().unwrap());
+        assert_eq!(1, wmat.quantile(0, 4, 0).ok().unwrap());
+
+        let wmat = WasmWaveletMatrix::from_bytes(&wmat.to_bytes()).ok().unwrap();
+        assert_eq!(6, wmat.access(7).ok().unwrap());
+    }
+}